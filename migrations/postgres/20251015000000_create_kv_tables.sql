@@ -0,0 +1,22 @@
+-- Items stored by SqlKeyValueStore. Plain items have kind 'i' and an empty sort
+-- key; records have kind 'r'.
+CREATE TABLE IF NOT EXISTS kv_items (
+    kind TEXT NOT NULL,
+    partition_key TEXT NOT NULL,
+    sort_key TEXT NOT NULL,
+    expires_at BIGINT,
+    version BIGINT NOT NULL DEFAULT 0,
+    PRIMARY KEY (kind, partition_key, sort_key)
+);
+
+-- One row per column of an item. Counters maintained by increment are kept in
+-- `counter` so they can be updated atomically.
+CREATE TABLE IF NOT EXISTS kv_columns (
+    kind TEXT NOT NULL,
+    partition_key TEXT NOT NULL,
+    sort_key TEXT NOT NULL,
+    name TEXT NOT NULL,
+    value BYTEA,
+    counter BIGINT,
+    PRIMARY KEY (kind, partition_key, sort_key, name)
+);
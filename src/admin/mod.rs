@@ -0,0 +1,525 @@
+use axum::{
+    Json, Router,
+    extract::{Path, Query, Request, State},
+    http::{HeaderValue, StatusCode, header},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use tracing::{info, warn};
+
+use crate::{
+    ServiceError,
+    content::{ContentParams, ContentTypeDescriptor},
+    flags::{self, Flag},
+    keyvalue::KeyValueStore,
+    metrics,
+    problem::ErrorResponse,
+    prompts::{self, PromptConfig},
+    state::AppState,
+    storage::ObjectStore,
+    usage::{self, DailyUsage},
+};
+
+/// Most days of token usage returned at once
+const MAX_USAGE_DAYS: u64 = 90;
+
+/// Most objects generated by one pre-generation request
+const MAX_PREGENERATE_COUNT: usize = 64;
+
+/// Summary of a prompt configuration returned by the listing endpoint
+#[derive(Serialize)]
+pub struct PromptSummary {
+    pub name: String,
+    pub description: String,
+    pub model: String,
+}
+
+/// A cached object returned by the listing endpoint
+#[derive(Serialize)]
+pub struct CachedObject {
+    pub key: String,
+    pub size: u64,
+    /// RFC 3339 timestamp, if the backend reports it
+    pub last_modified: Option<String>,
+}
+
+/// Selects the cache window and parameter partition to list
+///
+/// The window is formatted like the window segment of object keys (e.g.
+/// "2025-10-11-14" for hourly windows); the current window if omitted. Other
+/// query parameters are the content parameters of the partition (e.g.
+/// `grade=4`); the content type's default parameters if there are none.
+#[derive(Deserialize)]
+pub struct WindowQuery {
+    pub window: Option<String>,
+
+    /// Prompt version of the partition, for prompts under an experiment
+    pub version: Option<String>,
+
+    #[serde(flatten)]
+    pub params: BTreeMap<String, String>,
+}
+
+/// Number of days of token usage to return, counting today
+#[derive(Deserialize)]
+pub struct UsageQuery {
+    #[serde(default = "default_usage_days")]
+    pub days: u64,
+}
+
+fn default_usage_days() -> u64 {
+    7
+}
+
+/// Number of objects to pre-generate
+#[derive(Deserialize)]
+pub struct GenerateQuery {
+    #[serde(default = "default_generate_count")]
+    pub count: usize,
+}
+
+fn default_generate_count() -> usize {
+    1
+}
+
+/// Result of a pre-generation request
+#[derive(Serialize)]
+pub struct GenerateSummary {
+    pub generated: usize,
+}
+
+/// Outcome of reviewing a flagged object
+#[derive(Serialize)]
+pub struct FlagReview {
+    pub object_key: String,
+
+    /// Number of open flags on the object that were closed
+    pub flags_closed: usize,
+
+    /// Whether the object was deleted
+    pub removed: bool,
+
+    /// Whether a replacement was generated
+    pub replaced: bool,
+}
+
+/// Builds the router for administrative endpoints, to be nested under `/admin`
+///
+/// Every endpoint requires an `Authorization: Bearer <token>` header carrying
+/// `token`.
+pub fn router<S, K>(token: &str) -> Router<AppState<S, K>>
+where
+    S: ObjectStore + 'static,
+    K: KeyValueStore + 'static,
+{
+    let token: Arc<str> = token.into();
+
+    Router::new()
+        .route("/prompts", get(list_prompts))
+        .route("/prompts/{name}", get(get_prompt).put(put_prompt))
+        .route("/contents/{content_type}", get(list_cached))
+        .route("/contents/{content_type}/generate", post(pregenerate))
+        .route("/objects/{*key}", get(get_cached).delete(delete_cached))
+        .route("/usage", get(token_usage))
+        .route("/flags", get(list_flags))
+        .route("/flags/{id}/remove", post(remove_flagged))
+        .route("/flags/{id}/dismiss", post(dismiss_flag))
+        .route_layer(middleware::from_fn(move |request: Request, next: Next| {
+            require_token(token.clone(), request, next)
+        }))
+}
+
+/// Rejects requests that don't carry the admin bearer token
+async fn require_token(token: Arc<str>, request: Request, next: Next) -> Response {
+    let presented = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+
+    match presented {
+        Some(presented) if constant_time_eq(presented.as_bytes(), token.as_bytes()) => {
+            next.run(request).await
+        }
+        _ => {
+            let mut response = ErrorResponse::new(
+                StatusCode::UNAUTHORIZED,
+                "unauthorized",
+                "A valid admin bearer token is required",
+            )
+            .into_response();
+            response
+                .headers_mut()
+                .insert(header::WWW_AUTHENTICATE, HeaderValue::from_static("Bearer"));
+            response
+        }
+    }
+}
+
+/// Compares secrets in time independent of where they differ
+pub(crate) fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+/// Looks up a registered content type
+fn content_type<'a, S: ObjectStore, K: KeyValueStore>(
+    state: &'a AppState<S, K>,
+    prefix: &str,
+) -> Result<&'a ContentTypeDescriptor, ServiceError> {
+    state
+        .content_types
+        .get(prefix)
+        .ok_or_else(|| ServiceError::NotFound(format!("Unknown content type: {}", prefix)))
+}
+
+/// Checks that a key names generated content, so the object endpoints can't
+/// reach prompts, assets, or anything else in the store
+fn content_key<'a, S: ObjectStore, K: KeyValueStore>(
+    state: &AppState<S, K>,
+    key: &'a str,
+) -> Result<&'a str, ServiceError> {
+    if !state.content_types.is_content_key(key) {
+        return Err(ServiceError::InvalidInput(format!(
+            "{:?} is not a cached content key",
+            key
+        )));
+    }
+    Ok(key)
+}
+
+/// Lists all loaded prompt configurations
+pub async fn list_prompts() -> Json<Vec<PromptSummary>> {
+    let mut summaries: Vec<PromptSummary> = prompts::prompts()
+        .iter()
+        .map(|(name, config)| PromptSummary {
+            name: name.clone(),
+            description: config.description.clone(),
+            model: config.model.clone(),
+        })
+        .collect();
+    summaries.sort_by(|a, b| a.name.cmp(&b.name));
+
+    Json(summaries)
+}
+
+/// Returns the full configuration of a loaded prompt
+pub async fn get_prompt(
+    Path(name): Path<String>,
+) -> Result<Json<PromptConfig>, ErrorResponse> {
+    prompts::get_prompt(&name)
+        .map(Json)
+        .ok_or_else(|| ServiceError::NotFound(format!("Unknown prompt: {}", name)).into())
+}
+
+/// Uploads a prompt configuration (as TOML) to the ObjectStore and activates it
+///
+/// The prompt takes effect immediately on this instance and is picked up by other
+/// instances on their next prompt reload.
+pub async fn put_prompt<S: ObjectStore, K: KeyValueStore>(
+    State(state): State<AppState<S, K>>,
+    Path(name): Path<String>,
+    body: String,
+) -> Result<Json<PromptConfig>, ErrorResponse> {
+    let valid_name = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+    if !valid_name {
+        return Err(ServiceError::InvalidInput(
+            "prompt names may only contain lowercase letters, digits, and underscores".into(),
+        )
+        .into());
+    }
+
+    let config = prompts::parse_prompt_config(&body)?;
+    if config.name != name {
+        return Err(ServiceError::InvalidInput(format!(
+            "prompt name {:?} doesn't match the path {:?}",
+            config.name, name
+        ))
+        .into());
+    }
+
+    let key = format!("{}{}.toml", prompts::PROMPTS_PREFIX, name);
+    state
+        .object_store
+        .put_object(&key, body.into_bytes())
+        .await?;
+
+    prompts::install_prompt(&name, config.clone());
+
+    Ok(Json(config))
+}
+
+/// Lists the cached objects of a content type in one cache window of one
+/// parameter partition
+pub async fn list_cached<S: ObjectStore, K: KeyValueStore>(
+    State(state): State<AppState<S, K>>,
+    Path(prefix): Path<String>,
+    Query(query): Query<WindowQuery>,
+) -> Result<Json<Vec<CachedObject>>, ErrorResponse> {
+    let descriptor = content_type(&state, &prefix)?;
+    let window = query.window.unwrap_or_else(|| {
+        state
+            .cache_policies
+            .for_content_type(&descriptor.prefix)
+            .window
+            .format(&Utc::now())
+    });
+    // Both become key segments, so neither may reach into another folder
+    let is_name =
+        |name: &String| !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !window.chars().all(|c| c.is_ascii_digit() || c == '-') || !query.params.keys().all(is_name) {
+        return Err(ServiceError::InvalidInput("invalid window or parameter name".into()).into());
+    }
+
+    let mut params = if query.params.is_empty() {
+        descriptor.default_params.clone()
+    } else {
+        query.params.iter().fold(ContentParams::new(), |params, (name, value)| params.with(name, value))
+    };
+    if let Some(version) = &query.version {
+        params = params.with_prompt_version(version);
+    }
+    let folder_path = format!("{}/{}{}/", descriptor.prefix, params.partition(), window);
+
+    let objects = state
+        .object_store
+        .list_objects(&folder_path)
+        .await?
+        .into_iter()
+        .map(|object| CachedObject {
+            key: object.key,
+            size: object.size,
+            last_modified: object.last_modified.map(|time| time.to_rfc3339()),
+        })
+        .collect();
+
+    Ok(Json(objects))
+}
+
+/// Returns a cached object
+pub async fn get_cached<S: ObjectStore, K: KeyValueStore>(
+    State(state): State<AppState<S, K>>,
+    Path(key): Path<String>,
+) -> Result<Json<serde_json::Value>, ErrorResponse> {
+    let key = content_key(&state, &key)?;
+    let data = state.object_store.get_object(key).await?;
+
+    let contents = serde_json::from_slice(&data).map_err(ServiceError::from)?;
+
+    Ok(Json(contents))
+}
+
+/// Deletes a cached object flagged as unsuitable, so it's no longer served
+pub async fn delete_cached<S: ObjectStore, K: KeyValueStore>(
+    State(state): State<AppState<S, K>>,
+    Path(key): Path<String>,
+) -> Result<StatusCode, ErrorResponse> {
+    let key = content_key(&state, &key)?;
+    state.object_store.delete_object(key).await?;
+
+    info!("Deleted flagged object {}", key);
+    metrics::increment("admin.objects_deleted");
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Lists open content flags, most recent first
+pub async fn list_flags<S: ObjectStore, K: KeyValueStore>(
+    State(state): State<AppState<S, K>>,
+) -> Result<Json<Vec<Flag>>, ErrorResponse> {
+    Ok(Json(flags::open_flags(&state.kv_store).await?))
+}
+
+/// Finds an open flag by ID
+async fn open_flag<K: KeyValueStore>(kv_store: &K, id: &str) -> Result<Flag, ServiceError> {
+    flags::open_flags(kv_store)
+        .await?
+        .into_iter()
+        .find(|flag| flag.id == id)
+        .ok_or_else(|| ServiceError::NotFound(format!("No open flag {}", id)))
+}
+
+/// Upholds a flag: deletes the flagged object, closes every flag on it, and
+/// generates a replacement with the content type's default parameters
+///
+/// The object stays deleted if the replacement can't be generated; the cache
+/// refills on its own as content is requested.
+pub async fn remove_flagged<S: ObjectStore, K: KeyValueStore>(
+    State(state): State<AppState<S, K>>,
+    Path(id): Path<String>,
+) -> Result<Json<FlagReview>, ErrorResponse> {
+    let flag = open_flag(&state.kv_store, &id).await?;
+    let key = content_key(&state, &flag.object_key)?;
+
+    state.object_store.delete_object(key).await?;
+    let closed = flags::resolve(&state.kv_store, key).await?;
+    info!("Deleted flagged object {} and closed {} flags", key, closed.len());
+    metrics::increment("admin.objects_deleted");
+
+    let prefix = key.split('/').next().unwrap_or_default();
+    let descriptor = content_type(&state, prefix)?;
+    let replaced = match state
+        .generate_batch(descriptor, &descriptor.default_params, 1)
+        .await
+    {
+        Ok(_) => true,
+        Err(e) => {
+            warn!("Failed to generate a replacement for {}: {}", key, e);
+            false
+        }
+    };
+
+    Ok(Json(FlagReview {
+        object_key: key.to_string(),
+        flags_closed: closed.len(),
+        removed: true,
+        replaced,
+    }))
+}
+
+/// Rejects a flag: closes every flag on the object and serves it again
+pub async fn dismiss_flag<S: ObjectStore, K: KeyValueStore>(
+    State(state): State<AppState<S, K>>,
+    Path(id): Path<String>,
+) -> Result<Json<FlagReview>, ErrorResponse> {
+    let flag = open_flag(&state.kv_store, &id).await?;
+    let closed = flags::resolve(&state.kv_store, &flag.object_key).await?;
+    info!("Dismissed {} flags on {}", closed.len(), flag.object_key);
+
+    Ok(Json(FlagReview {
+        object_key: flag.object_key,
+        flags_closed: closed.len(),
+        removed: false,
+        replaced: false,
+    }))
+}
+
+/// Returns daily LLM token usage per prompt, most recent day first
+pub async fn token_usage<S: ObjectStore, K: KeyValueStore>(
+    State(state): State<AppState<S, K>>,
+    Query(query): Query<UsageQuery>,
+) -> Result<Json<Vec<DailyUsage>>, ErrorResponse> {
+    if !(1..=MAX_USAGE_DAYS).contains(&query.days) {
+        return Err(ServiceError::InvalidInput(format!(
+            "days must be between 1 and {}",
+            MAX_USAGE_DAYS
+        ))
+        .into());
+    }
+
+    let mut names: Vec<String> = prompts::prompts().keys().cloned().collect();
+    names.sort();
+    let usage =
+        usage::daily_usage(&state.kv_store, &names, Utc::now().date_naive(), query.days).await?;
+
+    Ok(Json(usage))
+}
+
+/// Generates objects for a content type's current cache window with its default
+/// parameters, regardless of how many are already cached
+pub async fn pregenerate<S: ObjectStore, K: KeyValueStore>(
+    State(state): State<AppState<S, K>>,
+    Path(prefix): Path<String>,
+    Query(query): Query<GenerateQuery>,
+) -> Result<Json<GenerateSummary>, ErrorResponse> {
+    if !(1..=MAX_PREGENERATE_COUNT).contains(&query.count) {
+        return Err(ServiceError::InvalidInput(format!(
+            "count must be between 1 and {}",
+            MAX_PREGENERATE_COUNT
+        ))
+        .into());
+    }
+
+    let descriptor = content_type(&state, &prefix)?;
+    let generated = state
+        .generate_batch(descriptor, &descriptor.default_params, query.count)
+        .await?;
+
+    info!("Pre-generated {} {} objects", generated, descriptor.prefix);
+    Ok(Json(GenerateSummary { generated }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::Config, content::ContentTypeRegistry, keyvalue::MemoryKeyValueStore, reading,
+        storage::MemoryObjectStore,
+    };
+    use axum::body::Body;
+    use tower::ServiceExt;
+
+    const TOKEN: &str = "0123456789abcdef0123456789abcdef";
+
+    async fn app() -> Router {
+        let state = AppState::new(
+            MemoryObjectStore::new(),
+            MemoryKeyValueStore::new(),
+            Config::default(),
+            ContentTypeRegistry::new().register(reading::descriptor()),
+        )
+        .await;
+        for key in [
+            "reading/grade-3/2025-10-11-14/a.json",
+            "reading/grade-3/2025-10-11-15/b.json",
+            "reading/grade-4/2025-10-11-14/c.json",
+        ] {
+            state.object_store.put_object(key, b"{}".to_vec()).await.unwrap();
+        }
+
+        router(TOKEN).with_state(state)
+    }
+
+    async fn send(method: &str, uri: &str, token: Option<&str>) -> Response {
+        let mut request = Request::builder().method(method).uri(uri);
+        if let Some(token) = token {
+            request = request.header(header::AUTHORIZATION, format!("Bearer {}", token));
+        }
+        app().await.oneshot(request.body(Body::empty()).unwrap()).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn requires_the_bearer_token() {
+        let uri = "/contents/reading?window=2025-10-11-14";
+        assert_eq!(send("GET", uri, None).await.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(send("GET", uri, Some("wrong")).await.status(), StatusCode::UNAUTHORIZED);
+
+        let response = send("GET", uri, Some(TOKEN)).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let objects: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(objects[0]["key"], "reading/grade-3/2025-10-11-14/a.json");
+        assert_eq!(objects.as_array().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn lists_one_partition_of_a_window() {
+        let response = send("GET", "/contents/reading?window=2025-10-11-14&grade=4", Some(TOKEN)).await;
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let objects: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(objects, serde_json::json!([{
+            "key": "reading/grade-4/2025-10-11-14/c.json",
+            "size": 2,
+            "last_modified": objects[0]["last_modified"],
+        }]));
+
+        for uri in ["/contents/reading?window=..", "/contents/reading?window=2025-10-11-14&..=4"] {
+            assert_eq!(send("GET", uri, Some(TOKEN)).await.status(), StatusCode::BAD_REQUEST);
+        }
+    }
+
+    #[tokio::test]
+    async fn only_deletes_content_objects() {
+        let status = send("DELETE", "/objects/prompts/reading.toml", Some(TOKEN)).await.status();
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let status = send("DELETE", "/objects/reading/grade-3/2025-10-11-14/a.json", Some(TOKEN)).await.status();
+        assert_eq!(status, StatusCode::NO_CONTENT);
+    }
+}
@@ -0,0 +1,136 @@
+use axum::{
+    Router,
+    extract::Request,
+    http::{HeaderName, HeaderValue, header},
+    middleware::Next,
+    response::Response,
+    routing::{get, post},
+};
+
+use crate::{
+    bilingual, classes, cloze, content, daily, etag, explanations, feedback, flags, flashcards,
+    gamification, grammar, graphql, history, idempotency, keyvalue::KeyValueStore, limits, logic,
+    math_drill, paired, poetry, progress, puzzle, quiz, reading, reports, search, state::AppState,
+    storage::ObjectStore, users,
+};
+
+/// Prefix of the current version of the API
+pub const CURRENT_VERSION: &str = "/v1";
+
+/// Header marking a response from a deprecated path (RFC 9745)
+const DEPRECATION: HeaderName = HeaderName::from_static("deprecation");
+
+/// Builds version 1 of the JSON API, served under `/v1`
+///
+/// Each version has its own router, so a breaking change to a schema can ship as
+/// `/v2` while clients of `/v1` keep getting the old one. Routes must run inside
+/// the `session` and `current_user` middleware.
+///
+/// Routes that may wait on the model to generate content time out after
+/// `limits.generation_timeout_secs`; the rest after `limits.timeout_secs`.
+/// Submissions, flashcard reviews, and requests to continue a story, honor an
+/// `Idempotency-Key` header.
+pub fn v1<S, K>(state: &AppState<S, K>) -> Router<AppState<S, K>>
+where
+    S: ObjectStore + 'static,
+    K: KeyValueStore + 'static,
+{
+    let limits = &state.config.limits;
+
+    // Generated content is tagged with an ETag so polling clients can revalidate it
+    let content_routes = Router::new()
+        .route("/reading_contents", get(reading::reading_contents))
+        .route("/bilingual_contents", get(bilingual::bilingual_contents))
+        .route("/daily_challenge", get(daily::daily_challenge))
+        .route("/cloze_exercises", get(cloze::cloze_exercises))
+        .route("/word_search", get(puzzle::word_search))
+        .route("/crossword", get(puzzle::crossword))
+        .route("/flashcards", get(flashcards::flashcards))
+        .route("/grammar_exercises", get(grammar::grammar_exercises))
+        .route("/poems", get(poetry::poems))
+        .route("/history_passages", get(history::history_passages))
+        .route("/logic_puzzles", get(logic::logic_puzzles))
+        .route("/paired_passages", get(paired::paired_passages))
+        .merge(content::router(&state.content_types))
+        .route_layer(axum::middleware::from_fn(etag::etag));
+
+    // Answers, feedback, flags, and reviews may be resubmitted by clients on flaky networks
+    let submission_routes = Router::new()
+        .merge(progress::router())
+        .merge(feedback::router())
+        .merge(flags::router())
+        .merge(flashcards::router())
+        .route_layer(axum::middleware::from_fn_with_state(state.clone(), idempotency::idempotency));
+
+    let generation_routes = Router::new()
+        .merge(content_routes)
+        .route("/reading_contents/stream", get(reading::reading_contents_stream))
+        .route("/reading_contents/personalized", get(reading::reading_personalized))
+        .route(
+            "/reading_contents/{id}/continue",
+            post(reading::reading_continue)
+                .route_layer(axum::middleware::from_fn_with_state(state.clone(), idempotency::idempotency)),
+        )
+        .route("/reading_contents/{id}/hint", get(reading::reading_hint))
+        .route(
+            "/explanations",
+            post(explanations::explain)
+                .route_layer(axum::middleware::from_fn_with_state(state.clone(), idempotency::idempotency)),
+        )
+        .route("/graphql", post(graphql::graphql))
+        .route_layer(axum::middleware::from_fn_with_state(limits.generation_timeout(), limits::timeout));
+
+    Router::new()
+        .route("/reading_contents/export.epub", get(reading::reading_export))
+        .route("/reading_contents/{id}/worksheet.pdf", get(reading::reading_worksheet))
+        .route("/ws/quiz", get(quiz::quiz_socket))
+        .route("/math_drill", get(math_drill::math_drill))
+        .nest("/account", users::router())
+        .nest("/classes", classes::router())
+        .merge(gamification::router())
+        .merge(reports::router())
+        .merge(search::router())
+        .merge(submission_routes)
+        .route_layer(axum::middleware::from_fn_with_state(limits.timeout(), limits::timeout))
+        .merge(generation_routes)
+}
+
+/// Middleware for the unversioned paths the API was first served at, which still
+/// answer as `/v1` does: marks responses as deprecated and links to the
+/// versioned path
+pub async fn deprecated(request: Request, next: Next) -> Response {
+    let successor = format!("<{}{}>; rel=\"successor-version\"", CURRENT_VERSION, request.uri().path());
+    let mut response = next.run(request).await;
+
+    let headers = response.headers_mut();
+    headers.insert(DEPRECATION, HeaderValue::from_static("true"));
+    if let Ok(link) = HeaderValue::from_str(&successor) {
+        headers.append(header::LINK, link);
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, http::StatusCode};
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn points_unversioned_paths_at_their_successor() {
+        let api = || Router::new().route("/search", get(|| async { "[]" }));
+        let app = Router::new()
+            .nest(CURRENT_VERSION, api())
+            .merge(api().route_layer(axum::middleware::from_fn(deprecated)));
+        let request = |path| axum::http::Request::get(path).body(Body::empty()).unwrap();
+
+        let legacy = app.clone().oneshot(request("/search")).await.unwrap();
+        assert_eq!(legacy.status(), StatusCode::OK);
+        assert_eq!(legacy.headers()[&DEPRECATION], "true");
+        assert_eq!(legacy.headers()[header::LINK], "</v1/search>; rel=\"successor-version\"");
+
+        let current = app.oneshot(request("/v1/search")).await.unwrap();
+        assert_eq!(current.status(), StatusCode::OK);
+        assert!(current.headers().get(&DEPRECATION).is_none());
+    }
+}
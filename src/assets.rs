@@ -0,0 +1,88 @@
+use axum::{
+    body::Body,
+    extract::{Path, State},
+    http::{StatusCode, header},
+    response::Response,
+};
+use tracing::error;
+
+use crate::{
+    ServiceError, keyvalue::KeyValueStore, problem::ErrorResponse, state::AppState,
+    storage::ObjectStore,
+};
+
+/// Object store prefix under which assets (audio, images, etc.) are stored
+pub const ASSETS_PREFIX: &str = "assets/";
+
+/// Returns the object store key for an asset path, rejecting paths that could
+/// escape the assets prefix
+pub fn asset_key(path: &str) -> Result<String, ServiceError> {
+    let is_safe = !path.is_empty()
+        && path
+            .split('/')
+            .all(|segment| !segment.is_empty() && segment != "." && segment != "..");
+
+    if !is_safe {
+        return Err(ServiceError::InvalidInput(format!("Invalid asset path: {}", path)));
+    }
+
+    Ok(format!("{}{}", ASSETS_PREFIX, path))
+}
+
+/// Streams an asset from the object store without loading it into memory
+pub async fn asset<S: ObjectStore, K: KeyValueStore>(
+    State(state): State<AppState<S, K>>,
+    Path(path): Path<String>,
+) -> Result<Response, ErrorResponse> {
+    let key = asset_key(&path)?;
+
+    let object = state
+        .object_store
+        .head_object(&key)
+        .await?
+        .ok_or_else(|| ServiceError::NotFound("Asset not found".to_string()))?;
+
+    let stream = state
+        .object_store
+        .get_object_stream(&key)
+        .await?;
+
+    Response::builder()
+        .header(
+            header::CONTENT_TYPE,
+            object
+                .content_type
+                .as_deref()
+                .unwrap_or("application/octet-stream"),
+        )
+        .body(Body::from_stream(stream))
+        .map_err(|e| {
+            error!("Failed to build response for {}: {}", key, e);
+            ErrorResponse::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "internal_error",
+                "Internal server error",
+            )
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_paths_under_assets_prefix() {
+        assert_eq!(
+            asset_key("audio/story.mp3").unwrap(),
+            "assets/audio/story.mp3"
+        );
+    }
+
+    #[test]
+    fn rejects_paths_escaping_prefix() {
+        assert!(asset_key("../prompts/reading.toml").is_err());
+        assert!(asset_key("audio/./story.mp3").is_err());
+        assert!(asset_key("audio//story.mp3").is_err());
+        assert!(asset_key("").is_err());
+    }
+}
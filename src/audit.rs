@@ -0,0 +1,206 @@
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::{ServiceError, storage::ObjectStore};
+
+/// ObjectStore prefix under which audit records are stored
+pub const AUDIT_PREFIX: &str = "audit/";
+
+/// Which LLM calls are written to the audit log
+///
+/// Off by default. Configured under `[audit]` in the config file, or with
+/// `AUDIT_SAMPLE_RATE` for the default rate:
+///
+/// ```toml
+/// [audit]
+/// sample_rate = 0.05
+///
+/// [audit.prompt_rates]
+/// weekly_report = 1.0
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AuditPolicy {
+    /// Share of calls audited, from 0 (none) to 1 (all)
+    pub sample_rate: f64,
+
+    /// Sample rates overriding `sample_rate`, keyed by prompt name
+    pub prompt_rates: HashMap<String, f64>,
+}
+
+impl AuditPolicy {
+    /// Describes every invalid field
+    pub fn validate(&self) -> Vec<String> {
+        let rates = std::iter::once(("audit.sample_rate".to_string(), self.sample_rate)).chain(
+            self.prompt_rates
+                .iter()
+                .map(|(prompt, rate)| (format!("audit.prompt_rates.{}", prompt), *rate)),
+        );
+        rates
+            .filter(|(_, rate)| !(0.0..=1.0).contains(rate))
+            .map(|(field, _)| format!("{} must be between 0 and 1", field))
+            .collect()
+    }
+
+    /// Returns the sample rate for a prompt
+    pub fn rate_for(&self, prompt: &str) -> f64 {
+        self.prompt_rates.get(prompt).copied().unwrap_or(self.sample_rate)
+    }
+
+    /// Decides at random whether to audit a call to a prompt
+    pub fn should_audit(&self, prompt: &str) -> bool {
+        let rate = self.rate_for(prompt);
+        rate > 0.0 && rand::random::<f64>() < rate
+    }
+}
+
+/// One message sent to the model
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditMessage {
+    /// "system", "user", or "assistant"
+    pub role: String,
+    pub content: String,
+}
+
+impl AuditMessage {
+    pub fn new(role: &str, content: &str) -> Self {
+        Self { role: role.to_string(), content: content.to_string() }
+    }
+}
+
+/// A raw LLM request and its response, kept for safety reviews
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditRecord {
+    pub id: String,
+    pub prompt: String,
+    pub prompt_version: String,
+    pub model: String,
+
+    /// Zero for the first request of a generation, then one more per repair
+    pub attempt: usize,
+
+    /// Name, description, and document of the JSON schema the output had to follow
+    pub schema_name: String,
+    pub schema_description: String,
+    pub schema: serde_json::Value,
+
+    /// Every message sent, in order
+    pub messages: Vec<AuditMessage>,
+
+    /// Text the model returned, if the call succeeded
+    pub response: Option<String>,
+
+    /// Why the call failed, if it did
+    pub error: Option<String>,
+
+    pub input_tokens: Option<u32>,
+    pub output_tokens: Option<u32>,
+
+    /// When the request was sent, in RFC 3339 format
+    pub requested_at: String,
+    pub duration_ms: u64,
+}
+
+impl AuditRecord {
+    /// Starts a record for a request sent at `requested_at`
+    pub fn new(prompt: &str, prompt_version: &str, model: &str, requested_at: DateTime<Utc>) -> Self {
+        Self {
+            id: Uuid::new_v4().simple().to_string(),
+            prompt: prompt.to_string(),
+            prompt_version: prompt_version.to_string(),
+            model: model.to_string(),
+            attempt: 0,
+            schema_name: String::new(),
+            schema_description: String::new(),
+            schema: serde_json::Value::Null,
+            messages: Vec::new(),
+            response: None,
+            error: None,
+            input_tokens: None,
+            output_tokens: None,
+            requested_at: requested_at.to_rfc3339_opts(SecondsFormat::Millis, true),
+            duration_ms: 0,
+        }
+    }
+
+    /// Key of the object holding the record, grouped by day so a day's calls can
+    /// be listed, e.g. "audit/2025-10-11/reading/143005123-1a2b.json"
+    pub fn key(&self) -> String {
+        let requested_at = DateTime::parse_from_rfc3339(&self.requested_at)
+            .map(|time| time.with_timezone(&Utc))
+            .unwrap_or_default();
+        format!(
+            "{}{}/{}/{}-{}.json",
+            AUDIT_PREFIX,
+            requested_at.format("%Y-%m-%d"),
+            self.prompt,
+            requested_at.format("%H%M%S%3f"),
+            self.id
+        )
+    }
+}
+
+/// Writes an audit record, returning its key
+pub async fn write<S: ObjectStore>(object_store: &S, record: &AuditRecord) -> Result<String, ServiceError> {
+    let key = record.key();
+    object_store.put_object(&key, serde_json::to_vec(record)?).await?;
+    Ok(key)
+}
+
+/// Reads an audit record
+pub async fn read<S: ObjectStore>(object_store: &S, key: &str) -> Result<AuditRecord, ServiceError> {
+    if !key.starts_with(AUDIT_PREFIX) {
+        return Err(ServiceError::InvalidInput(format!("{:?} is not an audit key", key)));
+    }
+    Ok(serde_json::from_slice(&object_store.get_object(key).await?)?)
+}
+
+/// Lists the keys of the audit records written on a day (YYYY-MM-DD), oldest first
+/// within each prompt
+pub async fn list<S: ObjectStore>(object_store: &S, date: &str) -> Result<Vec<String>, ServiceError> {
+    let mut keys: Vec<String> = object_store
+        .list_objects(&format!("{}{}/", AUDIT_PREFIX, date))
+        .await?
+        .into_iter()
+        .map(|object| object.key)
+        .collect();
+    keys.sort();
+    Ok(keys)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::MemoryObjectStore;
+
+    #[test]
+    fn samples_by_prompt() {
+        let policy = AuditPolicy {
+            sample_rate: 0.0,
+            prompt_rates: HashMap::from([("weekly_report".to_string(), 1.0)]),
+        };
+        assert!(policy.should_audit("weekly_report"));
+        assert!(!policy.should_audit("reading"));
+        assert!(policy.validate().is_empty());
+
+        let invalid = AuditPolicy { sample_rate: 2.0, ..policy };
+        assert_eq!(invalid.validate(), vec!["audit.sample_rate must be between 0 and 1"]);
+    }
+
+    #[tokio::test]
+    async fn round_trips_through_the_store() {
+        let store = MemoryObjectStore::new();
+        let time = DateTime::parse_from_rfc3339("2025-10-11T14:30:05.123Z").unwrap().with_timezone(&Utc);
+        let mut record = AuditRecord::new("reading", "v1", "gpt-4o-mini", time);
+        record.messages.push(AuditMessage::new("user", "Write a story"));
+        record.response = Some("{}".to_string());
+
+        let key = write(&store, &record).await.unwrap();
+        assert_eq!(key, format!("audit/2025-10-11/reading/143005123-{}.json", record.id));
+        assert_eq!(read(&store, &key).await.unwrap(), record);
+        assert_eq!(list(&store, "2025-10-11").await.unwrap(), vec![key]);
+        assert!(read(&store, "reading/a.json").await.is_err());
+    }
+}
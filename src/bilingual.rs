@@ -0,0 +1,212 @@
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    content::{ContentParams, ContentSchema, ContentTypeDescriptor},
+    keyvalue::KeyValueStore,
+    problem::ErrorResponse,
+    reading::{MAX_GRADE, MIN_GRADE},
+    session::Session,
+    state::AppState,
+    storage::ObjectStore,
+    validation::{BannedWords, ContentValidator, MinItems, NoEmptyFields},
+    ServiceError,
+};
+
+/// Storage prefix and registry identifier for bilingual reading content
+pub const BILINGUAL_PREFIX: &str = "bilingual";
+
+/// Grade used when the request doesn't specify one
+const DEFAULT_GRADE: u8 = 3;
+
+/// Language passages are translated into when the request doesn't specify one
+const DEFAULT_LANGUAGE: &str = "spanish";
+
+/// Maximum length of a language name, in characters
+const MAX_LANGUAGE_LEN: usize = 30;
+
+/// Query parameters accepted by the bilingual contents endpoint
+#[derive(Debug, Deserialize, Default, JsonSchema)]
+pub struct BilingualQuery {
+    /// Grade level the passage should target
+    pub grade: Option<u8>,
+
+    /// Language the English passage is translated into (e.g., "french")
+    pub language: Option<String>,
+}
+
+impl BilingualQuery {
+    /// Validates the query and converts it into content parameters
+    pub fn into_params(self) -> Result<ContentParams, ServiceError> {
+        let grade = self.grade.unwrap_or(DEFAULT_GRADE);
+        if !(MIN_GRADE..=MAX_GRADE).contains(&grade) {
+            return Err(ServiceError::InvalidInput(format!(
+                "grade must be between {} and {}",
+                MIN_GRADE, MAX_GRADE
+            )));
+        }
+
+        let language = self
+            .language
+            .map(|l| l.trim().to_lowercase())
+            .filter(|l| !l.is_empty())
+            .unwrap_or_else(|| DEFAULT_LANGUAGE.to_string());
+        let valid_chars = language.chars().all(|c| c.is_ascii_alphabetic() || c == ' ');
+        if language.len() > MAX_LANGUAGE_LEN || !valid_chars || language == "english" {
+            return Err(ServiceError::InvalidInput(format!(
+                "language must be a language other than English, in at most {} letters",
+                MAX_LANGUAGE_LEN
+            )));
+        }
+
+        Ok(ContentParams::new().with("grade", grade).with("language", language))
+    }
+}
+
+/// A paragraph of the passage and its translation
+#[derive(Serialize, Deserialize, Clone, JsonSchema)]
+pub struct ParagraphPair {
+    pub english: String,
+    pub translation: String,
+}
+
+/// A reading passage in English with a paragraph-by-paragraph translation, for
+/// language learners
+#[derive(Serialize, Deserialize, Clone, JsonSchema)]
+pub struct BilingualContents {
+    pub title: String,
+    pub translated_title: String,
+
+    /// Language of the translation, in English (e.g., "Spanish")
+    pub language: String,
+    pub paragraphs: Vec<ParagraphPair>,
+
+    /// Comprehension questions, in English
+    pub questions: Vec<String>,
+}
+
+/// Rejects passages whose translations don't line up with the English paragraphs
+///
+/// A translation far shorter or longer than its paragraph usually means the model
+/// merged, split, or skipped paragraphs.
+#[derive(Debug, Clone)]
+pub struct AlignedParagraphs {
+    /// Largest ratio between the word counts of a paragraph and its translation
+    pub max_ratio: f64,
+}
+
+impl Default for AlignedParagraphs {
+    fn default() -> Self {
+        Self { max_ratio: 2.0 }
+    }
+}
+
+impl ContentValidator for AlignedParagraphs {
+    fn validate(&self, content: &Value, _params: &ContentParams) -> Result<(), String> {
+        let paragraphs = content
+            .get("paragraphs")
+            .and_then(Value::as_array)
+            .ok_or_else(|| "missing array field \"paragraphs\"".to_string())?;
+
+        let words = |pair: &Value, field: &str| {
+            pair.get(field)
+                .and_then(Value::as_str)
+                .map_or(0, |text| text.split_whitespace().count())
+        };
+        for (i, pair) in paragraphs.iter().enumerate() {
+            let (english, translation) = (words(pair, "english"), words(pair, "translation"));
+            let ratio = english.max(translation) as f64 / english.min(translation).max(1) as f64;
+            if english == 0 || translation == 0 || ratio > self.max_ratio {
+                return Err(format!(
+                    "paragraph {} has {} English words but {} translated",
+                    i + 1,
+                    english,
+                    translation
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the content type descriptor for bilingual reading passages
+pub fn descriptor() -> ContentTypeDescriptor {
+    ContentTypeDescriptor::new(
+        BILINGUAL_PREFIX,
+        "bilingual_reading",
+        ContentSchema::for_type::<BilingualContents>(
+            "BilingualContents",
+            "A reading passage with a paragraph-aligned translation and questions",
+        ),
+    )
+    .with_validator(MinItems::new("paragraphs", 2))
+    .with_validator(MinItems::new("questions", 3))
+    .with_validator(AlignedParagraphs::default())
+    .with_validator(NoEmptyFields)
+    .with_validator(BannedWords::default())
+    .with_default_params(
+        ContentParams::new()
+            .with("grade", DEFAULT_GRADE)
+            .with("language", DEFAULT_LANGUAGE),
+    )
+}
+
+pub async fn bilingual_contents<S: ObjectStore, K: KeyValueStore>(
+    State(state): State<AppState<S, K>>,
+    Query(query): Query<BilingualQuery>,
+    session: Session,
+) -> Result<Json<BilingualContents>, ErrorResponse> {
+    let params = query.into_params()?;
+
+    let descriptor = state
+        .content_types
+        .get(BILINGUAL_PREFIX)
+        .ok_or_else(|| ServiceError::ConfigError(BILINGUAL_PREFIX.into()))?;
+
+    // Pick the prompt version when the bilingual prompt is under an experiment
+    let session_id = session.id();
+    let params = state
+        .with_prompt_variant(descriptor, params, Some(session_id))
+        .await?;
+
+    // Serve a cached passage the session hasn't read, or generate and store a new one
+    let contents: BilingualContents = state
+        .get_or_generate(descriptor, &params, Some(session_id))
+        .await?;
+
+    Ok(Json(contents))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn validates_query_and_alignment() {
+        let query = BilingualQuery { grade: None, language: Some(" French ".into()) };
+        assert_eq!(query.into_params().unwrap().partition(), "grade-3/language-french/");
+        for language in ["english", "klingon!", ""] {
+            let query = BilingualQuery { grade: None, language: Some(language.into()) };
+            assert_eq!(query.into_params().is_ok(), language.is_empty());
+        }
+
+        let aligned = AlignedParagraphs::default();
+        let params = ContentParams::new();
+        let pair = |english: &str, translation: &str| json!({ "english": english, "translation": translation });
+        let good = json!({ "paragraphs": [pair("The cat sat down.", "El gato se sentó.")] });
+        assert!(aligned.validate(&good, &params).is_ok());
+        let merged = json!({
+            "paragraphs": [pair("The cat sat down.", "El gato se sentó. Luego el perro llegó y jugaron todo el día.")]
+        });
+        assert!(aligned.validate(&merged, &params).is_err());
+        let empty = json!({ "paragraphs": [pair("The cat sat down.", "")] });
+        assert!(aligned.validate(&empty, &params).is_err());
+    }
+}
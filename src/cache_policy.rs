@@ -0,0 +1,174 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// How far back to search for stale content when generation fails
+const STALE_LOOKBACK: Duration = Duration::hours(24);
+
+/// Time window that cached objects are grouped into
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CacheWindow {
+    /// One folder per hour, e.g. `2025-10-11-14`
+    Hourly,
+
+    /// One folder per day, e.g. `2025-10-11`
+    Daily,
+}
+
+impl CacheWindow {
+    /// Formats the folder name for the window containing `dt`
+    pub fn format(&self, dt: &DateTime<Utc>) -> String {
+        match self {
+            CacheWindow::Hourly => dt.format("%Y-%m-%d-%H").to_string(),
+            CacheWindow::Daily => dt.format("%Y-%m-%d").to_string(),
+        }
+    }
+
+    /// Returns the length of the window
+    pub fn duration(&self) -> Duration {
+        match self {
+            CacheWindow::Hourly => Duration::hours(1),
+            CacheWindow::Daily => Duration::days(1),
+        }
+    }
+
+    /// Returns the start times of the current window and earlier windows within the
+    /// stale lookback period, most recent first
+    pub fn lookback(&self, now: DateTime<Utc>) -> impl Iterator<Item = DateTime<Utc>> {
+        let step = self.duration();
+        let windows = (STALE_LOOKBACK.num_seconds() / step.num_seconds()).max(1);
+        (0..=windows).map(move |i| now - step * i as i32)
+    }
+}
+
+/// Caching policy for a content type
+#[derive(Debug, Clone, Deserialize)]
+pub struct CachePolicy {
+    /// Maximum number of objects to store per window before only reusing existing ones
+    pub max_objects: usize,
+
+    /// Time window objects are grouped into
+    pub window: CacheWindow,
+
+    /// Probability of generating new content, rather than reusing cached content,
+    /// while the window holds fewer than `max_objects` objects
+    ///
+    /// At 1.0 every request generates until the window is full. Lower values fill
+    /// the window gradually instead of all at once at the start of each window.
+    pub fill_ratio: f64,
+}
+
+impl Default for CachePolicy {
+    fn default() -> Self {
+        Self {
+            max_objects: 16,
+            window: CacheWindow::Hourly,
+            fill_ratio: 1.0,
+        }
+    }
+}
+
+impl CachePolicy {
+    /// Decides whether to generate new content given how many objects are cached
+    ///
+    /// Always generates for an empty window and never for a full one; otherwise
+    /// generates with probability `fill_ratio`.
+    pub fn should_generate(&self, cached: usize) -> bool {
+        if cached == 0 {
+            return true;
+        }
+        if cached >= self.max_objects {
+            return false;
+        }
+
+        rand::random::<f64>() < self.fill_ratio
+    }
+}
+
+/// Cache policies for all content types, with per-content-type overrides
+#[derive(Debug, Clone, Default)]
+pub struct CachePolicies {
+    default: CachePolicy,
+    overrides: Arc<HashMap<String, CachePolicy>>,
+}
+
+impl CachePolicies {
+    /// Creates policies that apply `default` to every content type
+    pub fn new(default: CachePolicy) -> Self {
+        Self {
+            default,
+            overrides: Arc::new(HashMap::new()),
+        }
+    }
+
+    /// Replaces the policy applied to content types without an override
+    pub fn with_default(mut self, policy: CachePolicy) -> Self {
+        self.default = policy;
+        self
+    }
+
+    /// Sets the policy for a single content type, by prefix
+    pub fn with_policy(mut self, prefix: &str, policy: CachePolicy) -> Self {
+        Arc::make_mut(&mut self.overrides).insert(prefix.to_string(), policy);
+        self
+    }
+
+    /// Returns the policy for a content type
+    pub fn for_content_type(&self, prefix: &str) -> &CachePolicy {
+        self.overrides.get(prefix).unwrap_or(&self.default)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_window_format() {
+        let dt = Utc.with_ymd_and_hms(2025, 10, 11, 14, 30, 0).unwrap();
+        assert_eq!(CacheWindow::Hourly.format(&dt), "2025-10-11-14");
+        assert_eq!(CacheWindow::Daily.format(&dt), "2025-10-11");
+    }
+
+    #[test]
+    fn test_lookback_covers_a_day() {
+        let now = Utc::now();
+        assert_eq!(CacheWindow::Hourly.lookback(now).count(), 25);
+        assert_eq!(CacheWindow::Daily.lookback(now).count(), 2);
+    }
+
+    #[test]
+    fn test_should_generate() {
+        let policy = CachePolicy {
+            max_objects: 4,
+            window: CacheWindow::Hourly,
+            fill_ratio: 0.0,
+        };
+        assert!(policy.should_generate(0));
+        assert!(!policy.should_generate(2));
+        assert!(!policy.should_generate(4));
+
+        let policy = CachePolicy {
+            fill_ratio: 1.0,
+            ..policy
+        };
+        assert!(policy.should_generate(3));
+        assert!(!policy.should_generate(4));
+    }
+
+    #[test]
+    fn test_overrides() {
+        let policies = CachePolicies::default().with_policy(
+            "reading",
+            CachePolicy {
+                max_objects: 2,
+                ..CachePolicy::default()
+            },
+        );
+        assert_eq!(policies.for_content_type("reading").max_objects, 2);
+        assert_eq!(policies.for_content_type("math").max_objects, 16);
+    }
+}
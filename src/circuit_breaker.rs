@@ -0,0 +1,170 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::metrics;
+
+/// Current state of a circuit breaker
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitState {
+    /// Calls flow normally; counts consecutive failures
+    Closed { consecutive_failures: u32 },
+
+    /// Calls are rejected until the deadline passes
+    Open { until: Instant },
+
+    /// A single probe call is allowed through to test recovery
+    HalfOpen { probe_started: Instant },
+}
+
+/// Circuit breaker protecting calls to an unreliable dependency
+///
+/// After `failure_threshold` consecutive failures the circuit opens and calls are
+/// rejected for `open_duration`, letting callers fall back to cached content instead
+/// of hammering a degraded provider. Afterwards a single probe is let through: success
+/// closes the circuit, failure opens it again.
+#[derive(Debug, Clone)]
+pub struct CircuitBreaker {
+    name: String,
+    state: Arc<Mutex<CircuitState>>,
+    failure_threshold: u32,
+    open_duration: Duration,
+}
+
+impl CircuitBreaker {
+    /// Creates a closed circuit breaker
+    ///
+    /// # Arguments
+    /// * `name` - Name used in metrics (e.g., "llm")
+    /// * `failure_threshold` - Consecutive failures that trip the circuit
+    /// * `open_duration` - How long the circuit stays open before probing
+    pub fn new(name: &str, failure_threshold: u32, open_duration: Duration) -> Self {
+        Self {
+            name: name.to_string(),
+            state: Arc::new(Mutex::new(CircuitState::Closed {
+                consecutive_failures: 0,
+            })),
+            failure_threshold,
+            open_duration,
+        }
+    }
+
+    /// Returns the current state
+    pub fn state(&self) -> CircuitState {
+        *self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Returns whether a call may proceed, transitioning to half-open when due
+    pub fn allow_request(&self) -> bool {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let now = Instant::now();
+
+        let allowed = match *state {
+            CircuitState::Closed { .. } => true,
+            CircuitState::Open { until } if now >= until => {
+                *state = CircuitState::HalfOpen { probe_started: now };
+                true
+            }
+            CircuitState::Open { .. } => false,
+            // Allow another probe if the previous one never reported back
+            CircuitState::HalfOpen { probe_started }
+                if now.duration_since(probe_started) >= self.open_duration =>
+            {
+                *state = CircuitState::HalfOpen { probe_started: now };
+                true
+            }
+            CircuitState::HalfOpen { .. } => false,
+        };
+
+        if !allowed {
+            metrics::increment(&format!("circuit_breaker.{}.rejected", self.name));
+        }
+
+        allowed
+    }
+
+    /// Records a successful call, closing the circuit
+    pub fn record_success(&self) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        *state = CircuitState::Closed {
+            consecutive_failures: 0,
+        };
+    }
+
+    /// Records a failed call, opening the circuit once the threshold is reached
+    pub fn record_failure(&self) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+
+        let failures = match *state {
+            CircuitState::Closed {
+                consecutive_failures,
+            } => consecutive_failures + 1,
+            // A failed probe reopens the circuit immediately
+            CircuitState::HalfOpen { .. } | CircuitState::Open { .. } => self.failure_threshold,
+        };
+
+        *state = if failures >= self.failure_threshold {
+            metrics::increment(&format!("circuit_breaker.{}.opened", self.name));
+            CircuitState::Open {
+                until: Instant::now() + self.open_duration,
+            }
+        } else {
+            CircuitState::Closed {
+                consecutive_failures: failures,
+            }
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_opens_after_threshold() {
+        let breaker = CircuitBreaker::new("test", 2, Duration::from_secs(60));
+
+        breaker.record_failure();
+        assert!(breaker.allow_request());
+        breaker.record_failure();
+        assert!(!breaker.allow_request());
+    }
+
+    #[test]
+    fn test_success_resets_failures() {
+        let breaker = CircuitBreaker::new("test", 2, Duration::from_secs(60));
+
+        breaker.record_failure();
+        breaker.record_success();
+        breaker.record_failure();
+        assert!(breaker.allow_request());
+    }
+
+    #[test]
+    fn test_half_open_allows_single_probe() {
+        let breaker = CircuitBreaker::new("test", 1, Duration::from_millis(10));
+
+        breaker.record_failure();
+        assert!(!breaker.allow_request());
+
+        std::thread::sleep(Duration::from_millis(20));
+        assert!(breaker.allow_request());
+        assert!(!breaker.allow_request());
+
+        breaker.record_success();
+        assert!(breaker.allow_request());
+    }
+
+    #[test]
+    fn test_failed_probe_reopens() {
+        let breaker = CircuitBreaker::new("test", 3, Duration::from_millis(10));
+
+        for _ in 0..3 {
+            breaker.record_failure();
+        }
+        std::thread::sleep(Duration::from_millis(20));
+        assert!(breaker.allow_request());
+
+        breaker.record_failure();
+        assert!(matches!(breaker.state(), CircuitState::Open { .. }));
+    }
+}
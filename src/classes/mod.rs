@@ -0,0 +1,478 @@
+mod results;
+mod routes;
+
+pub use routes::router;
+
+use chrono::{SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    ServiceError,
+    keyvalue::{Column, KeyValueStore, RecordQuery, TypedKv, column_json, encode_json},
+    users::ChildProfile,
+};
+
+/// Characters join codes are made of, leaving out ones that are easily confused
+/// (0/O, 1/I/L)
+const JOIN_CODE_ALPHABET: &[u8] = b"ABCDEFGHJKMNPQRSTUVWXYZ23456789";
+
+/// Length of a join code
+const JOIN_CODE_LEN: usize = 8;
+
+/// Times a new join code is drawn when it collides with an existing one
+const MAX_JOIN_CODE_ATTEMPTS: usize = 5;
+
+/// Times a class update is retried when another write wins the race
+const MAX_UPDATE_ATTEMPTS: usize = 3;
+
+/// Most classes per teacher
+const MAX_CLASSES: usize = 50;
+
+/// Most students per class
+const MAX_STUDENTS: usize = 200;
+
+/// Longest class name accepted, in characters
+const MAX_NAME_LEN: usize = 60;
+
+/// Column of the class item holding its JSON
+const CLASS_COLUMN: &str = "class";
+
+/// Column of the join code item holding the class ID
+const CLASS_ID_COLUMN: &str = "class_id";
+
+/// Column of a class index record holding when it was added, as Unix seconds
+///
+/// Index records need a column, since some backends store a record as its columns.
+const ADDED_AT_COLUMN: &str = "added_at";
+
+/// Column of a roster record holding the student's JSON
+const STUDENT_COLUMN: &str = "student";
+
+/// Column of an assignment record holding its JSON
+const ASSIGNMENT_COLUMN: &str = "assignment";
+
+/// A teacher's class
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Class {
+    pub id: String,
+    pub name: String,
+
+    /// ID of the teacher's account
+    pub teacher_id: String,
+
+    /// Code parents enter to add a child to the class
+    pub join_code: String,
+
+    /// When the class was created, as Unix seconds
+    pub created_at: i64,
+}
+
+/// A child on a class's roster
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Student {
+    /// ID of the child profile
+    pub child: String,
+    pub name: String,
+    pub grade: u8,
+
+    /// ID of the parent account the profile belongs to
+    pub account_id: String,
+
+    /// When the child joined, as Unix seconds
+    pub joined_at: i64,
+}
+
+/// A story assigned to a class
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Assignment {
+    pub id: String,
+    pub class_id: String,
+
+    /// Key of the content object to read
+    pub object_key: String,
+
+    /// When the story was assigned, in RFC 3339 format
+    pub assigned_at: String,
+}
+
+/// Key of the item holding a class
+fn class_key(id: &str) -> String {
+    format!("class#{}", id)
+}
+
+/// Key of the item mapping a join code to its class
+fn join_code_key(code: &str) -> String {
+    format!("class-code#{}", code)
+}
+
+/// Partition key of the records listing a teacher's classes, by class ID
+fn teacher_classes_key(teacher_id: &str) -> String {
+    format!("teacher-classes#{}", teacher_id)
+}
+
+/// Partition key of the records listing a class's students, by child ID
+fn roster_key(class_id: &str) -> String {
+    format!("class-roster#{}", class_id)
+}
+
+/// Partition key of the records listing the classes a child is in, by class ID
+fn child_classes_key(child: &str) -> String {
+    format!("child-classes#{}", child)
+}
+
+/// Partition key of the records listing a class's assignments, by assignment time
+fn assignments_key(class_id: &str) -> String {
+    format!("class-assignments#{}", class_id)
+}
+
+/// Draws a random join code
+fn new_join_code() -> String {
+    (0..JOIN_CODE_LEN)
+        .map(|_| JOIN_CODE_ALPHABET[rand::random::<usize>() % JOIN_CODE_ALPHABET.len()] as char)
+        .collect()
+}
+
+/// Uppercases a join code as typed and drops separators, so "abcd-efgh" matches
+fn normalize_join_code(code: &str) -> String {
+    code.chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_uppercase())
+        .collect()
+}
+
+/// Claims an unused join code for a class
+async fn claim_join_code<K: KeyValueStore>(kv_store: &K, class_id: &str) -> Result<String, ServiceError> {
+    for _ in 0..MAX_JOIN_CODE_ATTEMPTS {
+        let code = new_join_code();
+        let claimed = kv_store
+            .put_if_not_exists(
+                join_code_key(&code),
+                vec![Column::new(CLASS_ID_COLUMN.to_string(), class_id.as_bytes().to_vec())],
+                None,
+            )
+            .await?;
+        if claimed {
+            return Ok(code);
+        }
+    }
+
+    Err(ServiceError::Conflict("Couldn't find an unused join code; try again".into()))
+}
+
+/// Adds a class ID to an index partition
+async fn add_to_index<K: KeyValueStore>(
+    kv_store: &K,
+    partition_key: String,
+    class_id: &str,
+) -> Result<(), ServiceError> {
+    let columns = vec![Column::counter(ADDED_AT_COLUMN.to_string(), Utc::now().timestamp())];
+    kv_store
+        .put_record(partition_key, class_id.to_string(), columns, None)
+        .await
+}
+
+/// Sort keys of an index or roster partition, in order
+async fn record_ids<K: KeyValueStore>(
+    kv_store: &K,
+    partition_key: String,
+    column: &str,
+) -> Result<Vec<String>, ServiceError> {
+    Ok(kv_store
+        .query_records(RecordQuery::new(partition_key), vec![column.to_string()])
+        .await?
+        .into_iter()
+        .map(|record| record.sort_key)
+        .collect())
+}
+
+/// Loads classes by ID, skipping any that were deleted
+async fn load_classes<K: KeyValueStore>(kv_store: &K, ids: &[String]) -> Result<Vec<Class>, ServiceError> {
+    let items = kv_store
+        .batch_get(ids.iter().map(|id| class_key(id)).collect(), vec![CLASS_COLUMN.to_string()])
+        .await?;
+
+    let mut classes = Vec::new();
+    for id in ids {
+        if let Some(columns) = items.get(&class_key(id))
+            && let Some(class) = column_json(columns, CLASS_COLUMN)?
+        {
+            classes.push(class);
+        }
+    }
+    Ok(classes)
+}
+
+/// Creates a class with a fresh join code
+pub async fn create_class<K: KeyValueStore>(
+    kv_store: &K,
+    teacher_id: &str,
+    name: &str,
+) -> Result<Class, ServiceError> {
+    let name = name.trim();
+    if name.is_empty() || name.chars().count() > MAX_NAME_LEN {
+        return Err(ServiceError::InvalidInput(format!(
+            "name must be 1 to {} characters",
+            MAX_NAME_LEN
+        )));
+    }
+    let existing = record_ids(kv_store, teacher_classes_key(teacher_id), ADDED_AT_COLUMN).await?;
+    if existing.len() >= MAX_CLASSES {
+        return Err(ServiceError::InvalidInput(format!(
+            "a teacher can have at most {} classes",
+            MAX_CLASSES
+        )));
+    }
+
+    let id = Uuid::new_v4().simple().to_string();
+    let class = Class {
+        join_code: claim_join_code(kv_store, &id).await?,
+        id,
+        name: name.to_string(),
+        teacher_id: teacher_id.to_string(),
+        created_at: Utc::now().timestamp(),
+    };
+
+    kv_store
+        .put_json(&class_key(&class.id), CLASS_COLUMN, &class, None)
+        .await?;
+    add_to_index(kv_store, teacher_classes_key(teacher_id), &class.id).await?;
+    Ok(class)
+}
+
+/// Loads a class by ID
+pub async fn load_class<K: KeyValueStore>(kv_store: &K, id: &str) -> Result<Option<Class>, ServiceError> {
+    kv_store.get_json(&class_key(id), CLASS_COLUMN).await
+}
+
+/// Loads a class, checking that it belongs to the teacher
+///
+/// Another teacher's class is reported as missing rather than forbidden, so class
+/// IDs can't be probed.
+pub async fn teacher_class<K: KeyValueStore>(
+    kv_store: &K,
+    teacher_id: &str,
+    id: &str,
+) -> Result<Class, ServiceError> {
+    load_class(kv_store, id)
+        .await?
+        .filter(|class| class.teacher_id == teacher_id)
+        .ok_or_else(|| ServiceError::NotFound(format!("Unknown class: {}", id)))
+}
+
+/// Returns a teacher's classes
+pub async fn teacher_classes<K: KeyValueStore>(
+    kv_store: &K,
+    teacher_id: &str,
+) -> Result<Vec<Class>, ServiceError> {
+    let ids = record_ids(kv_store, teacher_classes_key(teacher_id), ADDED_AT_COLUMN).await?;
+    load_classes(kv_store, &ids).await
+}
+
+/// Replaces a class's join code, so the old one stops working
+pub async fn reset_join_code<K: KeyValueStore>(kv_store: &K, class: &Class) -> Result<Class, ServiceError> {
+    let code = claim_join_code(kv_store, &class.id).await?;
+    let key = class_key(&class.id);
+
+    for _ in 0..MAX_UPDATE_ATTEMPTS {
+        let (version, current) = kv_store.get_json_versioned::<Class>(&key, CLASS_COLUMN).await?;
+        let mut current =
+            current.ok_or_else(|| ServiceError::NotFound(format!("Unknown class: {}", class.id)))?;
+        let old_code = std::mem::replace(&mut current.join_code, code.clone());
+
+        if kv_store
+            .put_json_if_version(&key, CLASS_COLUMN, &current, version)
+            .await?
+        {
+            kv_store.delete(join_code_key(&old_code)).await?;
+            return Ok(current);
+        }
+    }
+
+    kv_store.delete(join_code_key(&code)).await?;
+    Err(ServiceError::Conflict(
+        "The class was changed by another request; try again".into(),
+    ))
+}
+
+/// Adds a parent's child to the class with a join code
+///
+/// Joining a class the child is already in succeeds without changes.
+pub async fn join_class<K: KeyValueStore>(
+    kv_store: &K,
+    code: &str,
+    account_id: &str,
+    profile: &ChildProfile,
+) -> Result<Class, ServiceError> {
+    let code = normalize_join_code(code);
+    let unknown = || ServiceError::NotFound("No class has that join code".into());
+
+    let columns = kv_store
+        .get(join_code_key(&code), vec![CLASS_ID_COLUMN.to_string()])
+        .await?;
+    let class_id = columns
+        .into_iter()
+        .find(|column| column.name == CLASS_ID_COLUMN)
+        .and_then(|column| String::from_utf8(column.value).ok())
+        .ok_or_else(unknown)?;
+    // A code left behind by a reset no longer matches its class
+    let class = load_class(kv_store, &class_id)
+        .await?
+        .filter(|class| class.join_code == code)
+        .ok_or_else(unknown)?;
+
+    let roster = record_ids(kv_store, roster_key(&class.id), STUDENT_COLUMN).await?;
+    if roster.contains(&profile.id) {
+        return Ok(class);
+    }
+    if roster.len() >= MAX_STUDENTS {
+        return Err(ServiceError::Conflict(format!(
+            "The class is full ({} students)",
+            MAX_STUDENTS
+        )));
+    }
+
+    let student = Student {
+        child: profile.id.clone(),
+        name: profile.name.clone(),
+        grade: profile.grade,
+        account_id: account_id.to_string(),
+        joined_at: Utc::now().timestamp(),
+    };
+    kv_store
+        .put_record(
+            roster_key(&class.id),
+            student.child.clone(),
+            vec![Column::new(STUDENT_COLUMN.to_string(), encode_json(&student)?)],
+            None,
+        )
+        .await?;
+    add_to_index(kv_store, child_classes_key(&profile.id), &class.id).await?;
+    Ok(class)
+}
+
+/// Returns a class's students, ordered by child ID
+pub async fn roster<K: KeyValueStore>(kv_store: &K, class_id: &str) -> Result<Vec<Student>, ServiceError> {
+    let records = kv_store
+        .query_records(RecordQuery::new(roster_key(class_id)), vec![STUDENT_COLUMN.to_string()])
+        .await?;
+
+    records
+        .iter()
+        .filter_map(|record| column_json(&record.columns, STUDENT_COLUMN).transpose())
+        .collect()
+}
+
+/// Returns the classes a child is in
+pub async fn child_classes<K: KeyValueStore>(kv_store: &K, child: &str) -> Result<Vec<Class>, ServiceError> {
+    let ids = record_ids(kv_store, child_classes_key(child), ADDED_AT_COLUMN).await?;
+    load_classes(kv_store, &ids).await
+}
+
+/// Assigns a story to a class
+///
+/// The caller checks that `object_key` names existing content.
+pub async fn assign<K: KeyValueStore>(
+    kv_store: &K,
+    class_id: &str,
+    object_key: &str,
+) -> Result<Assignment, ServiceError> {
+    let assignment = Assignment {
+        id: Uuid::new_v4().simple().to_string(),
+        class_id: class_id.to_string(),
+        object_key: object_key.to_string(),
+        assigned_at: Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+    };
+
+    kv_store
+        .put_record(
+            assignments_key(class_id),
+            format!("{}#{}", assignment.assigned_at, assignment.id),
+            vec![Column::new(ASSIGNMENT_COLUMN.to_string(), encode_json(&assignment)?)],
+            None,
+        )
+        .await?;
+    Ok(assignment)
+}
+
+/// Returns a class's assignments, most recent first
+pub async fn assignments<K: KeyValueStore>(
+    kv_store: &K,
+    class_id: &str,
+) -> Result<Vec<Assignment>, ServiceError> {
+    let records = kv_store
+        .query_records(
+            RecordQuery::new(assignments_key(class_id)).descending(),
+            vec![ASSIGNMENT_COLUMN.to_string()],
+        )
+        .await?;
+
+    records
+        .iter()
+        .filter_map(|record| column_json(&record.columns, ASSIGNMENT_COLUMN).transpose())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keyvalue::MemoryKeyValueStore;
+
+    fn child(id: &str) -> ChildProfile {
+        ChildProfile {
+            id: id.to_string(),
+            name: "Ada".to_string(),
+            grade: 3,
+            interests: Vec::new(),
+            pet: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn students_join_with_the_current_code() {
+        let store = MemoryKeyValueStore::new();
+        let class = create_class(&store, "t1", " Room 4 ").await.unwrap();
+        assert_eq!(class.name, "Room 4");
+        assert_eq!(class.join_code.len(), JOIN_CODE_LEN);
+
+        let code = class.join_code.to_lowercase();
+        join_class(&store, &code, "p1", &child("c1")).await.unwrap();
+        join_class(&store, &code, "p1", &child("c1")).await.unwrap();
+        assert_eq!(roster(&store, &class.id).await.unwrap().len(), 1);
+        assert_eq!(child_classes(&store, "c1").await.unwrap(), vec![class.clone()]);
+
+        let reset = reset_join_code(&store, &class).await.unwrap();
+        assert_ne!(reset.join_code, class.join_code);
+        let stale = join_class(&store, &class.join_code, "p2", &child("c2")).await;
+        assert!(matches!(stale, Err(ServiceError::NotFound(_))));
+        join_class(&store, &reset.join_code, "p2", &child("c2")).await.unwrap();
+
+        let students: Vec<String> = roster(&store, &class.id).await.unwrap().into_iter().map(|s| s.child).collect();
+        assert_eq!(students, vec!["c1", "c2"]);
+    }
+
+    #[tokio::test]
+    async fn lists_only_the_teachers_classes() {
+        let store = MemoryKeyValueStore::new();
+        let class = create_class(&store, "t1", "Room 4").await.unwrap();
+        create_class(&store, "t2", "Room 5").await.unwrap();
+
+        assert_eq!(teacher_classes(&store, "t1").await.unwrap(), vec![class.clone()]);
+        assert!(teacher_class(&store, "t1", &class.id).await.is_ok());
+        assert!(teacher_class(&store, "t2", &class.id).await.is_err());
+        assert!(create_class(&store, "t1", "").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn lists_assignments_most_recent_first() {
+        let store = MemoryKeyValueStore::new();
+        let first = assign(&store, "k1", "reading/2025-10-11-14/a.json").await.unwrap();
+        // Assignments are ordered by time to the millisecond
+        tokio::time::sleep(std::time::Duration::from_millis(2)).await;
+        let second = assign(&store, "k1", "reading/2025-10-11-14/b.json").await.unwrap();
+
+        let keys: Vec<String> = assignments(&store, "k1").await.unwrap().into_iter().map(|a| a.id).collect();
+        assert_eq!(keys, vec![second.id, first.id]);
+        assert!(assignments(&store, "k2").await.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,173 @@
+use bytes::Bytes;
+use chrono::{DateTime, Utc};
+use futures::{Stream, StreamExt, stream};
+use std::collections::HashMap;
+
+use super::{Assignment, Student};
+use crate::{ServiceError, keyvalue::KeyValueStore, progress};
+
+/// Header row of the results CSV
+const HEADER: &str = "student_id,student_name,assignment_id,object_key,assigned_at,score,completed_at\r\n";
+
+/// Quotes a CSV field if needed, and defuses values a spreadsheet would run as a
+/// formula, since student names come from parents
+fn csv_field(value: &str) -> String {
+    let value = if value.starts_with(['=', '+', '-', '@', '\t', '\r']) {
+        format!("'{}", value)
+    } else {
+        value.to_string()
+    };
+
+    if value.contains([',', '"', '\r', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value
+    }
+}
+
+/// Formats one student's rows: one per assignment, with their latest score on it,
+/// or empty score and time if they haven't completed it
+fn student_rows(
+    student: &Student,
+    assignments: &[Assignment],
+    latest: &HashMap<String, progress::ActivityResult>,
+) -> String {
+    let mut rows = String::new();
+    for assignment in assignments {
+        let result = latest.get(&assignment.object_key);
+        let fields = [
+            csv_field(&student.child),
+            csv_field(&student.name),
+            csv_field(&assignment.id),
+            csv_field(&assignment.object_key),
+            csv_field(&assignment.assigned_at),
+            result.map(|r| r.score.to_string()).unwrap_or_default(),
+            result.map(|r| csv_field(&r.completed_at)).unwrap_or_default(),
+        ];
+        rows.push_str(&fields.join(","));
+        rows.push_str("\r\n");
+    }
+    rows
+}
+
+/// Returns a student's latest result for each assigned object completed since
+/// `since`
+async fn latest_results<K: KeyValueStore>(
+    kv_store: &K,
+    child: &str,
+    assignments: &[Assignment],
+    since: Option<DateTime<Utc>>,
+) -> Result<HashMap<String, progress::ActivityResult>, ServiceError> {
+    let history = progress::history(kv_store, child, since, usize::MAX).await?;
+
+    let mut latest = HashMap::new();
+    // History is most recent first, so the first result per key is the latest
+    for result in history {
+        if let Some(key) = &result.content_key
+            && assignments.iter().any(|a| &a.object_key == key)
+            && !latest.contains_key(key)
+        {
+            latest.insert(key.clone(), result);
+        }
+    }
+    Ok(latest)
+}
+
+/// Streams a class's results as CSV, one student at a time
+///
+/// Each student's history is queried only when the previous student's rows have
+/// been sent, so memory use doesn't grow with the size of the class.
+pub fn results_csv<K: KeyValueStore + 'static>(
+    kv_store: K,
+    roster: Vec<Student>,
+    assignments: Vec<Assignment>,
+) -> impl Stream<Item = Result<Bytes, ServiceError>> + Send + 'static {
+    // Activities completed before the first assignment can't count toward any
+    let since = assignments
+        .iter()
+        .filter_map(|a| DateTime::parse_from_rfc3339(&a.assigned_at).ok())
+        .map(|time| time.with_timezone(&Utc))
+        .min();
+
+    let header = stream::once(async { Ok(Bytes::from_static(HEADER.as_bytes())) });
+    let rows = stream::iter(roster).then(move |student| {
+        let kv_store = kv_store.clone();
+        let assignments = assignments.clone();
+        async move {
+            let latest = latest_results(&kv_store, &student.child, &assignments, since).await?;
+            Ok(Bytes::from(student_rows(&student, &assignments, &latest)))
+        }
+    });
+
+    header.chain(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        keyvalue::MemoryKeyValueStore,
+        progress::{NewActivity, Skill},
+    };
+    use futures::TryStreamExt;
+
+    fn student(child: &str, name: &str) -> Student {
+        Student {
+            child: child.to_string(),
+            name: name.to_string(),
+            grade: 3,
+            account_id: "p1".to_string(),
+            joined_at: 0,
+        }
+    }
+
+    fn assignment(id: &str, key: &str) -> Assignment {
+        Assignment {
+            id: id.to_string(),
+            class_id: "k1".to_string(),
+            object_key: key.to_string(),
+            assigned_at: "2025-10-01T00:00:00.000Z".to_string(),
+        }
+    }
+
+    #[test]
+    fn escapes_fields() {
+        assert_eq!(csv_field("Ada"), "Ada");
+        assert_eq!(csv_field("Lovelace, Ada"), "\"Lovelace, Ada\"");
+        assert_eq!(csv_field("Ada \"A\""), "\"Ada \"\"A\"\"\"");
+        assert_eq!(csv_field("=1+1"), "'=1+1");
+    }
+
+    #[tokio::test]
+    async fn streams_latest_scores_per_student_and_assignment() {
+        let store = MemoryKeyValueStore::new();
+        let completed = |score, time: &str| {
+            let activity = NewActivity {
+                child: "c1".to_string(),
+                activity: "reading".to_string(),
+                content_key: Some("reading/a.json".to_string()),
+                skill: Skill::Comprehension,
+                score,
+            };
+            let time = DateTime::parse_from_rfc3339(time).unwrap().with_timezone(&Utc);
+            progress::record(&store, activity, time)
+        };
+        completed(50, "2025-10-02T10:00:00Z").await.unwrap();
+        completed(90, "2025-10-03T10:00:00Z").await.unwrap();
+
+        let roster = vec![student("c1", "Ada, L"), student("c2", "Bo")];
+        let assignments = vec![assignment("a1", "reading/a.json"), assignment("a2", "reading/b.json")];
+        let chunks: Vec<Bytes> = results_csv(store, roster, assignments).try_collect().await.unwrap();
+        let csv = String::from_utf8(chunks.concat()).unwrap();
+
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 5);
+        assert_eq!(lines[0], HEADER.trim_end());
+        assert_eq!(
+            lines[1],
+            "c1,\"Ada, L\",a1,reading/a.json,2025-10-01T00:00:00.000Z,90,2025-10-03T10:00:00.000Z"
+        );
+        assert_eq!(lines[2], "c1,\"Ada, L\",a2,reading/b.json,2025-10-01T00:00:00.000Z,,");
+        assert_eq!(lines[3], "c2,Bo,a1,reading/a.json,2025-10-01T00:00:00.000Z,,");
+    }
+}
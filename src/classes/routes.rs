@@ -0,0 +1,235 @@
+use axum::{
+    Json, Router,
+    body::Body,
+    extract::{Path, Query, State},
+    http::{StatusCode, header},
+    response::Response,
+    routing::{get, post},
+};
+use serde::{Deserialize, Serialize};
+use tracing::error;
+
+use super::{Assignment, Class, Student};
+use crate::{
+    ServiceError,
+    keyvalue::KeyValueStore,
+    problem::ErrorResponse,
+    state::AppState,
+    storage::ObjectStore,
+    users::{ChildProfile, CurrentUser, Role},
+};
+
+/// Name sent to create a class
+#[derive(Deserialize)]
+pub struct NewClass {
+    pub name: String,
+}
+
+/// Join code a parent enters to add a child to a class
+#[derive(Deserialize)]
+pub struct JoinRequest {
+    pub code: String,
+
+    /// ID of the child profile joining; the selected profile if omitted
+    pub child: Option<String>,
+}
+
+/// Story sent to assign to a class
+#[derive(Deserialize)]
+pub struct NewAssignment {
+    /// Key of a generated content object, e.g. "reading/2025-10-11-14/1a2b.json"
+    pub object_key: String,
+}
+
+#[derive(Deserialize)]
+pub struct ChildQuery {
+    /// ID of the child profile; the selected profile if omitted
+    pub child: Option<String>,
+}
+
+/// A class a child is in, with its assignments
+///
+/// Leaves out the join code, which only the teacher sees.
+#[derive(Serialize)]
+pub struct EnrolledClass {
+    pub id: String,
+    pub name: String,
+    pub assignments: Vec<Assignment>,
+}
+
+/// Builds the router for class endpoints, to be nested under `/classes`
+///
+/// Routes must run inside the `session` and `current_user` middleware.
+pub fn router<S, K>() -> Router<AppState<S, K>>
+where
+    S: ObjectStore + 'static,
+    K: KeyValueStore + 'static,
+{
+    Router::new()
+        .route("/", get(list_classes).post(create_class))
+        .route("/join", post(join_class))
+        .route("/enrolled", get(enrolled_classes))
+        .route("/{id}", get(get_class))
+        .route("/{id}/join-code", post(reset_join_code))
+        .route("/{id}/roster", get(get_roster))
+        .route("/{id}/assignments", get(list_assignments).post(assign))
+        .route("/{id}/results.csv", get(results_csv))
+}
+
+/// Returns the child profile a parent names, or their selected one
+fn child_profile(user: &CurrentUser, child: Option<String>) -> Result<ChildProfile, ServiceError> {
+    let child = child
+        .or_else(|| user.profile.as_ref().map(|profile| profile.id.clone()))
+        .ok_or_else(|| ServiceError::InvalidInput("child is required when no profile is selected".into()))?;
+    user.account
+        .profile(&child)
+        .cloned()
+        .ok_or_else(|| ServiceError::NotFound(format!("Unknown profile: {}", child)))
+}
+
+/// Creates a class owned by the logged-in teacher
+pub async fn create_class<S: ObjectStore, K: KeyValueStore>(
+    State(state): State<AppState<S, K>>,
+    user: CurrentUser,
+    Json(class): Json<NewClass>,
+) -> Result<(StatusCode, Json<Class>), ErrorResponse> {
+    user.require_role(Role::Teacher)?;
+    let class = super::create_class(&state.kv_store, &user.account.id, &class.name).await?;
+    Ok((StatusCode::CREATED, Json(class)))
+}
+
+/// Lists the logged-in teacher's classes
+pub async fn list_classes<S: ObjectStore, K: KeyValueStore>(
+    State(state): State<AppState<S, K>>,
+    user: CurrentUser,
+) -> Result<Json<Vec<Class>>, ErrorResponse> {
+    user.require_role(Role::Teacher)?;
+    Ok(Json(super::teacher_classes(&state.kv_store, &user.account.id).await?))
+}
+
+/// Returns one of the logged-in teacher's classes
+pub async fn get_class<S: ObjectStore, K: KeyValueStore>(
+    State(state): State<AppState<S, K>>,
+    user: CurrentUser,
+    Path(id): Path<String>,
+) -> Result<Json<Class>, ErrorResponse> {
+    user.require_role(Role::Teacher)?;
+    Ok(Json(super::teacher_class(&state.kv_store, &user.account.id, &id).await?))
+}
+
+/// Replaces a class's join code
+pub async fn reset_join_code<S: ObjectStore, K: KeyValueStore>(
+    State(state): State<AppState<S, K>>,
+    user: CurrentUser,
+    Path(id): Path<String>,
+) -> Result<Json<Class>, ErrorResponse> {
+    user.require_role(Role::Teacher)?;
+    let class = super::teacher_class(&state.kv_store, &user.account.id, &id).await?;
+    Ok(Json(super::reset_join_code(&state.kv_store, &class).await?))
+}
+
+/// Lists a class's students
+pub async fn get_roster<S: ObjectStore, K: KeyValueStore>(
+    State(state): State<AppState<S, K>>,
+    user: CurrentUser,
+    Path(id): Path<String>,
+) -> Result<Json<Vec<Student>>, ErrorResponse> {
+    user.require_role(Role::Teacher)?;
+    let class = super::teacher_class(&state.kv_store, &user.account.id, &id).await?;
+    Ok(Json(super::roster(&state.kv_store, &class.id).await?))
+}
+
+/// Assigns an existing story to a class
+pub async fn assign<S: ObjectStore, K: KeyValueStore>(
+    State(state): State<AppState<S, K>>,
+    user: CurrentUser,
+    Path(id): Path<String>,
+    Json(assignment): Json<NewAssignment>,
+) -> Result<(StatusCode, Json<Assignment>), ErrorResponse> {
+    user.require_role(Role::Teacher)?;
+    let class = super::teacher_class(&state.kv_store, &user.account.id, &id).await?;
+
+    let key = assignment.object_key.trim_start_matches('/');
+    if !state.content_types.is_content_key(key) {
+        return Err(ServiceError::InvalidInput(format!("{:?} is not a content key", key)).into());
+    }
+    if !state.object_store.object_exists(key).await? {
+        return Err(ServiceError::NotFound(format!("No content at {}", key)).into());
+    }
+
+    let assignment = super::assign(&state.kv_store, &class.id, key).await?;
+    Ok((StatusCode::CREATED, Json(assignment)))
+}
+
+/// Lists a class's assignments, most recent first
+pub async fn list_assignments<S: ObjectStore, K: KeyValueStore>(
+    State(state): State<AppState<S, K>>,
+    user: CurrentUser,
+    Path(id): Path<String>,
+) -> Result<Json<Vec<Assignment>>, ErrorResponse> {
+    user.require_role(Role::Teacher)?;
+    let class = super::teacher_class(&state.kv_store, &user.account.id, &id).await?;
+    Ok(Json(super::assignments(&state.kv_store, &class.id).await?))
+}
+
+/// Adds one of the logged-in parent's children to a class
+pub async fn join_class<S: ObjectStore, K: KeyValueStore>(
+    State(state): State<AppState<S, K>>,
+    user: CurrentUser,
+    Json(request): Json<JoinRequest>,
+) -> Result<Json<EnrolledClass>, ErrorResponse> {
+    user.require_role(Role::Parent)?;
+    let profile = child_profile(&user, request.child)?;
+
+    let class = super::join_class(&state.kv_store, &request.code, &user.account.id, &profile).await?;
+    let assignments = super::assignments(&state.kv_store, &class.id).await?;
+    Ok(Json(EnrolledClass { id: class.id, name: class.name, assignments }))
+}
+
+/// Lists the classes one of the logged-in parent's children is in, with their
+/// assignments
+pub async fn enrolled_classes<S: ObjectStore, K: KeyValueStore>(
+    State(state): State<AppState<S, K>>,
+    user: CurrentUser,
+    Query(query): Query<ChildQuery>,
+) -> Result<Json<Vec<EnrolledClass>>, ErrorResponse> {
+    user.require_role(Role::Parent)?;
+    let profile = child_profile(&user, query.child)?;
+
+    let mut enrolled = Vec::new();
+    for class in super::child_classes(&state.kv_store, &profile.id).await? {
+        let assignments = super::assignments(&state.kv_store, &class.id).await?;
+        enrolled.push(EnrolledClass { id: class.id, name: class.name, assignments });
+    }
+    Ok(Json(enrolled))
+}
+
+/// Streams a class's assignment results as CSV: a row per student and assignment
+/// with the student's latest score and when they completed it
+pub async fn results_csv<S: ObjectStore, K: KeyValueStore + 'static>(
+    State(state): State<AppState<S, K>>,
+    user: CurrentUser,
+    Path(id): Path<String>,
+) -> Result<Response, ErrorResponse> {
+    user.require_role(Role::Teacher)?;
+    let class = super::teacher_class(&state.kv_store, &user.account.id, &id).await?;
+    let roster = super::roster(&state.kv_store, &class.id).await?;
+    let assignments = super::assignments(&state.kv_store, &class.id).await?;
+
+    let stream = super::results::results_csv(state.kv_store.clone(), roster, assignments);
+    Response::builder()
+        .header(header::CONTENT_TYPE, "text/csv; charset=utf-8")
+        .header(
+            header::CONTENT_DISPOSITION,
+            format!("attachment; filename=\"class-{}-results.csv\"", class.id),
+        )
+        .body(Body::from_stream(stream))
+        .map_err(|e| {
+            error!("Failed to build results response for class {}: {}", class.id, e);
+            ErrorResponse::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "internal_error",
+                "Internal server error",
+            )
+        })
+}
@@ -0,0 +1,309 @@
+use axum::{
+    Json,
+    extract::{Query, State},
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::ops::Range;
+
+use crate::{
+    ServiceError,
+    content::{ContentAnnotator, ContentParams, ContentSchema, ContentTypeDescriptor},
+    keyvalue::KeyValueStore,
+    problem::ErrorResponse,
+    reading::ReadingQuery,
+    session::Session,
+    state::AppState,
+    storage::ObjectStore,
+    validation::{BannedWords, ContentValidator, MinItems, NoEmptyFields, WordCount},
+};
+
+/// Storage prefix and registry identifier for cloze exercises
+pub const CLOZE_PREFIX: &str = "cloze";
+
+/// Grade used when the request doesn't specify one
+const DEFAULT_GRADE: u8 = 3;
+
+/// Fewest words blanked out of a story
+const MIN_GAPS: usize = 5;
+
+/// Query parameters accepted by the cloze exercise endpoint
+#[derive(Debug, Deserialize, Default, JsonSchema)]
+pub struct ClozeQuery {
+    /// Grade level the story should target
+    pub grade: Option<u8>,
+
+    /// Subject of the story (e.g., "ocean animals")
+    pub topic: Option<String>,
+}
+
+impl ClozeQuery {
+    /// Validates the query and converts it into content parameters, accepting the
+    /// same grades and topics as reading passages
+    pub fn into_params(self) -> Result<ContentParams, ServiceError> {
+        let grade = Some(self.grade.unwrap_or(DEFAULT_GRADE));
+        ReadingQuery { grade, topic: self.topic, ..Default::default() }.into_params()
+    }
+}
+
+/// A story with vocabulary words blanked out, and the words to fill them with
+#[derive(Serialize, Deserialize, Clone, JsonSchema)]
+pub struct ClozeExercise {
+    pub title: String,
+
+    /// The story as written; kept out of responses, which show `gapped_text`
+    #[serde(default, skip_serializing)]
+    pub story: String,
+
+    /// Words the model chose to blank out; kept out of responses, which list the
+    /// words actually blanked in `answers`
+    #[serde(default, skip_serializing)]
+    pub target_words: Vec<String>,
+
+    /// The story with each target word replaced by a numbered gap, e.g. "(1) _____"
+    #[serde(default)]
+    #[schemars(skip_deserializing)]
+    pub gapped_text: String,
+
+    /// The blanked words, in alphabetical order, for students to choose from
+    #[serde(default)]
+    #[schemars(skip_deserializing)]
+    pub answer_bank: Vec<String>,
+
+    /// The word that fills each gap, in gap order
+    #[serde(default)]
+    #[schemars(skip_deserializing)]
+    pub answers: Vec<String>,
+}
+
+/// Byte ranges of the words in `text`: runs of letters and digits, with any
+/// apostrophes or hyphens between them (e.g., "didn't", "well-known")
+fn words(text: &str) -> Vec<Range<usize>> {
+    let mut words = Vec::new();
+    let mut start = None;
+    let mut chars = text.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        let joins = (c == '\'' || c == '-')
+            && start.is_some()
+            && chars.peek().is_some_and(|(_, next)| next.is_alphanumeric());
+        match (c.is_alphanumeric() || joins, start) {
+            (true, None) => start = Some(i),
+            (false, Some(s)) => {
+                words.push(s..i);
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    if let Some(s) = start {
+        words.push(s..text.len());
+    }
+    words
+}
+
+/// Finds where to blank each target word: its first occurrence in `story` as a
+/// whole word, ignoring case
+///
+/// Returns the ranges in story order. Targets that don't occur, repeat an
+/// earlier target, or aren't single words are skipped.
+fn gaps(story: &str, targets: &[String]) -> Vec<Range<usize>> {
+    let mut remaining: Vec<String> = Vec::new();
+    for target in targets {
+        let target = target.trim().to_lowercase();
+        if !target.is_empty() && words(&target).len() == 1 && !remaining.contains(&target) {
+            remaining.push(target);
+        }
+    }
+
+    let mut gaps = Vec::new();
+    for word in words(story) {
+        let lower = story[word.clone()].to_lowercase();
+        if let Some(i) = remaining.iter().position(|target| *target == lower) {
+            remaining.swap_remove(i);
+            gaps.push(word);
+        }
+    }
+    gaps
+}
+
+/// Blanks the target words out of the story, filling in `gapped_text`,
+/// `answers`, and `answer_bank`
+///
+/// The blanking is deterministic, so the same story and targets always give
+/// the same exercise.
+#[derive(Debug, Clone, Default)]
+pub struct BlankTargetWords;
+
+impl ContentAnnotator for BlankTargetWords {
+    fn annotate(&self, content: &mut Value, _params: &ContentParams) {
+        let story = content.get("story").and_then(Value::as_str).unwrap_or_default().to_string();
+        let targets: Vec<String> = content
+            .get("target_words")
+            .cloned()
+            .and_then(|targets| serde_json::from_value(targets).ok())
+            .unwrap_or_default();
+
+        let mut gapped_text = String::with_capacity(story.len());
+        let mut answers = Vec::new();
+        let mut end = 0;
+        for gap in gaps(&story, &targets) {
+            gapped_text.push_str(&story[end..gap.start]);
+            gapped_text.push_str(&format!("({}) _____", answers.len() + 1));
+            answers.push(story[gap.clone()].to_string());
+            end = gap.end;
+        }
+        gapped_text.push_str(&story[end..]);
+
+        let mut answer_bank = answers.clone();
+        answer_bank.sort_by_key(|word| word.to_lowercase());
+        content["gapped_text"] = gapped_text.into();
+        content["answers"] = answers.into();
+        content["answer_bank"] = answer_bank.into();
+    }
+}
+
+/// Requires at least `MIN_GAPS` of the target words to be blankable, so the
+/// exercise has enough gaps
+#[derive(Debug, Clone, Default)]
+pub struct TargetWordsInStory;
+
+impl ContentValidator for TargetWordsInStory {
+    fn validate(&self, content: &Value, _params: &ContentParams) -> Result<(), String> {
+        let story = content
+            .get("story")
+            .and_then(Value::as_str)
+            .ok_or_else(|| "missing text field \"story\"".to_string())?;
+        let targets: Vec<String> = content
+            .get("target_words")
+            .cloned()
+            .and_then(|targets| serde_json::from_value(targets).ok())
+            .ok_or_else(|| "missing array field \"target_words\"".to_string())?;
+
+        let found = gaps(story, &targets).len();
+        if found < MIN_GAPS {
+            return Err(format!(
+                "only {} of the target words appear in the story, expected at least {}",
+                found, MIN_GAPS
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Returns the content type descriptor for cloze exercises
+pub fn descriptor() -> ContentTypeDescriptor {
+    ContentTypeDescriptor::new(
+        CLOZE_PREFIX,
+        "cloze_exercise",
+        ContentSchema::for_type::<ClozeExercise>(
+            "ClozeExercise",
+            "A story and the vocabulary words in it to blank out",
+        ),
+    )
+    .with_validator(WordCount::new("story", 80, 300))
+    .with_validator(MinItems::new("target_words", MIN_GAPS))
+    .with_validator(TargetWordsInStory)
+    .with_validator(NoEmptyFields)
+    .with_validator(BannedWords::default())
+    .with_annotator(BlankTargetWords)
+    .with_dedup_field("story")
+    .with_default_params(ContentParams::new().with("grade", DEFAULT_GRADE))
+}
+
+/// Serves a cached cloze exercise the session hasn't done, or generates and
+/// blanks a new one
+pub async fn cloze_exercises<S: ObjectStore, K: KeyValueStore>(
+    State(state): State<AppState<S, K>>,
+    Query(query): Query<ClozeQuery>,
+    session: Session,
+) -> Result<Json<ClozeExercise>, ErrorResponse> {
+    let params = query.into_params()?;
+
+    let descriptor = state
+        .content_types
+        .get(CLOZE_PREFIX)
+        .ok_or_else(|| ServiceError::ConfigError(CLOZE_PREFIX.into()))?;
+
+    let session_id = session.id();
+    let params = state
+        .with_prompt_variant(descriptor, params, Some(session_id))
+        .await?;
+
+    let exercise: ClozeExercise = state
+        .get_or_generate(descriptor, &params, Some(session_id))
+        .await?;
+
+    Ok(Json(exercise))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn blanks_the_first_whole_word_match_of_each_target() {
+        let mut content = json!({
+            "title": "The Fox",
+            "story": "A curious fox didn't rest. The fox was Curious and nimble, and a well-known foxglove grew.",
+            "target_words": ["curious", "Fox", "nimble", "well-known", "glove", "two words", "fox"],
+        });
+        let params = ContentParams::new();
+        assert!(TargetWordsInStory.validate(&content, &params).is_err());
+
+        BlankTargetWords.annotate(&mut content, &params);
+        assert_eq!(
+            content["gapped_text"],
+            "A (1) _____ (2) _____ didn't rest. The fox was Curious and (3) _____, and a (4) _____ foxglove grew."
+        );
+        assert_eq!(content["answers"], json!(["curious", "fox", "nimble", "well-known"]));
+        assert_eq!(content["answer_bank"], json!(["curious", "fox", "nimble", "well-known"]));
+
+        let exercise: ClozeExercise = serde_json::from_value(content).unwrap();
+        let response = serde_json::to_value(&exercise).unwrap();
+        assert!(response.get("story").is_none() && response.get("target_words").is_none());
+
+        let enough = json!({
+            "story": "Bright stars shine over calm seas while gentle waves roll past quiet, sleepy towns.",
+            "target_words": ["bright", "calm", "gentle", "quiet", "sleepy"],
+        });
+        assert!(TargetWordsInStory.validate(&enough, &params).is_ok());
+    }
+
+    #[tokio::test]
+    async fn generates_exercises_from_the_stored_story() {
+        let story = "Mia found a tiny shell on the sandy beach one bright morning. \
+            She held it up to her ear and heard the ocean whisper softly. \
+            Her brother laughed and said shells could not talk, but Mia listened again. \
+            The gentle sound reminded her of waves rolling over smooth stones. \
+            She carried the shell home in her pocket and placed it on her windowsill. \
+            Every night before bed, she listened to it and dreamed of sailing across \
+            the wide blue sea with dolphins leaping beside her little wooden boat.";
+        let state = AppState::new(
+            crate::storage::MemoryObjectStore::new(),
+            crate::keyvalue::MemoryKeyValueStore::new(),
+            crate::config::Config::default(),
+            crate::content::ContentTypeRegistry::new().register(descriptor()),
+        )
+        .await
+        .with_stub_model(vec![json!({
+            "title": "The Whispering Shell",
+            "story": story,
+            "target_words": ["tiny", "whisper", "gentle", "smooth", "dolphins"],
+        })])
+        .await;
+
+        let descriptor = state.content_types.get(CLOZE_PREFIX).unwrap();
+        let exercise: ClozeExercise = state
+            .get_or_generate(descriptor, &descriptor.default_params, None)
+            .await
+            .unwrap();
+        assert_eq!(exercise.answers, ["tiny", "whisper", "gentle", "smooth", "dolphins"]);
+        assert!(exercise.gapped_text.starts_with("Mia found a (1) _____ shell"));
+
+        let key = &state.object_store.list_objects("cloze/").await.unwrap()[0].key;
+        let stored: Value = serde_json::from_slice(&state.object_store.get_object(key).await.unwrap()).unwrap();
+        assert_eq!(stored["story"], story);
+    }
+}
@@ -0,0 +1,423 @@
+use axum::{
+    Json, Router,
+    extract::{Path, State},
+    routing::get,
+};
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use schemars::{JsonSchema, schema_for};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fmt::Debug;
+use std::sync::Arc;
+
+use crate::{
+    ServiceError, cache_policy::CachePolicy, keyvalue::KeyValueStore, problem::ErrorResponse, session::Session,
+    state::AppState, storage::ObjectStore, validation::ContentValidator,
+};
+
+/// JSON schema used to request structured output for a content type
+#[derive(Debug, Clone, Deserialize)]
+pub struct ContentSchema {
+    /// Name of the schema sent to the model (e.g., "ReadingContents")
+    pub name: String,
+
+    /// Description of what the schema represents
+    pub description: String,
+
+    /// The JSON schema document itself
+    pub schema: serde_json::Value,
+}
+
+impl ContentSchema {
+    /// Builds a schema from a Rust type deriving `JsonSchema`
+    ///
+    /// Every property is marked required, as strict structured output demands, even
+    /// fields with a serde default so older stored content still parses.
+    pub fn for_type<T: JsonSchema>(name: &str, description: &str) -> Self {
+        let mut schema =
+            serde_json::to_value(schema_for!(T)).expect("JSON schema should always serialize");
+        require_all_properties(&mut schema);
+
+        Self {
+            name: name.to_string(),
+            description: description.to_string(),
+            schema,
+        }
+    }
+}
+
+/// Lists every property of each object in a schema as required
+fn require_all_properties(schema: &mut serde_json::Value) {
+    match schema {
+        serde_json::Value::Object(map) => {
+            if let Some(serde_json::Value::Object(properties)) = map.get("properties") {
+                let names = properties.keys().cloned().map(serde_json::Value::String).collect();
+                map.insert("required".to_string(), serde_json::Value::Array(names));
+            }
+            map.values_mut().for_each(require_all_properties);
+        }
+        serde_json::Value::Array(items) => items.iter_mut().for_each(require_all_properties),
+        _ => {}
+    }
+}
+
+/// Returns the opaque, URL-safe ID of a content object
+pub fn object_id(object_key: &str) -> String {
+    URL_SAFE_NO_PAD.encode(object_key)
+}
+
+/// Whether a key is a JSON object with no empty, `.`, or `..` segments, so it
+/// can't reach outside its prefix in any store
+fn is_safe_key(key: &str) -> bool {
+    key.ends_with(".json")
+        && key.split('/').all(|segment| !segment.is_empty() && segment != "." && segment != "..")
+}
+
+/// Returns the key of the content object with an ID, if the ID is well formed
+pub fn object_key_for_id(id: &str) -> Option<String> {
+    let key = String::from_utf8(URL_SAFE_NO_PAD.decode(id).ok()?).ok()?;
+    is_safe_key(&key).then_some(key)
+}
+
+/// Describes a content type served by the application
+///
+/// A descriptor ties together the storage prefix used for caching, the prompt
+/// used for generation, and the schema the generated content must follow.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ContentTypeDescriptor {
+    /// Storage prefix, also used as the content type's identifier (e.g., "reading")
+    pub prefix: String,
+
+    /// Name of the prompt configuration used to generate this content
+    pub prompt_name: String,
+
+    /// Schema the generated content must follow
+    pub schema: ContentSchema,
+
+    /// Checks generated content must pass before it is cached
+    #[serde(skip)]
+    pub validators: Vec<Arc<dyn ContentValidator>>,
+
+    /// Annotations added to generated content before it is cached
+    #[serde(skip)]
+    pub annotators: Vec<Arc<dyn ContentAnnotator>>,
+
+    /// Parameters used when a request doesn't specify any, and for cache warm-up
+    #[serde(skip)]
+    pub default_params: ContentParams,
+
+    /// String field compared against cached content to reject near-duplicates; no
+    /// deduplication if unset
+    #[serde(skip)]
+    pub dedup_field: Option<String>,
+
+    /// Array fields whose values are stored as tags, so cached content can be
+    /// filtered by them
+    #[serde(skip)]
+    pub tag_fields: Vec<String>,
+
+    /// Field set to each generated object's ID (see `object_id`), so clients can
+    /// refer to it
+    #[serde(skip)]
+    pub id_field: Option<String>,
+
+    /// Text field narrated after content is generated; no narration if unset
+    #[serde(skip)]
+    pub narration_field: Option<String>,
+
+    /// Text field illustrated after content is generated, and the field set to the
+    /// illustration's key; no illustration if unset or illustrations are disabled
+    #[serde(skip)]
+    pub illustration_fields: Option<(String, String)>,
+
+    /// Cache policy the content type always uses, in place of any configured one
+    #[serde(skip)]
+    pub cache_policy: Option<CachePolicy>,
+}
+
+impl ContentTypeDescriptor {
+    /// Creates a descriptor without validators or annotators
+    pub fn new(prefix: &str, prompt_name: &str, schema: ContentSchema) -> Self {
+        Self {
+            prefix: prefix.to_string(),
+            prompt_name: prompt_name.to_string(),
+            schema,
+            validators: Vec::new(),
+            annotators: Vec::new(),
+            default_params: ContentParams::new(),
+            dedup_field: None,
+            tag_fields: Vec::new(),
+            id_field: None,
+            narration_field: None,
+            illustration_fields: None,
+            cache_policy: None,
+        }
+    }
+
+    /// Sets the parameters used when a request doesn't specify any
+    pub fn with_default_params(mut self, params: ContentParams) -> Self {
+        self.default_params = params;
+        self
+    }
+
+    /// Adds a validator run on generated content before it is cached
+    pub fn with_validator(mut self, validator: impl ContentValidator + 'static) -> Self {
+        self.validators.push(Arc::new(validator));
+        self
+    }
+
+    /// Rejects generated content whose `field` is too similar to that of content
+    /// already cached in the same window
+    pub fn with_dedup_field(mut self, field: &str) -> Self {
+        self.dedup_field = Some(field.to_string());
+        self
+    }
+
+    /// Stores the values of an array field of generated content as tags
+    pub fn with_tag_field(mut self, field: &str) -> Self {
+        self.tag_fields.push(field.to_string());
+        self
+    }
+
+    /// Sets `field` of generated content to the object's ID
+    pub fn with_id_field(mut self, field: &str) -> Self {
+        self.id_field = Some(field.to_string());
+        self
+    }
+
+    /// Narrates `field` of generated content and stores the audio next to it
+    pub fn with_narration_field(mut self, field: &str) -> Self {
+        self.narration_field = Some(field.to_string());
+        self
+    }
+
+    /// Illustrates `source_field` of generated content, setting `key_field` to the
+    /// key of the stored image
+    pub fn with_illustration(mut self, source_field: &str, key_field: &str) -> Self {
+        self.illustration_fields = Some((source_field.to_string(), key_field.to_string()));
+        self
+    }
+
+    /// Fixes the content type's cache policy, for content whose caching is part of
+    /// what it is, such as one object a day shared by everyone
+    pub fn with_cache_policy(mut self, policy: CachePolicy) -> Self {
+        self.cache_policy = Some(policy);
+        self
+    }
+
+    /// Adds an annotator run on validated content before it is cached
+    pub fn with_annotator(mut self, annotator: impl ContentAnnotator + 'static) -> Self {
+        self.annotators.push(Arc::new(annotator));
+        self
+    }
+}
+
+/// Adds computed fields to generated content before it is cached
+///
+/// Annotated fields aren't part of the schema sent to the model; they are stored
+/// with the object so clients can display them.
+pub trait ContentAnnotator: Debug + Send + Sync {
+    /// Adds fields to content generated with the given parameters
+    fn annotate(&self, content: &mut serde_json::Value, params: &ContentParams);
+}
+
+/// Parameters that customize generated content
+///
+/// Each parameter is substituted into the prompt template as a variable and also
+/// partitions the cache, so content generated for one set of parameters is never
+/// served for another (e.g., `reading/grade-3/2025-10-11-14/`).
+#[derive(Debug, Clone, Default)]
+pub struct ContentParams {
+    variables: BTreeMap<String, String>,
+
+    /// Prompt variables that don't partition the cache, such as the text of the
+    /// story a sequel continues
+    context: BTreeMap<String, String>,
+
+    /// Prompt version to generate with, when the prompt is under an experiment
+    prompt_version: Option<String>,
+}
+
+impl ContentParams {
+    /// Creates an empty set of parameters
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a parameter, replacing any existing value with the same name
+    pub fn with(mut self, name: &str, value: impl ToString) -> Self {
+        self.variables.insert(name.to_string(), value.to_string());
+        self
+    }
+
+    /// Adds a prompt variable that doesn't partition the cache, replacing any
+    /// existing context with the same name
+    pub fn with_context(mut self, name: &str, value: impl ToString) -> Self {
+        self.context.insert(name.to_string(), value.to_string());
+        self
+    }
+
+    /// Selects a specific prompt version to generate with
+    pub fn with_prompt_version(mut self, version: impl ToString) -> Self {
+        self.prompt_version = Some(version.to_string());
+        self
+    }
+
+    /// Returns the prompt template variables
+    pub fn variables(&self) -> &BTreeMap<String, String> {
+        &self.variables
+    }
+
+    /// Returns the prompt variables that don't partition the cache
+    pub fn context(&self) -> &BTreeMap<String, String> {
+        &self.context
+    }
+
+    /// Returns the variables and context together, as rendered into the prompt
+    pub fn prompt_variables(&self) -> BTreeMap<String, String> {
+        let mut variables = self.context.clone();
+        variables.extend(self.variables.clone());
+        variables
+    }
+
+    /// Returns the selected prompt version, if any
+    pub fn prompt_version(&self) -> Option<&str> {
+        self.prompt_version.as_deref()
+    }
+
+    /// Returns the storage path segments for these parameters
+    ///
+    /// Format: `{name}-{value}/` for each parameter in name order, followed by
+    /// `version-{version}/` when a prompt version is selected, or an empty string
+    /// when there are no parameters. Values are lowercased and anything other than
+    /// ASCII letters and digits becomes `-` so they are safe in keys.
+    pub fn partition(&self) -> String {
+        let mut partition: String = self
+            .variables
+            .iter()
+            .map(|(name, value)| format!("{}-{}/", name, slugify(value)))
+            .collect();
+
+        // Tag content with its prompt version so results can be attributed to a variant
+        if let Some(version) = &self.prompt_version {
+            partition.push_str(&format!("version-{}/", slugify(version)));
+        }
+
+        partition
+    }
+}
+
+/// Converts a parameter value into a storage-safe path segment
+fn slugify(value: &str) -> String {
+    value
+        .to_lowercase()
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// Registry of content types, consulted at runtime by routes and caching
+///
+/// Content types are registered once at startup (from `main()` or configuration)
+/// and shared read-only across all requests.
+#[derive(Debug, Clone, Default)]
+pub struct ContentTypeRegistry {
+    types: Arc<BTreeMap<String, ContentTypeDescriptor>>,
+}
+
+impl ContentTypeRegistry {
+    /// Creates an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a content type, replacing any existing descriptor with the same prefix
+    pub fn register(mut self, descriptor: ContentTypeDescriptor) -> Self {
+        Arc::make_mut(&mut self.types).insert(descriptor.prefix.clone(), descriptor);
+        self
+    }
+
+    /// Gets a content type descriptor by its prefix
+    pub fn get(&self, prefix: &str) -> Option<&ContentTypeDescriptor> {
+        self.types.get(prefix)
+    }
+
+    /// Iterates over all registered content types
+    pub fn iter(&self) -> impl Iterator<Item = &ContentTypeDescriptor> {
+        self.types.values()
+    }
+
+    /// Whether a key names a generated content object: a JSON object under a
+    /// registered content type's prefix, with no segments that could leave it
+    pub fn is_content_key(&self, key: &str) -> bool {
+        let prefix = key.split('/').next().unwrap_or_default();
+        self.get(prefix).is_some() && is_safe_key(key)
+    }
+}
+
+/// Builds a `/contents/{prefix}` route for every registered content type
+///
+/// Unregistered content types fall through to the router's 404.
+pub fn router<S, K>(content_types: &ContentTypeRegistry) -> Router<AppState<S, K>>
+where
+    S: ObjectStore + 'static,
+    K: KeyValueStore + 'static,
+{
+    content_types.iter().fold(Router::new(), |router, descriptor| {
+        let prefix = descriptor.prefix.clone();
+        router.route(
+            &format!("/contents/{}", prefix),
+            get(move |state, session| contents(state, Path(prefix), session)),
+        )
+    })
+}
+
+/// Serves content for any registered content type
+///
+/// Looks up the content type from the path, then returns cached content or
+/// generates new content using the type's prompt and schema.
+pub async fn contents<S: ObjectStore, K: KeyValueStore>(
+    State(state): State<AppState<S, K>>,
+    Path(content_type): Path<String>,
+    session: Session,
+) -> Result<Json<serde_json::Value>, ErrorResponse> {
+    Ok(Json(content_of_type(&state, &content_type, Some(session.id())).await?))
+}
+
+/// Returns cached content of a registered type the session hasn't seen, with the
+/// type's default parameters, generating and storing new content if needed
+pub async fn content_of_type<S: ObjectStore, K: KeyValueStore>(
+    state: &AppState<S, K>,
+    content_type: &str,
+    session_id: Option<&str>,
+) -> Result<serde_json::Value, ServiceError> {
+    let descriptor = state
+        .content_types
+        .get(content_type)
+        .ok_or_else(|| ServiceError::NotFound(format!("Unknown content type: {}", content_type)))?;
+
+    let params = state
+        .with_prompt_variant(descriptor, descriptor.default_params.clone(), session_id)
+        .await?;
+
+    state.get_or_generate(descriptor, &params, session_id).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn content_keys_stay_under_their_prefix() {
+        let registry = ContentTypeRegistry::new().register(crate::reading::descriptor());
+        assert!(registry.is_content_key("reading/grade-3/2025-10-11-14/abc.json"));
+        assert!(!registry.is_content_key("reading/../../etc/passwd.json"));
+        assert!(!registry.is_content_key("reading/./grade-3//abc.json"));
+        assert!(!registry.is_content_key("reading/grade-3/abc.txt"));
+        assert!(!registry.is_content_key("quiz/abc.json"));
+
+        assert_eq!(object_key_for_id(&object_id("reading/a/b.json")).as_deref(), Some("reading/a/b.json"));
+        assert_eq!(object_key_for_id(&object_id("reading/../b.json")), None);
+    }
+}
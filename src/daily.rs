@@ -0,0 +1,180 @@
+use axum::{Json, extract::State};
+use chrono::Utc;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    ServiceError,
+    cache_policy::{CachePolicy, CacheWindow},
+    content::{ContentAnnotator, ContentParams, ContentSchema, ContentTypeDescriptor},
+    keyvalue::KeyValueStore,
+    problem::ErrorResponse,
+    state::AppState,
+    storage::ObjectStore,
+    validation::{BannedWords, ContentValidator, MinItems, NoEmptyFields, WordCount},
+};
+
+/// Storage prefix and registry identifier for the daily challenge
+pub const DAILY_CHALLENGE_PREFIX: &str = "daily_challenge";
+
+/// Grade the challenge is written for
+const GRADE: u8 = 3;
+
+/// A math word problem with its worked solution
+#[derive(Serialize, Deserialize, Clone, JsonSchema)]
+pub struct ChallengeProblem {
+    pub problem: String,
+    pub answer: String,
+    pub explanation: String,
+}
+
+/// The word of the day
+#[derive(Serialize, Deserialize, Clone, JsonSchema)]
+pub struct ChallengeWord {
+    pub word: String,
+    pub definition: String,
+    pub example: String,
+}
+
+/// The day's challenge, the same for everyone: a short passage, a math problem,
+/// and a word of the day sharing one theme
+#[derive(Serialize, Deserialize, Clone, JsonSchema)]
+pub struct DailyChallenge {
+    /// Day the challenge is for, as YYYY-MM-DD in UTC
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schemars(skip_deserializing)]
+    pub date: Option<String>,
+
+    pub title: String,
+    pub passage: String,
+    pub questions: Vec<String>,
+    pub math: ChallengeProblem,
+    pub word: ChallengeWord,
+}
+
+/// Requires the word of the day to appear in the passage, so it is met in context
+#[derive(Debug, Clone, Default)]
+pub struct WordInPassage;
+
+impl ContentValidator for WordInPassage {
+    fn validate(&self, content: &Value, _params: &ContentParams) -> Result<(), String> {
+        let passage = content
+            .get("passage")
+            .and_then(Value::as_str)
+            .ok_or_else(|| "missing text field \"passage\"".to_string())?;
+        let word = content
+            .pointer("/word/word")
+            .and_then(Value::as_str)
+            .ok_or_else(|| "missing text field \"word.word\"".to_string())?;
+
+        let word = word.trim().to_lowercase();
+        if !passage.to_lowercase().contains(&word) {
+            return Err(format!("word of the day {:?} isn't in the passage", word));
+        }
+
+        Ok(())
+    }
+}
+
+/// Sets `date` to the day the challenge was generated for
+#[derive(Debug, Clone, Default)]
+pub struct ChallengeDate;
+
+impl ContentAnnotator for ChallengeDate {
+    fn annotate(&self, content: &mut Value, _params: &ContentParams) {
+        content["date"] = CacheWindow::Daily.format(&Utc::now()).into();
+    }
+}
+
+/// Returns the content type descriptor for the daily challenge
+///
+/// Its cache holds a single object per day whatever the configuration says, so
+/// the first request of the day generates the challenge (one instance at a time,
+/// through the window's generation slot) and every later one is served it.
+pub fn descriptor() -> ContentTypeDescriptor {
+    ContentTypeDescriptor::new(
+        DAILY_CHALLENGE_PREFIX,
+        "daily_challenge",
+        ContentSchema::for_type::<DailyChallenge>(
+            "DailyChallenge",
+            "A short passage, a math problem, and a word of the day sharing one theme",
+        ),
+    )
+    .with_validator(WordCount::new("passage", 60, 160))
+    .with_validator(MinItems::new("questions", 2))
+    .with_validator(WordInPassage)
+    .with_validator(NoEmptyFields)
+    .with_validator(BannedWords::default())
+    .with_annotator(ChallengeDate)
+    .with_default_params(ContentParams::new().with("grade", GRADE))
+    .with_cache_policy(CachePolicy {
+        max_objects: 1,
+        window: CacheWindow::Daily,
+        fill_ratio: 1.0,
+    })
+}
+
+/// Serves today's challenge, generating it if this is the day's first request
+///
+/// Sessions and prompt experiments are ignored so everyone gets the same one.
+pub async fn daily_challenge<S: ObjectStore, K: KeyValueStore>(
+    State(state): State<AppState<S, K>>,
+) -> Result<Json<DailyChallenge>, ErrorResponse> {
+    let descriptor = state
+        .content_types
+        .get(DAILY_CHALLENGE_PREFIX)
+        .ok_or_else(|| ServiceError::ConfigError(DAILY_CHALLENGE_PREFIX.into()))?;
+
+    let challenge = state
+        .get_or_generate(descriptor, &descriptor.default_params, None)
+        .await?;
+
+    Ok(Json(challenge))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::Config, content::ContentTypeRegistry, keyvalue::MemoryKeyValueStore, storage::MemoryObjectStore,
+    };
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn serves_everyone_the_day_s_one_challenge() {
+        let passage = "The brave little seed pushed up through the soil.";
+        let challenge = json!({
+            "date": CacheWindow::Daily.format(&Utc::now()),
+            "title": "The Seed",
+            "passage": passage,
+            "questions": ["What pushed up?", "Through what?"],
+            "math": { "problem": "2 + 3 seeds?", "answer": "5", "explanation": "Add them." },
+            "word": { "word": "Brave", "definition": "Not afraid", "example": "She was brave." },
+        });
+        assert!(WordInPassage.validate(&challenge, &ContentParams::new()).is_ok());
+        let mut missing = challenge.clone();
+        missing["word"]["word"] = "timid".into();
+        assert!(WordInPassage.validate(&missing, &ContentParams::new()).is_err());
+
+        // A configured policy allowing more objects doesn't apply
+        let mut config = Config::default();
+        config.default_cache_policy.max_objects = 8;
+        let object_store = MemoryObjectStore::new();
+        let key = format!("daily_challenge/grade-3/{}/a.json", CacheWindow::Daily.format(&Utc::now()));
+        object_store.put_object(&key, serde_json::to_vec(&challenge).unwrap()).await.unwrap();
+        let state = AppState::new(
+            object_store,
+            MemoryKeyValueStore::new(),
+            config,
+            ContentTypeRegistry::new().register(descriptor()),
+        )
+        .await;
+
+        for _ in 0..3 {
+            let Json(served) = daily_challenge(State(state.clone())).await.unwrap();
+            assert_eq!(served.passage, passage);
+            assert_eq!(served.date, challenge["date"].as_str().map(str::to_string));
+        }
+    }
+}
@@ -0,0 +1,232 @@
+use async_openai::{
+    Client as OpenAIClient,
+    config::OpenAIConfig,
+    types::{CreateEmbeddingRequestArgs, EmbeddingInput},
+};
+use async_trait::async_trait;
+use serde::Deserialize;
+use std::collections::HashSet;
+
+use crate::{
+    ServiceError,
+    storage::{ObjectStore, StoredObject},
+};
+
+/// ObjectStore prefix under which content embeddings are stored, mirroring the
+/// content keys
+pub const EMBEDDINGS_PREFIX: &str = "embeddings/";
+
+/// When generated content is too similar to cached content to keep
+///
+/// Configured under `[dedup]` in the config file, or with `DEDUP_THRESHOLD` for
+/// the threshold:
+///
+/// ```toml
+/// [dedup]
+/// threshold = 0.9
+/// model = "text-embedding-3-small"
+/// ```
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DedupPolicy {
+    /// Cosine similarity at or above which new content counts as a duplicate
+    pub threshold: f64,
+
+    /// OpenAI embeddings model
+    pub model: String,
+}
+
+impl Default for DedupPolicy {
+    fn default() -> Self {
+        Self { threshold: 0.9, model: "text-embedding-3-small".to_string() }
+    }
+}
+
+impl DedupPolicy {
+    /// Describes every invalid field
+    pub fn validate(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if !(0.0..=1.0).contains(&self.threshold) {
+            problems.push("dedup.threshold must be between 0 and 1".to_string());
+        }
+        if self.model.trim().is_empty() {
+            problems.push("dedup.model must not be empty".to_string());
+        }
+        problems
+    }
+}
+
+/// Turns text into a vector, so similar texts can be found by comparing vectors
+#[async_trait]
+pub trait Embedder: Send + Sync {
+    /// Embeds a piece of text
+    ///
+    /// # Returns
+    /// * `Ok(Some(vector))` - The text's embedding
+    /// * `Ok(None)` - If embeddings are disabled, so nothing is deduplicated
+    /// * `Err(ServiceError)` - If the embeddings service can't be reached
+    async fn embed(&self, text: &str) -> Result<Option<Vec<f32>>, ServiceError>;
+}
+
+/// Embedder backed by the OpenAI embeddings endpoint
+#[derive(Clone)]
+pub struct OpenAIEmbedder {
+    client: OpenAIClient<OpenAIConfig>,
+    model: String,
+}
+
+impl OpenAIEmbedder {
+    /// Creates a new OpenAIEmbedder using the given client and model
+    pub fn new(client: OpenAIClient<OpenAIConfig>, model: &str) -> Self {
+        Self { client, model: model.to_string() }
+    }
+}
+
+#[async_trait]
+impl Embedder for OpenAIEmbedder {
+    async fn embed(&self, text: &str) -> Result<Option<Vec<f32>>, ServiceError> {
+        let request = CreateEmbeddingRequestArgs::default()
+            .model(&self.model)
+            .input(EmbeddingInput::String(text.to_string()))
+            .build()
+            .map_err(|e| {
+                ServiceError::OpenAIError(format!("Failed to build embeddings request: {}", e))
+            })?;
+
+        let response = self
+            .client
+            .embeddings()
+            .create(request)
+            .await
+            .map_err(|e| ServiceError::from_openai("Embeddings call failed", e))?;
+
+        response
+            .data
+            .into_iter()
+            .next()
+            .map(|embedding| Some(embedding.embedding))
+            .ok_or_else(|| ServiceError::OpenAIError("No embedding in OpenAI response".to_string()))
+    }
+}
+
+/// Embedder that embeds nothing, turning deduplication off, for local development
+/// and tests
+#[derive(Clone, Default)]
+pub struct NoopEmbedder;
+
+#[async_trait]
+impl Embedder for NoopEmbedder {
+    async fn embed(&self, _text: &str) -> Result<Option<Vec<f32>>, ServiceError> {
+        Ok(None)
+    }
+}
+
+/// Cosine similarity of two vectors, from -1 (opposite) to 1 (same direction)
+///
+/// Zero if either vector is zero or they differ in length.
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f64 {
+    if a.len() != b.len() {
+        return 0.0;
+    }
+    let (mut dot, mut norm_a, mut norm_b) = (0.0, 0.0, 0.0);
+    for (x, y) in a.iter().zip(b) {
+        let (x, y) = (*x as f64, *y as f64);
+        dot += x * y;
+        norm_a += x * x;
+        norm_b += y * y;
+    }
+    if norm_a == 0.0 || norm_b == 0.0 {
+        return 0.0;
+    }
+    dot / (norm_a.sqrt() * norm_b.sqrt())
+}
+
+/// Key of the object holding a content object's embedding, e.g.
+/// "embeddings/reading/2025-10-11-14/1a2b.json"
+pub fn embedding_key(object_key: &str) -> String {
+    format!("{}{}", EMBEDDINGS_PREFIX, object_key)
+}
+
+/// Stores the embedding of a content object
+pub async fn store<S: ObjectStore>(
+    object_store: &S,
+    object_key: &str,
+    vector: &[f32],
+) -> Result<(), ServiceError> {
+    object_store
+        .put_object(&embedding_key(object_key), serde_json::to_vec(vector)?)
+        .await
+}
+
+/// Finds the object among `objects`, all in the folder `folder`, most similar to
+/// a vector
+///
+/// Objects stored without an embedding are skipped.
+///
+/// # Returns
+/// * `Ok(Some((key, similarity)))` - The most similar object and how similar it is
+/// * `Ok(None)` - If no object has an embedding
+/// * `Err(ServiceError)` - If storage fails
+pub async fn most_similar<S: ObjectStore>(
+    object_store: &S,
+    folder: &str,
+    objects: &[StoredObject],
+    vector: &[f32],
+) -> Result<Option<(String, f64)>, ServiceError> {
+    let embedded: HashSet<String> = object_store
+        .list_objects(&embedding_key(folder))
+        .await?
+        .into_iter()
+        .map(|object| object.key)
+        .collect();
+
+    let mut best: Option<(String, f64)> = None;
+    for object in objects {
+        let key = embedding_key(&object.key);
+        if !embedded.contains(&key) {
+            continue;
+        }
+        let other: Vec<f32> = serde_json::from_slice(&object_store.get_object(&key).await?)?;
+        let similarity = cosine_similarity(vector, &other);
+        if best.as_ref().is_none_or(|(_, best)| similarity > *best) {
+            best = Some((object.key.clone(), similarity));
+        }
+    }
+    Ok(best)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::MemoryObjectStore;
+
+    #[test]
+    fn measures_similarity() {
+        assert!((cosine_similarity(&[1.0, 2.0], &[2.0, 4.0]) - 1.0).abs() < 1e-9);
+        assert!(cosine_similarity(&[1.0, 0.0], &[0.0, 1.0]).abs() < 1e-9);
+        assert_eq!(cosine_similarity(&[0.0, 0.0], &[1.0, 1.0]), 0.0);
+        assert_eq!(cosine_similarity(&[1.0], &[1.0, 1.0]), 0.0);
+
+        assert!(DedupPolicy::default().validate().is_empty());
+        let invalid = DedupPolicy { threshold: 1.5, model: " ".to_string() };
+        assert_eq!(invalid.validate().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn finds_the_most_similar_object() {
+        let object_store = MemoryObjectStore::new();
+        let folder = "reading/2025-10-11-14/";
+        let objects = [
+            StoredObject::new("reading/2025-10-11-14/a.json", 1, None),
+            StoredObject::new("reading/2025-10-11-14/b.json", 1, None),
+            StoredObject::new("reading/2025-10-11-14/c.json", 1, None),
+        ];
+        store(&object_store, &objects[0].key, &[1.0, 0.0]).await.unwrap();
+        store(&object_store, &objects[1].key, &[0.6, 0.8]).await.unwrap();
+
+        assert_eq!(most_similar(&object_store, folder, &[], &[1.0, 0.0]).await.unwrap(), None);
+        let (key, similarity) = most_similar(&object_store, folder, &objects, &[0.0, 1.0]).await.unwrap().unwrap();
+        assert_eq!(key, "reading/2025-10-11-14/b.json");
+        assert!((similarity - 0.8).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,253 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    ServiceError,
+    keyvalue::{KeyValueStore, TypedKv},
+    progress::Skill,
+    reading::{MAX_GRADE, MIN_GRADE},
+    users::ChildProfile,
+};
+
+/// Times a difficulty update is retried when another write wins the race
+const MAX_UPDATE_ATTEMPTS: usize = 3;
+
+/// Column of the difficulty item holding its JSON
+const DIFFICULTY_COLUMN: &str = "difficulty";
+
+/// When a child's difficulty moves up or down a level
+///
+/// Configured under `[difficulty]` in the config file:
+///
+/// ```toml
+/// [difficulty]
+/// promote_above = 90
+/// promote_after = 3
+/// demote_below = 60
+/// demote_after = 2
+/// max_offset = 2
+/// ```
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DifficultyPolicy {
+    /// Scores above this percentage count toward moving up a level
+    pub promote_above: u8,
+
+    /// Consecutive scores above `promote_above` that move a child up a level
+    pub promote_after: u32,
+
+    /// Scores below this percentage count toward moving down a level
+    pub demote_below: u8,
+
+    /// Consecutive scores below `demote_below` that move a child down a level
+    pub demote_after: u32,
+
+    /// Most levels a child can move away from their profile's grade, either way
+    pub max_offset: i8,
+}
+
+impl Default for DifficultyPolicy {
+    fn default() -> Self {
+        Self {
+            promote_above: 90,
+            promote_after: 3,
+            demote_below: 60,
+            demote_after: 2,
+            max_offset: 2,
+        }
+    }
+}
+
+/// A child's adjustment for one skill and the scores counting toward the next one
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Difficulty {
+    /// Levels above (or, if negative, below) the profile's grade
+    pub offset: i8,
+
+    /// Consecutive scores above the promotion threshold since the last change
+    pub successes: u32,
+
+    /// Consecutive scores below the demotion threshold since the last change
+    pub struggles: u32,
+}
+
+impl Difficulty {
+    /// Returns the grade to generate content at for a child in `grade`
+    pub fn level(&self, grade: u8) -> u8 {
+        (i16::from(grade) + i16::from(self.offset)).clamp(MIN_GRADE.into(), MAX_GRADE.into()) as u8
+    }
+}
+
+impl DifficultyPolicy {
+    /// Checks that the thresholds are percentages and the streaks can be reached
+    pub fn validate(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if self.promote_above > 100 || self.demote_below > 100 {
+            problems.push("difficulty thresholds must be between 0 and 100".to_string());
+        }
+        if self.demote_below > self.promote_above {
+            problems.push("difficulty.demote_below must not exceed difficulty.promote_above".to_string());
+        }
+        if self.promote_after == 0 || self.demote_after == 0 {
+            problems.push("difficulty streaks must be at least 1".to_string());
+        }
+        if self.max_offset < 0 {
+            problems.push("difficulty.max_offset must not be negative".to_string());
+        }
+        problems
+    }
+
+    /// Returns the difficulty after a child scores `score` percent
+    ///
+    /// A score between the thresholds breaks both streaks. Reaching a streak moves
+    /// the offset one level, within `max_offset`, and starts counting again.
+    pub fn apply(&self, difficulty: Difficulty, score: u8) -> Difficulty {
+        let mut next = Difficulty { offset: difficulty.offset, successes: 0, struggles: 0 };
+
+        if score > self.promote_above {
+            next.successes = difficulty.successes + 1;
+            if next.successes >= self.promote_after {
+                next.offset = difficulty.offset.saturating_add(1).min(self.max_offset);
+                next.successes = 0;
+            }
+        } else if score < self.demote_below {
+            next.struggles = difficulty.struggles + 1;
+            if next.struggles >= self.demote_after {
+                next.offset = difficulty.offset.saturating_sub(1).max(-self.max_offset);
+                next.struggles = 0;
+            }
+        }
+
+        next
+    }
+}
+
+/// Key of the item holding a child's difficulty for a skill
+fn difficulty_key(child: &str, skill: Skill) -> String {
+    format!("difficulty#{}#{}", child, skill.as_str())
+}
+
+/// Loads a child's difficulty for a skill, unadjusted if no scores were recorded
+pub async fn load<K: KeyValueStore>(
+    kv_store: &K,
+    child: &str,
+    skill: Skill,
+) -> Result<Difficulty, ServiceError> {
+    Ok(kv_store
+        .get_json(&difficulty_key(child, skill), DIFFICULTY_COLUMN)
+        .await?
+        .unwrap_or_default())
+}
+
+/// Returns the grade to generate content at for a child practicing a skill
+pub async fn level_for<K: KeyValueStore>(
+    kv_store: &K,
+    profile: &ChildProfile,
+    skill: Skill,
+) -> Result<u8, ServiceError> {
+    Ok(load(kv_store, &profile.id, skill).await?.level(profile.grade))
+}
+
+/// Applies a score to a child's difficulty for a skill and saves it
+///
+/// Writes are checked against the item's version, so concurrent scores aren't lost.
+pub async fn record_score<K: KeyValueStore>(
+    kv_store: &K,
+    policy: &DifficultyPolicy,
+    child: &str,
+    skill: Skill,
+    score: u8,
+) -> Result<Difficulty, ServiceError> {
+    let key = difficulty_key(child, skill);
+
+    for _ in 0..MAX_UPDATE_ATTEMPTS {
+        let (version, difficulty) = kv_store.get_json_versioned(&key, DIFFICULTY_COLUMN).await?;
+        let next = policy.apply(difficulty.unwrap_or_default(), score);
+        if kv_store
+            .put_json_if_version(&key, DIFFICULTY_COLUMN, &next, version)
+            .await?
+        {
+            return Ok(next);
+        }
+    }
+
+    Err(ServiceError::Conflict(
+        "The difficulty was changed by another request; try again".into(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keyvalue::MemoryKeyValueStore;
+
+    fn apply_all(policy: &DifficultyPolicy, scores: &[u8]) -> Difficulty {
+        scores
+            .iter()
+            .fold(Difficulty::default(), |difficulty, &score| policy.apply(difficulty, score))
+    }
+
+    #[test]
+    fn moves_up_after_consecutive_high_scores() {
+        let policy = DifficultyPolicy::default();
+
+        assert_eq!(apply_all(&policy, &[95, 95]).offset, 0);
+        assert_eq!(apply_all(&policy, &[95, 95, 95]).offset, 1);
+        assert_eq!(apply_all(&policy, &[95, 95, 80, 95]).offset, 0);
+        assert_eq!(apply_all(&policy, &[95; 6]).offset, 2);
+        assert_eq!(apply_all(&policy, &[95; 30]).offset, policy.max_offset);
+
+        // Exactly the threshold isn't above it
+        assert_eq!(apply_all(&policy, &[90, 90, 90]).offset, 0);
+    }
+
+    #[test]
+    fn moves_down_after_repeated_struggles() {
+        let policy = DifficultyPolicy::default();
+
+        assert_eq!(apply_all(&policy, &[40]).offset, 0);
+        assert_eq!(apply_all(&policy, &[40, 30]).offset, -1);
+        assert_eq!(apply_all(&policy, &[40, 70, 30]).offset, 0);
+        assert_eq!(apply_all(&policy, &[95, 95, 95, 40, 40]).offset, 0);
+        assert_eq!(apply_all(&policy, &[0; 30]).offset, -policy.max_offset);
+    }
+
+    #[test]
+    fn follows_configured_thresholds() {
+        let policy = DifficultyPolicy {
+            promote_above: 70,
+            promote_after: 1,
+            max_offset: 1,
+            ..DifficultyPolicy::default()
+        };
+
+        assert_eq!(apply_all(&policy, &[80]).offset, 1);
+        assert_eq!(apply_all(&policy, &[80, 80]).offset, 1);
+        assert!(policy.validate().is_empty());
+
+        let invalid = DifficultyPolicy { demote_below: 95, promote_after: 0, ..policy };
+        assert_eq!(invalid.validate().len(), 2);
+    }
+
+    #[test]
+    fn keeps_levels_within_supported_grades() {
+        let up = Difficulty { offset: 2, ..Difficulty::default() };
+        let down = Difficulty { offset: -2, ..Difficulty::default() };
+
+        assert_eq!(up.level(3), 5);
+        assert_eq!(up.level(MAX_GRADE), MAX_GRADE);
+        assert_eq!(down.level(MIN_GRADE + 1), MIN_GRADE);
+    }
+
+    #[tokio::test]
+    async fn records_scores_per_child_and_skill() {
+        let store = MemoryKeyValueStore::new();
+        let policy = DifficultyPolicy::default();
+        for _ in 0..3 {
+            record_score(&store, &policy, "c1", Skill::Comprehension, 100).await.unwrap();
+        }
+
+        assert_eq!(load(&store, "c1", Skill::Comprehension).await.unwrap().offset, 1);
+        assert_eq!(load(&store, "c1", Skill::Math).await.unwrap().offset, 0);
+        assert_eq!(load(&store, "c2", Skill::Comprehension).await.unwrap().offset, 0);
+    }
+}
@@ -0,0 +1,188 @@
+use async_trait::async_trait;
+use std::sync::Arc;
+use tracing::{info, warn};
+
+use crate::{
+    ServiceError,
+    config::{Config, EmailBackend},
+};
+
+mod ses;
+mod smtp;
+
+pub use ses::SesMailer;
+pub use smtp::SmtpMailer;
+
+/// An email to a single recipient
+#[derive(Debug, Clone, PartialEq)]
+pub struct EmailMessage {
+    pub to: String,
+    pub subject: String,
+
+    /// Plain text body, which every email has
+    pub text: String,
+
+    /// HTML alternative to the text body, if any
+    pub html: Option<String>,
+}
+
+impl EmailMessage {
+    /// Creates a plain text email
+    pub fn new(to: &str, subject: &str, text: String) -> Self {
+        Self {
+            to: to.to_string(),
+            subject: subject.to_string(),
+            text,
+            html: None,
+        }
+    }
+
+    /// Adds an HTML alternative to the text body
+    pub fn with_html(mut self, html: String) -> Self {
+        self.html = Some(html);
+        self
+    }
+}
+
+/// Sends emails on behalf of the service
+#[async_trait]
+pub trait Mailer: Send + Sync {
+    /// Sends an email, returning once the provider has accepted it
+    ///
+    /// # Returns
+    /// * `Ok(())` - If the email was accepted for delivery
+    /// * `Err(ServiceError)` - If the message is invalid or the provider rejects it
+    ///   or can't be reached
+    async fn send(&self, message: &EmailMessage) -> Result<(), ServiceError>;
+}
+
+/// Mailer that logs emails instead of sending them, for local development and
+/// tests
+///
+/// The text body is logged, so magic links can be followed from the console.
+#[derive(Clone, Default)]
+pub struct NoopMailer;
+
+#[async_trait]
+impl Mailer for NoopMailer {
+    async fn send(&self, message: &EmailMessage) -> Result<(), ServiceError> {
+        info!(
+            "Not sending email to {} (no email backend): {}\n{}",
+            message.to, message.subject, message.text
+        );
+        Ok(())
+    }
+}
+
+/// Mailer that tries a second mailer when the first fails
+#[derive(Clone)]
+pub struct FailoverMailer {
+    primary: Arc<dyn Mailer>,
+    fallback: Arc<dyn Mailer>,
+}
+
+impl FailoverMailer {
+    /// Creates a mailer sending through `primary`, or `fallback` if that fails
+    pub fn new(primary: Arc<dyn Mailer>, fallback: Arc<dyn Mailer>) -> Self {
+        Self { primary, fallback }
+    }
+}
+
+#[async_trait]
+impl Mailer for FailoverMailer {
+    async fn send(&self, message: &EmailMessage) -> Result<(), ServiceError> {
+        match self.primary.send(message).await {
+            Ok(()) => Ok(()),
+            Err(e) => {
+                warn!("Failed to send email, trying fallback: {}", e);
+                self.fallback.send(message).await
+            }
+        }
+    }
+}
+
+/// Creates the mailer selected by `config.email_backend()`
+///
+/// With SES, `smtp_url` is used as a fallback if it is set.
+pub async fn from_config(config: &Config) -> Result<Arc<dyn Mailer>, ServiceError> {
+    let from = || {
+        config
+            .email_from
+            .as_deref()
+            .ok_or_else(|| ServiceError::ConfigError("EMAIL_FROM must be set".into()))
+    };
+    let smtp = match &config.smtp_url {
+        Some(url) => Some(SmtpMailer::from_url(url, from()?)?),
+        None => None,
+    };
+
+    Ok(match config.email_backend() {
+        EmailBackend::None => Arc::new(NoopMailer),
+        EmailBackend::Smtp => Arc::new(
+            smtp.ok_or_else(|| ServiceError::ConfigError("SMTP_URL must be set".into()))?,
+        ),
+        EmailBackend::Ses => {
+            let ses = Arc::new(SesMailer::from_env(from()?).await?);
+            match smtp {
+                Some(smtp) => Arc::new(FailoverMailer::new(ses, Arc::new(smtp))),
+                None => ses,
+            }
+        }
+    })
+}
+
+/// Returns the bare address of a mailbox such as "Thinkaroo <hello@example.com>"
+pub(crate) fn address(mailbox: &str) -> &str {
+    match (mailbox.rfind('<'), mailbox.rfind('>')) {
+        (Some(start), Some(end)) if start < end => &mailbox[start + 1..end],
+        _ => mailbox.trim(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// Mailer that keeps every email it is asked to send
+    #[derive(Clone, Default)]
+    struct RecordingMailer {
+        sent: Arc<Mutex<Vec<EmailMessage>>>,
+    }
+
+    #[async_trait]
+    impl Mailer for RecordingMailer {
+        async fn send(&self, message: &EmailMessage) -> Result<(), ServiceError> {
+            self.sent.lock().unwrap().push(message.clone());
+            Ok(())
+        }
+    }
+
+    struct FailingMailer;
+
+    #[async_trait]
+    impl Mailer for FailingMailer {
+        async fn send(&self, _message: &EmailMessage) -> Result<(), ServiceError> {
+            Err(ServiceError::EmailError("unavailable".into()))
+        }
+    }
+
+    #[tokio::test]
+    async fn falls_back_when_the_primary_fails() {
+        let fallback = RecordingMailer::default();
+        let mailer = FailoverMailer::new(Arc::new(FailingMailer), Arc::new(fallback.clone()));
+        let message = EmailMessage::new("parent@example.com", "Hello", "Hi there".into());
+
+        mailer.send(&message).await.unwrap();
+        assert_eq!(*fallback.sent.lock().unwrap(), vec![message.clone()]);
+
+        let failing = FailoverMailer::new(Arc::new(FailingMailer), Arc::new(FailingMailer));
+        assert!(matches!(failing.send(&message).await, Err(ServiceError::EmailError(_))));
+    }
+
+    #[test]
+    fn extracts_addresses() {
+        assert_eq!(address("Thinkaroo <hello@example.com>"), "hello@example.com");
+        assert_eq!(address(" hello@example.com "), "hello@example.com");
+    }
+}
@@ -0,0 +1,126 @@
+use async_trait::async_trait;
+use aws_credential_types::provider::{ProvideCredentials, SharedCredentialsProvider};
+use aws_sigv4::{
+    http_request::{SignableBody, SignableRequest, SigningSettings, sign},
+    sign::v4,
+};
+use serde_json::json;
+use std::time::SystemTime;
+
+use super::{EmailMessage, Mailer};
+use crate::ServiceError;
+
+/// Name SES requests are signed for
+const SIGNING_NAME: &str = "ses";
+
+/// Mailer backed by the Amazon SES v2 `SendEmail` API
+///
+/// Requests are signed with the same credentials and region as the rest of the
+/// AWS clients, and the sender must be verified in SES.
+#[derive(Clone)]
+pub struct SesMailer {
+    http: reqwest::Client,
+    credentials: SharedCredentialsProvider,
+    region: String,
+    from: String,
+}
+
+impl SesMailer {
+    /// Creates an SES mailer from the default AWS configuration
+    ///
+    /// # Arguments
+    /// * `from` - Sender of every email, e.g. "Thinkaroo <hello@example.com>"
+    pub async fn from_env(from: &str) -> Result<Self, ServiceError> {
+        let aws_config = aws_config::load_defaults(aws_config::BehaviorVersion::latest()).await;
+        let credentials = aws_config.credentials_provider().ok_or_else(|| {
+            ServiceError::ConfigError("AWS credentials are required to send email with SES".into())
+        })?;
+        let region = aws_config.region().ok_or_else(|| {
+            ServiceError::ConfigError("An AWS region is required to send email with SES".into())
+        })?;
+
+        Ok(Self {
+            http: reqwest::Client::new(),
+            credentials,
+            region: region.to_string(),
+            from: from.to_string(),
+        })
+    }
+
+    /// Builds the `SendEmail` request body
+    fn request_body(&self, message: &EmailMessage) -> serde_json::Value {
+        let mut body = json!({
+            "Text": { "Data": message.text, "Charset": "UTF-8" },
+        });
+        if let Some(html) = &message.html {
+            body["Html"] = json!({ "Data": html, "Charset": "UTF-8" });
+        }
+
+        json!({
+            "FromEmailAddress": self.from,
+            "Destination": { "ToAddresses": [message.to] },
+            "Content": {
+                "Simple": {
+                    "Subject": { "Data": message.subject, "Charset": "UTF-8" },
+                    "Body": body,
+                },
+            },
+        })
+    }
+}
+
+#[async_trait]
+impl Mailer for SesMailer {
+    async fn send(&self, message: &EmailMessage) -> Result<(), ServiceError> {
+        let url = format!("https://email.{}.amazonaws.com/v2/email/outbound-emails", self.region);
+        let body = serde_json::to_vec(&self.request_body(message))?;
+
+        let identity = self
+            .credentials
+            .provide_credentials()
+            .await
+            .map_err(|e| ServiceError::EmailError(format!("Failed to load AWS credentials: {}", e)))?
+            .into();
+        let signing_params = v4::SigningParams::builder()
+            .identity(&identity)
+            .region(&self.region)
+            .name(SIGNING_NAME)
+            .time(SystemTime::now())
+            .settings(SigningSettings::default())
+            .build()
+            .map_err(|e| ServiceError::EmailError(format!("Failed to sign SES request: {}", e)))?
+            .into();
+        let signable = SignableRequest::new(
+            "POST",
+            url.as_str(),
+            [("content-type", "application/json")].into_iter(),
+            SignableBody::Bytes(&body),
+        )
+        .and_then(|request| sign(request, &signing_params))
+        .map_err(|e| ServiceError::EmailError(format!("Failed to sign SES request: {}", e)))?;
+        let (instructions, _signature) = signable.into_parts();
+
+        let mut request = self
+            .http
+            .post(&url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json");
+        for (name, value) in instructions.headers() {
+            request = request.header(name, value);
+        }
+
+        let response = request
+            .body(body)
+            .send()
+            .await
+            .map_err(|e| ServiceError::EmailError(format!("SES request failed: {}", e)))?;
+        let status = response.status();
+        if !status.is_success() {
+            let detail = response.text().await.unwrap_or_default();
+            return Err(ServiceError::EmailError(format!(
+                "SES rejected email with {}: {}",
+                status, detail
+            )));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,335 @@
+use chrono::{DateTime, Datelike, Timelike, Utc};
+use flate2::{Compression, Crc, write::DeflateEncoder};
+use std::io::Write;
+
+/// ZIP compression methods
+const STORED: u16 = 0;
+const DEFLATED: u16 = 8;
+
+/// ZIP version needed to extract deflated entries (2.0)
+const ZIP_VERSION: u16 = 20;
+
+/// Escapes text for XHTML content and attribute values
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// An entry already written, recorded for the central directory
+struct ZipEntry {
+    name: String,
+    method: u16,
+    crc: u32,
+    compressed_size: u32,
+    size: u32,
+    offset: u32,
+}
+
+/// Writes a ZIP archive one entry at a time, so each can be sent as soon as it's
+/// ready
+struct ZipWriter {
+    entries: Vec<ZipEntry>,
+
+    /// Bytes written so far
+    offset: u32,
+
+    /// Modification time of every entry, in MS-DOS format
+    dos_time: u16,
+    dos_date: u16,
+}
+
+impl ZipWriter {
+    fn new(modified: DateTime<Utc>) -> Self {
+        // MS-DOS dates start in 1980
+        let year = modified.year().clamp(1980, 2107) as u16;
+        Self {
+            entries: Vec::new(),
+            offset: 0,
+            dos_time: ((modified.hour() << 11) | (modified.minute() << 5) | (modified.second() / 2)) as u16,
+            dos_date: ((year - 1980) << 9) | ((modified.month() << 5) | modified.day()) as u16,
+        }
+    }
+
+    /// Returns the bytes of an entry: its local header followed by its data
+    fn entry(&mut self, name: &str, data: &[u8], compress: bool) -> Vec<u8> {
+        let mut crc = Crc::new();
+        crc.update(data);
+        let (method, stored) = if compress {
+            let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(data).expect("writing to a Vec can't fail");
+            (DEFLATED, encoder.finish().expect("writing to a Vec can't fail"))
+        } else {
+            (STORED, data.to_vec())
+        };
+        let entry = ZipEntry {
+            name: name.to_string(),
+            method,
+            crc: crc.sum(),
+            compressed_size: stored.len() as u32,
+            size: data.len() as u32,
+            offset: self.offset,
+        };
+
+        let mut bytes = Vec::with_capacity(30 + name.len() + stored.len());
+        bytes.extend(0x04034b50u32.to_le_bytes());
+        bytes.extend(ZIP_VERSION.to_le_bytes());
+        bytes.extend(0u16.to_le_bytes()); // flags
+        bytes.extend(entry.method.to_le_bytes());
+        bytes.extend(self.dos_time.to_le_bytes());
+        bytes.extend(self.dos_date.to_le_bytes());
+        bytes.extend(entry.crc.to_le_bytes());
+        bytes.extend(entry.compressed_size.to_le_bytes());
+        bytes.extend(entry.size.to_le_bytes());
+        bytes.extend((name.len() as u16).to_le_bytes());
+        bytes.extend(0u16.to_le_bytes()); // extra field length
+        bytes.extend(name.as_bytes());
+        bytes.extend(stored);
+
+        self.offset += bytes.len() as u32;
+        self.entries.push(entry);
+        bytes
+    }
+
+    /// Returns the central directory listing every entry, which ends the archive
+    fn finish(self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        for entry in &self.entries {
+            bytes.extend(0x02014b50u32.to_le_bytes());
+            bytes.extend(ZIP_VERSION.to_le_bytes()); // made by
+            bytes.extend(ZIP_VERSION.to_le_bytes()); // needed to extract
+            bytes.extend(0u16.to_le_bytes()); // flags
+            bytes.extend(entry.method.to_le_bytes());
+            bytes.extend(self.dos_time.to_le_bytes());
+            bytes.extend(self.dos_date.to_le_bytes());
+            bytes.extend(entry.crc.to_le_bytes());
+            bytes.extend(entry.compressed_size.to_le_bytes());
+            bytes.extend(entry.size.to_le_bytes());
+            bytes.extend((entry.name.len() as u16).to_le_bytes());
+            bytes.extend([0u8; 12]); // extra and comment lengths, disk, attributes
+            bytes.extend(entry.offset.to_le_bytes());
+            bytes.extend(entry.name.as_bytes());
+        }
+
+        let count = self.entries.len() as u16;
+        let directory_size = bytes.len() as u32;
+        bytes.extend(0x06054b50u32.to_le_bytes());
+        bytes.extend([0u8; 4]); // disk numbers
+        bytes.extend(count.to_le_bytes());
+        bytes.extend(count.to_le_bytes());
+        bytes.extend(directory_size.to_le_bytes());
+        bytes.extend(self.offset.to_le_bytes());
+        bytes.extend(0u16.to_le_bytes()); // comment length
+        bytes
+    }
+}
+
+/// Writes an EPUB 3 book of text chapters, returning each part's bytes as soon as
+/// it's written so books can be streamed while their chapters are loaded
+///
+/// # Example
+/// ```
+/// use thinkaroo::epub::EpubWriter;
+///
+/// let mut book = EpubWriter::new("Stories", "urn:uuid:1234");
+/// let mut bytes = book.start();
+/// bytes.extend(book.chapter("The Lost Kite", &["Once upon a time..."]));
+/// bytes.extend(book.finish());
+/// assert!(bytes.starts_with(b"PK"));
+/// ```
+pub struct EpubWriter {
+    zip: ZipWriter,
+    title: String,
+
+    /// Unique identifier of the book, e.g. a URN
+    identifier: String,
+    modified: DateTime<Utc>,
+
+    /// Title of each chapter written so far
+    chapters: Vec<String>,
+}
+
+impl EpubWriter {
+    /// Creates a book with a title and a unique identifier
+    pub fn new(title: &str, identifier: &str) -> Self {
+        let modified = Utc::now();
+        Self {
+            zip: ZipWriter::new(modified),
+            title: title.to_string(),
+            identifier: identifier.to_string(),
+            modified,
+            chapters: Vec::new(),
+        }
+    }
+
+    /// Returns the start of the book, which must be sent first
+    pub fn start(&mut self) -> Vec<u8> {
+        // Readers identify the format by the uncompressed `mimetype` entry at the start
+        let mut bytes = self.zip.entry("mimetype", b"application/epub+zip", false);
+        bytes.extend(self.zip.entry(
+            "META-INF/container.xml",
+            br#"<?xml version="1.0" encoding="UTF-8"?>
+<container version="1.0" xmlns="urn:oasis:names:tc:opendocument:xmlns:container">
+  <rootfiles>
+    <rootfile full-path="OEBPS/content.opf" media-type="application/oebps-package+xml"/>
+  </rootfiles>
+</container>
+"#,
+            true,
+        ));
+        bytes
+    }
+
+    /// Returns a chapter with a title and paragraphs of plain text
+    pub fn chapter(&mut self, title: &str, paragraphs: &[&str]) -> Vec<u8> {
+        self.chapters.push(title.to_string());
+        let body: String = paragraphs
+            .iter()
+            .map(|paragraph| format!("    <p>{}</p>\n", escape(paragraph)))
+            .collect();
+        let xhtml = page(title, &format!("    <h1>{}</h1>\n{}", escape(title), body));
+        let name = format!("OEBPS/chapter-{}.xhtml", self.chapters.len());
+        self.zip.entry(&name, xhtml.as_bytes(), true)
+    }
+
+    /// Returns the end of the book: its table of contents, package document, and
+    /// the ZIP directory
+    pub fn finish(mut self) -> Vec<u8> {
+        let links: String = self
+            .chapters
+            .iter()
+            .enumerate()
+            .map(|(i, title)| format!("        <li><a href=\"chapter-{}.xhtml\">{}</a></li>\n", i + 1, escape(title)))
+            .collect();
+        let nav = page(
+            "Contents",
+            &format!("    <nav epub:type=\"toc\">\n      <h1>Contents</h1>\n      <ol>\n{}      </ol>\n    </nav>\n", links),
+        );
+        let mut bytes = self.zip.entry("OEBPS/nav.xhtml", nav.as_bytes(), true);
+
+        let items: String = (1..=self.chapters.len())
+            .map(|i| {
+                format!("    <item id=\"chapter-{i}\" href=\"chapter-{i}.xhtml\" media-type=\"application/xhtml+xml\"/>\n")
+            })
+            .collect();
+        let spine: String = (1..=self.chapters.len())
+            .map(|i| format!("    <itemref idref=\"chapter-{}\"/>\n", i))
+            .collect();
+        let package = format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<package xmlns="http://www.idpf.org/2007/opf" version="3.0" unique-identifier="book-id">
+  <metadata xmlns:dc="http://purl.org/dc/elements/1.1/">
+    <dc:identifier id="book-id">{}</dc:identifier>
+    <dc:title>{}</dc:title>
+    <dc:language>en</dc:language>
+    <meta property="dcterms:modified">{}</meta>
+  </metadata>
+  <manifest>
+    <item id="nav" href="nav.xhtml" media-type="application/xhtml+xml" properties="nav"/>
+{}  </manifest>
+  <spine>
+{}  </spine>
+</package>
+"#,
+            escape(&self.identifier),
+            escape(&self.title),
+            self.modified.format("%Y-%m-%dT%H:%M:%SZ"),
+            items,
+            spine
+        );
+        bytes.extend(self.zip.entry("OEBPS/content.opf", package.as_bytes(), true));
+        bytes.extend(self.zip.finish());
+        bytes
+    }
+}
+
+/// Wraps a page body in an XHTML document
+fn page(title: &str, body: &str) -> String {
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE html>
+<html xmlns="http://www.w3.org/1999/xhtml" xmlns:epub="http://www.idpf.org/2007/ops">
+  <head>
+    <title>{}</title>
+  </head>
+  <body>
+{}  </body>
+</html>
+"#,
+        escape(title),
+        body
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::DeflateDecoder;
+    use std::io::Read;
+
+    fn u16_at(bytes: &[u8], at: usize) -> usize {
+        u16::from_le_bytes([bytes[at], bytes[at + 1]]) as usize
+    }
+
+    fn u32_at(bytes: &[u8], at: usize) -> usize {
+        u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap()) as usize
+    }
+
+    #[test]
+    fn writes_a_readable_archive() {
+        let mut book = EpubWriter::new("Tom & Jerry's <Stories>", "urn:uuid:1");
+        let mut bytes = book.start();
+        bytes.extend(book.chapter("The Fox", &["One day.", "The fox ran & hid."]));
+        bytes.extend(book.finish());
+
+        // The mimetype entry comes first, uncompressed
+        assert_eq!(&bytes[30..38], b"mimetype");
+        assert_eq!(&bytes[38..58], b"application/epub+zip");
+
+        // Walk the central directory and read every entry back
+        let end = bytes.len() - 22;
+        assert_eq!(u32_at(&bytes, end), 0x06054b50);
+        let count = u16_at(&bytes, end + 10);
+        let mut at = u32_at(&bytes, end + 16);
+        let mut files = Vec::new();
+        for _ in 0..count {
+            assert_eq!(u32_at(&bytes, at), 0x02014b50);
+            let method = u16_at(&bytes, at + 10);
+            let size = u32_at(&bytes, at + 24);
+            let name_len = u16_at(&bytes, at + 28);
+            let name = String::from_utf8(bytes[at + 46..at + 46 + name_len].to_vec()).unwrap();
+            let local = u32_at(&bytes, at + 42);
+            let data_start = local + 30 + u16_at(&bytes, local + 26);
+            let data = &bytes[data_start..data_start + u32_at(&bytes, at + 20)];
+            let mut contents = Vec::new();
+            if method == DEFLATED as usize {
+                DeflateDecoder::new(data).read_to_end(&mut contents).unwrap();
+            } else {
+                contents = data.to_vec();
+            }
+            assert_eq!(contents.len(), size);
+            let mut crc = Crc::new();
+            crc.update(&contents);
+            assert_eq!(crc.sum() as usize, u32_at(&bytes, at + 16));
+            files.push((name, String::from_utf8(contents).unwrap()));
+            at += 46 + name_len;
+        }
+
+        let names: Vec<&str> = files.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(
+            names,
+            ["mimetype", "META-INF/container.xml", "OEBPS/chapter-1.xhtml", "OEBPS/nav.xhtml", "OEBPS/content.opf"]
+        );
+        assert!(files[2].1.contains("<p>The fox ran &amp; hid.</p>"));
+        assert!(files[4].1.contains("<dc:title>Tom &amp; Jerry's &lt;Stories&gt;</dc:title>"));
+        assert!(files[4].1.contains(r#"<itemref idref="chapter-1"/>"#));
+    }
+}
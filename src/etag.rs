@@ -0,0 +1,123 @@
+use axum::{
+    body::Body,
+    extract::Request,
+    http::{HeaderValue, Method, StatusCode, header},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use sha2::{Digest, Sha256};
+use tracing::error;
+
+use crate::problem::ErrorResponse;
+
+/// Hex digits of the body's SHA-256 hash kept in an entity tag
+const TAG_LEN: usize = 32;
+
+/// Returns the strong entity tag of a response body, a hash of its bytes, so
+/// identical content always gets the same tag
+pub fn entity_tag(body: &[u8]) -> String {
+    let hash: String = Sha256::digest(body).iter().map(|byte| format!("{:02x}", byte)).collect();
+    format!("\"{}\"", &hash[..TAG_LEN])
+}
+
+/// Whether an `If-None-Match` header matches an entity tag
+///
+/// Tags are compared weakly, as RFC 9110 requires for `If-None-Match`, so a
+/// `W/` prefix is ignored.
+pub fn none_match(header: &str, etag: &str) -> bool {
+    let opaque = |tag: &str| tag.trim().trim_start_matches("W/").to_string();
+    header.trim() == "*" || header.split(',').any(|tag| opaque(tag) == opaque(etag))
+}
+
+/// Middleware that tags JSON responses with an `ETag` and answers requests whose
+/// `If-None-Match` already has it with 304 Not Modified, so clients polling for
+/// content don't download it again
+///
+/// Only successful GET and HEAD responses are tagged, and streamed responses
+/// such as server-sent events are passed through untouched.
+pub async fn etag(request: Request, next: Next) -> Response {
+    let conditional = matches!(*request.method(), Method::GET | Method::HEAD);
+    let if_none_match = request
+        .headers()
+        .get(header::IF_NONE_MATCH)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string);
+
+    let response = next.run(request).await;
+    let is_json = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("application/json"));
+    if !conditional || response.status() != StatusCode::OK || !is_json {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let body = match axum::body::to_bytes(body, usize::MAX).await {
+        Ok(body) => body,
+        Err(e) => {
+            error!("Failed to read response body to tag it: {}", e);
+            return ErrorResponse::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "internal_error",
+                "Internal server error",
+            )
+            .into_response();
+        }
+    };
+    let etag = entity_tag(&body);
+    parts.headers.insert(
+        header::ETAG,
+        HeaderValue::from_str(&etag).expect("entity tags are ASCII"),
+    );
+
+    if if_none_match.is_some_and(|header| none_match(&header, &etag)) {
+        parts.status = StatusCode::NOT_MODIFIED;
+        parts.headers.remove(header::CONTENT_TYPE);
+        parts.headers.remove(header::CONTENT_LENGTH);
+        return Response::from_parts(parts, Body::empty());
+    }
+    Response::from_parts(parts, Body::from(body))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{Json, Router, routing::get};
+    use serde_json::json;
+    use tower::ServiceExt;
+
+    fn app() -> Router {
+        Router::new()
+            .route("/", get(|| async { Json(json!({ "title": "The Fox" })) }))
+            .route("/text", get(|| async { "plain" }))
+            .layer(axum::middleware::from_fn(etag))
+    }
+
+    async fn get_with(path: &str, if_none_match: Option<&str>) -> Response {
+        let mut request = axum::http::Request::get(path);
+        if let Some(tag) = if_none_match {
+            request = request.header(header::IF_NONE_MATCH, tag);
+        }
+        app().oneshot(request.body(Body::empty()).unwrap()).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn answers_matching_requests_with_not_modified() {
+        let response = get_with("/", None).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let tag = response.headers()[header::ETAG].to_str().unwrap().to_string();
+        assert_eq!(tag, entity_tag(br#"{"title":"The Fox"}"#));
+
+        let response = get_with("/", Some(&tag)).await;
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(response.headers()[header::ETAG], tag.as_str());
+        assert!(axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap().is_empty());
+
+        let weak_list = format!("\"other\", W/{}", tag);
+        assert_eq!(get_with("/", Some(&weak_list)).await.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(get_with("/", Some("\"other\"")).await.status(), StatusCode::OK);
+        assert!(get_with("/text", None).await.headers().get(header::ETAG).is_none());
+    }
+}
@@ -0,0 +1,169 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::{
+    ServiceError,
+    keyvalue::{Column, KeyValueStore},
+};
+
+/// Column holding a session's assigned variant
+const VARIANT_COLUMN: &str = "variant";
+
+/// An A/B experiment comparing versions of a prompt
+///
+/// Declared in the base prompt's TOML; each variant other than the base prompt's own
+/// version is loaded from a prompt named `{base}@{variant}`:
+///
+/// ```toml
+/// version = "v1"
+///
+/// [experiment.variants]
+/// v1 = 50
+/// v2 = 50
+/// ```
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ExperimentConfig {
+    /// Relative weight of each variant, keyed by version
+    pub variants: BTreeMap<String, u32>,
+}
+
+impl ExperimentConfig {
+    /// Picks a variant by weight, deterministically for a given seed
+    pub fn pick_variant(&self, seed: u64) -> Option<&str> {
+        let total: u64 = self.variants.values().map(|w| u64::from(*w)).sum();
+        if total == 0 {
+            return None;
+        }
+
+        let mut point = seed % total;
+        for (variant, weight) in &self.variants {
+            let weight = u64::from(*weight);
+            if point < weight {
+                return Some(variant);
+            }
+            point -= weight;
+        }
+
+        None
+    }
+}
+
+/// Returns the prompt name for a variant of a base prompt (e.g., `reading_comprehension@v2`)
+pub fn variant_prompt_name(base: &str, variant: &str) -> String {
+    format!("{}@{}", base, variant)
+}
+
+/// Assigns a session to a variant of an experiment
+///
+/// The first assignment for a session is derived from a hash of the session id and
+/// persisted in the KeyValueStore, so the session keeps its variant even if the
+/// experiment's weights change later. Requests without a session get a random variant.
+///
+/// # Arguments
+/// * `kv_store` - The store persisting assignments
+/// * `prompt_name` - The base prompt the experiment belongs to
+/// * `experiment` - The experiment configuration
+/// * `session_id` - The session to assign, if known
+///
+/// # Returns
+/// * `Ok(Some(String))` - The assigned variant
+/// * `Ok(None)` - The experiment has no variants with weight
+/// * `Err(ServiceError)` - If reading or persisting the assignment fails
+pub async fn assign_variant<K: KeyValueStore>(
+    kv_store: &K,
+    prompt_name: &str,
+    experiment: &ExperimentConfig,
+    session_id: Option<&str>,
+) -> Result<Option<String>, ServiceError> {
+    let Some(session_id) = session_id else {
+        return Ok(experiment.pick_variant(rand::random()).map(str::to_string));
+    };
+
+    let key = format!("experiment#{}#{}", prompt_name, session_id);
+    let existing = kv_store
+        .get(key.clone(), vec![VARIANT_COLUMN.to_string()])
+        .await?;
+    if let Some(column) = existing.into_iter().next() {
+        let variant = String::from_utf8(column.value)?;
+        if experiment.variants.contains_key(&variant) {
+            return Ok(Some(variant));
+        }
+    }
+
+    let Some(variant) = experiment.pick_variant(fnv1a(session_id.as_bytes())) else {
+        return Ok(None);
+    };
+
+    kv_store
+        .put(
+            key,
+            vec![Column::new(
+                VARIANT_COLUMN.to_string(),
+                variant.as_bytes().to_vec(),
+            )],
+            None,
+        )
+        .await?;
+
+    Ok(Some(variant.to_string()))
+}
+
+/// Stable 64-bit FNV-1a hash, so assignments don't depend on the standard hasher
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x100000001b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keyvalue::MemoryKeyValueStore;
+
+    fn experiment() -> ExperimentConfig {
+        ExperimentConfig {
+            variants: BTreeMap::from([("v1".to_string(), 1), ("v2".to_string(), 3)]),
+        }
+    }
+
+    #[test]
+    fn test_pick_variant_by_weight() {
+        let experiment = experiment();
+        assert_eq!(experiment.pick_variant(0), Some("v1"));
+        assert_eq!(experiment.pick_variant(1), Some("v2"));
+        assert_eq!(experiment.pick_variant(3), Some("v2"));
+        assert_eq!(experiment.pick_variant(4), Some("v1"));
+    }
+
+    #[test]
+    fn test_pick_variant_without_weight() {
+        let experiment = ExperimentConfig {
+            variants: BTreeMap::from([("v1".to_string(), 0)]),
+        };
+        assert_eq!(experiment.pick_variant(7), None);
+    }
+
+    #[tokio::test]
+    async fn test_assignment_is_sticky() {
+        let kv_store = MemoryKeyValueStore::new();
+        let experiment = experiment();
+
+        let first = assign_variant(&kv_store, "reading", &experiment, Some("session-1"))
+            .await
+            .unwrap();
+
+        // Changing the weights must not move an already-assigned session
+        let flipped = ExperimentConfig {
+            variants: experiment
+                .variants
+                .keys()
+                .map(|v| (v.clone(), if Some(v) == first.as_ref() { 0 } else { 1 }))
+                .collect(),
+        };
+        let second = assign_variant(&kv_store, "reading", &flipped, Some("session-1"))
+            .await
+            .unwrap();
+
+        assert_eq!(first, second);
+    }
+}
@@ -0,0 +1,254 @@
+use axum::{Json, extract::State, http::StatusCode};
+use chrono::{Days, NaiveDate, Utc};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tracing::warn;
+
+use crate::{
+    ServiceError,
+    content::{ContentParams, ContentSchema, ContentTypeDescriptor},
+    keyvalue::{Column, KeyValueStore, RecordQuery, column_json, encode_json},
+    metrics,
+    problem::ErrorResponse,
+    state::AppState,
+    storage::ObjectStore,
+    users::CurrentUser,
+    validation::{BannedWords, NoEmptyFields},
+};
+
+/// Name of the prompt explanations are generated with
+const EXPLANATION_PROMPT: &str = "mistake_explanation";
+
+/// Column of an explanation record holding its JSON
+const EXPLANATION_COLUMN: &str = "explanation";
+
+/// Column of the item counting an account's generated explanations
+const COUNT_COLUMN: &str = "explanations";
+
+/// Longest question or answer that can be explained, in characters
+const MAX_TEXT_LEN: usize = 500;
+
+/// A question a child answered wrongly, as sent by the client after grading
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct MistakeReport {
+    pub question: String,
+
+    /// The child's answer
+    pub answer: String,
+
+    pub correct_answer: String,
+}
+
+/// Why an answer was wrong, in words a child understands
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct MistakeExplanation {
+    /// What went wrong with the answer and why the correct one is right
+    pub explanation: String,
+
+    /// Something to try next time to avoid the mistake
+    pub tip: String,
+}
+
+/// Lowercases text and collapses its whitespace, so answers differing only in
+/// case or spacing share an explanation
+fn normalized(text: &str) -> String {
+    text.to_lowercase().split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Returns the key the explanation of a mistake is kept under
+///
+/// The correct answer is part of it, so a question asked about many stories
+/// (e.g., "What is the main idea?") gets an explanation for each.
+fn mistake_hash(report: &MistakeReport) -> String {
+    let mut hasher = Sha256::new();
+    for text in [&report.question, &report.answer, &report.correct_answer] {
+        hasher.update(normalized(text));
+        hasher.update([0]);
+    }
+    hasher.finalize().iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Checks a report has a question and two different answers of reasonable length
+fn validate_report(report: &MistakeReport) -> Result<(), ServiceError> {
+    for (name, text) in [
+        ("question", &report.question),
+        ("answer", &report.answer),
+        ("correct_answer", &report.correct_answer),
+    ] {
+        if text.trim().is_empty() || text.chars().count() > MAX_TEXT_LEN {
+            return Err(ServiceError::InvalidInput(format!(
+                "{} must be 1 to {} characters",
+                name, MAX_TEXT_LEN
+            )));
+        }
+    }
+    if normalized(&report.answer) == normalized(&report.correct_answer) {
+        return Err(ServiceError::InvalidInput("answer is the correct answer".into()));
+    }
+    Ok(())
+}
+
+/// Key of the item counting an account's generated explanations on a day
+fn allowance_key(account_id: &str, date: NaiveDate) -> String {
+    format!("explanations#{}#{}", account_id, date.format("%Y-%m-%d"))
+}
+
+/// Counts a generated explanation against an account's daily limit
+///
+/// # Returns
+/// * `Ok(true)` - The explanation is within the limit
+/// * `Ok(false)` - The account has used up the day's explanations
+/// * `Err(ServiceError)` - If the store can't be reached
+async fn take_explanation<K: KeyValueStore>(
+    kv_store: &K,
+    account_id: &str,
+    date: NaiveDate,
+    limit: u32,
+) -> Result<bool, ServiceError> {
+    let count = kv_store
+        .increment(allowance_key(account_id, date), COUNT_COLUMN.to_string(), 1)
+        .await?;
+    Ok(count <= i64::from(limit))
+}
+
+/// Gives back an explanation that couldn't be generated, logging rather than failing
+async fn return_explanation<K: KeyValueStore>(kv_store: &K, account_id: &str, date: NaiveDate) {
+    let returned = kv_store
+        .increment(allowance_key(account_id, date), COUNT_COLUMN.to_string(), -1)
+        .await;
+    if let Err(e) = returned {
+        warn!("Failed to return explanation to {}: {}", account_id, e);
+    }
+}
+
+/// Returns the descriptor explanations are generated with
+///
+/// Explanations aren't a registered content type: they're only made for a
+/// particular mistake, so there's nothing to warm or serve at random.
+fn descriptor() -> ContentTypeDescriptor {
+    ContentTypeDescriptor::new(
+        EXPLANATION_PROMPT,
+        EXPLANATION_PROMPT,
+        ContentSchema::for_type::<MistakeExplanation>(
+            "MistakeExplanation",
+            "Why an answer was wrong, and a tip for next time",
+        ),
+    )
+    .with_validator(NoEmptyFields)
+    .with_validator(BannedWords::default())
+}
+
+/// Explains why a child's answer to a question was wrong, generating the
+/// explanation the first time anyone makes the mistake
+///
+/// Explanations are kept by question, wrong answer, and correct answer, so a
+/// mistake many children make is only explained once. Only signed-in accounts
+/// may ask, and each may generate `limits.explanations_per_day` a day; cached
+/// explanations are free.
+pub async fn explain<S: ObjectStore, K: KeyValueStore>(
+    State(state): State<AppState<S, K>>,
+    user: CurrentUser,
+    Json(report): Json<MistakeReport>,
+) -> Result<Json<MistakeExplanation>, ErrorResponse> {
+    validate_report(&report)?;
+
+    let partition = format!("explanations#{}", mistake_hash(&report));
+    let sort_key = EXPLANATION_COLUMN.to_string();
+    let records = state
+        .kv_store
+        .query_records(
+            RecordQuery::new(partition.clone()).between(sort_key.clone(), sort_key.clone()),
+            vec![EXPLANATION_COLUMN.to_string()],
+        )
+        .await?;
+    if let Some(record) = records.first()
+        && let Some(explanation) = column_json::<MistakeExplanation>(&record.columns, EXPLANATION_COLUMN)?
+    {
+        metrics::increment("explanations.cached");
+        return Ok(Json(explanation));
+    }
+
+    let account_id = &user.account.id;
+    let today = Utc::now().date_naive();
+    let limit = state.config.limits.explanations_per_day;
+    if !take_explanation(&state.kv_store, account_id, today, limit).await? {
+        metrics::increment("explanations.limited");
+        let mut response = ErrorResponse::new(
+            StatusCode::TOO_MANY_REQUESTS,
+            "explanation_limit_reached",
+            format!("At most {} new explanations a day; try again tomorrow", limit),
+        );
+        response.retry_after = today
+            .checked_add_days(Days::new(1))
+            .and_then(|tomorrow| tomorrow.and_hms_opt(0, 0, 0))
+            .and_then(|midnight| (midnight.and_utc() - Utc::now()).to_std().ok());
+        return Err(response);
+    }
+
+    let params = ContentParams::new()
+        .with_context("question", report.question.trim())
+        .with_context("answer", report.answer.trim())
+        .with_context("correct_answer", report.correct_answer.trim());
+    let explanation: MistakeExplanation = match state.generate_uncached(&descriptor(), &params).await {
+        Ok(explanation) => explanation,
+        Err(e) => {
+            return_explanation(&state.kv_store, account_id, today).await;
+            return Err(e.into());
+        }
+    };
+    state
+        .kv_store
+        .put_record(
+            partition,
+            sort_key,
+            vec![Column::new(EXPLANATION_COLUMN.to_string(), encode_json(&explanation)?)],
+            None,
+        )
+        .await?;
+    metrics::increment("explanations.generated");
+    Ok(Json(explanation))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mistakes_share_explanations_whatever_their_spacing() {
+        let report = |answer: &str, correct_answer: &str| MistakeReport {
+            question: "What is 7 x 8?".into(),
+            answer: answer.into(),
+            correct_answer: correct_answer.into(),
+        };
+        assert!(validate_report(&report("54", "56")).is_ok());
+        assert!(validate_report(&report(" 56 ", "56")).is_err());
+        assert!(validate_report(&report("", "56")).is_err());
+        assert!(validate_report(&report(&"5".repeat(MAX_TEXT_LEN + 1), "56")).is_err());
+
+        let mistake = |question: &str, answer: &str, correct_answer: &str| {
+            mistake_hash(&MistakeReport {
+                question: question.into(),
+                answer: answer.into(),
+                correct_answer: correct_answer.into(),
+            })
+        };
+        assert_eq!(mistake("What is 7 x 8?", "Fifty Four", "56"), mistake("what is  7 x 8?", "fifty four", "56"));
+        assert_ne!(mistake("What is 7 x 8?", "54", "56"), mistake("What is 7 x 8?", "55", "56"));
+        assert_ne!(mistake("Main idea?", "Cats", "Dogs"), mistake("Main idea?", "Cats", "Birds"));
+        assert_ne!(mistake("a b", "c", "d"), mistake("a", "b c", "d"));
+    }
+
+    #[tokio::test]
+    async fn limits_explanations_per_account() {
+        let kv_store = crate::keyvalue::MemoryKeyValueStore::new();
+        let today = Utc::now().date_naive();
+        assert!(take_explanation(&kv_store, "a1", today, 1).await.unwrap());
+        assert!(!take_explanation(&kv_store, "a1", today, 1).await.unwrap());
+        assert!(take_explanation(&kv_store, "a2", today, 1).await.unwrap());
+
+        return_explanation(&kv_store, "a1", today).await;
+        return_explanation(&kv_store, "a1", today).await;
+        assert!(take_explanation(&kv_store, "a1", today, 1).await.unwrap());
+    }
+}
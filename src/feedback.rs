@@ -0,0 +1,337 @@
+use axum::{
+    Json, Router,
+    extract::State,
+    http::StatusCode,
+    routing::post,
+};
+use chrono::{SecondsFormat, Utc};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::{
+    ServiceError,
+    keyvalue::{Column, KeyValueStore, encode_json},
+    metrics,
+    problem::ErrorResponse,
+    session::{SESSION_TTL, Session},
+    state::AppState,
+    storage::{ObjectStore, StoredObject},
+};
+
+/// Longest comment accepted with a rating
+const MAX_COMMENT_LEN: usize = 500;
+
+/// Down votes an object needs before it can be evicted
+const EVICT_MIN_DOWN_VOTES: i64 = 5;
+
+/// Down votes per up vote at which an object is evicted
+const EVICT_DOWN_RATIO: i64 = 3;
+
+/// Counter column of up votes
+const UP_COLUMN: &str = "up";
+
+/// Counter column of down votes
+const DOWN_COLUMN: &str = "down";
+
+/// Column of a comment record holding its JSON
+const COMMENT_COLUMN: &str = "feedback";
+
+/// Thumbs up or down
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Rating {
+    Up,
+    Down,
+}
+
+/// A rating sent for a piece of generated content
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct NewFeedback {
+    /// Key of the content object, e.g. "reading/2025-10-11-14/1a2b.json"
+    pub object_key: String,
+    pub rating: Rating,
+    pub comment: Option<String>,
+}
+
+/// A stored comment on a piece of content
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Comment {
+    pub rating: Rating,
+    pub comment: String,
+
+    /// When the comment was left, in RFC 3339 format
+    pub created_at: String,
+}
+
+/// Votes an object has received
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+pub struct ObjectRatings {
+    pub up: i64,
+    pub down: i64,
+}
+
+impl ObjectRatings {
+    /// How likely the object is to be picked, relative to an unrated one (1.0)
+    ///
+    /// Twice the smoothed share of up votes, so a few votes move it only a little:
+    /// one down vote gives 0.67, ten down and no up votes give 0.17.
+    pub fn weight(&self) -> f64 {
+        let up = self.up.max(0) as f64;
+        let down = self.down.max(0) as f64;
+        2.0 * (up + 1.0) / (up + down + 2.0)
+    }
+
+    /// Whether enough people disliked the object that it should no longer be served
+    pub fn should_evict(&self) -> bool {
+        self.down >= EVICT_MIN_DOWN_VOTES && self.down >= EVICT_DOWN_RATIO * self.up
+    }
+
+    fn from_columns(columns: &[Column]) -> Self {
+        let counter = |name: &str| {
+            columns
+                .iter()
+                .find(|column| column.name == name)
+                .and_then(Column::as_counter)
+                .unwrap_or(0)
+        };
+        Self { up: counter(UP_COLUMN), down: counter(DOWN_COLUMN) }
+    }
+}
+
+/// Votes for a set of objects, by key; objects without any are absent
+#[derive(Debug, Default, Clone)]
+pub struct Ratings(HashMap<String, ObjectRatings>);
+
+impl Ratings {
+    /// Returns an object's votes, zero if it has none
+    pub fn get(&self, key: &str) -> ObjectRatings {
+        self.0.get(key).copied().unwrap_or_default()
+    }
+
+    /// Picks one of `objects` at random, in proportion to each one's weight
+    ///
+    /// Returns `None` only if `objects` is empty.
+    pub fn weighted_pick<'a>(&self, objects: &[&'a StoredObject]) -> Option<&'a StoredObject> {
+        let weights: Vec<f64> = objects.iter().map(|o| self.get(&o.key).weight()).collect();
+        let total: f64 = weights.iter().sum();
+
+        let mut target = rand::random::<f64>() * total;
+        for (object, weight) in objects.iter().zip(&weights) {
+            if target < *weight {
+                return Some(object);
+            }
+            target -= weight;
+        }
+        // Rounding can leave a sliver past the last weight
+        objects.last().copied()
+    }
+}
+
+/// Key of the item counting an object's votes
+fn ratings_key(object_key: &str) -> String {
+    format!("feedback#{}", object_key)
+}
+
+/// Partition of the records holding an object's comments
+fn comments_partition(object_key: &str) -> String {
+    format!("feedback-comments#{}", object_key)
+}
+
+/// Key of the item recording that a session has rated an object
+fn vote_key(session_id: &str, object_key: &str) -> String {
+    format!("feedback-vote#{}#{}", session_id, object_key)
+}
+
+/// Loads the votes for several objects at once
+pub async fn ratings<K: KeyValueStore>(
+    kv_store: &K,
+    objects: &[StoredObject],
+) -> Result<Ratings, ServiceError> {
+    let keys = objects.iter().map(|object| ratings_key(&object.key)).collect();
+    let items = kv_store
+        .batch_get(keys, vec![UP_COLUMN.to_string(), DOWN_COLUMN.to_string()])
+        .await?;
+
+    Ok(Ratings(
+        objects
+            .iter()
+            .filter_map(|object| {
+                let columns = items.get(&ratings_key(&object.key))?;
+                Some((object.key.clone(), ObjectRatings::from_columns(columns)))
+            })
+            .collect(),
+    ))
+}
+
+/// Records a session's rating of an object, and its comment if any
+///
+/// # Returns
+/// * `Ok(ObjectRatings)` - The object's votes, including this one
+/// * `Err(ServiceError::Conflict)` - If the session has already rated the object
+/// * `Err(ServiceError)` - If the comment is too long or storage fails
+pub async fn record<K: KeyValueStore>(
+    kv_store: &K,
+    session_id: &str,
+    feedback: NewFeedback,
+) -> Result<ObjectRatings, ServiceError> {
+    let comment = feedback
+        .comment
+        .map(|comment| comment.trim().to_string())
+        .filter(|comment| !comment.is_empty());
+    if comment.as_ref().is_some_and(|comment| comment.chars().count() > MAX_COMMENT_LEN) {
+        return Err(ServiceError::InvalidInput(format!(
+            "comment must be at most {} characters",
+            MAX_COMMENT_LEN
+        )));
+    }
+
+    let first_vote = kv_store
+        .put_if_not_exists(
+            vote_key(session_id, &feedback.object_key),
+            vec![Column::new("rating".to_string(), encode_json(&feedback.rating)?)],
+            Some(SESSION_TTL),
+        )
+        .await?;
+    if !first_vote {
+        return Err(ServiceError::Conflict("You have already rated this".into()));
+    }
+
+    let column = match feedback.rating {
+        Rating::Up => UP_COLUMN,
+        Rating::Down => DOWN_COLUMN,
+    };
+    kv_store
+        .increment(ratings_key(&feedback.object_key), column.to_string(), 1)
+        .await?;
+
+    if let Some(comment) = comment {
+        let now = Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true);
+        let sort_key = format!("{}#{}", now, Uuid::new_v4().simple());
+        let comment = Comment { rating: feedback.rating, comment, created_at: now };
+        kv_store
+            .put_record(
+                comments_partition(&feedback.object_key),
+                sort_key,
+                vec![Column::new(COMMENT_COLUMN.to_string(), encode_json(&comment)?)],
+                None,
+            )
+            .await?;
+    }
+
+    let columns = kv_store
+        .get(
+            ratings_key(&feedback.object_key),
+            vec![UP_COLUMN.to_string(), DOWN_COLUMN.to_string()],
+        )
+        .await?;
+    Ok(ObjectRatings::from_columns(&columns))
+}
+
+/// Builds the router for the feedback endpoint
+///
+/// Routes must run inside the `session` middleware.
+pub fn router<S, K>() -> Router<AppState<S, K>>
+where
+    S: ObjectStore + 'static,
+    K: KeyValueStore + 'static,
+{
+    Router::new().route("/feedback", post(post_feedback))
+}
+
+/// Rates a piece of content, evicting it from the cache once it is rated poorly
+/// enough
+pub async fn post_feedback<S: ObjectStore, K: KeyValueStore>(
+    State(state): State<AppState<S, K>>,
+    session: Session,
+    Json(feedback): Json<NewFeedback>,
+) -> Result<(StatusCode, Json<ObjectRatings>), ErrorResponse> {
+    let ratings = rate(&state, session.id(), feedback).await?;
+    Ok((StatusCode::CREATED, Json(ratings)))
+}
+
+/// Records a session's rating of an existing piece of content, evicting it once
+/// it is rated poorly enough
+pub async fn rate<S: ObjectStore, K: KeyValueStore>(
+    state: &AppState<S, K>,
+    session_id: &str,
+    mut feedback: NewFeedback,
+) -> Result<ObjectRatings, ServiceError> {
+    feedback.object_key = feedback.object_key.trim_start_matches('/').to_string();
+    let key = feedback.object_key.clone();
+    if !state.content_types.is_content_key(&key) {
+        return Err(ServiceError::InvalidInput(format!("{:?} is not a content key", key)));
+    }
+    if !state.object_store.object_exists(&key).await? {
+        return Err(ServiceError::NotFound(format!("No content at {}", key)));
+    }
+
+    let ratings = record(&state.kv_store, session_id, feedback).await?;
+    if ratings.should_evict() {
+        match state.object_store.delete_object(&key).await {
+            Ok(()) => {
+                info!("Evicted {} after {} down votes", key, ratings.down);
+                metrics::increment("feedback.evicted");
+            }
+            Err(e) => warn!("Failed to evict down-voted {}: {}", key, e),
+        }
+    }
+
+    Ok(ratings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keyvalue::MemoryKeyValueStore;
+
+    fn feedback(rating: Rating, comment: Option<&str>) -> NewFeedback {
+        NewFeedback {
+            object_key: "reading/2025-10-11-14/a.json".to_string(),
+            rating,
+            comment: comment.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn weighs_and_evicts_by_votes() {
+        let unrated = ObjectRatings::default();
+        assert_eq!(unrated.weight(), 1.0);
+        assert!(ObjectRatings { up: 3, down: 0 }.weight() > 1.0);
+        assert!(ObjectRatings { up: 0, down: 1 }.weight() < 1.0);
+
+        assert!(!ObjectRatings { up: 0, down: 4 }.should_evict());
+        assert!(ObjectRatings { up: 1, down: 5 }.should_evict());
+        assert!(!ObjectRatings { up: 2, down: 5 }.should_evict());
+
+        let objects = [StoredObject::new("a.json", 1, None), StoredObject::new("b.json", 1, None)];
+        let refs: Vec<&StoredObject> = objects.iter().collect();
+        let ratings = Ratings(HashMap::from([("a.json".to_string(), ObjectRatings { up: 0, down: 1000 })]));
+        let picks = (0..100)
+            .filter(|_| ratings.weighted_pick(&refs).unwrap().key == "b.json")
+            .count();
+        assert!(picks > 90);
+        assert!(ratings.weighted_pick(&[]).is_none());
+    }
+
+    #[tokio::test]
+    async fn records_one_vote_per_session() {
+        let store = MemoryKeyValueStore::new();
+        let votes = record(&store, "s1", feedback(Rating::Down, Some(" Too scary "))).await.unwrap();
+        assert_eq!(votes, ObjectRatings { up: 0, down: 1 });
+
+        let again = record(&store, "s1", feedback(Rating::Up, None)).await;
+        assert!(matches!(again, Err(ServiceError::Conflict(_))));
+        let votes = record(&store, "s2", feedback(Rating::Up, None)).await.unwrap();
+        assert_eq!(votes, ObjectRatings { up: 1, down: 1 });
+
+        let long = "a".repeat(MAX_COMMENT_LEN + 1);
+        assert!(record(&store, "s3", feedback(Rating::Up, Some(&long))).await.is_err());
+
+        let objects = [StoredObject::new("reading/2025-10-11-14/a.json", 1, None)];
+        let loaded = ratings(&store, &objects).await.unwrap();
+        assert_eq!(loaded.get("reading/2025-10-11-14/a.json"), ObjectRatings { up: 1, down: 1 });
+    }
+}
@@ -0,0 +1,238 @@
+use axum::{
+    Json, Router,
+    extract::State,
+    http::StatusCode,
+    routing::post,
+};
+use chrono::{SecondsFormat, Utc};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use tracing::info;
+use uuid::Uuid;
+
+use crate::{
+    ServiceError,
+    keyvalue::{Column, KeyValueStore, RecordQuery, column_json, encode_json},
+    metrics,
+    problem::ErrorResponse,
+    session::Session,
+    state::AppState,
+    storage::{ObjectStore, StoredObject},
+};
+
+/// Longest reason accepted with a flag
+const MAX_REASON_LEN: usize = 500;
+
+/// Most open flags loaded at once
+const MAX_OPEN_FLAGS: usize = 1000;
+
+/// Partition of the records holding every open flag
+const FLAGS_PARTITION: &str = "flags";
+
+/// Column of a flag record holding its JSON
+const FLAG_COLUMN: &str = "flag";
+
+/// Column of a quarantine item holding the ID of the flag that put the object there
+const FLAG_ID_COLUMN: &str = "flag_id";
+
+/// A report that a piece of content is inappropriate, sent by a parent or child
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct NewFlag {
+    /// Key of the content object, e.g. "reading/2025-10-11-14/1a2b.json"
+    pub object_key: String,
+    pub reason: Option<String>,
+}
+
+/// An open report against a piece of content
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct Flag {
+    pub id: String,
+    pub object_key: String,
+    pub reason: Option<String>,
+
+    /// Session that sent the flag, so abuse can be traced
+    pub session_id: String,
+
+    /// When the flag was sent, in RFC 3339 format
+    pub created_at: String,
+}
+
+impl Flag {
+    fn sort_key(&self) -> String {
+        format!("{}#{}", self.created_at, self.id)
+    }
+}
+
+/// Key of the item marking an object as quarantined
+fn quarantine_key(object_key: &str) -> String {
+    format!("quarantine#{}", object_key)
+}
+
+/// Flags an object and quarantines it, so it isn't served until an admin reviews
+/// it
+pub async fn flag<K: KeyValueStore>(
+    kv_store: &K,
+    session_id: &str,
+    flag: NewFlag,
+) -> Result<Flag, ServiceError> {
+    let reason = flag
+        .reason
+        .map(|reason| reason.trim().to_string())
+        .filter(|reason| !reason.is_empty());
+    if reason.as_ref().is_some_and(|reason| reason.chars().count() > MAX_REASON_LEN) {
+        return Err(ServiceError::InvalidInput(format!(
+            "reason must be at most {} characters",
+            MAX_REASON_LEN
+        )));
+    }
+
+    let flag = Flag {
+        id: Uuid::new_v4().simple().to_string(),
+        object_key: flag.object_key,
+        reason,
+        session_id: session_id.to_string(),
+        created_at: Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+    };
+
+    kv_store
+        .put(
+            quarantine_key(&flag.object_key),
+            vec![Column::new(FLAG_ID_COLUMN.to_string(), flag.id.clone().into_bytes())],
+            None,
+        )
+        .await?;
+    kv_store
+        .put_record(
+            FLAGS_PARTITION.to_string(),
+            flag.sort_key(),
+            vec![Column::new(FLAG_COLUMN.to_string(), encode_json(&flag)?)],
+            None,
+        )
+        .await?;
+    Ok(flag)
+}
+
+/// Returns the keys of the quarantined objects among `objects`
+pub async fn quarantined<K: KeyValueStore>(
+    kv_store: &K,
+    objects: &[StoredObject],
+) -> Result<HashSet<String>, ServiceError> {
+    let keys = objects.iter().map(|object| quarantine_key(&object.key)).collect();
+    let items = kv_store.batch_get(keys, vec![FLAG_ID_COLUMN.to_string()]).await?;
+
+    Ok(objects
+        .iter()
+        .filter(|object| items.contains_key(&quarantine_key(&object.key)))
+        .map(|object| object.key.clone())
+        .collect())
+}
+
+/// Lists open flags, most recent first
+pub async fn open_flags<K: KeyValueStore>(kv_store: &K) -> Result<Vec<Flag>, ServiceError> {
+    let records = kv_store
+        .query_records(
+            RecordQuery::new(FLAGS_PARTITION).descending().limit(MAX_OPEN_FLAGS),
+            vec![FLAG_COLUMN.to_string()],
+        )
+        .await?;
+
+    records
+        .iter()
+        .filter_map(|record| column_json(&record.columns, FLAG_COLUMN).transpose())
+        .collect()
+}
+
+/// Closes every open flag on an object and lifts its quarantine
+///
+/// # Returns
+/// * `Ok(Vec<Flag>)` - The flags closed
+/// * `Err(ServiceError)` - If storage fails
+pub async fn resolve<K: KeyValueStore>(kv_store: &K, object_key: &str) -> Result<Vec<Flag>, ServiceError> {
+    let flags: Vec<Flag> = open_flags(kv_store)
+        .await?
+        .into_iter()
+        .filter(|flag| flag.object_key == object_key)
+        .collect();
+
+    for flag in &flags {
+        kv_store
+            .delete_record(FLAGS_PARTITION.to_string(), flag.sort_key())
+            .await?;
+    }
+    kv_store.delete(quarantine_key(object_key)).await?;
+    Ok(flags)
+}
+
+/// Builds the router for the flagging endpoint
+///
+/// Routes must run inside the `session` middleware.
+pub fn router<S, K>() -> Router<AppState<S, K>>
+where
+    S: ObjectStore + 'static,
+    K: KeyValueStore + 'static,
+{
+    Router::new().route("/flag", post(post_flag))
+}
+
+/// Flags a piece of content as inappropriate, taking it out of rotation
+pub async fn post_flag<S: ObjectStore, K: KeyValueStore>(
+    State(state): State<AppState<S, K>>,
+    session: Session,
+    Json(mut new_flag): Json<NewFlag>,
+) -> Result<(StatusCode, Json<Flag>), ErrorResponse> {
+    new_flag.object_key = new_flag.object_key.trim_start_matches('/').to_string();
+    let key = &new_flag.object_key;
+    if !state.content_types.is_content_key(key) {
+        return Err(ServiceError::InvalidInput(format!("{:?} is not a content key", key)).into());
+    }
+    if !state.object_store.object_exists(key).await? {
+        return Err(ServiceError::NotFound(format!("No content at {}", key)).into());
+    }
+
+    let flag = flag(&state.kv_store, session.id(), new_flag).await?;
+    info!("Quarantined {} after flag {}", flag.object_key, flag.id);
+    metrics::increment("flags.created");
+    Ok((StatusCode::CREATED, Json(flag)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keyvalue::MemoryKeyValueStore;
+
+    fn new_flag(key: &str) -> NewFlag {
+        NewFlag { object_key: key.to_string(), reason: Some(" Scary ".to_string()) }
+    }
+
+    #[tokio::test]
+    async fn quarantines_until_resolved() {
+        let store = MemoryKeyValueStore::new();
+        let objects = [
+            StoredObject::new("reading/a.json", 1, None),
+            StoredObject::new("reading/b.json", 1, None),
+        ];
+
+        let first = flag(&store, "s1", new_flag("reading/a.json")).await.unwrap();
+        assert_eq!(first.reason.as_deref(), Some("Scary"));
+        flag(&store, "s2", new_flag("reading/a.json")).await.unwrap();
+        flag(&store, "s2", new_flag("reading/b.json")).await.unwrap();
+
+        let quarantined_keys = quarantined(&store, &objects).await.unwrap();
+        assert_eq!(quarantined_keys.len(), 2);
+        assert_eq!(open_flags(&store).await.unwrap().len(), 3);
+
+        let closed = resolve(&store, "reading/a.json").await.unwrap();
+        assert_eq!(closed.len(), 2);
+        assert_eq!(
+            quarantined(&store, &objects).await.unwrap(),
+            HashSet::from(["reading/b.json".to_string()])
+        );
+        let open = open_flags(&store).await.unwrap();
+        assert_eq!(open.len(), 1);
+        assert_eq!(open[0].object_key, "reading/b.json");
+
+        let long = NewFlag { object_key: "reading/c.json".into(), reason: Some("a".repeat(MAX_REASON_LEN + 1)) };
+        assert!(flag(&store, "s1", long).await.is_err());
+    }
+}
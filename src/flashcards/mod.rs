@@ -0,0 +1,357 @@
+pub mod schedule;
+
+use axum::{
+    Json, Router,
+    extract::{Query, State},
+    routing::{get, post},
+};
+use chrono::{NaiveDate, Utc};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    ServiceError,
+    content::{self, ContentParams, ContentSchema, ContentTypeDescriptor},
+    flags,
+    keyvalue::{Column, KeyValueStore, RecordQuery, column_json, encode_json},
+    metrics,
+    problem::ErrorResponse,
+    reading::ReadingQuery,
+    session::Session,
+    state::AppState,
+    storage::ObjectStore,
+    users::CurrentUser,
+    validation::{BannedWords, MinItems, NoEmptyFields},
+};
+
+pub use schedule::Schedule;
+
+/// Storage prefix and registry identifier for flashcard decks
+pub const FLASHCARDS_PREFIX: &str = "flashcards";
+
+/// Grade used when the request doesn't specify one
+const DEFAULT_GRADE: u8 = 3;
+
+/// Fewest cards in a deck
+const MIN_CARDS: usize = 6;
+
+/// Due cards returned by a request that doesn't set a limit, and the most returned
+const DEFAULT_DUE_LIMIT: usize = 20;
+const MAX_DUE_LIMIT: usize = 100;
+
+/// Column of a card record holding its JSON
+const CARD_COLUMN: &str = "card";
+
+/// Query parameters accepted by the flashcards endpoint
+#[derive(Debug, Deserialize, Default, JsonSchema)]
+pub struct FlashcardQuery {
+    /// Grade level the cards should target
+    pub grade: Option<u8>,
+
+    /// Subject of the cards (e.g., "planets")
+    pub topic: Option<String>,
+}
+
+impl FlashcardQuery {
+    /// Validates the query and converts it into content parameters, accepting the
+    /// same grades and topics as reading passages
+    pub fn into_params(self) -> Result<ContentParams, ServiceError> {
+        let grade = Some(self.grade.unwrap_or(DEFAULT_GRADE));
+        ReadingQuery { grade, topic: self.topic, ..Default::default() }.into_params()
+    }
+}
+
+/// One card: a prompt on the front and its answer on the back
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, JsonSchema)]
+pub struct Flashcard {
+    pub front: String,
+    pub back: String,
+}
+
+/// A deck of flashcards on one subject
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+pub struct FlashcardDeck {
+    /// ID of the stored deck, used to review its cards
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schemars(skip_deserializing)]
+    pub id: Option<String>,
+
+    pub title: String,
+    pub cards: Vec<Flashcard>,
+}
+
+/// A card a child has reviewed, with when to review it next
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct ScheduledCard {
+    pub child: String,
+
+    /// ID of the deck the card is from
+    pub deck: String,
+
+    /// Position of the card in its deck, from 0
+    pub card: usize,
+
+    pub front: String,
+    pub back: String,
+
+    #[serde(flatten)]
+    pub schedule: Schedule,
+}
+
+/// A child's review of a card, as sent by the client
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct CardReview {
+    /// ID of the child profile reviewing the card; the selected profile if omitted
+    #[serde(default)]
+    pub child: Option<String>,
+
+    /// ID of the deck the card is from
+    pub deck: String,
+
+    /// Position of the card in its deck, from 0
+    pub card: usize,
+
+    /// How well the child remembered the card, from 0 (not at all) to 5 (perfectly)
+    pub quality: u8,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct DueQuery {
+    /// ID of the child profile; the selected profile if omitted
+    pub child: Option<String>,
+
+    /// Most cards to return
+    pub limit: Option<usize>,
+}
+
+/// The cards a child should review today
+#[derive(Debug, Clone, PartialEq, Serialize, JsonSchema)]
+pub struct DueCards {
+    pub child: String,
+
+    /// Number of cards due, which may be more than are returned
+    pub due: usize,
+
+    /// Due cards, longest overdue first
+    pub cards: Vec<ScheduledCard>,
+}
+
+/// Returns the content type descriptor for flashcard decks
+pub fn descriptor() -> ContentTypeDescriptor {
+    ContentTypeDescriptor::new(
+        FLASHCARDS_PREFIX,
+        "flashcards",
+        ContentSchema::for_type::<FlashcardDeck>("FlashcardDeck", "A deck of flashcards on one subject"),
+    )
+    .with_validator(MinItems::new("cards", MIN_CARDS))
+    .with_validator(NoEmptyFields)
+    .with_validator(BannedWords::default())
+    .with_id_field("id")
+    .with_default_params(ContentParams::new().with("grade", DEFAULT_GRADE))
+}
+
+/// Partition key of a child's card records
+fn cards_key(child: &str) -> String {
+    format!("flashcards#{}", child)
+}
+
+/// Sort key of a card record; deck IDs are URL-safe base64, so never contain '#'
+fn card_sort_key(deck: &str, card: usize) -> String {
+    format!("{}#{}", deck, card)
+}
+
+/// Returns the child a request is for, the selected profile by default, checking
+/// that it belongs to the account
+fn requested_child(user: &CurrentUser, child: Option<String>) -> Result<String, ServiceError> {
+    let child = child
+        .or_else(|| user.profile.as_ref().map(|profile| profile.id.clone()))
+        .ok_or_else(|| ServiceError::InvalidInput("child is required when no profile is selected".into()))?;
+    if user.account.profile(&child).is_none() {
+        return Err(ServiceError::NotFound(format!("Unknown profile: {}", child)));
+    }
+    Ok(child)
+}
+
+/// Loads a card from a stored deck; quarantined decks aren't found
+async fn stored_card<S: ObjectStore, K: KeyValueStore>(
+    state: &AppState<S, K>,
+    deck_id: &str,
+    card: usize,
+) -> Result<Flashcard, ServiceError> {
+    let not_found = || ServiceError::NotFound("No such card".to_string());
+    let object_key = content::object_key_for_id(deck_id)
+        .filter(|key| key.starts_with(&format!("{}/", FLASHCARDS_PREFIX)))
+        .ok_or_else(not_found)?;
+    let object = state.object_store.head_object(&object_key).await?.ok_or_else(not_found)?;
+    if !flags::quarantined(&state.kv_store, std::slice::from_ref(&object)).await?.is_empty() {
+        return Err(not_found());
+    }
+
+    let data = state.object_store.get_object(&object_key).await?;
+    let deck: FlashcardDeck = serde_json::from_slice(&data)?;
+    deck.cards.into_iter().nth(card).ok_or_else(not_found)
+}
+
+/// Records a child's review of a card and schedules its next review
+pub async fn review<K: KeyValueStore>(
+    kv_store: &K,
+    child: &str,
+    deck: &str,
+    card: usize,
+    flashcard: Flashcard,
+    quality: u8,
+    today: NaiveDate,
+) -> Result<ScheduledCard, ServiceError> {
+    if quality > schedule::MAX_QUALITY {
+        return Err(ServiceError::InvalidInput(format!(
+            "quality must be between 0 and {}",
+            schedule::MAX_QUALITY
+        )));
+    }
+
+    let key = cards_key(child);
+    let sort_key = card_sort_key(deck, card);
+    let records = kv_store
+        .query_records(
+            RecordQuery::new(key.clone()).between(sort_key.clone(), sort_key.clone()),
+            vec![CARD_COLUMN.to_string()],
+        )
+        .await?;
+    let previous: Option<ScheduledCard> = match records.first() {
+        Some(record) => column_json(&record.columns, CARD_COLUMN)?,
+        None => None,
+    };
+
+    let scheduled = ScheduledCard {
+        child: child.to_string(),
+        deck: deck.to_string(),
+        card,
+        front: flashcard.front,
+        back: flashcard.back,
+        schedule: Schedule::after_review(previous.as_ref().map(|card| &card.schedule), quality, today),
+    };
+    kv_store
+        .put_record(key, sort_key, vec![Column::new(CARD_COLUMN.to_string(), encode_json(&scheduled)?)], None)
+        .await?;
+    Ok(scheduled)
+}
+
+/// Returns the cards a child should review on `today`, up to `limit`, longest
+/// overdue first
+pub async fn due_cards<K: KeyValueStore>(
+    kv_store: &K,
+    child: &str,
+    today: NaiveDate,
+    limit: usize,
+) -> Result<DueCards, ServiceError> {
+    let records = kv_store
+        .query_records(RecordQuery::new(cards_key(child)), vec![CARD_COLUMN.to_string()])
+        .await?;
+    let mut cards = Vec::new();
+    for record in &records {
+        if let Some(card) = column_json::<ScheduledCard>(&record.columns, CARD_COLUMN)?
+            && card.schedule.is_due(today)
+        {
+            cards.push(card);
+        }
+    }
+    cards.sort_by(|a, b| a.schedule.due.cmp(&b.schedule.due));
+
+    let due = cards.len();
+    cards.truncate(limit);
+    Ok(DueCards { child: child.to_string(), due, cards })
+}
+
+/// Builds the router for flashcard review endpoints
+///
+/// Routes must run inside the `session` and `current_user` middleware.
+pub fn router<S, K>() -> Router<AppState<S, K>>
+where
+    S: ObjectStore + 'static,
+    K: KeyValueStore + 'static,
+{
+    Router::new()
+        .route("/flashcards/due", get(get_due))
+        .route("/flashcards/review", post(post_review))
+}
+
+/// Serves a cached flashcard deck the session hasn't seen, or generates a new one
+pub async fn flashcards<S: ObjectStore, K: KeyValueStore>(
+    State(state): State<AppState<S, K>>,
+    Query(query): Query<FlashcardQuery>,
+    session: Session,
+) -> Result<Json<FlashcardDeck>, ErrorResponse> {
+    let params = query.into_params()?;
+
+    let descriptor = state
+        .content_types
+        .get(FLASHCARDS_PREFIX)
+        .ok_or_else(|| ServiceError::ConfigError(FLASHCARDS_PREFIX.into()))?;
+
+    let session_id = session.id();
+    let params = state
+        .with_prompt_variant(descriptor, params, Some(session_id))
+        .await?;
+
+    let deck: FlashcardDeck = state
+        .get_or_generate(descriptor, &params, Some(session_id))
+        .await?;
+
+    Ok(Json(deck))
+}
+
+/// Returns the cards one of the logged-in account's children should review today
+pub async fn get_due<S: ObjectStore, K: KeyValueStore>(
+    State(state): State<AppState<S, K>>,
+    user: CurrentUser,
+    Query(query): Query<DueQuery>,
+) -> Result<Json<DueCards>, ErrorResponse> {
+    let child = requested_child(&user, query.child)?;
+    let limit = query.limit.unwrap_or(DEFAULT_DUE_LIMIT).min(MAX_DUE_LIMIT);
+    let today = Utc::now().date_naive();
+    Ok(Json(due_cards(&state.kv_store, &child, today, limit).await?))
+}
+
+/// Records how well one of the logged-in account's children remembered a card,
+/// returning when it's next due
+pub async fn post_review<S: ObjectStore, K: KeyValueStore>(
+    State(state): State<AppState<S, K>>,
+    user: CurrentUser,
+    Json(body): Json<CardReview>,
+) -> Result<Json<ScheduledCard>, ErrorResponse> {
+    let child = requested_child(&user, body.child)?;
+    let flashcard = stored_card(&state, &body.deck, body.card).await?;
+    let today = Utc::now().date_naive();
+    let scheduled = review(&state.kv_store, &child, &body.deck, body.card, flashcard, body.quality, today).await?;
+    metrics::increment("flashcards.reviewed");
+    Ok(Json(scheduled))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keyvalue::MemoryKeyValueStore;
+
+    #[tokio::test]
+    async fn lists_reviewed_cards_once_they_fall_due() {
+        let kv_store = MemoryKeyValueStore::new();
+        let day = |d| NaiveDate::from_ymd_opt(2025, 10, d).unwrap();
+        let card = |front: &str| Flashcard { front: front.to_string(), back: "answer".to_string() };
+
+        review(&kv_store, "c1", "deck1", 0, card("Mars"), 5, day(1)).await.unwrap();
+        review(&kv_store, "c1", "deck1", 1, card("Venus"), 1, day(1)).await.unwrap();
+        review(&kv_store, "c2", "deck1", 0, card("Mars"), 1, day(1)).await.unwrap();
+        assert!(review(&kv_store, "c1", "deck1", 2, card("Earth"), 6, day(1)).await.is_err());
+        assert_eq!(due_cards(&kv_store, "c1", day(1), 10).await.unwrap().due, 0);
+
+        let due = due_cards(&kv_store, "c1", day(2), 1).await.unwrap();
+        assert_eq!(due.due, 2);
+        assert_eq!(due.cards.len(), 1);
+
+        // Reviewing a card again builds on its schedule
+        let again = review(&kv_store, "c1", "deck1", 0, card("Mars"), 5, day(2)).await.unwrap();
+        assert_eq!((again.schedule.repetitions, again.schedule.interval_days), (2, 6));
+        let due = due_cards(&kv_store, "c1", day(2), 10).await.unwrap();
+        assert_eq!(due.cards.iter().map(|c| c.front.as_str()).collect::<Vec<_>>(), ["Venus"]);
+    }
+}
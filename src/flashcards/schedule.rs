@@ -0,0 +1,116 @@
+use chrono::{Days, NaiveDate};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Ease of a card that hasn't been reviewed
+const INITIAL_EASE: f64 = 2.5;
+
+/// Lowest ease a card can fall to, so hard cards still come round less often
+const MIN_EASE: f64 = 1.3;
+
+/// Longest wait between reviews, in days
+const MAX_INTERVAL_DAYS: u32 = 365;
+
+/// Lowest quality that counts as remembering the card
+const PASSING_QUALITY: u8 = 3;
+
+/// Highest review quality: a perfect, instant answer
+pub const MAX_QUALITY: u8 = 5;
+
+/// When a card is next reviewed, as worked out by SM-2
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct Schedule {
+    /// Reviews in a row remembered, with quality 3 or better
+    pub repetitions: u32,
+
+    /// Days from the last review to the next
+    pub interval_days: u32,
+
+    /// How much the interval grows with each review remembered
+    pub ease: f64,
+
+    /// Day the card is next due, as YYYY-MM-DD in UTC
+    pub due: String,
+
+    /// Day the card was last reviewed, as YYYY-MM-DD in UTC
+    pub last_reviewed: String,
+}
+
+fn format_date(date: NaiveDate) -> String {
+    date.format("%Y-%m-%d").to_string()
+}
+
+impl Schedule {
+    /// Returns the schedule after a review on `today` with `quality` from 0 to
+    /// `MAX_QUALITY`, given the card's schedule before it (`None` for a new card)
+    ///
+    /// Remembered cards come back after 1 day, then 6, then the last interval
+    /// times the ease; forgotten ones start again at 1 day. The ease rises after
+    /// easy reviews and falls after hard ones.
+    pub fn after_review(previous: Option<&Schedule>, quality: u8, today: NaiveDate) -> Schedule {
+        let quality = quality.min(MAX_QUALITY);
+        let (repetitions, interval_days, ease) = match previous {
+            Some(schedule) => (schedule.repetitions, schedule.interval_days, schedule.ease),
+            None => (0, 0, INITIAL_EASE),
+        };
+
+        let (repetitions, interval_days) = if quality < PASSING_QUALITY {
+            (0, 1)
+        } else {
+            let interval = match repetitions {
+                0 => 1,
+                1 => 6,
+                _ => (f64::from(interval_days) * ease).round() as u32,
+            };
+            (repetitions + 1, interval.clamp(1, MAX_INTERVAL_DAYS))
+        };
+
+        let shortfall = f64::from(MAX_QUALITY - quality);
+        let ease = (ease + 0.1 - shortfall * (0.08 + shortfall * 0.02)).max(MIN_EASE);
+        let due = today.checked_add_days(Days::new(interval_days.into())).unwrap_or(today);
+
+        Schedule {
+            repetitions,
+            interval_days,
+            ease: (ease * 100.0).round() / 100.0,
+            due: format_date(due),
+            last_reviewed: format_date(today),
+        }
+    }
+
+    /// Whether the card should be reviewed on `today`
+    pub fn is_due(&self, today: NaiveDate) -> bool {
+        self.due <= format_date(today)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spaces_out_remembered_cards_and_resets_forgotten_ones() {
+        let day = |d| NaiveDate::from_ymd_opt(2025, 10, d).unwrap();
+
+        let first = Schedule::after_review(None, 4, day(1));
+        assert_eq!((first.repetitions, first.interval_days, first.ease), (1, 1, 2.5));
+        assert_eq!(first.due, "2025-10-02");
+        assert!(!first.is_due(day(1)) && first.is_due(day(2)));
+
+        let second = Schedule::after_review(Some(&first), 5, day(2));
+        assert_eq!((second.repetitions, second.interval_days, second.ease), (2, 6, 2.6));
+        let third = Schedule::after_review(Some(&second), 3, day(8));
+        assert_eq!((third.repetitions, third.interval_days, third.ease), (3, 16, 2.46));
+        assert_eq!(third.due, "2025-10-24");
+
+        let forgotten = Schedule::after_review(Some(&third), 1, day(24));
+        assert_eq!((forgotten.repetitions, forgotten.interval_days), (0, 1));
+        assert!(forgotten.ease < third.ease);
+
+        let mut hard = Schedule::after_review(None, 0, day(1));
+        for _ in 0..10 {
+            hard = Schedule::after_review(Some(&hard), 0, day(1));
+        }
+        assert_eq!(hard.ease, MIN_EASE);
+    }
+}
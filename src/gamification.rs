@@ -0,0 +1,340 @@
+use axum::{
+    Json, Router,
+    extract::{Query, State},
+    routing::get,
+};
+use chrono::{Days, NaiveDate, Utc};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    ServiceError,
+    keyvalue::{Column, KeyValueStore, TypedKv},
+    problem::ErrorResponse,
+    progress::ActivityResult,
+    reading::READING_PREFIX,
+    state::AppState,
+    storage::ObjectStore,
+    users::CurrentUser,
+};
+
+/// Points for completing any activity
+const ACTIVITY_POINTS: i64 = 10;
+
+/// Times a streak update is retried when another write wins the race
+const MAX_UPDATE_ATTEMPTS: usize = 3;
+
+/// Column of the streak item holding its JSON
+const STREAK_COLUMN: &str = "streak";
+
+/// Counters kept for each child
+const COUNTERS: [&str; 4] = ["points", "activities", "stories", "perfect_scores"];
+
+/// A badge and the counter threshold that earns it
+struct BadgeRule {
+    id: &'static str,
+    name: &'static str,
+    counter: &'static str,
+    threshold: i64,
+}
+
+/// Every badge, in the order they're shown
+const BADGES: [BadgeRule; 3] = [
+    BadgeRule { id: "first_story", name: "First story", counter: "stories", threshold: 1 },
+    BadgeRule { id: "ten_stories", name: "10 stories", counter: "stories", threshold: 10 },
+    BadgeRule { id: "perfect_quiz", name: "Perfect quiz", counter: "perfect_scores", threshold: 1 },
+];
+
+/// Consecutive days on which a child completed at least one activity
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Streak {
+    /// Days in the streak ending on `last_day`
+    pub current: u32,
+
+    /// Longest streak ever reached
+    pub longest: u32,
+
+    /// Last day with an activity, as YYYY-MM-DD in UTC
+    pub last_day: Option<String>,
+}
+
+impl Streak {
+    fn last_day(&self) -> Option<NaiveDate> {
+        NaiveDate::parse_from_str(self.last_day.as_deref()?, "%Y-%m-%d").ok()
+    }
+
+    /// Returns the streak after an activity on `day`
+    ///
+    /// Another activity the same day leaves it unchanged; one the next day extends
+    /// it; after a gap it starts again at 1.
+    pub fn advance(&self, day: NaiveDate) -> Streak {
+        let last_day = self.last_day();
+        let current = match last_day {
+            Some(last) if last >= day => return self.clone(),
+            Some(last) if last.checked_add_days(Days::new(1)) == Some(day) => self.current + 1,
+            _ => 1,
+        };
+
+        Streak {
+            current,
+            longest: self.longest.max(current),
+            last_day: Some(day.format("%Y-%m-%d").to_string()),
+        }
+    }
+
+    /// Returns the streak as of `today`: broken if the last activity was before
+    /// yesterday
+    pub fn as_of(&self, today: NaiveDate) -> u32 {
+        match self.last_day() {
+            Some(last) if last.checked_add_days(Days::new(1)).is_some_and(|next| next >= today) => {
+                self.current
+            }
+            _ => 0,
+        }
+    }
+}
+
+/// A badge and whether the child has earned it
+#[derive(Debug, Clone, PartialEq, Serialize, JsonSchema)]
+pub struct Badge {
+    pub id: &'static str,
+    pub name: &'static str,
+    pub earned: bool,
+}
+
+/// A child's points, streaks, and badges
+#[derive(Debug, Clone, PartialEq, Serialize, JsonSchema)]
+pub struct Achievements {
+    pub child: String,
+    pub points: i64,
+
+    /// Activities completed
+    pub activities: i64,
+
+    /// Consecutive days with an activity, up to today; 0 if broken
+    pub current_streak: u32,
+    pub longest_streak: u32,
+    pub badges: Vec<Badge>,
+}
+
+/// Key of the item holding a child's counters
+fn counters_key(child: &str) -> String {
+    format!("achievements#{}", child)
+}
+
+/// Key of the item holding a child's streak
+fn streak_key(child: &str) -> String {
+    format!("streak#{}", child)
+}
+
+/// Points for an activity: a flat amount plus one per 10% scored
+pub fn points_for(score: u8) -> i64 {
+    ACTIVITY_POINTS + i64::from(score / 10)
+}
+
+/// Badges earned given a child's counters
+fn badges(counter: impl Fn(&str) -> i64) -> Vec<Badge> {
+    BADGES
+        .iter()
+        .map(|rule| Badge {
+            id: rule.id,
+            name: rule.name,
+            earned: counter(rule.counter) >= rule.threshold,
+        })
+        .collect()
+}
+
+/// Adds a completed activity to a child's points, counters, and streak
+///
+/// Counters are incremented in the store, so concurrent activities all count.
+/// Returns the badges earned by this activity.
+pub async fn record_activity<K: KeyValueStore>(
+    kv_store: &K,
+    activity: &ActivityResult,
+    day: NaiveDate,
+) -> Result<Vec<&'static str>, ServiceError> {
+    let key = counters_key(&activity.child);
+    let is_story = activity.activity == READING_PREFIX;
+    let deltas = [
+        ("points", points_for(activity.score)),
+        ("activities", 1),
+        ("stories", i64::from(is_story)),
+        ("perfect_scores", i64::from(activity.score == 100)),
+    ];
+
+    let mut newly_earned = Vec::new();
+    for (counter, delta) in deltas {
+        if delta == 0 {
+            continue;
+        }
+        let value = kv_store
+            .increment(key.clone(), counter.to_string(), delta)
+            .await?;
+        // A badge is new if this increment crossed its threshold
+        newly_earned.extend(
+            BADGES
+                .iter()
+                .filter(|rule| {
+                    rule.counter == counter
+                        && value >= rule.threshold
+                        && value - delta < rule.threshold
+                })
+                .map(|rule| rule.id),
+        );
+    }
+
+    update_streak(kv_store, &activity.child, day).await?;
+    Ok(newly_earned)
+}
+
+/// Extends a child's streak with an activity on `day`
+async fn update_streak<K: KeyValueStore>(
+    kv_store: &K,
+    child: &str,
+    day: NaiveDate,
+) -> Result<Streak, ServiceError> {
+    let key = streak_key(child);
+
+    for _ in 0..MAX_UPDATE_ATTEMPTS {
+        let (version, streak) = kv_store.get_json_versioned::<Streak>(&key, STREAK_COLUMN).await?;
+        let streak = streak.unwrap_or_default();
+        let next = streak.advance(day);
+        if next == streak {
+            return Ok(next);
+        }
+        if kv_store
+            .put_json_if_version(&key, STREAK_COLUMN, &next, version)
+            .await?
+        {
+            return Ok(next);
+        }
+    }
+
+    Err(ServiceError::Conflict(
+        "The streak was changed by another request; try again".into(),
+    ))
+}
+
+/// Returns a child's achievements as of `today`
+pub async fn achievements<K: KeyValueStore>(
+    kv_store: &K,
+    child: &str,
+    today: NaiveDate,
+) -> Result<Achievements, ServiceError> {
+    let columns = kv_store
+        .get(counters_key(child), COUNTERS.iter().map(|c| c.to_string()).collect())
+        .await?;
+    let counter = |name: &str| {
+        columns
+            .iter()
+            .find(|column| column.name == name)
+            .and_then(Column::as_counter)
+            .unwrap_or(0)
+    };
+    let streak: Streak = kv_store
+        .get_json(&streak_key(child), STREAK_COLUMN)
+        .await?
+        .unwrap_or_default();
+
+    Ok(Achievements {
+        child: child.to_string(),
+        points: counter("points"),
+        activities: counter("activities"),
+        current_streak: streak.as_of(today),
+        longest_streak: streak.longest,
+        badges: badges(counter),
+    })
+}
+
+/// Builds the router for the logged-in user's achievements
+///
+/// Routes must run inside the `session` and `current_user` middleware.
+pub fn router<S, K>() -> Router<AppState<S, K>>
+where
+    S: ObjectStore + 'static,
+    K: KeyValueStore + 'static,
+{
+    Router::new().route("/me/achievements", get(get_achievements))
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct AchievementsQuery {
+    /// ID of the child profile; the selected profile if omitted
+    pub child: Option<String>,
+}
+
+/// Returns the achievements of one of the logged-in account's children
+pub async fn get_achievements<S: ObjectStore, K: KeyValueStore>(
+    State(state): State<AppState<S, K>>,
+    user: CurrentUser,
+    Query(query): Query<AchievementsQuery>,
+) -> Result<Json<Achievements>, ErrorResponse> {
+    let child = query
+        .child
+        .or_else(|| user.profile.as_ref().map(|profile| profile.id.clone()))
+        .ok_or_else(|| ServiceError::InvalidInput("child is required when no profile is selected".into()))?;
+    if user.account.profile(&child).is_none() {
+        return Err(ServiceError::NotFound(format!("Unknown profile: {}", child)).into());
+    }
+
+    let today = Utc::now().date_naive();
+    Ok(Json(achievements(&state.kv_store, &child, today).await?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{keyvalue::MemoryKeyValueStore, progress::Skill};
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 10, day).unwrap()
+    }
+
+    fn activity(name: &str, score: u8) -> ActivityResult {
+        ActivityResult {
+            id: "a".to_string(),
+            child: "c1".to_string(),
+            activity: name.to_string(),
+            content_key: None,
+            skill: Skill::Comprehension,
+            score,
+            completed_at: "2025-10-01T00:00:00.000Z".to_string(),
+        }
+    }
+
+    #[test]
+    fn counts_consecutive_days() {
+        let streak = [1, 1, 2, 3].into_iter().fold(Streak::default(), |s, d| s.advance(date(d)));
+        assert_eq!((streak.current, streak.longest), (3, 3));
+        assert_eq!(streak.as_of(date(4)), 3);
+        assert_eq!(streak.as_of(date(5)), 0);
+
+        let streak = streak.advance(date(6));
+        assert_eq!((streak.current, streak.longest), (1, 3));
+    }
+
+    #[tokio::test]
+    async fn awards_points_and_badges() {
+        let store = MemoryKeyValueStore::new();
+
+        let earned = record_activity(&store, &activity("reading", 100), date(1)).await.unwrap();
+        assert_eq!(earned, vec!["first_story", "perfect_quiz"]);
+
+        for _ in 0..8 {
+            record_activity(&store, &activity("reading", 50), date(2)).await.unwrap();
+        }
+        record_activity(&store, &activity("math", 50), date(2)).await.unwrap();
+        let earned = record_activity(&store, &activity("reading", 50), date(2)).await.unwrap();
+        assert_eq!(earned, vec!["ten_stories"]);
+
+        let earned = achievements(&store, "c1", date(3)).await.unwrap();
+        assert_eq!(earned.activities, 11);
+        assert_eq!(earned.points, points_for(100) + 10 * points_for(50));
+        assert_eq!(earned.current_streak, 2);
+        assert!(earned.badges.iter().all(|badge| badge.earned));
+
+        let other = achievements(&store, "c2", date(3)).await.unwrap();
+        assert_eq!(other.points, 0);
+        assert!(other.badges.iter().all(|badge| !badge.earned));
+    }
+}
@@ -0,0 +1,134 @@
+use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, Utc};
+use std::collections::BTreeSet;
+use tracing::info;
+
+use crate::{
+    ServiceError, cache_policy::CacheWindow, content::ContentTypeRegistry, dedup, illustration, metrics, narration,
+    storage::ObjectStore,
+};
+
+/// Summary of a garbage collection run
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GcReport {
+    /// Expired window folders found (and deleted, unless a dry run)
+    pub folders: usize,
+
+    /// Objects in those folders
+    pub objects: usize,
+}
+
+/// Parses a window folder name back into the window's start and length
+fn parse_window(segment: &str) -> Option<(DateTime<Utc>, Duration)> {
+    if let Ok(start) = NaiveDateTime::parse_from_str(&format!("{}:00", segment), "%Y-%m-%d-%H:%M") {
+        return Some((start.and_utc(), CacheWindow::Hourly.duration()));
+    }
+
+    NaiveDate::parse_from_str(segment, "%Y-%m-%d")
+        .ok()
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+        .map(|start| (start.and_utc(), CacheWindow::Daily.duration()))
+}
+
+/// Returns the window folder containing a cached object if the window ended before
+/// `cutoff`
+///
+/// Cached object keys look like `{prefix}/{partition}{window}/{guid}.json`, so the
+/// window is the last folder in the key.
+fn expired_folder(key: &str, cutoff: DateTime<Utc>) -> Option<String> {
+    let (folder, _file) = key.rsplit_once('/')?;
+    let window = folder.rsplit('/').next()?;
+    let (start, length) = parse_window(window)?;
+
+    (start + length < cutoff).then(|| format!("{}/", folder))
+}
+
+/// Deletes cached content older than the retention period, with its embeddings,
+/// narrations, and illustrations
+///
+/// # Arguments
+/// * `object_store` - The store holding cached content
+/// * `content_types` - The content types whose caches to clean up
+/// * `retention` - How long content is kept after its window ends
+/// * `dry_run` - If true, only report what would be deleted
+///
+/// # Returns
+/// * `Ok(GcReport)` - What was (or would be) deleted
+/// * `Err(ServiceError)` - If listing or deletion fails
+pub async fn collect_garbage<S: ObjectStore>(
+    object_store: &S,
+    content_types: &ContentTypeRegistry,
+    retention: Duration,
+    dry_run: bool,
+) -> Result<GcReport, ServiceError> {
+    let cutoff = Utc::now() - retention;
+    let mut report = GcReport::default();
+
+    for descriptor in content_types.iter() {
+        let objects = object_store
+            .list_objects(&format!("{}/", descriptor.prefix))
+            .await?;
+
+        let folders: BTreeSet<String> = objects
+            .iter()
+            .filter_map(|obj| expired_folder(&obj.key, cutoff))
+            .collect();
+
+        for folder in folders {
+            report.folders += 1;
+
+            if dry_run {
+                let count = objects
+                    .iter()
+                    .filter(|o| o.key.starts_with(&folder))
+                    .count();
+                info!("[dry run] Would delete {} objects under {}", count, folder);
+                report.objects += count;
+            } else {
+                let count = object_store.delete_objects_with_prefix(&folder).await?;
+                info!("Deleted {} objects under {}", count, folder);
+                object_store
+                    .delete_objects_with_prefix(&dedup::embedding_key(&folder))
+                    .await?;
+                object_store
+                    .delete_objects_with_prefix(&format!("{}{}", narration::NARRATION_PREFIX, folder))
+                    .await?;
+                object_store
+                    .delete_objects_with_prefix(&format!("{}{}", illustration::ILLUSTRATIONS_PREFIX, folder))
+                    .await?;
+                report.objects += count;
+            }
+        }
+    }
+
+    if !dry_run {
+        metrics::add("gc.objects_deleted", report.objects as u64);
+    }
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_expired_folder() {
+        let cutoff = Utc.with_ymd_and_hms(2025, 10, 11, 14, 0, 0).unwrap();
+
+        assert_eq!(
+            expired_folder("reading/grade-3/2025-10-11-12/abc.json", cutoff),
+            Some("reading/grade-3/2025-10-11-12/".to_string())
+        );
+        assert_eq!(
+            expired_folder("reading/2025-10-11-13/abc.json", cutoff),
+            None
+        );
+        assert_eq!(
+            expired_folder("reading/2025-10-10/abc.json", cutoff),
+            Some("reading/2025-10-10/".to_string())
+        );
+        assert_eq!(expired_folder("reading/2025-10-11/abc.json", cutoff), None);
+        assert_eq!(expired_folder("reading/topic-space/abc.json", cutoff), None);
+    }
+}
@@ -0,0 +1,169 @@
+use axum::{
+    Json,
+    extract::{Query, State},
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    ServiceError,
+    content::{ContentParams, ContentSchema, ContentTypeDescriptor},
+    keyvalue::KeyValueStore,
+    problem::ErrorResponse,
+    reading::ReadingQuery,
+    session::Session,
+    state::AppState,
+    storage::ObjectStore,
+    validation::{BannedWords, ContentValidator, MinItems, NoEmptyFields},
+};
+
+/// Storage prefix and registry identifier for grammar exercises
+pub const GRAMMAR_PREFIX: &str = "grammar";
+
+/// Grade used when the request doesn't specify one
+const DEFAULT_GRADE: u8 = 3;
+
+/// Fewest sentences in an exercise
+const MIN_SENTENCES: usize = 5;
+
+/// Query parameters accepted by the grammar exercise endpoint
+#[derive(Debug, Deserialize, Default, JsonSchema)]
+pub struct GrammarQuery {
+    /// Grade level the sentences should target
+    pub grade: Option<u8>,
+
+    /// Subject of the sentences (e.g., "the seaside")
+    pub topic: Option<String>,
+}
+
+impl GrammarQuery {
+    /// Validates the query and converts it into content parameters, accepting the
+    /// same grades and topics as reading passages
+    pub fn into_params(self) -> Result<ContentParams, ServiceError> {
+        let grade = Some(self.grade.unwrap_or(DEFAULT_GRADE));
+        ReadingQuery { grade, topic: self.topic, ..Default::default() }.into_params()
+    }
+}
+
+/// A sentence with one grammar mistake for students to find and correct
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+pub struct GrammarSentence {
+    /// The sentence as written, with the mistake
+    pub original: String,
+
+    /// The mistaken word or words, exactly as they appear in `original`
+    pub error: String,
+
+    /// The sentence with the mistake corrected
+    pub corrected: String,
+
+    /// Why the correction is right, in words a student understands
+    pub explanation: String,
+}
+
+/// Sentences with mistakes to find and correct
+#[derive(Serialize, Deserialize, Clone, JsonSchema)]
+pub struct GrammarExercise {
+    pub title: String,
+    pub sentences: Vec<GrammarSentence>,
+}
+
+/// Collapses runs of whitespace, so corrections that only reflow a sentence
+/// don't count as changes
+fn normalized(sentence: &str) -> String {
+    sentence.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Requires every sentence's correction to actually change it, and its error to
+/// be part of it
+#[derive(Debug, Clone, Default)]
+pub struct CorrectionsDiffer;
+
+impl ContentValidator for CorrectionsDiffer {
+    fn validate(&self, content: &Value, _params: &ContentParams) -> Result<(), String> {
+        let sentences: Vec<GrammarSentence> = content
+            .get("sentences")
+            .cloned()
+            .and_then(|sentences| serde_json::from_value(sentences).ok())
+            .ok_or_else(|| "missing array field \"sentences\"".to_string())?;
+
+        for (i, sentence) in sentences.iter().enumerate() {
+            if normalized(&sentence.original) == normalized(&sentence.corrected) {
+                return Err(format!("sentence {} is corrected to itself", i + 1));
+            }
+            if !sentence.original.contains(sentence.error.trim()) {
+                return Err(format!("sentence {} doesn't contain its error {:?}", i + 1, sentence.error));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Returns the content type descriptor for grammar exercises
+pub fn descriptor() -> ContentTypeDescriptor {
+    ContentTypeDescriptor::new(
+        GRAMMAR_PREFIX,
+        "grammar_exercise",
+        ContentSchema::for_type::<GrammarExercise>(
+            "GrammarExercise",
+            "Sentences with grammar mistakes, their corrections, and explanations",
+        ),
+    )
+    .with_validator(MinItems::new("sentences", MIN_SENTENCES))
+    .with_validator(CorrectionsDiffer)
+    .with_validator(NoEmptyFields)
+    .with_validator(BannedWords::default())
+    .with_default_params(ContentParams::new().with("grade", DEFAULT_GRADE))
+}
+
+/// Serves a cached grammar exercise the session hasn't done, or generates a new one
+pub async fn grammar_exercises<S: ObjectStore, K: KeyValueStore>(
+    State(state): State<AppState<S, K>>,
+    Query(query): Query<GrammarQuery>,
+    session: Session,
+) -> Result<Json<GrammarExercise>, ErrorResponse> {
+    let params = query.into_params()?;
+
+    let descriptor = state
+        .content_types
+        .get(GRAMMAR_PREFIX)
+        .ok_or_else(|| ServiceError::ConfigError(GRAMMAR_PREFIX.into()))?;
+
+    let session_id = session.id();
+    let params = state
+        .with_prompt_variant(descriptor, params, Some(session_id))
+        .await?;
+
+    let exercise: GrammarExercise = state
+        .get_or_generate(descriptor, &params, Some(session_id))
+        .await?;
+
+    Ok(Json(exercise))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn rejects_corrections_that_change_nothing() {
+        let exercise = |corrected: &str, error: &str| {
+            json!({
+                "title": "Fix It",
+                "sentences": [{
+                    "original": "The dogs runs  fast.",
+                    "error": error,
+                    "corrected": corrected,
+                    "explanation": "A plural subject takes \"run\".",
+                }],
+            })
+        };
+        let params = ContentParams::new();
+        assert!(CorrectionsDiffer.validate(&exercise("The dogs run fast.", "runs"), &params).is_ok());
+        assert!(CorrectionsDiffer.validate(&exercise("The dogs runs fast.", "runs"), &params).is_err());
+        assert!(CorrectionsDiffer.validate(&exercise("The dogs run fast.", "walks"), &params).is_err());
+        assert!(CorrectionsDiffer.validate(&json!({ "title": "Fix It" }), &params).is_err());
+    }
+}
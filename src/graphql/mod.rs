@@ -0,0 +1,345 @@
+mod parser;
+
+use axum::{
+    Json,
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use futures::future::join_all;
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use serde_json::{Map, Value, json};
+use std::collections::HashMap;
+
+use crate::{
+    ServiceError, content, feedback, keyvalue::KeyValueStore, problem::ErrorResponse, progress,
+    reading, search, session::Session, state::AppState, storage::ObjectStore, users::CurrentUser,
+};
+use parser::{Field, OperationKind, Selection};
+
+/// Longest request document accepted, in bytes
+const MAX_DOCUMENT_LEN: usize = 10_000;
+
+/// Most root fields one operation may select, since each may generate content
+const MAX_ROOT_FIELDS: usize = 10;
+
+/// Deepest fragment spreads may nest, which also stops fragments spreading
+/// themselves
+const MAX_FRAGMENT_DEPTH: usize = 8;
+
+/// A GraphQL request, as POSTed to `/graphql`
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GraphQLRequest {
+    pub query: String,
+    #[serde(default)]
+    pub operation_name: Option<String>,
+    #[serde(default)]
+    pub variables: Option<Map<String, Value>>,
+}
+
+/// Who a request runs as
+struct Caller {
+    user: Option<CurrentUser>,
+    session_id: String,
+}
+
+impl Caller {
+    fn user(&self) -> Result<&CurrentUser, ServiceError> {
+        self.user.as_ref().ok_or_else(|| ServiceError::Unauthorized("Log in to continue".into()))
+    }
+}
+
+/// Arguments of the `contents` field
+#[derive(Deserialize)]
+struct ContentsArguments {
+    #[serde(rename = "type")]
+    content_type: String,
+}
+
+/// Converts a camelCase GraphQL name to the snake_case name the API uses
+fn snake_case(name: &str) -> String {
+    let mut snake = String::with_capacity(name.len() + 4);
+    for c in name.chars() {
+        if c.is_ascii_uppercase() {
+            snake.push('_');
+        }
+        snake.push(c.to_ascii_lowercase());
+    }
+    snake
+}
+
+/// A field's arguments as a JSON object with snake_case keys
+fn arguments(field: &Field, variables: &Map<String, Value>) -> Result<Value, ServiceError> {
+    field
+        .arguments
+        .iter()
+        .map(|(name, value)| Ok((snake_case(name), value.to_json(variables).map_err(ServiceError::InvalidInput)?)))
+        .collect::<Result<Map<_, _>, ServiceError>>()
+        .map(Value::Object)
+}
+
+/// Reads a field's arguments into the type the REST endpoint takes
+fn parse_arguments<T: DeserializeOwned>(field: &Field, arguments: Value) -> Result<T, ServiceError> {
+    serde_json::from_value(arguments)
+        .map_err(|e| ServiceError::InvalidInput(format!("Invalid arguments to {}: {}", field.name, e)))
+}
+
+fn to_value(value: impl Serialize) -> Result<Value, ServiceError> {
+    Ok(serde_json::to_value(value)?)
+}
+
+/// Resolves a root field with the service behind the matching REST endpoint
+///
+/// Query fields:
+/// * `readingContents(grade, topic, skill)` - as `GET /reading_contents`
+/// * `contents(type)` - content of any registered type, as `GET /contents/{type}`
+/// * `search(q, like, limit)` - as `GET /search`
+/// * `progress(child, since, limit)` - as `GET /progress`, for a logged-in account
+///
+/// Mutation fields:
+/// * `submitAnswers(child, activity, contentKey, skill, score)` - records a
+///   completed activity, as `POST /progress`
+/// * `feedback(objectKey, rating, comment)` - as `POST /feedback`
+///
+/// Objects have the fields of the JSON the REST endpoints return, described in
+/// `/openapi.json`, and can be selected by their camelCase names.
+async fn resolve<S: ObjectStore, K: KeyValueStore>(
+    state: &AppState<S, K>,
+    caller: &Caller,
+    kind: OperationKind,
+    field: &Field,
+    variables: &Map<String, Value>,
+) -> Result<Value, ServiceError> {
+    let arguments = arguments(field, variables)?;
+    match (kind, field.name.as_str()) {
+        (_, "__typename") => to_value(format!("{:?}", kind)),
+        (OperationKind::Query, "readingContents") => {
+            let query = parse_arguments(field, arguments)?;
+            to_value(reading::story(state, query, caller.user.clone(), Some(&caller.session_id)).await?)
+        }
+        (OperationKind::Query, "contents") => {
+            let ContentsArguments { content_type } = parse_arguments(field, arguments)?;
+            content::content_of_type(state, &content_type, Some(&caller.session_id)).await
+        }
+        (OperationKind::Query, "search") => {
+            to_value(search::search(state, parse_arguments(field, arguments)?).await?)
+        }
+        (OperationKind::Query, "progress") => {
+            to_value(progress::report(state, caller.user()?, parse_arguments(field, arguments)?).await?)
+        }
+        (OperationKind::Mutation, "submitAnswers") => {
+            to_value(progress::complete(state, caller.user()?, parse_arguments(field, arguments)?).await?)
+        }
+        (OperationKind::Mutation, "feedback") => {
+            to_value(feedback::rate(state, &caller.session_id, parse_arguments(field, arguments)?).await?)
+        }
+        (kind, name) => Err(ServiceError::InvalidInput(format!("{:?} has no field {}", kind, name))),
+    }
+}
+
+/// Checks that every fragment spread names a fragment and they don't nest too
+/// deeply
+fn check_fragments(
+    selections: &[Selection],
+    fragments: &HashMap<String, Vec<Selection>>,
+    depth: usize,
+) -> Result<(), String> {
+    for selection in selections {
+        match selection {
+            Selection::Field(field) => check_fragments(&field.selection_set, fragments, depth)?,
+            Selection::InlineFragment(selections) => check_fragments(selections, fragments, depth)?,
+            Selection::FragmentSpread(name) => {
+                let selections = fragments.get(name).ok_or_else(|| format!("Unknown fragment {}", name))?;
+                if depth == MAX_FRAGMENT_DEPTH {
+                    return Err(format!("Fragments may nest at most {} levels", MAX_FRAGMENT_DEPTH));
+                }
+                check_fragments(selections, fragments, depth + 1)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The fields of a selection set, with fragments spread into it
+fn fields<'a>(selections: &'a [Selection], fragments: &'a HashMap<String, Vec<Selection>>) -> Vec<&'a Field> {
+    selections
+        .iter()
+        .flat_map(|selection| match selection {
+            Selection::Field(field) => vec![field],
+            Selection::InlineFragment(selections) => fields(selections, fragments),
+            Selection::FragmentSpread(name) => {
+                fragments.get(name).map(|selections| fields(selections, fragments)).unwrap_or_default()
+            }
+        })
+        .collect()
+}
+
+/// Picks the selected fields out of a resolved value, looking up camelCase
+/// names under their snake_case keys; fields the value lacks are null
+fn select(value: Value, selections: &[Selection], fragments: &HashMap<String, Vec<Selection>>) -> Value {
+    match value {
+        Value::Array(items) => items.into_iter().map(|item| select(item, selections, fragments)).collect(),
+        Value::Object(object) if !selections.is_empty() => {
+            let mut selected = Map::new();
+            for field in fields(selections, fragments) {
+                let value = object
+                    .get(&field.name)
+                    .or_else(|| object.get(&snake_case(&field.name)))
+                    .cloned()
+                    .unwrap_or(Value::Null);
+                selected.insert(field.response_key().to_string(), select(value, &field.selection_set, fragments));
+            }
+            Value::Object(selected)
+        }
+        value => value,
+    }
+}
+
+/// Runs a request's operation
+///
+/// Queries resolve their root fields concurrently and mutations one at a time.
+/// A field that fails is null, with its error listed under `errors`.
+///
+/// # Returns
+/// * `Ok(Value)` - The response, with `data` and any field `errors`
+/// * `Err(String)` - Why the document couldn't be run at all
+async fn execute<S: ObjectStore, K: KeyValueStore>(
+    state: &AppState<S, K>,
+    caller: &Caller,
+    request: GraphQLRequest,
+) -> Result<Value, String> {
+    if request.query.len() > MAX_DOCUMENT_LEN {
+        return Err(format!("Documents may be at most {} bytes", MAX_DOCUMENT_LEN));
+    }
+    let document = parser::parse(&request.query)?;
+    let operation = document.operation(request.operation_name.as_deref())?;
+    check_fragments(&operation.selection_set, &document.fragments, 0)?;
+
+    let mut variables = request.variables.unwrap_or_default();
+    for (name, default) in &operation.defaults {
+        if !variables.contains_key(name) {
+            variables.insert(name.clone(), default.to_json(&Map::new())?);
+        }
+    }
+
+    let fields = fields(&operation.selection_set, &document.fragments);
+    if fields.len() > MAX_ROOT_FIELDS {
+        return Err(format!("Operations may select at most {} fields", MAX_ROOT_FIELDS));
+    }
+
+    let run = |field| resolve(state, caller, operation.kind, field, &variables);
+    let results = match operation.kind {
+        OperationKind::Query => join_all(fields.iter().copied().map(run)).await,
+        OperationKind::Mutation => {
+            let mut results = Vec::new();
+            for field in &fields {
+                results.push(run(field).await);
+            }
+            results
+        }
+    };
+
+    let mut data = Map::new();
+    let mut errors = Vec::new();
+    for (field, result) in fields.into_iter().zip(results) {
+        let value = match result {
+            Ok(value) => select(value, &field.selection_set, &document.fragments),
+            Err(e) => {
+                let error = ErrorResponse::from(e);
+                errors.push(json!({
+                    "message": error.detail,
+                    "path": [field.response_key()],
+                    "extensions": { "code": error.code, "retryable": error.retryable },
+                }));
+                Value::Null
+            }
+        };
+        data.insert(field.response_key().to_string(), value);
+    }
+
+    let mut response = json!({ "data": data });
+    if !errors.is_empty() {
+        response["errors"] = Value::Array(errors);
+    }
+    Ok(response)
+}
+
+/// Runs a GraphQL query or mutation over the same services as the REST API, so a
+/// client can fetch a whole dashboard in one round trip
+///
+/// A document that can't be run is answered with 400 and its error; otherwise
+/// the response is 200, with any field errors beside the data.
+pub async fn graphql<S: ObjectStore, K: KeyValueStore>(
+    State(state): State<AppState<S, K>>,
+    session: Session,
+    user: Option<CurrentUser>,
+    Json(request): Json<GraphQLRequest>,
+) -> Response {
+    let caller = Caller { user, session_id: session.id().to_string() };
+    match execute(&state, &caller, request).await {
+        Ok(response) => Json(response).into_response(),
+        Err(message) => {
+            (StatusCode::BAD_REQUEST, Json(json!({ "errors": [{ "message": message }] }))).into_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::Config, content::ContentTypeRegistry, keyvalue::MemoryKeyValueStore,
+        storage::MemoryObjectStore,
+    };
+
+    #[tokio::test]
+    async fn resolves_fields_and_reports_errors_beside_data() {
+        let state = AppState::new(
+            MemoryObjectStore::new(),
+            MemoryKeyValueStore::new(),
+            Config::default(),
+            ContentTypeRegistry::new().register(reading::descriptor()),
+        )
+        .await;
+        state.object_store.put_object("reading/2025-10-11-14/a.json", b"{}".to_vec()).await.unwrap();
+        let caller = Caller { user: None, session_id: "s".to_string() };
+        let request = |query: &str, variables: Value| GraphQLRequest {
+            query: query.to_string(),
+            operation_name: None,
+            variables: variables.as_object().cloned(),
+        };
+
+        let response = execute(
+            &state,
+            &caller,
+            request(
+                "mutation Rate($key: String!) { __typename vote: feedback(objectKey: $key, rating: UP) { ...Votes } }
+                 fragment Votes on ObjectRatings { up down }",
+                json!({ "key": "reading/2025-10-11-14/a.json" }),
+            ),
+        )
+        .await
+        .unwrap();
+        assert_eq!(response, json!({ "data": { "__typename": "Mutation", "vote": { "up": 1, "down": 0 } } }));
+
+        let response = execute(&state, &caller, request("{ progress { child } search { objectKey } }", json!({})))
+            .await
+            .unwrap();
+        assert_eq!(response["data"], json!({ "progress": null, "search": null }));
+        assert_eq!(response["errors"][0]["extensions"]["code"], "unauthorized");
+        assert_eq!(response["errors"][1]["path"], json!(["search"]));
+
+        let selected = select(
+            json!([{ "object_key": "k", "similarity": 0.5 }]),
+            &parser::parse("{ objectKey score: similarity missing }").unwrap().operations[0].selection_set,
+            &HashMap::new(),
+        );
+        assert_eq!(selected, json!([{ "objectKey": "k", "score": 0.5, "missing": null }]));
+
+        assert!(execute(&state, &caller, request("{ a ...Loop } fragment Loop on Query { ...Loop }", json!({})))
+            .await
+            .is_err());
+        assert!(execute(&state, &caller, request("{ a { ...Missing } }", json!({}))).await.is_err());
+    }
+}
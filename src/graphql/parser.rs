@@ -0,0 +1,480 @@
+use std::collections::HashMap;
+
+/// Deepest nesting of selection sets and values a document may have
+const MAX_DEPTH: usize = 16;
+
+/// An argument or variable value as written in a document
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Variable(String),
+    Int(i64),
+    Float(f64),
+    String(String),
+    Boolean(bool),
+    Null,
+    Enum(String),
+    List(Vec<Value>),
+    Object(Vec<(String, Value)>),
+}
+
+impl Value {
+    /// Converts the value to JSON, substituting variables
+    ///
+    /// Enum values are lowercased, so `MAIN_IDEA` reads as the API's "main_idea".
+    pub fn to_json(&self, variables: &serde_json::Map<String, serde_json::Value>) -> Result<serde_json::Value, String> {
+        Ok(match self {
+            Value::Variable(name) => variables.get(name).cloned().unwrap_or(serde_json::Value::Null),
+            Value::Int(n) => (*n).into(),
+            Value::Float(n) => serde_json::Number::from_f64(*n)
+                .map(serde_json::Value::Number)
+                .ok_or_else(|| format!("{} is not a valid number", n))?,
+            Value::String(s) => s.clone().into(),
+            Value::Boolean(b) => (*b).into(),
+            Value::Null => serde_json::Value::Null,
+            Value::Enum(name) => name.to_lowercase().into(),
+            Value::List(items) => items
+                .iter()
+                .map(|item| item.to_json(variables))
+                .collect::<Result<_, _>>()?,
+            Value::Object(fields) => fields
+                .iter()
+                .map(|(name, value)| Ok((name.clone(), value.to_json(variables)?)))
+                .collect::<Result<serde_json::Map<_, _>, String>>()?
+                .into(),
+        })
+    }
+}
+
+/// A field to fetch, e.g. `story: readingContents(grade: 3) { title }`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Field {
+    pub alias: Option<String>,
+    pub name: String,
+    pub arguments: Vec<(String, Value)>,
+    pub selection_set: Vec<Selection>,
+}
+
+impl Field {
+    /// Key of the field in the response
+    pub fn response_key(&self) -> &str {
+        self.alias.as_deref().unwrap_or(&self.name)
+    }
+}
+
+/// An entry of a selection set
+#[derive(Debug, Clone, PartialEq)]
+pub enum Selection {
+    Field(Field),
+
+    /// `...Name`, a named fragment's selections
+    FragmentSpread(String),
+
+    /// `... on Type { ... }`; type conditions are not checked
+    InlineFragment(Vec<Selection>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OperationKind {
+    Query,
+    Mutation,
+}
+
+/// A query or mutation
+#[derive(Debug, Clone, PartialEq)]
+pub struct Operation {
+    pub kind: OperationKind,
+    pub name: Option<String>,
+
+    /// Variables with a default value, and their defaults
+    pub defaults: Vec<(String, Value)>,
+    pub selection_set: Vec<Selection>,
+}
+
+/// A parsed GraphQL request document
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Document {
+    pub operations: Vec<Operation>,
+    pub fragments: HashMap<String, Vec<Selection>>,
+}
+
+impl Document {
+    /// Picks the operation to run: the one named, or the only one
+    pub fn operation(&self, name: Option<&str>) -> Result<&Operation, String> {
+        match name {
+            Some(name) => self
+                .operations
+                .iter()
+                .find(|op| op.name.as_deref() == Some(name))
+                .ok_or_else(|| format!("Unknown operation {:?}", name)),
+            None if self.operations.len() == 1 => Ok(&self.operations[0]),
+            None => Err("operationName is required when the document has several operations".into()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Punctuator(char),
+    Spread,
+    Name(String),
+    Int(i64),
+    Float(f64),
+    String(String),
+}
+
+/// Splits a document into tokens, dropping whitespace, commas, and comments
+fn tokenize(source: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = source.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() || c == ',' || c == '\u{feff}' => {
+                chars.next();
+            }
+            '#' => {
+                while chars.next_if(|&c| c != '\n' && c != '\r').is_some() {}
+            }
+            '{' | '}' | '(' | ')' | '[' | ']' | ':' | '$' | '!' | '=' | '@' | '|' | '&' => {
+                tokens.push(Token::Punctuator(c));
+                chars.next();
+            }
+            '.' => {
+                let dots: String = std::iter::from_fn(|| chars.next_if_eq(&'.')).collect();
+                if dots != "..." {
+                    return Err(format!("Unexpected {:?}", dots));
+                }
+                tokens.push(Token::Spread);
+            }
+            '"' => {
+                chars.next();
+                let mut value = String::new();
+                loop {
+                    match chars.next() {
+                        None | Some('\n') | Some('\r') => return Err("Unterminated string".into()),
+                        Some('"') if value.is_empty() && chars.peek() == Some(&'"') => {
+                            return Err("Block strings are not supported".into());
+                        }
+                        Some('"') => break,
+                        Some('\\') => value.push(match chars.next() {
+                            Some('n') => '\n',
+                            Some('t') => '\t',
+                            Some('r') => '\r',
+                            Some('b') => '\u{8}',
+                            Some('f') => '\u{c}',
+                            Some('u') => {
+                                let hex: String = (0..4).filter_map(|_| chars.next()).collect();
+                                u32::from_str_radix(&hex, 16)
+                                    .ok()
+                                    .and_then(char::from_u32)
+                                    .ok_or_else(|| format!("Invalid escape \\u{}", hex))?
+                            }
+                            Some(c @ ('"' | '\\' | '/')) => c,
+                            other => return Err(format!("Invalid escape {:?}", other)),
+                        }),
+                        Some(c) => value.push(c),
+                    }
+                }
+                tokens.push(Token::String(value));
+            }
+            c if c == '-' || c.is_ascii_digit() => {
+                let number: String = std::iter::from_fn(|| {
+                    chars.next_if(|&c| c.is_ascii_alphanumeric() || matches!(c, '-' | '+' | '.'))
+                })
+                .collect();
+                let token = if number.contains(['.', 'e', 'E']) {
+                    number.parse().map(Token::Float).ok()
+                } else {
+                    number.parse().map(Token::Int).ok()
+                };
+                tokens.push(token.ok_or_else(|| format!("Invalid number {:?}", number))?);
+            }
+            c if c == '_' || c.is_ascii_alphabetic() => {
+                let name = std::iter::from_fn(|| chars.next_if(|&c| c == '_' || c.is_ascii_alphanumeric())).collect();
+                tokens.push(Token::Name(name));
+            }
+            c => return Err(format!("Unexpected character {:?}", c)),
+        }
+    }
+    Ok(tokens)
+}
+
+/// Recursive descent parser over a document's tokens
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+    depth: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Result<Token, String> {
+        let token = self.tokens.get(self.position).cloned().ok_or("Unexpected end of document")?;
+        self.position += 1;
+        Ok(token)
+    }
+
+    /// Consumes a punctuator if it is next
+    fn eat(&mut self, punctuator: char) -> bool {
+        let found = self.peek() == Some(&Token::Punctuator(punctuator));
+        if found {
+            self.position += 1;
+        }
+        found
+    }
+
+    fn expect(&mut self, punctuator: char) -> Result<(), String> {
+        match self.next()? {
+            Token::Punctuator(c) if c == punctuator => Ok(()),
+            token => Err(format!("Expected {:?}, found {:?}", punctuator, token)),
+        }
+    }
+
+    fn name(&mut self) -> Result<String, String> {
+        match self.next()? {
+            Token::Name(name) => Ok(name),
+            token => Err(format!("Expected a name, found {:?}", token)),
+        }
+    }
+
+    /// Guards against documents nested deeply enough to exhaust the stack
+    fn nested<T>(&mut self, parse: impl FnOnce(&mut Self) -> Result<T, String>) -> Result<T, String> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err(format!("Documents may nest at most {} levels", MAX_DEPTH));
+        }
+        let result = parse(self);
+        self.depth -= 1;
+        result
+    }
+
+    fn document(&mut self) -> Result<Document, String> {
+        let mut document = Document::default();
+        while let Some(token) = self.peek() {
+            match token {
+                Token::Punctuator('{') => document.operations.push(Operation {
+                    kind: OperationKind::Query,
+                    name: None,
+                    defaults: Vec::new(),
+                    selection_set: self.selection_set()?,
+                }),
+                Token::Name(keyword) if keyword == "fragment" => {
+                    self.position += 1;
+                    let name = self.name()?;
+                    self.type_condition()?;
+                    let selections = self.selection_set()?;
+                    if document.fragments.insert(name.clone(), selections).is_some() {
+                        return Err(format!("Fragment {} is defined twice", name));
+                    }
+                }
+                Token::Name(_) => {
+                    let operation = self.operation()?;
+                    document.operations.push(operation);
+                }
+                token => return Err(format!("Unexpected {:?}", token)),
+            }
+        }
+        if document.operations.is_empty() {
+            return Err("The document has no operations".into());
+        }
+        Ok(document)
+    }
+
+    fn operation(&mut self) -> Result<Operation, String> {
+        let kind = match self.name()?.as_str() {
+            "query" => OperationKind::Query,
+            "mutation" => OperationKind::Mutation,
+            other => return Err(format!("Unsupported operation {:?}", other)),
+        };
+        let name = match self.peek() {
+            Some(Token::Name(_)) => Some(self.name()?),
+            _ => None,
+        };
+
+        let mut defaults = Vec::new();
+        if self.eat('(') {
+            while !self.eat(')') {
+                self.expect('$')?;
+                let variable = self.name()?;
+                self.expect(':')?;
+                self.variable_type()?;
+                if self.eat('=') {
+                    defaults.push((variable, self.value()?));
+                }
+            }
+        }
+        self.no_directives()?;
+
+        Ok(Operation { kind, name, defaults, selection_set: self.selection_set()? })
+    }
+
+    /// Skips a variable's type, which isn't checked
+    fn variable_type(&mut self) -> Result<(), String> {
+        if self.eat('[') {
+            self.nested(Self::variable_type)?;
+            self.expect(']')?;
+        } else {
+            self.name()?;
+        }
+        self.eat('!');
+        Ok(())
+    }
+
+    /// Skips `on Type`
+    fn type_condition(&mut self) -> Result<(), String> {
+        match self.name()?.as_str() {
+            "on" => self.name().map(drop),
+            other => Err(format!("Expected \"on\", found {:?}", other)),
+        }
+    }
+
+    fn no_directives(&self) -> Result<(), String> {
+        match self.peek() {
+            Some(Token::Punctuator('@')) => Err("Directives are not supported".into()),
+            _ => Ok(()),
+        }
+    }
+
+    fn selection_set(&mut self) -> Result<Vec<Selection>, String> {
+        self.expect('{')?;
+        self.nested(|parser| {
+            let mut selections = Vec::new();
+            while !parser.eat('}') {
+                selections.push(parser.selection()?);
+            }
+            if selections.is_empty() {
+                return Err("Selection sets must not be empty".into());
+            }
+            Ok(selections)
+        })
+    }
+
+    fn selection(&mut self) -> Result<Selection, String> {
+        if self.peek() == Some(&Token::Spread) {
+            self.position += 1;
+            return match self.peek() {
+                Some(Token::Name(name)) if name != "on" => {
+                    let name = self.name()?;
+                    self.no_directives()?;
+                    Ok(Selection::FragmentSpread(name))
+                }
+                _ => {
+                    if matches!(self.peek(), Some(Token::Name(_))) {
+                        self.type_condition()?;
+                    }
+                    self.no_directives()?;
+                    Ok(Selection::InlineFragment(self.selection_set()?))
+                }
+            };
+        }
+
+        let mut name = self.name()?;
+        let mut alias = None;
+        if self.eat(':') {
+            alias = Some(name);
+            name = self.name()?;
+        }
+
+        let mut arguments = Vec::new();
+        if self.eat('(') {
+            while !self.eat(')') {
+                let argument = self.name()?;
+                self.expect(':')?;
+                arguments.push((argument, self.value()?));
+            }
+        }
+        self.no_directives()?;
+
+        let selection_set = if self.peek() == Some(&Token::Punctuator('{')) {
+            self.selection_set()?
+        } else {
+            Vec::new()
+        };
+        Ok(Selection::Field(Field { alias, name, arguments, selection_set }))
+    }
+
+    fn value(&mut self) -> Result<Value, String> {
+        Ok(match self.next()? {
+            Token::Punctuator('$') => Value::Variable(self.name()?),
+            Token::Int(n) => Value::Int(n),
+            Token::Float(n) => Value::Float(n),
+            Token::String(s) => Value::String(s),
+            Token::Name(name) => match name.as_str() {
+                "true" => Value::Boolean(true),
+                "false" => Value::Boolean(false),
+                "null" => Value::Null,
+                _ => Value::Enum(name),
+            },
+            Token::Punctuator('[') => Value::List(self.nested(|parser| {
+                let mut items = Vec::new();
+                while !parser.eat(']') {
+                    items.push(parser.value()?);
+                }
+                Ok(items)
+            })?),
+            Token::Punctuator('{') => Value::Object(self.nested(|parser| {
+                let mut fields = Vec::new();
+                while !parser.eat('}') {
+                    let name = parser.name()?;
+                    parser.expect(':')?;
+                    fields.push((name, parser.value()?));
+                }
+                Ok(fields)
+            })?),
+            token => return Err(format!("Expected a value, found {:?}", token)),
+        })
+    }
+}
+
+/// Parses a request document
+///
+/// Supports queries, mutations, variables, aliases, and fragments; directives,
+/// subscriptions, and block strings are rejected.
+pub fn parse(source: &str) -> Result<Document, String> {
+    Parser { tokens: tokenize(source)?, position: 0, depth: 0 }.document()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_operations_arguments_and_fragments() {
+        let document = parse(
+            r#"
+            # A dashboard
+            query Dashboard($topic: String = "owls", $n: [Int!]!) {
+                story: readingContents(grade: 3, topic: $topic, skill: MAIN_IDEA) { title ...Questions }
+                search(q: "café\n", limit: -2.5e1) { ... on SearchResult { similarity } }
+            }
+            fragment Questions on ReadingContents { questions }
+            mutation { feedback(input: {rating: UP, tags: [1, null]}) { up } }
+            "#,
+        )
+        .unwrap();
+
+        let query = document.operation(Some("Dashboard")).unwrap();
+        assert_eq!(query.defaults, vec![("topic".to_string(), Value::String("owls".into()))]);
+        let Selection::Field(story) = &query.selection_set[0] else { panic!("not a field") };
+        assert_eq!((story.response_key(), story.name.as_str()), ("story", "readingContents"));
+        assert_eq!(story.arguments[1], ("topic".to_string(), Value::Variable("topic".into())));
+        assert_eq!(story.selection_set[1], Selection::FragmentSpread("Questions".into()));
+        let Selection::Field(search) = &query.selection_set[1] else { panic!("not a field") };
+        assert_eq!(search.arguments[0].1, Value::String("café\n".into()));
+        assert_eq!(search.arguments[1].1, Value::Float(-25.0));
+        assert!(document.fragments.contains_key("Questions"));
+
+        let variables = serde_json::Map::from_iter([("topic".to_string(), "bats".into())]);
+        assert_eq!(story.arguments[1].1.to_json(&variables).unwrap(), "bats");
+        assert_eq!(story.arguments[2].1.to_json(&variables).unwrap(), "main_idea");
+        assert!(document.operation(None).is_err());
+
+        assert!(parse("{ a @skip(if: true) }").is_err());
+        assert!(parse("subscription { a }").is_err());
+        assert!(parse("{ a }}").is_err());
+        assert!(parse(&format!("{}{}", "{ a ".repeat(20), "}".repeat(20))).is_err());
+    }
+}
@@ -0,0 +1,430 @@
+mod protobuf;
+
+use axum::{
+    Router,
+    body::{Body, Bytes},
+    extract::{Request, State},
+    http::{HeaderMap, HeaderName, HeaderValue, StatusCode, header},
+    middleware::{self, Next},
+    response::Response,
+    routing::post,
+};
+use futures::stream;
+use http_body::Frame;
+use http_body_util::StreamBody;
+use percent_encoding::{AsciiSet, CONTROLS, utf8_percent_encode};
+use serde::de::DeserializeOwned;
+use std::{convert::Infallible, sync::Arc};
+
+use crate::{
+    ServiceError,
+    admin::constant_time_eq,
+    content,
+    keyvalue::KeyValueStore,
+    problem::ErrorResponse,
+    progress::{self, ActivityResult, NewActivity, ProgressReport, Skill},
+    reading::{self, ReadingContents, ReadingQuery},
+    state::AppState,
+    storage::ObjectStore,
+};
+use protobuf::{Encoder, WireValue};
+
+/// Media type of gRPC requests and responses
+const GRPC: &str = "application/grpc";
+
+const GRPC_STATUS: HeaderName = HeaderName::from_static("grpc-status");
+const GRPC_MESSAGE: HeaderName = HeaderName::from_static("grpc-message");
+
+/// Characters percent-encoded in `grpc-message`
+const MESSAGE_ESCAPES: &AsciiSet = &CONTROLS.add(b'%');
+
+/// gRPC status codes the service answers with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Code {
+    Ok = 0,
+    InvalidArgument = 3,
+    NotFound = 5,
+    AlreadyExists = 6,
+    PermissionDenied = 7,
+    ResourceExhausted = 8,
+    Unimplemented = 12,
+    Internal = 13,
+    Unavailable = 14,
+    Unauthenticated = 16,
+}
+
+/// A failed call's status
+#[derive(Debug)]
+struct Status {
+    code: Code,
+    message: String,
+}
+
+impl Status {
+    fn new(code: Code, message: impl Into<String>) -> Self {
+        Self { code, message: message.into() }
+    }
+
+    fn invalid(message: impl Into<String>) -> Self {
+        Self::new(Code::InvalidArgument, message)
+    }
+}
+
+impl From<ServiceError> for Status {
+    /// Maps errors as the HTTP API reports them to the matching gRPC code
+    fn from(err: ServiceError) -> Self {
+        let error = ErrorResponse::from(err);
+        let code = match error.status {
+            StatusCode::BAD_REQUEST => Code::InvalidArgument,
+            StatusCode::UNAUTHORIZED => Code::Unauthenticated,
+            StatusCode::FORBIDDEN => Code::PermissionDenied,
+            StatusCode::NOT_FOUND => Code::NotFound,
+            StatusCode::CONFLICT => Code::AlreadyExists,
+            StatusCode::TOO_MANY_REQUESTS => Code::ResourceExhausted,
+            StatusCode::SERVICE_UNAVAILABLE => Code::Unavailable,
+            _ => Code::Internal,
+        };
+        Self::new(code, error.detail)
+    }
+}
+
+/// Headers carrying a call's status
+fn status_headers(code: Code, message: &str) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    headers.insert(GRPC_STATUS, HeaderValue::from(code as i32));
+    if let Ok(message) = HeaderValue::from_str(&utf8_percent_encode(message, MESSAGE_ESCAPES).to_string())
+        && !message.is_empty()
+    {
+        headers.insert(GRPC_MESSAGE, message);
+    }
+    headers
+}
+
+/// Answers a call: a length-prefixed message followed by an OK status in the
+/// trailers, or for a failed call, only its status
+fn reply(result: Result<Vec<u8>, Status>) -> Response {
+    let mut response = match result {
+        Ok(message) => {
+            let mut framed = Vec::with_capacity(message.len() + 5);
+            framed.push(0);
+            framed.extend_from_slice(&(message.len() as u32).to_be_bytes());
+            framed.extend_from_slice(&message);
+            let frames = [Frame::data(Bytes::from(framed)), Frame::trailers(status_headers(Code::Ok, ""))];
+            Response::new(Body::new(StreamBody::new(stream::iter(frames.map(Ok::<_, Infallible>)))))
+        }
+        Err(status) => {
+            let mut response = Response::new(Body::empty());
+            response.headers_mut().extend(status_headers(status.code, &status.message));
+            response
+        }
+    };
+    response.headers_mut().insert(header::CONTENT_TYPE, HeaderValue::from_static(GRPC));
+    response
+}
+
+/// Reads the one message of a unary call's request body
+fn unframe(body: &[u8]) -> Result<&[u8], Status> {
+    let Some(([compressed, length @ ..], message)) = body.split_first_chunk::<5>() else {
+        return Err(Status::invalid("Expected a length-prefixed message"));
+    };
+    if *compressed != 0 {
+        return Err(Status::new(Code::Unimplemented, "Compressed messages are not supported"));
+    }
+    if message.len() != u32::from_be_bytes(*length) as usize {
+        return Err(Status::invalid("Expected exactly one message"));
+    }
+    Ok(message)
+}
+
+/// Reads a message's fields
+fn fields(message: &[u8]) -> Result<Vec<(u32, WireValue<'_>)>, Status> {
+    protobuf::decode(message).map_err(Status::invalid)
+}
+
+/// Reads a string field
+fn string(value: &WireValue) -> Result<String, Status> {
+    value.as_string().map_err(Status::invalid)
+}
+
+/// Reads a string field naming a value of an enum the API uses, e.g. "main_idea"
+fn enum_value<T: DeserializeOwned>(value: &WireValue) -> Result<T, Status> {
+    let name = string(value)?;
+    serde_json::from_value(name.clone().into()).map_err(|_| Status::invalid(format!("Unknown value {:?}", name)))
+}
+
+/// Reads an integer field that must fit a smaller type
+fn integer<T: TryFrom<u64>>(value: &WireValue) -> Result<T, Status> {
+    let value = value.as_u64().map_err(Status::invalid)?;
+    T::try_from(value).map_err(|_| Status::invalid(format!("{} is out of range", value)))
+}
+
+/// A string that is absent when empty, as proto3 can't tell them apart
+fn non_empty(value: String) -> Option<String> {
+    (!value.is_empty()).then_some(value)
+}
+
+fn reading_contents(contents: &ReadingContents) -> Encoder {
+    Encoder::new()
+        .string(1, contents.id.as_deref().unwrap_or_default())
+        .string(2, &contents.title)
+        .string(3, &contents.story)
+        .strings(4, &contents.questions)
+        .strings(5, &contents.topics)
+        .strings(6, contents.skills.iter().map(|skill| skill.as_str()))
+        .double(7, contents.readability_grade)
+        .string(8, contents.illustration_key.as_deref().unwrap_or_default())
+}
+
+fn activity_result(result: &ActivityResult) -> Encoder {
+    Encoder::new()
+        .string(1, &result.id)
+        .string(2, &result.child)
+        .string(3, &result.activity)
+        .string(4, result.content_key.as_deref().unwrap_or_default())
+        .string(5, result.skill.as_str())
+        .uint(6, result.score.into())
+        .string(7, &result.completed_at)
+}
+
+fn progress_report(report: &ProgressReport) -> Encoder {
+    let encoder = report
+        .activities
+        .iter()
+        .fold(Encoder::new().string(1, &report.child), |encoder, activity| {
+            encoder.message(2, activity_result(activity))
+        });
+    report.skills.iter().fold(encoder, |encoder, (skill, summary)| {
+        let summary = Encoder::new()
+            .string(1, skill.as_str())
+            .int(2, summary.activities)
+            .double(3, summary.average_score);
+        encoder.message(3, summary)
+    })
+}
+
+/// `Content.GetReadingContents`
+async fn get_reading_contents<S: ObjectStore, K: KeyValueStore>(
+    State(state): State<AppState<S, K>>,
+    body: Bytes,
+) -> Response {
+    reply(
+        async {
+            let mut query = ReadingQuery::default();
+            let mut session_id = None;
+            for (field, value) in fields(unframe(&body)?)? {
+                match field {
+                    1 => query.grade = Some(integer(&value)?).filter(|&grade| grade != 0),
+                    2 => query.topic = non_empty(string(&value)?),
+                    3 if value != WireValue::Bytes(b"") => query.skill = Some(enum_value(&value)?),
+                    4 => session_id = non_empty(string(&value)?),
+                    _ => {}
+                }
+            }
+            let contents = reading::story(&state, query, None, session_id.as_deref()).await?;
+            Ok(reading_contents(&contents).finish())
+        }
+        .await,
+    )
+}
+
+/// `Content.GetContents`
+async fn get_contents<S: ObjectStore, K: KeyValueStore>(
+    State(state): State<AppState<S, K>>,
+    body: Bytes,
+) -> Response {
+    reply(
+        async {
+            let mut content_type = String::new();
+            let mut session_id = None;
+            for (field, value) in fields(unframe(&body)?)? {
+                match field {
+                    1 => content_type = string(&value)?,
+                    2 => session_id = non_empty(string(&value)?),
+                    _ => {}
+                }
+            }
+            let contents = content::content_of_type(&state, &content_type, session_id.as_deref()).await?;
+            Ok(Encoder::new().string(1, &contents.to_string()).finish())
+        }
+        .await,
+    )
+}
+
+/// `Progress.RecordActivity`
+///
+/// Internal callers are trusted to record activities for any child.
+async fn record_activity<S: ObjectStore, K: KeyValueStore>(
+    State(state): State<AppState<S, K>>,
+    body: Bytes,
+) -> Response {
+    reply(
+        async {
+            let mut activity = NewActivity {
+                child: String::new(),
+                activity: String::new(),
+                content_key: None,
+                skill: Skill::Comprehension,
+                score: 0,
+            };
+            let mut skill = None;
+            for (field, value) in fields(unframe(&body)?)? {
+                match field {
+                    1 => activity.child = string(&value)?,
+                    2 => activity.activity = string(&value)?,
+                    3 => activity.content_key = non_empty(string(&value)?),
+                    4 => skill = Some(enum_value(&value)?),
+                    5 => activity.score = integer(&value)?,
+                    _ => {}
+                }
+            }
+            if activity.child.is_empty() {
+                return Err(Status::invalid("child is required"));
+            }
+            activity.skill = skill.ok_or_else(|| Status::invalid("skill is required"))?;
+
+            let result = progress::complete_for_child(&state, activity).await?;
+            Ok(activity_result(&result).finish())
+        }
+        .await,
+    )
+}
+
+/// `Progress.GetProgress`
+async fn get_progress<S: ObjectStore, K: KeyValueStore>(
+    State(state): State<AppState<S, K>>,
+    body: Bytes,
+) -> Response {
+    reply(
+        async {
+            let (mut child, mut since, mut limit) = (String::new(), None, None);
+            for (field, value) in fields(unframe(&body)?)? {
+                match field {
+                    1 => child = string(&value)?,
+                    2 => since = non_empty(string(&value)?),
+                    3 => limit = Some(integer(&value)?).filter(|&limit| limit != 0),
+                    _ => {}
+                }
+            }
+            if child.is_empty() {
+                return Err(Status::invalid("child is required"));
+            }
+
+            let report = progress::report_for_child(&state, child, since.as_deref(), limit).await?;
+            Ok(progress_report(&report).finish())
+        }
+        .await,
+    )
+}
+
+/// Builds the router for the gRPC API described in
+/// `proto/thinkaroo/v1/thinkaroo.proto`, served on the HTTP listeners over HTTP/2
+///
+/// Every call requires `authorization: Bearer <token>` metadata carrying `token`.
+pub fn router<S, K>(token: &str) -> Router<AppState<S, K>>
+where
+    S: ObjectStore + 'static,
+    K: KeyValueStore + 'static,
+{
+    let token: Arc<str> = token.into();
+
+    Router::new()
+        .route("/thinkaroo.v1.Content/GetReadingContents", post(get_reading_contents))
+        .route("/thinkaroo.v1.Content/GetContents", post(get_contents))
+        .route("/thinkaroo.v1.Progress/RecordActivity", post(record_activity))
+        .route("/thinkaroo.v1.Progress/GetProgress", post(get_progress))
+        .route_layer(middleware::from_fn(move |request: Request, next: Next| {
+            require_token(token.clone(), request, next)
+        }))
+}
+
+/// Rejects calls that don't carry the gRPC bearer token
+async fn require_token(token: Arc<str>, request: Request, next: Next) -> Response {
+    let presented = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+
+    match presented {
+        Some(presented) if constant_time_eq(presented.as_bytes(), token.as_bytes()) => next.run(request).await,
+        _ => reply(Err(Status::new(Code::Unauthenticated, "A valid bearer token is required"))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::Config, content::ContentTypeRegistry, keyvalue::MemoryKeyValueStore,
+        served::ServedHistory, storage::MemoryObjectStore,
+    };
+    use http_body_util::BodyExt;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn answers_unary_calls_with_trailers() {
+        let state = AppState::new(
+            MemoryObjectStore::new(),
+            MemoryKeyValueStore::new(),
+            Config::default(),
+            ContentTypeRegistry::new(),
+        )
+        .await;
+        let app = router("secret").with_state(state);
+        let call = |method: &str, token: &str, message: Vec<u8>| {
+            let mut body = vec![0];
+            body.extend_from_slice(&(message.len() as u32).to_be_bytes());
+            body.extend_from_slice(&message);
+            axum::http::Request::post(format!("/thinkaroo.v1.Progress/{}", method))
+                .header(header::CONTENT_TYPE, GRPC)
+                .header(header::AUTHORIZATION, format!("Bearer {}", token))
+                .body(Body::from(body))
+                .unwrap()
+        };
+
+        let activity = Encoder::new().string(1, "kid").string(2, "reading").string(4, "math").uint(5, 80);
+        let response = app.clone().oneshot(call("RecordActivity", "secret", activity.finish())).await.unwrap();
+        assert_eq!(response.headers()[header::CONTENT_TYPE], GRPC);
+        let collected = response.into_body().collect().await.unwrap();
+        assert_eq!(collected.trailers().unwrap()[&GRPC_STATUS], "0");
+        let body = collected.to_bytes();
+        let result = protobuf::decode(unframe(&body).unwrap()).unwrap();
+        assert!(result.contains(&(2, WireValue::Bytes(b"kid"))));
+        assert!(result.contains(&(6, WireValue::Varint(80))));
+
+        let response = app.clone().oneshot(call("GetProgress", "secret", Encoder::new().finish())).await.unwrap();
+        assert_eq!(response.headers()[&GRPC_STATUS], "3");
+        assert_eq!(response.headers()[&GRPC_MESSAGE], "child is required");
+
+        let response = app.oneshot(call("GetProgress", "wrong", Vec::new())).await.unwrap();
+        assert_eq!(response.headers()[&GRPC_STATUS], "16");
+    }
+
+    #[tokio::test]
+    async fn tracks_served_content_only_for_callers_with_a_session() {
+        let (state, _) = crate::reading::tests::state_with_story(Vec::new()).await;
+        let app = router("secret").with_state(state.clone());
+        let call = |message: Vec<u8>| {
+            let mut body = vec![0];
+            body.extend_from_slice(&(message.len() as u32).to_be_bytes());
+            body.extend_from_slice(&message);
+            axum::http::Request::post("/thinkaroo.v1.Content/GetReadingContents")
+                .header(header::CONTENT_TYPE, GRPC)
+                .header(header::AUTHORIZATION, "Bearer secret")
+                .body(Body::from(body))
+                .unwrap()
+        };
+
+        for message in [Encoder::new(), Encoder::new().string(4, "tablet")] {
+            let response = app.clone().oneshot(call(message.finish())).await.unwrap();
+            let body = response.into_body().collect().await.unwrap().to_bytes();
+            let contents = protobuf::decode(unframe(&body).unwrap()).unwrap();
+            assert!(contents.contains(&(2, WireValue::Bytes(b"The Lost Kite"))));
+        }
+
+        let unnamed = ServedHistory::load(&state.kv_store, "grpc").await.unwrap();
+        assert_eq!(unnamed, ServedHistory::default());
+        let tablet = ServedHistory::load(&state.kv_store, "tablet").await.unwrap();
+        assert_ne!(tablet, ServedHistory::default());
+    }
+}
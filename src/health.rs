@@ -0,0 +1,158 @@
+use axum::{Json, extract::State, http::StatusCode};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+use crate::{
+    ServiceError, circuit_breaker::CircuitState, keyvalue::KeyValueStore, state::AppState,
+    storage::ObjectStore,
+};
+
+/// Prefix listed to check that the object store is reachable
+const OBJECT_STORE_PROBE_PREFIX: &str = "health/";
+
+/// Key read to check that the key-value store is reachable
+const KV_STORE_PROBE_KEY: &str = "health#probe";
+
+/// How long each dependency check may take before it counts as down
+const CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Outcome of checking one dependency
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct DependencyStatus {
+    /// Whether the dependency is usable
+    pub healthy: bool,
+
+    /// Whether the service is unready while this dependency is down
+    pub required: bool,
+
+    /// How long the check took
+    pub latency_ms: u64,
+
+    /// Why the dependency is unhealthy
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Readiness of the service and each of its dependencies
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct ReadinessReport {
+    /// Whether every required dependency is healthy
+    pub ready: bool,
+
+    /// Status of each dependency, keyed by name
+    pub dependencies: BTreeMap<String, DependencyStatus>,
+}
+
+/// Runs a dependency check with a timeout, timing it
+async fn check(
+    required: bool,
+    probe: impl Future<Output = Result<(), ServiceError>>,
+) -> DependencyStatus {
+    let started = Instant::now();
+    let error = match tokio::time::timeout(CHECK_TIMEOUT, probe).await {
+        Ok(Ok(())) => None,
+        Ok(Err(e)) => Some(e.to_string()),
+        Err(_) => Some(format!("timed out after {:?}", CHECK_TIMEOUT)),
+    };
+
+    DependencyStatus {
+        healthy: error.is_none(),
+        required,
+        latency_ms: started.elapsed().as_millis() as u64,
+        error,
+    }
+}
+
+/// Checks every dependency the service needs to serve requests
+///
+/// The object store and key-value store are required. The LLM is optional, since
+/// cached content can still be served without it, and is reported from the circuit
+/// breaker's state rather than by calling the provider.
+pub async fn readiness<S: ObjectStore, K: KeyValueStore>(state: &AppState<S, K>) -> ReadinessReport {
+    let (object_store, kv_store) = tokio::join!(
+        check(true, async {
+            state
+                .object_store
+                .list_objects_page(OBJECT_STORE_PROBE_PREFIX, None)
+                .await
+                .map(|_| ())
+        }),
+        check(true, async {
+            state
+                .kv_store
+                .get(KV_STORE_PROBE_KEY.to_string(), Vec::new())
+                .await
+                .map(|_| ())
+        }),
+    );
+
+    let llm = check(false, async {
+        match state.llm_circuit.state() {
+            CircuitState::Open { .. } => Err(ServiceError::OpenAIError(
+                "circuit breaker is open after repeated failures".to_string(),
+            )),
+            _ => Ok(()),
+        }
+    })
+    .await;
+
+    let dependencies = BTreeMap::from([
+        ("object_store".to_string(), object_store),
+        ("kv_store".to_string(), kv_store),
+        ("llm".to_string(), llm),
+    ]);
+    let ready = dependencies
+        .values()
+        .all(|status| status.healthy || !status.required);
+
+    ReadinessReport { ready, dependencies }
+}
+
+/// Reports readiness, with 503 Service Unavailable when a required dependency is down
+pub async fn ready<S: ObjectStore, K: KeyValueStore>(
+    State(state): State<AppState<S, K>>,
+) -> (StatusCode, Json<ReadinessReport>) {
+    let report = readiness(&state).await;
+    let status = if report.ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+
+    (status, Json(report))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::content::ContentTypeRegistry;
+    use crate::keyvalue::MemoryKeyValueStore;
+    use crate::storage::MemoryObjectStore;
+
+    #[tokio::test]
+    async fn ready_when_stores_respond() {
+        let state = AppState::new(
+            MemoryObjectStore::new(),
+            MemoryKeyValueStore::new(),
+            Config::default(),
+            ContentTypeRegistry::new(),
+        )
+        .await;
+
+        let (status, Json(report)) = ready(State(state)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(report.ready);
+        assert_eq!(report.dependencies.len(), 3);
+        assert!(report.dependencies.values().all(|status| status.healthy));
+    }
+
+    #[tokio::test]
+    async fn reports_failed_checks() {
+        let failed = check(true, async { Err(ServiceError::S3Error("unreachable".into())) }).await;
+        assert!(!failed.healthy);
+        assert_eq!(failed.error.as_deref(), Some("S3 error: unreachable"));
+    }
+}
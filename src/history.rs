@@ -0,0 +1,152 @@
+use axum::{
+    Json,
+    extract::{Query, State},
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    ServiceError,
+    content::{ContentParams, ContentSchema, ContentTypeDescriptor},
+    keyvalue::KeyValueStore,
+    problem::ErrorResponse,
+    reading::ReadingQuery,
+    session::Session,
+    state::AppState,
+    storage::ObjectStore,
+    validation::{BannedWords, ContentValidator, MinItems, NoEmptyFields, WordCount},
+};
+
+/// Storage prefix and registry identifier for history passages
+pub const HISTORY_PREFIX: &str = "history";
+
+/// Grade used when the request doesn't specify one
+const DEFAULT_GRADE: u8 = 4;
+
+/// Fewest events on a timeline
+const MIN_EVENTS: usize = 3;
+
+/// Query parameters accepted by the history passage endpoint
+#[derive(Debug, Deserialize, Default, JsonSchema)]
+pub struct HistoryQuery {
+    /// Grade level the passage should target
+    pub grade: Option<u8>,
+
+    /// Period, place, or person the passage should be about (e.g., "ancient egypt")
+    pub topic: Option<String>,
+}
+
+impl HistoryQuery {
+    /// Validates the query and converts it into content parameters, accepting the
+    /// same grades and topics as reading passages
+    pub fn into_params(self) -> Result<ContentParams, ServiceError> {
+        let grade = Some(self.grade.unwrap_or(DEFAULT_GRADE));
+        ReadingQuery { grade, topic: self.topic, ..Default::default() }.into_params()
+    }
+}
+
+/// Something that happened, and when
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, JsonSchema)]
+pub struct TimelineEvent {
+    /// Year of the event, negative for years BCE
+    pub year: i32,
+    pub event: String,
+}
+
+/// A nonfiction history passage with a timeline of the events it covers
+#[derive(Serialize, Deserialize, Clone, JsonSchema)]
+pub struct HistoryPassage {
+    pub title: String,
+    pub passage: String,
+
+    /// Events from the passage, earliest first
+    pub timeline: Vec<TimelineEvent>,
+
+    pub questions: Vec<String>,
+}
+
+/// Requires a passage's timeline to run from its earliest event to its latest
+#[derive(Debug, Clone, Default)]
+pub struct TimelineInOrder;
+
+impl ContentValidator for TimelineInOrder {
+    fn validate(&self, content: &Value, _params: &ContentParams) -> Result<(), String> {
+        let timeline: Vec<TimelineEvent> = content
+            .get("timeline")
+            .cloned()
+            .and_then(|timeline| serde_json::from_value(timeline).ok())
+            .ok_or_else(|| "missing array field \"timeline\"".to_string())?;
+
+        match timeline.windows(2).find(|pair| pair[1].year < pair[0].year) {
+            Some(pair) => Err(format!(
+                "timeline goes back from {} to {}",
+                pair[0].year, pair[1].year
+            )),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Returns the content type descriptor for history passages
+pub fn descriptor() -> ContentTypeDescriptor {
+    ContentTypeDescriptor::new(
+        HISTORY_PREFIX,
+        "history_passage",
+        ContentSchema::for_type::<HistoryPassage>(
+            "HistoryPassage",
+            "A nonfiction history passage with a timeline and questions",
+        ),
+    )
+    .with_validator(WordCount::new("passage", 100, 400))
+    .with_validator(MinItems::new("timeline", MIN_EVENTS))
+    .with_validator(TimelineInOrder)
+    .with_validator(MinItems::new("questions", 3))
+    .with_validator(NoEmptyFields)
+    .with_validator(BannedWords::default())
+    .with_dedup_field("passage")
+    .with_default_params(ContentParams::new().with("grade", DEFAULT_GRADE))
+}
+
+/// Serves a cached history passage the session hasn't read, or generates a new one
+pub async fn history_passages<S: ObjectStore, K: KeyValueStore>(
+    State(state): State<AppState<S, K>>,
+    Query(query): Query<HistoryQuery>,
+    session: Session,
+) -> Result<Json<HistoryPassage>, ErrorResponse> {
+    let params = query.into_params()?;
+
+    let descriptor = state
+        .content_types
+        .get(HISTORY_PREFIX)
+        .ok_or_else(|| ServiceError::ConfigError(HISTORY_PREFIX.into()))?;
+
+    let session_id = session.id();
+    let params = state
+        .with_prompt_variant(descriptor, params, Some(session_id))
+        .await?;
+
+    let passage: HistoryPassage = state
+        .get_or_generate(descriptor, &params, Some(session_id))
+        .await?;
+
+    Ok(Json(passage))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn requires_timelines_in_order() {
+        let timeline = |years: &[i32]| {
+            let events: Vec<Value> = years.iter().map(|year| json!({ "year": year, "event": "Something" })).collect();
+            json!({ "timeline": events })
+        };
+        let params = ContentParams::new();
+        assert!(TimelineInOrder.validate(&timeline(&[-2560, -1332, -1332, 1922]), &params).is_ok());
+        assert!(TimelineInOrder.validate(&timeline(&[1492, 1776, 1620]), &params).is_err());
+        assert!(TimelineInOrder.validate(&json!({ "passage": "..." }), &params).is_err());
+    }
+}
@@ -0,0 +1,284 @@
+use axum::{
+    body::{Body, Bytes},
+    extract::{Request, State},
+    http::{HeaderName, HeaderValue, Method, StatusCode, header},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::time::Duration;
+use tracing::{error, warn};
+
+use crate::{
+    keyvalue::{Column, KeyValueStore, TypedKv, encode_json},
+    metrics,
+    problem::ErrorResponse,
+    session::Session,
+    state::AppState,
+    storage::ObjectStore,
+};
+
+/// Header a client sets to make retrying a submission safe
+pub const IDEMPOTENCY_KEY: HeaderName = HeaderName::from_static("idempotency-key");
+
+/// Header marking a response replayed for a repeated key
+pub const IDEMPOTENT_REPLAYED: HeaderName = HeaderName::from_static("idempotent-replayed");
+
+/// Longest idempotency key accepted
+const MAX_KEY_LEN: usize = 255;
+
+/// Largest request or response body remembered
+const MAX_BODY_BYTES: usize = 1 << 20;
+
+/// How long a key's response is replayed for
+const RECORD_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// How long a key stays claimed by a request still in progress, so one that
+/// never finishes (say, its instance crashed) doesn't block retries for good
+const PENDING_TTL: Duration = Duration::from_secs(5 * 60);
+
+/// Column holding an `IdempotencyRecord`
+const RECORD_COLUMN: &str = "record";
+
+/// What is remembered about the first request with a key
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct IdempotencyRecord {
+    /// Hash of the request's method, path, and body, so a key reused for a
+    /// different request is caught
+    fingerprint: String,
+
+    /// The response, once the request has finished
+    response: Option<StoredResponse>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct StoredResponse {
+    status: u16,
+    content_type: Option<String>,
+    body: String,
+}
+
+impl StoredResponse {
+    fn into_response(self) -> Response {
+        let mut response = Response::new(Body::from(self.body));
+        *response.status_mut() = StatusCode::from_u16(self.status).unwrap_or(StatusCode::OK);
+        if let Some(value) = self.content_type.and_then(|value| HeaderValue::from_str(&value).ok()) {
+            response.headers_mut().insert(header::CONTENT_TYPE, value);
+        }
+        response.headers_mut().insert(IDEMPOTENT_REPLAYED, HeaderValue::from_static("true"));
+        response
+    }
+}
+
+fn item_key(session_id: &str, key: &str) -> String {
+    format!("idempotency#{}#{}", session_id, key)
+}
+
+fn fingerprint(request: &Request, body: &[u8]) -> String {
+    let mut hash = Sha256::new();
+    hash.update(request.method().as_str());
+    hash.update(b" ");
+    hash.update(request.uri().path());
+    hash.update(b"\n");
+    hash.update(body);
+    hash.finalize().iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Middleware that lets clients safely retry a submission by sending an
+/// `Idempotency-Key` header
+///
+/// The first POST with a key runs as usual and its response is remembered for a
+/// day; a repeat with the same key and body gets that response back, marked
+/// `Idempotent-Replayed`, without running again. A repeat while the first is still
+/// running gets 409, and the same key with a different body gets 422. Keys are
+/// scoped to the session, so clients can't see each other's responses. Server
+/// errors aren't remembered, so the request can be retried. Requests without the
+/// header, and methods other than POST, pass through. Routes must run inside the
+/// `session` middleware.
+pub async fn idempotency<S: ObjectStore, K: KeyValueStore>(
+    State(state): State<AppState<S, K>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let Some(key) = request.headers().get(&IDEMPOTENCY_KEY).cloned() else {
+        return next.run(request).await;
+    };
+    let Some(session) = request.extensions().get::<Session>().cloned() else {
+        return next.run(request).await;
+    };
+    if request.method() != Method::POST {
+        return next.run(request).await;
+    }
+    let key = match key.to_str() {
+        Ok(key) if !key.is_empty() && key.len() <= MAX_KEY_LEN => key.to_string(),
+        _ => {
+            return ErrorResponse::new(
+                StatusCode::BAD_REQUEST,
+                "invalid_idempotency_key",
+                format!("Idempotency-Key must be 1 to {} visible ASCII characters", MAX_KEY_LEN),
+            )
+            .into_response();
+        }
+    };
+
+    let (parts, body) = request.into_parts();
+    let body = match axum::body::to_bytes(body, MAX_BODY_BYTES).await {
+        Ok(body) => body,
+        Err(_) => {
+            return ErrorResponse::new(StatusCode::PAYLOAD_TOO_LARGE, "payload_too_large", "Request body is too large")
+                .into_response();
+        }
+    };
+    let request = Request::from_parts(parts, Body::from(body.clone()));
+    let fingerprint = fingerprint(&request, &body);
+    let item_key = item_key(session.id(), &key);
+
+    let pending = IdempotencyRecord { fingerprint: fingerprint.clone(), response: None };
+    let claimed = match encode_json(&pending) {
+        Ok(value) => state
+            .kv_store
+            .put_if_not_exists(item_key.clone(), vec![Column::new(RECORD_COLUMN.to_string(), value)], Some(PENDING_TTL))
+            .await,
+        Err(e) => Err(e),
+    };
+    match claimed {
+        Ok(true) => {}
+        Ok(false) => return repeat(&state.kv_store, &item_key, &fingerprint).await,
+        Err(e) => return ErrorResponse::from(e).into_response(),
+    }
+
+    let response = next.run(request).await;
+    if response.status().is_server_error() {
+        release(&state.kv_store, &item_key).await;
+        return response;
+    }
+
+    let (parts, body) = response.into_parts();
+    let stored = match axum::body::to_bytes(body, MAX_BODY_BYTES).await.map(|bytes| String::from_utf8(bytes.to_vec())) {
+        Ok(Ok(body)) => StoredResponse {
+            status: parts.status.as_u16(),
+            content_type: parts
+                .headers
+                .get(header::CONTENT_TYPE)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string),
+            body,
+        },
+        _ => {
+            // The request has run, but its response can't be replayed
+            error!("Failed to read the response for idempotency key {}", item_key);
+            release(&state.kv_store, &item_key).await;
+            return ErrorResponse::new(StatusCode::INTERNAL_SERVER_ERROR, "internal_error", "Failed to read the response")
+                .into_response();
+        }
+    };
+    let record = IdempotencyRecord { fingerprint, response: Some(stored.clone()) };
+    if let Err(e) = state.kv_store.put_json(&item_key, RECORD_COLUMN, &record, Some(RECORD_TTL)).await {
+        warn!("Failed to remember the response for idempotency key {}: {}", item_key, e);
+    }
+    Response::from_parts(parts, Body::from(Bytes::from(stored.body)))
+}
+
+/// Answers a request whose key was already claimed
+async fn repeat<K: KeyValueStore>(kv_store: &K, item_key: &str, fingerprint: &str) -> Response {
+    let record: Option<IdempotencyRecord> = match kv_store.get_json(item_key, RECORD_COLUMN).await {
+        Ok(record) => record,
+        Err(e) => return ErrorResponse::from(e).into_response(),
+    };
+    match record {
+        Some(record) if record.fingerprint != fingerprint => ErrorResponse::new(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            "idempotency_key_reused",
+            "Idempotency-Key was already used for a different request",
+        )
+        .into_response(),
+        Some(IdempotencyRecord { response: Some(response), .. }) => {
+            metrics::increment("idempotency.replayed");
+            response.into_response()
+        }
+        // Still running, or expired between the claim and the read
+        _ => ErrorResponse {
+            retryable: true,
+            retry_after: Some(Duration::from_secs(1)),
+            ..ErrorResponse::new(
+                StatusCode::CONFLICT,
+                "idempotency_key_in_use",
+                "A request with this Idempotency-Key is still in progress",
+            )
+        }
+        .into_response(),
+    }
+}
+
+/// Forgets a claimed key, so the request can be retried
+async fn release<K: KeyValueStore>(kv_store: &K, item_key: &str) {
+    if let Err(e) = kv_store.delete(item_key.to_string()).await {
+        warn!("Failed to release idempotency key {}: {}", item_key, e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{config::Config, content::ContentTypeRegistry, keyvalue::MemoryKeyValueStore, session, storage::MemoryObjectStore};
+    use axum::{Router, routing::post};
+    use std::sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    };
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn replays_the_first_response_for_a_key() {
+        let state = AppState::new(
+            MemoryObjectStore::new(),
+            MemoryKeyValueStore::new(),
+            Config::default(),
+            ContentTypeRegistry::new(),
+        )
+        .await;
+        let runs = Arc::new(AtomicUsize::new(0));
+        let counter = runs.clone();
+        let app = Router::new()
+            .route(
+                "/feedback",
+                post(move |body: String| async move {
+                    let run = counter.fetch_add(1, Ordering::SeqCst) + 1;
+                    (StatusCode::CREATED, format!("{} {}", body, run))
+                }),
+            )
+            .route_layer(axum::middleware::from_fn_with_state(state.clone(), idempotency))
+            .route_layer(axum::middleware::from_fn_with_state(state.clone(), session::session))
+            .with_state(state);
+
+        let response = app.clone().oneshot(Request::get("/feedback").body(Body::empty()).unwrap()).await.unwrap();
+        let cookie = response.headers()[header::SET_COOKIE].to_str().unwrap();
+        let cookie = cookie.split(';').next().unwrap().to_string();
+        let submit = |key: &str, body: &'static str| {
+            Request::post("/feedback")
+                .header(header::COOKIE, &cookie)
+                .header(IDEMPOTENCY_KEY, key)
+                .body(Body::from(body))
+                .unwrap()
+        };
+
+        let first = app.clone().oneshot(submit("a", "up")).await.unwrap();
+        assert_eq!(first.status(), StatusCode::CREATED);
+        assert!(first.headers().get(&IDEMPOTENT_REPLAYED).is_none());
+
+        let repeat = app.clone().oneshot(submit("a", "up")).await.unwrap();
+        assert_eq!(repeat.status(), StatusCode::CREATED);
+        assert_eq!(repeat.headers()[&IDEMPOTENT_REPLAYED], "true");
+        let body = axum::body::to_bytes(repeat.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(body, "up 1");
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+
+        let reused = app.clone().oneshot(submit("a", "down")).await.unwrap();
+        assert_eq!(reused.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        let other = app.oneshot(submit("b", "up")).await.unwrap();
+        assert_eq!(other.status(), StatusCode::CREATED);
+        assert_eq!(runs.load(Ordering::SeqCst), 2);
+    }
+}
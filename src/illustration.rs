@@ -0,0 +1,192 @@
+use async_openai::{
+    Client as OpenAIClient,
+    config::OpenAIConfig,
+    types::{CreateImageRequestArgs, Image, ImageModel, ImageQuality, ImageSize},
+};
+use async_trait::async_trait;
+use axum::{
+    body::Body,
+    extract::{Path, State},
+    http::{StatusCode, header},
+    response::Response,
+};
+use base64::{Engine, engine::general_purpose::STANDARD};
+use tracing::error;
+
+use crate::{
+    ServiceError, keyvalue::KeyValueStore, problem::ErrorResponse, state::AppState,
+    storage::ObjectStore,
+};
+
+/// ObjectStore prefix under which illustrations are stored, mirroring the content keys
+pub const ILLUSTRATIONS_PREFIX: &str = "illustrations/";
+
+/// Image model illustrations are drawn with
+const IMAGE_MODEL: &str = "gpt-image-1";
+
+/// Most characters of the illustrated text included in the image prompt
+const MAX_DESCRIPTION_CHARS: usize = 1000;
+
+/// Draws pictures for generated content
+#[async_trait]
+pub trait Illustrator: Send + Sync {
+    /// Draws an illustration for a piece of text
+    ///
+    /// # Returns
+    /// * `Ok(Some(png))` - The illustration, as PNG
+    /// * `Ok(None)` - If illustration is disabled
+    /// * `Err(ServiceError)` - If the image service can't be reached
+    async fn illustrate(&self, text: &str) -> Result<Option<Vec<u8>>, ServiceError>;
+}
+
+/// Illustrator backed by the OpenAI image generation endpoint
+#[derive(Clone)]
+pub struct OpenAIIllustrator {
+    client: OpenAIClient<OpenAIConfig>,
+}
+
+impl OpenAIIllustrator {
+    /// Creates a new OpenAIIllustrator using the given client
+    pub fn new(client: OpenAIClient<OpenAIConfig>) -> Self {
+        Self { client }
+    }
+}
+
+#[async_trait]
+impl Illustrator for OpenAIIllustrator {
+    async fn illustrate(&self, text: &str) -> Result<Option<Vec<u8>>, ServiceError> {
+        let request = CreateImageRequestArgs::default()
+            .model(ImageModel::Other(IMAGE_MODEL.to_string()))
+            .prompt(image_prompt(text))
+            .size(ImageSize::S1024x1024)
+            .quality(ImageQuality::Low)
+            .n(1)
+            .build()
+            .map_err(|e| ServiceError::OpenAIError(format!("Failed to build image request: {}", e)))?;
+
+        let response = self
+            .client
+            .images()
+            .create(request)
+            .await
+            .map_err(|e| ServiceError::from_openai("Image call failed", e))?;
+
+        match response.data.first().map(|image| image.as_ref()) {
+            Some(Image::B64Json { b64_json, .. }) => STANDARD
+                .decode(b64_json.as_bytes())
+                .map(Some)
+                .map_err(|e| ServiceError::OpenAIError(format!("Invalid image data: {}", e))),
+            _ => Err(ServiceError::OpenAIError("No image data in OpenAI response".to_string())),
+        }
+    }
+}
+
+/// Illustrator that draws nothing, for local development and tests
+#[derive(Clone, Default)]
+pub struct NoopIllustrator;
+
+#[async_trait]
+impl Illustrator for NoopIllustrator {
+    async fn illustrate(&self, _text: &str) -> Result<Option<Vec<u8>>, ServiceError> {
+        Ok(None)
+    }
+}
+
+/// Builds the image prompt for a piece of text, cut short if it is long
+pub fn image_prompt(text: &str) -> String {
+    let description: String = text.chars().take(MAX_DESCRIPTION_CHARS).collect();
+    format!(
+        "A single warm, friendly picture-book illustration for young children, in soft \
+         colors, with no words or letters in the image. Nothing scary or violent. \
+         It illustrates this story:\n\n{}",
+        description
+    )
+}
+
+/// Key of the object holding a content object's illustration, e.g.
+/// "illustrations/reading/2025-10-11-14/1a2b.png"
+pub fn illustration_key(object_key: &str) -> String {
+    let stem = object_key.strip_suffix(".json").unwrap_or(object_key);
+    format!("{}{}.png", ILLUSTRATIONS_PREFIX, stem)
+}
+
+/// Draws an illustration for a piece of text and stores it under `key`
+pub async fn illustrate_and_store<S: ObjectStore>(
+    illustrator: &dyn Illustrator,
+    object_store: &S,
+    key: &str,
+    text: &str,
+) -> Result<(), ServiceError> {
+    if let Some(image) = illustrator.illustrate(text).await? {
+        object_store.put_object(key, image).await?;
+    }
+    Ok(())
+}
+
+/// Streams an illustration, given the key referenced by generated content
+///
+/// Illustrations are drawn in the background after content is generated, so one
+/// may briefly be missing.
+pub async fn image<S: ObjectStore, K: KeyValueStore>(
+    State(state): State<AppState<S, K>>,
+    Path(key): Path<String>,
+) -> Result<Response, ErrorResponse> {
+    let is_safe = key.starts_with(ILLUSTRATIONS_PREFIX)
+        && key
+            .split('/')
+            .all(|segment| !segment.is_empty() && segment != "." && segment != "..");
+    if !is_safe {
+        return Err(ServiceError::NotFound("Image not found".to_string()).into());
+    }
+
+    let object = state
+        .object_store
+        .head_object(&key)
+        .await?
+        .ok_or_else(|| ServiceError::NotFound("Image not found".to_string()))?;
+    let stream = state.object_store.get_object_stream(&key).await?;
+
+    Response::builder()
+        .header(header::CONTENT_TYPE, object.content_type.as_deref().unwrap_or("image/png"))
+        .header(header::CONTENT_LENGTH, object.size)
+        .header(header::CACHE_CONTROL, "public, max-age=86400, immutable")
+        .body(Body::from_stream(stream))
+        .map_err(|e| {
+            error!("Failed to build response for {}: {}", key, e);
+            ErrorResponse::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "internal_error",
+                "Internal server error",
+            )
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::MemoryObjectStore;
+
+    struct FixedIllustrator;
+
+    #[async_trait]
+    impl Illustrator for FixedIllustrator {
+        async fn illustrate(&self, text: &str) -> Result<Option<Vec<u8>>, ServiceError> {
+            Ok(Some(text.as_bytes().to_vec()))
+        }
+    }
+
+    #[tokio::test]
+    async fn stores_illustrations_next_to_content() {
+        let key = illustration_key("reading/2025-10-11-14/1a2b.json");
+        assert_eq!(key, "illustrations/reading/2025-10-11-14/1a2b.png");
+
+        let long = "a".repeat(MAX_DESCRIPTION_CHARS * 2);
+        assert!(image_prompt(&long).len() < MAX_DESCRIPTION_CHARS + 300);
+
+        let object_store = MemoryObjectStore::new();
+        illustrate_and_store(&NoopIllustrator, &object_store, &key, "story").await.unwrap();
+        assert!(!object_store.object_exists(&key).await.unwrap());
+        illustrate_and_store(&FixedIllustrator, &object_store, &key, "story").await.unwrap();
+        assert_eq!(object_store.get_object(&key).await.unwrap(), b"story");
+    }
+}
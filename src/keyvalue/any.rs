@@ -0,0 +1,160 @@
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::time::Duration;
+
+use super::{
+    Column, DynamoKeyValueStore, KeyValueStore, MemoryKeyValueStore, Record, RecordQuery,
+    RedisKeyValueStore, SqlKeyValueStore,
+};
+use crate::ServiceError;
+use crate::config::{Config, KvBackend};
+
+/// Key-value store backend chosen at runtime
+///
+/// Lets `main` pick a backend from configuration while `AppState` keeps a single
+/// concrete store type.
+#[derive(Clone)]
+pub enum AnyKeyValueStore {
+    Dynamo(DynamoKeyValueStore),
+    Memory(MemoryKeyValueStore),
+    Redis(RedisKeyValueStore),
+    Sql(SqlKeyValueStore),
+}
+
+/// Calls the same method on whichever backend the store holds
+macro_rules! dispatch {
+    ($self:ident, $store:ident => $call:expr) => {
+        match $self {
+            AnyKeyValueStore::Dynamo($store) => $call,
+            AnyKeyValueStore::Memory($store) => $call,
+            AnyKeyValueStore::Redis($store) => $call,
+            AnyKeyValueStore::Sql($store) => $call,
+        }
+    };
+}
+
+impl AnyKeyValueStore {
+    /// Creates and connects the backend selected by `config.kv_backend()`
+    pub async fn from_config(config: &Config) -> Result<Self, ServiceError> {
+        let missing = |name: &str| ServiceError::ConfigError(format!("{} must be set", name));
+
+        Ok(match config.kv_backend() {
+            KvBackend::Dynamo => {
+                let aws_config =
+                    aws_config::load_defaults(aws_config::BehaviorVersion::latest()).await;
+                Self::Dynamo(DynamoKeyValueStore::with_tables(
+                    aws_sdk_dynamodb::Client::new(&aws_config),
+                    &config.table,
+                    &config.records_table,
+                ))
+            }
+            KvBackend::Memory => Self::Memory(match &config.memory_kv_path {
+                Some(path) => MemoryKeyValueStore::open(path).await?,
+                None => MemoryKeyValueStore::new(),
+            }),
+            KvBackend::Redis => {
+                let url = config.redis_url.as_deref().ok_or_else(|| missing("REDIS_URL"))?;
+                Self::Redis(RedisKeyValueStore::connect(url).await?)
+            }
+            KvBackend::Sql => {
+                let url = config
+                    .database_url
+                    .as_deref()
+                    .ok_or_else(|| missing("DATABASE_URL"))?;
+                Self::Sql(SqlKeyValueStore::connect(url).await?)
+            }
+        })
+    }
+}
+
+#[async_trait]
+impl KeyValueStore for AnyKeyValueStore {
+    async fn put(
+        &self,
+        key: String,
+        columns: Vec<Column>,
+        ttl: Option<Duration>,
+    ) -> Result<(), ServiceError> {
+        dispatch!(self, store => store.put(key, columns, ttl).await)
+    }
+
+    async fn get(&self, key: String, column_names: Vec<String>) -> Result<Vec<Column>, ServiceError> {
+        dispatch!(self, store => store.get(key, column_names).await)
+    }
+
+    async fn put_if_not_exists(
+        &self,
+        key: String,
+        columns: Vec<Column>,
+        ttl: Option<Duration>,
+    ) -> Result<bool, ServiceError> {
+        dispatch!(self, store => store.put_if_not_exists(key, columns, ttl).await)
+    }
+
+    async fn increment(&self, key: String, column: String, delta: i64) -> Result<i64, ServiceError> {
+        dispatch!(self, store => store.increment(key, column, delta).await)
+    }
+
+    async fn get_versioned(
+        &self,
+        key: String,
+        column_names: Vec<String>,
+    ) -> Result<(u64, Vec<Column>), ServiceError> {
+        dispatch!(self, store => store.get_versioned(key, column_names).await)
+    }
+
+    async fn put_if_version(
+        &self,
+        key: String,
+        columns: Vec<Column>,
+        expected_version: u64,
+    ) -> Result<bool, ServiceError> {
+        dispatch!(self, store => store.put_if_version(key, columns, expected_version).await)
+    }
+
+    async fn delete(&self, key: String) -> Result<(), ServiceError> {
+        dispatch!(self, store => store.delete(key).await)
+    }
+
+    async fn batch_get(
+        &self,
+        keys: Vec<String>,
+        column_names: Vec<String>,
+    ) -> Result<HashMap<String, Vec<Column>>, ServiceError> {
+        dispatch!(self, store => store.batch_get(keys, column_names).await)
+    }
+
+    async fn batch_put(&self, items: Vec<(String, Vec<Column>)>) -> Result<(), ServiceError> {
+        dispatch!(self, store => store.batch_put(items).await)
+    }
+
+    async fn scan_prefix(
+        &self,
+        prefix: String,
+        column_names: Vec<String>,
+    ) -> Result<Vec<(String, Vec<Column>)>, ServiceError> {
+        dispatch!(self, store => store.scan_prefix(prefix, column_names).await)
+    }
+
+    async fn put_record(
+        &self,
+        partition_key: String,
+        sort_key: String,
+        columns: Vec<Column>,
+        ttl: Option<Duration>,
+    ) -> Result<(), ServiceError> {
+        dispatch!(self, store => store.put_record(partition_key, sort_key, columns, ttl).await)
+    }
+
+    async fn query_records(
+        &self,
+        query: RecordQuery,
+        column_names: Vec<String>,
+    ) -> Result<Vec<Record>, ServiceError> {
+        dispatch!(self, store => store.query_records(query, column_names).await)
+    }
+
+    async fn delete_record(&self, partition_key: String, sort_key: String) -> Result<(), ServiceError> {
+        dispatch!(self, store => store.delete_record(partition_key, sort_key).await)
+    }
+}
@@ -0,0 +1,162 @@
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::time::Duration;
+
+use super::{Column, KeyValueStore, Record, RecordQuery};
+use crate::{ServiceError, metrics};
+
+/// Key-value store wrapper that records call counts, errors, and latency per operation
+///
+/// See `metrics::record_operation` for the counters maintained.
+#[derive(Clone)]
+pub struct InstrumentedKeyValueStore<K: KeyValueStore> {
+    inner: K,
+    name: String,
+}
+
+impl<K: KeyValueStore> InstrumentedKeyValueStore<K> {
+    /// Wraps a store, naming its metrics `{name}.{operation}.*`
+    pub fn new(inner: K, name: &str) -> Self {
+        Self {
+            inner,
+            name: name.to_string(),
+        }
+    }
+}
+
+#[async_trait]
+impl<K: KeyValueStore> KeyValueStore for InstrumentedKeyValueStore<K> {
+    async fn put(
+        &self,
+        key: String,
+        columns: Vec<Column>,
+        ttl: Option<Duration>,
+    ) -> Result<(), ServiceError> {
+        metrics::record_operation(&self.name, "put", self.inner.put(key, columns, ttl)).await
+    }
+
+    async fn get(&self, key: String, column_names: Vec<String>) -> Result<Vec<Column>, ServiceError> {
+        metrics::record_operation(&self.name, "get", self.inner.get(key, column_names)).await
+    }
+
+    async fn put_if_not_exists(
+        &self,
+        key: String,
+        columns: Vec<Column>,
+        ttl: Option<Duration>,
+    ) -> Result<bool, ServiceError> {
+        metrics::record_operation(
+            &self.name,
+            "put_if_not_exists",
+            self.inner.put_if_not_exists(key, columns, ttl),
+        )
+        .await
+    }
+
+    async fn increment(&self, key: String, column: String, delta: i64) -> Result<i64, ServiceError> {
+        metrics::record_operation(
+            &self.name,
+            "increment",
+            self.inner.increment(key, column, delta),
+        )
+        .await
+    }
+
+    async fn get_versioned(
+        &self,
+        key: String,
+        column_names: Vec<String>,
+    ) -> Result<(u64, Vec<Column>), ServiceError> {
+        metrics::record_operation(
+            &self.name,
+            "get_versioned",
+            self.inner.get_versioned(key, column_names),
+        )
+        .await
+    }
+
+    async fn put_if_version(
+        &self,
+        key: String,
+        columns: Vec<Column>,
+        expected_version: u64,
+    ) -> Result<bool, ServiceError> {
+        metrics::record_operation(
+            &self.name,
+            "put_if_version",
+            self.inner.put_if_version(key, columns, expected_version),
+        )
+        .await
+    }
+
+    async fn delete(&self, key: String) -> Result<(), ServiceError> {
+        metrics::record_operation(&self.name, "delete", self.inner.delete(key)).await
+    }
+
+    async fn batch_get(
+        &self,
+        keys: Vec<String>,
+        column_names: Vec<String>,
+    ) -> Result<HashMap<String, Vec<Column>>, ServiceError> {
+        metrics::record_operation(
+            &self.name,
+            "batch_get",
+            self.inner.batch_get(keys, column_names),
+        )
+        .await
+    }
+
+    async fn batch_put(&self, items: Vec<(String, Vec<Column>)>) -> Result<(), ServiceError> {
+        metrics::record_operation(&self.name, "batch_put", self.inner.batch_put(items)).await
+    }
+
+    async fn scan_prefix(
+        &self,
+        prefix: String,
+        column_names: Vec<String>,
+    ) -> Result<Vec<(String, Vec<Column>)>, ServiceError> {
+        metrics::record_operation(
+            &self.name,
+            "scan_prefix",
+            self.inner.scan_prefix(prefix, column_names),
+        )
+        .await
+    }
+
+    async fn put_record(
+        &self,
+        partition_key: String,
+        sort_key: String,
+        columns: Vec<Column>,
+        ttl: Option<Duration>,
+    ) -> Result<(), ServiceError> {
+        metrics::record_operation(
+            &self.name,
+            "put_record",
+            self.inner.put_record(partition_key, sort_key, columns, ttl),
+        )
+        .await
+    }
+
+    async fn query_records(
+        &self,
+        query: RecordQuery,
+        column_names: Vec<String>,
+    ) -> Result<Vec<Record>, ServiceError> {
+        metrics::record_operation(
+            &self.name,
+            "query_records",
+            self.inner.query_records(query, column_names),
+        )
+        .await
+    }
+
+    async fn delete_record(&self, partition_key: String, sort_key: String) -> Result<(), ServiceError> {
+        metrics::record_operation(
+            &self.name,
+            "delete_record",
+            self.inner.delete_record(partition_key, sort_key),
+        )
+        .await
+    }
+}
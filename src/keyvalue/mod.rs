@@ -0,0 +1,1465 @@
+use async_trait::async_trait;
+use aws_sdk_dynamodb::Client as DynamoDbClient;
+use aws_sdk_dynamodb::types::{
+    AttributeValue, KeysAndAttributes, PutRequest, ReturnValue, WriteRequest,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::time::Duration;
+use tokio::fs;
+use tokio::sync::{Mutex, RwLock};
+
+use crate::ServiceError;
+
+mod any;
+mod instrumented;
+mod redis;
+mod retrying;
+mod sql;
+mod typed;
+
+pub use any::AnyKeyValueStore;
+pub use self::redis::RedisKeyValueStore;
+pub use instrumented::InstrumentedKeyValueStore;
+pub use retrying::RetryingKeyValueStore;
+pub use sql::SqlKeyValueStore;
+pub use typed::{TypedKv, column_json, decode_json, encode_json};
+
+/// Default DynamoDB table name for key-value storage
+pub const DEFAULT_DYNAMODB_TABLE_NAME: &str = "thinkaroo-data";
+
+/// Default DynamoDB table name for records, keyed by partition key and sort key
+pub const DEFAULT_DYNAMODB_RECORDS_TABLE_NAME: &str = "thinkaroo-records";
+
+/// Primary key attribute name in DynamoDB
+const PRIMARY_KEY_ATTR: &str = "pk";
+
+/// Sort key attribute name in the records table
+const SORT_KEY_ATTR: &str = "sk";
+
+/// Attribute holding the expiry time (Unix seconds) of items written with a TTL
+///
+/// The table's TTL setting must be enabled on this attribute for DynamoDB to delete
+/// expired items; until it does, reads treat them as missing.
+const TTL_ATTR: &str = "expires_at";
+
+/// Attribute holding the version of items written with `put_if_version`
+const VERSION_ATTR: &str = "_version";
+
+/// Maximum number of keys in a DynamoDB BatchGetItem request
+const BATCH_GET_LIMIT: usize = 100;
+
+/// Maximum number of items in a DynamoDB BatchWriteItem request
+const BATCH_WRITE_LIMIT: usize = 25;
+
+/// Represents a column with a name and binary value
+#[derive(Debug, Clone, PartialEq)]
+pub struct Column {
+    pub name: String,
+    pub value: Vec<u8>,
+}
+
+impl Column {
+    /// Creates a new Column
+    pub fn new(name: String, value: Vec<u8>) -> Self {
+        Self { name, value }
+    }
+
+    /// Creates a counter column, stored as a big-endian `i64`
+    pub fn counter(name: String, value: i64) -> Self {
+        Self::new(name, value.to_be_bytes().to_vec())
+    }
+
+    /// Reads the column as a counter maintained by `increment`
+    pub fn as_counter(&self) -> Option<i64> {
+        <[u8; 8]>::try_from(self.value.as_slice())
+            .ok()
+            .map(i64::from_be_bytes)
+    }
+}
+
+/// An item stored under a partition key and sort key (see `KeyValueStore::put_record`)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Record {
+    pub sort_key: String,
+    pub columns: Vec<Column>,
+}
+
+/// Selects the records of one partition key, in sort key order
+///
+/// # Examples
+///
+/// ```
+/// use thinkaroo::keyvalue::RecordQuery;
+///
+/// // The 10 most recent attempts, with sort keys like "attempt#2025-10-11T14:03:00Z"
+/// let query = RecordQuery::new("user#42")
+///     .with_prefix("attempt#")
+///     .descending()
+///     .limit(10);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordQuery {
+    pub partition_key: String,
+    pub condition: SortKeyCondition,
+    pub descending: bool,
+    pub limit: Option<usize>,
+}
+
+/// Restricts which sort keys a `RecordQuery` matches
+#[derive(Debug, Clone, PartialEq)]
+pub enum SortKeyCondition {
+    /// Every record in the partition
+    All,
+
+    /// Sort keys starting with a prefix
+    Prefix(String),
+
+    /// Sort keys between two values, inclusive
+    Between(String, String),
+}
+
+impl RecordQuery {
+    /// Selects every record of a partition key, in ascending sort key order
+    pub fn new(partition_key: impl Into<String>) -> Self {
+        Self {
+            partition_key: partition_key.into(),
+            condition: SortKeyCondition::All,
+            descending: false,
+            limit: None,
+        }
+    }
+
+    /// Only matches sort keys starting with a prefix
+    pub fn with_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.condition = SortKeyCondition::Prefix(prefix.into());
+        self
+    }
+
+    /// Only matches sort keys between two values, inclusive
+    pub fn between(mut self, from: impl Into<String>, to: impl Into<String>) -> Self {
+        self.condition = SortKeyCondition::Between(from.into(), to.into());
+        self
+    }
+
+    /// Returns records in descending sort key order
+    pub fn descending(mut self) -> Self {
+        self.descending = true;
+        self
+    }
+
+    /// Returns at most this many records
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Whether a sort key satisfies the query's condition
+    pub fn matches(&self, sort_key: &str) -> bool {
+        match &self.condition {
+            SortKeyCondition::All => true,
+            SortKeyCondition::Prefix(prefix) => sort_key.starts_with(prefix.as_str()),
+            SortKeyCondition::Between(from, to) => {
+                sort_key >= from.as_str() && sort_key <= to.as_str()
+            }
+        }
+    }
+}
+
+/// KeyValueStore trait for abstracting key-value storage operations
+///
+/// This trait provides a common interface for put, get, delete, batch, and scan operations,
+/// allowing implementations using different backends (DynamoDB, in-memory, etc.)
+#[async_trait]
+pub trait KeyValueStore: Clone + Send + Sync {
+    /// Stores columns associated with a key
+    ///
+    /// # Arguments
+    /// * `key` - The primary key for the item
+    /// * `columns` - The columns to store (name and binary value pairs)
+    /// * `ttl` - How long the item lives before it is treated as deleted, or `None`
+    ///   to keep it until it is deleted
+    ///
+    /// # Returns
+    /// * `Ok(())` - If the item was successfully stored
+    /// * `Err(ServiceError)` - If storage operations fail
+    async fn put(
+        &self,
+        key: String,
+        columns: Vec<Column>,
+        ttl: Option<Duration>,
+    ) -> Result<(), ServiceError>;
+
+    /// Retrieves specific columns for a key
+    ///
+    /// # Arguments
+    /// * `key` - The primary key for the item
+    /// * `column_names` - The names of columns to retrieve
+    ///
+    /// # Returns
+    /// * `Ok(Vec<Column>)` - The retrieved columns (may be empty if key doesn't exist)
+    /// * `Err(ServiceError)` - If retrieval fails
+    async fn get(&self, key: String, column_names: Vec<String>) -> Result<Vec<Column>, ServiceError>;
+
+    /// Stores columns associated with a key only if the key doesn't exist yet
+    ///
+    /// # Arguments
+    /// * `key` - The primary key for the item
+    /// * `columns` - The columns to store (name and binary value pairs)
+    /// * `ttl` - How long the item lives before it is treated as deleted, or `None`
+    ///   to keep it until it is deleted
+    ///
+    /// # Returns
+    /// * `Ok(true)` - If the item was created
+    /// * `Ok(false)` - If an unexpired item with the key already exists (nothing is written)
+    /// * `Err(ServiceError)` - If storage operations fail
+    async fn put_if_not_exists(
+        &self,
+        key: String,
+        columns: Vec<Column>,
+        ttl: Option<Duration>,
+    ) -> Result<bool, ServiceError>;
+
+    /// Atomically adds to a counter column, creating the item and column if needed
+    ///
+    /// Counter columns hold a big-endian `i64` (see `Column::as_counter`).
+    ///
+    /// # Arguments
+    /// * `key` - The primary key for the item
+    /// * `column` - The name of the counter column
+    /// * `delta` - The amount to add (negative to subtract)
+    ///
+    /// # Returns
+    /// * `Ok(i64)` - The counter's value after the increment
+    /// * `Err(ServiceError)` - If the update fails
+    async fn increment(&self, key: String, column: String, delta: i64) -> Result<i64, ServiceError>;
+
+    /// Retrieves specific columns for a key along with the item's version
+    ///
+    /// # Arguments
+    /// * `key` - The primary key for the item
+    /// * `column_names` - The names of columns to retrieve
+    ///
+    /// # Returns
+    /// * `Ok((u64, Vec<Column>))` - The item's version (0 if it doesn't exist or was
+    ///   never written with `put_if_version`) and the retrieved columns
+    /// * `Err(ServiceError)` - If retrieval fails
+    async fn get_versioned(
+        &self,
+        key: String,
+        column_names: Vec<String>,
+    ) -> Result<(u64, Vec<Column>), ServiceError>;
+
+    /// Replaces an item only if its version is still the expected one
+    ///
+    /// Used for optimistic concurrency: read the item with `get_versioned`, compute
+    /// the new columns, then write them back with the version that was read. The
+    /// stored version is incremented on every successful write.
+    ///
+    /// # Arguments
+    /// * `key` - The primary key for the item
+    /// * `columns` - The columns to store, replacing all existing columns
+    /// * `expected_version` - The version read by `get_versioned` (0 for a new item)
+    ///
+    /// # Returns
+    /// * `Ok(true)` - If the item was written
+    /// * `Ok(false)` - If the item was changed by another writer (nothing is written)
+    /// * `Err(ServiceError)` - If storage operations fail
+    async fn put_if_version(
+        &self,
+        key: String,
+        columns: Vec<Column>,
+        expected_version: u64,
+    ) -> Result<bool, ServiceError>;
+
+    /// Deletes the item with a key
+    ///
+    /// Deleting a key that doesn't exist is not an error.
+    ///
+    /// # Arguments
+    /// * `key` - The primary key for the item
+    ///
+    /// # Returns
+    /// * `Ok(())` - If the item was deleted or didn't exist
+    /// * `Err(ServiceError)` - If deletion fails
+    async fn delete(&self, key: String) -> Result<(), ServiceError>;
+
+    /// Retrieves specific columns for several keys
+    ///
+    /// The default implementation calls `get` for each key; backends with a batch
+    /// API should override it.
+    ///
+    /// # Arguments
+    /// * `keys` - The primary keys of the items
+    /// * `column_names` - The names of columns to retrieve from each item
+    ///
+    /// # Returns
+    /// * `Ok(HashMap<String, Vec<Column>>)` - The retrieved columns by key; keys
+    ///   without any of the requested columns are omitted
+    /// * `Err(ServiceError)` - If retrieval fails
+    async fn batch_get(
+        &self,
+        keys: Vec<String>,
+        column_names: Vec<String>,
+    ) -> Result<HashMap<String, Vec<Column>>, ServiceError> {
+        let mut items = HashMap::new();
+
+        for key in keys {
+            let columns = self.get(key.clone(), column_names.clone()).await?;
+            if !columns.is_empty() {
+                items.insert(key, columns);
+            }
+        }
+
+        Ok(items)
+    }
+
+    /// Stores several items
+    ///
+    /// The default implementation calls `put` for each item; backends with a batch
+    /// API should override it. Items aren't written atomically.
+    ///
+    /// # Arguments
+    /// * `items` - The primary key and columns of each item
+    ///
+    /// # Returns
+    /// * `Ok(())` - If every item was successfully stored
+    /// * `Err(ServiceError)` - If storage operations fail
+    async fn batch_put(&self, items: Vec<(String, Vec<Column>)>) -> Result<(), ServiceError> {
+        for (key, columns) in items {
+            self.put(key, columns, None).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Retrieves specific columns of every item whose key starts with a prefix
+    ///
+    /// # Arguments
+    /// * `prefix` - The key prefix to match (e.g., "experiment#reading_comprehension#")
+    /// * `column_names` - The names of columns to retrieve from each item
+    ///
+    /// # Returns
+    /// * `Ok(Vec<(String, Vec<Column>)>)` - The matching keys and their columns, in key order
+    /// * `Err(ServiceError)` - If the scan fails
+    async fn scan_prefix(
+        &self,
+        prefix: String,
+        column_names: Vec<String>,
+    ) -> Result<Vec<(String, Vec<Column>)>, ServiceError>;
+
+    /// Stores a record under a partition key and sort key
+    ///
+    /// Records are kept separately from items stored with `put`, and are read back
+    /// in sort key order with `query_records` (e.g., a user's attempt history with
+    /// timestamped sort keys).
+    ///
+    /// # Arguments
+    /// * `partition_key` - The key grouping related records (e.g., "user#42")
+    /// * `sort_key` - The key ordering records within the partition
+    /// * `columns` - The columns to store, replacing any existing record
+    /// * `ttl` - How long the record lives before it is treated as deleted, or `None`
+    ///
+    /// # Returns
+    /// * `Ok(())` - If the record was successfully stored
+    /// * `Err(ServiceError)` - If storage operations fail
+    async fn put_record(
+        &self,
+        partition_key: String,
+        sort_key: String,
+        columns: Vec<Column>,
+        ttl: Option<Duration>,
+    ) -> Result<(), ServiceError>;
+
+    /// Retrieves specific columns of the records matching a query
+    ///
+    /// # Arguments
+    /// * `query` - The partition key, sort key condition, order, and limit
+    /// * `column_names` - The names of columns to retrieve from each record
+    ///
+    /// # Returns
+    /// * `Ok(Vec<Record>)` - The matching records in the query's order
+    /// * `Err(ServiceError)` - If the query fails
+    async fn query_records(
+        &self,
+        query: RecordQuery,
+        column_names: Vec<String>,
+    ) -> Result<Vec<Record>, ServiceError>;
+
+    /// Deletes a record
+    ///
+    /// Deleting a record that doesn't exist is not an error.
+    ///
+    /// # Arguments
+    /// * `partition_key` - The record's partition key
+    /// * `sort_key` - The record's sort key
+    ///
+    /// # Returns
+    /// * `Ok(())` - If the record was deleted or didn't exist
+    /// * `Err(ServiceError)` - If deletion fails
+    async fn delete_record(&self, partition_key: String, sort_key: String) -> Result<(), ServiceError>;
+}
+
+/// DynamoDB-based key-value store implementation
+#[derive(Clone)]
+pub struct DynamoKeyValueStore {
+    client: DynamoDbClient,
+    table: String,
+    records_table: String,
+}
+
+impl DynamoKeyValueStore {
+    /// Creates a new DynamoKeyValueStore instance using the default table names
+    pub fn new(client: DynamoDbClient) -> Self {
+        Self::with_tables(
+            client,
+            DEFAULT_DYNAMODB_TABLE_NAME,
+            DEFAULT_DYNAMODB_RECORDS_TABLE_NAME,
+        )
+    }
+
+    /// Creates a new DynamoKeyValueStore instance using the given tables for items
+    /// and records
+    pub fn with_tables(client: DynamoDbClient, table: &str, records_table: &str) -> Self {
+        Self {
+            client,
+            table: table.to_string(),
+            records_table: records_table.to_string(),
+        }
+    }
+
+    /// Builds a DynamoDB item from a key, its columns, and an optional TTL
+    fn build_item(
+        key: String,
+        columns: Vec<Column>,
+        ttl: Option<Duration>,
+    ) -> HashMap<String, AttributeValue> {
+        let mut item = HashMap::new();
+
+        // Add primary key
+        item.insert(
+            PRIMARY_KEY_ATTR.to_string(),
+            AttributeValue::S(key),
+        );
+
+        // Add all columns as binary attributes
+        for column in columns {
+            item.insert(
+                column.name,
+                AttributeValue::B(column.value.into()),
+            );
+        }
+
+        if let Some(ttl) = ttl {
+            item.insert(
+                TTL_ATTR.to_string(),
+                AttributeValue::N(expires_at(ttl).to_string()),
+            );
+        }
+
+        item
+    }
+
+    /// Builds the DynamoDB key for an item
+    fn build_key(key: String) -> HashMap<String, AttributeValue> {
+        HashMap::from([(PRIMARY_KEY_ATTR.to_string(), AttributeValue::S(key))])
+    }
+
+    /// Builds a projection of the primary key and the given columns
+    ///
+    /// Column names are passed as expression attribute names so they can't
+    /// collide with DynamoDB reserved words.
+    fn build_projection(column_names: &[String]) -> (String, HashMap<String, String>) {
+        let mut names = HashMap::from([
+            ("#pk".to_string(), PRIMARY_KEY_ATTR.to_string()),
+            ("#ttl".to_string(), TTL_ATTR.to_string()),
+        ]);
+        let mut projection = vec!["#pk".to_string(), "#ttl".to_string()];
+
+        for (i, column_name) in column_names.iter().enumerate() {
+            let placeholder = format!("#c{}", i);
+            names.insert(placeholder.clone(), column_name.clone());
+            projection.push(placeholder);
+        }
+
+        (projection.join(", "), names)
+    }
+
+    /// Whether an item's TTL has passed (DynamoDB deletes expired items lazily)
+    fn is_expired(item: &HashMap<String, AttributeValue>) -> bool {
+        item.get(TTL_ATTR)
+            .and_then(|value| value.as_n().ok())
+            .and_then(|number| number.parse::<i64>().ok())
+            .is_some_and(|expires_at| expires_at <= unix_now())
+    }
+
+    /// Extracts the primary key and requested columns from a DynamoDB item,
+    /// or `None` if the item has expired
+    fn parse_item(
+        item: &HashMap<String, AttributeValue>,
+        column_names: &[String],
+    ) -> Option<(String, Vec<Column>)> {
+        if Self::is_expired(item) {
+            return None;
+        }
+
+        let key = item.get(PRIMARY_KEY_ATTR)?.as_s().ok()?.clone();
+
+        let columns = column_names
+            .iter()
+            .filter_map(|column_name| match item.get(column_name)? {
+                AttributeValue::B(bytes) => {
+                    Some(Column::new(column_name.clone(), bytes.clone().into_inner()))
+                }
+                // Counters are numbers so they can be updated with ADD
+                AttributeValue::N(number) => {
+                    Some(Column::counter(column_name.clone(), number.parse().ok()?))
+                }
+                _ => None,
+            })
+            .collect();
+
+        Some((key, columns))
+    }
+}
+
+#[async_trait]
+impl KeyValueStore for DynamoKeyValueStore {
+    async fn put(
+        &self,
+        key: String,
+        columns: Vec<Column>,
+        ttl: Option<Duration>,
+    ) -> Result<(), ServiceError> {
+        let item = Self::build_item(key, columns, ttl);
+
+        self.client
+            .put_item()
+            .table_name(&self.table)
+            .set_item(Some(item))
+            .send()
+            .await
+            .map_err(|e| ServiceError::DynamoDbError(e.to_string()))?;
+
+        Ok(())
+    }
+
+    async fn get(&self, key: String, column_names: Vec<String>) -> Result<Vec<Column>, ServiceError> {
+        // Only retrieve requested columns
+        let (projection, names) = Self::build_projection(&column_names);
+
+        let result = self
+            .client
+            .get_item()
+            .table_name(&self.table)
+            .set_key(Some(Self::build_key(key)))
+            .projection_expression(projection)
+            .set_expression_attribute_names(Some(names))
+            .send()
+            .await
+            .map_err(|e| ServiceError::DynamoDbError(e.to_string()))?;
+
+        Ok(result
+            .item
+            .and_then(|item| Self::parse_item(&item, &column_names))
+            .map(|(_, columns)| columns)
+            .unwrap_or_default())
+    }
+
+    async fn put_if_not_exists(
+        &self,
+        key: String,
+        columns: Vec<Column>,
+        ttl: Option<Duration>,
+    ) -> Result<bool, ServiceError> {
+        let item = Self::build_item(key, columns, ttl);
+
+        // An expired item DynamoDB hasn't deleted yet counts as not existing
+        let result = self
+            .client
+            .put_item()
+            .table_name(&self.table)
+            .set_item(Some(item))
+            .condition_expression("attribute_not_exists(#pk) OR #ttl <= :now")
+            .expression_attribute_names("#pk", PRIMARY_KEY_ATTR)
+            .expression_attribute_names("#ttl", TTL_ATTR)
+            .expression_attribute_values(":now", AttributeValue::N(unix_now().to_string()))
+            .send()
+            .await;
+
+        match result {
+            Ok(_) => Ok(true),
+            Err(e) if e.as_service_error().is_some_and(|e| e.is_conditional_check_failed_exception()) => {
+                Ok(false)
+            }
+            Err(e) => Err(ServiceError::DynamoDbError(e.to_string())),
+        }
+    }
+
+    async fn increment(&self, key: String, column: String, delta: i64) -> Result<i64, ServiceError> {
+        let output = self
+            .client
+            .update_item()
+            .table_name(&self.table)
+            .set_key(Some(Self::build_key(key)))
+            .update_expression("ADD #c :delta")
+            .expression_attribute_names("#c", &column)
+            .expression_attribute_values(":delta", AttributeValue::N(delta.to_string()))
+            .return_values(ReturnValue::UpdatedNew)
+            .send()
+            .await
+            .map_err(|e| ServiceError::DynamoDbError(e.to_string()))?;
+
+        output
+            .attributes()
+            .and_then(|attributes| attributes.get(&column))
+            .and_then(|value| value.as_n().ok())
+            .and_then(|number| number.parse().ok())
+            .ok_or_else(|| {
+                ServiceError::DynamoDbError(format!("Counter {} missing from update result", column))
+            })
+    }
+
+    async fn get_versioned(
+        &self,
+        key: String,
+        column_names: Vec<String>,
+    ) -> Result<(u64, Vec<Column>), ServiceError> {
+        let (projection, mut names) = Self::build_projection(&column_names);
+        names.insert("#v".to_string(), VERSION_ATTR.to_string());
+
+        let result = self
+            .client
+            .get_item()
+            .table_name(&self.table)
+            .set_key(Some(Self::build_key(key)))
+            .projection_expression(format!("{}, #v", projection))
+            .set_expression_attribute_names(Some(names))
+            .consistent_read(true)
+            .send()
+            .await
+            .map_err(|e| ServiceError::DynamoDbError(e.to_string()))?;
+
+        let Some(item) = result.item else {
+            return Ok((0, Vec::new()));
+        };
+
+        let version = item
+            .get(VERSION_ATTR)
+            .and_then(|v| v.as_n().ok())
+            .and_then(|n| n.parse().ok())
+            .unwrap_or(0);
+        let columns = Self::parse_item(&item, &column_names)
+            .map(|(_, columns)| columns)
+            .unwrap_or_default();
+
+        Ok((version, columns))
+    }
+
+    async fn put_if_version(
+        &self,
+        key: String,
+        columns: Vec<Column>,
+        expected_version: u64,
+    ) -> Result<bool, ServiceError> {
+        let mut item = Self::build_item(key, columns, None);
+        item.insert(
+            VERSION_ATTR.to_string(),
+            AttributeValue::N((expected_version + 1).to_string()),
+        );
+
+        let mut request = self
+            .client
+            .put_item()
+            .table_name(&self.table)
+            .set_item(Some(item))
+            .expression_attribute_names("#v", VERSION_ATTR);
+
+        request = if expected_version == 0 {
+            request.condition_expression("attribute_not_exists(#v)")
+        } else {
+            request
+                .condition_expression("#v = :expected")
+                .expression_attribute_values(
+                    ":expected",
+                    AttributeValue::N(expected_version.to_string()),
+                )
+        };
+
+        match request.send().await {
+            Ok(_) => Ok(true),
+            Err(e) if e.as_service_error().is_some_and(|e| e.is_conditional_check_failed_exception()) => {
+                Ok(false)
+            }
+            Err(e) => Err(ServiceError::DynamoDbError(e.to_string())),
+        }
+    }
+
+    async fn delete(&self, key: String) -> Result<(), ServiceError> {
+        self.client
+            .delete_item()
+            .table_name(&self.table)
+            .set_key(Some(Self::build_key(key)))
+            .send()
+            .await
+            .map_err(|e| ServiceError::DynamoDbError(e.to_string()))?;
+
+        Ok(())
+    }
+
+    async fn batch_get(
+        &self,
+        keys: Vec<String>,
+        column_names: Vec<String>,
+    ) -> Result<HashMap<String, Vec<Column>>, ServiceError> {
+        // BatchGetItem rejects requests containing the same key twice
+        let keys: BTreeSet<String> = keys.into_iter().collect();
+        let keys: Vec<String> = keys.into_iter().collect();
+        let (projection, names) = Self::build_projection(&column_names);
+        let mut items = HashMap::new();
+
+        for chunk in keys.chunks(BATCH_GET_LIMIT) {
+            let mut request = KeysAndAttributes::builder()
+                .set_keys(Some(chunk.iter().cloned().map(Self::build_key).collect()))
+                .projection_expression(&projection)
+                .set_expression_attribute_names(Some(names.clone()))
+                .build()
+                .map_err(|e| ServiceError::DynamoDbError(e.to_string()))?;
+
+            // Keys DynamoDB couldn't process (e.g., due to throttling) are returned
+            // and must be requested again
+            loop {
+                let output = self
+                    .client
+                    .batch_get_item()
+                    .request_items(&self.table, request)
+                    .send()
+                    .await
+                    .map_err(|e| ServiceError::DynamoDbError(e.to_string()))?;
+
+                for item in output.responses().and_then(|r| r.get(&self.table)).into_iter().flatten() {
+                    if let Some((key, columns)) = Self::parse_item(item, &column_names)
+                        && !columns.is_empty()
+                    {
+                        items.insert(key, columns);
+                    }
+                }
+
+                match output
+                    .unprocessed_keys
+                    .and_then(|mut unprocessed| unprocessed.remove(&self.table))
+                {
+                    Some(unprocessed) if !unprocessed.keys().is_empty() => request = unprocessed,
+                    _ => break,
+                }
+            }
+        }
+
+        Ok(items)
+    }
+
+    async fn batch_put(&self, items: Vec<(String, Vec<Column>)>) -> Result<(), ServiceError> {
+        let requests = items
+            .into_iter()
+            .map(|(key, columns)| {
+                let put = PutRequest::builder()
+                    .set_item(Some(Self::build_item(key, columns, None)))
+                    .build()
+                    .map_err(|e| ServiceError::DynamoDbError(e.to_string()))?;
+                Ok(WriteRequest::builder().put_request(put).build())
+            })
+            .collect::<Result<Vec<_>, ServiceError>>()?;
+
+        for chunk in requests.chunks(BATCH_WRITE_LIMIT) {
+            let mut pending = chunk.to_vec();
+
+            // Items DynamoDB couldn't process are returned and must be written again
+            while !pending.is_empty() {
+                let output = self
+                    .client
+                    .batch_write_item()
+                    .request_items(&self.table, pending)
+                    .send()
+                    .await
+                    .map_err(|e| ServiceError::DynamoDbError(e.to_string()))?;
+
+                pending = output
+                    .unprocessed_items
+                    .and_then(|mut unprocessed| unprocessed.remove(&self.table))
+                    .unwrap_or_default();
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn put_record(
+        &self,
+        partition_key: String,
+        sort_key: String,
+        columns: Vec<Column>,
+        ttl: Option<Duration>,
+    ) -> Result<(), ServiceError> {
+        let mut item = Self::build_item(partition_key, columns, ttl);
+        item.insert(SORT_KEY_ATTR.to_string(), AttributeValue::S(sort_key));
+
+        self.client
+            .put_item()
+            .table_name(&self.records_table)
+            .set_item(Some(item))
+            .send()
+            .await
+            .map_err(|e| ServiceError::DynamoDbError(e.to_string()))?;
+
+        Ok(())
+    }
+
+    async fn query_records(
+        &self,
+        query: RecordQuery,
+        column_names: Vec<String>,
+    ) -> Result<Vec<Record>, ServiceError> {
+        let (projection, mut names) = Self::build_projection(&column_names);
+        names.insert("#sk".to_string(), SORT_KEY_ATTR.to_string());
+        let projection = format!("{}, #sk", projection);
+
+        let mut values =
+            HashMap::from([(":pk".to_string(), AttributeValue::S(query.partition_key.clone()))]);
+        let key_condition = match &query.condition {
+            SortKeyCondition::All => "#pk = :pk",
+            SortKeyCondition::Prefix(prefix) => {
+                values.insert(":prefix".to_string(), AttributeValue::S(prefix.clone()));
+                "#pk = :pk AND begins_with(#sk, :prefix)"
+            }
+            SortKeyCondition::Between(from, to) => {
+                values.insert(":from".to_string(), AttributeValue::S(from.clone()));
+                values.insert(":to".to_string(), AttributeValue::S(to.clone()));
+                "#pk = :pk AND #sk BETWEEN :from AND :to"
+            }
+        };
+
+        let mut records = Vec::new();
+        let mut exclusive_start_key = None;
+
+        loop {
+            let output = self
+                .client
+                .query()
+                .table_name(&self.records_table)
+                .key_condition_expression(key_condition)
+                .projection_expression(&projection)
+                .set_expression_attribute_names(Some(names.clone()))
+                .set_expression_attribute_values(Some(values.clone()))
+                .scan_index_forward(!query.descending)
+                .set_limit(query.limit.map(|limit| (limit - records.len()).min(i32::MAX as usize) as i32))
+                .set_exclusive_start_key(exclusive_start_key)
+                .send()
+                .await
+                .map_err(|e| ServiceError::DynamoDbError(e.to_string()))?;
+
+            for item in output.items() {
+                let sort_key = item.get(SORT_KEY_ATTR).and_then(|v| v.as_s().ok());
+                if let (Some(sort_key), Some((_, columns))) =
+                    (sort_key, Self::parse_item(item, &column_names))
+                {
+                    records.push(Record {
+                        sort_key: sort_key.clone(),
+                        columns,
+                    });
+                }
+            }
+
+            let reached_limit = query.limit.is_some_and(|limit| records.len() >= limit);
+            match output.last_evaluated_key {
+                Some(key) if !key.is_empty() && !reached_limit => exclusive_start_key = Some(key),
+                _ => break,
+            }
+        }
+
+        Ok(records)
+    }
+
+    async fn delete_record(&self, partition_key: String, sort_key: String) -> Result<(), ServiceError> {
+        let mut key = Self::build_key(partition_key);
+        key.insert(SORT_KEY_ATTR.to_string(), AttributeValue::S(sort_key));
+
+        self.client
+            .delete_item()
+            .table_name(&self.records_table)
+            .set_key(Some(key))
+            .send()
+            .await
+            .map_err(|e| ServiceError::DynamoDbError(e.to_string()))?;
+
+        Ok(())
+    }
+
+    async fn scan_prefix(
+        &self,
+        prefix: String,
+        column_names: Vec<String>,
+    ) -> Result<Vec<(String, Vec<Column>)>, ServiceError> {
+        let (projection, names) = Self::build_projection(&column_names);
+        let mut items = Vec::new();
+        let mut exclusive_start_key = None;
+
+        // Keys are hash keys, so a prefix match needs a (paginated) scan of the table
+        loop {
+            let output = self
+                .client
+                .scan()
+                .table_name(&self.table)
+                .filter_expression("begins_with(#pk, :prefix)")
+                .projection_expression(&projection)
+                .set_expression_attribute_names(Some(names.clone()))
+                .expression_attribute_values(":prefix", AttributeValue::S(prefix.clone()))
+                .set_exclusive_start_key(exclusive_start_key)
+                .send()
+                .await
+                .map_err(|e| ServiceError::DynamoDbError(e.to_string()))?;
+
+            items.extend(
+                output
+                    .items()
+                    .iter()
+                    .filter_map(|item| Self::parse_item(item, &column_names)),
+            );
+
+            match output.last_evaluated_key {
+                Some(key) if !key.is_empty() => exclusive_start_key = Some(key),
+                _ => break,
+            }
+        }
+
+        items.sort_by(|a, b| a.0.cmp(&b.0));
+        Ok(items)
+    }
+}
+
+/// Minimum time between sweeps of expired items from the in-memory store
+const MEMORY_SWEEP_INTERVAL_SECS: i64 = 60;
+
+/// A single item in the in-memory store
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct MemoryItem {
+    /// Column values keyed by column name
+    columns: HashMap<String, Vec<u8>>,
+
+    /// Unix time (seconds) at which the item expires, if it was written with a TTL
+    expires_at: Option<i64>,
+}
+
+impl MemoryItem {
+    /// Creates an item from columns and an optional TTL
+    fn new(columns: Vec<Column>, ttl: Option<Duration>) -> Self {
+        Self {
+            columns: columns.into_iter().map(|c| (c.name, c.value)).collect(),
+            expires_at: ttl.map(expires_at),
+        }
+    }
+
+    /// Whether the item's TTL has passed
+    fn is_expired(&self, now: i64) -> bool {
+        self.expires_at.is_some_and(|expires_at| expires_at <= now)
+    }
+
+    /// Returns the requested columns that the item has
+    fn project(&self, column_names: &[String]) -> Vec<Column> {
+        column_names
+            .iter()
+            .filter_map(|name| Some(Column::new(name.clone(), self.columns.get(name)?.clone())))
+            .collect()
+    }
+
+    /// Returns the version written by `put_if_version`, or 0
+    fn version(&self) -> u64 {
+        self.columns
+            .get(VERSION_ATTR)
+            .and_then(|bytes| <[u8; 8]>::try_from(bytes.as_slice()).ok())
+            .map(u64::from_be_bytes)
+            .unwrap_or(0)
+    }
+}
+
+/// Contents of a `MemoryKeyValueStore` snapshot file
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct MemorySnapshot {
+    items: HashMap<String, MemoryItem>,
+    records: HashMap<String, BTreeMap<String, MemoryItem>>,
+}
+
+/// Borrowed form of `MemorySnapshot`, so persisting doesn't clone the store
+#[derive(Serialize)]
+struct MemorySnapshotRef<'a> {
+    items: &'a HashMap<String, MemoryItem>,
+    records: &'a HashMap<String, BTreeMap<String, MemoryItem>>,
+}
+
+/// In-memory key-value store implementation for testing and development
+///
+/// Expired items are ignored by reads and removed lazily: writes sweep the whole
+/// store at most once every `MEMORY_SWEEP_INTERVAL_SECS`.
+///
+/// A store created with `open` is persisted to a JSON snapshot file after every
+/// write, so local data survives restarts.
+#[derive(Clone)]
+pub struct MemoryKeyValueStore {
+    data: Arc<RwLock<HashMap<String, MemoryItem>>>,
+    records: Arc<RwLock<HashMap<String, BTreeMap<String, MemoryItem>>>>,
+    last_sweep: Arc<AtomicI64>,
+    snapshot_path: Option<Arc<PathBuf>>,
+    snapshot_lock: Arc<Mutex<()>>,
+}
+
+impl MemoryKeyValueStore {
+    /// Creates a new MemoryKeyValueStore instance
+    pub fn new() -> Self {
+        Self {
+            data: Arc::new(RwLock::new(HashMap::new())),
+            records: Arc::new(RwLock::new(HashMap::new())),
+            last_sweep: Arc::new(AtomicI64::new(unix_now())),
+            snapshot_path: None,
+            snapshot_lock: Arc::new(Mutex::new(())),
+        }
+    }
+
+    /// Creates a store persisted to a snapshot file, loading the file if it exists
+    pub async fn open(path: impl Into<PathBuf>) -> Result<Self, ServiceError> {
+        let path = path.into();
+        let snapshot = match fs::read(&path).await {
+            Ok(bytes) => serde_json::from_slice(&bytes)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => MemorySnapshot::default(),
+            Err(e) => return Err(e.into()),
+        };
+
+        Ok(Self {
+            data: Arc::new(RwLock::new(snapshot.items)),
+            records: Arc::new(RwLock::new(snapshot.records)),
+            snapshot_path: Some(Arc::new(path)),
+            ..Self::new()
+        })
+    }
+
+    /// Writes the store's contents to its snapshot file, if it has one
+    ///
+    /// The snapshot is written to a temporary file and renamed into place, so a crash
+    /// mid-write leaves the previous snapshot intact.
+    async fn persist(&self) -> Result<(), ServiceError> {
+        let Some(path) = &self.snapshot_path else {
+            return Ok(());
+        };
+
+        // Serializes snapshots so an older one never replaces a newer one
+        let _guard = self.snapshot_lock.lock().await;
+
+        let bytes = {
+            let data = self.data.read().await;
+            let records = self.records.read().await;
+            serde_json::to_vec(&MemorySnapshotRef {
+                items: &data,
+                records: &records,
+            })?
+        };
+
+        let temp_path = path.with_extension("tmp");
+        fs::write(&temp_path, bytes).await?;
+        fs::rename(&temp_path, path.as_ref()).await?;
+
+        Ok(())
+    }
+
+    /// Returns an item if it exists and hasn't expired
+    fn live<'a>(data: &'a HashMap<String, MemoryItem>, key: &str) -> Option<&'a MemoryItem> {
+        data.get(key).filter(|item| !item.is_expired(unix_now()))
+    }
+
+    /// Removes an expired item before it is written, and periodically sweeps every
+    /// expired item from the store
+    fn expire(&self, data: &mut HashMap<String, MemoryItem>, key: &str) {
+        let now = unix_now();
+
+        if data.get(key).is_some_and(|item| item.is_expired(now)) {
+            data.remove(key);
+        }
+
+        let last_sweep = self.last_sweep.load(Ordering::Relaxed);
+        if now - last_sweep >= MEMORY_SWEEP_INTERVAL_SECS {
+            self.last_sweep.store(now, Ordering::Relaxed);
+            data.retain(|_, item| !item.is_expired(now));
+        }
+    }
+}
+
+impl Default for MemoryKeyValueStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl KeyValueStore for MemoryKeyValueStore {
+    async fn put(
+        &self,
+        key: String,
+        columns: Vec<Column>,
+        ttl: Option<Duration>,
+    ) -> Result<(), ServiceError> {
+        let mut data = self.data.write().await;
+        self.expire(&mut data, &key);
+
+        let item = data.entry(key).or_default();
+
+        for column in columns {
+            item.columns.insert(column.name, column.value);
+        }
+        item.expires_at = ttl.map(expires_at);
+        drop(data);
+
+        self.persist().await
+    }
+
+    async fn get(&self, key: String, column_names: Vec<String>) -> Result<Vec<Column>, ServiceError> {
+        let data = self.data.read().await;
+
+        Ok(Self::live(&data, &key)
+            .map(|item| item.project(&column_names))
+            .unwrap_or_default())
+    }
+
+    async fn put_if_not_exists(
+        &self,
+        key: String,
+        columns: Vec<Column>,
+        ttl: Option<Duration>,
+    ) -> Result<bool, ServiceError> {
+        let mut data = self.data.write().await;
+        self.expire(&mut data, &key);
+
+        if data.contains_key(&key) {
+            return Ok(false);
+        }
+
+        data.insert(key, MemoryItem::new(columns, ttl));
+        drop(data);
+
+        self.persist().await?;
+        Ok(true)
+    }
+
+    async fn increment(&self, key: String, column: String, delta: i64) -> Result<i64, ServiceError> {
+        let mut data = self.data.write().await;
+        self.expire(&mut data, &key);
+
+        let item = data.entry(key).or_default();
+        let current = item
+            .columns
+            .get(&column)
+            .and_then(|bytes| <[u8; 8]>::try_from(bytes.as_slice()).ok())
+            .map(i64::from_be_bytes)
+            .unwrap_or(0);
+        let value = current + delta;
+        item.columns.insert(column, value.to_be_bytes().to_vec());
+        drop(data);
+
+        self.persist().await?;
+        Ok(value)
+    }
+
+    async fn get_versioned(
+        &self,
+        key: String,
+        column_names: Vec<String>,
+    ) -> Result<(u64, Vec<Column>), ServiceError> {
+        let data = self.data.read().await;
+
+        Ok(Self::live(&data, &key)
+            .map(|item| (item.version(), item.project(&column_names)))
+            .unwrap_or_default())
+    }
+
+    async fn put_if_version(
+        &self,
+        key: String,
+        columns: Vec<Column>,
+        expected_version: u64,
+    ) -> Result<bool, ServiceError> {
+        let mut data = self.data.write().await;
+        self.expire(&mut data, &key);
+
+        // Compare and swap under the write lock
+        let current_version = data.get(&key).map(MemoryItem::version).unwrap_or(0);
+        if current_version != expected_version {
+            return Ok(false);
+        }
+
+        let mut item = MemoryItem::new(columns, None);
+        item.columns.insert(
+            VERSION_ATTR.to_string(),
+            (expected_version + 1).to_be_bytes().to_vec(),
+        );
+        data.insert(key, item);
+        drop(data);
+
+        self.persist().await?;
+        Ok(true)
+    }
+
+    async fn delete(&self, key: String) -> Result<(), ServiceError> {
+        self.data.write().await.remove(&key);
+        self.persist().await
+    }
+
+    async fn put_record(
+        &self,
+        partition_key: String,
+        sort_key: String,
+        columns: Vec<Column>,
+        ttl: Option<Duration>,
+    ) -> Result<(), ServiceError> {
+        let mut records = self.records.write().await;
+
+        let partition = records.entry(partition_key).or_default();
+        let now = unix_now();
+        partition.retain(|_, record| !record.is_expired(now));
+        partition.insert(sort_key, MemoryItem::new(columns, ttl));
+        drop(records);
+
+        self.persist().await
+    }
+
+    async fn query_records(
+        &self,
+        query: RecordQuery,
+        column_names: Vec<String>,
+    ) -> Result<Vec<Record>, ServiceError> {
+        let records = self.records.read().await;
+        let Some(partition) = records.get(&query.partition_key) else {
+            return Ok(Vec::new());
+        };
+
+        let now = unix_now();
+        let matching = partition
+            .iter()
+            .filter(|(sort_key, record)| query.matches(sort_key) && !record.is_expired(now))
+            .map(|(sort_key, record)| Record {
+                sort_key: sort_key.clone(),
+                columns: record.project(&column_names),
+            });
+
+        let limit = query.limit.unwrap_or(usize::MAX);
+        Ok(if query.descending {
+            matching.rev().take(limit).collect()
+        } else {
+            matching.take(limit).collect()
+        })
+    }
+
+    async fn delete_record(&self, partition_key: String, sort_key: String) -> Result<(), ServiceError> {
+        let mut records = self.records.write().await;
+
+        if let Some(partition) = records.get_mut(&partition_key) {
+            partition.remove(&sort_key);
+            if partition.is_empty() {
+                records.remove(&partition_key);
+            }
+        }
+        drop(records);
+
+        self.persist().await
+    }
+
+    async fn scan_prefix(
+        &self,
+        prefix: String,
+        column_names: Vec<String>,
+    ) -> Result<Vec<(String, Vec<Column>)>, ServiceError> {
+        let data = self.data.read().await;
+        let now = unix_now();
+
+        let mut items: Vec<(String, Vec<Column>)> = data
+            .iter()
+            .filter(|(key, item)| key.starts_with(&prefix) && !item.is_expired(now))
+            .map(|(key, item)| (key.clone(), item.project(&column_names)))
+            .collect();
+
+        items.sort_by(|a, b| a.0.cmp(&b.0));
+        Ok(items)
+    }
+}
+
+/// Returns the current Unix time in seconds
+fn unix_now() -> i64 {
+    chrono::Utc::now().timestamp()
+}
+
+/// Returns the Unix time (seconds) at which an item written now with a TTL expires
+fn expires_at(ttl: Duration) -> i64 {
+    unix_now().saturating_add(ttl.as_secs().try_into().unwrap_or(i64::MAX))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn column(name: &str, value: &str) -> Column {
+        Column::new(name.to_string(), value.as_bytes().to_vec())
+    }
+
+    #[tokio::test]
+    async fn memory_store_deletes_items() {
+        let store = MemoryKeyValueStore::new();
+        store.put("a".to_string(), vec![column("v", "1")], None).await.unwrap();
+
+        store.delete("a".to_string()).await.unwrap();
+        store.delete("missing".to_string()).await.unwrap();
+
+        assert!(store.get("a".to_string(), vec!["v".to_string()]).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn memory_store_expires_items_after_ttl() {
+        let store = MemoryKeyValueStore::new();
+        let columns = vec![column("v", "1")];
+
+        store
+            .put("expired".to_string(), columns.clone(), Some(Duration::ZERO))
+            .await
+            .unwrap();
+        store
+            .put("live".to_string(), columns.clone(), Some(Duration::from_secs(3600)))
+            .await
+            .unwrap();
+
+        assert!(store.get("expired".to_string(), vec!["v".to_string()]).await.unwrap().is_empty());
+        assert_eq!(store.get("live".to_string(), vec!["v".to_string()]).await.unwrap(), columns);
+
+        // An expired item no longer blocks a conditional create
+        assert!(store.put_if_not_exists("expired".to_string(), columns, None).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn memory_store_compares_versions_before_writing() {
+        let store = MemoryKeyValueStore::new();
+        let key = "counter".to_string();
+
+        let (version, _) = store.get_versioned(key.clone(), vec![]).await.unwrap();
+        assert_eq!(version, 0);
+        assert!(store.put_if_version(key.clone(), vec![column("v", "1")], 0).await.unwrap());
+
+        // A second writer holding the stale version loses
+        assert!(!store.put_if_version(key.clone(), vec![column("v", "x")], 0).await.unwrap());
+
+        let (version, columns) = store
+            .get_versioned(key.clone(), vec!["v".to_string()])
+            .await
+            .unwrap();
+        assert_eq!(version, 1);
+        assert_eq!(columns, vec![column("v", "1")]);
+        assert!(store.put_if_version(key, vec![column("v", "2")], 1).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn memory_store_increments_counters() {
+        let store = MemoryKeyValueStore::new();
+        let key = "usage#2025-10-11".to_string();
+
+        assert_eq!(store.increment(key.clone(), "tokens".to_string(), 120).await.unwrap(), 120);
+        assert_eq!(store.increment(key.clone(), "tokens".to_string(), -20).await.unwrap(), 100);
+
+        let columns = store.get(key, vec!["tokens".to_string()]).await.unwrap();
+        assert_eq!(columns[0].as_counter(), Some(100));
+    }
+
+    #[tokio::test]
+    async fn memory_store_queries_records_by_sort_key() {
+        let store = MemoryKeyValueStore::new();
+        for sort_key in ["attempt#1", "attempt#2", "attempt#3", "event#1"] {
+            store
+                .put_record("user#42".to_string(), sort_key.to_string(), vec![column("v", sort_key)], None)
+                .await
+                .unwrap();
+        }
+
+        let sort_keys = |records: Vec<Record>| -> Vec<String> {
+            records.into_iter().map(|record| record.sort_key).collect()
+        };
+        let columns = vec!["v".to_string()];
+
+        let recent = store
+            .query_records(RecordQuery::new("user#42").with_prefix("attempt#").descending().limit(2), columns.clone())
+            .await
+            .unwrap();
+        assert_eq!(sort_keys(recent), vec!["attempt#3", "attempt#2"]);
+
+        let range = store
+            .query_records(RecordQuery::new("user#42").between("attempt#2", "event#1"), columns.clone())
+            .await
+            .unwrap();
+        assert_eq!(sort_keys(range), vec!["attempt#2", "attempt#3", "event#1"]);
+
+        store.delete_record("user#42".to_string(), "event#1".to_string()).await.unwrap();
+        let all = store.query_records(RecordQuery::new("user#42"), columns).await.unwrap();
+        assert_eq!(all.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn memory_store_batches_and_scans() {
+        let store = MemoryKeyValueStore::new();
+        store
+            .batch_put(vec![
+                ("session#b".to_string(), vec![column("v", "2")]),
+                ("session#a".to_string(), vec![column("v", "1")]),
+                ("user#a".to_string(), vec![column("v", "3")]),
+            ])
+            .await
+            .unwrap();
+
+        let items = store
+            .batch_get(
+                vec!["session#a".to_string(), "missing".to_string()],
+                vec!["v".to_string()],
+            )
+            .await
+            .unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items["session#a"], vec![column("v", "1")]);
+
+        let scanned = store
+            .scan_prefix("session#".to_string(), vec!["v".to_string()])
+            .await
+            .unwrap();
+        let keys: Vec<&str> = scanned.iter().map(|(key, _)| key.as_str()).collect();
+        assert_eq!(keys, vec!["session#a", "session#b"]);
+    }
+
+    #[tokio::test]
+    async fn memory_store_reloads_snapshot_file() {
+        let path = std::env::temp_dir().join(format!("thinkaroo-kv-{}.json", uuid::Uuid::new_v4()));
+
+        let store = MemoryKeyValueStore::open(&path).await.unwrap();
+        store.put("a".to_string(), vec![column("v", "1")], None).await.unwrap();
+        store.increment("a".to_string(), "n".to_string(), 5).await.unwrap();
+        store
+            .put_record("user#42".to_string(), "attempt#1".to_string(), vec![column("v", "2")], None)
+            .await
+            .unwrap();
+
+        let reopened = MemoryKeyValueStore::open(&path).await.unwrap();
+        let columns = reopened
+            .get("a".to_string(), vec!["v".to_string(), "n".to_string()])
+            .await
+            .unwrap();
+        assert_eq!(columns[0], column("v", "1"));
+        assert_eq!(columns[1].as_counter(), Some(5));
+        let records = reopened
+            .query_records(RecordQuery::new("user#42"), vec!["v".to_string()])
+            .await
+            .unwrap();
+        assert_eq!(records.len(), 1);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,516 @@
+use ::redis::{Client, Pipeline, Script, aio::ConnectionManager, cmd, pipe};
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::time::Duration;
+
+use super::{Column, KeyValueStore, Record, RecordQuery, SortKeyCondition, VERSION_ATTR};
+use crate::ServiceError;
+
+/// Prefix added to every Redis key, so one Redis instance can be shared
+const DEFAULT_KEY_PREFIX: &str = "thinkaroo:";
+
+/// Prefix of the hash fields holding counters maintained by `increment`
+///
+/// Counters are kept in their own fields as decimal strings so `HINCRBY` can update
+/// them atomically; reads convert them to the big-endian `i64` columns used by the
+/// other backends.
+const COUNTER_FIELD_PREFIX: &str = "#counter:";
+
+/// Number of keys requested per `SCAN` call
+const SCAN_COUNT: usize = 1000;
+
+/// Creates an item only if its key doesn't exist
+///
+/// KEYS[1] = item key; ARGV[1] = TTL in milliseconds (0 for none), then field/value pairs
+const PUT_IF_NOT_EXISTS_SCRIPT: &str = r"
+if redis.call('EXISTS', KEYS[1]) == 1 then return 0 end
+for i = 2, #ARGV, 2 do redis.call('HSET', KEYS[1], ARGV[i], ARGV[i + 1]) end
+if tonumber(ARGV[1]) > 0 then redis.call('PEXPIRE', KEYS[1], ARGV[1]) end
+return 1
+";
+
+/// Replaces an item only if its version field still has the expected value
+///
+/// KEYS[1] = item key; ARGV[1] = version field, ARGV[2] = expected version, then
+/// field/value pairs
+const PUT_IF_VERSION_SCRIPT: &str = r"
+local current = tonumber(redis.call('HGET', KEYS[1], ARGV[1]) or '0')
+if current ~= tonumber(ARGV[2]) then return 0 end
+redis.call('DEL', KEYS[1])
+redis.call('HSET', KEYS[1], ARGV[1], tonumber(ARGV[2]) + 1)
+for i = 3, #ARGV, 2 do redis.call('HSET', KEYS[1], ARGV[i], ARGV[i + 1]) end
+return 1
+";
+
+impl From<::redis::RedisError> for ServiceError {
+    fn from(err: ::redis::RedisError) -> Self {
+        ServiceError::RedisError(err.to_string())
+    }
+}
+
+/// Redis-based key-value store implementation
+///
+/// Each item is a Redis hash with one field per column, expiring with Redis TTLs.
+/// Records are hashes too, indexed per partition key by a sorted set whose members
+/// are the sort keys, so range queries use `ZRANGEBYLEX`.
+#[derive(Clone)]
+pub struct RedisKeyValueStore {
+    connection: ConnectionManager,
+    key_prefix: String,
+}
+
+impl RedisKeyValueStore {
+    /// Connects to Redis (e.g., "redis://127.0.0.1:6379"), reconnecting automatically
+    pub async fn connect(url: &str) -> Result<Self, ServiceError> {
+        let client = Client::open(url)?;
+        let connection = ConnectionManager::new(client).await?;
+
+        Ok(Self {
+            connection,
+            key_prefix: DEFAULT_KEY_PREFIX.to_string(),
+        })
+    }
+
+    /// Sets the prefix added to every Redis key (default: "thinkaroo:")
+    pub fn with_key_prefix(mut self, key_prefix: &str) -> Self {
+        self.key_prefix = key_prefix.to_string();
+        self
+    }
+
+    /// Returns the Redis key of an item
+    fn item_key(&self, key: &str) -> String {
+        format!("{}kv:{}", self.key_prefix, key)
+    }
+
+    /// Returns the Redis key of the sorted set indexing a partition's records
+    fn index_key(&self, partition_key: &str) -> String {
+        format!("{}idx:{}", self.key_prefix, partition_key)
+    }
+
+    /// Returns the Redis key of a record
+    ///
+    /// The partition key is length-prefixed so different (partition, sort) pairs
+    /// can't produce the same key.
+    fn record_key(&self, partition_key: &str, sort_key: &str) -> String {
+        format!(
+            "{}rec:{}:{}{}",
+            self.key_prefix,
+            partition_key.len(),
+            partition_key,
+            sort_key
+        )
+    }
+
+    /// Reads the requested columns of the hash at a Redis key
+    async fn read_columns(
+        &self,
+        redis_key: &str,
+        column_names: &[String],
+    ) -> Result<Vec<Column>, ServiceError> {
+        if column_names.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let values: Vec<Option<Vec<u8>>> = cmd("HMGET")
+            .arg(redis_key)
+            .arg(column_fields(column_names))
+            .query_async(&mut self.connection.clone())
+            .await?;
+
+        Ok(parse_columns(column_names, &values))
+    }
+
+    /// Reads the requested columns of the hashes at several Redis keys in one round trip
+    async fn read_many(
+        &self,
+        redis_keys: &[String],
+        column_names: &[String],
+    ) -> Result<Vec<Vec<Column>>, ServiceError> {
+        if redis_keys.is_empty() || column_names.is_empty() {
+            return Ok(vec![Vec::new(); redis_keys.len()]);
+        }
+
+        let fields = column_fields(column_names);
+        let mut pipeline = pipe();
+        for redis_key in redis_keys {
+            pipeline.cmd("HMGET").arg(redis_key).arg(&fields);
+        }
+
+        let values: Vec<Vec<Option<Vec<u8>>>> =
+            pipeline.query_async(&mut self.connection.clone()).await?;
+
+        Ok(values
+            .iter()
+            .map(|values| parse_columns(column_names, values))
+            .collect())
+    }
+}
+
+/// Returns the hash fields holding each column: its value and its counter
+fn column_fields(column_names: &[String]) -> Vec<String> {
+    column_names
+        .iter()
+        .flat_map(|name| [name.clone(), format!("{}{}", COUNTER_FIELD_PREFIX, name)])
+        .collect()
+}
+
+/// Converts `HMGET` results for `column_fields` back into columns
+fn parse_columns(column_names: &[String], values: &[Option<Vec<u8>>]) -> Vec<Column> {
+    column_names
+        .iter()
+        .zip(values.chunks(2))
+        .filter_map(|(name, fields)| match fields {
+            [_, Some(counter)] => std::str::from_utf8(counter)
+                .ok()?
+                .parse()
+                .ok()
+                .map(|value| Column::counter(name.clone(), value)),
+            [Some(value), _] => Some(Column::new(name.clone(), value.clone())),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Flattens columns into `HSET` field/value arguments
+fn field_values(columns: Vec<Column>) -> Vec<(String, Vec<u8>)> {
+    columns.into_iter().map(|c| (c.name, c.value)).collect()
+}
+
+/// Adds commands replacing the hash at a Redis key to a pipeline
+fn replace_hash(pipeline: &mut Pipeline, redis_key: &str, columns: Vec<Column>, ttl: Option<Duration>) {
+    pipeline.del(redis_key).ignore();
+
+    if !columns.is_empty() {
+        pipeline.hset_multiple(redis_key, &field_values(columns)).ignore();
+    }
+    if let Some(ttl) = ttl {
+        pipeline
+            .pexpire(redis_key, ttl.as_millis().max(1) as i64)
+            .ignore();
+    }
+}
+
+/// Escapes glob characters so a key prefix matches literally in `SCAN MATCH`
+fn escape_glob(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, '*' | '?' | '[' | ']' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Returns the `ZRANGEBYLEX` bounds (min, max) matching a sort key condition
+fn lex_range(condition: &SortKeyCondition) -> (Vec<u8>, Vec<u8>) {
+    match condition {
+        SortKeyCondition::All => (b"-".to_vec(), b"+".to_vec()),
+        SortKeyCondition::Prefix(prefix) => {
+            // 0xFF never occurs in UTF-8, so it sorts after every key with the prefix
+            let min = [b"[", prefix.as_bytes()].concat();
+            let max = [b"(", prefix.as_bytes(), &[0xFF]].concat();
+            (min, max)
+        }
+        SortKeyCondition::Between(from, to) => (
+            [b"[", from.as_bytes()].concat(),
+            [b"[", to.as_bytes()].concat(),
+        ),
+    }
+}
+
+#[async_trait]
+impl KeyValueStore for RedisKeyValueStore {
+    async fn put(
+        &self,
+        key: String,
+        columns: Vec<Column>,
+        ttl: Option<Duration>,
+    ) -> Result<(), ServiceError> {
+        let mut pipeline = pipe();
+        pipeline.atomic();
+        replace_hash(&mut pipeline, &self.item_key(&key), columns, ttl);
+
+        let () = pipeline.query_async(&mut self.connection.clone()).await?;
+        Ok(())
+    }
+
+    async fn get(&self, key: String, column_names: Vec<String>) -> Result<Vec<Column>, ServiceError> {
+        self.read_columns(&self.item_key(&key), &column_names).await
+    }
+
+    async fn put_if_not_exists(
+        &self,
+        key: String,
+        columns: Vec<Column>,
+        ttl: Option<Duration>,
+    ) -> Result<bool, ServiceError> {
+        let script = Script::new(PUT_IF_NOT_EXISTS_SCRIPT);
+        let mut invocation = script.key(self.item_key(&key));
+        invocation.arg(ttl.map(|ttl| ttl.as_millis().max(1) as u64).unwrap_or(0));
+        for (field, value) in field_values(columns) {
+            invocation.arg(field).arg(value);
+        }
+
+        let created: i64 = invocation.invoke_async(&mut self.connection.clone()).await?;
+        Ok(created == 1)
+    }
+
+    async fn increment(&self, key: String, column: String, delta: i64) -> Result<i64, ServiceError> {
+        let value: i64 = cmd("HINCRBY")
+            .arg(self.item_key(&key))
+            .arg(format!("{}{}", COUNTER_FIELD_PREFIX, column))
+            .arg(delta)
+            .query_async(&mut self.connection.clone())
+            .await?;
+
+        Ok(value)
+    }
+
+    async fn get_versioned(
+        &self,
+        key: String,
+        column_names: Vec<String>,
+    ) -> Result<(u64, Vec<Column>), ServiceError> {
+        let mut fields = column_fields(&column_names);
+        fields.push(VERSION_ATTR.to_string());
+
+        let mut values: Vec<Option<Vec<u8>>> = cmd("HMGET")
+            .arg(self.item_key(&key))
+            .arg(fields)
+            .query_async(&mut self.connection.clone())
+            .await?;
+
+        let version = values
+            .pop()
+            .flatten()
+            .and_then(|bytes| String::from_utf8(bytes).ok())
+            .and_then(|text| text.parse().ok())
+            .unwrap_or(0);
+
+        Ok((version, parse_columns(&column_names, &values)))
+    }
+
+    async fn put_if_version(
+        &self,
+        key: String,
+        columns: Vec<Column>,
+        expected_version: u64,
+    ) -> Result<bool, ServiceError> {
+        let script = Script::new(PUT_IF_VERSION_SCRIPT);
+        let mut invocation = script.key(self.item_key(&key));
+        invocation.arg(VERSION_ATTR).arg(expected_version);
+        for (field, value) in field_values(columns) {
+            invocation.arg(field).arg(value);
+        }
+
+        let written: i64 = invocation.invoke_async(&mut self.connection.clone()).await?;
+        Ok(written == 1)
+    }
+
+    async fn delete(&self, key: String) -> Result<(), ServiceError> {
+        let () = cmd("DEL")
+            .arg(self.item_key(&key))
+            .query_async(&mut self.connection.clone())
+            .await?;
+
+        Ok(())
+    }
+
+    async fn batch_get(
+        &self,
+        keys: Vec<String>,
+        column_names: Vec<String>,
+    ) -> Result<HashMap<String, Vec<Column>>, ServiceError> {
+        let redis_keys: Vec<String> = keys.iter().map(|key| self.item_key(key)).collect();
+        let items = self.read_many(&redis_keys, &column_names).await?;
+
+        Ok(keys
+            .into_iter()
+            .zip(items)
+            .filter(|(_, columns)| !columns.is_empty())
+            .collect())
+    }
+
+    async fn batch_put(&self, items: Vec<(String, Vec<Column>)>) -> Result<(), ServiceError> {
+        let mut pipeline = pipe();
+        for (key, columns) in items {
+            replace_hash(&mut pipeline, &self.item_key(&key), columns, None);
+        }
+
+        let () = pipeline.query_async(&mut self.connection.clone()).await?;
+        Ok(())
+    }
+
+    async fn scan_prefix(
+        &self,
+        prefix: String,
+        column_names: Vec<String>,
+    ) -> Result<Vec<(String, Vec<Column>)>, ServiceError> {
+        let item_prefix = self.item_key("");
+        let pattern = format!("{}*", escape_glob(&self.item_key(&prefix)));
+
+        let mut redis_keys = Vec::new();
+        let mut cursor = 0u64;
+        loop {
+            let (next_cursor, keys): (u64, Vec<String>) = cmd("SCAN")
+                .arg(cursor)
+                .arg("MATCH")
+                .arg(&pattern)
+                .arg("COUNT")
+                .arg(SCAN_COUNT)
+                .query_async(&mut self.connection.clone())
+                .await?;
+
+            redis_keys.extend(keys);
+            if next_cursor == 0 {
+                break;
+            }
+            cursor = next_cursor;
+        }
+
+        // SCAN may return a key more than once
+        redis_keys.sort();
+        redis_keys.dedup();
+
+        let items = self.read_many(&redis_keys, &column_names).await?;
+        Ok(redis_keys
+            .into_iter()
+            .zip(items)
+            .map(|(redis_key, columns)| (redis_key[item_prefix.len()..].to_string(), columns))
+            .collect())
+    }
+
+    async fn put_record(
+        &self,
+        partition_key: String,
+        sort_key: String,
+        columns: Vec<Column>,
+        ttl: Option<Duration>,
+    ) -> Result<(), ServiceError> {
+        let mut pipeline = pipe();
+        pipeline.atomic();
+        replace_hash(
+            &mut pipeline,
+            &self.record_key(&partition_key, &sort_key),
+            columns,
+            ttl,
+        );
+        pipeline
+            .zadd(self.index_key(&partition_key), &sort_key, 0)
+            .ignore();
+
+        let () = pipeline.query_async(&mut self.connection.clone()).await?;
+        Ok(())
+    }
+
+    async fn query_records(
+        &self,
+        query: RecordQuery,
+        column_names: Vec<String>,
+    ) -> Result<Vec<Record>, ServiceError> {
+        let index_key = self.index_key(&query.partition_key);
+        let (min, max) = lex_range(&query.condition);
+
+        let mut range = if query.descending {
+            cmd("ZREVRANGEBYLEX")
+        } else {
+            cmd("ZRANGEBYLEX")
+        };
+        range.arg(&index_key);
+        if query.descending {
+            range.arg(max).arg(min);
+        } else {
+            range.arg(min).arg(max);
+        }
+        let sort_keys: Vec<String> = range.query_async(&mut self.connection.clone()).await?;
+
+        // Index entries outlive records that expired; find and drop them
+        let record_keys: Vec<String> = sort_keys
+            .iter()
+            .map(|sort_key| self.record_key(&query.partition_key, sort_key))
+            .collect();
+        let mut exists_pipeline = pipe();
+        for record_key in &record_keys {
+            exists_pipeline.exists(record_key);
+        }
+        let exists: Vec<bool> = exists_pipeline
+            .query_async(&mut self.connection.clone())
+            .await?;
+
+        let (live, expired): (Vec<_>, Vec<_>) = sort_keys
+            .into_iter()
+            .zip(record_keys)
+            .zip(exists)
+            .partition(|(_, exists)| *exists);
+
+        if !expired.is_empty() {
+            let expired: Vec<String> = expired.into_iter().map(|((sort_key, _), _)| sort_key).collect();
+            let () = cmd("ZREM")
+                .arg(&index_key)
+                .arg(expired)
+                .query_async(&mut self.connection.clone())
+                .await?;
+        }
+
+        let limit = query.limit.unwrap_or(usize::MAX);
+        let (sort_keys, record_keys): (Vec<String>, Vec<String>) =
+            live.into_iter().take(limit).map(|(keys, _)| keys).unzip();
+        let columns = self.read_many(&record_keys, &column_names).await?;
+
+        Ok(sort_keys
+            .into_iter()
+            .zip(columns)
+            .map(|(sort_key, columns)| Record { sort_key, columns })
+            .collect())
+    }
+
+    async fn delete_record(&self, partition_key: String, sort_key: String) -> Result<(), ServiceError> {
+        let mut pipeline = pipe();
+        pipeline
+            .atomic()
+            .del(self.record_key(&partition_key, &sort_key))
+            .ignore()
+            .zrem(self.index_key(&partition_key), &sort_key)
+            .ignore();
+
+        let () = pipeline.query_async(&mut self.connection.clone()).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counters_take_precedence_over_raw_values() {
+        let names = vec!["plain".to_string(), "count".to_string(), "missing".to_string()];
+        let values = vec![
+            Some(b"raw".to_vec()),
+            None,
+            None,
+            Some(b"42".to_vec()),
+            None,
+            None,
+        ];
+
+        let columns = parse_columns(&names, &values);
+        assert_eq!(columns.len(), 2);
+        assert_eq!(columns[0], Column::new("plain".to_string(), b"raw".to_vec()));
+        assert_eq!(columns[1].as_counter(), Some(42));
+    }
+
+    #[test]
+    fn escapes_glob_characters_in_prefixes() {
+        assert_eq!(escape_glob("session#a"), "session#a");
+        assert_eq!(escape_glob("a*b?[c]\\"), "a\\*b\\?\\[c\\]\\\\");
+    }
+
+    #[test]
+    fn prefix_range_covers_only_keys_with_prefix() {
+        let (min, max) = lex_range(&SortKeyCondition::Prefix("attempt#".to_string()));
+        assert_eq!(min, b"[attempt#");
+        assert_eq!(max, b"(attempt#\xff");
+    }
+}
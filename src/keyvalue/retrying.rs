@@ -0,0 +1,164 @@
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::time::Duration;
+
+use super::{Column, KeyValueStore, Record, RecordQuery};
+use crate::ServiceError;
+use crate::retry::RetryPolicy;
+
+/// Key-value store wrapper that retries transient failures with backoff
+///
+/// Conditional writes (`put_if_not_exists`, `put_if_version`) and increments aren't
+/// retried: if an attempt succeeded but its response was lost, the retry would
+/// report a conflict or count twice.
+#[derive(Clone)]
+pub struct RetryingKeyValueStore<K: KeyValueStore> {
+    inner: K,
+    policy: RetryPolicy,
+}
+
+impl<K: KeyValueStore> RetryingKeyValueStore<K> {
+    /// Wraps a store with the default retry policy
+    pub fn new(inner: K) -> Self {
+        Self::with_policy(inner, RetryPolicy::default())
+    }
+
+    /// Wraps a store with a custom retry policy
+    pub fn with_policy(inner: K, policy: RetryPolicy) -> Self {
+        Self { inner, policy }
+    }
+}
+
+#[async_trait]
+impl<K: KeyValueStore> KeyValueStore for RetryingKeyValueStore<K> {
+    async fn put(
+        &self,
+        key: String,
+        columns: Vec<Column>,
+        ttl: Option<Duration>,
+    ) -> Result<(), ServiceError> {
+        let inner = &self.inner;
+        self.policy
+            .run("put", move || inner.put(key.clone(), columns.clone(), ttl))
+            .await
+    }
+
+    async fn get(&self, key: String, column_names: Vec<String>) -> Result<Vec<Column>, ServiceError> {
+        let inner = &self.inner;
+        self.policy
+            .run("get", move || inner.get(key.clone(), column_names.clone()))
+            .await
+    }
+
+    async fn put_if_not_exists(
+        &self,
+        key: String,
+        columns: Vec<Column>,
+        ttl: Option<Duration>,
+    ) -> Result<bool, ServiceError> {
+        self.inner.put_if_not_exists(key, columns, ttl).await
+    }
+
+    async fn increment(&self, key: String, column: String, delta: i64) -> Result<i64, ServiceError> {
+        self.inner.increment(key, column, delta).await
+    }
+
+    async fn get_versioned(
+        &self,
+        key: String,
+        column_names: Vec<String>,
+    ) -> Result<(u64, Vec<Column>), ServiceError> {
+        let inner = &self.inner;
+        self.policy
+            .run("get_versioned", move || {
+                inner.get_versioned(key.clone(), column_names.clone())
+            })
+            .await
+    }
+
+    async fn put_if_version(
+        &self,
+        key: String,
+        columns: Vec<Column>,
+        expected_version: u64,
+    ) -> Result<bool, ServiceError> {
+        self.inner.put_if_version(key, columns, expected_version).await
+    }
+
+    async fn delete(&self, key: String) -> Result<(), ServiceError> {
+        let inner = &self.inner;
+        self.policy
+            .run("delete", move || inner.delete(key.clone()))
+            .await
+    }
+
+    async fn batch_get(
+        &self,
+        keys: Vec<String>,
+        column_names: Vec<String>,
+    ) -> Result<HashMap<String, Vec<Column>>, ServiceError> {
+        let inner = &self.inner;
+        self.policy
+            .run("batch_get", move || {
+                inner.batch_get(keys.clone(), column_names.clone())
+            })
+            .await
+    }
+
+    async fn batch_put(&self, items: Vec<(String, Vec<Column>)>) -> Result<(), ServiceError> {
+        let inner = &self.inner;
+        self.policy
+            .run("batch_put", move || inner.batch_put(items.clone()))
+            .await
+    }
+
+    async fn scan_prefix(
+        &self,
+        prefix: String,
+        column_names: Vec<String>,
+    ) -> Result<Vec<(String, Vec<Column>)>, ServiceError> {
+        let inner = &self.inner;
+        self.policy
+            .run("scan_prefix", move || {
+                inner.scan_prefix(prefix.clone(), column_names.clone())
+            })
+            .await
+    }
+
+    async fn put_record(
+        &self,
+        partition_key: String,
+        sort_key: String,
+        columns: Vec<Column>,
+        ttl: Option<Duration>,
+    ) -> Result<(), ServiceError> {
+        let inner = &self.inner;
+        self.policy
+            .run("put_record", move || {
+                inner.put_record(partition_key.clone(), sort_key.clone(), columns.clone(), ttl)
+            })
+            .await
+    }
+
+    async fn query_records(
+        &self,
+        query: RecordQuery,
+        column_names: Vec<String>,
+    ) -> Result<Vec<Record>, ServiceError> {
+        let inner = &self.inner;
+        self.policy
+            .run("query_records", move || {
+                inner.query_records(query.clone(), column_names.clone())
+            })
+            .await
+    }
+
+    async fn delete_record(&self, partition_key: String, sort_key: String) -> Result<(), ServiceError> {
+        let inner = &self.inner;
+        self.policy
+            .run("delete_record", move || {
+                inner.delete_record(partition_key.clone(), sort_key.clone())
+            })
+            .await
+    }
+}
@@ -0,0 +1,164 @@
+use async_trait::async_trait;
+use serde::Serialize;
+use serde::de::{DeserializeOwned, Error as _};
+use std::time::Duration;
+
+use super::{Column, KeyValueStore};
+use crate::ServiceError;
+
+/// First byte of column values written by `encode_json`
+///
+/// JSON text never starts with this byte, so values written without a prefix are
+/// still read as plain JSON, and a future encoding can use a different byte.
+const JSON_FORMAT_V1: u8 = 1;
+
+/// Encodes a value as a column value: a format byte followed by JSON
+pub fn encode_json<T: Serialize + ?Sized>(value: &T) -> Result<Vec<u8>, ServiceError> {
+    let mut bytes = vec![JSON_FORMAT_V1];
+    serde_json::to_writer(&mut bytes, value)?;
+    Ok(bytes)
+}
+
+/// Decodes a column value written by `encode_json`, or unprefixed JSON
+pub fn decode_json<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, ServiceError> {
+    match bytes.split_first() {
+        Some((&JSON_FORMAT_V1, json)) => Ok(serde_json::from_slice(json)?),
+        Some((&format, _)) if format < b' ' && !b"\t\n\r".contains(&format) => Err(
+            serde_json::Error::custom(format!("unknown column format {}", format)).into(),
+        ),
+        _ => Ok(serde_json::from_slice(bytes)?),
+    }
+}
+
+/// Finds a column by name in the columns returned by a read and decodes it
+pub fn column_json<T: DeserializeOwned>(
+    columns: &[Column],
+    name: &str,
+) -> Result<Option<T>, ServiceError> {
+    columns
+        .iter()
+        .find(|column| column.name == name)
+        .map(|column| decode_json(&column.value))
+        .transpose()
+}
+
+/// Reads and writes serde values as JSON columns of a `KeyValueStore`
+///
+/// Implemented for every store. A missing item or column reads as `None`.
+///
+/// # Examples
+///
+/// ```
+/// use thinkaroo::keyvalue::{MemoryKeyValueStore, TypedKv};
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let store = MemoryKeyValueStore::new();
+/// store.put_json("user#42", "settings", &vec!["dark-mode"], None).await.unwrap();
+///
+/// let settings: Option<Vec<String>> = store.get_json("user#42", "settings").await.unwrap();
+/// assert_eq!(settings, Some(vec!["dark-mode".to_string()]));
+/// # }
+/// ```
+#[async_trait]
+pub trait TypedKv: KeyValueStore {
+    /// Reads a column as JSON
+    async fn get_json<T: DeserializeOwned>(
+        &self,
+        key: &str,
+        column: &str,
+    ) -> Result<Option<T>, ServiceError> {
+        let columns = self.get(key.to_string(), vec![column.to_string()]).await?;
+        column_json(&columns, column)
+    }
+
+    /// Writes a value as the only column of an item, replacing the item
+    async fn put_json<T: Serialize + Sync + ?Sized>(
+        &self,
+        key: &str,
+        column: &str,
+        value: &T,
+        ttl: Option<Duration>,
+    ) -> Result<(), ServiceError> {
+        let columns = vec![Column::new(column.to_string(), encode_json(value)?)];
+        self.put(key.to_string(), columns, ttl).await
+    }
+
+    /// Reads a column as JSON along with the item's version (see `get_versioned`)
+    async fn get_json_versioned<T: DeserializeOwned>(
+        &self,
+        key: &str,
+        column: &str,
+    ) -> Result<(u64, Option<T>), ServiceError> {
+        let (version, columns) = self
+            .get_versioned(key.to_string(), vec![column.to_string()])
+            .await?;
+        Ok((version, column_json(&columns, column)?))
+    }
+
+    /// Writes a value only if the item's version still matches (see `put_if_version`)
+    async fn put_json_if_version<T: Serialize + Sync + ?Sized>(
+        &self,
+        key: &str,
+        column: &str,
+        value: &T,
+        expected_version: u64,
+    ) -> Result<bool, ServiceError> {
+        let columns = vec![Column::new(column.to_string(), encode_json(value)?)];
+        self.put_if_version(key.to_string(), columns, expected_version)
+            .await
+    }
+}
+
+impl<K: KeyValueStore> TypedKv for K {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keyvalue::MemoryKeyValueStore;
+    use serde::Deserialize;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Progress {
+        stories_read: u32,
+        streak: u32,
+    }
+
+    #[test]
+    fn decodes_prefixed_and_plain_json() {
+        let progress = Progress { stories_read: 3, streak: 2 };
+        let encoded = encode_json(&progress).unwrap();
+        assert_eq!(encoded[0], JSON_FORMAT_V1);
+        assert_eq!(decode_json::<Progress>(&encoded).unwrap(), progress);
+
+        let plain = br#"{"stories_read":3,"streak":2}"#;
+        assert_eq!(decode_json::<Progress>(plain).unwrap(), progress);
+
+        assert!(decode_json::<Progress>(&[2, b'{', b'}']).is_err());
+    }
+
+    #[tokio::test]
+    async fn missing_items_and_columns_read_as_none() {
+        let store = MemoryKeyValueStore::new();
+        let progress = Progress { stories_read: 1, streak: 1 };
+
+        assert_eq!(store.get_json::<Progress>("user#1", "progress").await.unwrap(), None);
+
+        store.put_json("user#1", "progress", &progress, None).await.unwrap();
+        assert_eq!(store.get_json("user#1", "progress").await.unwrap(), Some(progress));
+        assert_eq!(store.get_json::<Progress>("user#1", "other").await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn versioned_writes_detect_conflicts() {
+        let store = MemoryKeyValueStore::new();
+
+        let (version, current) = store.get_json_versioned::<u32>("count", "value").await.unwrap();
+        assert_eq!((version, current), (0, None));
+        assert!(store.put_json_if_version("count", "value", &1, version).await.unwrap());
+        assert!(!store.put_json_if_version("count", "value", &2, version).await.unwrap());
+
+        let (version, current) = store.get_json_versioned::<u32>("count", "value").await.unwrap();
+        assert_eq!((version, current), (1, Some(1)));
+    }
+}
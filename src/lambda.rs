@@ -0,0 +1,258 @@
+use axum::{
+    Router,
+    body::Body,
+    http::{HeaderMap, HeaderName, HeaderValue, Method, Request, Response, header},
+};
+use base64::{Engine, engine::general_purpose::STANDARD};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+use tower::ServiceExt;
+use tracing::{error, info};
+
+use crate::{ServiceError, config::Config};
+
+/// Environment variable Lambda sets to the host and port of its runtime API
+pub const RUNTIME_API: &str = "AWS_LAMBDA_RUNTIME_API";
+
+/// Version prefix of the runtime API's paths
+const RUNTIME_API_VERSION: &str = "2018-06-01";
+
+/// Header of the next invocation carrying its ID
+const REQUEST_ID_HEADER: &str = "lambda-runtime-aws-request-id";
+
+/// The only directory Lambda lets functions write to
+const WRITABLE_DIR: &str = "/tmp";
+
+/// Whether this process was started by Lambda
+pub fn on_lambda() -> bool {
+    std::env::var_os(RUNTIME_API).is_some()
+}
+
+/// Describes settings that can't work on Lambda, whose filesystem is read-only
+/// outside `/tmp`
+///
+/// Prompts, templates, and static files are embedded in the binary, and
+/// `PROMPTS_DIR` is only read, so they work as they are; the disk and memory
+/// backends' files must be under `/tmp`, and are lost when the instance is.
+pub fn check_config(config: &Config) -> Vec<String> {
+    let mut problems = Vec::new();
+    for (name, path) in [("disk_path", &config.disk_path), ("memory_kv_path", &config.memory_kv_path)] {
+        if let Some(path) = path
+            && !path.starts_with(Path::new(WRITABLE_DIR))
+        {
+            problems.push(format!("{} must be under {} on Lambda, got {:?}", name, WRITABLE_DIR, path));
+        }
+    }
+    problems
+}
+
+/// An HTTP request from API Gateway (HTTP APIs) or a function URL, in payload
+/// format 2.0
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct HttpEvent {
+    raw_path: String,
+    #[serde(default)]
+    raw_query_string: String,
+    #[serde(default)]
+    cookies: Vec<String>,
+    #[serde(default)]
+    headers: BTreeMap<String, String>,
+    request_context: RequestContext,
+    body: Option<String>,
+    #[serde(default)]
+    is_base64_encoded: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RequestContext {
+    http: HttpContext,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct HttpContext {
+    method: String,
+    source_ip: Option<String>,
+}
+
+/// The response to an `HttpEvent`
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct HttpResponse {
+    status_code: u16,
+    headers: BTreeMap<String, String>,
+    cookies: Vec<String>,
+    body: String,
+    is_base64_encoded: bool,
+}
+
+/// Builds the request an event describes
+fn to_request(event: HttpEvent) -> Result<Request<Body>, ServiceError> {
+    let invalid = |e: &dyn std::fmt::Display| ServiceError::InvalidInput(format!("Invalid Lambda event: {}", e));
+
+    let mut uri = event.raw_path;
+    if !event.raw_query_string.is_empty() {
+        uri = format!("{}?{}", uri, event.raw_query_string);
+    }
+    let method = Method::from_bytes(event.request_context.http.method.as_bytes()).map_err(|e| invalid(&e))?;
+    let mut request = Request::builder().method(method).uri(uri);
+
+    let headers = request.headers_mut().expect("request builder is valid so far");
+    for (name, value) in &event.headers {
+        let name = HeaderName::from_bytes(name.as_bytes()).map_err(|e| invalid(&e))?;
+        headers.insert(name, HeaderValue::from_str(value).map_err(|e| invalid(&e))?);
+    }
+    if !event.cookies.is_empty() {
+        let cookies = HeaderValue::from_str(&event.cookies.join("; ")).map_err(|e| invalid(&e))?;
+        headers.insert(header::COOKIE, cookies);
+    }
+    if let Some(ip) = event.request_context.http.source_ip
+        && !headers.contains_key("x-forwarded-for")
+    {
+        headers.insert("x-forwarded-for", HeaderValue::from_str(&ip).map_err(|e| invalid(&e))?);
+    }
+
+    let body = match event.body {
+        Some(body) if event.is_base64_encoded => STANDARD.decode(body).map_err(|e| invalid(&e))?,
+        Some(body) => body.into_bytes(),
+        None => Vec::new(),
+    };
+    request.body(Body::from(body)).map_err(|e| invalid(&e))
+}
+
+/// Whether a body of this type can be returned as text rather than base64
+fn is_text(headers: &HeaderMap) -> bool {
+    headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| {
+            value.starts_with("text/") || ["json", "xml", "javascript"].iter().any(|kind| value.contains(kind))
+        })
+}
+
+/// Buffers a response into the form Lambda returns to API Gateway
+///
+/// Streamed responses, such as server-sent events, are sent once they finish;
+/// WebSocket upgrades aren't supported.
+async fn to_http_response(response: Response<Body>) -> Result<HttpResponse, ServiceError> {
+    let (parts, body) = response.into_parts();
+    let body = axum::body::to_bytes(body, usize::MAX)
+        .await
+        .map_err(|e| ServiceError::IoError(std::io::Error::other(e)))?;
+
+    let mut headers: BTreeMap<String, String> = BTreeMap::new();
+    let mut cookies = Vec::new();
+    for (name, value) in &parts.headers {
+        let Ok(value) = value.to_str() else { continue };
+        if name == header::SET_COOKIE {
+            cookies.push(value.to_string());
+        } else {
+            headers
+                .entry(name.to_string())
+                .and_modify(|joined| {
+                    joined.push_str(", ");
+                    joined.push_str(value);
+                })
+                .or_insert_with(|| value.to_string());
+        }
+    }
+
+    let (body, is_base64_encoded) = match String::from_utf8(body.to_vec()) {
+        Ok(text) if is_text(&parts.headers) => (text, false),
+        _ => (STANDARD.encode(&body), true),
+    };
+    Ok(HttpResponse { status_code: parts.status.as_u16(), headers, cookies, body, is_base64_encoded })
+}
+
+/// Serves `app` as a Lambda function, one invocation at a time, until the runtime
+/// API fails
+///
+/// Implements Lambda's custom runtime protocol: deploy the binary as `bootstrap`
+/// on a `provided.al2023` runtime, behind an API Gateway HTTP API or a function
+/// URL. Background work that outlives a request is frozen between invocations.
+pub async fn run(app: Router) -> Result<(), ServiceError> {
+    let api = std::env::var(RUNTIME_API)
+        .map_err(|_| ServiceError::ConfigError(format!("{} is not set; not running on Lambda", RUNTIME_API)))?;
+    let base = format!("http://{}/{}/runtime/invocation", api, RUNTIME_API_VERSION);
+    let http = reqwest::Client::new();
+    let runtime_error = |e: reqwest::Error| ServiceError::IoError(std::io::Error::other(e));
+    info!("Serving Lambda invocations");
+
+    loop {
+        let next = http.get(format!("{}/next", base)).send().await.map_err(runtime_error)?;
+        let Some(request_id) = next.headers().get(REQUEST_ID_HEADER).and_then(|value| value.to_str().ok()) else {
+            return Err(ServiceError::IoError(std::io::Error::other("Invocation has no request ID")));
+        };
+        let request_id = request_id.to_string();
+        let event = next.bytes().await.map_err(runtime_error)?;
+
+        let result = match serde_json::from_slice::<HttpEvent>(&event).map_err(ServiceError::from).and_then(to_request) {
+            Ok(request) => {
+                let response = app.clone().oneshot(request).await.unwrap_or_else(|never| match never {});
+                to_http_response(response).await
+            }
+            Err(e) => Err(e),
+        };
+
+        let posted = match result {
+            Ok(response) => http.post(format!("{}/{}/response", base, request_id)).json(&response).send().await,
+            Err(e) => {
+                error!("Invocation {} failed: {}", request_id, e);
+                http.post(format!("{}/{}/error", base, request_id))
+                    .json(&serde_json::json!({ "errorMessage": e.to_string(), "errorType": "InvalidEvent" }))
+                    .send()
+                    .await
+            }
+        };
+        if let Err(e) = posted.and_then(reqwest::Response::error_for_status) {
+            error!("Failed to return the result of invocation {}: {}", request_id, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{http::StatusCode, routing::post};
+
+    #[tokio::test]
+    async fn translates_events_and_responses() {
+        let event: HttpEvent = serde_json::from_value(serde_json::json!({
+            "version": "2.0",
+            "rawPath": "/echo",
+            "rawQueryString": "grade=3",
+            "cookies": ["a=1", "b=2"],
+            "headers": { "content-type": "text/plain" },
+            "requestContext": { "http": { "method": "POST", "sourceIp": "203.0.113.9" } },
+            "body": STANDARD.encode("hello"),
+            "isBase64Encoded": true,
+        }))
+        .unwrap();
+
+        let app = Router::new().route(
+            "/echo",
+            post(|request: Request<Body>| async move {
+                let cookie = request.headers()[header::COOKIE].to_str().unwrap().to_string();
+                let query = request.uri().query().unwrap_or_default().to_string();
+                let body = axum::body::to_bytes(request.into_body(), usize::MAX).await.unwrap();
+                let body = String::from_utf8(body.to_vec()).unwrap();
+                (
+                    StatusCode::CREATED,
+                    [(header::SET_COOKIE, "session=x"), (header::CONTENT_TYPE, "text/plain")],
+                    format!("{} {} {}", cookie, query, body),
+                )
+            }),
+        );
+        let response = app.oneshot(to_request(event).unwrap()).await.unwrap();
+        let response = to_http_response(response).await.unwrap();
+
+        assert_eq!(response.status_code, 201);
+        assert_eq!(response.body, "a=1; b=2 grade=3 hello");
+        assert!(!response.is_base64_encoded);
+        assert_eq!(response.cookies, ["session=x"]);
+        assert!(!response.headers.contains_key("set-cookie"));
+    }
+}
@@ -0,0 +1,65 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use uuid::Uuid;
+
+use crate::{
+    ServiceError,
+    keyvalue::{Column, KeyValueStore},
+};
+
+/// Column recording which instance holds a lease
+const OWNER_COLUMN: &str = "owner";
+
+/// Tries to acquire a short-lived lease, so only one instance performs some work
+///
+/// Leases are built on `put_if_not_exists`: the lease key includes the current
+/// lease period (`now / duration`), so a lease held by an instance that crashed
+/// lapses on its own at the end of the period without any cleanup.
+///
+/// # Arguments
+/// * `kv_store` - The store holding leases
+/// * `name` - Identifies the work being leased
+/// * `duration` - Length of the lease period
+///
+/// # Returns
+/// * `Ok(true)` - This caller holds the lease for the current period
+/// * `Ok(false)` - Another caller already holds it
+/// * `Err(ServiceError)` - If the store can't be reached
+pub async fn try_acquire<K: KeyValueStore>(
+    kv_store: &K,
+    name: &str,
+    duration: Duration,
+) -> Result<bool, ServiceError> {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let period = now / duration.as_secs().max(1);
+
+    kv_store
+        .put_if_not_exists(
+            format!("lease#{}#{}", name, period),
+            vec![Column::new(
+                OWNER_COLUMN.to_string(),
+                Uuid::new_v4().to_string().into_bytes(),
+            )],
+            // Leases are never reused after their period, so let the store expire them
+            Some(duration),
+        )
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keyvalue::MemoryKeyValueStore;
+
+    #[tokio::test]
+    async fn test_only_one_caller_acquires() {
+        let kv_store = MemoryKeyValueStore::new();
+        let duration = Duration::from_secs(3600);
+
+        assert!(try_acquire(&kv_store, "work", duration).await.unwrap());
+        assert!(!try_acquire(&kv_store, "work", duration).await.unwrap());
+        assert!(try_acquire(&kv_store, "other", duration).await.unwrap());
+    }
+}
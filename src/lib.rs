@@ -1,11 +1,82 @@
+pub mod admin;
+pub mod api;
+pub mod assets;
+pub mod audit;
+pub mod bilingual;
+pub mod cache_policy;
+pub mod circuit_breaker;
+pub mod classes;
+pub mod cloze;
+pub mod config;
+pub mod content;
+pub mod daily;
+pub mod dedup;
+pub mod difficulty;
+pub mod email;
+pub mod epub;
+pub mod etag;
+pub mod experiments;
+pub mod explanations;
+pub mod feedback;
+pub mod flags;
+pub mod flashcards;
+pub mod gamification;
+pub mod gc;
+pub mod grammar;
+pub mod graphql;
+pub mod grpc;
+pub mod health;
+pub mod history;
+pub mod idempotency;
+pub mod illustration;
 pub mod keyvalue;
+#[cfg(feature = "lambda")]
+pub mod lambda;
+pub mod lease;
+pub mod limits;
+pub mod logic;
+pub mod math_drill;
+pub mod metrics;
+pub mod moderation;
+pub mod narration;
+pub mod openapi;
+pub mod pages;
+pub mod paired;
+pub mod pdf;
+pub mod poetry;
+pub mod problem;
+pub mod progress;
 pub mod prompts;
+pub mod puzzle;
+pub mod queue;
+pub mod quiz;
+pub mod readability;
 pub mod reading;
+pub mod reports;
+pub mod request_id;
+pub mod retry;
+pub mod rng;
+pub mod scheduler;
+pub mod search;
+pub mod security_headers;
+pub mod served;
+pub mod server;
+pub mod session;
+pub mod shutdown;
 pub mod state;
+pub mod static_files;
 pub mod storage;
+pub mod tags;
+pub mod usage;
+pub mod users;
+pub mod validation;
+pub mod websocket;
+pub mod worker;
 
+use async_openai::error::OpenAIError;
 use axum::http::StatusCode;
 use aws_smithy_types::byte_stream::error::Error as ByteStreamError;
+use std::time::Duration;
 use thiserror::Error;
 use tracing::warn;
 
@@ -17,12 +88,51 @@ pub enum ServiceError {
     #[error("DynamoDB error: {0}")]
     DynamoDbError(String),
 
+    #[error("Redis error: {0}")]
+    RedisError(String),
+
+    #[error("SQL error: {0}")]
+    SqlError(String),
+
     #[error("OpenAI API error: {0}")]
     OpenAIError(String),
 
+    #[error("Rate limited: {message}")]
+    RateLimited {
+        message: String,
+        retry_after: Option<Duration>,
+    },
+
+    #[error("Quota exceeded: {0}")]
+    QuotaExceeded(String),
+
     #[error("Configuration error: {0}")]
     ConfigError(String),
 
+    #[error("Invalid input: {0}")]
+    InvalidInput(String),
+
+    #[error("Not found: {0}")]
+    NotFound(String),
+
+    #[error("Unauthorized: {0}")]
+    Unauthorized(String),
+
+    #[error("Forbidden: {0}")]
+    Forbidden(String),
+
+    #[error("Conflict: {0}")]
+    Conflict(String),
+
+    #[error("Email error: {0}")]
+    EmailError(String),
+
+    #[error("Queue error: {0}")]
+    QueueError(String),
+
+    #[error("Generated content rejected: {0}")]
+    ContentRejected(String),
+
     #[error("JSON parsing error: {0}")]
     JsonError(#[from] serde_json::Error),
 
@@ -45,7 +155,100 @@ where
     }
 }
 
+/// How long clients are told to wait when rate limited without a hint from OpenAI
+const DEFAULT_RATE_LIMIT_RETRY_AFTER: Duration = Duration::from_secs(30);
+
+/// How long clients are told to wait once the OpenAI quota is used up
+const QUOTA_RETRY_AFTER: Duration = Duration::from_secs(3600);
+
 impl ServiceError {
+    /// Converts an OpenAI client error, prefixing its message with `context`
+    ///
+    /// Rate limit and insufficient quota errors become `RateLimited` and
+    /// `QuotaExceeded`, so clients are told to back off rather than that the
+    /// service is down.
+    pub fn from_openai(context: &str, err: OpenAIError) -> Self {
+        let message = format!("{}: {}", context, err);
+
+        if let OpenAIError::ApiError(api_error) = &err {
+            let is = |kind: &str| {
+                api_error.code.as_deref() == Some(kind) || api_error.r#type.as_deref() == Some(kind)
+            };
+
+            if is("insufficient_quota") {
+                return ServiceError::QuotaExceeded(message);
+            }
+            if is("rate_limit_exceeded") || is("requests") || is("tokens") {
+                return ServiceError::RateLimited {
+                    retry_after: parse_retry_hint(&api_error.message),
+                    message,
+                };
+            }
+        }
+
+        ServiceError::OpenAIError(message)
+    }
+
+    /// How long the client should wait before retrying, for errors that say so
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            ServiceError::RateLimited { retry_after, .. } => {
+                Some(retry_after.unwrap_or(DEFAULT_RATE_LIMIT_RETRY_AFTER))
+            }
+            ServiceError::QuotaExceeded(_) => Some(QUOTA_RETRY_AFTER),
+            _ => None,
+        }
+    }
+
+    /// Whether the error may be transient, so retrying the operation could succeed
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            ServiceError::RateLimited { .. }
+            | ServiceError::S3Error(_)
+            | ServiceError::DynamoDbError(_)
+            | ServiceError::RedisError(_)
+            | ServiceError::SqlError(_)
+            | ServiceError::EmailError(_)
+            | ServiceError::QueueError(_)
+            | ServiceError::ByteStreamError(_) => true,
+            ServiceError::IoError(e) => !matches!(
+                e.kind(),
+                std::io::ErrorKind::NotFound
+                    | std::io::ErrorKind::PermissionDenied
+                    | std::io::ErrorKind::InvalidInput
+                    | std::io::ErrorKind::InvalidData
+            ),
+            _ => false,
+        }
+    }
+
+    /// Machine-readable code identifying the kind of error in error responses
+    pub fn code(&self) -> &'static str {
+        match self {
+            ServiceError::S3Error(_) => "storage_unavailable",
+            ServiceError::DynamoDbError(_)
+            | ServiceError::RedisError(_)
+            | ServiceError::SqlError(_) => "database_unavailable",
+            ServiceError::OpenAIError(_) => "ai_unavailable",
+            ServiceError::RateLimited { .. } => "rate_limited",
+            ServiceError::QuotaExceeded(_) => "quota_exceeded",
+            ServiceError::ConfigError(_) => "configuration_error",
+            ServiceError::InvalidInput(_) => "invalid_input",
+            ServiceError::NotFound(_) => "not_found",
+            ServiceError::Unauthorized(_) => "unauthorized",
+            ServiceError::Forbidden(_) => "forbidden",
+            ServiceError::Conflict(_) => "conflict",
+            ServiceError::EmailError(_) => "email_unavailable",
+            ServiceError::QueueError(_) => "queue_unavailable",
+            ServiceError::ContentRejected(_) => "content_rejected",
+            ServiceError::JsonError(_) => "data_parsing_error",
+            ServiceError::Utf8Error(_) => "data_encoding_error",
+            ServiceError::IoError(_) => "io_error",
+            ServiceError::ByteStreamError(_) => "stream_error",
+        }
+    }
+
+    /// Converts the error into a response status and client-facing message
     pub fn into_status(self) -> (StatusCode, String) {
         warn!("Service error: {:?}", self);
         match self {
@@ -53,7 +256,9 @@ impl ServiceError {
                 StatusCode::SERVICE_UNAVAILABLE,
                 "Internal server error".to_string(),
             ),
-            ServiceError::DynamoDbError(_) => (
+            ServiceError::DynamoDbError(_)
+            | ServiceError::RedisError(_)
+            | ServiceError::SqlError(_) => (
                 StatusCode::SERVICE_UNAVAILABLE,
                 "Database service unavailable".to_string(),
             ),
@@ -61,10 +266,35 @@ impl ServiceError {
                 StatusCode::SERVICE_UNAVAILABLE,
                 "AI service unavailable".to_string(),
             ),
+            ServiceError::RateLimited { .. } => (
+                StatusCode::TOO_MANY_REQUESTS,
+                "AI service is rate limited; try again later".to_string(),
+            ),
+            ServiceError::QuotaExceeded(_) => (
+                StatusCode::TOO_MANY_REQUESTS,
+                "AI service quota exceeded; try again later".to_string(),
+            ),
             ServiceError::ConfigError(_) => (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 "Configuration error".to_string(),
             ),
+            ServiceError::InvalidInput(message) => (StatusCode::BAD_REQUEST, message),
+            ServiceError::NotFound(message) => (StatusCode::NOT_FOUND, message),
+            ServiceError::Unauthorized(message) => (StatusCode::UNAUTHORIZED, message),
+            ServiceError::Forbidden(message) => (StatusCode::FORBIDDEN, message),
+            ServiceError::Conflict(message) => (StatusCode::CONFLICT, message),
+            ServiceError::EmailError(_) => (
+                StatusCode::SERVICE_UNAVAILABLE,
+                "Email service unavailable".to_string(),
+            ),
+            ServiceError::QueueError(_) => (
+                StatusCode::SERVICE_UNAVAILABLE,
+                "Queue service unavailable".to_string(),
+            ),
+            ServiceError::ContentRejected(_) => (
+                StatusCode::SERVICE_UNAVAILABLE,
+                "Content generation failed".to_string(),
+            ),
             ServiceError::JsonError(_) => (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 "Data parsing error".to_string(),
@@ -84,3 +314,71 @@ impl ServiceError {
         }
     }
 }
+
+/// Parses the wait from OpenAI rate limit messages such as
+/// "... Please try again in 1.5s.", "... in 20ms." or "... in 6m0s."
+fn parse_retry_hint(message: &str) -> Option<Duration> {
+    let mut hint = message.split("try again in ").nth(1)?;
+    let mut seconds = 0.0;
+    let mut parsed = false;
+
+    loop {
+        let end = hint.find(|c: char| !c.is_ascii_digit() && c != '.').unwrap_or(hint.len());
+        let Ok(value) = hint[..end].parse::<f64>() else {
+            break;
+        };
+        let (scale, unit_len) = match &hint[end..] {
+            rest if rest.starts_with("ms") => (0.001, 2),
+            rest if rest.starts_with('s') => (1.0, 1),
+            rest if rest.starts_with('m') => (60.0, 1),
+            rest if rest.starts_with('h') => (3600.0, 1),
+            _ => break,
+        };
+        seconds += value * scale;
+        parsed = true;
+        hint = &hint[end + unit_len..];
+    }
+
+    // Retry-After has whole-second resolution
+    parsed.then(|| Duration::from_secs(seconds.ceil().max(1.0) as u64))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_openai::error::ApiError;
+
+    fn api_error(code: &str, message: &str) -> OpenAIError {
+        OpenAIError::ApiError(ApiError {
+            message: message.to_string(),
+            r#type: None,
+            param: None,
+            code: Some(code.to_string()),
+        })
+    }
+
+    #[test]
+    fn classifies_openai_rate_limit_and_quota_errors() {
+        let limited = ServiceError::from_openai(
+            "OpenAI API call failed",
+            api_error("rate_limit_exceeded", "Rate limit reached. Please try again in 1.5s."),
+        );
+        assert_eq!(limited.retry_after(), Some(Duration::from_secs(2)));
+        assert_eq!(limited.into_status().0, StatusCode::TOO_MANY_REQUESTS);
+
+        let quota = ServiceError::from_openai("OpenAI API call failed", api_error("insufficient_quota", "No quota"));
+        assert!(matches!(quota, ServiceError::QuotaExceeded(_)));
+        assert_eq!(quota.retry_after(), Some(QUOTA_RETRY_AFTER));
+
+        let other = ServiceError::from_openai("OpenAI API call failed", api_error("server_error", "Oops"));
+        assert!(matches!(other, ServiceError::OpenAIError(_)));
+    }
+
+    #[test]
+    fn parses_retry_hints() {
+        assert_eq!(parse_retry_hint("Please try again in 20ms."), Some(Duration::from_secs(1)));
+        assert_eq!(parse_retry_hint("Please try again in 1m30s."), Some(Duration::from_secs(90)));
+        assert_eq!(parse_retry_hint("Please try again in 7s."), Some(Duration::from_secs(7)));
+        assert_eq!(parse_retry_hint("Please try again later."), None);
+    }
+}
@@ -0,0 +1,191 @@
+use axum::{
+    extract::{Request, State},
+    http::StatusCode,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use serde::Deserialize;
+use std::{sync::Arc, time::Duration};
+use tokio::sync::Semaphore;
+
+use crate::{metrics, problem::ErrorResponse};
+
+/// How long clients are told to wait after a timeout or while the service is busy
+const RETRY_AFTER: Duration = Duration::from_secs(1);
+
+/// How long requests may take, how many may run at once, and how much
+/// uncached content an account may generate
+///
+/// Requests that may generate content wait on the model, so they get longer than
+/// the rest of the API and pages; health checks, metrics, and static files get
+/// the least. Configured in the `[limits]` table:
+///
+/// ```toml
+/// [limits]
+/// generation_timeout_secs = 90
+/// max_concurrent_requests = 128
+/// ```
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RequestLimits {
+    /// Longest a request that may generate content may take to respond
+    pub generation_timeout_secs: u64,
+
+    /// Longest other API requests and pages may take to respond
+    pub timeout_secs: u64,
+
+    /// Longest health checks, metrics, and static files may take to respond
+    pub static_timeout_secs: u64,
+
+    /// Most API requests and pages handled at once; others wait up to
+    /// `timeout_secs` for a turn, so a burst of cold-cache requests can't open
+    /// more OpenAI connections than the pool holds
+    pub max_concurrent_requests: usize,
+
+    /// Most personalized stories an account may generate per day (UTC); they
+    /// bypass the shared cache, so each one is a model call
+    pub personalized_stories_per_day: u32,
+
+    /// Most explanations of wrong answers an account may generate per day (UTC);
+    /// explanations already cached don't count
+    pub explanations_per_day: u32,
+
+    /// Most failed logins to an email address per hour (UTC), slowing password
+    /// guessing
+    pub failed_logins_per_hour: u32,
+}
+
+impl Default for RequestLimits {
+    fn default() -> Self {
+        Self {
+            generation_timeout_secs: 120,
+            timeout_secs: 30,
+            static_timeout_secs: 5,
+            max_concurrent_requests: 256,
+            personalized_stories_per_day: 10,
+            explanations_per_day: 50,
+            failed_logins_per_hour: 10,
+        }
+    }
+}
+
+impl RequestLimits {
+    /// Describes every invalid field
+    pub fn validate(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        for (name, secs) in [
+            ("generation_timeout_secs", self.generation_timeout_secs),
+            ("timeout_secs", self.timeout_secs),
+            ("static_timeout_secs", self.static_timeout_secs),
+        ] {
+            if secs == 0 {
+                problems.push(format!("limits.{} must be positive", name));
+            }
+        }
+        if self.max_concurrent_requests == 0 {
+            problems.push("limits.max_concurrent_requests must be positive".to_string());
+        }
+        if self.personalized_stories_per_day == 0 {
+            problems.push("limits.personalized_stories_per_day must be positive".to_string());
+        }
+        if self.explanations_per_day == 0 {
+            problems.push("limits.explanations_per_day must be positive".to_string());
+        }
+        if self.failed_logins_per_hour == 0 {
+            problems.push("limits.failed_logins_per_hour must be positive".to_string());
+        }
+        problems
+    }
+
+    pub fn generation_timeout(&self) -> Duration {
+        Duration::from_secs(self.generation_timeout_secs)
+    }
+
+    pub fn timeout(&self) -> Duration {
+        Duration::from_secs(self.timeout_secs)
+    }
+
+    pub fn static_timeout(&self) -> Duration {
+        Duration::from_secs(self.static_timeout_secs)
+    }
+}
+
+/// A retryable 503
+fn unavailable(code: &'static str, detail: &str) -> Response {
+    ErrorResponse {
+        retryable: true,
+        retry_after: Some(RETRY_AFTER),
+        ..ErrorResponse::new(StatusCode::SERVICE_UNAVAILABLE, code, detail)
+    }
+    .into_response()
+}
+
+/// Middleware that answers 503 when a request takes longer than `limit` to
+/// respond
+///
+/// Only the time until the response starts counts, so streams and WebSockets can
+/// run on after it.
+pub async fn timeout(State(limit): State<Duration>, request: Request, next: Next) -> Response {
+    match tokio::time::timeout(limit, next.run(request)).await {
+        Ok(response) => response,
+        Err(_) => {
+            metrics::increment("requests.timed_out");
+            unavailable("timeout", "The request took too long; try again later")
+        }
+    }
+}
+
+/// Turns to handle a request, shared by every route it limits
+#[derive(Debug, Clone)]
+pub struct ConcurrencyLimit {
+    permits: Arc<Semaphore>,
+    wait: Duration,
+}
+
+impl ConcurrencyLimit {
+    pub fn new(limits: &RequestLimits) -> Self {
+        Self {
+            permits: Arc::new(Semaphore::new(limits.max_concurrent_requests)),
+            wait: limits.timeout(),
+        }
+    }
+}
+
+/// Middleware that holds requests beyond the concurrency limit until one in
+/// progress responds, answering 503 if none does in time
+pub async fn concurrency_limit(State(limit): State<ConcurrencyLimit>, request: Request, next: Next) -> Response {
+    let Ok(Ok(_permit)) = tokio::time::timeout(limit.wait, limit.permits.acquire_owned()).await else {
+        metrics::increment("requests.shed");
+        return unavailable("overloaded", "The service is busy; try again shortly");
+    };
+    next.run(request).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{Router, body::Body, routing::get};
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn times_out_and_sheds_requests() {
+        let limit = ConcurrencyLimit { permits: Arc::new(Semaphore::new(1)), wait: Duration::from_millis(20) };
+        let app = Router::new()
+            .route("/slow", get(|| tokio::time::sleep(Duration::from_secs(10))))
+            .route_layer(axum::middleware::from_fn_with_state(Duration::from_millis(20), timeout))
+            .route("/wait", get(|| tokio::time::sleep(Duration::from_millis(200))))
+            .route_layer(axum::middleware::from_fn_with_state(limit, concurrency_limit));
+        let request = |path| axum::http::Request::get(path).body(Body::empty()).unwrap();
+
+        let slow = app.clone().oneshot(request("/slow")).await.unwrap();
+        assert_eq!(slow.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+        let (first, second) = tokio::join!(app.clone().oneshot(request("/wait")), app.oneshot(request("/wait")));
+        assert_eq!(first.unwrap().status(), StatusCode::OK);
+        let second = second.unwrap();
+        assert_eq!(second.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(second.headers()["retry-after"], "1");
+
+        assert_eq!(RequestLimits { timeout_secs: 0, ..Default::default() }.validate().len(), 1);
+    }
+}
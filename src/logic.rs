@@ -0,0 +1,348 @@
+use axum::{
+    Json,
+    extract::{Query, State},
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    ServiceError,
+    content::{ContentAnnotator, ContentParams, ContentSchema, ContentTypeDescriptor},
+    keyvalue::KeyValueStore,
+    problem::ErrorResponse,
+    reading::ReadingQuery,
+    session::Session,
+    state::AppState,
+    storage::ObjectStore,
+    validation::{BannedWords, ContentValidator, MinItems, NoEmptyFields},
+};
+
+/// Storage prefix and registry identifier for logic puzzles
+pub const LOGIC_PREFIX: &str = "logic";
+
+/// Grade used when the request doesn't specify one
+const DEFAULT_GRADE: u8 = 4;
+
+/// Fewest and most categories in a puzzle, counting the first
+const MIN_CATEGORIES: usize = 2;
+const MAX_CATEGORIES: usize = 4;
+
+/// Fewest and most values in each category
+const MIN_VALUES: usize = 3;
+const MAX_VALUES: usize = 5;
+
+/// Query parameters accepted by the logic puzzle endpoint
+#[derive(Debug, Deserialize, Default, JsonSchema)]
+pub struct LogicQuery {
+    /// Grade level the puzzle should target
+    pub grade: Option<u8>,
+
+    /// Theme of the puzzle (e.g., "pets")
+    pub topic: Option<String>,
+}
+
+impl LogicQuery {
+    /// Validates the query and converts it into content parameters, accepting the
+    /// same grades and topics as reading passages
+    pub fn into_params(self) -> Result<ContentParams, ServiceError> {
+        let grade = Some(self.grade.unwrap_or(DEFAULT_GRADE));
+        ReadingQuery { grade, topic: self.topic, ..Default::default() }.into_params()
+    }
+}
+
+/// A kind of thing matched up in a puzzle (e.g., "Pet"), and its values
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+pub struct Category {
+    pub name: String,
+    pub values: Vec<String>,
+}
+
+/// A clue saying whether two values, from different categories, go together
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+pub struct LogicClue {
+    /// The clue as students read it
+    pub text: String,
+
+    /// The values the clue is about, each exactly as listed in its category
+    pub first: String,
+    pub second: String,
+
+    /// Whether the values go together, or are known not to
+    pub same: bool,
+}
+
+/// A grid logic puzzle: match each value of the first category with one value
+/// of every other category, using the clues
+#[derive(Serialize, Deserialize, Clone, JsonSchema)]
+pub struct LogicPuzzle {
+    pub title: String,
+
+    /// The puzzle's setup, as students read it
+    pub introduction: String,
+
+    pub categories: Vec<Category>,
+    pub clues: Vec<LogicClue>,
+
+    /// The one solution, found by the service: for each value of the first
+    /// category, in order, its value in every category
+    #[serde(default)]
+    #[schemars(skip_deserializing)]
+    pub solution: Vec<Vec<String>>,
+}
+
+/// A clue with its values resolved to (category, value) indexes
+struct Constraint {
+    first: (usize, usize),
+    second: (usize, usize),
+    same: bool,
+}
+
+/// Rows of the solution grid each value is in, by category then value; the
+/// first category's values are rows 0, 1, ...
+type Assignment = Vec<Vec<usize>>;
+
+/// Resolves a puzzle's clues, checking its categories are well formed
+fn constraints(puzzle: &LogicPuzzle) -> Result<Vec<Constraint>, String> {
+    let categories = &puzzle.categories;
+    if !(MIN_CATEGORIES..=MAX_CATEGORIES).contains(&categories.len()) {
+        return Err(format!("puzzles need {} to {} categories", MIN_CATEGORIES, MAX_CATEGORIES));
+    }
+    let size = categories[0].values.len();
+    if !(MIN_VALUES..=MAX_VALUES).contains(&size) || categories.iter().any(|c| c.values.len() != size) {
+        return Err(format!("every category needs the same number of values, {} to {}", MIN_VALUES, MAX_VALUES));
+    }
+
+    let lookup = |value: &str| {
+        let found: Vec<(usize, usize)> = categories
+            .iter()
+            .enumerate()
+            .flat_map(|(c, category)| {
+                category
+                    .values
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, v)| v.trim().eq_ignore_ascii_case(value.trim()))
+                    .map(move |(v, _)| (c, v))
+            })
+            .collect();
+        match found.as_slice() {
+            [index] => Ok(*index),
+            [] => Err(format!("clue names {:?}, which isn't a value of any category", value)),
+            _ => Err(format!("{:?} is listed more than once", value)),
+        }
+    };
+
+    puzzle
+        .clues
+        .iter()
+        .map(|clue| {
+            let (first, second) = (lookup(&clue.first)?, lookup(&clue.second)?);
+            if first.0 == second.0 {
+                return Err(format!("clue {:?} compares values of the same category", clue.text));
+            }
+            Ok(Constraint { first, second, same: clue.same })
+        })
+        .collect()
+}
+
+/// Extends `partial`, which places the first `partial.len()` categories, with
+/// every placement of the rest the constraints allow, stopping at `limit`
+/// solutions
+fn search(
+    constraints: &[Constraint],
+    categories: usize,
+    size: usize,
+    partial: &mut Assignment,
+    found: &mut Vec<Assignment>,
+    limit: usize,
+) {
+    if found.len() >= limit {
+        return;
+    }
+    if partial.len() == categories {
+        found.push(partial.clone());
+        return;
+    }
+
+    let mut rows: Vec<usize> = (0..size).collect();
+    loop {
+        partial.push(rows.clone());
+        let placed = partial.len();
+        let consistent = constraints.iter().all(|constraint| {
+            let (a, b) = (constraint.first, constraint.second);
+            a.0 >= placed || b.0 >= placed || (partial[a.0][a.1] == partial[b.0][b.1]) == constraint.same
+        });
+        if consistent {
+            search(constraints, categories, size, partial, found, limit);
+        }
+        partial.pop();
+        if found.len() >= limit || !next_permutation(&mut rows) {
+            return;
+        }
+    }
+}
+
+/// Advances `items` to the next permutation in lexicographic order, returning
+/// false after the last
+fn next_permutation(items: &mut [usize]) -> bool {
+    let Some(i) = (1..items.len()).rev().find(|&i| items[i - 1] < items[i]) else {
+        return false;
+    };
+    let j = (i..items.len()).rev().find(|&j| items[j] > items[i - 1]).unwrap_or(i);
+    items.swap(i - 1, j);
+    items[i..].reverse();
+    true
+}
+
+/// Solves a puzzle, returning up to two solutions, which is enough to tell
+/// whether it has exactly one
+fn solve(puzzle: &LogicPuzzle) -> Result<Vec<Assignment>, String> {
+    let constraints = constraints(puzzle)?;
+    let size = puzzle.categories[0].values.len();
+    let mut partial = vec![(0..size).collect()];
+    let mut found = Vec::new();
+    search(&constraints, puzzle.categories.len(), size, &mut partial, &mut found, 2);
+    Ok(found)
+}
+
+/// Lists each row's value in every category
+fn solution_rows(puzzle: &LogicPuzzle, assignment: &Assignment) -> Vec<Vec<String>> {
+    (0..puzzle.categories[0].values.len())
+        .map(|row| {
+            puzzle
+                .categories
+                .iter()
+                .zip(assignment)
+                .filter_map(|(category, rows)| {
+                    let value = rows.iter().position(|&r| r == row)?;
+                    Some(category.values[value].clone())
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Requires a puzzle to have exactly one solution, found by trying every way
+/// of matching up its values
+#[derive(Debug, Clone, Default)]
+pub struct UniquelySolvable;
+
+impl ContentValidator for UniquelySolvable {
+    fn validate(&self, content: &Value, _params: &ContentParams) -> Result<(), String> {
+        let puzzle: LogicPuzzle =
+            serde_json::from_value(content.clone()).map_err(|e| format!("malformed puzzle: {}", e))?;
+        match solve(&puzzle)?.len() {
+            0 => Err("the clues contradict each other, so the puzzle has no solution".to_string()),
+            1 => Ok(()),
+            _ => Err("the clues allow more than one solution".to_string()),
+        }
+    }
+}
+
+/// Fills in `solution` with the puzzle's one solution
+#[derive(Debug, Clone, Default)]
+pub struct SolvePuzzle;
+
+impl ContentAnnotator for SolvePuzzle {
+    fn annotate(&self, content: &mut Value, _params: &ContentParams) {
+        let Ok(puzzle) = serde_json::from_value::<LogicPuzzle>(content.clone()) else {
+            return;
+        };
+        if let Ok([assignment]) = solve(&puzzle).as_deref() {
+            content["solution"] = serde_json::to_value(solution_rows(&puzzle, assignment)).unwrap_or_default();
+        }
+    }
+}
+
+/// Returns the content type descriptor for logic puzzles
+pub fn descriptor() -> ContentTypeDescriptor {
+    ContentTypeDescriptor::new(
+        LOGIC_PREFIX,
+        "logic_puzzle",
+        ContentSchema::for_type::<LogicPuzzle>(
+            "LogicPuzzle",
+            "A grid logic puzzle with categories of values to match up, and clues",
+        ),
+    )
+    .with_validator(MinItems::new("clues", 2))
+    .with_validator(UniquelySolvable)
+    .with_validator(NoEmptyFields)
+    .with_validator(BannedWords::default())
+    .with_annotator(SolvePuzzle)
+    .with_default_params(ContentParams::new().with("grade", DEFAULT_GRADE))
+}
+
+/// Serves a cached logic puzzle the session hasn't solved, or generates a new
+/// one; only puzzles with exactly one solution are ever cached
+pub async fn logic_puzzles<S: ObjectStore, K: KeyValueStore>(
+    State(state): State<AppState<S, K>>,
+    Query(query): Query<LogicQuery>,
+    session: Session,
+) -> Result<Json<LogicPuzzle>, ErrorResponse> {
+    let params = query.into_params()?;
+
+    let descriptor = state
+        .content_types
+        .get(LOGIC_PREFIX)
+        .ok_or_else(|| ServiceError::ConfigError(LOGIC_PREFIX.into()))?;
+
+    let session_id = session.id();
+    let params = state
+        .with_prompt_variant(descriptor, params, Some(session_id))
+        .await?;
+
+    let puzzle: LogicPuzzle = state
+        .get_or_generate(descriptor, &params, Some(session_id))
+        .await?;
+
+    Ok(Json(puzzle))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn accepts_only_puzzles_with_one_solution() {
+        let clue = |first: &str, second: &str, same: bool| {
+            json!({ "text": format!("{} and {}", first, second), "first": first, "second": second, "same": same })
+        };
+        let puzzle = |clues: Vec<Value>| {
+            json!({
+                "title": "Pet Show",
+                "introduction": "Three friends brought pets in different colors.",
+                "categories": [
+                    { "name": "Child", "values": ["Ana", "Ben", "Cy"] },
+                    { "name": "Pet", "values": ["cat", "dog", "fish"] },
+                    { "name": "Color", "values": ["red", "blue", "green"] },
+                ],
+                "clues": clues,
+            })
+        };
+        let params = ContentParams::new();
+        let mut clues = vec![
+            clue("Ana", "cat", true),
+            clue("Ben", "fish", false),
+            clue("dog", "blue", true),
+            clue("Cy", "red", false),
+        ];
+
+        let mut solvable = puzzle(clues.clone());
+        assert_eq!(UniquelySolvable.validate(&solvable, &params), Ok(()));
+        SolvePuzzle.annotate(&mut solvable, &params);
+        assert_eq!(
+            solvable["solution"],
+            json!([["Ana", "cat", "red"], ["Ben", "dog", "blue"], ["Cy", "fish", "green"]])
+        );
+
+        clues.pop();
+        assert!(UniquelySolvable.validate(&puzzle(clues.clone()), &params).unwrap_err().contains("more than one"));
+        clues.push(clue("Ana", "dog", true));
+        assert!(UniquelySolvable.validate(&puzzle(clues.clone()), &params).unwrap_err().contains("no solution"));
+        clues.push(clue("Ana", "Ben", false));
+        assert!(UniquelySolvable.validate(&puzzle(clues), &params).unwrap_err().contains("same category"));
+        assert!(UniquelySolvable.validate(&puzzle(vec![clue("Ana", "hamster", true)]), &params).is_err());
+    }
+}
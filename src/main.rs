@@ -1,96 +1,556 @@
 use axum::{
-    body::Body,
-    http::{header, StatusCode},
+    http::HeaderMap,
     response::Response,
     routing::get,
     Router,
 };
-use thinkaroo::{keyvalue::DynamoKeyValueStore, prompts, reading, state::AppState, storage::S3ObjectStore};
-use tokio::fs::File;
-use tokio_util::io::ReaderStream;
+use chrono::Utc;
+use clap::{Args, Parser, Subcommand};
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use thinkaroo::{admin, api, assets, audit, bilingual, cloze, config::Config, content::ContentTypeRegistry, daily, flashcards, gc, grammar, grpc, health, history, illustration, logic, metrics, narration, openapi, paired, poetry, prompts, puzzle, quiz, reading, request_id, state::{AppState, DynAppState}, static_files};
+use thinkaroo::config::{KvBackend, StorageBackend};
+use thinkaroo::{ServiceError, reports, server, session, shutdown, users, worker};
+use tokio_util::sync::CancellationToken;
 use tracing::{error, info};
-use thinkaroo::keyvalue::MemoryKeyValueStore;
-use thinkaroo::storage::DiskObjectStore;
+use thinkaroo::dedup::NoopEmbedder;
+use thinkaroo::moderation::NoopModerator;
+use thinkaroo::narration::NoopNarrator;
+use thinkaroo::problem::ErrorResponse;
+use thinkaroo::limits::{self, ConcurrencyLimit};
+use thinkaroo::scheduler::Scheduler;
+use thinkaroo::security_headers::{self, SecurityHeaders};
 
 async fn health() -> &'static str {
     "OK"
 }
 
-async fn stream_file(file_path: &str) -> Result<Response, (StatusCode, String)> {
-    let file = File::open(file_path).await.map_err(|e| {
-        error!("Failed to open file {}: {}", file_path, e);
-        (StatusCode::NOT_FOUND, "File not found".to_string())
-    })?;
+async fn home(headers: HeaderMap) -> Result<Response, ErrorResponse> {
+    static_files::serve("home.html", &headers)
+}
+
+/// Thinkaroo test preparation service
+#[derive(Parser)]
+#[command(name = "thinkaroo", version)]
+struct Cli {
+    /// What to run; the server if omitted
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Runs the HTTP server
+    Serve(ServeArgs),
+
+    /// Generates content into the object store without serving requests
+    Generate {
+        /// Prefix of the content type to generate (e.g., "reading")
+        #[arg(long)]
+        content_type: String,
+
+        /// Number of objects to generate
+        #[arg(long, default_value_t = 16)]
+        count: usize,
+    },
+
+    /// Generates content for jobs from the generation queue without serving requests
+    Worker {
+        /// Number of jobs to generate at once
+        #[arg(long, default_value_t = 4)]
+        concurrency: usize,
+    },
+
+    /// Parses every prompt file and reports any errors
+    ValidatePrompts {
+        /// Also check the prompt files in this directory
+        #[arg(long)]
+        dir: Option<PathBuf>,
+    },
+
+    /// Lists, prints, or replays audited LLM requests
+    Audit {
+        #[command(subcommand)]
+        command: AuditCommand,
+    },
+}
+
+/// Ways to look at the audit log
+#[derive(Subcommand)]
+enum AuditCommand {
+    /// Lists the keys of the records written on a day
+    List {
+        /// Day to list, as YYYY-MM-DD; today (UTC) if unset
+        #[arg(long)]
+        date: Option<String>,
+    },
+
+    /// Prints a record as JSON
+    Inspect {
+        /// Key of the record, e.g. "audit/2025-10-11/reading/143005123-1a2b.json"
+        key: String,
+    },
+
+    /// Sends a recorded request to the model again and prints both responses
+    Replay {
+        /// Key of the record to replay
+        key: String,
+    },
+}
+
+/// Overrides for the loaded configuration when serving
+#[derive(Args, Default)]
+struct ServeArgs {
+    /// Address to listen on, overriding BIND_ADDRESS
+    #[arg(long)]
+    bind: Option<SocketAddr>,
+
+    /// Port to listen on, keeping the configured host
+    #[arg(long)]
+    port: Option<u16>,
+
+    /// Also listen on all IPv6 interfaces at the same port
+    #[arg(long)]
+    ipv6: bool,
 
-    let stream = ReaderStream::new(file);
-    let body = Body::from_stream(stream);
+    /// PEM certificate chain to serve HTTPS with (requires --tls-key)
+    #[arg(long)]
+    tls_cert: Option<PathBuf>,
 
-    let response = Response::builder()
-        .header(header::CONTENT_TYPE, "text/html; charset=utf-8")
-        .body(body)
-        .map_err(|e| {
-            error!("Failed to build response for {}: {}", file_path, e);
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                "Internal server error".to_string(),
-            )
-        })?;
+    /// PEM private key for --tls-cert
+    #[arg(long)]
+    tls_key: Option<PathBuf>,
 
-    Ok(response)
+    /// Object storage backend (s3, disk, or memory), overriding STORAGE_BACKEND
+    #[arg(long)]
+    storage_backend: Option<StorageBackend>,
+
+    /// Key-value backend (dynamo, memory, redis, or sql), overriding KV_BACKEND
+    #[arg(long)]
+    kv_backend: Option<KvBackend>,
 }
 
-async fn home() -> Result<Response, (StatusCode, String)> {
-    stream_file("static/home.html").await
+impl ServeArgs {
+    /// Applies the flags to a configuration and validates the result
+    fn apply(&self, config: &mut Config) -> Result<(), ServiceError> {
+        if let Some(bind) = self.bind {
+            config.bind_address = bind;
+        }
+        if let Some(port) = self.port {
+            config.bind_address.set_port(port);
+        }
+        if self.ipv6 {
+            config.bind_ipv6 = true;
+        }
+        if let Some(path) = &self.tls_cert {
+            config.tls_cert_path = Some(path.clone());
+        }
+        if let Some(path) = &self.tls_key {
+            config.tls_key_path = Some(path.clone());
+        }
+        if let Some(backend) = self.storage_backend {
+            config.storage_backend = Some(backend);
+        }
+        if let Some(backend) = self.kv_backend {
+            config.kv_backend = Some(backend);
+        }
+
+        config.validate()
+    }
 }
 
-async fn reading() -> Result<Response, (StatusCode, String)> {
-    stream_file("static/reading.html").await
+/// Logs an error and exits, for failures that leave nothing to run
+fn or_exit<T>(result: Result<T, ServiceError>, context: &str) -> T {
+    result.unwrap_or_else(|e| {
+        error!("{}: {}", context, e);
+        std::process::exit(1);
+    })
 }
 
+/// Returns the content types served by this instance
+fn content_types() -> ContentTypeRegistry {
+    ContentTypeRegistry::new()
+        .register(reading::descriptor())
+        .register(bilingual::descriptor())
+        .register(quiz::descriptor())
+        .register(daily::descriptor())
+        .register(cloze::descriptor())
+        .register(puzzle::descriptor())
+        .register(flashcards::descriptor())
+        .register(grammar::descriptor())
+        .register(poetry::descriptor())
+        .register(history::descriptor())
+        .register(logic::descriptor())
+        .register(paired::descriptor())
+}
+
+/// Loads configuration, applies command-line overrides, and creates the backends
+/// it selects: S3 and DynamoDB with APP_ENV=production, disk and memory in
+/// development, unless STORAGE_BACKEND or KV_BACKEND say otherwise
+async fn app_state(args: &ServeArgs) -> DynAppState {
+    let mut config = or_exit(Config::load(), "Invalid configuration");
+    or_exit(args.apply(&mut config), "Invalid configuration");
+    info!("Loaded configuration: {:?}", config);
+
+    or_exit(
+        AppState::from_config(config, content_types()).await,
+        "Failed to initialize storage backends",
+    )
+}
+
+/// Initializes the tracing subscriber
+///
+/// Logs are human-readable text by default; set `LOG_FORMAT=json` for one JSON
+/// object per line, including the current request's span fields, for log
+/// aggregation.
+fn init_tracing() {
+    let filter = tracing_subscriber::EnvFilter::try_from_default_env()
+        .unwrap_or_else(|_| "info".into());
+    let builder = tracing_subscriber::fmt().with_env_filter(filter);
+
+    if std::env::var("LOG_FORMAT").is_ok_and(|format| format.eq_ignore_ascii_case("json")) {
+        builder.json().with_current_span(true).with_span_list(false).init();
+    } else {
+        builder.init();
+    }
+}
 
 #[tokio::main]
 async fn main() {
-    // Initialize tracing subscriber
-    tracing_subscriber::fmt()
-        .with_env_filter(
-            tracing_subscriber::EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| "info".into()),
-        )
-        .init();
+    init_tracing();
+
+    match Cli::parse().command {
+        #[cfg(feature = "lambda")]
+        None if thinkaroo::lambda::on_lambda() => serve_lambda().await,
+        None => serve(ServeArgs::default()).await,
+        Some(Command::Serve(args)) => serve(args).await,
+        Some(Command::Generate { content_type, count }) => generate(&content_type, count).await,
+        Some(Command::Worker { concurrency }) => run_worker(concurrency).await,
+        Some(Command::ValidatePrompts { dir }) => validate_prompts(dir).await,
+        Some(Command::Audit { command }) => audit(command).await,
+    }
+}
+
+/// Generates content for one content type into the object store
+async fn generate(content_type: &str, count: usize) {
+    let app_state = app_state(&ServeArgs::default()).await;
+
+    // Use prompts uploaded to the object store, as the server would
+    let uploaded = or_exit(
+        prompts::load_prompts_from_store(&app_state.object_store).await,
+        "Failed to load prompts",
+    );
+    prompts::install_prompts(uploaded);
+
+    let Some(descriptor) = app_state.content_types.get(content_type) else {
+        let known: Vec<&str> = app_state.content_types.iter().map(|d| d.prefix.as_str()).collect();
+        error!("Unknown content type {:?}; expected one of {:?}", content_type, known);
+        std::process::exit(1);
+    };
+
+    let generated = or_exit(
+        app_state
+            .generate_batch(descriptor, &descriptor.default_params, count)
+            .await,
+        "Generation failed",
+    );
+    info!("Generated {} {} objects", generated, content_type);
+}
+
+/// Generates content for queued jobs until a signal stops the worker
+async fn run_worker(concurrency: usize) {
+    let app_state = disable_services(app_state(&ServeArgs::default()).await);
+    let Some(queue) = app_state.generation_queue.clone() else {
+        error!("GENERATION_QUEUE_URL must be set to run a worker");
+        std::process::exit(1);
+    };
 
+    // Use prompts uploaded to the object store, as the server does, reloading them
+    // periodically
+    let shutdown_token = CancellationToken::new();
+    let reloader = prompts::spawn_prompt_reloader(
+        app_state.object_store.clone(),
+        std::env::var("PROMPTS_DIR").ok().map(Into::into),
+        prompts::PROMPT_RELOAD_INTERVAL,
+        shutdown_token.clone(),
+    );
+
+    tokio::spawn(shutdown::wait_for_signal(shutdown_token.clone()));
+    worker::run(app_state, queue, concurrency.max(1), shutdown_token).await;
+    shutdown::join_tasks(vec![reloader], shutdown::SHUTDOWN_DEADLINE).await;
+}
+
+/// Parses the embedded prompts, and those in `dir`, exiting with an error if any fail
+async fn validate_prompts(dir: Option<PathBuf>) {
+    let results = or_exit(
+        prompts::validate_prompt_files(dir.as_deref()).await,
+        "Failed to read prompts",
+    );
+
+    let mut failures = 0;
+    for (file, result) in &results {
+        match result {
+            Ok(()) => println!("ok      {}", file),
+            Err(e) => {
+                failures += 1;
+                println!("FAILED  {}: {}", file, e);
+            }
+        }
+    }
+
+    println!("{} prompt files checked, {} failed", results.len(), failures);
+    if failures > 0 {
+        std::process::exit(1);
+    }
+}
+
+/// Lists, prints, or replays audit records
+async fn audit(command: AuditCommand) {
+    let app_state = app_state(&ServeArgs::default()).await;
+
+    match command {
+        AuditCommand::List { date } => {
+            let date = date.unwrap_or_else(|| Utc::now().format("%Y-%m-%d").to_string());
+            let keys = or_exit(
+                audit::list(&app_state.object_store, &date).await,
+                "Failed to list audit records",
+            );
+            for key in &keys {
+                println!("{}", key);
+            }
+            println!("{} audit records on {}", keys.len(), date);
+        }
+        AuditCommand::Inspect { key } => {
+            let record = or_exit(
+                audit::read(&app_state.object_store, &key).await,
+                "Failed to read audit record",
+            );
+            let json = or_exit(
+                serde_json::to_string_pretty(&record).map_err(ServiceError::from),
+                "Failed to format audit record",
+            );
+            println!("{}", json);
+        }
+        AuditCommand::Replay { key } => {
+            let record = or_exit(
+                audit::read(&app_state.object_store, &key).await,
+                "Failed to read audit record",
+            );
+            let replayed = or_exit(app_state.replay_audit(&record).await, "Replay failed");
+            println!("Recorded response ({}):", record.requested_at);
+            println!("{}", record.response.as_deref().or(record.error.as_deref()).unwrap_or(""));
+            println!();
+            println!("Replayed response ({}):", record.model);
+            println!("{}", replayed);
+        }
+    }
+}
+
+/// Builds the application: every route, with the middleware each needs
+fn router(app_state: DynAppState) -> Router {
+    // Pages, the API, and media track the visitor's session; probes and assets
+    // don't need to. The API is also served at its original unversioned paths.
+    // Pages that may wait on the model get longer to respond than the rest, and
+    // only so many requests that might are handled at once.
+    let limits = app_state.config.limits.clone();
+    let timeout = |limit| axum::middleware::from_fn_with_state(limit, limits::timeout);
+    let concurrency = ConcurrencyLimit::new(&limits);
+    let generated_pages = Router::new()
+        .route("/reading", get(reading::reading_page))
+        .route("/reading_audio/{story_id}", get(narration::reading_audio))
+        .route_layer(timeout(limits.generation_timeout()));
+    let pages = Router::new()
+        .route("/home", get(home))
+        .route("/", get(home))
+        .route("/images/{*key}", get(illustration::image))
+        .route_layer(timeout(limits.timeout()))
+        .merge(generated_pages)
+        .nest(api::CURRENT_VERSION, api::v1(&app_state))
+        .merge(api::v1(&app_state).route_layer(axum::middleware::from_fn(api::deprecated)))
+        .route_layer(axum::middleware::from_fn_with_state(
+            app_state.clone(),
+            users::current_user,
+        ))
+        .route_layer(axum::middleware::from_fn_with_state(
+            app_state.clone(),
+            session::session,
+        ))
+        .route_layer(axum::middleware::from_fn_with_state(concurrency.clone(), limits::concurrency_limit));
+
+    let mut app = Router::new()
+        .route("/health", get(health))
+        .route("/ready", get(health::ready))
+        .route("/metrics", get(metrics::metrics))
+        .route("/openapi.json", get(openapi::openapi_json))
+        .route("/docs", get(openapi::docs))
+        .route("/assets/{*path}", get(assets::asset))
+        .route("/static/{*path}", get(static_files::static_file))
+        .route_layer(timeout(limits.static_timeout()))
+        .merge(pages);
+
+    // Admin endpoints are only exposed when a token to protect them is configured
+    if let Some(token) = &app_state.config.admin_token {
+        info!("Admin API enabled under /admin");
+        app = app.nest("/admin", admin::router(token));
+    }
+
+    // Likewise the gRPC API for internal services, served over HTTP/2
+    if let Some(token) = &app_state.config.grpc_token {
+        info!("gRPC API enabled");
+        app = app.merge(
+            grpc::router(token)
+                .route_layer(timeout(limits.generation_timeout()))
+                .route_layer(axum::middleware::from_fn_with_state(concurrency, limits::concurrency_limit)),
+        );
+    }
+
+    let config = app_state.config.clone();
+    app.with_state(app_state)
+        .layer(axum::middleware::from_fn_with_state(
+            SecurityHeaders::new(&config.security_headers),
+            security_headers::security_headers,
+        ))
+        .layer(axum::middleware::from_fn(request_id::request_id))
+}
+
+/// Swaps out services disabled for local development by `DISABLE_*` variables
+fn disable_services(mut app_state: DynAppState) -> DynAppState {
+    // Moderation calls can be skipped in local development
+    if std::env::var("DISABLE_MODERATION").is_ok() {
+        info!("Content moderation disabled");
+        app_state = app_state.with_moderator(NoopModerator);
+    }
+    if std::env::var("DISABLE_NARRATION").is_ok() {
+        info!("Story narration disabled");
+        app_state = app_state.with_narrator(NoopNarrator);
+    }
+    if std::env::var("DISABLE_DEDUP").is_ok() {
+        info!("Content deduplication disabled");
+        app_state = app_state.with_embedder(NoopEmbedder);
+    }
+    app_state
+}
+
+/// Serves requests as an AWS Lambda function, when built with the `lambda` feature
+/// and started by the Lambda runtime
+///
+/// Background tasks don't run, since the instance is frozen between invocations;
+/// prompts are loaded once at startup.
+#[cfg(feature = "lambda")]
+async fn serve_lambda() {
+    let app_state = disable_services(app_state(&ServeArgs::default()).await);
+    let problems = thinkaroo::lambda::check_config(&app_state.config);
+    if !problems.is_empty() {
+        error!("Invalid configuration for Lambda: {}", problems.join("; "));
+        std::process::exit(1);
+    }
+
+    let prompts_dir = std::env::var("PROMPTS_DIR").ok().map(PathBuf::from);
+    or_exit(
+        prompts::reload_prompts(&app_state.object_store, prompts_dir.as_deref()).await,
+        "Failed to load prompts",
+    );
+    or_exit(thinkaroo::lambda::run(router(app_state)).await, "Lambda runtime failed");
+}
+
+/// Builds the scheduler for the background jobs configured in `[schedule]`
+fn scheduler(app_state: &DynAppState) -> Scheduler {
+    let schedules = &app_state.config.schedule;
+    let mut scheduler = Scheduler::new();
+
+    if let Some(schedule) = &schedules.pregenerate {
+        let state = app_state.clone();
+        scheduler = scheduler.add("pregenerate", schedule.clone(), move |shutdown| {
+            let state = state.clone();
+            async move {
+                state.warm_cache(&shutdown).await;
+                Ok(())
+            }
+        });
+    }
+
+    if let (Some(schedule), Some(hours)) = (&schedules.gc, schedules.gc_retention_hours) {
+        let retention = chrono::Duration::hours(hours.into());
+        let dry_run = schedules.gc_dry_run;
+        info!("Deleting content older than {} hours (dry run: {})", hours, dry_run);
+        let state = app_state.clone();
+        scheduler = scheduler.add("gc", schedule.clone(), move |_shutdown| {
+            let state = state.clone();
+            async move {
+                let report = gc::collect_garbage(&state.object_store, &state.content_types, retention, dry_run).await?;
+                info!("Garbage collection finished: {:?}", report);
+                Ok(())
+            }
+        });
+    }
+
+    if let Some(schedule) = &schedules.weekly_reports {
+        let state = app_state.clone();
+        scheduler = scheduler.add("weekly_reports", schedule.clone(), move |shutdown| {
+            let state = state.clone();
+            async move {
+                let written = reports::run_weekly_reports(&state, Utc::now().date_naive(), &shutdown).await?;
+                if written > 0 {
+                    info!("Wrote {} weekly reports", written);
+                }
+                Ok(())
+            }
+        });
+    }
+
+    scheduler
+}
+
+/// Runs the HTTP server until it exits
+async fn serve(args: ServeArgs) {
     // Initialize prompts (load at startup)
     let prompt_names = prompts::list_prompt_names();
     info!("Loaded {} prompts: {:?}", prompt_names.len(), prompt_names);
 
-    // Initialize AWS configuration and storage backends
-    let aws_config = aws_config::load_defaults(aws_config::BehaviorVersion::latest()).await;
-    //let object_store = S3ObjectStore::new(aws_sdk_s3::Client::new(&aws_config));
-    let object_store = DiskObjectStore::new();
+    let app_state = disable_services(app_state(&args).await);
+    info!(
+        "Initialized AppState with {:?} object storage, {:?} key-value store, and OpenAI client",
+        app_state.config.storage_backend(),
+        app_state.config.kv_backend()
+    );
 
-    //let kv_store = DynamoKeyValueStore::new(aws_sdk_dynamodb::Client::new(&aws_config));
-    let kv_store = MemoryKeyValueStore::new();
+    // Cancelled on SIGINT or SIGTERM; background tasks stop and the server drains
+    let shutdown_token = CancellationToken::new();
+    let mut background_tasks = Vec::new();
 
-    // Get OpenAI API key from environment
-    let openai_api_key = std::env::var("OPENAI_API_KEY")
-        .expect("OPENAI_API_KEY environment variable must be set");
+    // Load prompts uploaded to the object store, and optionally from a directory,
+    // reloading periodically
+    let prompts_dir = std::env::var("PROMPTS_DIR").ok();
+    if let Some(dir) = &prompts_dir {
+        info!("Loading prompts from {} with periodic reload", dir);
+    }
+    background_tasks.push(prompts::spawn_prompt_reloader(
+        app_state.object_store.clone(),
+        prompts_dir.map(Into::into),
+        prompts::PROMPT_RELOAD_INTERVAL,
+        shutdown_token.clone(),
+    ));
 
-    // Initialize application state with all clients
-    let app_state = AppState::new(object_store, kv_store, openai_api_key).await;
-    info!("Initialized AppState with S3 object storage, DynamoDB key-value store, and OpenAI client");
+    // Optionally fill the current hour's cache in the background; off by default so
+    // local development doesn't spend tokens on every restart
+    if std::env::var("WARM_CACHE_ON_STARTUP").is_ok() {
+        let state = app_state.clone();
+        let token = shutdown_token.clone();
+        background_tasks.push(tokio::spawn(async move { state.warm_cache(&token).await }));
+    }
 
-    let app = Router::new()
-        .route("/health", get(health))
-        .route("/home", get(home))
-        .route("/", get(home))
-        .route("/reading", get(reading))
-        .route("/reading_contents", get(reading::reading_contents))
-        .with_state(app_state);
+    // Run the scheduled jobs; each is off unless `[schedule]` sets it, so local
+    // development doesn't spend tokens on them
+    background_tasks.extend(scheduler(&app_state).spawn(shutdown_token.clone()));
 
-    let listener = tokio::net::TcpListener::bind("0.0.0.0:8080")
-        .await
-        .unwrap();
+    let config = app_state.config.clone();
+    let app = router(app_state);
 
-    info!("Server listening on http://0.0.0.0:8080");
+    // Stop accepting connections on a signal and let in-flight requests (and their
+    // OpenAI calls and storage writes) finish, up to the shutdown deadline
+    tokio::spawn(shutdown::wait_for_signal(shutdown_token.clone()));
+    or_exit(server::serve(app, &config, shutdown_token).await, "Server failed");
 
-    axum::serve(listener, app).await.unwrap();
+    shutdown::join_tasks(background_tasks, shutdown::SHUTDOWN_DEADLINE).await;
+    info!("Server stopped");
 }
@@ -0,0 +1,256 @@
+use axum::{Json, extract::Query};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    ServiceError,
+    problem::ErrorResponse,
+    rng::{self, SplitMix64},
+};
+
+/// Operations drilled when the request doesn't say
+const DEFAULT_OPERATIONS: [Operation; 2] = [Operation::Add, Operation::Subtract];
+
+/// Operand range used when the request doesn't specify one
+const DEFAULT_MIN: u32 = 0;
+const DEFAULT_MAX: u32 = 10;
+
+/// Problems per drill when the request doesn't specify a count
+const DEFAULT_COUNT: usize = 20;
+
+/// Largest operand accepted
+const MAX_OPERAND: u32 = 1000;
+
+/// Most problems per drill
+const MAX_COUNT: usize = 100;
+
+/// An arithmetic operation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Operation {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+}
+
+impl Operation {
+    /// Parses an operation's name, as used in queries
+    fn parse(name: &str) -> Option<Self> {
+        match name.trim() {
+            "add" => Some(Operation::Add),
+            "subtract" => Some(Operation::Subtract),
+            "multiply" => Some(Operation::Multiply),
+            "divide" => Some(Operation::Divide),
+            _ => None,
+        }
+    }
+
+    /// Sign the operation is written with
+    fn symbol(self) -> char {
+        match self {
+            Operation::Add => '+',
+            Operation::Subtract => '−',
+            Operation::Multiply => '×',
+            Operation::Divide => '÷',
+        }
+    }
+}
+
+/// Query parameters accepted by the math drill endpoint
+#[derive(Debug, Deserialize, Default, JsonSchema)]
+pub struct DrillQuery {
+    /// Comma-separated operations to mix, from add, subtract, multiply, and
+    /// divide; "add,subtract" if omitted
+    pub operations: Option<String>,
+
+    /// Smallest operand, 0 if omitted
+    pub min: Option<u32>,
+
+    /// Largest operand, 10 if omitted
+    pub max: Option<u32>,
+
+    /// Number of problems, 20 if omitted
+    pub count: Option<usize>,
+
+    /// Seed to reproduce an earlier drill; a random one if omitted
+    pub seed: Option<u64>,
+}
+
+/// A validated description of a drill; the same spec always gives the same drill
+#[derive(Debug, Clone, PartialEq)]
+pub struct DrillSpec {
+    pub operations: Vec<Operation>,
+    pub min: u32,
+    pub max: u32,
+    pub count: usize,
+    pub seed: u64,
+}
+
+impl DrillQuery {
+    /// Validates the query, picking a seed if it has none
+    pub fn into_spec(self) -> Result<DrillSpec, ServiceError> {
+        let mut operations = Vec::new();
+        match &self.operations {
+            Some(names) => {
+                for name in names.split(',').filter(|name| !name.trim().is_empty()) {
+                    let operation = Operation::parse(name).ok_or_else(|| {
+                        ServiceError::InvalidInput(format!(
+                            "unknown operation {:?}; use add, subtract, multiply, or divide",
+                            name.trim()
+                        ))
+                    })?;
+                    if !operations.contains(&operation) {
+                        operations.push(operation);
+                    }
+                }
+            }
+            None => operations.extend(DEFAULT_OPERATIONS),
+        }
+        if operations.is_empty() {
+            return Err(ServiceError::InvalidInput("operations must name at least one operation".into()));
+        }
+
+        let min = self.min.unwrap_or(DEFAULT_MIN);
+        let max = self.max.unwrap_or(DEFAULT_MAX);
+        if min > max || max > MAX_OPERAND {
+            return Err(ServiceError::InvalidInput(format!(
+                "min and max must satisfy min <= max <= {}",
+                MAX_OPERAND
+            )));
+        }
+        if operations.contains(&Operation::Divide) && max == 0 {
+            return Err(ServiceError::InvalidInput("max must be at least 1 to divide".into()));
+        }
+
+        let count = self.count.unwrap_or(DEFAULT_COUNT);
+        if !(1..=MAX_COUNT).contains(&count) {
+            return Err(ServiceError::InvalidInput(format!("count must be between 1 and {}", MAX_COUNT)));
+        }
+
+        let seed = self.seed.unwrap_or_else(rng::random_seed);
+
+        Ok(DrillSpec { operations, min, max, count, seed })
+    }
+}
+
+/// One problem of a drill
+#[derive(Debug, Clone, PartialEq, Serialize, JsonSchema)]
+pub struct DrillProblem {
+    pub left: u32,
+    pub operation: Operation,
+    pub right: u32,
+    pub answer: u32,
+
+    /// The problem as written, e.g. "7 × 8"
+    pub text: String,
+}
+
+/// A set of arithmetic problems, reproducible from its seed
+#[derive(Debug, Clone, PartialEq, Serialize, JsonSchema)]
+pub struct MathDrill {
+    /// Seed to pass back, with the same options, for the same drill
+    pub seed: u64,
+    pub operations: Vec<Operation>,
+    pub min: u32,
+    pub max: u32,
+    pub problems: Vec<DrillProblem>,
+}
+
+/// Generates the drill a spec describes
+///
+/// Operands fall within `min..=max`. Subtraction puts the larger operand first so
+/// answers are never negative; division picks the divisor and the answer from the
+/// range and multiplies them for the dividend, so answers are whole numbers.
+pub fn generate(spec: &DrillSpec) -> MathDrill {
+    let mut rng = SplitMix64::new(spec.seed);
+    let problems = (0..spec.count)
+        .map(|_| {
+            let operation = spec.operations[rng.index(spec.operations.len())];
+            let a = rng.between(spec.min, spec.max);
+            let (left, right, answer) = match operation {
+                Operation::Add => {
+                    let b = rng.between(spec.min, spec.max);
+                    (a, b, a + b)
+                }
+                Operation::Subtract => {
+                    let b = rng.between(spec.min, spec.max);
+                    (a.max(b), a.min(b), a.max(b) - a.min(b))
+                }
+                Operation::Multiply => {
+                    let b = rng.between(spec.min, spec.max);
+                    (a, b, a * b)
+                }
+                Operation::Divide => {
+                    let divisor = rng.between(spec.min.max(1), spec.max);
+                    (divisor * a, divisor, a)
+                }
+            };
+            DrillProblem {
+                left,
+                operation,
+                right,
+                answer,
+                text: format!("{} {} {}", left, operation.symbol(), right),
+            }
+        })
+        .collect();
+
+    MathDrill {
+        seed: spec.seed,
+        operations: spec.operations.clone(),
+        min: spec.min,
+        max: spec.max,
+        problems,
+    }
+}
+
+/// Serves an arithmetic drill, generated in code without calling the model
+pub async fn math_drill(Query(query): Query<DrillQuery>) -> Result<Json<MathDrill>, ErrorResponse> {
+    Ok(Json(generate(&query.into_spec()?)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generates_reproducible_drills_with_whole_answers() {
+        let query = || DrillQuery {
+            operations: Some("add, subtract,multiply,divide".into()),
+            min: Some(2),
+            max: Some(12),
+            count: Some(100),
+            seed: Some(42),
+        };
+        let drill = generate(&query().into_spec().unwrap());
+        assert_eq!(drill, generate(&query().into_spec().unwrap()));
+        assert_ne!(drill.problems, generate(&DrillQuery { seed: Some(43), ..query() }.into_spec().unwrap()).problems);
+
+        for problem in &drill.problems {
+            let expected = match problem.operation {
+                Operation::Add => problem.left + problem.right,
+                Operation::Subtract => problem.left - problem.right,
+                Operation::Multiply => problem.left * problem.right,
+                Operation::Divide => problem.left / problem.right,
+            };
+            assert_eq!(problem.answer, expected, "{}", problem.text);
+            assert!((2..=12).contains(&problem.right), "{}", problem.text);
+            if problem.operation == Operation::Divide {
+                assert_eq!(problem.left % problem.right, 0, "{}", problem.text);
+            }
+        }
+        assert!(drill.problems.iter().any(|p| p.operation == Operation::Divide));
+
+        let defaults = DrillQuery::default().into_spec().unwrap();
+        assert_eq!((defaults.min, defaults.max, defaults.count), (0, 10, 20));
+        for invalid in [
+            DrillQuery { operations: Some("modulo".into()), ..Default::default() },
+            DrillQuery { min: Some(5), max: Some(4), ..Default::default() },
+            DrillQuery { count: Some(0), ..Default::default() },
+            DrillQuery { operations: Some("divide".into()), max: Some(0), ..Default::default() },
+        ] {
+            assert!(invalid.into_spec().is_err());
+        }
+    }
+}
@@ -0,0 +1,57 @@
+use axum::Json;
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::sync::{Mutex, OnceLock};
+use std::time::Instant;
+
+static COUNTERS: OnceLock<Mutex<BTreeMap<String, u64>>> = OnceLock::new();
+
+fn counters() -> &'static Mutex<BTreeMap<String, u64>> {
+    COUNTERS.get_or_init(|| Mutex::new(BTreeMap::new()))
+}
+
+/// Adds `delta` to the named counter, creating it if needed
+pub fn add(name: &str, delta: u64) {
+    let mut counters = counters().lock().unwrap_or_else(|e| e.into_inner());
+    *counters.entry(name.to_string()).or_insert(0) += delta;
+}
+
+/// Increments the named counter by one
+pub fn increment(name: &str) {
+    add(name, 1);
+}
+
+/// Runs a fallible operation and records its call count, error count, and latency
+///
+/// Maintains `{name}.{operation}.calls`, `{name}.{operation}.errors`, and
+/// `{name}.{operation}.latency_ms` (total milliseconds, so the mean is
+/// `latency_ms / calls`).
+pub async fn record_operation<T, E>(
+    name: &str,
+    operation: &str,
+    future: impl Future<Output = Result<T, E>>,
+) -> Result<T, E> {
+    let started = Instant::now();
+    let result = future.await;
+
+    increment(&format!("{}.{}.calls", name, operation));
+    add(
+        &format!("{}.{}.latency_ms", name, operation),
+        started.elapsed().as_millis() as u64,
+    );
+    if result.is_err() {
+        increment(&format!("{}.{}.errors", name, operation));
+    }
+
+    result
+}
+
+/// Returns the current value of every counter
+pub fn snapshot() -> BTreeMap<String, u64> {
+    counters().lock().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// Serves the current counter values as JSON
+pub async fn metrics() -> Json<BTreeMap<String, u64>> {
+    Json(snapshot())
+}
@@ -0,0 +1,102 @@
+use async_openai::{
+    Client as OpenAIClient,
+    config::OpenAIConfig,
+    types::{CreateModerationRequestArgs, ModerationInput},
+};
+use async_trait::async_trait;
+use serde_json::Value;
+
+use crate::{ServiceError, validation};
+
+/// Outcome of moderating a piece of content
+#[derive(Debug, Clone, PartialEq)]
+pub enum ModerationVerdict {
+    /// The content is safe to serve
+    Allowed,
+
+    /// The content was flagged in the listed categories
+    Flagged(Vec<String>),
+}
+
+/// Moderation hook run on generated content before it is cached
+///
+/// Since all content is served to children, anything flagged is discarded and
+/// regenerated rather than stored.
+#[async_trait]
+pub trait Moderator: Send + Sync {
+    /// Classifies the text of generated content
+    ///
+    /// # Arguments
+    /// * `content` - The generated content; every string value is moderated
+    ///
+    /// # Returns
+    /// * `Ok(ModerationVerdict)` - Whether the content is allowed
+    /// * `Err(ServiceError)` - If the moderation service can't be reached
+    async fn moderate(&self, content: &Value) -> Result<ModerationVerdict, ServiceError>;
+}
+
+/// Moderator backed by the OpenAI moderation endpoint
+#[derive(Clone)]
+pub struct OpenAIModerator {
+    client: OpenAIClient<OpenAIConfig>,
+}
+
+impl OpenAIModerator {
+    /// Creates a new OpenAIModerator using the given client
+    pub fn new(client: OpenAIClient<OpenAIConfig>) -> Self {
+        Self { client }
+    }
+}
+
+#[async_trait]
+impl Moderator for OpenAIModerator {
+    async fn moderate(&self, content: &Value) -> Result<ModerationVerdict, ServiceError> {
+        let mut strings = Vec::new();
+        validation::collect_strings(content, &mut strings);
+
+        let request = CreateModerationRequestArgs::default()
+            .input(ModerationInput::StringArray(
+                strings.into_iter().map(str::to_string).collect(),
+            ))
+            .build()
+            .map_err(|e| {
+                ServiceError::OpenAIError(format!("Failed to build moderation request: {}", e))
+            })?;
+
+        let response = self
+            .client
+            .moderations()
+            .create(request)
+            .await
+            .map_err(|e| ServiceError::from_openai("Moderation call failed", e))?;
+
+        if !response.results.iter().any(|r| r.flagged) {
+            return Ok(ModerationVerdict::Allowed);
+        }
+
+        // Collect the names of all flagged categories across every input
+        let mut categories = Vec::new();
+        for result in response.results.iter().filter(|r| r.flagged) {
+            if let Value::Object(flags) = serde_json::to_value(&result.categories)? {
+                for (category, flagged) in flags {
+                    if flagged.as_bool() == Some(true) && !categories.contains(&category) {
+                        categories.push(category);
+                    }
+                }
+            }
+        }
+
+        Ok(ModerationVerdict::Flagged(categories))
+    }
+}
+
+/// Moderator that allows everything, for local development and tests
+#[derive(Clone, Default)]
+pub struct NoopModerator;
+
+#[async_trait]
+impl Moderator for NoopModerator {
+    async fn moderate(&self, _content: &Value) -> Result<ModerationVerdict, ServiceError> {
+        Ok(ModerationVerdict::Allowed)
+    }
+}
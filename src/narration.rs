@@ -0,0 +1,270 @@
+use async_openai::{
+    Client as OpenAIClient,
+    config::OpenAIConfig,
+    types::{CreateSpeechRequestArgs, SpeechModel, SpeechResponseFormat, Voice},
+};
+use async_trait::async_trait;
+use axum::{
+    body::Body,
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode, header},
+    response::Response,
+};
+use futures::{StreamExt, TryStreamExt, future};
+use std::ops::Range;
+use tracing::error;
+
+use crate::{
+    ServiceError,
+    content,
+    keyvalue::KeyValueStore,
+    problem::ErrorResponse,
+    reading::READING_PREFIX,
+    state::AppState,
+    storage::{ObjectStore, ObjectStream},
+};
+
+/// ObjectStore prefix under which narrations are stored, mirroring the content keys
+pub const NARRATION_PREFIX: &str = "narration/";
+
+/// Longest text the speech endpoint accepts, in characters
+const MAX_NARRATION_CHARS: usize = 4096;
+
+/// Voice stories are read in
+const NARRATION_VOICE: Voice = Voice::Fable;
+
+/// Turns text into spoken audio
+#[async_trait]
+pub trait Narrator: Send + Sync {
+    /// Synthesizes narration of a piece of text
+    ///
+    /// # Returns
+    /// * `Ok(Some(mp3))` - The narration, as MP3 audio
+    /// * `Ok(None)` - If narration is disabled
+    /// * `Err(ServiceError)` - If the speech service can't be reached
+    async fn narrate(&self, text: &str) -> Result<Option<Vec<u8>>, ServiceError>;
+}
+
+/// Narrator backed by the OpenAI text-to-speech endpoint
+#[derive(Clone)]
+pub struct OpenAINarrator {
+    client: OpenAIClient<OpenAIConfig>,
+}
+
+impl OpenAINarrator {
+    /// Creates a new OpenAINarrator using the given client
+    pub fn new(client: OpenAIClient<OpenAIConfig>) -> Self {
+        Self { client }
+    }
+}
+
+#[async_trait]
+impl Narrator for OpenAINarrator {
+    async fn narrate(&self, text: &str) -> Result<Option<Vec<u8>>, ServiceError> {
+        if text.chars().count() > MAX_NARRATION_CHARS {
+            return Err(ServiceError::InvalidInput(format!(
+                "text to narrate must be at most {} characters",
+                MAX_NARRATION_CHARS
+            )));
+        }
+
+        let request = CreateSpeechRequestArgs::default()
+            .model(SpeechModel::Tts1)
+            .voice(NARRATION_VOICE)
+            .response_format(SpeechResponseFormat::Mp3)
+            .input(text)
+            .build()
+            .map_err(|e| ServiceError::OpenAIError(format!("Failed to build speech request: {}", e)))?;
+
+        let response = self
+            .client
+            .audio()
+            .speech(request)
+            .await
+            .map_err(|e| ServiceError::from_openai("Speech call failed", e))?;
+
+        Ok(Some(response.bytes.to_vec()))
+    }
+}
+
+/// Narrator that narrates nothing, for local development and tests
+#[derive(Clone, Default)]
+pub struct NoopNarrator;
+
+#[async_trait]
+impl Narrator for NoopNarrator {
+    async fn narrate(&self, _text: &str) -> Result<Option<Vec<u8>>, ServiceError> {
+        Ok(None)
+    }
+}
+
+/// Key of the object holding a content object's narration, e.g.
+/// "narration/reading/2025-10-11-14/1a2b.mp3"
+pub fn narration_key(object_key: &str) -> String {
+    let stem = object_key.strip_suffix(".json").unwrap_or(object_key);
+    format!("{}{}.mp3", NARRATION_PREFIX, stem)
+}
+
+/// Narrates a piece of text and stores the audio next to a content object
+pub async fn narrate_and_store<S: ObjectStore>(
+    narrator: &dyn Narrator,
+    object_store: &S,
+    object_key: &str,
+    text: &str,
+) -> Result<(), ServiceError> {
+    if let Some(audio) = narrator.narrate(text).await? {
+        object_store.put_object(&narration_key(object_key), audio).await?;
+    }
+    Ok(())
+}
+
+/// Parses a `Range` header against an object's length
+///
+/// Only single ranges are supported; anything else is served whole.
+///
+/// # Returns
+/// * `Ok(Some(range))` - The bytes to send
+/// * `Ok(None)` - If the whole object should be sent
+/// * `Err(())` - If the range lies outside the object
+pub(crate) fn parse_range(value: &str, len: usize) -> Result<Option<Range<usize>>, ()> {
+    let Some(spec) = value.trim().strip_prefix("bytes=") else {
+        return Ok(None);
+    };
+    let Some((start, end)) = spec.split_once('-') else {
+        return Ok(None);
+    };
+    if spec.contains(',') {
+        return Ok(None);
+    }
+
+    let (start, end) = match (start.trim(), end.trim()) {
+        // The last `suffix` bytes
+        ("", suffix) => {
+            let suffix: usize = suffix.parse().map_err(|_| ())?;
+            if suffix == 0 {
+                return Err(());
+            }
+            (len.saturating_sub(suffix), len)
+        }
+        (start, "") => (start.parse().map_err(|_| ())?, len),
+        (start, end) => {
+            let end: usize = end.parse().map_err(|_| ())?;
+            (start.parse().map_err(|_| ())?, end.saturating_add(1).min(len))
+        }
+    };
+    if start >= len || start >= end {
+        return Err(());
+    }
+    Ok(Some(start..end))
+}
+
+/// Keeps the bytes of `range` from a stream, ending it once they've passed
+fn byte_range(stream: ObjectStream, range: Range<usize>) -> ObjectStream {
+    stream
+        .scan(0, move |offset: &mut usize, chunk| {
+            let chunk = match chunk {
+                Ok(chunk) => chunk,
+                Err(e) => return future::ready(Some(Err(e))),
+            };
+            let start = *offset;
+            *offset += chunk.len();
+            if start >= range.end {
+                return future::ready(None);
+            }
+            let from = range.start.saturating_sub(start).min(chunk.len());
+            let to = (range.end - start).min(chunk.len());
+            future::ready(Some(Ok(chunk.slice(from..to))))
+        })
+        .try_filter(|chunk| future::ready(!chunk.is_empty()))
+        .boxed()
+}
+
+/// Serves a story's narration as MP3, honoring `Range` requests so players can seek
+///
+/// The story ID is the `id` field of the reading content. Narration is synthesized
+/// in the background after a story is generated, so it may briefly be missing.
+/// The audio is streamed from storage rather than loaded, as players request many
+/// ranges of it while playing.
+pub async fn reading_audio<S: ObjectStore, K: KeyValueStore>(
+    State(state): State<AppState<S, K>>,
+    Path(story_id): Path<String>,
+    headers: HeaderMap,
+) -> Result<Response, ErrorResponse> {
+    let object_key = content::object_key_for_id(&story_id)
+        .filter(|key| key.starts_with(&format!("{}/", READING_PREFIX)))
+        .ok_or_else(|| ServiceError::NotFound("No such story".to_string()))?;
+    let key = narration_key(&object_key);
+    let object = state
+        .object_store
+        .head_object(&key)
+        .await?
+        .ok_or_else(|| ServiceError::NotFound("No narration for this story".to_string()))?;
+    let len = object.size as usize;
+
+    let range = match headers.get(header::RANGE).and_then(|value| value.to_str().ok()) {
+        Some(value) => parse_range(value, len),
+        None => Ok(None),
+    };
+    let builder = Response::builder()
+        .header(header::CONTENT_TYPE, "audio/mpeg")
+        .header(header::ACCEPT_RANGES, "bytes");
+    let response = match range {
+        Ok(None) => builder
+            .header(header::CONTENT_LENGTH, len)
+            .body(Body::from_stream(state.object_store.get_object_stream(&key).await?)),
+        Ok(Some(range)) => {
+            let stream = byte_range(state.object_store.get_object_stream(&key).await?, range.clone());
+            builder
+                .status(StatusCode::PARTIAL_CONTENT)
+                .header(
+                    header::CONTENT_RANGE,
+                    format!("bytes {}-{}/{}", range.start, range.end - 1, len),
+                )
+                .header(header::CONTENT_LENGTH, range.len())
+                .body(Body::from_stream(stream))
+        }
+        Err(()) => builder
+            .status(StatusCode::RANGE_NOT_SATISFIABLE)
+            .header(header::CONTENT_RANGE, format!("bytes */{}", len))
+            .body(Body::empty()),
+    };
+
+    response.map_err(|e| {
+        error!("Failed to build response for {}: {}", key, e);
+        ErrorResponse::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "internal_error",
+            "Internal server error",
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_single_byte_ranges() {
+        assert_eq!(parse_range("bytes=0-99", 1000), Ok(Some(0..100)));
+        assert_eq!(parse_range("bytes=900-", 1000), Ok(Some(900..1000)));
+        assert_eq!(parse_range("bytes=-100", 1000), Ok(Some(900..1000)));
+        assert_eq!(parse_range("bytes=990-2000", 1000), Ok(Some(990..1000)));
+        assert_eq!(parse_range("bytes=0-1,5-9", 1000), Ok(None));
+        assert_eq!(parse_range("items=0-1", 1000), Ok(None));
+        assert_eq!(parse_range("bytes=1000-", 1000), Err(()));
+        assert_eq!(parse_range("bytes=5-2", 1000), Err(()));
+        assert_eq!(parse_range("bytes=a-b", 1000), Err(()));
+
+        let chunks = ["abcd", "efgh", "ij"].map(|chunk| Ok(bytes::Bytes::from(chunk)));
+        let sliced: Vec<bytes::Bytes> =
+            futures::executor::block_on(byte_range(futures::stream::iter(chunks).boxed(), 3..9).try_collect())
+                .unwrap();
+        assert_eq!(sliced.concat(), b"defghi");
+
+        let key = "reading/2025-10-11-14/1a2b.json";
+        assert_eq!(narration_key(key), "narration/reading/2025-10-11-14/1a2b.mp3");
+        assert_eq!(content::object_key_for_id(&content::object_id(key)).as_deref(), Some(key));
+        assert_eq!(content::object_key_for_id(&content::object_id("reading/../x.json")), None);
+        assert_eq!(content::object_key_for_id("not base64!"), None);
+    }
+}
@@ -0,0 +1,497 @@
+use axum::{
+    Json,
+    extract::State,
+    http::{HeaderMap, HeaderValue, header},
+    response::Response,
+};
+use schemars::{
+    JsonSchema,
+    generate::{SchemaGenerator, SchemaSettings},
+};
+use serde_json::{Map, Value, json};
+
+use crate::{
+    api,
+    bilingual::{BilingualContents, BilingualQuery},
+    cloze::{ClozeExercise, ClozeQuery},
+    content::ContentTypeRegistry,
+    daily::DailyChallenge,
+    explanations::{MistakeExplanation, MistakeReport},
+    feedback::{NewFeedback, ObjectRatings},
+    flags::{Flag, NewFlag},
+    flashcards::{CardReview, DueCards, DueQuery, FlashcardDeck, FlashcardQuery, ScheduledCard},
+    gamification::{Achievements, AchievementsQuery},
+    grammar::{GrammarExercise, GrammarQuery},
+    history::{HistoryPassage, HistoryQuery},
+    keyvalue::KeyValueStore,
+    logic::{LogicPuzzle, LogicQuery},
+    math_drill::{DrillQuery, MathDrill},
+    paired::{PairedPassages, PairedQuery},
+    poetry::{Poem, PoemQuery},
+    problem::{ErrorResponse, PROBLEM_JSON, ProblemDetails},
+    progress::{ActivityResult, NewActivity, ProgressQuery, ProgressReport},
+    puzzle::{Crossword, PuzzleQuery, WordSearch},
+    reading::{ExportQuery, HintQuery, ReadingContents, ReadingHint, ReadingQuery},
+    reports::{ReportQuery, WeeklyReport},
+    search::{SearchQuery, SearchResult},
+    state::AppState,
+    static_files,
+    storage::ObjectStore,
+};
+
+/// Content-Security-Policy of the API docs page, which loads Swagger UI from a CDN
+const DOCS_CONTENT_SECURITY_POLICY: &str = "default-src 'self'; \
+    script-src 'self' 'unsafe-inline' https://unpkg.com; \
+    style-src 'self' 'unsafe-inline' https://unpkg.com; img-src 'self' data: https://unpkg.com; \
+    object-src 'none'; base-uri 'self'; frame-ancestors 'none'";
+
+/// Where JSON Schema definitions live in an OpenAPI document
+const SCHEMAS_PATH: &str = "#/components/schemas/";
+
+/// An OpenAPI document being built
+///
+/// Request bodies and parameters are described as they deserialize, responses as
+/// they serialize, so fields the service fills in appear only in responses.
+struct Document {
+    requests: SchemaGenerator,
+    responses: SchemaGenerator,
+
+    /// Definitions taken from content type schemas
+    schemas: Map<String, Value>,
+    paths: Map<String, Value>,
+}
+
+/// Schema generator for OpenAPI 3.1, whose schemas are JSON Schema 2020-12
+fn generator(settings: SchemaSettings) -> SchemaGenerator {
+    settings
+        .with(|s| {
+            s.definitions_path = SCHEMAS_PATH.trim_start_matches('#').into();
+            s.meta_schema = None;
+        })
+        .into_generator()
+}
+
+/// Points `$ref`s at `#/$defs/` to the document's schemas instead
+fn rewrite_refs(schema: &mut Value) {
+    match schema {
+        Value::Object(map) => {
+            if let Some(Value::String(reference)) = map.get_mut("$ref")
+                && let Some(name) = reference.strip_prefix("#/$defs/")
+            {
+                *reference = format!("{}{}", SCHEMAS_PATH, name);
+            }
+            map.values_mut().for_each(rewrite_refs);
+        }
+        Value::Array(items) => items.iter_mut().for_each(rewrite_refs),
+        _ => {}
+    }
+}
+
+impl Document {
+    fn new() -> Self {
+        Self {
+            requests: generator(SchemaSettings::draft2020_12().for_deserialize()),
+            responses: generator(SchemaSettings::draft2020_12().for_serialize()),
+            schemas: Map::new(),
+            paths: Map::new(),
+        }
+    }
+
+    /// Describes the fields of a query string type as parameters
+    fn query<Q: JsonSchema>(&mut self) -> Vec<Value> {
+        let root = self.requests.root_schema_for::<Q>();
+        let required = root.get("required").and_then(Value::as_array).cloned().unwrap_or_default();
+        let Some(properties) = root.get("properties").and_then(Value::as_object) else {
+            return Vec::new();
+        };
+
+        properties
+            .iter()
+            .map(|(name, schema)| {
+                let mut schema = schema.clone();
+                let description = schema.as_object_mut().and_then(|s| s.remove("description"));
+                let mut parameter = json!({
+                    "name": name,
+                    "in": "query",
+                    "required": required.contains(&Value::String(name.clone())),
+                    "schema": schema,
+                });
+                if let Some(description) = description {
+                    parameter["description"] = description;
+                }
+                parameter
+            })
+            .collect()
+    }
+
+    /// A JSON request body
+    fn body<T: JsonSchema>(&mut self) -> Value {
+        json!({
+            "required": true,
+            "content": { "application/json": { "schema": self.requests.subschema_for::<T>() } },
+        })
+    }
+
+    /// A JSON response
+    fn json<T: JsonSchema>(&mut self, description: &str) -> Value {
+        json!({
+            "description": description,
+            "content": { "application/json": { "schema": self.responses.subschema_for::<T>() } },
+        })
+    }
+
+    /// A JSON response following a content type's schema, whose definitions are
+    /// moved into the document
+    fn content(&mut self, schema: &Value, description: &str) -> Value {
+        let mut schema = schema.clone();
+        if let Some(object) = schema.as_object_mut() {
+            object.remove("$schema");
+            if let Some(Value::Object(definitions)) = object.remove("$defs") {
+                for (name, definition) in definitions {
+                    self.schemas.entry(name).or_insert(definition);
+                }
+            }
+        }
+        json!({
+            "description": description,
+            "content": { "application/json": { "schema": schema } },
+        })
+    }
+
+    /// Adds an operation; every operation may fail with a problem response, and
+    /// every POST is a submission that takes an idempotency key
+    fn operation(&mut self, path: &str, method: &str, mut operation: Value) {
+        operation["responses"]["default"] = json!({ "$ref": "#/components/responses/Problem" });
+        if method == "post" {
+            let idempotency_key = json!({ "$ref": "#/components/parameters/IdempotencyKey" });
+            match operation["parameters"].as_array_mut() {
+                Some(parameters) => parameters.push(idempotency_key),
+                None => operation["parameters"] = json!([idempotency_key]),
+            }
+        }
+        let item = self.paths.entry(path.to_string()).or_insert_with(|| json!({}));
+        item[method] = operation;
+    }
+
+    fn finish(mut self) -> Value {
+        let problem = self.responses.subschema_for::<ProblemDetails>();
+        let mut schemas = self.responses.take_definitions(true);
+        let definitions = self.requests.take_definitions(true).into_iter().chain(self.schemas);
+        for (name, definition) in definitions {
+            schemas.entry(name).or_insert(definition);
+        }
+
+        let mut document = json!({
+            "openapi": "3.1.0",
+            "info": {
+                "title": "Thinkaroo API",
+                "version": env!("CARGO_PKG_VERSION"),
+            },
+            "servers": [{ "url": api::CURRENT_VERSION }],
+            "paths": self.paths,
+            "components": {
+                "schemas": schemas,
+                "parameters": {
+                    "IdempotencyKey": {
+                        "name": "Idempotency-Key",
+                        "in": "header",
+                        "description": "Makes retrying safe: a repeat with the same key and body \
+                            gets the first response back, for a day",
+                        "schema": { "type": "string", "maxLength": 255 },
+                    },
+                },
+                "responses": {
+                    "Problem": {
+                        "description": "The request failed",
+                        "content": { PROBLEM_JSON: { "schema": problem } },
+                    },
+                },
+            },
+        });
+        rewrite_refs(&mut document);
+        document
+    }
+}
+
+/// Builds the OpenAPI document of the current version of the JSON API
+///
+/// Covers the content, feedback, search, and progress endpoints clients generate
+/// code for; account and class management are left to the web pages that use them.
+pub fn spec(content_types: &ContentTypeRegistry) -> Value {
+    let mut doc = Document::new();
+
+    let parameters = doc.query::<ReadingQuery>();
+    let response = doc.json::<ReadingContents>("A reading passage with questions");
+    doc.operation("/reading_contents", "get", json!({
+        "summary": "Get a reading comprehension passage",
+        "parameters": parameters,
+        "responses": { "200": response },
+    }));
+    let parameters = doc.query::<ReadingQuery>();
+    doc.operation("/reading_contents/stream", "get", json!({
+        "summary": "Stream a reading passage as server-sent events",
+        "parameters": parameters,
+        "responses": {
+            "200": { "description": "The passage as it is written", "content": { "text/event-stream": {} } },
+        },
+    }));
+    doc.operation("/reading_contents/{id}/worksheet.pdf", "get", json!({
+        "summary": "Download a printable worksheet for a passage",
+        "parameters": [{ "name": "id", "in": "path", "required": true, "schema": { "type": "string" } }],
+        "responses": {
+            "200": { "description": "The worksheet, with an answer key", "content": { "application/pdf": {} } },
+        },
+    }));
+    let response = doc.json::<ReadingContents>("A story starring the child, generated for this request");
+    doc.operation("/reading_contents/personalized", "get", json!({
+        "summary": "Get a story personalized for the selected child profile",
+        "responses": { "200": response },
+    }));
+    let response = doc.json::<ReadingContents>("The next chapter, with the series it belongs to");
+    doc.operation("/reading_contents/{id}/continue", "post", json!({
+        "summary": "Continue a passage with a sequel chapter",
+        "parameters": [{ "name": "id", "in": "path", "required": true, "schema": { "type": "string" } }],
+        "responses": { "200": response },
+    }));
+    let mut parameters = vec![json!({ "name": "id", "in": "path", "required": true, "schema": { "type": "string" } })];
+    parameters.extend(doc.query::<HintQuery>());
+    let response = doc.json::<ReadingHint>("A hint toward the answer, in steps that each help a little more");
+    doc.operation("/reading_contents/{id}/hint", "get", json!({
+        "summary": "Get a hint for a question about a passage without its answer",
+        "parameters": parameters,
+        "responses": { "200": response },
+    }));
+    let parameters = doc.query::<ExportQuery>();
+    doc.operation("/reading_contents/export.epub", "get", json!({
+        "summary": "Download a day's passages as an e-book",
+        "parameters": parameters,
+        "responses": {
+            "200": { "description": "The passages, one per chapter", "content": { "application/epub+zip": {} } },
+        },
+    }));
+
+    let parameters = doc.query::<BilingualQuery>();
+    let response = doc.json::<BilingualContents>("A passage with its translation");
+    doc.operation("/bilingual_contents", "get", json!({
+        "summary": "Get a bilingual reading passage",
+        "parameters": parameters,
+        "responses": { "200": response },
+    }));
+
+    let parameters = doc.query::<ClozeQuery>();
+    let response = doc.json::<ClozeExercise>("A story with words blanked out, and the words");
+    doc.operation("/cloze_exercises", "get", json!({
+        "summary": "Get a fill-in-the-blank vocabulary exercise",
+        "parameters": parameters,
+        "responses": { "200": response },
+    }));
+
+    let parameters = doc.query::<GrammarQuery>();
+    let response = doc.json::<GrammarExercise>("Sentences with mistakes, their corrections, and why");
+    doc.operation("/grammar_exercises", "get", json!({
+        "summary": "Get a find-and-fix grammar exercise",
+        "parameters": parameters,
+        "responses": { "200": response },
+    }));
+
+    let parameters = doc.query::<HistoryQuery>();
+    let response = doc.json::<HistoryPassage>("A history passage, a timeline of its events, and questions");
+    doc.operation("/history_passages", "get", json!({
+        "summary": "Get a nonfiction history passage",
+        "parameters": parameters,
+        "responses": { "200": response },
+    }));
+
+    let parameters = doc.query::<LogicQuery>();
+    let response = doc.json::<LogicPuzzle>("A logic puzzle with exactly one solution, and the solution");
+    doc.operation("/logic_puzzles", "get", json!({
+        "summary": "Get a grid logic puzzle, checked to have exactly one solution",
+        "parameters": parameters,
+        "responses": { "200": response },
+    }));
+
+    let parameters = doc.query::<PairedQuery>();
+    let response = doc.json::<PairedPassages>("Two passages on one topic and questions comparing them");
+    doc.operation("/paired_passages", "get", json!({
+        "summary": "Get paired passages to read and compare",
+        "parameters": parameters,
+        "responses": { "200": response },
+    }));
+
+    let parameters = doc.query::<PoemQuery>();
+    let response = doc.json::<Poem>("A poem, the literary devices it uses, and questions about them");
+    doc.operation("/poems", "get", json!({
+        "summary": "Get a poem to study for figurative language",
+        "parameters": parameters,
+        "responses": { "200": response },
+    }));
+
+    let parameters = doc.query::<PuzzleQuery>();
+    let response = doc.json::<WordSearch>("A grid of letters with the words hidden in it, and where");
+    doc.operation("/word_search", "get", json!({
+        "summary": "Get a word search of supplied or generated vocabulary",
+        "parameters": parameters,
+        "responses": { "200": response },
+    }));
+    let parameters = doc.query::<PuzzleQuery>();
+    let response = doc.json::<Crossword>("A crossword grid, its clues, and its solution");
+    doc.operation("/crossword", "get", json!({
+        "summary": "Get a crossword of supplied or generated vocabulary",
+        "parameters": parameters,
+        "responses": { "200": response },
+    }));
+
+    let response = doc.json::<DailyChallenge>("Today's challenge");
+    doc.operation("/daily_challenge", "get", json!({
+        "summary": "Get today's challenge, the same for everyone",
+        "responses": { "200": response },
+    }));
+
+    let parameters = doc.query::<DrillQuery>();
+    let response = doc.json::<MathDrill>("Arithmetic problems with their answers, and the seed to repeat them");
+    doc.operation("/math_drill", "get", json!({
+        "summary": "Get an arithmetic drill, generated without the model",
+        "parameters": parameters,
+        "responses": { "200": response },
+    }));
+
+    for descriptor in content_types.iter() {
+        let response = doc.content(&descriptor.schema.schema, &descriptor.schema.description);
+        doc.operation(&format!("/contents/{}", descriptor.prefix), "get", json!({
+            "summary": format!("Get {} content", descriptor.prefix),
+            "responses": { "200": response },
+        }));
+    }
+
+    let body = doc.body::<NewFeedback>();
+    let response = doc.json::<ObjectRatings>("The content's ratings so far");
+    doc.operation("/feedback", "post", json!({
+        "summary": "Rate a piece of content",
+        "requestBody": body,
+        "responses": { "201": response },
+    }));
+    let body = doc.body::<NewFlag>();
+    let response = doc.json::<Flag>("The flag, after the content is quarantined");
+    doc.operation("/flag", "post", json!({
+        "summary": "Flag a piece of content as inappropriate",
+        "requestBody": body,
+        "responses": { "201": response },
+    }));
+    let parameters = doc.query::<FlashcardQuery>();
+    let response = doc.json::<FlashcardDeck>("A deck of flashcards, with the ID to review its cards by");
+    doc.operation("/flashcards", "get", json!({
+        "summary": "Get a deck of flashcards",
+        "parameters": parameters,
+        "responses": { "200": response },
+    }));
+    let parameters = doc.query::<DueQuery>();
+    let response = doc.json::<DueCards>("The child's cards due for review, longest overdue first");
+    doc.operation("/flashcards/due", "get", json!({
+        "summary": "List the flashcards a child should review today",
+        "parameters": parameters,
+        "responses": { "200": response },
+    }));
+    let body = doc.body::<CardReview>();
+    let response = doc.json::<ScheduledCard>("The card, with when it is next due");
+    doc.operation("/flashcards/review", "post", json!({
+        "summary": "Record how well a child remembered a flashcard",
+        "requestBody": body,
+        "responses": { "200": response },
+    }));
+    let parameters = doc.query::<SearchQuery>();
+    let response = doc.json::<Vec<SearchResult>>("Matching content, most similar first");
+    doc.operation("/search", "get", json!({
+        "summary": "Search past content",
+        "parameters": parameters,
+        "responses": { "200": response },
+    }));
+
+    let body = doc.body::<NewActivity>();
+    let response = doc.json::<ActivityResult>("The recorded activity");
+    doc.operation("/progress", "post", json!({
+        "summary": "Record a completed activity",
+        "requestBody": body,
+        "responses": { "201": response },
+    }));
+    let parameters = doc.query::<ProgressQuery>();
+    let response = doc.json::<ProgressReport>("The child's activities and skill totals");
+    doc.operation("/progress", "get", json!({
+        "summary": "Get a child's progress",
+        "parameters": parameters,
+        "responses": { "200": response },
+    }));
+    let body = doc.body::<MistakeReport>();
+    let response = doc.json::<MistakeExplanation>("Why the answer was wrong, and a tip for next time");
+    doc.operation("/explanations", "post", json!({
+        "summary": "Explain a wrong answer after grading; signed-in accounts only",
+        "requestBody": body,
+        "responses": { "200": response },
+    }));
+    let parameters = doc.query::<AchievementsQuery>();
+    let response = doc.json::<Achievements>("The child's points, streaks, and badges");
+    doc.operation("/me/achievements", "get", json!({
+        "summary": "Get a child's achievements",
+        "parameters": parameters,
+        "responses": { "200": response },
+    }));
+    let parameters = doc.query::<ReportQuery>();
+    let response = doc.json::<WeeklyReport>("The latest weekly report");
+    doc.operation("/reports/latest", "get", json!({
+        "summary": "Get a child's latest weekly report",
+        "parameters": parameters,
+        "responses": { "200": response },
+    }));
+
+    doc.finish()
+}
+
+/// Serves the OpenAPI document, for generating typed clients
+pub async fn openapi_json<S: ObjectStore, K: KeyValueStore>(
+    State(state): State<AppState<S, K>>,
+) -> Json<Value> {
+    Json(spec(&state.content_types))
+}
+
+/// Serves Swagger UI for browsing the OpenAPI document
+pub async fn docs(headers: HeaderMap) -> Result<Response, ErrorResponse> {
+    let mut response = static_files::serve("docs.html", &headers)?;
+    response.headers_mut().insert(
+        header::CONTENT_SECURITY_POLICY,
+        HeaderValue::from_static(DOCS_CONTENT_SECURITY_POLICY),
+    );
+    Ok(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::reading;
+
+    #[test]
+    fn documents_paths_and_response_schemas() {
+        let spec = spec(&ContentTypeRegistry::new().register(reading::descriptor()));
+
+        let reading = &spec["paths"]["/reading_contents"]["get"];
+        assert_eq!(
+            reading["responses"]["200"]["content"]["application/json"]["schema"]["$ref"],
+            "#/components/schemas/ReadingContents"
+        );
+        let grade = reading["parameters"].as_array().unwrap().iter().find(|p| p["name"] == "grade").unwrap();
+        assert_eq!(grade["description"], "Grade level the passage should target");
+        assert!(spec["paths"]["/contents/reading"]["get"].is_object());
+        assert!(spec["paths"]["/progress"]["post"]["requestBody"].is_object());
+        assert_eq!(spec["paths"]["/progress"]["post"]["parameters"][0]["$ref"], "#/components/parameters/IdempotencyKey");
+        let continue_parameters = &spec["paths"]["/reading_contents/{id}/continue"]["post"]["parameters"];
+        assert_eq!(continue_parameters[0]["name"], "id");
+        assert_eq!(continue_parameters[1]["$ref"], "#/components/parameters/IdempotencyKey");
+
+        // Responses include the fields the service sets, and not those it hides
+        let properties = &spec["components"]["schemas"]["ReadingContents"]["properties"];
+        assert!(properties["id"].is_object() && properties["illustration_key"].is_object());
+        assert!(properties.get("answers").is_none());
+
+        let text = spec.to_string();
+        assert!(!text.contains("#/$defs/"));
+        assert!(spec["components"]["schemas"]["ProblemDetails"].is_object());
+    }
+}
@@ -0,0 +1,77 @@
+use axum::response::Html;
+use handlebars::Handlebars;
+use include_dir::{Dir, include_dir};
+use serde::Serialize;
+use std::sync::OnceLock;
+use tracing::error;
+
+use crate::ServiceError;
+
+/// Templates of server-rendered pages
+static TEMPLATES_DIR: Dir = include_dir!("$CARGO_MANIFEST_DIR/templates");
+
+static PAGES: OnceLock<Handlebars<'static>> = OnceLock::new();
+
+/// Initialize and return the page template engine
+///
+/// Templates are loaded from `templates/*.hbs` and named after their file, without
+/// the extension. Unlike prompts, values are HTML-escaped, and referencing a
+/// missing value is an error rather than an empty string.
+fn pages() -> &'static Handlebars<'static> {
+    PAGES.get_or_init(|| {
+        let mut handlebars = Handlebars::new();
+        handlebars.set_strict_mode(true);
+
+        for file in TEMPLATES_DIR.files() {
+            if file.path().extension().is_none_or(|ext| ext != "hbs") {
+                continue;
+            }
+
+            let name = file.path().file_stem().and_then(|s| s.to_str());
+            if let (Some(name), Some(contents)) = (name, file.contents_utf8())
+                && let Err(e) = handlebars.register_template_string(name, contents)
+            {
+                error!("Failed to register page template {:?}: {}", file.path(), e);
+            }
+        }
+
+        handlebars
+    })
+}
+
+/// Renders a page template with the given data
+///
+/// # Returns
+/// * `Ok(Html)` - The rendered page
+/// * `Err(ServiceError)` - If the template is missing or uses a value `data` lacks
+pub fn render(name: &str, data: &impl Serialize) -> Result<Html<String>, ServiceError> {
+    pages()
+        .render(name, data)
+        .map(Html)
+        .map_err(|e| ServiceError::ConfigError(format!("Failed to render page {}: {}", name, e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn escapes_values_and_rejects_missing_ones() {
+        let page = json!({
+            "title": "Tom & <Jerry>",
+            "paragraphs": ["<script>alert(1)</script>"],
+            "questions": [{ "number": 1, "text": "Who?" }],
+            "illustration_url": null,
+            "worksheet_url": null,
+            "query": "",
+        });
+        let Html(html) = render("reading", &page).unwrap();
+        assert!(html.contains("Tom &amp; &lt;Jerry&gt;"));
+        assert!(html.contains("&lt;script&gt;") && !html.contains("<script>"));
+        assert!(html.contains("answer-1"));
+
+        assert!(render("reading", &json!({ "title": "t" })).is_err());
+        assert!(render("missing", &page).is_err());
+    }
+}
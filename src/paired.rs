@@ -0,0 +1,177 @@
+use axum::{
+    Json,
+    extract::{Query, State},
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    ServiceError,
+    content::{ContentParams, ContentSchema, ContentTypeDescriptor},
+    keyvalue::KeyValueStore,
+    problem::ErrorResponse,
+    reading::ReadingQuery,
+    session::Session,
+    state::AppState,
+    storage::ObjectStore,
+    validation::{BannedWords, ContentValidator, MinItems, NoEmptyFields, WordCount},
+};
+
+/// Storage prefix and registry identifier for paired passages
+pub const PAIRED_PREFIX: &str = "paired";
+
+/// Grade used when the request doesn't specify one
+const DEFAULT_GRADE: u8 = 4;
+
+/// Fewest questions comparing the two passages
+const MIN_CROSS_TEXT: usize = 2;
+
+/// Query parameters accepted by the paired passages endpoint
+#[derive(Debug, Deserialize, Default, JsonSchema)]
+pub struct PairedQuery {
+    /// Grade level the passages should target
+    pub grade: Option<u8>,
+
+    /// Subject both passages should be about (e.g., "honeybees")
+    pub topic: Option<String>,
+}
+
+impl PairedQuery {
+    /// Validates the query and converts it into content parameters, accepting the
+    /// same grades and topics as reading passages
+    pub fn into_params(self) -> Result<ContentParams, ServiceError> {
+        let grade = Some(self.grade.unwrap_or(DEFAULT_GRADE));
+        ReadingQuery { grade, topic: self.topic, ..Default::default() }.into_params()
+    }
+}
+
+/// Which of the passages a question is about
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PassageRef {
+    First,
+    Second,
+    /// Compares or contrasts the two passages
+    Both,
+}
+
+/// A comprehension question about one or both passages
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, JsonSchema)]
+pub struct PairedQuestion {
+    pub text: String,
+    pub about: PassageRef,
+}
+
+/// Two related passages on one topic, such as a story and an article, with
+/// questions comparing them
+#[derive(Serialize, Deserialize, Clone, JsonSchema)]
+pub struct PairedPassages {
+    /// Topic the passages share
+    pub title: String,
+
+    pub first_title: String,
+
+    /// Kind of text, e.g. "story" or "article"
+    pub first_genre: String,
+
+    pub first_passage: String,
+
+    pub second_title: String,
+    pub second_genre: String,
+    pub second_passage: String,
+
+    pub questions: Vec<PairedQuestion>,
+}
+
+/// Requires questions about each passage and at least `MIN_CROSS_TEXT` comparing
+/// the two
+#[derive(Debug, Clone, Default)]
+pub struct CrossTextQuestions;
+
+impl ContentValidator for CrossTextQuestions {
+    fn validate(&self, content: &Value, _params: &ContentParams) -> Result<(), String> {
+        let questions: Vec<PairedQuestion> = content
+            .get("questions")
+            .cloned()
+            .and_then(|questions| serde_json::from_value(questions).ok())
+            .ok_or_else(|| "missing array field \"questions\"".to_string())?;
+
+        let count = |about: PassageRef| questions.iter().filter(|q| q.about == about).count();
+        if count(PassageRef::First) == 0 || count(PassageRef::Second) == 0 {
+            return Err("every passage needs a question of its own".to_string());
+        }
+        if count(PassageRef::Both) < MIN_CROSS_TEXT {
+            return Err(format!(
+                "{} questions compare the passages, expected at least {}",
+                count(PassageRef::Both),
+                MIN_CROSS_TEXT
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Returns the content type descriptor for paired passages
+pub fn descriptor() -> ContentTypeDescriptor {
+    ContentTypeDescriptor::new(
+        PAIRED_PREFIX,
+        "paired_passages",
+        ContentSchema::for_type::<PairedPassages>(
+            "PairedPassages",
+            "Two related passages on one topic with questions comparing them",
+        ),
+    )
+    .with_validator(WordCount::new("first_passage", 100, 300))
+    .with_validator(WordCount::new("second_passage", 100, 300))
+    .with_validator(MinItems::new("questions", 4))
+    .with_validator(CrossTextQuestions)
+    .with_validator(NoEmptyFields)
+    .with_validator(BannedWords::default())
+    .with_dedup_field("first_passage")
+    .with_default_params(ContentParams::new().with("grade", DEFAULT_GRADE))
+}
+
+/// Serves cached paired passages the session hasn't read, or generates new ones
+pub async fn paired_passages<S: ObjectStore, K: KeyValueStore>(
+    State(state): State<AppState<S, K>>,
+    Query(query): Query<PairedQuery>,
+    session: Session,
+) -> Result<Json<PairedPassages>, ErrorResponse> {
+    let params = query.into_params()?;
+
+    let descriptor = state
+        .content_types
+        .get(PAIRED_PREFIX)
+        .ok_or_else(|| ServiceError::ConfigError(PAIRED_PREFIX.into()))?;
+
+    let session_id = session.id();
+    let params = state
+        .with_prompt_variant(descriptor, params, Some(session_id))
+        .await?;
+
+    let passages: PairedPassages = state
+        .get_or_generate(descriptor, &params, Some(session_id))
+        .await?;
+
+    Ok(Json(passages))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn requires_questions_comparing_the_passages() {
+        let questions = |about: &[&str]| {
+            let questions: Vec<Value> = about.iter().map(|about| json!({ "text": "Why?", "about": about })).collect();
+            json!({ "questions": questions })
+        };
+        let params = ContentParams::new();
+        assert!(CrossTextQuestions.validate(&questions(&["first", "second", "both", "both"]), &params).is_ok());
+        assert!(CrossTextQuestions.validate(&questions(&["first", "second", "both", "first"]), &params).is_err());
+        assert!(CrossTextQuestions.validate(&questions(&["first", "both", "both", "both"]), &params).is_err());
+        assert!(CrossTextQuestions.validate(&questions(&["neither"]), &params).is_err());
+    }
+}
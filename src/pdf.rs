@@ -0,0 +1,278 @@
+use std::fmt::Write;
+
+/// US Letter page size, in points
+const PAGE_WIDTH: f32 = 612.0;
+const PAGE_HEIGHT: f32 = 792.0;
+
+/// Space around the text on every side, in points
+const MARGIN: f32 = 72.0;
+
+/// Line height as a multiple of the font size
+const LINE_SPACING: f32 = 1.4;
+
+/// Widths of the printable ASCII characters (32 to 126) in Helvetica, in
+/// thousandths of the font size, from its Adobe font metrics
+const HELVETICA_WIDTHS: [u16; 95] = [
+    278, 278, 355, 556, 556, 889, 667, 191, 333, 333, 389, 584, 278, 333, 278, 278, // space to /
+    556, 556, 556, 556, 556, 556, 556, 556, 556, 556, // 0 to 9
+    278, 278, 584, 584, 584, 556, 1015, // : to @
+    667, 667, 722, 722, 667, 611, 778, 722, 278, 500, 667, 556, 833, // A to M
+    722, 778, 667, 778, 722, 667, 611, 722, 667, 944, 667, 667, 611, // N to Z
+    278, 278, 278, 469, 556, 333, // [ to `
+    556, 556, 500, 556, 556, 278, 556, 556, 222, 222, 500, 222, 833, // a to m
+    556, 556, 556, 556, 333, 500, 278, 556, 500, 722, 500, 500, 500, // n to z
+    334, 260, 334, 584, // { to ~
+];
+
+/// Width assumed for characters outside printable ASCII
+const DEFAULT_WIDTH: u16 = 556;
+
+/// How much wider Helvetica-Bold runs than Helvetica, rounded up so bold text
+/// never overflows the margin
+const BOLD_SCALE: f32 = 1.1;
+
+/// One of the standard fonts every PDF reader has, so none are embedded
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Font {
+    Regular,
+    Bold,
+}
+
+impl Font {
+    fn resource(self) -> &'static str {
+        match self {
+            Font::Regular => "F1",
+            Font::Bold => "F2",
+        }
+    }
+
+    /// Width of a string set in this font, in points
+    fn width(self, text: &str, size: f32) -> f32 {
+        let units: u32 = text
+            .chars()
+            .map(|c| match c {
+                ' '..='~' => HELVETICA_WIDTHS[c as usize - 32],
+                _ => DEFAULT_WIDTH,
+            } as u32)
+            .sum();
+        let scale = if self == Font::Bold { BOLD_SCALE } else { 1.0 };
+        units as f32 * size / 1000.0 * scale
+    }
+}
+
+/// Encodes a character in WinAnsiEncoding, the encoding the standard fonts use
+fn win_ansi(c: char) -> u8 {
+    match c {
+        ' '..='~' | '\u{A0}'..='\u{FF}' => c as u8,
+        '\u{2026}' => 0x85,
+        '\u{2022}' => 0x95,
+        '\u{2018}' => 0x91,
+        '\u{2019}' => 0x92,
+        '\u{201C}' => 0x93,
+        '\u{201D}' => 0x94,
+        '\u{2013}' => 0x96,
+        '\u{2014}' => 0x97,
+        _ => b'?',
+    }
+}
+
+/// Writes text as a PDF string literal, escaping what needs it so the content
+/// stream stays ASCII
+fn string_literal(text: &str) -> String {
+    let mut literal = String::from("(");
+    for byte in text.chars().map(win_ansi) {
+        match byte {
+            b'(' | b')' | b'\\' => {
+                literal.push('\\');
+                literal.push(byte as char);
+            }
+            0x20..=0x7E => literal.push(byte as char),
+            _ => write!(literal, "\\{:03o}", byte).expect("writing to a string can't fail"),
+        }
+    }
+    literal.push(')');
+    literal
+}
+
+/// Breaks text into lines no wider than `max_width`, at spaces where possible
+fn wrap(text: &str, font: Font, size: f32, max_width: f32) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        let candidate = if line.is_empty() { word.to_string() } else { format!("{} {}", line, word) };
+        if font.width(&candidate, size) <= max_width {
+            line = candidate;
+            continue;
+        }
+        if !line.is_empty() {
+            lines.push(std::mem::take(&mut line));
+        }
+        // Split words too long for a line of their own
+        for c in word.chars() {
+            line.push(c);
+            if font.width(&line, size) > max_width && line.chars().count() > 1 {
+                let last = line.pop().expect("line has a character");
+                lines.push(std::mem::take(&mut line));
+                line.push(last);
+            }
+        }
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+/// A simple text document rendered to PDF: paragraphs flow down the page and
+/// onto new pages as needed
+///
+/// # Example
+/// ```
+/// use thinkaroo::pdf::{Font, PdfDocument};
+///
+/// let mut doc = PdfDocument::new();
+/// doc.paragraph("The Lost Kite", Font::Bold, 20.0);
+/// doc.paragraph("Once upon a time...", Font::Regular, 12.0);
+/// let bytes = doc.render();
+/// assert!(bytes.starts_with(b"%PDF-"));
+/// ```
+#[derive(Debug)]
+pub struct PdfDocument {
+    /// Content stream of each page
+    pages: Vec<String>,
+
+    /// Baseline of the next line on the current page, from the bottom
+    y: f32,
+}
+
+impl Default for PdfDocument {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PdfDocument {
+    /// Creates a document with one empty page
+    pub fn new() -> Self {
+        Self { pages: vec![String::new()], y: PAGE_HEIGHT - MARGIN }
+    }
+
+    /// Starts a new page
+    pub fn new_page(&mut self) {
+        self.pages.push(String::new());
+        self.y = PAGE_HEIGHT - MARGIN;
+    }
+
+    /// Adds vertical space, in points
+    pub fn space(&mut self, points: f32) {
+        self.y -= points;
+    }
+
+    /// Adds a paragraph, wrapped to the page width, followed by half a line of space
+    pub fn paragraph(&mut self, text: &str, font: Font, size: f32) {
+        self.indented_paragraph(text, font, size, 0.0);
+    }
+
+    /// Adds a paragraph whose lines start `indent` points in from the margin
+    pub fn indented_paragraph(&mut self, text: &str, font: Font, size: f32, indent: f32) {
+        let line_height = size * LINE_SPACING;
+        for line in wrap(text, font, size, PAGE_WIDTH - 2.0 * MARGIN - indent) {
+            if self.y - line_height < MARGIN {
+                self.new_page();
+            }
+            self.y -= line_height;
+            let page = self.pages.last_mut().expect("document has a page");
+            writeln!(
+                page,
+                "BT /{} {} Tf {} {} Td {} Tj ET",
+                font.resource(),
+                size,
+                MARGIN + indent,
+                self.y,
+                string_literal(&line)
+            )
+            .expect("writing to a string can't fail");
+        }
+        self.y -= line_height / 2.0;
+    }
+
+    /// Number of pages so far
+    pub fn page_count(&self) -> usize {
+        self.pages.len()
+    }
+
+    /// Renders the document as PDF 1.4
+    pub fn render(&self) -> Vec<u8> {
+        // Objects: 1 catalog, 2 page tree, 3 and 4 fonts, then each page and its
+        // content stream
+        let page_ids: Vec<usize> = (0..self.pages.len()).map(|i| 5 + 2 * i).collect();
+        let kids: Vec<String> = page_ids.iter().map(|id| format!("{} 0 R", id)).collect();
+        let mut objects = vec![
+            "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+            format!("<< /Type /Pages /Kids [{}] /Count {} >>", kids.join(" "), self.pages.len()),
+            "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>".to_string(),
+            "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica-Bold /Encoding /WinAnsiEncoding >>"
+                .to_string(),
+        ];
+        for (content, id) in self.pages.iter().zip(&page_ids) {
+            objects.push(format!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] \
+                 /Resources << /Font << /F1 3 0 R /F2 4 0 R >> >> /Contents {} 0 R >>",
+                PAGE_WIDTH,
+                PAGE_HEIGHT,
+                id + 1
+            ));
+            objects.push(format!("<< /Length {} >>\nstream\n{}endstream", content.len(), content));
+        }
+
+        let mut pdf = String::from("%PDF-1.4\n");
+        let mut offsets = Vec::new();
+        for (i, object) in objects.iter().enumerate() {
+            offsets.push(pdf.len());
+            writeln!(pdf, "{} 0 obj\n{}\nendobj", i + 1, object).expect("writing to a string can't fail");
+        }
+
+        let xref = pdf.len();
+        writeln!(pdf, "xref\n0 {}\n0000000000 65535 f ", objects.len() + 1).expect("writing to a string can't fail");
+        for offset in offsets {
+            writeln!(pdf, "{:010} 00000 n ", offset).expect("writing to a string can't fail");
+        }
+        writeln!(
+            pdf,
+            "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF",
+            objects.len() + 1,
+            xref
+        )
+        .expect("writing to a string can't fail");
+        pdf.into_bytes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_wrapped_text_across_pages() {
+        assert_eq!(string_literal("Tom (age 8) said \\hi\u{2019}"), "(Tom \\(age 8\\) said \\\\hi\\222)");
+        let lines = wrap("the quick brown fox jumps over the lazy dog", Font::Regular, 12.0, 100.0);
+        assert!(lines.len() > 1 && lines.iter().all(|line| Font::Regular.width(line, 12.0) <= 100.0));
+        assert_eq!(wrap(&"m".repeat(50), Font::Regular, 12.0, 100.0).len(), 5);
+
+        let mut doc = PdfDocument::new();
+        for _ in 0..60 {
+            doc.paragraph("A line of the story.", Font::Regular, 12.0);
+        }
+        assert_eq!(doc.page_count(), 3);
+
+        let pdf = String::from_utf8(doc.render()).unwrap();
+        assert!(pdf.starts_with("%PDF-1.4\n") && pdf.ends_with("%%EOF\n"));
+        assert!(pdf.contains("/Count 3"));
+        // Every cross-reference entry points at its object
+        let xref: usize = pdf.rsplit("startxref\n").next().unwrap().lines().next().unwrap().parse().unwrap();
+        for (i, entry) in pdf[xref..].lines().skip(3).take(10).enumerate() {
+            let offset: usize = entry[..10].parse().unwrap();
+            assert!(pdf[offset..].starts_with(&format!("{} 0 obj", i + 1)));
+        }
+    }
+}
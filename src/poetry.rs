@@ -0,0 +1,193 @@
+use axum::{
+    Json,
+    extract::{Query, State},
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    ServiceError,
+    content::{ContentParams, ContentSchema, ContentTypeDescriptor},
+    keyvalue::KeyValueStore,
+    problem::ErrorResponse,
+    reading::ReadingQuery,
+    session::Session,
+    state::AppState,
+    storage::ObjectStore,
+    validation::{BannedWords, ContentValidator, MinItems, NoEmptyFields},
+};
+
+/// Storage prefix and registry identifier for poems
+pub const POEMS_PREFIX: &str = "poems";
+
+/// Grade used when the request doesn't specify one
+const DEFAULT_GRADE: u8 = 3;
+
+/// Query parameters accepted by the poems endpoint
+#[derive(Debug, Deserialize, Default, JsonSchema)]
+pub struct PoemQuery {
+    /// Grade level the poem should target
+    pub grade: Option<u8>,
+
+    /// Subject of the poem (e.g., "autumn leaves")
+    pub topic: Option<String>,
+}
+
+impl PoemQuery {
+    /// Validates the query and converts it into content parameters, accepting the
+    /// same grades and topics as reading passages
+    pub fn into_params(self) -> Result<ContentParams, ServiceError> {
+        let grade = Some(self.grade.unwrap_or(DEFAULT_GRADE));
+        ReadingQuery { grade, topic: self.topic, ..Default::default() }.into_params()
+    }
+}
+
+/// Figurative language and sound devices poems are studied for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum LiteraryDevice {
+    Rhyme,
+    Simile,
+    Metaphor,
+    Imagery,
+    Personification,
+    Alliteration,
+    Onomatopoeia,
+    Repetition,
+}
+
+/// Where a poem uses a device
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+pub struct DeviceExample {
+    pub device: LiteraryDevice,
+
+    /// Words quoted exactly from the poem
+    pub quote: String,
+
+    /// How the quote uses the device, in words a student understands
+    pub explanation: String,
+}
+
+/// A question about how a poem uses a device
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+pub struct PoemQuestion {
+    pub question: String,
+
+    /// Device the question asks about
+    pub device: LiteraryDevice,
+}
+
+/// A short poem annotated with its literary devices, and questions about them
+#[derive(Serialize, Deserialize, Clone, JsonSchema)]
+pub struct Poem {
+    pub title: String,
+
+    /// Form of the poem (e.g., "rhyming couplets", "free verse", "limerick")
+    pub form: String,
+
+    /// The poem's stanzas, each one string per line
+    pub stanzas: Vec<Vec<String>>,
+
+    /// Devices the poem uses, with where
+    pub devices: Vec<DeviceExample>,
+
+    pub questions: Vec<PoemQuestion>,
+}
+
+/// Lowercases text and collapses its whitespace, so quotes match across line
+/// breaks and capitalization
+fn normalized(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase()
+}
+
+/// Requires every annotated device to quote the poem, and every question to ask
+/// about an annotated device
+#[derive(Debug, Clone, Default)]
+pub struct DevicesQuoted;
+
+impl ContentValidator for DevicesQuoted {
+    fn validate(&self, content: &Value, _params: &ContentParams) -> Result<(), String> {
+        let poem: Poem = serde_json::from_value(content.clone()).map_err(|e| format!("malformed poem: {}", e))?;
+        let text = normalized(&poem.stanzas.concat().join("\n"));
+
+        if let Some(example) = poem.devices.iter().find(|example| !text.contains(&normalized(&example.quote))) {
+            return Err(format!("the poem doesn't contain the quote {:?}", example.quote));
+        }
+        if let Some(question) = poem
+            .questions
+            .iter()
+            .find(|question| !poem.devices.iter().any(|example| example.device == question.device))
+        {
+            return Err(format!("question {:?} asks about a device the poem isn't annotated with", question.question));
+        }
+        Ok(())
+    }
+}
+
+/// Returns the content type descriptor for poems
+pub fn descriptor() -> ContentTypeDescriptor {
+    ContentTypeDescriptor::new(
+        POEMS_PREFIX,
+        "poem",
+        ContentSchema::for_type::<Poem>(
+            "Poem",
+            "A short poem, the literary devices it uses, and questions about them",
+        ),
+    )
+    .with_validator(MinItems::new("stanzas", 1))
+    .with_validator(MinItems::new("devices", 2))
+    .with_validator(MinItems::new("questions", 3))
+    .with_validator(DevicesQuoted)
+    .with_validator(NoEmptyFields)
+    .with_validator(BannedWords::default())
+    .with_default_params(ContentParams::new().with("grade", DEFAULT_GRADE))
+}
+
+/// Serves a cached poem the session hasn't read, or generates a new one
+pub async fn poems<S: ObjectStore, K: KeyValueStore>(
+    State(state): State<AppState<S, K>>,
+    Query(query): Query<PoemQuery>,
+    session: Session,
+) -> Result<Json<Poem>, ErrorResponse> {
+    let params = query.into_params()?;
+
+    let descriptor = state
+        .content_types
+        .get(POEMS_PREFIX)
+        .ok_or_else(|| ServiceError::ConfigError(POEMS_PREFIX.into()))?;
+
+    let session_id = session.id();
+    let params = state
+        .with_prompt_variant(descriptor, params, Some(session_id))
+        .await?;
+
+    let poem: Poem = state
+        .get_or_generate(descriptor, &params, Some(session_id))
+        .await?;
+
+    Ok(Json(poem))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn requires_devices_quoted_from_the_poem() {
+        let poem = |quote: &str, asked: &str| {
+            json!({
+                "title": "Wind",
+                "form": "rhyming couplets",
+                "stanzas": [["The wind is a wolf that howls at night,"], ["It rattles the windows with all its might."]],
+                "devices": [{ "device": "metaphor", "quote": quote, "explanation": "The wind is called a wolf." }],
+                "questions": [{ "question": "What is the wind compared to?", "device": asked }],
+            })
+        };
+        let params = ContentParams::new();
+        assert!(DevicesQuoted.validate(&poem("the wind is a  WOLF", "metaphor"), &params).is_ok());
+        assert!(DevicesQuoted.validate(&poem("the wind is a tiger", "metaphor"), &params).is_err());
+        assert!(DevicesQuoted.validate(&poem("the wind is a wolf", "simile"), &params).is_err());
+    }
+}
@@ -0,0 +1,157 @@
+use axum::{
+    Json,
+    http::{HeaderValue, StatusCode, header},
+    response::{IntoResponse, Response},
+};
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::time::Duration;
+
+use crate::{ServiceError, request_id};
+
+/// Media type of RFC 7807 problem details
+pub const PROBLEM_JSON: &str = "application/problem+json";
+
+/// Error returned by HTTP handlers, rendered as RFC 7807 problem details
+///
+/// Handlers return `Result<_, ErrorResponse>` and can use `?` on
+/// `ServiceError`s, which convert with their status, code, and retryability.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ErrorResponse {
+    /// HTTP status of the response
+    pub status: StatusCode,
+
+    /// Machine-readable error code, stable across releases
+    pub code: &'static str,
+
+    /// Human-readable explanation safe to show clients
+    pub detail: String,
+
+    /// Whether the client may retry the same request
+    pub retryable: bool,
+
+    /// How long the client should wait before retrying, sent as `Retry-After`
+    pub retry_after: Option<Duration>,
+}
+
+/// Body of a problem+json response
+#[derive(Debug, Serialize, JsonSchema)]
+pub(crate) struct ProblemDetails<'a> {
+    #[serde(rename = "type")]
+    problem_type: &'static str,
+    title: &'a str,
+    status: u16,
+    detail: &'a str,
+    code: &'a str,
+    retryable: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    request_id: Option<String>,
+}
+
+impl ErrorResponse {
+    /// Creates an error response that isn't retryable
+    pub fn new(status: StatusCode, code: &'static str, detail: impl Into<String>) -> Self {
+        Self {
+            status,
+            code,
+            detail: detail.into(),
+            retryable: false,
+            retry_after: None,
+        }
+    }
+}
+
+impl From<ServiceError> for ErrorResponse {
+    fn from(err: ServiceError) -> Self {
+        let code = err.code();
+        let retryable = err.is_retryable();
+        let retry_after = err.retry_after();
+        let (status, detail) = err.into_status();
+
+        Self {
+            status,
+            code,
+            detail,
+            retryable,
+            retry_after,
+        }
+    }
+}
+
+impl IntoResponse for ErrorResponse {
+    fn into_response(self) -> Response {
+        let body = ProblemDetails {
+            problem_type: "about:blank",
+            title: self.status.canonical_reason().unwrap_or("Error"),
+            status: self.status.as_u16(),
+            detail: &self.detail,
+            code: self.code,
+            retryable: self.retryable,
+            request_id: request_id::current(),
+        };
+
+        let mut response = (self.status, Json(body)).into_response();
+        let headers = response.headers_mut();
+        headers.insert(header::CONTENT_TYPE, HeaderValue::from_static(PROBLEM_JSON));
+        if let Some(retry_after) = self.retry_after {
+            headers.insert(header::RETRY_AFTER, HeaderValue::from(retry_after.as_secs()));
+        }
+        response
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{Router, body::Body, http::Request, routing::get};
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn renders_problem_details_with_request_id() {
+        let app = Router::new()
+            .route(
+                "/",
+                get(|| async {
+                    Err::<(), ErrorResponse>(ServiceError::DynamoDbError("timeout".into()).into())
+                }),
+            )
+            .layer(axum::middleware::from_fn(request_id::request_id));
+
+        let request = Request::get("/")
+            .header(request_id::REQUEST_ID_HEADER, "req-1")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(response.headers()[header::CONTENT_TYPE], PROBLEM_JSON);
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(
+            body,
+            serde_json::json!({
+                "type": "about:blank",
+                "title": "Service Unavailable",
+                "status": 503,
+                "detail": "Database service unavailable",
+                "code": "database_unavailable",
+                "retryable": true,
+                "request_id": "req-1",
+            })
+        );
+    }
+
+    #[test]
+    fn sets_retry_after_when_rate_limited() {
+        let response = ErrorResponse::from(ServiceError::RateLimited {
+            message: "slow down".into(),
+            retry_after: Some(Duration::from_secs(7)),
+        })
+        .into_response();
+
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers()[header::RETRY_AFTER], "7");
+    }
+}
@@ -0,0 +1,445 @@
+use axum::{
+    Json, Router,
+    extract::{Query, State},
+    http::StatusCode,
+    routing::get,
+};
+use chrono::{DateTime, NaiveDate, SecondsFormat, Utc};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::{
+    ServiceError, difficulty, gamification,
+    keyvalue::{Column, KeyValueStore, RecordQuery, column_json, encode_json},
+    problem::ErrorResponse,
+    state::AppState,
+    storage::ObjectStore,
+    users::{Account, CurrentUser},
+};
+
+/// Most activities returned by one history request
+const MAX_HISTORY_LIMIT: usize = 500;
+
+/// Activities returned by a history request that doesn't set a limit
+const DEFAULT_HISTORY_LIMIT: usize = 100;
+
+/// Longest activity name accepted, in characters
+const MAX_ACTIVITY_LEN: usize = 64;
+
+/// Longest content key accepted
+const MAX_CONTENT_KEY_LEN: usize = 256;
+
+/// Column of an activity record holding its JSON
+const ACTIVITY_COLUMN: &str = "activity";
+
+/// Skill an activity exercises
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Skill {
+    Comprehension,
+    Vocabulary,
+    Math,
+}
+
+impl Skill {
+    pub const ALL: [Skill; 3] = [Skill::Comprehension, Skill::Vocabulary, Skill::Math];
+
+    /// Name used in JSON and counter column names
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Skill::Comprehension => "comprehension",
+            Skill::Vocabulary => "vocabulary",
+            Skill::Math => "math",
+        }
+    }
+}
+
+/// A completed activity, as sent by the client
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct NewActivity {
+    /// ID of the child profile that completed the activity
+    pub child: String,
+
+    /// Kind of activity (e.g., "reading")
+    pub activity: String,
+
+    /// Key of the content object the activity was built from, if any
+    #[serde(default)]
+    pub content_key: Option<String>,
+
+    pub skill: Skill,
+
+    /// Score as a percentage, 0 to 100
+    pub score: u8,
+}
+
+/// A completed activity, as stored and returned
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct ActivityResult {
+    pub id: String,
+    pub child: String,
+    pub activity: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_key: Option<String>,
+    pub skill: Skill,
+
+    /// Score as a percentage, 0 to 100
+    pub score: u8,
+
+    /// When the activity was completed, in RFC 3339 format
+    pub completed_at: String,
+}
+
+/// A child's totals for one skill, over all recorded activities
+#[derive(Debug, Default, Clone, PartialEq, Serialize, JsonSchema)]
+pub struct SkillSummary {
+    /// Number of activities completed
+    pub activities: i64,
+
+    /// Mean score as a percentage, or `None` before the first activity
+    pub average_score: Option<f64>,
+}
+
+/// A child's activity history and per-skill totals
+#[derive(Debug, Clone, PartialEq, Serialize, JsonSchema)]
+pub struct ProgressReport {
+    pub child: String,
+
+    /// Activities completed since the requested time, most recent first
+    pub activities: Vec<ActivityResult>,
+
+    /// Totals by skill over all time, regardless of `since`
+    pub skills: BTreeMap<Skill, SkillSummary>,
+}
+
+/// Partition key of a child's activity records
+fn activities_key(child: &str) -> String {
+    format!("progress#{}", child)
+}
+
+/// Key of the item holding a child's per-skill counters
+fn skills_key(child: &str) -> String {
+    format!("progress-skills#{}", child)
+}
+
+/// Sort key of an activity record: its completion time, then its ID so activities
+/// completed in the same millisecond don't collide
+fn activity_sort_key(completed_at: &str, id: &str) -> String {
+    format!("{}#{}", completed_at, id)
+}
+
+/// Formats a time the way sort keys store it, so string order is time order
+fn format_time(time: DateTime<Utc>) -> String {
+    time.to_rfc3339_opts(SecondsFormat::Millis, true)
+}
+
+/// Parses a `since` parameter: an RFC 3339 time or a YYYY-MM-DD date (midnight UTC)
+fn parse_since(since: &str) -> Result<DateTime<Utc>, ServiceError> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(since) {
+        return Ok(time.with_timezone(&Utc));
+    }
+    NaiveDate::parse_from_str(since, "%Y-%m-%d")
+        .ok()
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+        .map(|time| time.and_utc())
+        .ok_or_else(|| {
+            ServiceError::InvalidInput("since must be an RFC 3339 time or a YYYY-MM-DD date".into())
+        })
+}
+
+/// Checks that a child profile belongs to the account
+fn require_child(account: &Account, child: &str) -> Result<(), ServiceError> {
+    match account.profile(child) {
+        Some(_) => Ok(()),
+        None => Err(ServiceError::NotFound(format!("Unknown profile: {}", child))),
+    }
+}
+
+/// Records a completed activity and adds it to the child's skill totals
+pub async fn record<K: KeyValueStore>(
+    kv_store: &K,
+    activity: NewActivity,
+    completed_at: DateTime<Utc>,
+) -> Result<ActivityResult, ServiceError> {
+    let name = activity.activity.trim();
+    if name.is_empty()
+        || name.chars().count() > MAX_ACTIVITY_LEN
+        || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(ServiceError::InvalidInput(format!(
+            "activity must be 1 to {} letters, digits, hyphens, or underscores",
+            MAX_ACTIVITY_LEN
+        )));
+    }
+    if activity.score > 100 {
+        return Err(ServiceError::InvalidInput("score must be between 0 and 100".into()));
+    }
+    if activity
+        .content_key
+        .as_ref()
+        .is_some_and(|key| key.is_empty() || key.len() > MAX_CONTENT_KEY_LEN)
+    {
+        return Err(ServiceError::InvalidInput(format!(
+            "content_key must be 1 to {} bytes",
+            MAX_CONTENT_KEY_LEN
+        )));
+    }
+
+    let result = ActivityResult {
+        id: Uuid::new_v4().simple().to_string(),
+        child: activity.child,
+        activity: name.to_string(),
+        content_key: activity.content_key,
+        skill: activity.skill,
+        score: activity.score,
+        completed_at: format_time(completed_at),
+    };
+
+    kv_store
+        .put_record(
+            activities_key(&result.child),
+            activity_sort_key(&result.completed_at, &result.id),
+            vec![Column::new(ACTIVITY_COLUMN.to_string(), encode_json(&result)?)],
+            None,
+        )
+        .await?;
+
+    let skill = result.skill.as_str();
+    let key = skills_key(&result.child);
+    kv_store
+        .increment(key.clone(), format!("{}.activities", skill), 1)
+        .await?;
+    kv_store
+        .increment(key, format!("{}.score_total", skill), result.score.into())
+        .await?;
+
+    Ok(result)
+}
+
+/// Returns a child's activities completed at or after `since`, most recent first
+pub async fn history<K: KeyValueStore>(
+    kv_store: &K,
+    child: &str,
+    since: Option<DateTime<Utc>>,
+    limit: usize,
+) -> Result<Vec<ActivityResult>, ServiceError> {
+    let mut query = RecordQuery::new(activities_key(child));
+    if let Some(since) = since {
+        // '~' sorts after every character of a time or ID, so the range is open-ended
+        query = query.between(format_time(since), "~");
+    }
+
+    let records = kv_store
+        .query_records(query.descending().limit(limit), vec![ACTIVITY_COLUMN.to_string()])
+        .await?;
+
+    records
+        .iter()
+        .filter_map(|record| column_json(&record.columns, ACTIVITY_COLUMN).transpose())
+        .collect()
+}
+
+/// Returns a child's totals for every skill
+pub async fn skill_summaries<K: KeyValueStore>(
+    kv_store: &K,
+    child: &str,
+) -> Result<BTreeMap<Skill, SkillSummary>, ServiceError> {
+    let column_names = Skill::ALL
+        .iter()
+        .flat_map(|skill| {
+            ["activities", "score_total"].map(|counter| format!("{}.{}", skill.as_str(), counter))
+        })
+        .collect();
+    let columns = kv_store.get(skills_key(child), column_names).await?;
+    let counter = |name: String| {
+        columns
+            .iter()
+            .find(|column| column.name == name)
+            .and_then(Column::as_counter)
+            .unwrap_or(0)
+    };
+
+    Ok(Skill::ALL
+        .into_iter()
+        .map(|skill| {
+            let activities = counter(format!("{}.activities", skill.as_str()));
+            let score_total = counter(format!("{}.score_total", skill.as_str()));
+            let average_score = (activities > 0).then(|| score_total as f64 / activities as f64);
+            (skill, SkillSummary { activities, average_score })
+        })
+        .collect())
+}
+
+/// Builds the router for progress endpoints
+///
+/// Routes must run inside the `session` and `current_user` middleware.
+pub fn router<S, K>() -> Router<AppState<S, K>>
+where
+    S: ObjectStore + 'static,
+    K: KeyValueStore + 'static,
+{
+    Router::new().route("/progress", get(get_progress).post(post_progress))
+}
+
+/// Records an activity completed by one of the logged-in account's children
+pub async fn post_progress<S: ObjectStore, K: KeyValueStore>(
+    State(state): State<AppState<S, K>>,
+    user: CurrentUser,
+    Json(activity): Json<NewActivity>,
+) -> Result<(StatusCode, Json<ActivityResult>), ErrorResponse> {
+    let result = complete(&state, &user, activity).await?;
+    Ok((StatusCode::CREATED, Json(result)))
+}
+
+/// Records an activity completed by one of an account's children, adjusting
+/// their difficulty for its skill and adding to their achievements
+pub async fn complete<S: ObjectStore, K: KeyValueStore>(
+    state: &AppState<S, K>,
+    user: &CurrentUser,
+    activity: NewActivity,
+) -> Result<ActivityResult, ServiceError> {
+    require_child(&user.account, &activity.child)?;
+    complete_for_child(state, activity).await
+}
+
+/// Records an activity completed by any child, for callers that have already
+/// checked who may record it
+pub async fn complete_for_child<S: ObjectStore, K: KeyValueStore>(
+    state: &AppState<S, K>,
+    activity: NewActivity,
+) -> Result<ActivityResult, ServiceError> {
+    let result = record(&state.kv_store, activity, Utc::now()).await?;
+
+    // The activity is already recorded, so a failed adjustment only delays it
+    if let Err(e) = difficulty::record_score(
+        &state.kv_store,
+        &state.config.difficulty,
+        &result.child,
+        result.skill,
+        result.score,
+    )
+    .await
+    {
+        warn!("Failed to update difficulty for {}: {}", result.child, e);
+    }
+    match gamification::record_activity(&state.kv_store, &result, Utc::now().date_naive()).await {
+        Ok(badges) if !badges.is_empty() => info!("{} earned {:?}", result.child, badges),
+        Ok(_) => {}
+        Err(e) => warn!("Failed to update achievements for {}: {}", result.child, e),
+    }
+
+    Ok(result)
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct ProgressQuery {
+    /// ID of the child profile; the selected profile if omitted
+    pub child: Option<String>,
+
+    /// Only return activities completed at or after this time or date
+    pub since: Option<String>,
+
+    /// Most activities to return
+    pub limit: Option<usize>,
+}
+
+/// Returns a child's activity history and skill totals
+pub async fn get_progress<S: ObjectStore, K: KeyValueStore>(
+    State(state): State<AppState<S, K>>,
+    user: CurrentUser,
+    Query(query): Query<ProgressQuery>,
+) -> Result<Json<ProgressReport>, ErrorResponse> {
+    Ok(Json(report(&state, &user, query).await?))
+}
+
+/// Builds the progress report of one of an account's children, the selected
+/// profile by default
+pub async fn report<S: ObjectStore, K: KeyValueStore>(
+    state: &AppState<S, K>,
+    user: &CurrentUser,
+    query: ProgressQuery,
+) -> Result<ProgressReport, ServiceError> {
+    let child = query
+        .child
+        .or_else(|| user.profile.as_ref().map(|profile| profile.id.clone()))
+        .ok_or_else(|| ServiceError::InvalidInput("child is required when no profile is selected".into()))?;
+    require_child(&user.account, &child)?;
+    report_for_child(state, child, query.since.as_deref(), query.limit).await
+}
+
+/// Builds the progress report of any child, for callers that have already
+/// checked who may read it
+pub async fn report_for_child<S: ObjectStore, K: KeyValueStore>(
+    state: &AppState<S, K>,
+    child: String,
+    since: Option<&str>,
+    limit: Option<usize>,
+) -> Result<ProgressReport, ServiceError> {
+    let since = since.map(parse_since).transpose()?;
+    let limit = limit.unwrap_or(DEFAULT_HISTORY_LIMIT);
+    if !(1..=MAX_HISTORY_LIMIT).contains(&limit) {
+        return Err(ServiceError::InvalidInput(format!(
+            "limit must be between 1 and {}",
+            MAX_HISTORY_LIMIT
+        )));
+    }
+
+    let activities = history(&state.kv_store, &child, since, limit).await?;
+    let skills = skill_summaries(&state.kv_store, &child).await?;
+
+    Ok(ProgressReport { child, activities, skills })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keyvalue::MemoryKeyValueStore;
+    use chrono::TimeZone;
+
+    fn activity(skill: Skill, score: u8) -> NewActivity {
+        NewActivity {
+            child: "c1".to_string(),
+            activity: "reading".to_string(),
+            content_key: None,
+            skill,
+            score,
+        }
+    }
+
+    #[tokio::test]
+    async fn records_history_and_skill_totals() {
+        let store = MemoryKeyValueStore::new();
+        let day = |d| Utc.with_ymd_and_hms(2025, 10, d, 12, 0, 0).unwrap();
+
+        record(&store, activity(Skill::Comprehension, 80), day(1)).await.unwrap();
+        record(&store, activity(Skill::Comprehension, 100), day(2)).await.unwrap();
+        record(&store, activity(Skill::Math, 50), day(3)).await.unwrap();
+        assert!(record(&store, activity(Skill::Math, 101), day(3)).await.is_err());
+
+        let all = history(&store, "c1", None, 10).await.unwrap();
+        let scores: Vec<u8> = all.iter().map(|a| a.score).collect();
+        assert_eq!(scores, vec![50, 100, 80]);
+
+        let recent = history(&store, "c1", Some(parse_since("2025-10-02").unwrap()), 10)
+            .await
+            .unwrap();
+        assert_eq!(recent.len(), 2);
+        assert!(history(&store, "c2", None, 10).await.unwrap().is_empty());
+
+        let skills = skill_summaries(&store, "c1").await.unwrap();
+        assert_eq!(skills[&Skill::Comprehension].activities, 2);
+        assert_eq!(skills[&Skill::Comprehension].average_score, Some(90.0));
+        assert_eq!(skills[&Skill::Vocabulary].average_score, None);
+    }
+
+    #[test]
+    fn parses_since() {
+        let midnight = Utc.with_ymd_and_hms(2025, 10, 2, 0, 0, 0).unwrap();
+        assert_eq!(parse_since("2025-10-02").unwrap(), midnight);
+        assert_eq!(parse_since("2025-10-02T02:00:00+02:00").unwrap(), midnight);
+        assert!(parse_since("yesterday").is_err());
+    }
+}
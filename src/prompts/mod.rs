@@ -1,67 +1,172 @@
 use include_dir::{include_dir, Dir};
-use serde::Deserialize;
-use std::collections::HashMap;
-use std::sync::OnceLock;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock, RwLock};
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+use tracing::warn;
+
+use crate::{experiments::ExperimentConfig, storage::ObjectStore, ServiceError};
+
+mod template;
+
+pub use template::render_template;
 
 static PROMPTS_DIR: Dir = include_dir!("$CARGO_MANIFEST_DIR/prompts");
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct PromptConfig {
     pub name: String,
     pub description: String,
     pub model: String,
     pub system_context: String,
     pub prompt: PromptText,
+
+    /// Version of this prompt, used to attribute generated content to a variant
+    #[serde(default = "default_version")]
+    pub version: String,
+
+    /// Optional A/B experiment between this prompt and its `@variant` prompts
+    #[serde(default)]
+    pub experiment: Option<ExperimentConfig>,
+
+    /// Example exchanges shown to the model before the prompt, to demonstrate
+    /// the expected style and difficulty
+    #[serde(default)]
+    pub examples: Vec<PromptExample>,
+}
+
+fn default_version() -> String {
+    "v1".to_string()
 }
 
-#[derive(Debug, Deserialize, Clone)]
+/// A few-shot example: a user request and the ideal assistant response
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PromptExample {
+    pub user: String,
+    pub assistant: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct PromptText {
     pub text: String,
+
+    /// Values for `{{name}}` variables that the caller doesn't supply
+    #[serde(default)]
+    pub defaults: BTreeMap<String, String>,
 }
 
-static PROMPTS: OnceLock<HashMap<String, PromptConfig>> = OnceLock::new();
-
-/// Initialize and return the prompts HashMap
-pub fn prompts() -> &'static HashMap<String, PromptConfig> {
-    PROMPTS.get_or_init(|| {
-        let mut map = HashMap::new();
-
-        for file in PROMPTS_DIR.files() {
-            if let Some(extension) = file.path().extension() {
-                if extension == "toml" {
-                    if let Some(contents) = file.contents_utf8() {
-                        match toml::from_str::<PromptConfig>(contents) {
-                            Ok(config) => {
-                                // Get filename without extension as key
-                                let key = file
-                                    .path()
-                                    .file_stem()
-                                    .and_then(|s| s.to_str())
-                                    .unwrap_or("unknown")
-                                    .to_string();
-
-                                map.insert(key, config);
-                            }
-                            Err(e) => {
-                                eprintln!(
-                                    "Failed to parse prompt file {:?}: {}",
-                                    file.path(),
-                                    e
-                                );
-                            }
-                        }
-                    }
-                }
-            }
+impl PromptConfig {
+    /// Returns a copy of this prompt with the system context and prompt text rendered
+    /// as templates, using the prompt's defaults for any variables not supplied
+    pub fn render(&self, variables: &BTreeMap<String, String>) -> Result<PromptConfig, ServiceError> {
+        let mut values = self.prompt.defaults.clone();
+        values.extend(variables.iter().map(|(k, v)| (k.clone(), v.clone())));
+
+        let mut rendered = self.clone();
+        rendered.system_context = render_template(&self.system_context, &values)?;
+        rendered.prompt.text = render_template(&self.prompt.text, &values)?;
+
+        Ok(rendered)
+    }
+}
+
+/// Interval between reloads of prompts from runtime sources
+pub const PROMPT_RELOAD_INTERVAL: Duration = Duration::from_secs(30);
+
+/// ObjectStore prefix under which uploaded prompt configurations are stored
+pub const PROMPTS_PREFIX: &str = "prompts/";
+
+static PROMPTS: OnceLock<RwLock<Arc<HashMap<String, PromptConfig>>>> = OnceLock::new();
+
+/// Parses and validates a prompt configuration
+///
+/// Besides parsing the TOML, this renders the templates with the prompt's defaults
+/// so malformed templates are rejected up front.
+///
+/// # Returns
+/// * `Ok(PromptConfig)` - The parsed configuration
+/// * `Err(ServiceError)` - If the TOML or its templates are invalid
+pub fn parse_prompt_config(contents: &str) -> Result<PromptConfig, ServiceError> {
+    let config = toml::from_str::<PromptConfig>(contents)
+        .map_err(|e| ServiceError::InvalidInput(format!("Invalid prompt configuration: {}", e)))?;
+
+    config
+        .render(&BTreeMap::new())
+        .map_err(|e| ServiceError::InvalidInput(e.to_string()))?;
+
+    Ok(config)
+}
+
+/// Parses a prompt file's contents, keyed by its file name without extension
+fn parse_prompt(path: &Path, contents: &str) -> Option<(String, PromptConfig)> {
+    match toml::from_str::<PromptConfig>(contents) {
+        Ok(config) => {
+            // Get filename without extension as key
+            let key = path
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .unwrap_or("unknown")
+                .to_string();
+
+            Some((key, config))
+        }
+        Err(e) => {
+            eprintln!("Failed to parse prompt file {:?}: {}", path, e);
+            None
         }
+    }
+}
 
-        map
-    })
+/// Loads the prompts embedded in the binary at build time
+fn embedded_prompts() -> HashMap<String, PromptConfig> {
+    let mut map = HashMap::new();
+
+    for file in PROMPTS_DIR.files() {
+        if file.path().extension().is_none_or(|ext| ext != "toml") {
+            continue;
+        }
+
+        if let Some((key, config)) = file
+            .contents_utf8()
+            .and_then(|contents| parse_prompt(file.path(), contents))
+        {
+            map.insert(key, config);
+        }
+    }
+
+    map
+}
+
+fn prompts_lock() -> &'static RwLock<Arc<HashMap<String, PromptConfig>>> {
+    PROMPTS.get_or_init(|| RwLock::new(Arc::new(embedded_prompts())))
+}
+
+/// Returns a snapshot of all currently loaded prompts
+pub fn prompts() -> Arc<HashMap<String, PromptConfig>> {
+    prompts_lock()
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone()
 }
 
 /// Get a specific prompt by name
-pub fn get_prompt(name: &str) -> Option<&'static PromptConfig> {
-    prompts().get(name)
+pub fn get_prompt(name: &str) -> Option<PromptConfig> {
+    prompts().get(name).cloned()
+}
+
+/// Get a specific version of a prompt
+///
+/// The base prompt's own version resolves to the base prompt; any other version
+/// resolves to the prompt named `{name}@{version}`.
+pub fn get_prompt_version(name: &str, version: &str) -> Option<PromptConfig> {
+    let base = get_prompt(name)?;
+    if base.version == version {
+        Some(base)
+    } else {
+        get_prompt(&crate::experiments::variant_prompt_name(name, version))
+    }
 }
 
 /// List all available prompt names
@@ -69,6 +174,175 @@ pub fn list_prompt_names() -> Vec<String> {
     prompts().keys().cloned().collect()
 }
 
+/// Replaces the loaded prompts with the embedded set overlaid by `overrides`
+///
+/// Prompts in `overrides` take precedence over embedded prompts with the same name,
+/// and embedded prompts remain available when they aren't overridden.
+pub fn install_prompts(overrides: HashMap<String, PromptConfig>) {
+    let mut map = embedded_prompts();
+    map.extend(overrides);
+
+    *prompts_lock().write().unwrap_or_else(|e| e.into_inner()) = Arc::new(map);
+}
+
+/// Adds or replaces a single loaded prompt, effective immediately
+pub fn install_prompt(name: &str, config: PromptConfig) {
+    let mut lock = prompts_lock().write().unwrap_or_else(|e| e.into_inner());
+    Arc::make_mut(&mut lock).insert(name.to_string(), config);
+}
+
+/// Loads all prompt configurations uploaded to the ObjectStore
+///
+/// # Arguments
+/// * `object_store` - The store holding prompts under `PROMPTS_PREFIX`
+///
+/// # Returns
+/// * `Ok(HashMap)` - The prompts that parsed successfully, keyed by name
+/// * `Err(ServiceError)` - If listing or fetching fails
+pub async fn load_prompts_from_store<S: ObjectStore>(
+    object_store: &S,
+) -> Result<HashMap<String, PromptConfig>, ServiceError> {
+    let mut map = HashMap::new();
+
+    for object in object_store.list_objects(PROMPTS_PREFIX).await? {
+        let path = Path::new(&object.key);
+        if path.extension().is_none_or(|ext| ext != "toml") {
+            continue;
+        }
+
+        let contents = String::from_utf8(object_store.get_object(&object.key).await?)?;
+        if let Some((key, config)) = parse_prompt(path, &contents) {
+            map.insert(key, config);
+        }
+    }
+
+    Ok(map)
+}
+
+/// Loads all prompt files from a directory on disk
+///
+/// # Arguments
+/// * `dir` - The directory containing `*.toml` prompt files
+///
+/// # Returns
+/// * `Ok(HashMap)` - The prompts that parsed successfully, keyed by file name
+/// * `Err(ServiceError)` - If the directory can't be read
+pub async fn load_prompts_from_dir(dir: &Path) -> Result<HashMap<String, PromptConfig>, ServiceError> {
+    let mut map = HashMap::new();
+    let mut entries = tokio::fs::read_dir(dir).await?;
+
+    while let Some(entry) = entries.next_entry().await? {
+        let path = entry.path();
+        if path.extension().is_none_or(|ext| ext != "toml") {
+            continue;
+        }
+
+        let contents = tokio::fs::read_to_string(&path).await?;
+        if let Some((key, config)) = parse_prompt(&path, &contents) {
+            map.insert(key, config);
+        }
+    }
+
+    Ok(map)
+}
+
+/// Parses every embedded prompt file, and those in a directory, reporting each result
+///
+/// Files are checked with `parse_prompt_config`, so templates must render too.
+/// Partials are skipped, since they're only meaningful inside a prompt.
+///
+/// # Arguments
+/// * `dir` - An optional directory containing `*.toml` prompt files
+///
+/// # Returns
+/// * `Ok(Vec)` - Each file's path and whether it parsed, sorted by path
+/// * `Err(ServiceError)` - If the directory can't be read
+pub async fn validate_prompt_files(
+    dir: Option<&Path>,
+) -> Result<Vec<(String, Result<(), ServiceError>)>, ServiceError> {
+    let mut results = Vec::new();
+
+    for file in PROMPTS_DIR.files() {
+        if file.path().extension().is_none_or(|ext| ext != "toml") {
+            continue;
+        }
+
+        let result = match file.contents_utf8() {
+            Some(contents) => parse_prompt_config(contents).map(|_| ()),
+            None => Err(ServiceError::InvalidInput("Prompt file is not UTF-8".to_string())),
+        };
+        results.push((format!("embedded:{}", file.path().display()), result));
+    }
+
+    if let Some(dir) = dir {
+        let mut entries = tokio::fs::read_dir(dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            if path.extension().is_none_or(|ext| ext != "toml") {
+                continue;
+            }
+
+            let result = match tokio::fs::read_to_string(&path).await {
+                Ok(contents) => parse_prompt_config(&contents).map(|_| ()),
+                Err(e) => Err(e.into()),
+            };
+            results.push((path.display().to_string(), result));
+        }
+    }
+
+    results.sort_by(|a, b| a.0.cmp(&b.0));
+    Ok(results)
+}
+
+/// Spawns a background task that periodically reloads prompts from runtime sources
+///
+/// Prompts are loaded immediately and then every `interval`. Prompts uploaded to the
+/// ObjectStore take precedence over those in `dir`, which take precedence over the
+/// embedded set. If a source can't be read, the previously loaded prompts are kept.
+/// Partials are always embedded. The task exits when `shutdown` is cancelled.
+///
+/// # Arguments
+/// * `object_store` - The store holding uploaded prompts under `PROMPTS_PREFIX`
+/// * `dir` - An optional directory containing `*.toml` prompt files
+/// * `interval` - How often to reload
+/// * `shutdown` - Cancelled when the server shuts down
+pub fn spawn_prompt_reloader<S: ObjectStore + 'static>(
+    object_store: S,
+    dir: Option<PathBuf>,
+    interval: Duration,
+    shutdown: CancellationToken,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => {}
+                _ = shutdown.cancelled() => return,
+            }
+
+            if let Err(e) = reload_prompts(&object_store, dir.as_deref()).await {
+                warn!("Failed to reload prompts: {}", e);
+            }
+        }
+    })
+}
+
+/// Loads prompts from runtime sources once and installs them
+///
+/// Prompts uploaded to the ObjectStore take precedence over those in `dir`, which
+/// take precedence over the embedded set. If a source can't be read, nothing is
+/// installed.
+pub async fn reload_prompts<S: ObjectStore>(object_store: &S, dir: Option<&Path>) -> Result<(), ServiceError> {
+    let mut overrides = HashMap::new();
+    if let Some(dir) = dir {
+        overrides.extend(load_prompts_from_dir(dir).await?);
+    }
+    overrides.extend(load_prompts_from_store(object_store).await?);
+    install_prompts(overrides);
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -88,6 +362,103 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_render_substitutes_variables() {
+        let prompt = get_prompt("reading_comprehension").expect("reading prompt should load");
+        let variables = BTreeMap::from([("grade".to_string(), "4".to_string())]);
+
+        let rendered = prompt.render(&variables).unwrap();
+        assert!(rendered.prompt.text.contains("grade 4"));
+        assert!(!rendered.prompt.text.contains("{{grade}}"));
+    }
+
+    #[test]
+    fn test_render_uses_defaults_for_missing_variables() {
+        let prompt = get_prompt("reading_comprehension").expect("reading prompt should load");
+        let variables = BTreeMap::from([
+            ("grade".to_string(), "2".to_string()),
+            ("topic".to_string(), "dinosaurs".to_string()),
+        ]);
+
+        let rendered = prompt.render(&variables).unwrap();
+        assert!(rendered.prompt.text.contains("about dinosaurs"));
+
+        let rendered = prompt.render(&BTreeMap::new()).unwrap();
+        assert!(rendered.prompt.text.contains("grade 3"));
+        assert!(!rendered.prompt.text.contains("about"));
+    }
+
+    #[test]
+    fn test_all_prompts_include_kid_safe_tone() {
+        for (name, prompt) in prompts().iter() {
+            let rendered = prompt.render(&BTreeMap::new()).unwrap();
+            assert!(
+                rendered.system_context.contains("read by children"),
+                "{} should include the kid_safe_tone partial",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_examples() {
+        let config: PromptConfig = toml::from_str(
+            r#"
+            name = "example"
+            description = "A prompt with few-shot examples"
+            model = "gpt-4o-mini"
+            system_context = "You are helpful."
+
+            [prompt]
+            text = "Write a story."
+
+            [[examples]]
+            user = "Write a story about a cat."
+            assistant = '{"title": "Whiskers"}'
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.examples.len(), 1);
+        assert_eq!(config.examples[0].user, "Write a story about a cat.");
+    }
+
+    #[tokio::test]
+    async fn test_load_prompts_from_dir() {
+        let dir = std::env::temp_dir().join(format!("thinkaroo-prompts-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("custom.toml"),
+            "name = \"custom\"\ndescription = \"d\"\nmodel = \"m\"\nsystem_context = \"s\"\n[prompt]\ntext = \"t\"\n",
+        )
+        .unwrap();
+        std::fs::write(dir.join("notes.txt"), "ignored").unwrap();
+
+        let loaded = load_prompts_from_dir(&dir).await.unwrap();
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded["custom"].prompt.text, "t");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_validate_prompt_files() {
+        let dir = std::env::temp_dir().join(format!("thinkaroo-prompts-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("broken.toml"), "name = \"broken\"\n").unwrap();
+
+        let results = validate_prompt_files(Some(&dir)).await.unwrap();
+        let failed: Vec<&str> = results
+            .iter()
+            .filter(|(_, result)| result.is_err())
+            .map(|(file, _)| file.as_str())
+            .collect();
+        assert_eq!(failed, vec![dir.join("broken.toml").display().to_string()]);
+        assert!(results.iter().any(|(file, _)| file.starts_with("embedded:")));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
     #[test]
     fn test_list_prompt_names() {
         let names = list_prompt_names();
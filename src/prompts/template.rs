@@ -0,0 +1,60 @@
+use handlebars::Handlebars;
+use include_dir::{Dir, include_dir};
+use std::collections::BTreeMap;
+use std::sync::OnceLock;
+use tracing::warn;
+
+use crate::ServiceError;
+
+static PARTIALS_DIR: Dir = include_dir!("$CARGO_MANIFEST_DIR/prompts/partials");
+
+static TEMPLATES: OnceLock<Handlebars<'static>> = OnceLock::new();
+
+/// Initialize and return the template engine with all shared partials registered
+///
+/// Partials are loaded from `prompts/partials/*.hbs` and can be included from any
+/// prompt with `{{> name}}`, where `name` is the file name without its extension.
+fn templates() -> &'static Handlebars<'static> {
+    TEMPLATES.get_or_init(|| {
+        let mut handlebars = Handlebars::new();
+
+        // Prompts are plain text, so never HTML-escape variable values
+        handlebars.register_escape_fn(handlebars::no_escape);
+
+        for file in PARTIALS_DIR.files() {
+            if file.path().extension().is_none_or(|ext| ext != "hbs") {
+                continue;
+            }
+
+            let name = file.path().file_stem().and_then(|s| s.to_str());
+            if let (Some(name), Some(contents)) = (name, file.contents_utf8())
+                && let Err(e) = handlebars.register_partial(name, contents.trim_end())
+            {
+                warn!("Failed to register partial {:?}: {}", file.path(), e);
+            }
+        }
+
+        handlebars
+    })
+}
+
+/// Renders a prompt template with the given variables
+///
+/// Templates use handlebars syntax: `{{name}}` for variables, `{{#if name}}...{{/if}}`
+/// for conditionals, and `{{> partial}}` to include a shared partial.
+///
+/// # Arguments
+/// * `template` - The template text
+/// * `variables` - The variable values available to the template
+///
+/// # Returns
+/// * `Ok(String)` - The rendered text
+/// * `Err(ServiceError)` - If the template is malformed or references a missing partial
+pub fn render_template(
+    template: &str,
+    variables: &BTreeMap<String, String>,
+) -> Result<String, ServiceError> {
+    templates()
+        .render_template(template, variables)
+        .map_err(|e| ServiceError::ConfigError(format!("Failed to render prompt template: {}", e)))
+}
@@ -0,0 +1,285 @@
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use super::{PuzzleWord, WordList};
+use crate::rng::SplitMix64;
+
+/// Square with no letter, in `grid` and `solution`
+const BLOCK: char = '#';
+
+/// Square to fill in, in `grid`
+const OPEN: char = '_';
+
+/// A numbered entry, counting rows and columns from 0 at the top left
+#[derive(Debug, Clone, PartialEq, Serialize, JsonSchema)]
+pub struct CrosswordClue {
+    pub number: u32,
+    pub row: usize,
+    pub column: usize,
+    pub clue: String,
+    pub answer: String,
+}
+
+/// A small crossword of interlocking words
+#[derive(Debug, Clone, PartialEq, Serialize, JsonSchema)]
+pub struct Crossword {
+    /// Seed to pass back, with the same words, for the same layout
+    pub seed: u64,
+    pub title: String,
+    pub width: usize,
+    pub height: usize,
+
+    /// The blank grid, one string per row, with "#" for blocked squares and "_"
+    /// for squares to fill in
+    pub grid: Vec<String>,
+
+    /// The filled grid, one string per row, with "#" for blocked squares
+    pub solution: Vec<String>,
+
+    pub across: Vec<CrosswordClue>,
+    pub down: Vec<CrosswordClue>,
+
+    /// Words that couldn't be crossed with the rest, so were left out
+    pub unplaced: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Axis {
+    Across,
+    Down,
+}
+
+impl Axis {
+    /// Rows and columns moved from one letter to the next
+    fn step(self) -> (i32, i32) {
+        match self {
+            Axis::Across => (0, 1),
+            Axis::Down => (1, 0),
+        }
+    }
+}
+
+/// A letter on the board, and the axes of the words through it
+#[derive(Debug, Clone, Copy)]
+struct Square {
+    letter: u8,
+    across: bool,
+    down: bool,
+}
+
+/// Squares by row and column, ordered so layouts don't depend on hashing
+type Board = BTreeMap<(i32, i32), Square>;
+
+struct Entry<'a> {
+    word: &'a PuzzleWord,
+    row: i32,
+    column: i32,
+    axis: Axis,
+}
+
+/// Counts the words a word would cross if placed at `row` and `column`, or
+/// `None` if it can't go there
+///
+/// A word can only cross words on the other axis, at matching letters. Its other
+/// squares must have no letters beside them, and the squares before and after it
+/// must be empty, so it never runs into other words.
+fn crossings(board: &Board, letters: &[u8], row: i32, column: i32, axis: Axis) -> Option<usize> {
+    let (down, across) = axis.step();
+    let len = letters.len() as i32;
+    if board.contains_key(&(row - down, column - across)) || board.contains_key(&(row + down * len, column + across * len)) {
+        return None;
+    }
+
+    let mut crossings = 0;
+    for (i, &letter) in letters.iter().enumerate() {
+        let (r, c) = (row + down * i as i32, column + across * i as i32);
+        match board.get(&(r, c)) {
+            Some(square) => {
+                let taken = match axis {
+                    Axis::Across => square.across,
+                    Axis::Down => square.down,
+                };
+                if square.letter != letter || taken {
+                    return None;
+                }
+                crossings += 1;
+            }
+            None => {
+                if board.contains_key(&(r + across, c + down)) || board.contains_key(&(r - across, c - down)) {
+                    return None;
+                }
+            }
+        }
+    }
+    Some(crossings)
+}
+
+/// Finds where a word crosses the most words already on the board, breaking ties
+/// with `rng`; `None` if it crosses none
+fn best_position(board: &Board, letters: &[u8], rng: &mut SplitMix64) -> Option<(i32, i32, Axis)> {
+    let mut best = Vec::new();
+    let mut most = 0;
+    for (&(row, column), square) in board {
+        let axis = match (square.across, square.down) {
+            (true, false) => Axis::Down,
+            (false, true) => Axis::Across,
+            _ => continue,
+        };
+        let (down, across) = axis.step();
+        for (i, _) in letters.iter().enumerate().filter(|(_, letter)| **letter == square.letter) {
+            let (start_row, start_column) = (row - down * i as i32, column - across * i as i32);
+            match crossings(board, letters, start_row, start_column, axis) {
+                Some(count) if count > most => {
+                    most = count;
+                    best = vec![(start_row, start_column, axis)];
+                }
+                Some(count) if count == most => best.push((start_row, start_column, axis)),
+                _ => {}
+            }
+        }
+    }
+    (!best.is_empty()).then(|| best[rng.index(best.len())])
+}
+
+/// Lays out a crossword from as many words in the list as interlock
+///
+/// The longest word goes across first; each word after it, longest first, goes
+/// where it crosses the most words already placed. Words that can't cross any
+/// are listed in `unplaced`. The same words and seed always give the same
+/// crossword.
+pub fn lay_out(list: &WordList, seed: u64) -> Crossword {
+    let mut words: Vec<&PuzzleWord> = list.words.iter().filter(|w| !w.word.is_empty()).collect();
+    words.sort_by_key(|w| std::cmp::Reverse(w.word.len()));
+
+    let mut rng = SplitMix64::new(seed);
+    let mut board = Board::new();
+    let mut entries: Vec<Entry> = Vec::new();
+    let mut unplaced = Vec::new();
+    for word in words {
+        let letters = word.word.as_bytes();
+        let position = if entries.is_empty() {
+            Some((0, 0, Axis::Across))
+        } else {
+            best_position(&board, letters, &mut rng)
+        };
+        let Some((row, column, axis)) = position else {
+            unplaced.push(word.word.clone());
+            continue;
+        };
+
+        let (down, across) = axis.step();
+        for (i, &letter) in letters.iter().enumerate() {
+            let square = board
+                .entry((row + down * i as i32, column + across * i as i32))
+                .or_insert(Square { letter, across: false, down: false });
+            match axis {
+                Axis::Across => square.across = true,
+                Axis::Down => square.down = true,
+            }
+        }
+        entries.push(Entry { word, row, column, axis });
+    }
+
+    let top = board.keys().map(|&(row, _)| row).min().unwrap_or(0);
+    let left = board.keys().map(|&(_, column)| column).min().unwrap_or(0);
+    let height = board.keys().map(|&(row, _)| row - top + 1).max().unwrap_or(0) as usize;
+    let width = board.keys().map(|&(_, column)| column - left + 1).max().unwrap_or(0) as usize;
+
+    let mut solution = vec![vec![BLOCK; width]; height];
+    for (&(row, column), square) in &board {
+        solution[(row - top) as usize][(column - left) as usize] = char::from(square.letter);
+    }
+    let grid = solution
+        .iter()
+        .map(|row| row.iter().map(|&c| if c == BLOCK { BLOCK } else { OPEN }).collect())
+        .collect();
+    let solution = solution.into_iter().map(|row| row.into_iter().collect()).collect();
+
+    // Entries are numbered in reading order of their first squares, which an
+    // across and a down entry may share
+    let mut starts: Vec<(usize, usize)> = entries
+        .iter()
+        .map(|entry| ((entry.row - top) as usize, (entry.column - left) as usize))
+        .collect();
+    starts.sort();
+    starts.dedup();
+
+    let (mut across, mut down) = (Vec::new(), Vec::new());
+    for entry in &entries {
+        let (row, column) = ((entry.row - top) as usize, (entry.column - left) as usize);
+        let number = starts.iter().position(|&start| start == (row, column)).unwrap_or_default() as u32 + 1;
+        let clue = CrosswordClue {
+            number,
+            row,
+            column,
+            clue: entry.word.clue.clone(),
+            answer: entry.word.word.clone(),
+        };
+        match entry.axis {
+            Axis::Across => across.push(clue),
+            Axis::Down => down.push(clue),
+        }
+    }
+    across.sort_by_key(|clue| clue.number);
+    down.sort_by_key(|clue| clue.number);
+
+    Crossword { seed, title: list.title.clone(), width, height, grid, solution, across, down, unplaced }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::supplied_words;
+    use super::*;
+
+    /// Runs of two or more letters in a line of squares, with where each starts
+    fn runs(line: &[char]) -> Vec<(usize, String)> {
+        let mut runs = Vec::new();
+        let mut start = 0;
+        for end in 0..=line.len() {
+            if end == line.len() || line[end] == BLOCK {
+                if end - start > 1 {
+                    runs.push((start, line[start..end].iter().collect()));
+                }
+                start = end + 1;
+            }
+        }
+        runs
+    }
+
+    #[test]
+    fn interlocks_words_without_stray_runs() {
+        let list = supplied_words("planet:A world circling a star, comet, orbit, telescope, moon, zzz").unwrap();
+        let puzzle = lay_out(&list, 11);
+        assert_eq!(puzzle, lay_out(&list, 11));
+        assert_eq!(puzzle.unplaced, ["ZZZ"]);
+        assert_eq!(puzzle.across.len() + puzzle.down.len(), 5);
+        assert!(puzzle.across.iter().any(|clue| clue.answer == "TELESCOPE"), "the longest word goes across");
+        let planet = puzzle.across.iter().chain(&puzzle.down).find(|clue| clue.answer == "PLANET").unwrap();
+        assert_eq!(planet.clue, "A world circling a star");
+
+        // Every run of letters, across or down, is exactly one numbered entry
+        let squares: Vec<Vec<char>> = puzzle.solution.iter().map(|row| row.chars().collect()).collect();
+        let mut found_across = Vec::new();
+        for (row, line) in squares.iter().enumerate() {
+            found_across.extend(runs(line).into_iter().map(|(column, word)| (row, column, word)));
+        }
+        let mut found_down = Vec::new();
+        for column in 0..puzzle.width {
+            let line: Vec<char> = squares.iter().map(|row| row[column]).collect();
+            found_down.extend(runs(&line).into_iter().map(|(row, word)| (row, column, word)));
+        }
+        let entries = |clues: &[CrosswordClue]| {
+            let mut entries: Vec<_> = clues.iter().map(|c| (c.row, c.column, c.answer.clone())).collect();
+            entries.sort();
+            entries
+        };
+        found_across.sort();
+        found_down.sort();
+        assert_eq!(found_across, entries(&puzzle.across));
+        assert_eq!(found_down, entries(&puzzle.down));
+
+        assert_eq!(puzzle.grid.len(), puzzle.height);
+        assert!(puzzle.grid.iter().flat_map(|row| row.chars()).all(|c| c == BLOCK || c == OPEN));
+    }
+}
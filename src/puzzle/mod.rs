@@ -0,0 +1,197 @@
+pub mod crossword;
+pub mod word_search;
+
+use axum::{
+    Json,
+    extract::{Query, State},
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    ServiceError,
+    content::{ContentParams, ContentSchema, ContentTypeDescriptor},
+    keyvalue::KeyValueStore,
+    problem::ErrorResponse,
+    reading::ReadingQuery,
+    rng,
+    session::Session,
+    state::AppState,
+    storage::ObjectStore,
+    validation::{BannedWords, ContentValidator, MinItems, NoEmptyFields},
+};
+
+pub use crossword::Crossword;
+pub use word_search::WordSearch;
+
+/// Storage prefix and registry identifier for generated puzzle word lists
+pub const PUZZLE_WORDS_PREFIX: &str = "puzzle_words";
+
+/// Grade used when the request doesn't specify one
+const DEFAULT_GRADE: u8 = 3;
+
+/// Title of puzzles made from supplied words
+const SUPPLIED_TITLE: &str = "Vocabulary";
+
+/// Fewest and most words in a puzzle
+const MIN_WORDS: usize = 4;
+const MAX_WORDS: usize = 20;
+
+/// Shortest and longest word in a puzzle, in letters
+const MIN_WORD_LEN: usize = 3;
+const MAX_WORD_LEN: usize = 15;
+
+/// Query parameters accepted by the puzzle endpoints
+#[derive(Debug, Deserialize, Default, JsonSchema)]
+pub struct PuzzleQuery {
+    /// Comma-separated words to use instead of generated ones; each may carry a
+    /// crossword clue after a colon (e.g., "orbit:Path around a planet")
+    pub words: Option<String>,
+
+    /// Grade level of generated words; ignored with `words`
+    pub grade: Option<u8>,
+
+    /// Subject of generated words (e.g., "ocean animals"); ignored with `words`
+    pub topic: Option<String>,
+
+    /// Seed to reproduce an earlier layout; a random one if omitted
+    pub seed: Option<u64>,
+}
+
+/// A word to hide or cross, with its clue
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, JsonSchema)]
+pub struct PuzzleWord {
+    pub word: String,
+
+    /// Crossword clue; empty for supplied words given without one
+    pub clue: String,
+}
+
+/// Vocabulary to make puzzles from
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, JsonSchema)]
+pub struct WordList {
+    pub title: String,
+    pub words: Vec<PuzzleWord>,
+}
+
+/// Returns a word as it is written in a grid, or `None` if it doesn't fit one:
+/// uppercase, and only letters
+fn grid_word(word: &str) -> Option<String> {
+    let word = word.trim().to_uppercase();
+    let fits = (MIN_WORD_LEN..=MAX_WORD_LEN).contains(&word.chars().count())
+        && word.chars().all(|c| c.is_ascii_uppercase());
+    fits.then_some(word)
+}
+
+/// Parses supplied words, e.g. "orbit:Path around a planet, comet"
+fn supplied_words(words: &str) -> Result<WordList, ServiceError> {
+    let mut list = Vec::new();
+    for entry in words.split(',').filter(|entry| !entry.trim().is_empty()) {
+        let (word, clue) = entry.split_once(':').unwrap_or((entry, ""));
+        let word = grid_word(word).ok_or_else(|| {
+            ServiceError::InvalidInput(format!(
+                "puzzle words must be {} to {} letters A-Z, not {:?}",
+                MIN_WORD_LEN,
+                MAX_WORD_LEN,
+                word.trim()
+            ))
+        })?;
+        if !list.iter().any(|w: &PuzzleWord| w.word == word) {
+            list.push(PuzzleWord { word, clue: clue.trim().to_string() });
+        }
+    }
+    if !(MIN_WORDS..=MAX_WORDS).contains(&list.len()) {
+        return Err(ServiceError::InvalidInput(format!(
+            "puzzles take between {} and {} different words",
+            MIN_WORDS, MAX_WORDS
+        )));
+    }
+    Ok(WordList { title: SUPPLIED_TITLE.to_string(), words: list })
+}
+
+/// Requires every generated word to fit in a grid
+#[derive(Debug, Clone, Default)]
+pub struct GridWords;
+
+impl ContentValidator for GridWords {
+    fn validate(&self, content: &Value, _params: &ContentParams) -> Result<(), String> {
+        let list: WordList = serde_json::from_value(content.clone())
+            .map_err(|e| format!("malformed word list: {}", e))?;
+        match list.words.iter().find(|w| grid_word(&w.word).is_none()) {
+            Some(word) => Err(format!(
+                "{:?} is not a single word of {} to {} letters",
+                word.word, MIN_WORD_LEN, MAX_WORD_LEN
+            )),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Returns the content type descriptor for generated puzzle word lists
+pub fn descriptor() -> ContentTypeDescriptor {
+    ContentTypeDescriptor::new(
+        PUZZLE_WORDS_PREFIX,
+        "puzzle_words",
+        ContentSchema::for_type::<WordList>("WordList", "Vocabulary words and clues for puzzles"),
+    )
+    .with_validator(MinItems::new("words", MIN_WORDS))
+    .with_validator(GridWords)
+    .with_validator(NoEmptyFields)
+    .with_validator(BannedWords::default())
+    .with_default_params(ContentParams::new().with("grade", DEFAULT_GRADE))
+}
+
+/// Returns the puzzle's words, from the query or from a cached or newly
+/// generated word list, with the words written as they are in a grid
+///
+/// Generated lists are cached like other content, and puzzles are laid out in
+/// code, so a puzzle costs no tokens beyond its word list.
+async fn word_list<S: ObjectStore, K: KeyValueStore>(
+    state: &AppState<S, K>,
+    query: PuzzleQuery,
+    session_id: &str,
+) -> Result<WordList, ServiceError> {
+    if let Some(words) = &query.words {
+        return supplied_words(words);
+    }
+
+    let grade = Some(query.grade.unwrap_or(DEFAULT_GRADE));
+    let params = ReadingQuery { grade, topic: query.topic, ..Default::default() }.into_params()?;
+    let descriptor = state
+        .content_types
+        .get(PUZZLE_WORDS_PREFIX)
+        .ok_or_else(|| ServiceError::ConfigError(PUZZLE_WORDS_PREFIX.into()))?;
+    let params = state.with_prompt_variant(descriptor, params, Some(session_id)).await?;
+
+    let mut list: WordList = state.get_or_generate(descriptor, &params, Some(session_id)).await?;
+    for word in &mut list.words {
+        word.word = grid_word(&word.word).unwrap_or_default();
+    }
+    list.words.truncate(MAX_WORDS);
+    Ok(list)
+}
+
+/// Serves a word search, hiding a supplied or generated word list
+pub async fn word_search<S: ObjectStore, K: KeyValueStore>(
+    State(state): State<AppState<S, K>>,
+    Query(query): Query<PuzzleQuery>,
+    session: Session,
+) -> Result<Json<WordSearch>, ErrorResponse> {
+    let seed = query.seed.unwrap_or_else(rng::random_seed);
+    let session_id = session.id();
+    let list = word_list(&state, query, session_id).await?;
+    Ok(Json(word_search::lay_out(&list, seed)?))
+}
+
+/// Serves a crossword, crossing as much of a supplied or generated word list as fits
+pub async fn crossword<S: ObjectStore, K: KeyValueStore>(
+    State(state): State<AppState<S, K>>,
+    Query(query): Query<PuzzleQuery>,
+    session: Session,
+) -> Result<Json<Crossword>, ErrorResponse> {
+    let seed = query.seed.unwrap_or_else(rng::random_seed);
+    let session_id = session.id();
+    let list = word_list(&state, query, session_id).await?;
+    Ok(Json(crossword::lay_out(&list, seed)))
+}
@@ -0,0 +1,157 @@
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use super::WordList;
+use crate::{ServiceError, rng::SplitMix64};
+
+/// Widest grid a word search is laid out in
+const MAX_SIZE: usize = 20;
+
+/// Positions tried for each word before trying a larger grid
+const ATTEMPTS_PER_WORD: usize = 200;
+
+/// Way a word reads in the grid
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Direction {
+    /// Left to right
+    Across,
+    /// Top to bottom
+    Down,
+    /// Top left to bottom right
+    Diagonal,
+}
+
+impl Direction {
+    const ALL: [Direction; 3] = [Direction::Across, Direction::Down, Direction::Diagonal];
+
+    /// Rows and columns moved from one letter to the next
+    fn step(self) -> (usize, usize) {
+        match self {
+            Direction::Across => (0, 1),
+            Direction::Down => (1, 0),
+            Direction::Diagonal => (1, 1),
+        }
+    }
+}
+
+/// Where a word is hidden, counting rows and columns from 0 at the top left
+#[derive(Debug, Clone, PartialEq, Serialize, JsonSchema)]
+pub struct HiddenWord {
+    pub word: String,
+    pub row: usize,
+    pub column: usize,
+    pub direction: Direction,
+}
+
+/// A square grid of letters with words hidden in it
+#[derive(Debug, Clone, PartialEq, Serialize, JsonSchema)]
+pub struct WordSearch {
+    /// Seed to pass back, with the same words, for the same grid
+    pub seed: u64,
+    pub title: String,
+
+    /// Rows and columns in the grid
+    pub size: usize,
+
+    /// The grid, one string of letters per row
+    pub grid: Vec<String>,
+
+    /// The words to find, in alphabetical order
+    pub words: Vec<String>,
+
+    /// Where each word is hidden
+    pub solution: Vec<HiddenWord>,
+}
+
+/// Letters of a grid by row and column, `None` where no word has been hidden
+type Grid = Vec<Vec<Option<u8>>>;
+
+/// Hides every word in a `size` by `size` grid, longest first, leaving unused
+/// cells empty; `None` if a word can't be placed
+fn hide(words: &[&str], size: usize, rng: &mut SplitMix64) -> Option<(Grid, Vec<HiddenWord>)> {
+    let mut grid = vec![vec![None; size]; size];
+    let mut solution = Vec::new();
+    for word in words {
+        let letters = word.as_bytes();
+        let placement = (0..ATTEMPTS_PER_WORD).find_map(|_| {
+            let direction = Direction::ALL[rng.index(Direction::ALL.len())];
+            let (down, across) = direction.step();
+            let row = rng.index(size - (letters.len() - 1) * down);
+            let column = rng.index(size - (letters.len() - 1) * across);
+            let fits = letters.iter().enumerate().all(|(i, &letter)| {
+                grid[row + i * down][column + i * across].is_none_or(|cell| cell == letter)
+            });
+            fits.then_some((row, column, direction))
+        })?;
+
+        let (row, column, direction) = placement;
+        let (down, across) = direction.step();
+        for (i, &letter) in letters.iter().enumerate() {
+            grid[row + i * down][column + i * across] = Some(letter);
+        }
+        solution.push(HiddenWord { word: word.to_string(), row, column, direction });
+    }
+    Some((grid, solution))
+}
+
+/// Lays out a word search hiding every word in the list
+///
+/// The grid starts just large enough for the longest word and about half filled
+/// by the words, and grows until they all fit. Words read forwards, across, down,
+/// or diagonally, and may share letters; the remaining cells are filled with
+/// random letters. The same words and seed always give the same grid.
+pub fn lay_out(list: &WordList, seed: u64) -> Result<WordSearch, ServiceError> {
+    let mut words: Vec<&str> = list.words.iter().map(|w| w.word.as_str()).filter(|w| !w.is_empty()).collect();
+    words.sort_by_key(|word| std::cmp::Reverse(word.len()));
+    let longest = words.first().map_or(0, |word| word.len());
+    let letters: usize = words.iter().map(|word| word.len()).sum();
+
+    let mut rng = SplitMix64::new(seed);
+    let smallest = longest.max(((letters * 2) as f64).sqrt().ceil() as usize).max(1);
+    let (size, grid, solution) = (smallest..=MAX_SIZE)
+        .find_map(|size| hide(&words, size, &mut rng).map(|(grid, solution)| (size, grid, solution)))
+        .ok_or_else(|| {
+            ServiceError::InvalidInput(format!("too many letters to hide in a {0}x{0} word search", MAX_SIZE))
+        })?;
+
+    let grid = grid
+        .into_iter()
+        .map(|row| {
+            row.into_iter()
+                .map(|cell| char::from(cell.unwrap_or_else(|| b'A' + rng.index(26) as u8)))
+                .collect()
+        })
+        .collect();
+
+    let mut sorted: Vec<String> = words.iter().map(|word| word.to_string()).collect();
+    sorted.sort();
+    Ok(WordSearch { seed, title: list.title.clone(), size, grid, words: sorted, solution })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::supplied_words;
+    use super::*;
+
+    #[test]
+    fn hides_every_word_where_the_solution_says() {
+        let list = supplied_words("orbit:Path around a planet, comet, galaxy , nebula, Comet, asteroid").unwrap();
+        let puzzle = lay_out(&list, 7).unwrap();
+        assert_eq!(puzzle, lay_out(&list, 7).unwrap());
+        assert_eq!(puzzle.words, ["ASTEROID", "COMET", "GALAXY", "NEBULA", "ORBIT"]);
+        assert_eq!(puzzle.grid.len(), puzzle.size);
+
+        for hidden in &puzzle.solution {
+            let (down, across) = hidden.direction.step();
+            let read: String = (0..hidden.word.len())
+                .map(|i| puzzle.grid[hidden.row + i * down].as_bytes()[hidden.column + i * across] as char)
+                .collect();
+            assert_eq!(read, hidden.word);
+        }
+        assert!(puzzle.grid.iter().all(|row| row.len() == puzzle.size && row.chars().all(|c| c.is_ascii_uppercase())));
+
+        assert!(supplied_words("cat, dog, emu").is_err());
+        assert!(supplied_words("cat, dog, emu, sea lion").is_err());
+    }
+}
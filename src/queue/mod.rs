@@ -0,0 +1,212 @@
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use tokio::sync::{Mutex, Notify};
+
+use crate::{ServiceError, config::Config, content::ContentParams};
+
+mod sqs;
+
+pub use sqs::SqsJobQueue;
+
+/// Longest a worker waits for jobs to arrive before checking for shutdown
+pub const RECEIVE_WAIT: Duration = Duration::from_secs(20);
+
+/// A request to generate one object for a content type's cache
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GenerationJob {
+    /// Prefix of the content type, e.g. "reading"
+    pub content_type: String,
+
+    /// Folder of the cache window the object is for, e.g.
+    /// "reading/grade-3/2025-10-11-14/"; once the window has ended, the job is
+    /// dropped
+    pub slot: String,
+
+    /// Prompt variables, which also partition the cache
+    pub variables: BTreeMap<String, String>,
+
+    /// Prompt variables that don't partition the cache
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub context: BTreeMap<String, String>,
+
+    /// Prompt version to generate with, when the prompt is under an experiment
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prompt_version: Option<String>,
+}
+
+impl GenerationJob {
+    /// Describes generating one object for `slot` with `params`
+    pub fn new(content_type: &str, slot: &str, params: &ContentParams) -> Self {
+        Self {
+            content_type: content_type.to_string(),
+            slot: slot.to_string(),
+            variables: params.variables().clone(),
+            context: params.context().clone(),
+            prompt_version: params.prompt_version().map(str::to_string),
+        }
+    }
+
+    /// Returns the parameters to generate with
+    pub fn params(&self) -> ContentParams {
+        let params = self
+            .variables
+            .iter()
+            .fold(ContentParams::new(), |params, (name, value)| params.with(name, value));
+        let params = self
+            .context
+            .iter()
+            .fold(params, |params, (name, value)| params.with_context(name, value));
+        match &self.prompt_version {
+            Some(version) => params.with_prompt_version(version),
+            None => params,
+        }
+    }
+}
+
+/// What a worker did with a job
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobOutcome {
+    /// An object was generated and stored
+    Generated,
+
+    /// The job's window has ended, so nothing was generated
+    Expired,
+
+    /// The window already holds as many objects as its cache policy allows
+    Full,
+
+    /// Another instance is generating the object for this slot
+    Contended,
+
+    /// The content type isn't served by this instance
+    UnknownContentType,
+}
+
+impl JobOutcome {
+    /// Name of the outcome in metrics
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobOutcome::Generated => "generated",
+            JobOutcome::Expired => "expired",
+            JobOutcome::Full => "full",
+            JobOutcome::Contended => "contended",
+            JobOutcome::UnknownContentType => "unknown_content_type",
+        }
+    }
+}
+
+/// A job taken from a queue, to be deleted once it has been handled
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueuedJob {
+    pub job: GenerationJob,
+
+    /// Identifies this delivery of the job to `JobQueue::delete`
+    pub receipt: String,
+}
+
+/// Carries generation jobs from the instances serving requests to workers
+///
+/// Delivery is at least once: a job that isn't deleted after it is received is
+/// delivered again later, so handling a job twice must be harmless.
+#[async_trait]
+pub trait JobQueue: Send + Sync {
+    /// Adds a job to the queue
+    async fn send(&self, job: &GenerationJob) -> Result<(), ServiceError>;
+
+    /// Takes up to `max` jobs, waiting up to `RECEIVE_WAIT` for any to arrive
+    ///
+    /// # Returns
+    /// * `Ok(Vec)` - The jobs received, empty if none arrived in time
+    /// * `Err(ServiceError)` - If the queue can't be reached
+    async fn receive(&self, max: usize) -> Result<Vec<QueuedJob>, ServiceError>;
+
+    /// Removes a received job, once it has been handled
+    async fn delete(&self, receipt: &str) -> Result<(), ServiceError>;
+}
+
+/// In-process queue, for tests
+///
+/// Jobs are handed out once and lost on restart; `delete` does nothing.
+#[derive(Default)]
+pub struct MemoryJobQueue {
+    jobs: Mutex<VecDeque<GenerationJob>>,
+    sent: Notify,
+    next_receipt: AtomicU64,
+}
+
+impl MemoryJobQueue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl JobQueue for MemoryJobQueue {
+    async fn send(&self, job: &GenerationJob) -> Result<(), ServiceError> {
+        self.jobs.lock().await.push_back(job.clone());
+        self.sent.notify_one();
+        Ok(())
+    }
+
+    async fn receive(&self, max: usize) -> Result<Vec<QueuedJob>, ServiceError> {
+        let take = || async {
+            let mut jobs = self.jobs.lock().await;
+            let count = max.min(jobs.len());
+            jobs.drain(..count)
+                .map(|job| QueuedJob {
+                    job,
+                    receipt: self.next_receipt.fetch_add(1, Ordering::Relaxed).to_string(),
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let received = take().await;
+        if !received.is_empty() {
+            return Ok(received);
+        }
+        let _ = tokio::time::timeout(RECEIVE_WAIT, self.sent.notified()).await;
+        Ok(take().await)
+    }
+
+    async fn delete(&self, _receipt: &str) -> Result<(), ServiceError> {
+        Ok(())
+    }
+}
+
+/// Creates the generation queue a configuration selects, if any
+pub async fn from_config(config: &Config) -> Result<Option<Arc<dyn JobQueue>>, ServiceError> {
+    Ok(match &config.generation_queue_url {
+        Some(url) => Some(Arc::new(SqsJobQueue::from_env(url).await?)),
+        None => None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn hands_out_jobs_with_their_parameters() {
+        let params = ContentParams::new().with("grade", 3).with_prompt_version("v2");
+        let job = GenerationJob::new("reading", "reading/grade-3/version-v2/2025-10-11-14/", &params);
+        let json = serde_json::to_string(&job).unwrap();
+        let decoded: GenerationJob = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded, job);
+        assert_eq!(decoded.params().partition(), params.partition());
+
+        let queue = Arc::new(MemoryJobQueue::new());
+        let receiver = tokio::spawn({
+            let queue = queue.clone();
+            async move { queue.receive(10).await.unwrap() }
+        });
+        queue.send(&job).await.unwrap();
+        let received = receiver.await.unwrap();
+        assert_eq!(received.len(), 1);
+        assert_eq!(received[0].job, job);
+        queue.delete(&received[0].receipt).await.unwrap();
+    }
+}
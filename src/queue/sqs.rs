@@ -0,0 +1,168 @@
+use async_trait::async_trait;
+use aws_credential_types::provider::{ProvideCredentials, SharedCredentialsProvider};
+use aws_sigv4::{
+    http_request::{SignableBody, SignableRequest, SigningSettings, sign},
+    sign::v4,
+};
+use serde::Deserialize;
+use serde_json::{Value, json};
+use std::time::SystemTime;
+use tracing::warn;
+
+use super::{GenerationJob, JobQueue, QueuedJob, RECEIVE_WAIT};
+use crate::ServiceError;
+
+/// Name SQS requests are signed for
+const SIGNING_NAME: &str = "sqs";
+
+/// Content type of the SQS JSON protocol
+const AMZ_JSON: &str = "application/x-amz-json-1.0";
+
+/// Most messages a single ReceiveMessage call returns
+const MAX_RECEIVE: usize = 10;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ReceiveMessageOutput {
+    #[serde(default)]
+    messages: Vec<Message>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct Message {
+    message_id: String,
+    receipt_handle: String,
+    body: String,
+}
+
+/// Queue backed by an Amazon SQS queue, through its JSON API
+///
+/// Requests are signed with the same credentials and region as the rest of the
+/// AWS clients. A job that isn't deleted becomes visible again after the queue's
+/// visibility timeout, which should exceed the time it takes to generate an
+/// object; configure a redrive policy to set aside jobs that keep failing.
+#[derive(Clone)]
+pub struct SqsJobQueue {
+    http: reqwest::Client,
+    credentials: SharedCredentialsProvider,
+    region: String,
+    queue_url: String,
+}
+
+impl SqsJobQueue {
+    /// Creates a queue client from the default AWS configuration
+    ///
+    /// # Arguments
+    /// * `queue_url` - URL of the queue, e.g.
+    ///   "https://sqs.us-east-1.amazonaws.com/123456789012/thinkaroo-generation"
+    pub async fn from_env(queue_url: &str) -> Result<Self, ServiceError> {
+        let aws_config = aws_config::load_defaults(aws_config::BehaviorVersion::latest()).await;
+        let credentials = aws_config.credentials_provider().ok_or_else(|| {
+            ServiceError::ConfigError("AWS credentials are required to use an SQS queue".into())
+        })?;
+        let region = aws_config.region().ok_or_else(|| {
+            ServiceError::ConfigError("An AWS region is required to use an SQS queue".into())
+        })?;
+
+        Ok(Self {
+            http: reqwest::Client::new(),
+            credentials,
+            region: region.to_string(),
+            queue_url: queue_url.to_string(),
+        })
+    }
+
+    /// Calls an SQS action with a JSON request, returning its JSON response
+    async fn call(&self, action: &str, mut input: Value) -> Result<Value, ServiceError> {
+        input["QueueUrl"] = json!(self.queue_url);
+        let body = serde_json::to_vec(&input)?;
+        let target = format!("AmazonSQS.{}", action);
+        let failed = |e: &dyn std::fmt::Display| ServiceError::QueueError(format!("SQS {} failed: {}", action, e));
+
+        let identity = self
+            .credentials
+            .provide_credentials()
+            .await
+            .map_err(|e| ServiceError::QueueError(format!("Failed to load AWS credentials: {}", e)))?
+            .into();
+        let signing_params = v4::SigningParams::builder()
+            .identity(&identity)
+            .region(&self.region)
+            .name(SIGNING_NAME)
+            .time(SystemTime::now())
+            .settings(SigningSettings::default())
+            .build()
+            .map_err(|e| failed(&e))?
+            .into();
+        let signable = SignableRequest::new(
+            "POST",
+            self.queue_url.as_str(),
+            [("content-type", AMZ_JSON), ("x-amz-target", target.as_str())].into_iter(),
+            SignableBody::Bytes(&body),
+        )
+        .and_then(|request| sign(request, &signing_params))
+        .map_err(|e| failed(&e))?;
+        let (instructions, _signature) = signable.into_parts();
+
+        let mut request = self
+            .http
+            .post(&self.queue_url)
+            .header(reqwest::header::CONTENT_TYPE, AMZ_JSON)
+            .header("x-amz-target", &target);
+        for (name, value) in instructions.headers() {
+            request = request.header(name, value);
+        }
+
+        let response = request.body(body).send().await.map_err(|e| failed(&e))?;
+        let status = response.status();
+        let text = response.text().await.map_err(|e| failed(&e))?;
+        if !status.is_success() {
+            return Err(failed(&format!("{}: {}", status, text)));
+        }
+        if text.is_empty() {
+            return Ok(Value::Null);
+        }
+        Ok(serde_json::from_str(&text)?)
+    }
+}
+
+#[async_trait]
+impl JobQueue for SqsJobQueue {
+    async fn send(&self, job: &GenerationJob) -> Result<(), ServiceError> {
+        let body = serde_json::to_string(job)?;
+        self.call("SendMessage", json!({ "MessageBody": body })).await?;
+        Ok(())
+    }
+
+    async fn receive(&self, max: usize) -> Result<Vec<QueuedJob>, ServiceError> {
+        let output = self
+            .call(
+                "ReceiveMessage",
+                json!({
+                    "MaxNumberOfMessages": max.clamp(1, MAX_RECEIVE),
+                    "WaitTimeSeconds": RECEIVE_WAIT.as_secs(),
+                }),
+            )
+            .await?;
+        let output: ReceiveMessageOutput = serde_json::from_value(output)?;
+
+        let mut jobs = Vec::new();
+        for message in output.messages {
+            match serde_json::from_str(&message.body) {
+                Ok(job) => jobs.push(QueuedJob { job, receipt: message.receipt_handle }),
+                Err(e) => {
+                    // A malformed job will never succeed; drop it rather than redeliver it
+                    warn!("Dropping malformed generation job {}: {}", message.message_id, e);
+                    self.delete(&message.receipt_handle).await?;
+                }
+            }
+        }
+        Ok(jobs)
+    }
+
+    async fn delete(&self, receipt: &str) -> Result<(), ServiceError> {
+        self.call("DeleteMessage", json!({ "ReceiptHandle": receipt })).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,314 @@
+use axum::{
+    extract::{Query, State},
+    response::Response,
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::time::Instant;
+use tracing::warn;
+
+use crate::{
+    ServiceError,
+    content::{ContentParams, ContentSchema, ContentTypeDescriptor},
+    keyvalue::KeyValueStore,
+    problem::ErrorResponse,
+    reading::ReadingQuery,
+    session::Session,
+    state::AppState,
+    storage::ObjectStore,
+    validation::{BannedWords, ContentValidator, MinItems, NoEmptyFields},
+    websocket::{Message, WebSocket, WebSocketUpgrade},
+};
+
+/// Storage prefix and registry identifier for quiz show questions
+pub const QUIZ_PREFIX: &str = "quiz";
+
+/// Grade used when the request doesn't specify one
+const DEFAULT_GRADE: u8 = 3;
+
+/// How long players have to answer each question
+const QUESTION_TIME_LIMIT: Duration = Duration::from_secs(20);
+
+/// Points for a correct answer given instantly; one given just before time runs
+/// out earns half
+const MAX_POINTS: u32 = 1000;
+
+/// Query parameters accepted by the quiz endpoint
+#[derive(Debug, Deserialize, Default)]
+pub struct QuizQuery {
+    /// Grade level the questions should target
+    pub grade: Option<u8>,
+
+    /// Subject of the questions (e.g., "space")
+    pub topic: Option<String>,
+}
+
+impl QuizQuery {
+    /// Validates the query and converts it into content parameters, accepting the
+    /// same grades and topics as reading passages
+    pub fn into_params(self) -> Result<ContentParams, ServiceError> {
+        let grade = Some(self.grade.unwrap_or(DEFAULT_GRADE));
+        ReadingQuery { grade, topic: self.topic, ..Default::default() }.into_params()
+    }
+}
+
+/// A multiple-choice question
+#[derive(Serialize, Deserialize, Clone, JsonSchema)]
+pub struct QuizQuestion {
+    pub question: String,
+    pub choices: Vec<String>,
+
+    /// The correct choice, exactly as written in `choices`
+    pub answer: String,
+}
+
+impl QuizQuestion {
+    /// Index of the correct choice
+    fn answer_index(&self) -> Option<usize> {
+        self.choices.iter().position(|choice| *choice == self.answer)
+    }
+}
+
+/// A round of multiple-choice questions for the quiz show
+#[derive(Serialize, Deserialize, Clone, JsonSchema)]
+pub struct QuizContents {
+    pub title: String,
+    pub questions: Vec<QuizQuestion>,
+}
+
+/// Requires every question to have 3 or 4 distinct choices, one of which is its
+/// answer
+#[derive(Debug, Clone, Default)]
+pub struct AnswersAmongChoices;
+
+impl ContentValidator for AnswersAmongChoices {
+    fn validate(&self, content: &Value, _params: &ContentParams) -> Result<(), String> {
+        let questions: Vec<QuizQuestion> = content
+            .get("questions")
+            .cloned()
+            .and_then(|questions| serde_json::from_value(questions).ok())
+            .ok_or_else(|| "missing array field \"questions\"".to_string())?;
+
+        for (i, question) in questions.iter().enumerate() {
+            let mut choices = question.choices.clone();
+            choices.sort();
+            choices.dedup();
+            if !(3..=4).contains(&question.choices.len()) || choices.len() != question.choices.len() {
+                return Err(format!("question {} must have 3 or 4 distinct choices", i + 1));
+            }
+            if question.answer_index().is_none() {
+                return Err(format!("question {} has an answer that isn't one of its choices", i + 1));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the content type descriptor for quiz show questions
+pub fn descriptor() -> ContentTypeDescriptor {
+    ContentTypeDescriptor::new(
+        QUIZ_PREFIX,
+        "quiz_show",
+        ContentSchema::for_type::<QuizContents>(
+            "QuizContents",
+            "A round of multiple-choice questions for a classroom quiz show",
+        ),
+    )
+    .with_validator(MinItems::new("questions", 5))
+    .with_validator(AnswersAmongChoices)
+    .with_validator(NoEmptyFields)
+    .with_validator(BannedWords::default())
+    .with_default_params(ContentParams::new().with("grade", DEFAULT_GRADE))
+}
+
+/// Points for an answer: none if it's wrong or late, otherwise more the faster
+/// it was given
+pub fn points(correct: bool, elapsed: Duration) -> u32 {
+    if !correct || elapsed > QUESTION_TIME_LIMIT {
+        return 0;
+    }
+    let remaining = 1.0 - elapsed.as_secs_f64() / QUESTION_TIME_LIMIT.as_secs_f64();
+    (MAX_POINTS as f64 * (0.5 + 0.5 * remaining)).round() as u32
+}
+
+/// Message sent to the player
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ServerMessage<'a> {
+    /// The next question; its answer is only sent in the result
+    Question {
+        index: usize,
+        total: usize,
+        question: &'a str,
+        choices: &'a [String],
+        time_limit_secs: u64,
+    },
+
+    /// How the player did on a question, and their running score
+    Result {
+        index: usize,
+        correct: bool,
+        answer: Option<usize>,
+        points: u32,
+        score: u32,
+    },
+
+    Finished {
+        score: u32,
+        correct: usize,
+        total: usize,
+    },
+
+    Error {
+        code: &'static str,
+        detail: String,
+    },
+}
+
+/// Message sent by the player: the index of their choice for a question
+#[derive(Debug, Deserialize)]
+struct Answer {
+    index: usize,
+    choice: usize,
+}
+
+async fn send<IO: AsyncRead + AsyncWrite + Unpin>(
+    socket: &mut WebSocket<IO>,
+    message: &ServerMessage<'_>,
+) -> Result<(), ServiceError> {
+    socket.send_text(&serde_json::to_string(message)?).await
+}
+
+/// Asks each question in turn, timing and scoring the answers
+///
+/// Answers to earlier questions are ignored, and questions left unanswered when
+/// time runs out score nothing. Closes the connection when the quiz is over, or
+/// returns early if the player leaves.
+async fn run_quiz<IO: AsyncRead + AsyncWrite + Unpin>(
+    mut socket: WebSocket<IO>,
+    quiz: &QuizContents,
+) -> Result<(), ServiceError> {
+    let total = quiz.questions.len();
+    let (mut score, mut correct_count) = (0, 0);
+
+    for (index, question) in quiz.questions.iter().enumerate() {
+        let asked = Instant::now();
+        send(
+            &mut socket,
+            &ServerMessage::Question {
+                index,
+                total,
+                question: &question.question,
+                choices: &question.choices,
+                time_limit_secs: QUESTION_TIME_LIMIT.as_secs(),
+            },
+        )
+        .await?;
+
+        let choice = loop {
+            let received = match tokio::time::timeout_at(asked + QUESTION_TIME_LIMIT, socket.recv()).await {
+                Ok(received) => received?,
+                Err(_) => break None,
+            };
+            let Some(message) = received else {
+                return Ok(());
+            };
+            let Message::Text(text) = message else {
+                continue;
+            };
+            match serde_json::from_str::<Answer>(&text) {
+                Ok(answer) if answer.index == index => break Some(answer.choice),
+                Ok(_) => {}
+                Err(e) => {
+                    let detail = format!("Answers look like {{\"index\": 0, \"choice\": 2}}: {}", e);
+                    send(&mut socket, &ServerMessage::Error { code: "invalid_input", detail }).await?;
+                }
+            }
+        };
+
+        let answer = question.answer_index();
+        let correct = choice.is_some() && choice == answer;
+        let points = points(correct, asked.elapsed());
+        score += points;
+        correct_count += usize::from(correct);
+        send(&mut socket, &ServerMessage::Result { index, correct, answer, points, score }).await?;
+    }
+
+    send(&mut socket, &ServerMessage::Finished { score, correct: correct_count, total }).await?;
+    socket.close().await
+}
+
+/// Plays a timed quiz show over a WebSocket
+///
+/// The server sends `question` messages one at a time; the player answers each
+/// with `{"index": 0, "choice": 2}` and gets back a `result` with the correct
+/// choice, the points earned (more for faster answers), and the running score.
+/// A `finished` message ends the quiz. Rounds are cached like other content, so a
+/// session isn't given a round it has already played while others are cached.
+pub async fn quiz_socket<S, K>(
+    State(state): State<AppState<S, K>>,
+    Query(query): Query<QuizQuery>,
+    session: Session,
+    upgrade: WebSocketUpgrade,
+) -> Result<Response, ErrorResponse>
+where
+    S: ObjectStore,
+    K: KeyValueStore + 'static,
+{
+    let params = query.into_params()?;
+    let session_id = session.id().to_string();
+
+    Ok(upgrade.on_upgrade(move |mut socket| async move {
+        let quiz = async {
+            let descriptor = state
+                .content_types
+                .get(QUIZ_PREFIX)
+                .ok_or_else(|| ServiceError::ConfigError(QUIZ_PREFIX.into()))?;
+            state
+                .get_or_generate::<QuizContents>(descriptor, &params, Some(&session_id))
+                .await
+        };
+
+        let played = match quiz.await {
+            Ok(quiz) => run_quiz(socket, &quiz).await,
+            Err(e) => {
+                let response = ErrorResponse::from(e);
+                let message = ServerMessage::Error { code: response.code, detail: response.detail };
+                match send(&mut socket, &message).await {
+                    Ok(()) => socket.close().await,
+                    Err(e) => Err(e),
+                }
+            }
+        };
+        if let Err(e) = played {
+            warn!("Quiz connection ended with an error: {}", e);
+        }
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn scores_faster_answers_higher() {
+        assert_eq!(points(true, Duration::ZERO), MAX_POINTS);
+        assert_eq!(points(true, QUESTION_TIME_LIMIT / 2), 750);
+        assert_eq!(points(true, QUESTION_TIME_LIMIT), MAX_POINTS / 2);
+        assert_eq!(points(true, QUESTION_TIME_LIMIT * 2), 0);
+        assert_eq!(points(false, Duration::ZERO), 0);
+
+        let params = ContentParams::new();
+        let question = |choices: &[&str], answer: &str| json!({ "question": "q", "choices": choices, "answer": answer });
+        let quiz = |question| json!({ "questions": [question] });
+        assert!(AnswersAmongChoices.validate(&quiz(question(&["a", "b", "c"], "b")), &params).is_ok());
+        assert!(AnswersAmongChoices.validate(&quiz(question(&["a", "b", "c"], "d")), &params).is_err());
+        assert!(AnswersAmongChoices.validate(&quiz(question(&["a", "a", "c"], "a")), &params).is_err());
+        assert!(AnswersAmongChoices.validate(&quiz(question(&["a", "b"], "a")), &params).is_err());
+    }
+}
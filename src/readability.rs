@@ -0,0 +1,204 @@
+use serde_json::Value;
+
+use crate::{
+    content::{ContentAnnotator, ContentParams},
+    validation::ContentValidator,
+};
+
+/// Flesch-Kincaid grade level of a text
+///
+/// Very simple text can score below zero, so scores are clamped at 0.0. Returns 0.0
+/// for text without any words or sentences.
+pub fn flesch_kincaid_grade(text: &str) -> f64 {
+    let words: Vec<&str> = text
+        .split_whitespace()
+        .filter(|w| w.chars().any(char::is_alphabetic))
+        .collect();
+    let sentences = text
+        .split(['.', '!', '?'])
+        .filter(|s| s.chars().any(char::is_alphabetic))
+        .count();
+
+    if words.is_empty() || sentences == 0 {
+        return 0.0;
+    }
+
+    let syllables: usize = words.iter().map(|w| count_syllables(w)).sum();
+    let words_per_sentence = words.len() as f64 / sentences as f64;
+    let syllables_per_word = syllables as f64 / words.len() as f64;
+
+    (0.39 * words_per_sentence + 11.8 * syllables_per_word - 15.59).max(0.0)
+}
+
+/// Estimates the number of syllables in a word by counting vowel groups
+fn count_syllables(word: &str) -> usize {
+    let word: Vec<char> = word
+        .chars()
+        .filter(char::is_ascii_alphabetic)
+        .map(|c| c.to_ascii_lowercase())
+        .collect();
+
+    let is_vowel = |c: char| "aeiouy".contains(c);
+    let mut count = 0;
+    let mut previous_vowel = false;
+    for &c in &word {
+        let vowel = is_vowel(c);
+        if vowel && !previous_vowel {
+            count += 1;
+        }
+        previous_vowel = vowel;
+    }
+
+    // A trailing silent "e" usually doesn't add a syllable ("make"), unless it
+    // follows a consonant + "l" ("little")
+    if let [.., a, b, 'e'] = word.as_slice()
+        && !is_vowel(*b)
+        && (*b != 'l' || is_vowel(*a))
+        && count > 1
+    {
+        count -= 1;
+    }
+
+    count.max(1)
+}
+
+/// Rounds a score to one decimal place for display
+fn round_score(score: f64) -> f64 {
+    (score * 10.0).round() / 10.0
+}
+
+/// Requires a text field's Flesch-Kincaid grade to be near the requested grade
+///
+/// The target grade is read from the `grade` parameter; content generated without
+/// one isn't checked.
+#[derive(Debug, Clone)]
+pub struct ReadingLevel {
+    pub field: String,
+
+    /// How far below the requested grade the text may score
+    pub below: f64,
+
+    /// How far above the requested grade the text may score
+    pub above: f64,
+}
+
+impl ReadingLevel {
+    pub fn new(field: &str, below: f64, above: f64) -> Self {
+        Self {
+            field: field.to_string(),
+            below,
+            above,
+        }
+    }
+}
+
+impl ContentValidator for ReadingLevel {
+    fn validate(&self, content: &Value, params: &ContentParams) -> Result<(), String> {
+        let Some(grade) = params
+            .variables()
+            .get("grade")
+            .and_then(|g| g.parse::<f64>().ok())
+        else {
+            return Ok(());
+        };
+
+        let text = content
+            .get(&self.field)
+            .and_then(Value::as_str)
+            .ok_or_else(|| format!("missing text field {:?}", self.field))?;
+
+        let score = flesch_kincaid_grade(text);
+        if score < grade - self.below || score > grade + self.above {
+            return Err(format!(
+                "{:?} reads at grade {:.1}, expected {:.1}-{:.1}",
+                self.field,
+                score,
+                grade - self.below,
+                grade + self.above
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// Stores a text field's Flesch-Kincaid grade in the content under `target`
+#[derive(Debug, Clone)]
+pub struct ReadabilityScore {
+    pub field: String,
+    pub target: String,
+}
+
+impl ReadabilityScore {
+    pub fn new(field: &str, target: &str) -> Self {
+        Self {
+            field: field.to_string(),
+            target: target.to_string(),
+        }
+    }
+}
+
+impl ContentAnnotator for ReadabilityScore {
+    fn annotate(&self, content: &mut Value, _params: &ContentParams) {
+        let Some(text) = content.get(&self.field).and_then(Value::as_str) else {
+            return;
+        };
+
+        let score = round_score(flesch_kincaid_grade(text));
+        if let Value::Object(map) = content {
+            map.insert(self.target.clone(), Value::from(score));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_count_syllables() {
+        assert_eq!(count_syllables("cat"), 1);
+        assert_eq!(count_syllables("make"), 1);
+        assert_eq!(count_syllables("little"), 2);
+        assert_eq!(count_syllables("elephant"), 3);
+        assert_eq!(count_syllables("the"), 1);
+    }
+
+    #[test]
+    fn test_simple_text_scores_lower() {
+        let simple = "The cat sat. The dog ran. We had fun.";
+        let complex = "Photosynthesis enables vegetation to transform electromagnetic \
+                       radiation into chemical energy, sustaining ecological communities.";
+
+        assert!(flesch_kincaid_grade(simple) < 2.0);
+        assert!(flesch_kincaid_grade(complex) > 12.0);
+    }
+
+    #[test]
+    fn test_reading_level_uses_grade_param() {
+        let validator = ReadingLevel::new("story", 2.0, 2.0);
+        let content = json!({"story": "The cat sat. The dog ran. We had fun."});
+
+        assert!(validator.validate(&content, &ContentParams::new()).is_ok());
+        assert!(
+            validator
+                .validate(&content, &ContentParams::new().with("grade", 1))
+                .is_ok()
+        );
+        assert!(
+            validator
+                .validate(&content, &ContentParams::new().with("grade", 8))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_readability_score_annotation() {
+        let mut content = json!({"story": "The cat sat. The dog ran."});
+        ReadabilityScore::new("story", "readability_grade")
+            .annotate(&mut content, &ContentParams::new());
+
+        assert!(content["readability_grade"].is_f64());
+    }
+}
@@ -0,0 +1,155 @@
+use axum::{
+    body::Body,
+    extract::{Query, State},
+    http::{StatusCode, header},
+    response::Response,
+};
+use chrono::{NaiveDate, Utc};
+use futures::{StreamExt, stream};
+use schemars::JsonSchema;
+use serde::Deserialize;
+use std::convert::Infallible;
+use tracing::{error, warn};
+
+use super::{MAX_GRADE, MIN_GRADE, READING_PREFIX, ReadingContents, paragraphs};
+use crate::{
+    ServiceError,
+    epub::EpubWriter,
+    flags,
+    keyvalue::KeyValueStore,
+    problem::ErrorResponse,
+    state::AppState,
+    storage::{ObjectStore, StoredObject},
+};
+
+/// Most stories bundled into one book
+const MAX_EXPORT_STORIES: usize = 50;
+
+/// Query parameters accepted by the export endpoint
+#[derive(Debug, Deserialize, Default, JsonSchema)]
+pub struct ExportQuery {
+    /// Day whose stories are bundled, as YYYY-MM-DD; today (UTC) by default
+    pub date: Option<String>,
+
+    /// Only bundle stories for this grade
+    pub grade: Option<u8>,
+}
+
+/// Whether a cached object was stored in a window on `date`
+///
+/// Cached object keys look like `{prefix}/{partition}{window}/{guid}.json`, with
+/// hourly windows like "2025-10-11-14" and daily ones like "2025-10-11".
+fn stored_on(key: &str, date: &str) -> bool {
+    key.rsplit('/')
+        .nth(1)
+        .is_some_and(|window| window == date || window.strip_prefix(date).is_some_and(|hour| hour.starts_with('-')))
+}
+
+/// Loads the next story that can still be read, skipping any removed since the
+/// stories were listed
+async fn next_story<S: ObjectStore>(
+    object_store: &S,
+    objects: &mut std::vec::IntoIter<StoredObject>,
+) -> Option<ReadingContents> {
+    for object in objects {
+        let story = match object_store.get_object(&object.key).await {
+            Ok(data) => serde_json::from_slice(&data).map_err(ServiceError::from),
+            Err(e) => Err(e),
+        };
+        match story {
+            Ok(story) => return Some(story),
+            Err(e) => warn!("Skipping {} in export: {}", object.key, e),
+        }
+    }
+    None
+}
+
+/// Bundles a day's stories into an EPUB book for reading on e-readers
+///
+/// The book is written as it is sent, one story per chapter, oldest first.
+pub async fn reading_export<S: ObjectStore, K: KeyValueStore>(
+    State(state): State<AppState<S, K>>,
+    Query(query): Query<ExportQuery>,
+) -> Result<Response, ErrorResponse> {
+    let date = match query.date {
+        Some(date) => NaiveDate::parse_from_str(&date, "%Y-%m-%d")
+            .map_err(|_| ServiceError::InvalidInput("date must look like 2025-10-11".to_string()))?,
+        None => Utc::now().date_naive(),
+    };
+    let date = date.format("%Y-%m-%d").to_string();
+    let prefix = match query.grade {
+        Some(grade) if !(MIN_GRADE..=MAX_GRADE).contains(&grade) => {
+            return Err(ServiceError::InvalidInput(format!(
+                "grade must be between {} and {}",
+                MIN_GRADE, MAX_GRADE
+            ))
+            .into());
+        }
+        Some(grade) => format!("{}/grade-{}/", READING_PREFIX, grade),
+        None => format!("{}/", READING_PREFIX),
+    };
+
+    let mut objects: Vec<StoredObject> = state
+        .object_store
+        .list_objects(&prefix)
+        .await?
+        .into_iter()
+        .filter(|object| object.key.ends_with(".json") && stored_on(&object.key, &date))
+        .collect();
+    let quarantined = flags::quarantined(&state.kv_store, &objects).await?;
+    objects.retain(|object| !quarantined.contains(&object.key));
+    if objects.is_empty() {
+        return Err(ServiceError::NotFound(format!("No stories from {}", date)).into());
+    }
+    objects.sort_by(|a, b| a.last_modified.cmp(&b.last_modified).then_with(|| a.key.cmp(&b.key)));
+    objects.truncate(MAX_EXPORT_STORIES);
+
+    let mut book = EpubWriter::new(
+        &format!("Thinkaroo Stories for {}", date),
+        &format!("urn:thinkaroo:{}:{}", prefix.trim_end_matches('/'), date),
+    );
+    let start = book.start();
+    let parts = stream::once(async { start }).chain(stream::unfold(
+        (state.object_store.clone(), objects.into_iter(), Some(book)),
+        |(object_store, mut objects, book)| async move {
+            let mut book = book?;
+            match next_story(&object_store, &mut objects).await {
+                Some(story) => {
+                    let chapter = book.chapter(&story.title, &paragraphs(&story.story, true));
+                    Some((chapter, (object_store, objects, Some(book))))
+                }
+                None => Some((book.finish(), (object_store, objects, None))),
+            }
+        },
+    ));
+
+    Response::builder()
+        .header(header::CONTENT_TYPE, "application/epub+zip")
+        .header(
+            header::CONTENT_DISPOSITION,
+            format!("attachment; filename=\"stories-{}.epub\"", date),
+        )
+        .body(Body::from_stream(parts.map(Ok::<_, Infallible>)))
+        .map_err(|e| {
+            error!("Failed to build export response for {}: {}", date, e);
+            ErrorResponse::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "internal_error",
+                "Internal server error",
+            )
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_hourly_and_daily_windows_on_the_date() {
+        assert!(stored_on("reading/grade-3/2025-10-11-14/a.json", "2025-10-11"));
+        assert!(stored_on("reading/grade-3/topic-space/2025-10-11/a.json", "2025-10-11"));
+        assert!(!stored_on("reading/grade-3/2025-10-12-00/a.json", "2025-10-11"));
+        assert!(!stored_on("reading/grade-3/2025-10-110/a.json", "2025-10-11"));
+        assert!(!stored_on("a.json", "2025-10-11"));
+    }
+}
@@ -0,0 +1,223 @@
+use axum::{
+    Json,
+    extract::{Path, Query, State},
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tracing::warn;
+
+use super::{DEFAULT_GRADE, ReadingContents, series::grade_of, stored_story};
+use crate::{
+    ServiceError,
+    content::{ContentParams, ContentSchema, ContentTypeDescriptor},
+    keyvalue::{Column, KeyValueStore, RecordQuery, column_json, encode_json},
+    metrics,
+    problem::ErrorResponse,
+    state::AppState,
+    storage::ObjectStore,
+    validation::{BannedWords, ContentValidator, MinItems, NoEmptyFields},
+};
+
+/// Name of the prompt hints are generated with
+const HINT_PROMPT: &str = "reading_hint";
+
+/// Column of a hint record holding its JSON
+const HINT_COLUMN: &str = "hint";
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct HintQuery {
+    /// Number of the question to hint at, from 1
+    pub question: usize,
+}
+
+/// A hint that helps a reader answer a question without giving the answer away
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct ReadingHint {
+    /// Number of the question the hint is for, from 1
+    #[serde(default)]
+    #[schemars(skip_deserializing)]
+    pub question: usize,
+
+    /// A nudge toward the answer, such as where in the story to look
+    pub hint: String,
+
+    /// Smaller steps to take in turn if the hint isn't enough, each giving more help
+    pub steps: Vec<String>,
+}
+
+/// Rejects hints that state the sample answer, read from the `answer` context
+///
+/// Hints for questions without a sample answer can't be checked; `reading_hint`
+/// logs and counts them.
+#[derive(Debug, Clone, Default)]
+pub struct AnswerWithheld;
+
+/// Lowercases text and collapses its whitespace, for comparing answers
+fn normalized(text: &str) -> String {
+    text.to_lowercase().split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+impl ContentValidator for AnswerWithheld {
+    fn validate(&self, content: &Value, params: &ContentParams) -> Result<(), String> {
+        let Some(answer) = params.context().get("answer").map(|answer| normalized(answer)) else {
+            return Ok(());
+        };
+        let answer = answer.trim_end_matches('.');
+        if answer.is_empty() {
+            return Ok(());
+        }
+
+        let hint: ReadingHint =
+            serde_json::from_value(content.clone()).map_err(|e| format!("malformed hint: {}", e))?;
+        let gives_away = std::iter::once(&hint.hint)
+            .chain(&hint.steps)
+            .any(|text| normalized(text).contains(answer));
+        if gives_away {
+            return Err("hint gives away the answer".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Returns the descriptor hints are generated with
+///
+/// Hints aren't a registered content type: they're only made for a particular
+/// question, so there's nothing to warm or serve at random.
+fn descriptor() -> ContentTypeDescriptor {
+    ContentTypeDescriptor::new(
+        HINT_PROMPT,
+        HINT_PROMPT,
+        ContentSchema::for_type::<ReadingHint>(
+            "ReadingHint",
+            "A hint toward the answer to a reading question, in steps",
+        ),
+    )
+    .with_validator(MinItems::new("steps", 2))
+    .with_validator(AnswerWithheld)
+    .with_validator(NoEmptyFields)
+    .with_validator(BannedWords::default())
+}
+
+/// Parameters for a hint at question `number` of a story stored at `key`, with
+/// its sample answer when the story has one for every question
+fn hint_params(key: &str, story: &ReadingContents, number: usize) -> ContentParams {
+    let mut params = ContentParams::new()
+        .with("grade", grade_of(key).unwrap_or(DEFAULT_GRADE))
+        .with_context("title", &story.title)
+        .with_context("story", &story.story)
+        .with_context("question", &story.questions[number - 1]);
+    // A mismatched list can't be matched up with the questions
+    if story.answers.len() == story.questions.len() {
+        params = params.with_context("answer", &story.answers[number - 1]);
+    }
+    params
+}
+
+/// Partition key of a story's hint records
+fn hints_key(story_id: &str) -> String {
+    format!("hints#{}", story_id)
+}
+
+/// Serves a hint for a question about a stored story, generating it the first
+/// time anyone asks
+///
+/// Hints are kept by story and question, so every later request for the same one
+/// is served without generating.
+pub async fn reading_hint<S: ObjectStore, K: KeyValueStore>(
+    State(state): State<AppState<S, K>>,
+    Path(story_id): Path<String>,
+    Query(query): Query<HintQuery>,
+) -> Result<Json<ReadingHint>, ErrorResponse> {
+    let (key, story) = stored_story(&state, &story_id).await?;
+    let number = query.question;
+    if !(1..=story.questions.len()).contains(&number) {
+        return Err(ServiceError::InvalidInput(format!(
+            "question must be between 1 and {}",
+            story.questions.len()
+        ))
+        .into());
+    }
+
+    let partition = hints_key(&story_id);
+    let sort_key = number.to_string();
+    let records = state
+        .kv_store
+        .query_records(
+            RecordQuery::new(partition.clone()).between(sort_key.clone(), sort_key.clone()),
+            vec![HINT_COLUMN.to_string()],
+        )
+        .await?;
+    if let Some(record) = records.first()
+        && let Some(hint) = column_json::<ReadingHint>(&record.columns, HINT_COLUMN)?
+    {
+        metrics::increment("hints.cached");
+        return Ok(Json(hint));
+    }
+
+    let params = hint_params(&key, &story, number);
+    if !params.context().contains_key("answer") {
+        // Stories generated before worksheets have no answers to withhold
+        metrics::increment("hints.unchecked");
+        warn!("Story {} has no sample answers, so its hints can't be checked", story_id);
+    }
+    let mut hint: ReadingHint = state.generate_uncached(&descriptor(), &params).await?;
+    hint.question = number;
+    state
+        .kv_store
+        .put_record(partition, sort_key, vec![Column::new(HINT_COLUMN.to_string(), encode_json(&hint)?)], None)
+        .await?;
+    metrics::increment("hints.generated");
+    Ok(Json(hint))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn rejects_hints_that_give_the_answer_away() {
+        let story: ReadingContents = serde_json::from_value(json!({
+            "title": "Moon Cat",
+            "story": "A cat went to the moon to find cheese.",
+            "questions": ["Why did the cat go to the moon?"],
+            "answers": ["To find cheese."],
+        }))
+        .unwrap();
+        let params = hint_params("reading/grade-2/2025-10-11-14/abc.json", &story, 1);
+        assert_eq!(params.variables()["grade"], "2");
+
+        let hint = |steps: &[&str]| json!({ "hint": "Look at the end of the story.", "steps": steps });
+        assert!(AnswerWithheld.validate(&hint(&["What does the cat want?", "Find it."]), &params).is_ok());
+        assert!(AnswerWithheld.validate(&hint(&["It went  to find Cheese"]), &params).is_err());
+        assert!(AnswerWithheld.validate(&hint(&["To find cheese"]), &ContentParams::new()).is_ok());
+    }
+
+    #[tokio::test]
+    async fn withholds_the_answers_of_stored_stories() {
+        let giveaway = json!({
+            "hint": "Think about the pond.",
+            "steps": ["It's in a tree by the pond.", "Look up."],
+        });
+        let fair = json!({
+            "hint": "Think about where Lily saw it.",
+            "steps": ["Reread what she says.", "Look up."],
+        });
+        let (state, id) = super::super::tests::state_with_story(vec![
+            giveaway.clone(),
+            giveaway.clone(),
+            giveaway,
+            fair.clone(),
+        ])
+        .await;
+
+        let hint = |question| {
+            reading_hint(State(state.clone()), Path(id.clone()), Query(HintQuery { question }))
+        };
+        assert!(hint(3).await.is_err());
+        let Json(accepted) = hint(3).await.unwrap();
+        assert_eq!(accepted.hint, fair["hint"]);
+        assert_eq!(accepted.question, 3);
+    }
+}
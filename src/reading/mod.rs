@@ -1,50 +1,481 @@
-use axum::{extract::State, Json};
+mod export;
+mod hint;
+mod page;
+mod personalized;
+mod questions;
+mod series;
+mod size;
+mod stream;
+mod worksheet;
+
+pub use export::{ExportQuery, reading_export};
+pub use hint::{HintQuery, ReadingHint, reading_hint};
+pub use page::reading_page;
+pub use personalized::reading_personalized;
+pub use questions::{QuestionDifficulty, QuestionType};
+pub use series::reading_continue;
+pub use size::StoryLength;
+pub use stream::reading_contents_stream;
+pub use worksheet::reading_worksheet;
+
+use axum::{
+    extract::{Query, State},
+    Json,
+};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::{
+    content::{self, ContentParams, ContentSchema, ContentTypeDescriptor},
+    difficulty, flags,
+    keyvalue::KeyValueStore,
+    problem::ErrorResponse,
+    progress::Skill,
+    readability::{ReadabilityScore, ReadingLevel},
+    session::Session,
+    users::CurrentUser,
+    state::AppState,
+    storage::ObjectStore,
+    validation::{BannedWords, MinItems, NoEmptyFields, WordCount},
+    ServiceError,
+};
+use questions::QuestionMix;
+use series::SeriesLink;
+use size::{MAX_QUESTIONS, MIN_QUESTIONS, RequestedSize};
+
+/// Storage prefix and registry identifier for reading content
+pub const READING_PREFIX: &str = "reading";
+
+/// Grade used when the request doesn't specify one
+const DEFAULT_GRADE: u8 = 3;
+
+/// Lowest supported grade level
+pub const MIN_GRADE: u8 = 1;
+
+/// Highest supported grade level
+pub const MAX_GRADE: u8 = 8;
+
+/// Maximum length of a topic, in characters
+const MAX_TOPIC_LEN: usize = 40;
+
+/// Query parameters accepted by the reading contents endpoint
+#[derive(Debug, Deserialize, Default, JsonSchema)]
+pub struct ReadingQuery {
+    /// Grade level the passage should target
+    #[serde(alias = "reading_level")]
+    pub grade: Option<u8>,
+
+    /// Subject the story should be about (e.g., "dinosaurs")
+    pub topic: Option<String>,
+
+    /// Only serve cached stories whose questions practice this skill
+    pub skill: Option<ReadingSkill>,
+
+    /// Only serve cached stories with a question of this type
+    pub question_type: Option<QuestionType>,
+
+    /// Only serve cached stories with a question of this difficulty
+    pub difficulty: Option<QuestionDifficulty>,
+
+    /// Number of questions the story should have, from 3 to 8
+    pub questions: Option<usize>,
+
+    /// How long the story should be
+    pub length: Option<StoryLength>,
+}
+
+impl ReadingQuery {
+    /// Validates the query and converts it into content parameters
+    pub fn into_params(self) -> Result<ContentParams, ServiceError> {
+        let grade = self.grade.unwrap_or(DEFAULT_GRADE);
+        if !(MIN_GRADE..=MAX_GRADE).contains(&grade) {
+            return Err(ServiceError::InvalidInput(format!(
+                "grade must be between {} and {}",
+                MIN_GRADE, MAX_GRADE
+            )));
+        }
 
-use crate::{keyvalue::KeyValueStore, prompts, state::{AppState, ContentType}, storage::ObjectStore, ServiceError};
+        let mut params = ContentParams::new().with("grade", grade);
+
+        if let Some(topic) = self.topic.map(|t| t.trim().to_lowercase()).filter(|t| !t.is_empty()) {
+            let valid_chars = topic
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == ' ' || c == '-');
+            if topic.len() > MAX_TOPIC_LEN || !valid_chars {
+                return Err(ServiceError::InvalidInput(format!(
+                    "topic must be at most {} letters, digits, spaces, or hyphens",
+                    MAX_TOPIC_LEN
+                )));
+            }
+
+            params = params.with("topic", topic);
+        }
+
+        // Both partition the cache, so a story is only served to requests for its size
+        if let Some(count) = self.questions {
+            if !(MIN_QUESTIONS..=MAX_QUESTIONS).contains(&count) {
+                return Err(ServiceError::InvalidInput(format!(
+                    "questions must be between {} and {}",
+                    MIN_QUESTIONS, MAX_QUESTIONS
+                )));
+            }
+            params = params.with("question_count", count);
+        }
+        if let Some(length) = self.length {
+            let (min, max) = length.words();
+            params = params
+                .with("length", length.as_str())
+                .with_context("word_count", format!("{}-{}", min, max));
+        }
+
+        Ok(params)
+    }
+
+    /// Tags a cached story must have to be served, as (field, value) pairs
+    pub fn tags(&self) -> Vec<(&'static str, &'static str)> {
+        let mut tags = Vec::new();
+        if let Some(skill) = self.skill {
+            tags.push(("skills", skill.as_str()));
+        }
+        if let Some(question_type) = self.question_type {
+            tags.push(("question_types", question_type.as_str()));
+        }
+        if let Some(difficulty) = self.difficulty {
+            tags.push(("difficulties", difficulty.as_str()));
+        }
+        tags
+    }
+}
+
+/// Comprehension skill a question practices
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ReadingSkill {
+    MainIdea,
+    Inference,
+    Sequencing,
+}
+
+impl ReadingSkill {
+    /// Name used in JSON and tags
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ReadingSkill::MainIdea => "main_idea",
+            ReadingSkill::Inference => "inference",
+            ReadingSkill::Sequencing => "sequencing",
+        }
+    }
+}
 
 #[derive(Serialize, Deserialize, Clone, JsonSchema)]
 pub struct ReadingContents {
+    // Fields the service sets skip deserializing for schemars only, so they're left
+    // out of the schema the model fills in but not out of the API's
+
+    /// ID of the stored story, used to fetch its narration; absent for stories
+    /// generated before IDs were added
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schemars(skip_deserializing)]
+    pub id: Option<String>,
+
     pub title: String,
     pub story: String,
     pub questions: Vec<String>,
+
+    /// A sample answer to each question, printed on worksheet answer keys; stored
+    /// with the story but kept out of responses so readers don't see them, and
+    /// absent for stories generated before worksheets
+    #[serde(default, skip_serializing)]
+    pub answers: Vec<String>,
+
+    /// Subjects of the story, e.g. "volcanoes"; empty for stories generated before
+    /// tagging
+    #[serde(default)]
+    pub topics: Vec<String>,
+
+    /// Skills the questions practice
+    #[serde(default)]
+    pub skills: Vec<ReadingSkill>,
+
+    /// Type of each question, in the same order; empty for stories generated
+    /// before question types
+    #[serde(default)]
+    pub question_types: Vec<QuestionType>,
+
+    /// Difficulty of each question, in the same order; empty for stories generated
+    /// before question types
+    #[serde(default)]
+    pub difficulties: Vec<QuestionDifficulty>,
+
+    /// Flesch-Kincaid grade level of the story, computed after generation
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schemars(skip_deserializing)]
+    pub readability_grade: Option<f64>,
+
+    /// Key of the story's illustration, served from `/images/{key}`; absent when
+    /// illustrations are disabled
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schemars(skip_deserializing)]
+    pub illustration_key: Option<String>,
+
+    /// Series the story is a sequel in, shared by every chapter after the first;
+    /// absent for stories that don't continue another
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schemars(skip_deserializing)]
+    pub series_id: Option<String>,
+
+    /// Chapter of the series the story is, from 2; absent for stories that don't
+    /// continue another
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schemars(skip_deserializing)]
+    pub chapter: Option<u32>,
+}
+
+/// Returns the content type descriptor for reading comprehension passages
+pub fn descriptor() -> ContentTypeDescriptor {
+    ContentTypeDescriptor::new(
+        READING_PREFIX,
+        "reading_comprehension",
+        ContentSchema::for_type::<ReadingContents>(
+            "ReadingContents",
+            "A reading comprehension passage with questions",
+        ),
+    )
+    .with_validator(WordCount::new("story", 100, 400))
+    .with_validator(MinItems::new("questions", MIN_QUESTIONS))
+    .with_validator(QuestionMix::default())
+    .with_validator(RequestedSize)
+    .with_validator(NoEmptyFields)
+    .with_validator(BannedWords::default())
+    .with_validator(ReadingLevel::new("story", 2.0, 2.5))
+    .with_annotator(ReadabilityScore::new("story", "readability_grade"))
+    .with_annotator(SeriesLink)
+    .with_dedup_field("story")
+    .with_tag_field("topics")
+    .with_tag_field("skills")
+    .with_tag_field("question_types")
+    .with_tag_field("difficulties")
+    .with_id_field("id")
+    .with_narration_field("story")
+    .with_illustration("story", "illustration_key")
+    .with_default_params(ContentParams::new().with("grade", DEFAULT_GRADE))
+}
+
+/// Splits a story into its paragraphs, which are separated by blank lines
+///
+/// While the story is still being written, its last paragraph may continue, so
+/// it is left out unless `complete` is set.
+fn paragraphs(story: &str, complete: bool) -> Vec<&str> {
+    let mut segments: Vec<&str> = story.split("\n\n").collect();
+    if !complete {
+        segments.pop();
+    }
+    segments.into_iter().map(str::trim).filter(|p| !p.is_empty()).collect()
+}
+
+/// Loads a stored story by its ID, the `id` field of the reading content,
+/// returning its key with it
+///
+/// Quarantined stories aren't found.
+async fn stored_story<S: ObjectStore, K: KeyValueStore>(
+    state: &AppState<S, K>,
+    story_id: &str,
+) -> Result<(String, ReadingContents), ServiceError> {
+    let not_found = || ServiceError::NotFound("No such story".to_string());
+    let object_key = content::object_key_for_id(story_id)
+        .filter(|key| key.starts_with(&format!("{}/", READING_PREFIX)))
+        .ok_or_else(not_found)?;
+    let object = state.object_store.head_object(&object_key).await?.ok_or_else(not_found)?;
+    if !flags::quarantined(&state.kv_store, std::slice::from_ref(&object)).await?.is_empty() {
+        return Err(not_found());
+    }
+
+    let data = state.object_store.get_object(&object_key).await?;
+    Ok((object_key, serde_json::from_slice(&data)?))
+}
+
+/// Converts a reading request into content parameters, with the prompt version
+/// when the reading prompt is under an experiment
+///
+/// Stories are read at the selected child's level unless the request asks for a
+/// grade: their profile's grade, adjusted for how they've been scoring.
+async fn request_params<S: ObjectStore, K: KeyValueStore>(
+    state: &AppState<S, K>,
+    descriptor: &ContentTypeDescriptor,
+    mut query: ReadingQuery,
+    user: Option<CurrentUser>,
+    session_id: Option<&str>,
+) -> Result<ContentParams, ServiceError> {
+    if let Some(profile) = user.and_then(|user| user.profile)
+        && query.grade.is_none()
+    {
+        let level = difficulty::level_for(&state.kv_store, &profile, Skill::Comprehension)
+            .await
+            .unwrap_or_else(|e| {
+                warn!("Failed to load difficulty for {}: {}", profile.id, e);
+                profile.grade
+            });
+        query.grade = Some(level);
+    }
+    let params = query.into_params()?;
+
+    state
+        .with_prompt_variant(descriptor, params, session_id)
+        .await
+}
+
+/// Picks a cached story with all of `tags`, as (field, value) pairs
+///
+/// Stories with tags are picked from those already cached, since new stories
+/// can't be made to have them.
+async fn tagged_story<S: ObjectStore, K: KeyValueStore>(
+    state: &AppState<S, K>,
+    descriptor: &ContentTypeDescriptor,
+    params: &ContentParams,
+    tags: &[(&str, &str)],
+    session_id: Option<&str>,
+) -> Result<ReadingContents, ServiceError> {
+    state
+        .get_tagged_object(descriptor, params, tags, session_id)
+        .await?
+        .ok_or_else(|| {
+            let wanted: Vec<&str> = tags.iter().map(|(_, value)| *value).collect();
+            ServiceError::NotFound(format!("No stories with {} questions yet", wanted.join(", ")))
+        })
+}
+
+/// Serves a cached story practicing the requested skill and with questions of
+/// the requested type and difficulty, or else one the session hasn't read,
+/// generating and storing a new one if needed
+///
+/// Without a session, any cached story may be served and nothing is recorded
+/// as read.
+pub async fn story<S: ObjectStore, K: KeyValueStore>(
+    state: &AppState<S, K>,
+    query: ReadingQuery,
+    user: Option<CurrentUser>,
+    session_id: Option<&str>,
+) -> Result<ReadingContents, ServiceError> {
+    let descriptor = state
+        .content_types
+        .get(READING_PREFIX)
+        .ok_or_else(|| ServiceError::ConfigError(READING_PREFIX.into()))?;
+
+    let tags = query.tags();
+    let params = request_params(state, descriptor, query, user, session_id).await?;
+
+    if !tags.is_empty() {
+        return tagged_story(state, descriptor, &params, &tags, session_id).await;
+    }
+    state.get_or_generate(descriptor, &params, session_id).await
 }
 
 pub async fn reading_contents<S: ObjectStore, K: KeyValueStore>(
     State(state): State<AppState<S, K>>,
-) -> Result<Json<ReadingContents>, (axum::http::StatusCode, String)> {
-    // Try to get an existing cached story
-    let contents = if let Some(contents) = state
-        .get_timed_object(ContentType::Reading)
+    Query(query): Query<ReadingQuery>,
+    session: Session,
+    user: Option<CurrentUser>,
+) -> Result<Json<ReadingContents>, ErrorResponse> {
+    let session_id = session.id();
+    Ok(Json(story(&state, query, user, Some(session_id)).await?))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::{
+        config::Config, content::ContentTypeRegistry, keyvalue::MemoryKeyValueStore,
+        storage::MemoryObjectStore,
+    };
+
+    /// A story as the model writes it, passing the reading validators
+    pub(super) fn generated_story() -> serde_json::Value {
+        serde_json::json!({
+            "title": "The Lost Kite",
+            "story": "Sam had a red kite. He took it to the park on a windy day. \
+                The kite went up and up into the sky. Sam held the string tight. \
+                Then a big gust came and the string slipped from his hand. \
+                The kite flew over the trees and out of sight. Sam felt sad and lonely, \
+                because he loved that beautiful kite. He sat on a wooden bench and looked \
+                up at the empty sky.\n\n\
+                A girl named Lily came by with her dog. She asked Sam why he was sad. \
+                He told her about his kite. Lily said she saw a red kite stuck in a tree \
+                by the pond. They ran to the pond with the dog. The kite was in the tree! \
+                Lily's father carefully reached up with a long stick and pulled it down. Sam said thank you \
+                and smiled. Now Sam and Lily fly the kite together every windy day.",
+            "questions": [
+                "What color was Sam's kite?",
+                "Why did Sam feel sad?",
+                "Where was the kite stuck?",
+            ],
+            "answers": ["Red", "His kite flew away", "In a tree by the pond"],
+            "topics": ["kites"],
+            "skills": ["main_idea", "inference"],
+            "question_types": ["literal", "inference", "literal"],
+            "difficulties": ["easy", "medium", "easy"],
+        })
+    }
+
+    /// State with `generated_story` generated and stored, returning the story's ID,
+    /// whose model then writes `outputs` in turn
+    pub(crate) async fn state_with_story(
+        outputs: Vec<serde_json::Value>,
+    ) -> (AppState<MemoryObjectStore, MemoryKeyValueStore>, String) {
+        let state = AppState::new(
+            MemoryObjectStore::new(),
+            MemoryKeyValueStore::new(),
+            Config::default(),
+            ContentTypeRegistry::new().register(descriptor()),
+        )
         .await
-        .map_err(|e| e.into_status())?
-    {
-        contents
-    } else {
-        // Load the reading comprehension prompt configuration
-        let prompt_config = prompts::get_prompt("reading_comprehension")
-            .ok_or_else(|| ServiceError::ConfigError("reading_comprehension".into()))
-            .map_err(|e| e.into_status())?;
-
-        // Generate new reading content using the generic generate_content method
-        let contents: ReadingContents = state
-            .generate_content(
-                prompt_config,
-                "ReadingContents",
-                "A reading comprehension passage with questions",
-            )
+        .with_stub_model([vec![generated_story()], outputs].concat())
+        .await;
+        let descriptor = state.content_types.get(READING_PREFIX).unwrap();
+        let story: ReadingContents = state
+            .get_or_generate(descriptor, &descriptor.default_params, None)
             .await
-            .map_err(|e| e.into_status())?;
+            .unwrap();
+        let id = story.id.unwrap();
+        (state, id)
+    }
 
-        // Store it for future use
-        state
-            .store_timed_object(&contents, ContentType::Reading)
-            .await
-            .map_err(|e| e.into_status())?;
+    #[test]
+    fn schema_requires_tags_but_old_stories_still_parse() {
+        let schema = descriptor().schema.schema;
+        let required: Vec<&str> = schema["required"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter_map(|field| field.as_str())
+            .collect();
+        assert!(required.contains(&"topics") && required.contains(&"skills"));
+        assert!(required.contains(&"answers"));
+        assert_eq!(schema["$defs"]["ReadingSkill"]["enum"][0], "main_idea");
 
-        contents
-    };
+        let old: ReadingContents =
+            serde_json::from_str(r#"{"title": "t", "story": "s", "questions": []}"#).unwrap();
+        assert!(old.topics.is_empty() && old.skills.is_empty() && old.answers.is_empty());
+
+        let answered: ReadingContents =
+            serde_json::from_str(r#"{"title": "t", "story": "s", "questions": ["q"], "answers": ["a"]}"#).unwrap();
+        assert_eq!(answered.answers, ["a"]);
+        assert!(serde_json::to_value(&answered).unwrap().get("answers").is_none());
+    }
 
-    Ok(Json(contents))
+    #[test]
+    fn questions_are_served_as_text_with_their_types_alongside() {
+        let generated = serde_json::json!({
+            "title": "t",
+            "story": "s",
+            "questions": ["Who ran?", "Why?"],
+            "question_types": ["literal", "inference"],
+            "difficulties": ["easy", "hard"],
+        });
+        let contents: ReadingContents = serde_json::from_value(generated).unwrap();
+        let served = serde_json::to_value(&contents).unwrap();
+        assert_eq!(served["questions"], serde_json::json!(["Who ran?", "Why?"]));
+        assert_eq!(served["question_types"], serde_json::json!(["literal", "inference"]));
+        assert_eq!(served["difficulties"], serde_json::json!(["easy", "hard"]));
+    }
 }
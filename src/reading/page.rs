@@ -0,0 +1,162 @@
+use axum::{
+    extract::{Query, State},
+    http::HeaderMap,
+    response::{IntoResponse, Response},
+};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    QuestionDifficulty, QuestionType, ReadingContents, ReadingQuery, ReadingSkill, StoryLength, paragraphs,
+    story,
+};
+use crate::{
+    keyvalue::KeyValueStore, pages, problem::ErrorResponse, session::Session,
+    state::AppState, static_files, storage::ObjectStore, users::CurrentUser,
+};
+
+/// How the reading page is delivered
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PageView {
+    /// Rendered on the server with the story in place, for browsers without
+    /// JavaScript and for printing
+    Html,
+}
+
+/// Query parameters accepted by the reading page
+///
+/// The story parameters are those of `/reading_contents`.
+#[derive(Debug, Deserialize, Default)]
+pub struct ReadingPageQuery {
+    /// Render the story on the server; otherwise the page fetches it itself
+    pub view: Option<PageView>,
+
+    #[serde(alias = "reading_level")]
+    pub grade: Option<u8>,
+    pub topic: Option<String>,
+    pub skill: Option<ReadingSkill>,
+    pub question_type: Option<QuestionType>,
+    pub difficulty: Option<QuestionDifficulty>,
+    pub questions: Option<usize>,
+    pub length: Option<StoryLength>,
+}
+
+impl ReadingPageQuery {
+    /// Query string that asks for another story like this one, starting with `&`
+    /// when not empty
+    fn story_query(&self) -> String {
+        let mut query = url::form_urlencoded::Serializer::new(String::new());
+        if let Some(grade) = self.grade {
+            query.append_pair("grade", &grade.to_string());
+        }
+        if let Some(topic) = &self.topic {
+            query.append_pair("topic", topic);
+        }
+        if let Some(skill) = self.skill {
+            query.append_pair("skill", skill.as_str());
+        }
+        if let Some(question_type) = self.question_type {
+            query.append_pair("question_type", question_type.as_str());
+        }
+        if let Some(difficulty) = self.difficulty {
+            query.append_pair("difficulty", difficulty.as_str());
+        }
+        if let Some(questions) = self.questions {
+            query.append_pair("questions", &questions.to_string());
+        }
+        if let Some(length) = self.length {
+            query.append_pair("length", length.as_str());
+        }
+        let query = query.finish();
+        if query.is_empty() { query } else { format!("&{}", query) }
+    }
+}
+
+/// A numbered question on the page
+#[derive(Serialize)]
+struct PageQuestion<'a> {
+    number: usize,
+    text: &'a str,
+}
+
+/// Values of the reading page template
+#[derive(Serialize)]
+struct ReadingPage<'a> {
+    title: &'a str,
+    paragraphs: Vec<&'a str>,
+    questions: Vec<PageQuestion<'a>>,
+    illustration_url: Option<String>,
+    worksheet_url: Option<String>,
+
+    /// Query string of the link to another story
+    query: String,
+}
+
+impl<'a> ReadingPage<'a> {
+    fn new(contents: &'a ReadingContents, query: String) -> Self {
+        Self {
+            title: &contents.title,
+            paragraphs: paragraphs(&contents.story, true),
+            questions: contents
+                .questions
+                .iter()
+                .enumerate()
+                .map(|(i, text)| PageQuestion { number: i + 1, text })
+                .collect(),
+            illustration_url: contents.illustration_key.as_ref().map(|key| format!("/images/{}", key)),
+            worksheet_url: contents.id.as_ref().map(|id| format!("/v1/reading_contents/{}/worksheet.pdf", id)),
+            query,
+        }
+    }
+}
+
+/// Serves the reading page, which loads a story with JavaScript, or with
+/// `?view=html` renders the story and questions on the server
+pub async fn reading_page<S: ObjectStore, K: KeyValueStore>(
+    State(state): State<AppState<S, K>>,
+    Query(query): Query<ReadingPageQuery>,
+    session: Session,
+    user: Option<CurrentUser>,
+    headers: HeaderMap,
+) -> Result<Response, ErrorResponse> {
+    if query.view != Some(PageView::Html) {
+        return static_files::serve("reading.html", &headers);
+    }
+
+    let session_id = session.id();
+    let story_query = query.story_query();
+    let reading_query = ReadingQuery {
+        grade: query.grade,
+        topic: query.topic,
+        skill: query.skill,
+        question_type: query.question_type,
+        difficulty: query.difficulty,
+        questions: query.questions,
+        length: query.length,
+    };
+    let contents = story(&state, reading_query, user, Some(session_id)).await?;
+    Ok(pages::render("reading", &ReadingPage::new(&contents, story_query))?.into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_the_story_and_numbered_questions() {
+        let contents: ReadingContents = serde_json::from_str(
+            r#"{"id": "abc", "title": "The Fox", "story": "One day.\n\nThe fox ran.", "questions": ["Who ran?", "Why?"]}"#,
+        )
+        .unwrap();
+        let query = ReadingPageQuery { grade: Some(3), topic: Some("red foxes".into()), ..Default::default() };
+        let axum::response::Html(html) =
+            pages::render("reading", &ReadingPage::new(&contents, query.story_query())).unwrap();
+
+        assert!(html.contains("<p>The fox ran.</p>"));
+        assert!(html.contains("<span class=\"question-number\">2.</span>"));
+        assert!(html.contains("/v1/reading_contents/abc/worksheet.pdf"));
+        assert!(!html.contains("story-image"));
+        assert_eq!(query.story_query(), "&grade=3&topic=red+foxes");
+        assert_eq!(ReadingPageQuery::default().story_query(), "");
+    }
+}
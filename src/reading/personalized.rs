@@ -0,0 +1,153 @@
+use axum::{Json, extract::State, http::StatusCode};
+use chrono::{Days, NaiveDate, Utc};
+use tracing::warn;
+
+use super::{READING_PREFIX, ReadingContents, ReadingQuery, request_params};
+use crate::{
+    ServiceError,
+    content::ContentParams,
+    keyvalue::KeyValueStore,
+    metrics,
+    problem::ErrorResponse,
+    session::Session,
+    state::AppState,
+    storage::ObjectStore,
+    users::{ChildProfile, CurrentUser},
+};
+
+/// Column of the item counting an account's personalized stories
+const COUNT_COLUMN: &str = "stories";
+
+/// Key of the item counting an account's personalized stories on a day
+fn allowance_key(account_id: &str, date: NaiveDate) -> String {
+    format!("personalized#{}#{}", account_id, date.format("%Y-%m-%d"))
+}
+
+/// Counts a personalized story against an account's daily limit
+///
+/// # Returns
+/// * `Ok(true)` - The story is within the limit
+/// * `Ok(false)` - The account has used up the day's stories
+/// * `Err(ServiceError)` - If the store can't be reached
+async fn take_story<K: KeyValueStore>(
+    kv_store: &K,
+    account_id: &str,
+    date: NaiveDate,
+    limit: u32,
+) -> Result<bool, ServiceError> {
+    let count = kv_store
+        .increment(allowance_key(account_id, date), COUNT_COLUMN.to_string(), 1)
+        .await?;
+    Ok(count <= i64::from(limit))
+}
+
+/// Gives back a story that couldn't be generated, logging rather than failing
+async fn return_story<K: KeyValueStore>(kv_store: &K, account_id: &str, date: NaiveDate) {
+    let returned = kv_store
+        .increment(allowance_key(account_id, date), COUNT_COLUMN.to_string(), -1)
+        .await;
+    if let Err(e) = returned {
+        warn!("Failed to return personalized story to {}: {}", account_id, e);
+    }
+}
+
+/// Adds a child's name, pet, and interests to the prompt
+///
+/// They are context rather than variables, so they never reach storage keys.
+fn personalize(params: ContentParams, profile: &ChildProfile) -> ContentParams {
+    let mut params = params.with_context("child_name", &profile.name);
+    if let Some(pet) = &profile.pet {
+        params = params.with_context("pet", pet);
+    }
+    if !profile.interests.is_empty() {
+        params = params.with_context("interests", profile.interests.join(", "));
+    }
+    params
+}
+
+/// Serves a story starring the selected child, with their pet and interests
+///
+/// Personalized stories are generated for each request and never cached, so no
+/// one else is served them; each account may generate
+/// `limits.personalized_stories_per_day` a day. Not being stored, they have no
+/// ID, so can't be narrated, printed, or continued.
+pub async fn reading_personalized<S: ObjectStore, K: KeyValueStore>(
+    State(state): State<AppState<S, K>>,
+    user: CurrentUser,
+    session: Session,
+) -> Result<Json<ReadingContents>, ErrorResponse> {
+    let profile = user
+        .profile
+        .clone()
+        .ok_or_else(|| ServiceError::InvalidInput("Select a child profile to personalize stories for".into()))?;
+    let descriptor = state
+        .content_types
+        .get(READING_PREFIX)
+        .ok_or_else(|| ServiceError::ConfigError(READING_PREFIX.into()))?;
+
+    let account_id = user.account.id.clone();
+    let session_id = session.id();
+    let params = request_params(&state, descriptor, ReadingQuery::default(), Some(user), Some(session_id)).await?;
+    let params = personalize(params, &profile);
+
+    let today = Utc::now().date_naive();
+    let limit = state.config.limits.personalized_stories_per_day;
+    if !take_story(&state.kv_store, &account_id, today, limit).await? {
+        metrics::increment("personalized.limited");
+        let mut response = ErrorResponse::new(
+            StatusCode::TOO_MANY_REQUESTS,
+            "personalized_limit_reached",
+            format!("At most {} personalized stories a day; try again tomorrow", limit),
+        );
+        response.retry_after = today
+            .checked_add_days(Days::new(1))
+            .and_then(|tomorrow| tomorrow.and_hms_opt(0, 0, 0))
+            .and_then(|midnight| (midnight.and_utc() - Utc::now()).to_std().ok());
+        return Err(response);
+    }
+
+    match state.generate_uncached(descriptor, &params).await {
+        Ok(story) => {
+            metrics::increment("personalized.generated");
+            Ok(Json(story))
+        }
+        Err(e) => {
+            return_story(&state.kv_store, &account_id, today).await;
+            Err(e.into())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keyvalue::MemoryKeyValueStore;
+
+    #[tokio::test]
+    async fn keeps_the_child_out_of_keys_and_limits_stories() {
+        let profile = ChildProfile {
+            id: "p1".to_string(),
+            name: "Ada".to_string(),
+            grade: 3,
+            interests: vec!["volcanoes".to_string(), "trains".to_string()],
+            pet: Some("a cat named Biscuit".to_string()),
+        };
+        let params = personalize(ContentParams::new().with("grade", 3), &profile);
+        assert_eq!(params.partition(), "grade-3/");
+        let variables = params.prompt_variables();
+        assert_eq!(variables["child_name"], "Ada");
+        assert_eq!(variables["pet"], "a cat named Biscuit");
+        assert_eq!(variables["interests"], "volcanoes, trains");
+
+        let kv_store = MemoryKeyValueStore::new();
+        let today = Utc::now().date_naive();
+        assert!(take_story(&kv_store, "a1", today, 2).await.unwrap());
+        assert!(take_story(&kv_store, "a1", today, 2).await.unwrap());
+        assert!(!take_story(&kv_store, "a1", today, 2).await.unwrap());
+        assert!(take_story(&kv_store, "a2", today, 2).await.unwrap());
+
+        return_story(&kv_store, "a1", today).await;
+        return_story(&kv_store, "a1", today).await;
+        assert!(take_story(&kv_store, "a1", today, 2).await.unwrap());
+    }
+}
@@ -0,0 +1,127 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{content::ContentParams, validation::ContentValidator};
+
+/// What a comprehension question asks students to do
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum QuestionType {
+    /// Recall something the passage states
+    Literal,
+    /// Work out something the passage implies
+    Inference,
+    /// Explain a word or phrase from the passage
+    Vocabulary,
+    /// Sum up what the passage is about
+    MainIdea,
+}
+
+impl QuestionType {
+    /// Name used in JSON and tags
+    pub fn as_str(self) -> &'static str {
+        match self {
+            QuestionType::Literal => "literal",
+            QuestionType::Inference => "inference",
+            QuestionType::Vocabulary => "vocabulary",
+            QuestionType::MainIdea => "main_idea",
+        }
+    }
+}
+
+/// How hard a question is for the passage's grade
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum QuestionDifficulty {
+    Easy,
+    Medium,
+    Hard,
+}
+
+impl QuestionDifficulty {
+    /// Name used in JSON and tags
+    pub fn as_str(self) -> &'static str {
+        match self {
+            QuestionDifficulty::Easy => "easy",
+            QuestionDifficulty::Medium => "medium",
+            QuestionDifficulty::Hard => "hard",
+        }
+    }
+}
+
+/// Requires `question_types` and `difficulties` to give the type and difficulty
+/// of each question, in order, and the questions to include at least
+/// `min_per_type` of each listed type
+#[derive(Debug, Clone)]
+pub struct QuestionMix {
+    pub min_per_type: Vec<(QuestionType, usize)>,
+}
+
+impl Default for QuestionMix {
+    /// At least one question recalling the passage and one reading between its lines
+    fn default() -> Self {
+        Self { min_per_type: vec![(QuestionType::Literal, 1), (QuestionType::Inference, 1)] }
+    }
+}
+
+/// Parses an array field of generated content
+fn array_field<T: for<'de> Deserialize<'de>>(content: &Value, field: &str) -> Result<Vec<T>, String> {
+    content
+        .get(field)
+        .cloned()
+        .and_then(|values| serde_json::from_value(values).ok())
+        .ok_or_else(|| format!("missing array field {:?}", field))
+}
+
+impl ContentValidator for QuestionMix {
+    fn validate(&self, content: &Value, _params: &ContentParams) -> Result<(), String> {
+        let questions: Vec<Value> = array_field(content, "questions")?;
+        let types: Vec<QuestionType> = array_field(content, "question_types")?;
+        let difficulties: Vec<QuestionDifficulty> = array_field(content, "difficulties")?;
+
+        if types.len() != questions.len() || difficulties.len() != questions.len() {
+            return Err(format!(
+                "{} questions but {} types and {} difficulties",
+                questions.len(),
+                types.len(),
+                difficulties.len()
+            ));
+        }
+        for (question_type, min) in &self.min_per_type {
+            let count = types.iter().filter(|t| *t == question_type).count();
+            if count < *min {
+                return Err(format!(
+                    "{} {} questions, expected at least {}",
+                    count,
+                    question_type.as_str(),
+                    min
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn requires_a_type_and_difficulty_per_question_in_the_mix() {
+        let params = ContentParams::new();
+        let content = |types: &[&str], difficulties: &[&str]| {
+            json!({
+                "questions": ["Who ran?", "Why did the fox run?", "What does \"swift\" mean?"],
+                "question_types": types,
+                "difficulties": difficulties,
+            })
+        };
+        let mix = QuestionMix::default();
+        assert!(mix.validate(&content(&["literal", "inference", "vocabulary"], &["easy", "hard", "easy"]), &params).is_ok());
+        assert!(mix.validate(&content(&["literal", "main_idea", "vocabulary"], &["easy", "hard", "easy"]), &params).is_err());
+        assert!(mix.validate(&content(&["literal", "inference"], &["easy", "hard", "easy"]), &params).is_err());
+        assert!(mix.validate(&json!({ "questions": ["Who ran?"] }), &params).is_err());
+    }
+}
@@ -0,0 +1,123 @@
+use axum::{
+    Json,
+    extract::{Path, State},
+};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+use super::{DEFAULT_GRADE, READING_PREFIX, ReadingContents, stored_story};
+use crate::{
+    ServiceError,
+    content::{ContentAnnotator, ContentParams},
+    keyvalue::KeyValueStore,
+    problem::ErrorResponse,
+    session::Session,
+    state::AppState,
+    storage::ObjectStore,
+};
+
+/// Hex digits of a series ID
+const SERIES_ID_LEN: usize = 16;
+
+/// Returns the ID of the series that begins with the story stored at `key`
+///
+/// Derived from the first chapter's key, so everyone continuing a story joins the
+/// same series, and lowercase hex, so it partitions the cache as is.
+fn series_id(first_chapter_key: &str) -> String {
+    let hash: String = Sha256::digest(first_chapter_key)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+    hash[..SERIES_ID_LEN].to_string()
+}
+
+/// Returns the grade a stored story was written for, from its key
+pub(super) fn grade_of(key: &str) -> Option<u8> {
+    key.split('/').find_map(|segment| segment.strip_prefix("grade-")?.parse().ok())
+}
+
+/// Parameters for the chapter after `previous`, which is stored at `key`
+///
+/// The series and chapter partition the cache, so a sequel is shared by everyone
+/// continuing the same chapter; the previous chapter itself is only context.
+fn sequel_params(key: &str, previous: &ReadingContents) -> ContentParams {
+    let series = previous.series_id.clone().unwrap_or_else(|| series_id(key));
+    let chapter = previous.chapter.unwrap_or(1) + 1;
+    ContentParams::new()
+        .with("grade", grade_of(key).unwrap_or(DEFAULT_GRADE))
+        .with("series", series)
+        .with("chapter", chapter)
+        .with_context("previous_title", &previous.title)
+        .with_context("previous_story", &previous.story)
+}
+
+/// Sets `series_id` and `chapter` on sequels, from their parameters
+#[derive(Debug, Clone, Default)]
+pub struct SeriesLink;
+
+impl ContentAnnotator for SeriesLink {
+    fn annotate(&self, content: &mut Value, params: &ContentParams) {
+        let variables = params.variables();
+        if let (Some(series), Some(chapter)) = (variables.get("series"), variables.get("chapter")) {
+            content["series_id"] = series.as_str().into();
+            content["chapter"] = chapter.parse::<u32>().ok().into();
+        }
+    }
+}
+
+/// Serves the next chapter of a story, generating it if no one has continued the
+/// story yet this hour
+///
+/// The story ID is the `id` field of the reading content. The sequel is written
+/// for the same grade as the story, and carries the series ID shared by every
+/// chapter after the first.
+pub async fn reading_continue<S: ObjectStore, K: KeyValueStore>(
+    State(state): State<AppState<S, K>>,
+    Path(story_id): Path<String>,
+    session: Session,
+) -> Result<Json<ReadingContents>, ErrorResponse> {
+    let descriptor = state
+        .content_types
+        .get(READING_PREFIX)
+        .ok_or_else(|| ServiceError::ConfigError(READING_PREFIX.into()))?;
+
+    let (key, previous) = stored_story(&state, &story_id).await?;
+    let params = sequel_params(&key, &previous);
+
+    let session_id = session.id();
+    let sequel = state.get_or_generate(descriptor, &params, Some(session_id)).await?;
+    Ok(Json(sequel))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sequels_share_their_series_and_count_chapters() {
+        let first_key = "reading/grade-4/topic-space/2025-10-11-14/abc.json";
+        let mut story: ReadingContents =
+            serde_json::from_str(r#"{"title": "Moon Cat", "story": "A cat went to the moon.", "questions": []}"#)
+                .unwrap();
+
+        let params = sequel_params(first_key, &story);
+        let series = series_id(first_key);
+        assert_eq!(params.partition(), format!("chapter-2/grade-4/series-{}/", series));
+        assert_eq!(params.prompt_variables()["previous_story"], "A cat went to the moon.");
+
+        let mut sequel = serde_json::json!({ "title": "Moon Cat Returns" });
+        SeriesLink.annotate(&mut sequel, &params);
+        assert_eq!(sequel["series_id"], series.as_str());
+        assert_eq!(sequel["chapter"], 2);
+
+        // Continuing a sequel keeps its series, wherever it is stored
+        story.series_id = Some(series.clone());
+        story.chapter = Some(2);
+        let params = sequel_params("reading/chapter-2/grade-4/other/abc.json", &story);
+        assert_eq!(params.partition(), format!("chapter-3/grade-4/series-{}/", series));
+
+        let mut plain = serde_json::json!({ "title": "Moon Cat" });
+        SeriesLink.annotate(&mut plain, &ContentParams::new().with("grade", 4));
+        assert!(plain.get("series_id").is_none());
+    }
+}
@@ -0,0 +1,112 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{content::ContentParams, validation::ContentValidator};
+
+/// Fewest and most questions a request may ask for
+pub const MIN_QUESTIONS: usize = 3;
+pub const MAX_QUESTIONS: usize = 8;
+
+/// How long a requested story should be
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum StoryLength {
+    Short,
+    Medium,
+    Long,
+}
+
+impl StoryLength {
+    /// Name used in queries and parameters
+    pub fn as_str(self) -> &'static str {
+        match self {
+            StoryLength::Short => "short",
+            StoryLength::Medium => "medium",
+            StoryLength::Long => "long",
+        }
+    }
+
+    /// Parses a name returned by `as_str`
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "short" => Some(StoryLength::Short),
+            "medium" => Some(StoryLength::Medium),
+            "long" => Some(StoryLength::Long),
+            _ => None,
+        }
+    }
+
+    /// Fewest and most words in a story of this length
+    pub fn words(self) -> (usize, usize) {
+        match self {
+            StoryLength::Short => (100, 175),
+            StoryLength::Medium => (150, 250),
+            StoryLength::Long => (250, 400),
+        }
+    }
+}
+
+/// Requires a story to have the length and number of questions it was requested
+/// with
+///
+/// These are read from the `length` and `question_count` parameters; content
+/// generated without one isn't checked for it.
+#[derive(Debug, Clone, Default)]
+pub struct RequestedSize;
+
+impl ContentValidator for RequestedSize {
+    fn validate(&self, content: &Value, params: &ContentParams) -> Result<(), String> {
+        let variables = params.variables();
+
+        if let Some(length) = variables.get("length").and_then(|length| StoryLength::parse(length)) {
+            let words = content
+                .get("story")
+                .and_then(Value::as_str)
+                .ok_or_else(|| "missing text field \"story\"".to_string())?
+                .split_whitespace()
+                .count();
+            let (min, max) = length.words();
+            if words < min || words > max {
+                return Err(format!(
+                    "{} story has {} words, expected {}-{}",
+                    length.as_str(),
+                    words,
+                    min,
+                    max
+                ));
+            }
+        }
+
+        if let Some(count) = variables.get("question_count").and_then(|count| count.parse::<usize>().ok()) {
+            let questions = content
+                .get("questions")
+                .and_then(Value::as_array)
+                .ok_or_else(|| "missing array field \"questions\"".to_string())?;
+            if questions.len() != count {
+                return Err(format!("{} questions, expected {}", questions.len(), count));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn checks_only_requested_sizes() {
+        let story = |words: usize, questions: usize| {
+            json!({ "story": vec!["word"; words].join(" "), "questions": vec!["Why?"; questions] })
+        };
+        let short_three = ContentParams::new().with("length", "short").with("question_count", 3);
+
+        assert!(RequestedSize.validate(&story(120, 3), &short_three).is_ok());
+        assert!(RequestedSize.validate(&story(300, 3), &short_three).is_err());
+        assert!(RequestedSize.validate(&story(120, 8), &short_three).is_err());
+        assert!(RequestedSize.validate(&story(300, 8), &ContentParams::new()).is_ok());
+    }
+}
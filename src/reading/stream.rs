@@ -0,0 +1,334 @@
+use axum::{
+    extract::{Query, State},
+    response::sse::{Event, KeepAlive, Sse},
+};
+use futures::{Stream, stream};
+use serde::Serialize;
+use serde_json::json;
+use std::convert::Infallible;
+use tokio::sync::mpsc;
+
+use super::{READING_PREFIX, ReadingContents, ReadingQuery, paragraphs, request_params, tagged_story};
+use crate::{
+    ServiceError,
+    content::ContentParams,
+    keyvalue::KeyValueStore,
+    moderation::ModerationVerdict,
+    problem::ErrorResponse,
+    session::Session,
+    state::AppState,
+    storage::ObjectStore,
+    users::CurrentUser,
+};
+
+/// Events buffered for a slow client
+const EVENT_BUFFER: usize = 16;
+
+/// Reads four hex digits of a `\u` escape
+fn hex_unit(chars: &mut std::str::Chars) -> Option<u16> {
+    let hex: String = chars.by_ref().take(4).collect();
+    if hex.len() < 4 {
+        return None;
+    }
+    u16::from_str_radix(&hex, 16).ok()
+}
+
+/// Reads a string field of a JSON object the model is still writing
+///
+/// # Returns
+/// * `Some((value, true))` - The whole value
+/// * `Some((value, false))` - The value written so far
+/// * `None` - If the field hasn't started yet
+fn partial_string(json: &str, field: &str) -> Option<(String, bool)> {
+    let key = format!("\"{}\"", field);
+    let start = json.find(&key)? + key.len();
+    let rest = json[start..].trim_start().strip_prefix(':')?.trim_start().strip_prefix('"')?;
+
+    let mut value = String::new();
+    let mut chars = rest.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => return Some((value, true)),
+            '\\' => match chars.next() {
+                Some('n') => value.push('\n'),
+                Some('t') => value.push('\t'),
+                Some('r') => value.push('\r'),
+                Some('b') => value.push('\u{8}'),
+                Some('f') => value.push('\u{c}'),
+                Some('u') => {
+                    let Some(unit) = hex_unit(&mut chars) else { break };
+                    // Characters outside the BMP are written as a surrogate pair
+                    let units = if (0xD800..0xDC00).contains(&unit) {
+                        let escaped = chars.next() == Some('\\') && chars.next() == Some('u');
+                        let Some(low) = hex_unit(&mut chars).filter(|_| escaped) else { break };
+                        vec![unit, low]
+                    } else {
+                        vec![unit]
+                    };
+                    value.extend(
+                        char::decode_utf16(units).map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER)),
+                    );
+                }
+                Some(other) => value.push(other),
+                None => break,
+            },
+            c => value.push(c),
+        }
+    }
+    Some((value, false))
+}
+
+/// A part of a story ready to send
+#[derive(Debug, PartialEq)]
+enum StoryPart {
+    Title(String),
+    Paragraph(String),
+}
+
+impl StoryPart {
+    fn text(&self) -> &str {
+        match self {
+            StoryPart::Title(text) | StoryPart::Paragraph(text) => text,
+        }
+    }
+
+    fn event(&self) -> Event {
+        match self {
+            StoryPart::Title(title) => event("title", title),
+            StoryPart::Paragraph(paragraph) => event("paragraph", paragraph),
+        }
+    }
+}
+
+/// The parts of a story sent to the client so far
+#[derive(Debug, Default)]
+struct StoryProgress {
+    /// JSON text received from the model
+    buffer: String,
+    title: Option<String>,
+    paragraphs: Vec<String>,
+}
+
+impl StoryProgress {
+    /// Adds text streamed by the model, returning the parts it completes
+    fn push(&mut self, delta: &str) -> Vec<StoryPart> {
+        self.buffer.push_str(delta);
+        let mut parts = Vec::new();
+
+        if self.title.is_none()
+            && let Some((title, true)) = partial_string(&self.buffer, "title")
+        {
+            self.title = Some(title.clone());
+            parts.push(StoryPart::Title(title));
+        }
+        if let Some((story, complete)) = partial_string(&self.buffer, "story") {
+            for paragraph in paragraphs(&story, complete).into_iter().skip(self.paragraphs.len()) {
+                self.paragraphs.push(paragraph.to_string());
+                parts.push(StoryPart::Paragraph(paragraph.to_string()));
+            }
+        }
+        parts
+    }
+
+    /// Whether what was sent is the start of `contents`, which differs when stale
+    /// content is served after streamed generation fails
+    fn is_start_of(&self, contents: &ReadingContents) -> bool {
+        let paragraphs = paragraphs(&contents.story, true);
+        self.title.as_ref().is_none_or(|title| *title == contents.title)
+            && self.paragraphs.len() <= paragraphs.len()
+            && self.paragraphs.iter().zip(&paragraphs).all(|(sent, p)| sent == p)
+    }
+}
+
+/// Builds an event whose data is JSON
+fn event(name: &str, data: &impl Serialize) -> Event {
+    Event::default()
+        .event(name)
+        .json_data(data)
+        .expect("story events serialize")
+}
+
+/// Builds the event ending a stream that failed
+fn error_event(error: ServiceError) -> Event {
+    let response = ErrorResponse::from(error);
+    event(
+        "error",
+        &json!({ "code": response.code, "detail": response.detail, "retryable": response.retryable }),
+    )
+}
+
+/// Moderates a part of a story still being generated, then sends it
+///
+/// The whole story is moderated again before it is stored.
+///
+/// # Returns
+/// Whether to keep streaming: false if the part was flagged or the client left
+async fn send_part<S: ObjectStore, K: KeyValueStore>(
+    state: &AppState<S, K>,
+    events: &mpsc::Sender<Event>,
+    part: StoryPart,
+) -> bool {
+    let event = match state.moderator.moderate(&json!({ "text": part.text() })).await {
+        Ok(ModerationVerdict::Allowed) => part.event(),
+        Ok(ModerationVerdict::Flagged(categories)) => {
+            let reason = format!("flagged by moderation: {}", categories.join(", "));
+            let _ = events.send(error_event(ServiceError::ContentRejected(reason))).await;
+            return false;
+        }
+        Err(e) => {
+            let _ = events.send(error_event(e)).await;
+            return false;
+        }
+    };
+    events.send(event).await.is_ok()
+}
+
+/// Sends the rest of a story after what was streamed, then the whole story, or
+/// the error that ended it
+async fn finish(
+    events: &mpsc::Sender<Event>,
+    mut progress: StoryProgress,
+    result: Result<ReadingContents, ServiceError>,
+) {
+    let contents = match result {
+        Ok(contents) => contents,
+        Err(e) => {
+            let _ = events.send(error_event(e)).await;
+            return;
+        }
+    };
+
+    if !progress.is_start_of(&contents) {
+        if events.send(Event::default().event("reset").data("")).await.is_err() {
+            return;
+        }
+        progress = StoryProgress::default();
+    }
+
+    let mut rest = Vec::new();
+    if progress.title.is_none() {
+        rest.push(StoryPart::Title(contents.title.clone()).event());
+    }
+    for paragraph in paragraphs(&contents.story, true).into_iter().skip(progress.paragraphs.len()) {
+        rest.push(StoryPart::Paragraph(paragraph.to_string()).event());
+    }
+    rest.push(event("questions", &contents.questions));
+    rest.push(event("done", &contents));
+    for event in rest {
+        if events.send(event).await.is_err() {
+            return;
+        }
+    }
+}
+
+/// Serves or generates a story, sending each part to `events` as it's ready
+async fn stream_story<S: ObjectStore, K: KeyValueStore>(
+    state: AppState<S, K>,
+    params: ContentParams,
+    session_id: String,
+    events: mpsc::Sender<Event>,
+) {
+    let (deltas, mut received) = mpsc::unbounded_channel();
+    let generating = async {
+        let descriptor = state
+            .content_types
+            .get(READING_PREFIX)
+            .ok_or_else(|| ServiceError::ConfigError(READING_PREFIX.into()))?;
+        state
+            .get_or_generate_streaming(descriptor, &params, Some(&session_id), deltas)
+            .await
+    };
+    // Generation continues if streaming stops, so the story is still cached
+    let forwarding = async {
+        let mut progress = StoryProgress::default();
+        while let Some(delta) = received.recv().await {
+            for part in progress.push(&delta) {
+                if !send_part(&state, &events, part).await {
+                    return None;
+                }
+            }
+        }
+        Some(progress)
+    };
+
+    let (result, progress) = futures::join!(generating, forwarding);
+    if let Some(progress) = progress {
+        finish(&events, progress, result).await;
+    }
+}
+
+/// Streams a story as server-sent events while it is generated, so a cold cache
+/// doesn't hold up the first words
+///
+/// Sends a `title` event, a `paragraph` event for each paragraph, then
+/// `questions` and `done`, whose data is the whole story as served by
+/// `/reading_contents`. Cached stories are sent the same way, all at once. A
+/// `reset` event means the parts sent so far should be discarded, and an `error`
+/// event ends a stream that failed, with the code and detail of a problem
+/// response; parts of a story that ends in an error must not be shown.
+pub async fn reading_contents_stream<S, K>(
+    State(state): State<AppState<S, K>>,
+    Query(query): Query<ReadingQuery>,
+    session: Session,
+    user: Option<CurrentUser>,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, ErrorResponse>
+where
+    S: ObjectStore,
+    K: KeyValueStore + 'static,
+{
+    let descriptor = state
+        .content_types
+        .get(READING_PREFIX)
+        .ok_or_else(|| ServiceError::ConfigError(READING_PREFIX.into()))?;
+
+    let session_id = session.id().to_string();
+    let tags = query.tags();
+    let params = request_params(&state, descriptor, query, user, Some(&session_id)).await?;
+
+    let (events, received) = mpsc::channel(EVENT_BUFFER);
+    if tags.is_empty() {
+        tokio::spawn(stream_story(state.clone(), params, session_id, events));
+    } else {
+        let contents = tagged_story(&state, descriptor, &params, &tags, Some(&session_id)).await?;
+        tokio::spawn(async move { finish(&events, StoryProgress::default(), Ok(contents)).await });
+    }
+
+    let stream = stream::unfold(received, |mut received| async move {
+        received.recv().await.map(|event| (Ok(event), received))
+    });
+    Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sends_parts_as_the_model_writes_them() {
+        assert_eq!(partial_string(r#"{"title": "A \"Big\" Da"#, "title"), Some(("A \"Big\" Da".into(), false)));
+        assert_eq!(partial_string(r#"{"title":"Caf\u00e9 \ud83e\udd8a"}"#, "title"), Some(("Café 🦊".into(), true)));
+        assert_eq!(partial_string(r#"{"title": "x", "sto"#, "story"), None);
+
+        let json = r#"{"title": "The Fox", "story": "One day.\n\nThe fox ran.\n\nThe end.", "questions": ["Why?"]}"#;
+        let mut progress = StoryProgress::default();
+        let mut parts = Vec::new();
+        // Split inside escapes and paragraph breaks
+        for chunk in json.as_bytes().chunks(3) {
+            parts.extend(progress.push(std::str::from_utf8(chunk).unwrap()));
+        }
+        assert_eq!(
+            parts,
+            [
+                StoryPart::Title("The Fox".into()),
+                StoryPart::Paragraph("One day.".into()),
+                StoryPart::Paragraph("The fox ran.".into()),
+                StoryPart::Paragraph("The end.".into()),
+            ]
+        );
+
+        let mut contents: ReadingContents = serde_json::from_str(json).unwrap();
+        assert!(progress.is_start_of(&contents));
+        contents.story = "One day.\n\nSomething else.".into();
+        assert!(!progress.is_start_of(&contents));
+    }
+}
@@ -0,0 +1,117 @@
+use axum::{
+    extract::{Path, State},
+    http::{StatusCode, header},
+    response::Response,
+};
+use tracing::error;
+
+use super::{ReadingContents, paragraphs, stored_story};
+use crate::{
+    keyvalue::KeyValueStore,
+    pdf::{Font, PdfDocument},
+    problem::ErrorResponse,
+    state::AppState,
+    storage::ObjectStore,
+};
+
+/// Font sizes, in points
+const TITLE_SIZE: f32 = 20.0;
+const HEADING_SIZE: f32 = 14.0;
+const TEXT_SIZE: f32 = 12.0;
+
+/// How far answers and answer lines are set in from their question, in points
+const ANSWER_INDENT: f32 = 18.0;
+
+/// Blank lines left under each question for the answer
+const ANSWER_LINES: usize = 3;
+
+/// Lays out a story as a printable worksheet: the story and its questions with
+/// room to write, then an answer key on a page of its own
+fn worksheet(contents: &ReadingContents) -> PdfDocument {
+    let mut doc = PdfDocument::new();
+    doc.paragraph(&contents.title, Font::Bold, TITLE_SIZE);
+    doc.paragraph("Name: ______________________________   Date: ______________", Font::Regular, TEXT_SIZE);
+    doc.space(TEXT_SIZE);
+    for paragraph in paragraphs(&contents.story, true) {
+        doc.paragraph(paragraph, Font::Regular, TEXT_SIZE);
+    }
+
+    doc.space(TEXT_SIZE);
+    doc.paragraph("Questions", Font::Bold, HEADING_SIZE);
+    for (i, question) in contents.questions.iter().enumerate() {
+        doc.paragraph(&format!("{}. {}", i + 1, question), Font::Regular, TEXT_SIZE);
+        for _ in 0..ANSWER_LINES {
+            doc.indented_paragraph(&"_".repeat(70), Font::Regular, TEXT_SIZE, ANSWER_INDENT);
+        }
+    }
+
+    doc.new_page();
+    doc.paragraph(&format!("Answer Key: {}", contents.title), Font::Bold, HEADING_SIZE);
+    // Older stories have no sample answers, and a mismatched list can't be matched up
+    let answered = contents.answers.len() == contents.questions.len();
+    if !answered {
+        doc.paragraph("Answers will vary; check them against the story.", Font::Regular, TEXT_SIZE);
+    }
+    for (i, question) in contents.questions.iter().enumerate() {
+        doc.paragraph(&format!("{}. {}", i + 1, question), Font::Bold, TEXT_SIZE);
+        if answered {
+            doc.indented_paragraph(&contents.answers[i], Font::Regular, TEXT_SIZE, ANSWER_INDENT);
+        }
+    }
+    doc
+}
+
+/// Renders a story as a printable PDF worksheet, with the story, its questions,
+/// and an answer key on a separate page
+///
+/// The story ID is the `id` field of the reading content.
+pub async fn reading_worksheet<S: ObjectStore, K: KeyValueStore>(
+    State(state): State<AppState<S, K>>,
+    Path(story_id): Path<String>,
+) -> Result<Response, ErrorResponse> {
+    let (object_key, contents) = stored_story(&state, &story_id).await?;
+    let pdf = worksheet(&contents).render();
+
+    Response::builder()
+        .header(header::CONTENT_TYPE, "application/pdf")
+        .header(header::CONTENT_DISPOSITION, "attachment; filename=\"worksheet.pdf\"")
+        .header(header::CONTENT_LENGTH, pdf.len())
+        .body(pdf.into())
+        .map_err(|e| {
+            error!("Failed to build worksheet response for {}: {}", object_key, e);
+            ErrorResponse::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "internal_error",
+                "Internal server error",
+            )
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn puts_the_answer_key_on_its_own_page() {
+        let mut contents: ReadingContents = serde_json::from_str(
+            r#"{"title": "The Fox", "story": "One day.\n\nThe fox ran.", "questions": ["Who ran?", "When?"]}"#,
+        )
+        .unwrap();
+        let doc = worksheet(&contents);
+        assert_eq!(doc.page_count(), 2);
+        let pdf = String::from_utf8(doc.render()).unwrap();
+        assert!(pdf.contains("(The fox ran.)") && pdf.contains("(Answers will vary"));
+
+        contents.answers = vec!["The fox".into(), "One day".into()];
+        let pdf = String::from_utf8(worksheet(&contents).render()).unwrap();
+        assert!(pdf.contains("(The fox)") && !pdf.contains("(Answers will vary"));
+    }
+
+    #[tokio::test]
+    async fn answer_keys_generated_stories() {
+        let (state, id) = super::super::tests::state_with_story(Vec::new()).await;
+        let (_, contents) = stored_story(&state, &id).await.unwrap();
+        let pdf = String::from_utf8(worksheet(&contents).render()).unwrap();
+        assert!(pdf.contains("(In a tree by the pond)") && !pdf.contains("(Answers will vary"));
+    }
+}
@@ -0,0 +1,464 @@
+use axum::{
+    Json, Router,
+    extract::{Query, State},
+    routing::get,
+};
+use chrono::{DateTime, Datelike, Days, NaiveDate, Utc};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+use tracing::warn;
+
+use crate::{
+    ServiceError,
+    content::ContentSchema,
+    email::EmailMessage,
+    keyvalue::KeyValueStore,
+    lease,
+    problem::ErrorResponse,
+    progress::{self, ActivityResult, Skill},
+    prompts,
+    reading::READING_PREFIX,
+    state::AppState,
+    storage::ObjectStore,
+    users::{self, ChildProfile, CurrentUser},
+};
+
+/// ObjectStore prefix under which weekly reports are stored
+pub const REPORTS_PREFIX: &str = "reports/";
+
+/// How often the report job checks for children missing last week's report
+pub const REPORT_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// Name of the prompt that writes a report from a week's numbers
+const REPORT_PROMPT: &str = "weekly_report";
+
+/// Most vocabulary words listed in a report
+const MAX_VOCABULARY_WORDS: usize = 30;
+
+/// Most activities read into one week's summary
+const MAX_WEEK_ACTIVITIES: usize = 1000;
+
+/// A child's numbers for one skill over a week
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct SkillWeek {
+    pub activities: usize,
+    pub average_score: Option<f64>,
+}
+
+/// What a child did over one week, Monday through Sunday (UTC)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct WeeklySummary {
+    /// Monday, as YYYY-MM-DD
+    pub week_start: String,
+
+    /// Sunday, as YYYY-MM-DD
+    pub week_end: String,
+
+    pub activities: usize,
+    pub stories_read: usize,
+
+    /// Mean score over all activities, as a percentage
+    pub average_score: Option<f64>,
+
+    pub skills: BTreeMap<Skill, SkillWeek>,
+
+    /// Words from the vocabulary exercises completed
+    pub vocabulary: Vec<String>,
+}
+
+/// Text the model writes for a report
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct ReportText {
+    pub headline: String,
+    pub summary: String,
+    pub suggestions: Vec<String>,
+}
+
+/// A weekly report for a parent about one child
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct WeeklyReport {
+    pub child: String,
+    pub name: String,
+    pub week: WeeklySummary,
+    pub headline: String,
+    pub summary: String,
+    pub suggestions: Vec<String>,
+
+    /// When the report was written, in RFC 3339 format
+    pub generated_at: String,
+}
+
+/// Returns the Monday starting the week containing `date`
+pub fn week_start(date: NaiveDate) -> NaiveDate {
+    date - Days::new(date.weekday().num_days_from_monday().into())
+}
+
+/// Returns the Monday starting the last week to have ended before `today`
+pub fn last_completed_week(today: NaiveDate) -> NaiveDate {
+    week_start(today) - Days::new(7)
+}
+
+/// Key of the object holding a child's report for a week
+fn report_key(child: &str, week_start: NaiveDate) -> String {
+    format!("{}{}/{}.json", REPORTS_PREFIX, child, week_start.format("%Y-%m-%d"))
+}
+
+/// Mean of scores, or `None` if there are none
+fn average(scores: impl Iterator<Item = u8>) -> Option<f64> {
+    let (count, total) = scores.fold((0u32, 0u32), |(count, total), score| {
+        (count + 1, total + u32::from(score))
+    });
+    (count > 0).then(|| f64::from(total) / f64::from(count))
+}
+
+/// Summarizes a week's activities
+fn summarize(
+    week_start: NaiveDate,
+    activities: &[ActivityResult],
+    vocabulary: Vec<String>,
+) -> WeeklySummary {
+    let skills = Skill::ALL
+        .into_iter()
+        .filter_map(|skill| {
+            let scores: Vec<u8> = activities
+                .iter()
+                .filter(|a| a.skill == skill)
+                .map(|a| a.score)
+                .collect();
+            (!scores.is_empty()).then(|| {
+                let week = SkillWeek {
+                    activities: scores.len(),
+                    average_score: average(scores.into_iter()),
+                };
+                (skill, week)
+            })
+        })
+        .collect();
+
+    WeeklySummary {
+        week_start: week_start.format("%Y-%m-%d").to_string(),
+        week_end: (week_start + Days::new(6)).format("%Y-%m-%d").to_string(),
+        activities: activities.len(),
+        stories_read: activities.iter().filter(|a| a.activity == READING_PREFIX).count(),
+        average_score: average(activities.iter().map(|a| a.score)),
+        skills,
+        vocabulary,
+    }
+}
+
+/// Collects the words of the vocabulary exercises a child completed
+///
+/// Exercises are read from the content objects the activities name; objects that
+/// are gone or have no word list are skipped.
+async fn vocabulary_learned<S: ObjectStore>(
+    object_store: &S,
+    activities: &[ActivityResult],
+) -> Vec<String> {
+    let keys: BTreeSet<&str> = activities
+        .iter()
+        .filter(|a| a.skill == Skill::Vocabulary)
+        .filter_map(|a| a.content_key.as_deref())
+        .collect();
+
+    let mut words = BTreeSet::new();
+    for key in keys {
+        let Ok(bytes) = object_store.get_object(key).await else {
+            continue;
+        };
+        let Ok(content) = serde_json::from_slice::<serde_json::Value>(&bytes) else {
+            continue;
+        };
+        let found = content["words"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|word| word["word"].as_str());
+        words.extend(found.map(|word| word.trim().to_lowercase()));
+    }
+
+    words.into_iter().take(MAX_VOCABULARY_WORDS).collect()
+}
+
+/// Summarizes a child's activities over the week starting `week_start`
+///
+/// Returns `None` if the child did nothing that week.
+pub async fn summarize_week<S: ObjectStore, K: KeyValueStore>(
+    object_store: &S,
+    kv_store: &K,
+    child: &str,
+    week_start: NaiveDate,
+) -> Result<Option<WeeklySummary>, ServiceError> {
+    let start = week_start.and_hms_opt(0, 0, 0).map(|time| time.and_utc());
+    let end = (week_start + Days::new(7)).and_hms_opt(0, 0, 0).map(|time| time.and_utc());
+
+    let history = progress::history(kv_store, child, start, MAX_WEEK_ACTIVITIES).await?;
+    let activities: Vec<ActivityResult> = history
+        .into_iter()
+        .filter(|a| {
+            DateTime::parse_from_rfc3339(&a.completed_at)
+                .is_ok_and(|completed| Some(completed.with_timezone(&Utc)) < end)
+        })
+        .collect();
+    if activities.is_empty() {
+        return Ok(None);
+    }
+
+    let vocabulary = vocabulary_learned(object_store, &activities).await;
+    Ok(Some(summarize(week_start, &activities, vocabulary)))
+}
+
+/// Writes and stores a child's report for the week starting `week_start`
+///
+/// Returns `None`, without calling the model, if the child did nothing that week.
+pub async fn generate_report<S: ObjectStore, K: KeyValueStore>(
+    state: &AppState<S, K>,
+    profile: &ChildProfile,
+    week_start: NaiveDate,
+) -> Result<Option<WeeklyReport>, ServiceError> {
+    let week = summarize_week(&state.object_store, &state.kv_store, &profile.id, week_start).await?;
+    let Some(week) = week else {
+        return Ok(None);
+    };
+
+    let variables = BTreeMap::from([
+        ("name".to_string(), profile.name.clone()),
+        ("grade".to_string(), profile.grade.to_string()),
+        ("week_start".to_string(), week.week_start.clone()),
+        ("week_end".to_string(), week.week_end.clone()),
+        ("stats".to_string(), serde_json::to_string_pretty(&week)?),
+    ]);
+    let prompt_config = prompts::get_prompt(REPORT_PROMPT)
+        .ok_or_else(|| ServiceError::ConfigError(REPORT_PROMPT.into()))?
+        .render(&variables)?;
+    let schema = ContentSchema::for_type::<ReportText>("ReportText", "A weekly progress report for a parent");
+    let text: ReportText = state.generate_content(&prompt_config, &schema).await?;
+
+    let report = WeeklyReport {
+        child: profile.id.clone(),
+        name: profile.name.clone(),
+        week,
+        headline: text.headline,
+        summary: text.summary,
+        suggestions: text.suggestions,
+        generated_at: Utc::now().to_rfc3339(),
+    };
+    store_report(&state.object_store, &report, week_start).await?;
+    Ok(Some(report))
+}
+
+/// Stores a report where `latest_report` finds it
+async fn store_report<S: ObjectStore>(
+    object_store: &S,
+    report: &WeeklyReport,
+    week_start: NaiveDate,
+) -> Result<(), ServiceError> {
+    object_store
+        .put_object(&report_key(&report.child, week_start), serde_json::to_vec(report)?)
+        .await
+}
+
+/// Returns a child's most recent report, if any
+pub async fn latest_report<S: ObjectStore>(
+    object_store: &S,
+    child: &str,
+) -> Result<Option<WeeklyReport>, ServiceError> {
+    // Keys end in the week's date, so the greatest key is the latest week
+    let objects = object_store
+        .list_objects(&format!("{}{}/", REPORTS_PREFIX, child))
+        .await?;
+    let Some(latest) = objects.iter().map(|object| &object.key).max() else {
+        return Ok(None);
+    };
+
+    let bytes = object_store.get_object(latest).await?;
+    Ok(Some(serde_json::from_slice(&bytes)?))
+}
+
+/// Builds the email sending a report to a parent
+pub fn report_message(to: &str, report: &WeeklyReport, public_url: &str) -> EmailMessage {
+    let mut text = format!("{}\n\n{}\n", report.headline, report.summary.trim_end());
+    if !report.suggestions.is_empty() {
+        text.push_str("\nThings to try at home:\n");
+        for suggestion in &report.suggestions {
+            text.push_str(&format!("- {}\n", suggestion));
+        }
+    }
+    text.push_str(&format!(
+        "\n{} activities and {} stories between {} and {}. See more at {}\n",
+        report.week.activities,
+        report.week.stories_read,
+        report.week.week_start,
+        report.week.week_end,
+        public_url
+    ));
+
+    EmailMessage::new(
+        to,
+        &format!("{}'s week on Thinkaroo ({})", report.name, report.week.week_start),
+        text,
+    )
+}
+
+/// Writes last week's report for every child who doesn't have one yet, and emails
+/// it to their parent
+///
+/// One instance at a time does this, per `REPORT_INTERVAL`. Children who did
+/// nothing last week get no report. A report whose email fails isn't sent again,
+/// since it can still be read in the app. Returns the number of reports written.
+pub async fn run_weekly_reports<S: ObjectStore, K: KeyValueStore>(
+    state: &AppState<S, K>,
+    today: NaiveDate,
+    shutdown: &CancellationToken,
+) -> Result<usize, ServiceError> {
+    if !lease::try_acquire(&state.kv_store, "weekly-reports", REPORT_INTERVAL).await? {
+        return Ok(0);
+    }
+
+    let week_start = last_completed_week(today);
+
+    let mut written = 0;
+    for account in users::all_accounts(&state.kv_store).await? {
+        for profile in &account.profiles {
+            if shutdown.is_cancelled() {
+                return Ok(written);
+            }
+            if state.object_store.object_exists(&report_key(&profile.id, week_start)).await? {
+                continue;
+            }
+            match generate_report(state, profile, week_start).await {
+                Ok(Some(report)) => {
+                    written += 1;
+                    let message = report_message(&account.email, &report, &state.config.public_url());
+                    if let Err(e) = state.mailer.send(&message).await {
+                        warn!("Failed to email weekly report for {}: {}", profile.id, e);
+                    }
+                }
+                Ok(None) => {}
+                Err(e) => warn!("Failed to write weekly report for {}: {}", profile.id, e),
+            }
+        }
+    }
+
+    Ok(written)
+}
+
+/// Builds the router for report endpoints
+///
+/// Routes must run inside the `session` and `current_user` middleware.
+pub fn router<S, K>() -> Router<AppState<S, K>>
+where
+    S: ObjectStore + 'static,
+    K: KeyValueStore + 'static,
+{
+    Router::new().route("/reports/latest", get(get_latest_report))
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct ReportQuery {
+    /// ID of the child profile; the selected profile if omitted
+    pub child: Option<String>,
+}
+
+/// Returns the latest weekly report for one of the logged-in account's children
+pub async fn get_latest_report<S: ObjectStore, K: KeyValueStore>(
+    State(state): State<AppState<S, K>>,
+    user: CurrentUser,
+    Query(query): Query<ReportQuery>,
+) -> Result<Json<WeeklyReport>, ErrorResponse> {
+    let child = query
+        .child
+        .or_else(|| user.profile.as_ref().map(|profile| profile.id.clone()))
+        .ok_or_else(|| ServiceError::InvalidInput("child is required when no profile is selected".into()))?;
+    if user.account.profile(&child).is_none() {
+        return Err(ServiceError::NotFound(format!("Unknown profile: {}", child)).into());
+    }
+
+    let report = latest_report(&state.object_store, &child)
+        .await?
+        .ok_or_else(|| ServiceError::NotFound("No report has been written yet".into()))?;
+    Ok(Json(report))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        keyvalue::MemoryKeyValueStore,
+        progress::NewActivity,
+        storage::MemoryObjectStore,
+    };
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 10, day).unwrap()
+    }
+
+    #[test]
+    fn finds_weeks() {
+        // 2025-10-15 is a Wednesday
+        assert_eq!(week_start(date(15)), date(13));
+        assert_eq!(week_start(date(13)), date(13));
+        assert_eq!(week_start(date(19)), date(13));
+        assert_eq!(last_completed_week(date(15)), date(6));
+    }
+
+    #[tokio::test]
+    async fn summarizes_one_week() {
+        let objects = MemoryObjectStore::new();
+        let kv = MemoryKeyValueStore::new();
+        objects
+            .put_object("vocabulary/a.json", br#"{"words": [{"word": "Brave"}, {"word": "calm"}]}"#.to_vec())
+            .await
+            .unwrap();
+
+        let complete = |activity: &str, skill, score, content_key: Option<&str>, day| {
+            let activity = NewActivity {
+                child: "c1".to_string(),
+                activity: activity.to_string(),
+                content_key: content_key.map(str::to_string),
+                skill,
+                score,
+            };
+            progress::record(&kv, activity, date(day).and_hms_opt(12, 0, 0).unwrap().and_utc())
+        };
+        complete("reading", Skill::Comprehension, 80, None, 5).await.unwrap();
+        complete("reading", Skill::Comprehension, 100, None, 6).await.unwrap();
+        complete("reading", Skill::Comprehension, 60, None, 12).await.unwrap();
+        complete("vocabulary", Skill::Vocabulary, 90, Some("vocabulary/a.json"), 8).await.unwrap();
+        complete("reading", Skill::Comprehension, 0, None, 13).await.unwrap();
+
+        let week = summarize_week(&objects, &kv, "c1", date(6)).await.unwrap().unwrap();
+        assert_eq!(week.week_end, "2025-10-12");
+        assert_eq!((week.activities, week.stories_read), (3, 2));
+        assert_eq!(week.average_score, Some(250.0 / 3.0));
+        assert_eq!(week.skills[&Skill::Comprehension].average_score, Some(80.0));
+        assert!(!week.skills.contains_key(&Skill::Math));
+        assert_eq!(week.vocabulary, vec!["brave", "calm"]);
+
+        assert!(summarize_week(&objects, &kv, "c1", date(20)).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn finds_the_latest_report() {
+        let objects = MemoryObjectStore::new();
+        assert!(latest_report(&objects, "c1").await.unwrap().is_none());
+
+        for week_start in [date(6), date(13)] {
+            let report = WeeklyReport {
+                child: "c1".to_string(),
+                name: "Ada".to_string(),
+                week: summarize(week_start, &[], Vec::new()),
+                headline: format!("Week of {}", week_start),
+                summary: String::new(),
+                suggestions: Vec::new(),
+                generated_at: String::new(),
+            };
+            store_report(&objects, &report, week_start).await.unwrap();
+        }
+
+        let latest = latest_report(&objects, "c1").await.unwrap().unwrap();
+        assert_eq!(latest.week.week_start, "2025-10-13");
+    }
+}
@@ -0,0 +1,123 @@
+use axum::{
+    extract::Request,
+    http::{HeaderName, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+use tracing::{Instrument, info_span};
+use uuid::Uuid;
+
+/// Header carrying the request ID, read from requests and set on responses
+pub const REQUEST_ID_HEADER: HeaderName = HeaderName::from_static("x-request-id");
+
+/// Longest request ID accepted from a client
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+/// ID correlating a request with its logs and error responses
+///
+/// Available to handlers as a request extension.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestId(pub String);
+
+tokio::task_local! {
+    static CURRENT_REQUEST_ID: RequestId;
+}
+
+/// Returns the ID of the request being handled by the current task
+///
+/// Returns `None` outside the request ID middleware, including in tasks spawned
+/// from a handler.
+pub fn current() -> Option<String> {
+    CURRENT_REQUEST_ID.try_with(|id| id.0.clone()).ok()
+}
+
+/// Whether a client-supplied request ID is safe to propagate and log
+fn is_valid(id: &str) -> bool {
+    !id.is_empty()
+        && id.len() <= MAX_REQUEST_ID_LEN
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | ':'))
+}
+
+/// Middleware that assigns each request an ID
+///
+/// Reuses the client's `x-request-id` when it is valid so IDs can be traced
+/// across services, and generates one otherwise. The ID is recorded on a span
+/// wrapping the request, so every log line emitted while handling it carries the
+/// ID, and is echoed back in the response's `x-request-id` header.
+pub async fn request_id(mut request: Request, next: Next) -> Response {
+    let id = request
+        .headers()
+        .get(&REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .filter(|id| is_valid(id))
+        .map(str::to_string)
+        .unwrap_or_else(|| Uuid::new_v4().to_string());
+
+    request.extensions_mut().insert(RequestId(id.clone()));
+    let span = info_span!(
+        "request",
+        request_id = %id,
+        method = %request.method(),
+        path = %request.uri().path(),
+    );
+
+    let mut response = CURRENT_REQUEST_ID
+        .scope(RequestId(id.clone()), next.run(request).instrument(span))
+        .await;
+
+    if let Ok(value) = HeaderValue::from_str(&id) {
+        response.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{Router, body::Body, http::StatusCode, routing::get};
+    use tower::ServiceExt;
+
+    fn app() -> Router {
+        Router::new()
+            .route("/", get(|| async { current().unwrap_or_default() }))
+            .layer(axum::middleware::from_fn(request_id))
+    }
+
+    async fn send(request: axum::http::Request<Body>) -> (String, String) {
+        let response = app().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let header = response.headers()[&REQUEST_ID_HEADER]
+            .to_str()
+            .unwrap()
+            .to_string();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (header, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn propagates_valid_client_ids() {
+        let request = axum::http::Request::get("/")
+            .header(REQUEST_ID_HEADER, "abc-123")
+            .body(Body::empty())
+            .unwrap();
+        assert_eq!(send(request).await, ("abc-123".to_string(), "abc-123".to_string()));
+    }
+
+    #[tokio::test]
+    async fn replaces_missing_or_invalid_ids() {
+        let request = axum::http::Request::get("/")
+            .header(REQUEST_ID_HEADER, "bad id")
+            .body(Body::empty())
+            .unwrap();
+        let (header, body) = send(request).await;
+        assert_eq!(header, body);
+        assert!(Uuid::parse_str(&header).is_ok());
+
+        assert_eq!(current(), None);
+    }
+}
@@ -0,0 +1,145 @@
+use rand::Rng;
+use std::future::Future;
+use std::time::Duration;
+use tracing::warn;
+
+use crate::ServiceError;
+
+/// Bounded retries with exponential backoff for transient backend failures
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    /// Total number of attempts, including the first
+    pub max_attempts: u32,
+
+    /// Delay before the first retry; doubled for each retry after that
+    pub base_delay: Duration,
+
+    /// Upper bound on the delay between attempts
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(2),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Returns the delay before the given retry (1 for the first retry)
+    ///
+    /// The delay is jittered between half and all of the exponential backoff so
+    /// instances retrying the same failure don't retry in lockstep.
+    pub fn delay(&self, retry: u32) -> Duration {
+        let backoff = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(retry.saturating_sub(1)))
+            .min(self.max_delay);
+
+        let jitter = rand::thread_rng().gen_range(0.5..=1.0);
+        backoff.mul_f64(jitter)
+    }
+
+    /// Runs an operation, retrying it while it fails with a retryable error
+    ///
+    /// # Arguments
+    /// * `operation` - Name of the operation, used in logs
+    /// * `f` - Produces a new attempt of the operation each time it is called
+    pub async fn run<T, F, Fut>(&self, operation: &str, mut f: F) -> Result<T, ServiceError>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, ServiceError>>,
+    {
+        let mut attempt = 1;
+
+        loop {
+            match f().await {
+                Err(e) if e.is_retryable() && attempt < self.max_attempts => {
+                    let delay = self.delay(attempt);
+                    warn!(
+                        "{} failed (attempt {}/{}), retrying in {:?}: {}",
+                        operation, attempt, self.max_attempts, delay, e
+                    );
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    fn policy() -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(2),
+        }
+    }
+
+    #[test]
+    fn delay_backs_off_up_to_max() {
+        let policy = RetryPolicy {
+            max_attempts: 10,
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_millis(300),
+        };
+
+        assert!(policy.delay(1) <= Duration::from_millis(100));
+        assert!(policy.delay(2) >= Duration::from_millis(100));
+        assert!(policy.delay(8) <= Duration::from_millis(300));
+    }
+
+    #[tokio::test]
+    async fn retries_transient_errors_until_success() {
+        let calls = AtomicU32::new(0);
+        let result = policy()
+            .run("test", || async {
+                if calls.fetch_add(1, Ordering::SeqCst) < 2 {
+                    Err(ServiceError::S3Error("throttled".to_string()))
+                } else {
+                    Ok(42)
+                }
+            })
+            .await;
+
+        assert_eq!(result.unwrap(), 42);
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn gives_up_after_max_attempts() {
+        let calls = AtomicU32::new(0);
+        let result: Result<(), _> = policy()
+            .run("test", || async {
+                calls.fetch_add(1, Ordering::SeqCst);
+                Err(ServiceError::DynamoDbError("unavailable".to_string()))
+            })
+            .await;
+
+        assert!(result.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn does_not_retry_permanent_errors() {
+        let calls = AtomicU32::new(0);
+        let result: Result<(), _> = policy()
+            .run("test", || async {
+                calls.fetch_add(1, Ordering::SeqCst);
+                Err(ServiceError::NotFound("missing".to_string()))
+            })
+            .await;
+
+        assert!(result.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+}
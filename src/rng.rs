@@ -0,0 +1,35 @@
+/// SplitMix64, a small generator whose output depends only on its seed
+///
+/// Used for content generated in code, like drills and puzzles, so it stays
+/// reproducible from a seed whatever version of `rand` is in use.
+#[derive(Debug, Clone)]
+pub struct SplitMix64(u64);
+
+impl SplitMix64 {
+    pub fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// A number from `min` to `max`, inclusive
+    pub fn between(&mut self, min: u32, max: u32) -> u32 {
+        min + (self.next_u64() % (u64::from(max - min) + 1)) as u32
+    }
+
+    /// An index below `len`, which must not be zero
+    pub fn index(&mut self, len: usize) -> usize {
+        (self.next_u64() % len as u64) as usize
+    }
+}
+
+/// A new seed, kept within 32 bits so JavaScript clients can send it back intact
+pub fn random_seed() -> u64 {
+    rand::random::<u32>().into()
+}
@@ -0,0 +1,187 @@
+use chrono::{DateTime, Datelike, Duration, DurationRound, Timelike, Utc};
+use serde::Deserialize;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::ServiceError;
+
+/// Furthest ahead to look for a matching time, so impossible schedules such as
+/// "0 0 31 2 *" end the search
+const MAX_LOOKAHEAD_DAYS: i64 = 5 * 366;
+
+/// A cron schedule, in UTC
+///
+/// Five fields: minute, hour, day of month, month, and day of week (0 or 7 is
+/// Sunday). Each is `*`, a number, a range `a-b`, a step `*/n` or `a-b/n`, or a
+/// comma-separated list of those. As in cron, when both day fields are
+/// restricted, a day matching either one matches. `@hourly`, `@daily`, and
+/// `@weekly` are shorthand for `0 * * * *`, `0 0 * * *`, and `0 0 * * 0`.
+///
+/// # Examples
+///
+/// ```
+/// use chrono::{TimeZone, Utc};
+/// use thinkaroo::scheduler::Schedule;
+///
+/// // 02:30 every Monday
+/// let schedule: Schedule = "30 2 * * 1".parse().unwrap();
+/// let friday = Utc.with_ymd_and_hms(2025, 10, 10, 12, 0, 0).unwrap();
+/// assert_eq!(schedule.next_after(friday), Some(Utc.with_ymd_and_hms(2025, 10, 13, 2, 30, 0).unwrap()));
+/// ```
+#[derive(Clone, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct Schedule {
+    source: String,
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    days_restricted: bool,
+    weekdays_restricted: bool,
+}
+
+/// Parses one field into a bit set of the values it matches
+fn parse_field(field: &str, min: u32, max: u32) -> Result<u64, String> {
+    let mut bits = 0u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, step.parse::<u32>().map_err(|_| format!("invalid step in {:?}", part))?),
+            None => (part, 1),
+        };
+        if step == 0 {
+            return Err(format!("step must be positive in {:?}", part));
+        }
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some((start, end)) = range.split_once('-') {
+            let parse = |value: &str| value.parse::<u32>().map_err(|_| format!("invalid range {:?}", range));
+            (parse(start)?, parse(end)?)
+        } else {
+            let value = range.parse::<u32>().map_err(|_| format!("invalid value {:?}", range))?;
+            // A single value with a step, like "5/15", runs from the value to the end
+            (value, if part.contains('/') { max } else { value })
+        };
+        if start < min || end > max || start > end {
+            return Err(format!("{:?} is outside {}-{}", part, min, max));
+        }
+        for value in (start..=end).step_by(step as usize) {
+            bits |= 1 << value;
+        }
+    }
+    Ok(bits)
+}
+
+impl FromStr for Schedule {
+    type Err = ServiceError;
+
+    fn from_str(source: &str) -> Result<Self, Self::Err> {
+        let expanded = match source.trim() {
+            "@hourly" => "0 * * * *",
+            "@daily" => "0 0 * * *",
+            "@weekly" => "0 0 * * 0",
+            other => other,
+        };
+        let invalid = |detail: String| ServiceError::ConfigError(format!("Invalid schedule {:?}: {}", source, detail));
+        let fields: Vec<&str> = expanded.split_whitespace().collect();
+        let [minute, hour, day, month, weekday] = fields[..] else {
+            return Err(invalid("expected 5 fields: minute hour day month weekday".into()));
+        };
+
+        let mut weekdays = parse_field(weekday, 0, 7).map_err(invalid)?;
+        // Sunday is both 0 and 7
+        if weekdays & (1 << 7) != 0 {
+            weekdays = (weekdays | 1) & !(1 << 7);
+        }
+        Ok(Self {
+            source: source.to_string(),
+            minutes: parse_field(minute, 0, 59).map_err(invalid)?,
+            hours: parse_field(hour, 0, 23).map_err(invalid)?,
+            days: parse_field(day, 1, 31).map_err(invalid)?,
+            months: parse_field(month, 1, 12).map_err(invalid)?,
+            weekdays,
+            days_restricted: day != "*",
+            weekdays_restricted: weekday != "*",
+        })
+    }
+}
+
+impl TryFrom<String> for Schedule {
+    type Error = ServiceError;
+
+    fn try_from(source: String) -> Result<Self, Self::Error> {
+        source.parse()
+    }
+}
+
+impl fmt::Debug for Schedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self.source)
+    }
+}
+
+impl fmt::Display for Schedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+impl Schedule {
+    /// Every hour, on the hour
+    pub fn hourly() -> Self {
+        "@hourly".parse().expect("@hourly is a valid schedule")
+    }
+
+    fn matches_day(&self, time: DateTime<Utc>) -> bool {
+        let day = self.days & (1 << time.day()) != 0;
+        let weekday = self.weekdays & (1 << time.weekday().num_days_from_sunday()) != 0;
+        match (self.days_restricted, self.weekdays_restricted) {
+            (true, true) => day || weekday,
+            _ => day && weekday,
+        }
+    }
+
+    /// Returns the first matching minute after `time`, or `None` if nothing
+    /// matches within the next five years
+    pub fn next_after(&self, time: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let minute = Duration::minutes(1);
+        let mut next = time.duration_trunc(minute).ok()? + minute;
+        let limit = time + Duration::days(MAX_LOOKAHEAD_DAYS);
+
+        while next < limit {
+            if self.months & (1 << next.month()) == 0 || !self.matches_day(next) {
+                next = next.duration_trunc(Duration::days(1)).ok()? + Duration::days(1);
+            } else if self.hours & (1 << next.hour()) == 0 {
+                next = next.duration_trunc(Duration::hours(1)).ok()? + Duration::hours(1);
+            } else if self.minutes & (1 << next.minute()) == 0 {
+                next += minute;
+            } else {
+                return Some(next);
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn finds_the_next_matching_minute() {
+        let at = |d, h, m| Utc.with_ymd_and_hms(2025, 10, d, h, m, 0).unwrap();
+        let next = |schedule: &str, time| schedule.parse::<Schedule>().unwrap().next_after(time);
+
+        assert_eq!(next("* * * * *", at(11, 14, 3) + Duration::seconds(30)), Some(at(11, 14, 4)));
+        assert_eq!(next("@hourly", at(11, 14, 0)), Some(at(11, 15, 0)));
+        assert_eq!(next("*/15 9-17 * * *", at(11, 17, 50)), Some(at(12, 9, 0)));
+        assert_eq!(next("0 0 * * 7", at(11, 14, 0)), Some(at(12, 0, 0)));
+        assert_eq!(next("0 6 1,15 * 1", at(11, 14, 0)), Some(at(13, 6, 0)));
+        assert_eq!(next("0 0 31 2 *", at(11, 14, 0)), None);
+
+        for invalid in ["* * * *", "60 * * * *", "*/0 * * * *", "5-1 * * * *", "x * * * *"] {
+            assert!(invalid.parse::<Schedule>().is_err(), "{} should not parse", invalid);
+        }
+    }
+}
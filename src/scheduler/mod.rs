@@ -0,0 +1,146 @@
+use chrono::Utc;
+use futures::future::BoxFuture;
+use serde::Deserialize;
+use std::future::Future;
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+
+use crate::{ServiceError, metrics};
+
+mod cron;
+
+pub use cron::Schedule;
+
+/// When the background jobs run; each is off unless scheduled
+///
+/// Configured in the `[schedule]` table, with cron expressions in UTC:
+///
+/// ```toml
+/// [schedule]
+/// pregenerate = "*/15 * * * *"
+/// gc = "@hourly"
+/// gc_retention_hours = 48
+/// weekly_reports = "0 6 * * 1"
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct JobSchedules {
+    /// Fills the current window's cache for every content type ahead of requests
+    pub pregenerate: Option<Schedule>,
+
+    /// Deletes cached content older than `gc_retention_hours`
+    pub gc: Option<Schedule>,
+
+    /// How long cached content is kept after its window ends
+    pub gc_retention_hours: Option<u32>,
+
+    /// Only logs what garbage collection would delete
+    pub gc_dry_run: bool,
+
+    /// Writes and emails last week's reports for children who don't have one yet
+    pub weekly_reports: Option<Schedule>,
+}
+
+impl JobSchedules {
+    /// Describes every invalid field
+    pub fn validate(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if self.gc.is_some() && self.gc_retention_hours.is_none() {
+            problems.push("schedule.gc_retention_hours must be set when schedule.gc is".to_string());
+        }
+        problems
+    }
+}
+
+type JobFn = Box<dyn Fn(CancellationToken) -> BoxFuture<'static, Result<(), ServiceError>> + Send + Sync>;
+
+struct ScheduledJob {
+    name: String,
+    schedule: Schedule,
+    run: JobFn,
+}
+
+/// Runs named jobs in the background on cron schedules
+///
+/// Each job gets its own task, so a slow job doesn't hold up the others; a run
+/// still going when the job is next due delays that run until it finishes. Runs
+/// are counted in `scheduler.{name}.calls`, `.errors`, and `.latency_ms`.
+///
+/// # Examples
+///
+/// ```
+/// use thinkaroo::scheduler::Scheduler;
+/// use tokio_util::sync::CancellationToken;
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let shutdown = CancellationToken::new();
+/// let tasks = Scheduler::new()
+///     .add("cleanup", "@daily".parse().unwrap(), |_shutdown| async { Ok(()) })
+///     .spawn(shutdown.clone());
+///
+/// shutdown.cancel();
+/// for task in tasks {
+///     task.await.unwrap();
+/// }
+/// # }
+/// ```
+#[derive(Default)]
+pub struct Scheduler {
+    jobs: Vec<ScheduledJob>,
+}
+
+impl Scheduler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a job, run at each time `schedule` matches
+    ///
+    /// The job is passed the shutdown token, so long runs can stop early.
+    pub fn add<F, Fut>(mut self, name: &str, schedule: Schedule, job: F) -> Self
+    where
+        F: Fn(CancellationToken) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<(), ServiceError>> + Send + 'static,
+    {
+        self.jobs.push(ScheduledJob {
+            name: name.to_string(),
+            schedule,
+            run: Box::new(move |shutdown| Box::pin(job(shutdown))),
+        });
+        self
+    }
+
+    /// Spawns a task for each job, running until `shutdown` is cancelled
+    ///
+    /// A run in progress at shutdown finishes first.
+    pub fn spawn(self, shutdown: CancellationToken) -> Vec<JoinHandle<()>> {
+        self.jobs
+            .into_iter()
+            .map(|job| {
+                info!("Scheduled {} at {:?}", job.name, job.schedule);
+                tokio::spawn(run_job(job, shutdown.clone()))
+            })
+            .collect()
+    }
+}
+
+async fn run_job(job: ScheduledJob, shutdown: CancellationToken) {
+    loop {
+        let now = Utc::now();
+        let Some(next) = job.schedule.next_after(now) else {
+            warn!("Schedule {:?} of {} never matches; not running it", job.schedule, job.name);
+            return;
+        };
+        tokio::select! {
+            _ = tokio::time::sleep((next - now).to_std().unwrap_or_default()) => {}
+            _ = shutdown.cancelled() => return,
+        }
+
+        match metrics::record_operation("scheduler", &job.name, (job.run)(shutdown.clone())).await {
+            Ok(()) => info!("Scheduled job {} finished", job.name),
+            Err(e) => warn!("Scheduled job {} failed: {}", job.name, e),
+        }
+    }
+}
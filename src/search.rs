@@ -0,0 +1,214 @@
+use axum::{
+    Json, Router,
+    extract::{Query, State},
+    routing::get,
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use crate::{
+    ServiceError,
+    dedup::{self, cosine_similarity},
+    flags,
+    keyvalue::{Column, KeyValueStore, RecordQuery},
+    problem::ErrorResponse,
+    state::AppState,
+    storage::{ObjectStore, StoredObject},
+};
+
+/// How long content stays searchable; longer than content is usually retained, so
+/// results whose content was deleted are skipped
+const INDEX_TTL: Duration = Duration::from_secs(90 * 24 * 60 * 60);
+
+/// Most recent objects of each content type compared against a query
+const MAX_CANDIDATES: usize = 2000;
+
+/// Results returned unless the request asks for fewer
+const DEFAULT_LIMIT: usize = 5;
+
+/// Most results returned by one search
+const MAX_LIMIT: usize = 20;
+
+/// Column of an index record holding the embedding, as little-endian `f32`s
+const VECTOR_COLUMN: &str = "vector";
+
+/// Partition of the index records of a content type; sort keys are object keys,
+/// so the most recent windows sort last
+fn index_partition(prefix: &str) -> String {
+    format!("search#{}", prefix)
+}
+
+/// Adds a content object's embedding to the search index
+pub async fn index<K: KeyValueStore>(
+    kv_store: &K,
+    prefix: &str,
+    object_key: &str,
+    vector: &[f32],
+) -> Result<(), ServiceError> {
+    let bytes = vector.iter().flat_map(|value| value.to_le_bytes()).collect();
+    kv_store
+        .put_record(
+            index_partition(prefix),
+            object_key.to_string(),
+            vec![Column::new(VECTOR_COLUMN.to_string(), bytes)],
+            Some(INDEX_TTL),
+        )
+        .await
+}
+
+/// Finds the indexed objects of a content type most similar to a vector, most
+/// similar first
+///
+/// # Returns
+/// * `Ok(Vec<(key, similarity)>)` - Up to `limit` object keys and their similarity
+/// * `Err(ServiceError)` - If the index can't be read
+pub async fn nearest<K: KeyValueStore>(
+    kv_store: &K,
+    prefix: &str,
+    vector: &[f32],
+    limit: usize,
+) -> Result<Vec<(String, f64)>, ServiceError> {
+    let records = kv_store
+        .query_records(
+            RecordQuery::new(index_partition(prefix)).descending().limit(MAX_CANDIDATES),
+            vec![VECTOR_COLUMN.to_string()],
+        )
+        .await?;
+
+    let mut scored: Vec<(String, f64)> = records
+        .into_iter()
+        .filter_map(|record| {
+            let column = record.columns.into_iter().find(|column| column.name == VECTOR_COLUMN)?;
+            let other: Vec<f32> = column
+                .value
+                .chunks_exact(4)
+                .map(|bytes| f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+                .collect();
+            Some((record.sort_key, cosine_similarity(vector, &other)))
+        })
+        .collect();
+    scored.sort_by(|a, b| b.1.total_cmp(&a.1));
+    scored.truncate(limit);
+    Ok(scored)
+}
+
+/// Search terms, or the key of an object to find more like
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct SearchQuery {
+    pub q: Option<String>,
+
+    /// Key of a content object, e.g. "reading/2025-10-11-14/1a2b.json"
+    pub like: Option<String>,
+
+    pub limit: Option<usize>,
+}
+
+/// A past content object matching a search
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct SearchResult {
+    pub object_key: String,
+    pub content_type: String,
+
+    /// Cosine similarity to the query, up to 1
+    pub similarity: f64,
+    pub content: serde_json::Value,
+}
+
+/// Builds the router for the search endpoint
+///
+/// Routes must run inside the `session` middleware.
+pub fn router<S, K>() -> Router<AppState<S, K>>
+where
+    S: ObjectStore + 'static,
+    K: KeyValueStore + 'static,
+{
+    Router::new().route("/search", get(get_search))
+}
+
+/// Finds past content about a topic (`q`) or similar to another object (`like`),
+/// across every window, most similar first
+pub async fn get_search<S: ObjectStore, K: KeyValueStore>(
+    State(state): State<AppState<S, K>>,
+    Query(query): Query<SearchQuery>,
+) -> Result<Json<Vec<SearchResult>>, ErrorResponse> {
+    Ok(Json(search(&state, query).await?))
+}
+
+/// Runs a search, skipping quarantined and deleted objects
+pub async fn search<S: ObjectStore, K: KeyValueStore>(
+    state: &AppState<S, K>,
+    query: SearchQuery,
+) -> Result<Vec<SearchResult>, ServiceError> {
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+    let like = query.like.as_deref().map(|key| key.trim_start_matches('/'));
+
+    let vector = match (query.q.as_deref().map(str::trim), like) {
+        (Some(q), None) if !q.is_empty() => state
+            .embedder
+            .embed(q)
+            .await?
+            .ok_or_else(|| ServiceError::OpenAIError("Embeddings are disabled".into()))?,
+        (None, Some(key)) => {
+            if !state.content_types.is_content_key(key) {
+                return Err(ServiceError::InvalidInput(format!("{:?} is not a content key", key)));
+            }
+            let stored = dedup::embedding_key(key);
+            if !state.object_store.object_exists(&stored).await? {
+                return Err(ServiceError::NotFound(format!("No searchable content at {}", key)));
+            }
+            serde_json::from_slice(&state.object_store.get_object(&stored).await?)?
+        }
+        _ => return Err(ServiceError::InvalidInput("Send exactly one of q or like".into())),
+    };
+
+    // Take a few extra matches, since some may have been deleted or quarantined
+    let mut matches = Vec::new();
+    for descriptor in state.content_types.iter().filter(|d| d.dedup_field.is_some()) {
+        for (key, similarity) in nearest(&state.kv_store, &descriptor.prefix, &vector, limit * 2).await? {
+            matches.push((descriptor.prefix.clone(), key, similarity));
+        }
+    }
+    matches.retain(|(_, key, _)| Some(key.as_str()) != like);
+    matches.sort_by(|a, b| b.2.total_cmp(&a.2));
+
+    let candidates: Vec<StoredObject> = matches
+        .iter()
+        .map(|(_, key, _)| StoredObject::new(key, 0, None))
+        .collect();
+    let quarantined = flags::quarantined(&state.kv_store, &candidates).await?;
+
+    let mut results = Vec::new();
+    for (content_type, key, similarity) in matches {
+        if results.len() == limit {
+            break;
+        }
+        if quarantined.contains(&key) || !state.object_store.object_exists(&key).await? {
+            continue;
+        }
+        let content = serde_json::from_slice(&state.object_store.get_object(&key).await?)?;
+        results.push(SearchResult { object_key: key, content_type, similarity, content });
+    }
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keyvalue::MemoryKeyValueStore;
+
+    #[tokio::test]
+    async fn ranks_indexed_objects_by_similarity() {
+        let store = MemoryKeyValueStore::new();
+        index(&store, "reading", "reading/2025-10-10-09/a.json", &[1.0, 0.0]).await.unwrap();
+        index(&store, "reading", "reading/2025-10-11-14/b.json", &[0.6, 0.8]).await.unwrap();
+        index(&store, "reading", "reading/2025-10-11-15/c.json", &[0.0, 1.0]).await.unwrap();
+        index(&store, "math", "math/2025-10-11-15/d.json", &[0.0, 1.0]).await.unwrap();
+
+        let found = nearest(&store, "reading", &[0.0, 1.0], 2).await.unwrap();
+        let keys: Vec<&str> = found.iter().map(|(key, _)| key.as_str()).collect();
+        assert_eq!(keys, ["reading/2025-10-11-15/c.json", "reading/2025-10-11-14/b.json"]);
+        assert!((found[1].1 - 0.8).abs() < 1e-6);
+        assert!(nearest(&store, "science", &[0.0, 1.0], 2).await.unwrap().is_empty());
+    }
+}
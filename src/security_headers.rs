@@ -0,0 +1,188 @@
+use axum::{
+    extract::{Request, State},
+    http::{HeaderValue, header},
+    middleware::Next,
+    response::Response,
+};
+use serde::Deserialize;
+
+/// Referrer policies browsers understand
+const REFERRER_POLICIES: [&str; 8] = [
+    "no-referrer",
+    "no-referrer-when-downgrade",
+    "origin",
+    "origin-when-cross-origin",
+    "same-origin",
+    "strict-origin",
+    "strict-origin-when-cross-origin",
+    "unsafe-url",
+];
+
+/// Security headers set on every HTML page
+///
+/// Pages are shown to children, often on school networks, so by default they may
+/// only load resources from this site, can't be framed, and don't tell other
+/// sites where visitors came from. Configured in the `[security_headers]` table:
+///
+/// ```toml
+/// [security_headers]
+/// frame_ancestors = ["https://lms.example.edu"]
+/// referrer_policy = "same-origin"
+/// ```
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SecurityHeadersPolicy {
+    /// Content-Security-Policy, without `frame-ancestors`
+    pub content_security_policy: String,
+
+    /// Origins allowed to show pages in a frame, e.g. a school's learning
+    /// management system; none if empty
+    pub frame_ancestors: Vec<String>,
+
+    /// Referrer-Policy
+    pub referrer_policy: String,
+}
+
+impl Default for SecurityHeadersPolicy {
+    fn default() -> Self {
+        Self {
+            // Pages keep their scripts and styles inline
+            content_security_policy: "default-src 'self'; script-src 'self' 'unsafe-inline'; \
+                style-src 'self' 'unsafe-inline'; img-src 'self' data:; object-src 'none'; \
+                base-uri 'self'; form-action 'self'"
+                .to_string(),
+            frame_ancestors: Vec::new(),
+            referrer_policy: "no-referrer".to_string(),
+        }
+    }
+}
+
+impl SecurityHeadersPolicy {
+    /// Describes every invalid field
+    pub fn validate(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        let csp = self.content_security_policy.trim();
+        if csp.is_empty() || HeaderValue::from_str(csp).is_err() {
+            problems.push("security_headers.content_security_policy must be a non-empty header value".to_string());
+        }
+        if csp.contains("frame-ancestors") {
+            problems.push(
+                "security_headers.content_security_policy must not set frame-ancestors; use frame_ancestors"
+                    .to_string(),
+            );
+        }
+        for origin in &self.frame_ancestors {
+            let is_source = !origin.is_empty()
+                && origin.chars().all(|c| c.is_ascii_graphic() && c != ';' && c != ',');
+            if !is_source {
+                problems.push(format!("security_headers.frame_ancestors has an invalid origin {:?}", origin));
+            }
+        }
+        if !REFERRER_POLICIES.contains(&self.referrer_policy.as_str()) {
+            problems.push(format!(
+                "security_headers.referrer_policy must be one of {}",
+                REFERRER_POLICIES.join(", ")
+            ));
+        }
+        problems
+    }
+
+    /// Full Content-Security-Policy, including `frame-ancestors`
+    fn content_security_policy(&self) -> String {
+        let ancestors = if self.frame_ancestors.is_empty() {
+            "'none'".to_string()
+        } else {
+            self.frame_ancestors.join(" ")
+        };
+        format!(
+            "{}; frame-ancestors {}",
+            self.content_security_policy.trim().trim_end_matches(';'),
+            ancestors
+        )
+    }
+}
+
+/// Header values built once from a validated policy, for the middleware
+#[derive(Debug, Clone)]
+pub struct SecurityHeaders {
+    content_security_policy: HeaderValue,
+    referrer_policy: HeaderValue,
+}
+
+impl SecurityHeaders {
+    /// Builds the headers of a policy, which must have passed `validate`
+    pub fn new(policy: &SecurityHeadersPolicy) -> Self {
+        Self {
+            content_security_policy: HeaderValue::from_str(&policy.content_security_policy())
+                .expect("validated security policy"),
+            referrer_policy: HeaderValue::from_str(&policy.referrer_policy)
+                .expect("validated referrer policy"),
+        }
+    }
+}
+
+/// Middleware that sets security headers on HTML responses, leaving any a
+/// handler set itself
+pub async fn security_headers(
+    State(security): State<SecurityHeaders>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let mut response = next.run(request).await;
+    let is_html = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("text/html"));
+    if !is_html {
+        return response;
+    }
+
+    let headers = response.headers_mut();
+    for (name, value) in [
+        (header::CONTENT_SECURITY_POLICY, security.content_security_policy),
+        (header::REFERRER_POLICY, security.referrer_policy),
+        (header::X_CONTENT_TYPE_OPTIONS, HeaderValue::from_static("nosniff")),
+    ] {
+        headers.entry(name).or_insert(value);
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{Json, Router, body::Body, response::Html, routing::get};
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn sets_headers_on_html_only() {
+        let policy = SecurityHeadersPolicy {
+            frame_ancestors: vec!["https://lms.example.edu".to_string()],
+            ..Default::default()
+        };
+        assert!(policy.validate().is_empty());
+        let app = Router::new()
+            .route("/", get(|| async { Html("<p>hi</p>") }))
+            .route("/json", get(|| async { Json(1) }))
+            .layer(axum::middleware::from_fn_with_state(SecurityHeaders::new(&policy), security_headers));
+
+        let request = |path| axum::http::Request::get(path).body(Body::empty()).unwrap();
+        let page = app.clone().oneshot(request("/")).await.unwrap();
+        let csp = page.headers()[header::CONTENT_SECURITY_POLICY].to_str().unwrap();
+        assert!(csp.starts_with("default-src 'self';"));
+        assert!(csp.ends_with("; frame-ancestors https://lms.example.edu"));
+        assert_eq!(page.headers()[header::X_CONTENT_TYPE_OPTIONS], "nosniff");
+        assert_eq!(page.headers()[header::REFERRER_POLICY], "no-referrer");
+
+        let json = app.oneshot(request("/json")).await.unwrap();
+        assert!(json.headers().get(header::CONTENT_SECURITY_POLICY).is_none());
+
+        let invalid = SecurityHeadersPolicy {
+            content_security_policy: "default-src 'self'; frame-ancestors *".to_string(),
+            frame_ancestors: vec!["a; b".to_string()],
+            referrer_policy: "sometimes".to_string(),
+        };
+        assert_eq!(invalid.validate().len(), 3);
+    }
+}
@@ -0,0 +1,131 @@
+use chrono::Utc;
+use std::collections::BTreeMap;
+
+use crate::{
+    ServiceError,
+    feedback::Ratings,
+    keyvalue::{KeyValueStore, TypedKv},
+    session::SESSION_TTL,
+    storage::StoredObject,
+};
+
+/// Most object keys remembered per session; the least recently served are forgotten
+/// first
+const MAX_HISTORY: usize = 512;
+
+/// Column of the history item holding served keys
+const SERVED_COLUMN: &str = "served";
+
+/// Objects served to one session, so it isn't shown the same content twice
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ServedHistory {
+    /// When each object was last served, as Unix seconds, by key
+    served: BTreeMap<String, i64>,
+}
+
+/// Key of the item holding a session's history
+fn history_key(session_id: &str) -> String {
+    format!("served#{}", session_id)
+}
+
+impl ServedHistory {
+    /// Loads a session's history, empty if it has none
+    pub async fn load<K: KeyValueStore>(kv_store: &K, session_id: &str) -> Result<Self, ServiceError> {
+        let served = kv_store
+            .get_json(&history_key(session_id), SERVED_COLUMN)
+            .await?
+            .unwrap_or_default();
+        Ok(Self { served })
+    }
+
+    /// Saves the history, expiring it along with the session
+    pub async fn save<K: KeyValueStore>(&self, kv_store: &K, session_id: &str) -> Result<(), ServiceError> {
+        kv_store
+            .put_json(&history_key(session_id), SERVED_COLUMN, &self.served, Some(SESSION_TTL))
+            .await
+    }
+
+    /// Whether the object has been served to the session
+    pub fn contains(&self, key: &str) -> bool {
+        self.served.contains_key(key)
+    }
+
+    /// Picks an object the session hasn't seen, at random and weighted by its
+    /// ratings, or the one it saw longest ago if it has seen them all
+    ///
+    /// Returns `None` only if `objects` is empty.
+    pub fn pick<'a>(&self, objects: &'a [StoredObject], ratings: &Ratings) -> Option<&'a StoredObject> {
+        let unseen: Vec<&StoredObject> = objects.iter().filter(|o| !self.contains(&o.key)).collect();
+        if !unseen.is_empty() {
+            return ratings.weighted_pick(&unseen);
+        }
+
+        objects
+            .iter()
+            .min_by_key(|object| self.served.get(&object.key).copied().unwrap_or(i64::MIN))
+    }
+
+    /// Records that an object was just served
+    pub fn record(&mut self, key: &str) {
+        self.served.insert(key.to_string(), Utc::now().timestamp());
+
+        while self.served.len() > MAX_HISTORY {
+            let Some(oldest) = self
+                .served
+                .iter()
+                .min_by_key(|(_, served_at)| **served_at)
+                .map(|(key, _)| key.clone())
+            else {
+                break;
+            };
+            self.served.remove(&oldest);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keyvalue::MemoryKeyValueStore;
+
+    fn objects(keys: &[&str]) -> Vec<StoredObject> {
+        keys.iter().map(|key| StoredObject::new(key, 1, None)).collect()
+    }
+
+    #[test]
+    fn picks_unseen_then_least_recently_served() {
+        let objects = objects(&["a.json", "b.json"]);
+        let mut history = ServedHistory::default();
+
+        history.served.insert("a.json".into(), 100);
+        assert_eq!(history.pick(&objects, &Ratings::default()).unwrap().key, "b.json");
+
+        history.served.insert("b.json".into(), 50);
+        assert_eq!(history.pick(&objects, &Ratings::default()).unwrap().key, "b.json");
+
+        assert!(history.pick(&[], &Ratings::default()).is_none());
+    }
+
+    #[test]
+    fn forgets_the_oldest_keys() {
+        let mut history = ServedHistory::default();
+        history.served.insert("old.json".into(), 0);
+        for i in 0..MAX_HISTORY {
+            history.record(&format!("{}.json", i));
+        }
+
+        assert_eq!(history.served.len(), MAX_HISTORY);
+        assert!(!history.contains("old.json"));
+    }
+
+    #[tokio::test]
+    async fn round_trips_through_the_store() {
+        let store = MemoryKeyValueStore::new();
+        let mut history = ServedHistory::load(&store, "s1").await.unwrap();
+        history.record("a.json");
+        history.save(&store, "s1").await.unwrap();
+
+        assert!(ServedHistory::load(&store, "s1").await.unwrap().contains("a.json"));
+        assert!(!ServedHistory::load(&store, "s2").await.unwrap().contains("a.json"));
+    }
+}
@@ -0,0 +1,104 @@
+use axum::Router;
+use axum_server::Handle;
+use axum_server::tls_rustls::RustlsConfig;
+use futures::future::{BoxFuture, FutureExt, try_join_all};
+use socket2::{Domain, Protocol, Socket, Type};
+use std::net::SocketAddr;
+use std::path::Path;
+use tokio_util::sync::CancellationToken;
+use tracing::info;
+
+use crate::{ServiceError, config::Config, shutdown::SHUTDOWN_DEADLINE};
+
+/// Maximum pending connections per listener
+const LISTEN_BACKLOG: i32 = 1024;
+
+/// Serves `app` on every configured address until `shutdown` is cancelled
+///
+/// Serves HTTPS when a TLS certificate and key are configured, HTTP otherwise.
+/// After `shutdown` is cancelled, listeners stop accepting connections and
+/// in-flight requests get `SHUTDOWN_DEADLINE` to finish.
+pub async fn serve(app: Router, config: &Config, shutdown: CancellationToken) -> Result<(), ServiceError> {
+    let tls = match config.tls() {
+        Some((cert, key)) => Some(load_tls(cert, key).await?),
+        None => None,
+    };
+    let scheme = if tls.is_some() { "https" } else { "http" };
+
+    let handle = Handle::new();
+    let mut servers: Vec<BoxFuture<'static, std::io::Result<()>>> = Vec::new();
+
+    for address in config.listen_addresses() {
+        let listener = bind(address)?;
+        let service = app.clone().into_make_service();
+        info!("Server listening on {}://{}", scheme, address);
+
+        servers.push(match &tls {
+            Some(tls) => axum_server::from_tcp_rustls(listener, tls.clone())
+                .handle(handle.clone())
+                .serve(service)
+                .boxed(),
+            None => axum_server::from_tcp(listener)
+                .handle(handle.clone())
+                .serve(service)
+                .boxed(),
+        });
+    }
+
+    tokio::spawn(async move {
+        shutdown.cancelled().await;
+        handle.graceful_shutdown(Some(SHUTDOWN_DEADLINE));
+    });
+
+    try_join_all(servers).await?;
+    Ok(())
+}
+
+/// Loads a PEM certificate chain and private key
+async fn load_tls(cert: &Path, key: &Path) -> Result<RustlsConfig, ServiceError> {
+    // Dependencies enable both of rustls's crypto backends, so it can't pick one
+    // itself; ignore the error if a provider is already installed
+    let _ = rustls::crypto::aws_lc_rs::default_provider().install_default();
+
+    RustlsConfig::from_pem_file(cert, key).await.map_err(|e| {
+        ServiceError::ConfigError(format!(
+            "Failed to load TLS certificate {} and key {}: {}",
+            cert.display(),
+            key.display(),
+            e
+        ))
+    })
+}
+
+/// Binds a listening socket
+///
+/// IPv6 sockets only accept IPv6, so `[::]` can be bound alongside `0.0.0.0` on
+/// the same port.
+fn bind(address: SocketAddr) -> Result<std::net::TcpListener, ServiceError> {
+    let socket = Socket::new(Domain::for_address(address), Type::STREAM, Some(Protocol::TCP))?;
+    if address.is_ipv6() {
+        socket.set_only_v6(true)?;
+    }
+    socket.set_reuse_address(true)?;
+    socket.set_nonblocking(true)?;
+    socket.bind(&address.into())?;
+    socket.listen(LISTEN_BACKLOG)?;
+
+    Ok(socket.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn binds_ipv4_and_ipv6_on_the_same_port() {
+        let ipv4 = bind("127.0.0.1:0".parse().unwrap()).unwrap();
+        let port = ipv4.local_addr().unwrap().port();
+
+        // Skip where the host has no IPv6 loopback
+        if let Ok(ipv6) = bind(SocketAddr::new(std::net::Ipv6Addr::LOCALHOST.into(), port)) {
+            assert_eq!(ipv6.local_addr().unwrap().port(), port);
+        }
+    }
+}
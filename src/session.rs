@@ -0,0 +1,327 @@
+use axum::{
+    extract::{FromRequestParts, Request, State},
+    http::{HeaderMap, HeaderValue, StatusCode, header, request::Parts},
+    middleware::Next,
+    response::Response,
+};
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use hmac::{Hmac, Mac};
+use serde::{Serialize, de::DeserializeOwned};
+use sha2::Sha256;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::{
+    ServiceError,
+    config::{Config, Environment},
+    keyvalue::{KeyValueStore, TypedKv},
+    problem::ErrorResponse,
+    state::AppState,
+    storage::ObjectStore,
+};
+
+/// Name of the cookie carrying the signed session ID
+pub const SESSION_COOKIE: &str = "thinkaroo_session";
+
+/// How long a session lasts after it was last written
+pub const SESSION_TTL: Duration = Duration::from_secs(180 * 24 * 3600);
+
+/// Column of the session item holding its data
+const DATA_COLUMN: &str = "data";
+
+/// Key signing session cookies so clients can't pick another session's ID
+#[derive(Clone)]
+pub struct SessionKey {
+    secret: Arc<[u8]>,
+
+    /// Whether cookies are marked `Secure`, i.e. only sent over HTTPS
+    secure: bool,
+}
+
+impl SessionKey {
+    /// Creates a key from `config.session_secret`, or a random one if unset
+    ///
+    /// Cookies are marked `Secure` in production, where the service is behind HTTPS.
+    pub fn from_config(config: &Config) -> Self {
+        let secret: Vec<u8> = match &config.session_secret {
+            Some(secret) => secret.as_bytes().to_vec(),
+            None => {
+                warn!("SESSION_SECRET not set; sessions won't survive a restart");
+                rand::random::<[u8; 32]>().to_vec()
+            }
+        };
+
+        Self {
+            secret: secret.into(),
+            secure: config.environment == Environment::Production,
+        }
+    }
+
+    fn mac(&self, id: &str) -> Hmac<Sha256> {
+        let mut mac = Hmac::<Sha256>::new_from_slice(&self.secret).expect("HMAC accepts any key size");
+        mac.update(id.as_bytes());
+        mac
+    }
+
+    /// Returns the cookie value for a session: its ID and the ID's signature
+    fn sign(&self, id: &str) -> String {
+        let signature = self.mac(id).finalize().into_bytes();
+        format!("{}.{}", id, URL_SAFE_NO_PAD.encode(signature))
+    }
+
+    /// Returns the session ID from a cookie value if its signature is valid
+    fn verify<'a>(&self, value: &'a str) -> Option<&'a str> {
+        let (id, signature) = value.split_once('.')?;
+        let signature = URL_SAFE_NO_PAD.decode(signature).ok()?;
+        self.mac(id).verify_slice(&signature).ok()?;
+        Some(id)
+    }
+
+    /// Builds the `Set-Cookie` header value issuing a session
+    fn cookie(&self, id: &str) -> String {
+        let mut cookie = format!(
+            "{}={}; Path=/; Max-Age={}; HttpOnly; SameSite=Lax",
+            SESSION_COOKIE,
+            self.sign(id),
+            SESSION_TTL.as_secs()
+        );
+        if self.secure {
+            cookie.push_str("; Secure");
+        }
+        cookie
+    }
+}
+
+impl std::fmt::Debug for SessionKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SessionKey")
+            .field("secret", &"<redacted>")
+            .field("secure", &self.secure)
+            .finish()
+    }
+}
+
+/// An anonymous visitor's session, identified by a signed cookie
+///
+/// Handlers take `Session` as an extractor on routes behind the `session`
+/// middleware. Data is kept as JSON values by name and saved to the key-value
+/// store after the handler returns, if it changed.
+#[derive(Clone)]
+pub struct Session {
+    inner: Arc<SessionInner>,
+}
+
+struct SessionInner {
+    id: String,
+    data: Mutex<BTreeMap<String, serde_json::Value>>,
+    dirty: AtomicBool,
+    renew: AtomicBool,
+}
+
+impl Session {
+    fn new(id: String, data: BTreeMap<String, serde_json::Value>) -> Self {
+        Self {
+            inner: Arc::new(SessionInner {
+                id,
+                data: Mutex::new(data),
+                dirty: AtomicBool::new(false),
+                renew: AtomicBool::new(false),
+            }),
+        }
+    }
+
+    /// The session's ID
+    pub fn id(&self) -> &str {
+        &self.inner.id
+    }
+
+    fn data(&self) -> std::sync::MutexGuard<'_, BTreeMap<String, serde_json::Value>> {
+        self.inner.data.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Returns the value stored under `name`, if any
+    ///
+    /// Values that no longer parse as `T` are treated as missing.
+    pub fn get<T: DeserializeOwned>(&self, name: &str) -> Option<T> {
+        let value = self.data().get(name)?.clone();
+        serde_json::from_value(value).ok()
+    }
+
+    /// Stores `value` under `name`
+    pub fn insert<T: Serialize>(&self, name: &str, value: &T) -> Result<(), ServiceError> {
+        let value = serde_json::to_value(value)?;
+        self.data().insert(name.to_string(), value);
+        self.inner.dirty.store(true, Ordering::Release);
+        Ok(())
+    }
+
+    /// Removes the value stored under `name`
+    pub fn remove(&self, name: &str) {
+        if self.data().remove(name).is_some() {
+            self.inner.dirty.store(true, Ordering::Release);
+        }
+    }
+
+    /// Moves the session's data to a new ID once the handler returns
+    ///
+    /// Call when the session gains privileges, such as on login, so an ID planted
+    /// in the client's cookie beforehand doesn't gain them too.
+    pub fn renew(&self) {
+        self.inner.renew.store(true, Ordering::Release);
+        self.inner.dirty.store(true, Ordering::Release);
+    }
+}
+
+impl<T: Send + Sync> FromRequestParts<T> for Session {
+    type Rejection = ErrorResponse;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &T) -> Result<Self, Self::Rejection> {
+        parts.extensions.get::<Session>().cloned().ok_or_else(|| {
+            ErrorResponse::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "configuration_error",
+                "Session middleware is not installed for this route",
+            )
+        })
+    }
+}
+
+/// Key of the item holding a session's data
+fn item_key(id: &str) -> String {
+    format!("session#{}", id)
+}
+
+/// Returns the signed session ID from the request's cookies, if any
+fn session_cookie<'a>(headers: &'a HeaderMap, key: &SessionKey) -> Option<&'a str> {
+    headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(';'))
+        .filter_map(|cookie| cookie.trim().split_once('='))
+        .filter(|(name, _)| *name == SESSION_COOKIE)
+        .find_map(|(_, value)| key.verify(value))
+}
+
+/// Middleware that attaches a `Session` to each request
+///
+/// Requests without a valid session cookie get a new session and a cookie
+/// issuing it. A session that can't be loaded is treated as empty rather than
+/// failing the request.
+pub async fn session<S: ObjectStore, K: KeyValueStore>(
+    State(state): State<AppState<S, K>>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let existing = session_cookie(request.headers(), &state.session_key).map(str::to_string);
+
+    let session = match &existing {
+        Some(id) => {
+            let data = match state.kv_store.get_json(&item_key(id), DATA_COLUMN).await {
+                Ok(data) => data.unwrap_or_default(),
+                Err(e) => {
+                    warn!("Failed to load session {}: {}", id, e);
+                    BTreeMap::new()
+                }
+            };
+            Session::new(id.clone(), data)
+        }
+        None => Session::new(new_id(), BTreeMap::new()),
+    };
+
+    request.extensions_mut().insert(session.clone());
+    let mut response = next.run(request).await;
+
+    let renew = session.inner.renew.load(Ordering::Acquire);
+    let id = if renew {
+        if let Some(old_id) = &existing
+            && let Err(e) = state.kv_store.delete(item_key(old_id)).await
+        {
+            warn!("Failed to delete renewed session {}: {}", old_id, e);
+        }
+        new_id()
+    } else {
+        session.id().to_string()
+    };
+
+    if session.inner.dirty.load(Ordering::Acquire) {
+        let data = session.data().clone();
+        if let Err(e) = state
+            .kv_store
+            .put_json(&item_key(&id), DATA_COLUMN, &data, Some(SESSION_TTL))
+            .await
+        {
+            warn!("Failed to save session {}: {}", id, e);
+        }
+    }
+
+    if (existing.is_none() || renew)
+        && let Ok(cookie) = HeaderValue::from_str(&state.session_key.cookie(&id))
+    {
+        response.headers_mut().append(header::SET_COOKIE, cookie);
+    }
+
+    response
+}
+
+/// Generates a session ID
+fn new_id() -> String {
+    Uuid::new_v4().simple().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        content::ContentTypeRegistry, keyvalue::MemoryKeyValueStore, storage::MemoryObjectStore,
+    };
+    use axum::{Router, body::Body, routing::get};
+    use tower::ServiceExt;
+
+    #[test]
+    fn rejects_tampered_cookies() {
+        let key = SessionKey::from_config(&Config::default());
+        let value = key.sign("abc");
+
+        assert_eq!(key.verify(&value), Some("abc"));
+        assert_eq!(key.verify(&value.replacen("abc", "abd", 1)), None);
+        assert_eq!(key.verify("abc"), None);
+    }
+
+    #[tokio::test]
+    async fn persists_session_data_across_requests() {
+        let state = AppState::new(
+            MemoryObjectStore::new(),
+            MemoryKeyValueStore::new(),
+            Config::default(),
+            ContentTypeRegistry::new(),
+        )
+        .await;
+        let app = Router::new()
+            .route(
+                "/",
+                get(|session: Session| async move {
+                    let visits = session.get::<u32>("visits").unwrap_or(0) + 1;
+                    session.insert("visits", &visits).unwrap();
+                    visits.to_string()
+                }),
+            )
+            .route_layer(axum::middleware::from_fn_with_state(state.clone(), session))
+            .with_state(state);
+
+        let response = app.clone().oneshot(Request::get("/").body(Body::empty()).unwrap()).await.unwrap();
+        let cookie = response.headers()[header::SET_COOKIE].to_str().unwrap();
+        let cookie = cookie.split(';').next().unwrap().to_string();
+
+        let request = Request::get("/").header(header::COOKIE, &cookie).body(Body::empty()).unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert!(response.headers().get(header::SET_COOKIE).is_none());
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(body, "2");
+    }
+}
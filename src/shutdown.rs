@@ -0,0 +1,83 @@
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+
+/// How long in-flight requests and background tasks get to finish after a shutdown
+/// signal before the process exits anyway
+pub const SHUTDOWN_DEADLINE: Duration = Duration::from_secs(30);
+
+/// Waits for SIGINT (Ctrl+C) or, on Unix, SIGTERM, then cancels `shutdown`
+///
+/// Returns early if `shutdown` is cancelled some other way.
+pub async fn wait_for_signal(shutdown: CancellationToken) {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            warn!("Failed to listen for Ctrl+C: {}", e);
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(e) => {
+                warn!("Failed to listen for SIGTERM: {}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => info!("Received Ctrl+C, shutting down"),
+        _ = terminate => info!("Received SIGTERM, shutting down"),
+        _ = shutdown.cancelled() => {}
+    }
+
+    shutdown.cancel();
+}
+
+/// Waits for background tasks to finish, giving up after `deadline`
+///
+/// Tasks should watch the same cancellation token as the server so they stop
+/// promptly, finishing any write already under way.
+pub async fn join_tasks(tasks: Vec<JoinHandle<()>>, deadline: Duration) {
+    let count = tasks.len();
+
+    match tokio::time::timeout(deadline, futures::future::join_all(tasks)).await {
+        Ok(_) => info!("{} background tasks stopped", count),
+        Err(_) => warn!(
+            "Background tasks still running after {:?}; exiting anyway",
+            deadline
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn joins_tasks_that_stop_when_cancelled() {
+        let shutdown = CancellationToken::new();
+        let token = shutdown.clone();
+        let task = tokio::spawn(async move { token.cancelled().await });
+
+        shutdown.cancel();
+        tokio::time::timeout(Duration::from_secs(1), join_tasks(vec![task], SHUTDOWN_DEADLINE))
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn stops_waiting_at_the_deadline() {
+        let task = tokio::spawn(std::future::pending::<()>());
+        join_tasks(vec![task], Duration::from_millis(10)).await;
+    }
+}
@@ -2,37 +2,72 @@ use async_openai::{
     config::OpenAIConfig,
     types::{
         responses::{
-            CreateResponseArgs, Input, InputItem, InputMessageArgs, Role, TextConfig,
-            TextResponseFormat,
+            CreateResponse, CreateResponseArgs, Input, InputItem, InputMessageArgs, Response,
+            ResponseEvent, Role, TextConfig, TextResponseFormat, Usage,
         },
         ResponseFormatJsonSchema,
     },
     Client as OpenAIClient,
 };
-use schemars::schema_for;
 use chrono::{DateTime, Utc};
+use futures::StreamExt;
 use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
 use uuid::Uuid;
 
-use crate::{keyvalue::KeyValueStore, prompts::PromptConfig, storage::ObjectStore, ServiceError};
+use crate::{
+    audit::{self, AuditMessage, AuditRecord},
+    cache_policy::{CachePolicies, CachePolicy},
+    circuit_breaker::CircuitBreaker,
+    config::Config,
+    content::{self, ContentParams, ContentSchema, ContentTypeDescriptor, ContentTypeRegistry},
+    dedup::{self, Embedder, OpenAIEmbedder},
+    email::{self, Mailer, NoopMailer},
+    experiments,
+    feedback::{self, Ratings},
+    flags,
+    illustration::{self, Illustrator, OpenAIIllustrator},
+    keyvalue::{AnyKeyValueStore, KeyValueStore},
+    lease,
+    metrics,
+    moderation::{ModerationVerdict, Moderator, OpenAIModerator},
+    narration::{self, Narrator, OpenAINarrator},
+    prompts::{self, PromptConfig},
+    queue::{self, GenerationJob, JobOutcome, JobQueue},
+    search,
+    served::ServedHistory,
+    session::SessionKey,
+    storage::{AnyObjectStore, ObjectStore, StoredObject},
+    tags,
+    usage,
+    validation,
+    ServiceError,
+};
 
-/// Maximum number of objects to store per hour before reusing existing ones
-const MAX_OBJECTS_PER_HOUR: usize = 16;
+/// Maximum number of times to ask the model to repair output that fails to parse
+const MAX_REPAIR_ATTEMPTS: usize = 2;
 
-/// Content type enum for organizing storage objects by type
-#[derive(Debug, Clone, Copy)]
-pub enum ContentType {
-    Reading,
-}
+/// Maximum number of times to generate content that fails validation before giving up
+const MAX_GENERATION_ATTEMPTS: usize = 3;
 
-impl ContentType {
-    /// Returns the string prefix for this content type
-    pub fn prefix(&self) -> &'static str {
-        match self {
-            ContentType::Reading => "reading",
-        }
-    }
-}
+/// How long an instance holds the lease to generate an object for a cache slot
+const GENERATION_LEASE: Duration = Duration::from_secs(60);
+
+/// How long to wait for another instance to generate content before giving up
+const GENERATION_WAIT: Duration = Duration::from_secs(20);
+
+/// How often to check for content generated by another instance
+const GENERATION_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Consecutive LLM failures that open the circuit breaker
+const LLM_FAILURE_THRESHOLD: u32 = 5;
+
+/// How long the LLM circuit breaker stays open before probing for recovery
+const LLM_OPEN_DURATION: Duration = Duration::from_secs(30);
 
 /// Application-wide state that can be shared across all routes
 /// Generic over the storage implementations to allow different backends
@@ -46,6 +81,40 @@ pub struct AppState<S: ObjectStore, K: KeyValueStore> {
 
     /// OpenAI client for OpenAI API interactions
     pub openai_client: OpenAIClient<async_openai::config::OpenAIConfig>,
+
+    /// Registry of content types that can be served and cached
+    pub content_types: ContentTypeRegistry,
+
+    /// Moderation hook that generated content must pass before it is cached
+    pub moderator: Arc<dyn Moderator>,
+
+    /// Embeds generated content to reject near-duplicates of cached content
+    pub embedder: Arc<dyn Embedder>,
+
+    /// Reads generated stories aloud
+    pub narrator: Arc<dyn Narrator>,
+
+    /// Draws illustrations for generated stories, when enabled in the config
+    pub illustrator: Arc<dyn Illustrator>,
+
+    /// Sends magic links and weekly reports
+    pub mailer: Arc<dyn Mailer>,
+
+    /// Carries generation jobs to workers, when configured; requests then serve
+    /// older content while a worker generates, rather than waiting on the model
+    pub generation_queue: Option<Arc<dyn JobQueue>>,
+
+    /// Circuit breaker around LLM calls; while open, only cached content is served
+    pub llm_circuit: CircuitBreaker,
+
+    /// Caching policy for each content type
+    pub cache_policies: CachePolicies,
+
+    /// Key signing session cookies
+    pub session_key: SessionKey,
+
+    /// Configuration loaded at startup
+    pub config: Arc<Config>,
 }
 
 impl<S: ObjectStore, K: KeyValueStore> AppState<S, K> {
@@ -54,58 +123,741 @@ impl<S: ObjectStore, K: KeyValueStore> AppState<S, K> {
     /// # Arguments
     /// * `object_store` - The object storage implementation to use
     /// * `kv_store` - The key-value store implementation to use
-    /// * `openai_api_key` - The OpenAI API key to use for API requests
+    /// * `config` - Validated configuration, supplying the OpenAI API key, model
+    ///   overrides, and cache policies
+    /// * `content_types` - The registry of content types to serve
     ///
     /// # Example
     /// ```no_run
+    /// use thinkaroo::config::Config;
+    /// use thinkaroo::content::ContentTypeRegistry;
     /// use thinkaroo::state::AppState;
     /// use thinkaroo::storage::S3ObjectStore;
     /// use thinkaroo::keyvalue::DynamoKeyValueStore;
+    /// use thinkaroo::reading;
     ///
     /// #[tokio::main]
     /// async fn main() {
-    ///     let config = aws_config::load_defaults(aws_config::BehaviorVersion::latest()).await;
-    ///     let object_store = S3ObjectStore::new(aws_sdk_s3::Client::new(&config));
-    ///     let kv_store = DynamoKeyValueStore::new(aws_sdk_dynamodb::Client::new(&config));
-    ///     let api_key = std::env::var("OPENAI_API_KEY").expect("OPENAI_API_KEY not set");
-    ///     let state = AppState::new(object_store, kv_store, api_key).await;
+    ///     let config = Config::load().expect("invalid configuration");
+    ///     let aws_config = aws_config::load_defaults(aws_config::BehaviorVersion::latest()).await;
+    ///     let object_store = S3ObjectStore::builder().bucket(&config.bucket).build(&aws_config);
+    ///     let kv_store = DynamoKeyValueStore::with_tables(
+    ///         aws_sdk_dynamodb::Client::new(&aws_config),
+    ///         &config.table,
+    ///         &config.records_table,
+    ///     );
+    ///     let content_types = ContentTypeRegistry::new().register(reading::descriptor());
+    ///     let state = AppState::new(object_store, kv_store, config, content_types).await;
     ///     // Use state with your Axum router
     /// }
     /// ```
-    pub async fn new(object_store: S, kv_store: K, openai_api_key: String) -> Self {
-        // Initialize OpenAI client with the provided API key
+    pub async fn new(
+        object_store: S,
+        kv_store: K,
+        config: Config,
+        content_types: ContentTypeRegistry,
+    ) -> Self {
+        // Initialize OpenAI client with the configured API key
+        let openai_api_key = config.openai_api_key.clone().unwrap_or_default();
         let openai_config = OpenAIConfig::new().with_api_key(openai_api_key);
         let openai_client = OpenAIClient::with_config(openai_config);
+        let moderator = Arc::new(OpenAIModerator::new(openai_client.clone()));
+        let embedder = Arc::new(OpenAIEmbedder::new(openai_client.clone(), &config.dedup.model));
+        let narrator = Arc::new(OpenAINarrator::new(openai_client.clone()));
+        let illustrator = Arc::new(OpenAIIllustrator::new(openai_client.clone()));
+        let cache_policies = content_types.iter().fold(config.cache_policies(), |policies, descriptor| {
+            match &descriptor.cache_policy {
+                Some(policy) => policies.with_policy(&descriptor.prefix, policy.clone()),
+                None => policies,
+            }
+        });
 
         Self {
             object_store,
             kv_store,
             openai_client,
+            content_types,
+            moderator,
+            embedder,
+            narrator,
+            illustrator,
+            mailer: Arc::new(NoopMailer),
+            generation_queue: None,
+            llm_circuit: CircuitBreaker::new("llm", LLM_FAILURE_THRESHOLD, LLM_OPEN_DURATION),
+            cache_policies,
+            session_key: SessionKey::from_config(&config),
+            config: Arc::new(config),
+        }
+    }
+
+    /// Sets the default cache policy for all content types
+    pub fn with_default_cache_policy(mut self, policy: CachePolicy) -> Self {
+        self.cache_policies = self.cache_policies.with_default(policy);
+        self
+    }
+
+    /// Sets the cache policy for a single content type
+    pub fn with_cache_policy(mut self, prefix: &str, policy: CachePolicy) -> Self {
+        self.cache_policies = self.cache_policies.with_policy(prefix, policy);
+        self
+    }
+
+    /// Replaces the moderation hook (the OpenAI moderation endpoint by default)
+    pub fn with_moderator(mut self, moderator: impl Moderator + 'static) -> Self {
+        self.moderator = Arc::new(moderator);
+        self
+    }
+
+    /// Replaces the embedder (the OpenAI embeddings endpoint by default)
+    pub fn with_embedder(mut self, embedder: impl Embedder + 'static) -> Self {
+        self.embedder = Arc::new(embedder);
+        self
+    }
+
+    /// Replaces the narrator (the OpenAI text-to-speech endpoint by default)
+    pub fn with_narrator(mut self, narrator: impl Narrator + 'static) -> Self {
+        self.narrator = Arc::new(narrator);
+        self
+    }
+
+    /// Replaces the illustrator (the OpenAI image endpoint by default)
+    pub fn with_illustrator(mut self, illustrator: impl Illustrator + 'static) -> Self {
+        self.illustrator = Arc::new(illustrator);
+        self
+    }
+
+    /// Replaces the mailer (one that only logs by default)
+    pub fn with_mailer(mut self, mailer: impl Mailer + 'static) -> Self {
+        self.mailer = Arc::new(mailer);
+        self
+    }
+
+    /// Hands generation to workers through a queue (none by default)
+    pub fn with_generation_queue(mut self, queue: impl JobQueue + 'static) -> Self {
+        self.generation_queue = Some(Arc::new(queue));
+        self
+    }
+
+    /// Gets cached content for a content type, generating and storing new content if needed
+    ///
+    /// # Type Parameters
+    /// * `T` - The type of content to return. Use `serde_json::Value` for untyped content.
+    ///
+    /// # Arguments
+    /// * `descriptor` - The content type to serve
+    /// * `params` - Parameters rendered into the prompt and used to partition the cache
+    /// * `session_id` - The requesting session, which isn't served cached content
+    ///   it has already seen while unseen content is available
+    ///
+    /// If generation fails, content from earlier hours is served instead when available.
+    ///
+    /// # Returns
+    /// * `Ok(T)` - Cached or freshly generated content
+    /// * `Err(ServiceError)` - If the prompt is missing, or storage or generation fails
+    ///   and there is no stale content to fall back to
+    pub async fn get_or_generate<T>(
+        &self,
+        descriptor: &ContentTypeDescriptor,
+        params: &ContentParams,
+        session_id: Option<&str>,
+    ) -> Result<T, ServiceError>
+    where
+        T: for<'de> Deserialize<'de> + Serialize + Sync,
+    {
+        self.get_or_generate_with(descriptor, params, session_id, None).await
+    }
+
+    /// Like `get_or_generate`, but sends the raw JSON text of newly generated content
+    /// to `deltas` as the model writes it
+    ///
+    /// Nothing is sent when cached content is served. Streamed content is generated
+    /// once rather than regenerated, since the client has already seen it, so it
+    /// fails with `ContentRejected` if it doesn't pass validation or moderation.
+    /// `deltas` is closed when this returns.
+    pub async fn get_or_generate_streaming<T>(
+        &self,
+        descriptor: &ContentTypeDescriptor,
+        params: &ContentParams,
+        session_id: Option<&str>,
+        deltas: mpsc::UnboundedSender<String>,
+    ) -> Result<T, ServiceError>
+    where
+        T: for<'de> Deserialize<'de> + Serialize + Sync,
+    {
+        self.get_or_generate_with(descriptor, params, session_id, Some(&deltas)).await
+    }
+
+    async fn get_or_generate_with<T>(
+        &self,
+        descriptor: &ContentTypeDescriptor,
+        params: &ContentParams,
+        session_id: Option<&str>,
+        deltas: Option<&mpsc::UnboundedSender<String>>,
+    ) -> Result<T, ServiceError>
+    where
+        T: for<'de> Deserialize<'de> + Serialize + Sync,
+    {
+        // Try to get an existing cached object
+        if let Some(contents) = self.get_timed_object(descriptor, params, session_id).await? {
+            return Ok(contents);
+        }
+
+        // With a generation queue, a worker generates while older content is served
+        if let Some(queue) = &self.generation_queue
+            && let Some(contents) = self.get_stale_object(descriptor, params).await?
+        {
+            self.enqueue_generation(queue.as_ref(), descriptor, params).await;
+            return Ok(contents);
+        }
+
+        // While the LLM is degraded, serve whatever is cached rather than generating;
+        // checked first, so no lease is taken for generation that won't happen
+        if let Err(e) = self.check_llm_circuit() {
+            return self.get_stale_object(descriptor, params).await?.ok_or(e);
+        }
+
+        // Only one instance generates each object in a window; the others wait for it
+        // or fall back to older content
+        if !self.try_acquire_generation_slot(descriptor, params).await? {
+            metrics::increment("generation.slot_contended");
+            return self.wait_for_content(descriptor, params).await;
+        }
+
+        let generated = match deltas {
+            Some(deltas) => self.generate_and_store_streaming(descriptor, params, deltas).await,
+            None => self.generate_and_store(descriptor, params).await,
+        };
+        match generated {
+            Ok((contents, key)) => {
+                if let Some(session_id) = session_id {
+                    self.record_served(session_id, &key).await;
+                }
+                Ok(contents)
+            }
+            Err(e) => {
+                // Older content is better than an error
+                warn!("Failed to generate {}, trying stale cache: {}", descriptor.prefix, e);
+                match self.get_stale_object(descriptor, params).await? {
+                    Some(contents) => {
+                        metrics::increment("generation.stale_served");
+                        Ok(contents)
+                    }
+                    None => Err(e),
+                }
+            }
+        }
+    }
+
+    /// Fills the current window's cache for every registered content type
+    ///
+    /// Each content type is warmed with its default parameters until its folder holds
+    /// the maximum number of objects allowed by its cache policy. Slots being generated
+    /// by another instance are skipped, so instances booting together share the work.
+    /// Failures are logged and stop warming that content type, and warming stops
+    /// while the LLM circuit breaker is open. Once `shutdown` is cancelled, the
+    /// object being generated is finished and warming stops.
+    pub async fn warm_cache(&self, shutdown: &CancellationToken) {
+        for descriptor in self.content_types.iter() {
+            let params = &descriptor.default_params;
+            let policy = self.cache_policies.for_content_type(&descriptor.prefix);
+            let folder_path = self.format_timed_prefix(&Utc::now(), descriptor, params);
+
+            let cached = match self.object_store.list_objects(&folder_path).await {
+                Ok(objects) => objects.len(),
+                Err(e) => {
+                    warn!("Failed to list {} for cache warm-up: {}", folder_path, e);
+                    continue;
+                }
+            };
+
+            info!(
+                "Warming {} cache: {} of {} objects present",
+                descriptor.prefix, cached, policy.max_objects
+            );
+
+            for _ in cached..policy.max_objects {
+                if shutdown.is_cancelled() {
+                    info!("Cache warm-up stopped for shutdown");
+                    return;
+                }
+                if let Err(e) = self.check_llm_circuit() {
+                    warn!("Cache warm-up stopped: {}", e);
+                    return;
+                }
+
+                match self.try_acquire_generation_slot(descriptor, params).await {
+                    Ok(true) => {}
+                    Ok(false) => continue,
+                    Err(e) => {
+                        warn!("Failed to acquire slot for {} warm-up: {}", descriptor.prefix, e);
+                        break;
+                    }
+                }
+
+                if let Err(e) = self
+                    .generate_and_store::<serde_json::Value>(descriptor, params)
+                    .await
+                {
+                    warn!("Cache warm-up for {} failed: {}", descriptor.prefix, e);
+                    break;
+                }
+                metrics::increment("generation.warm_up");
+            }
+        }
+    }
+
+    /// Generates and stores `count` new objects for a content type, ignoring its cache
+    /// policy's limits
+    ///
+    /// Used for offline batch generation. Stops at the first failure, or once the
+    /// LLM circuit breaker opens.
+    ///
+    /// # Returns
+    /// * `Ok(usize)` - The number of objects generated
+    /// * `Err(ServiceError)` - If generation or storage fails
+    pub async fn generate_batch(
+        &self,
+        descriptor: &ContentTypeDescriptor,
+        params: &ContentParams,
+        count: usize,
+    ) -> Result<usize, ServiceError> {
+        for generated in 0..count {
+            self.check_llm_circuit()?;
+            self.generate_and_store::<serde_json::Value>(descriptor, params)
+                .await?;
+            metrics::increment("generation.batch");
+            info!("Generated {} object {} of {}", descriptor.prefix, generated + 1, count);
+        }
+
+        Ok(count)
+    }
+
+    /// Queues a job to generate the next object in the current window, logging
+    /// rather than failing
+    ///
+    /// Like generation slots, jobs are identified by the window's folder and how
+    /// many objects it holds, so concurrent requests queue one job between them.
+    async fn enqueue_generation(&self, queue: &dyn JobQueue, descriptor: &ContentTypeDescriptor, params: &ContentParams) {
+        let folder_path = self.format_timed_prefix(&Utc::now(), descriptor, params);
+        let enqueued = async {
+            let object_count = self.object_store.list_objects(&folder_path).await?.len();
+            let slot = format!("enqueue#{}{}", folder_path, object_count);
+            if lease::try_acquire(&self.kv_store, &slot, GENERATION_LEASE).await? {
+                queue.send(&GenerationJob::new(&descriptor.prefix, &folder_path, params)).await?;
+                metrics::increment("generation.enqueued");
+            }
+            Ok::<_, ServiceError>(())
+        };
+        if let Err(e) = enqueued.await {
+            warn!("Failed to queue generation for {}: {}", folder_path, e);
+        }
+    }
+
+    /// Generates one object for a queued job, as a worker
+    ///
+    /// Jobs for windows that have ended, for full windows, and for slots another
+    /// instance is generating are skipped, so a job delivered twice generates at
+    /// most one object.
+    ///
+    /// # Returns
+    /// * `Ok(JobOutcome)` - What was done; the job can be deleted
+    /// * `Err(ServiceError)` - If storage or generation fails, or the LLM circuit
+    ///   breaker is open, so the job should be retried
+    pub async fn run_generation_job(&self, job: &GenerationJob) -> Result<JobOutcome, ServiceError> {
+        let Some(descriptor) = self.content_types.get(&job.content_type) else {
+            return Ok(JobOutcome::UnknownContentType);
+        };
+        let params = job.params();
+        let folder_path = self.format_timed_prefix(&Utc::now(), descriptor, &params);
+        if folder_path != job.slot {
+            return Ok(JobOutcome::Expired);
+        }
+
+        let policy = self.cache_policies.for_content_type(&descriptor.prefix);
+        if self.object_store.list_objects(&folder_path).await?.len() >= policy.max_objects {
+            return Ok(JobOutcome::Full);
+        }
+        self.check_llm_circuit()?;
+        if !self.try_acquire_generation_slot(descriptor, &params).await? {
+            return Ok(JobOutcome::Contended);
+        }
+
+        self.generate_and_store::<serde_json::Value>(descriptor, &params).await?;
+        Ok(JobOutcome::Generated)
+    }
+
+    /// Adds an object to a session's served history, logging rather than failing
+    async fn record_served(&self, session_id: &str, key: &str) {
+        let recorded = async {
+            let mut history = ServedHistory::load(&self.kv_store, session_id).await?;
+            history.record(key);
+            history.save(&self.kv_store, session_id).await
+        };
+        if let Err(e) = recorded.await {
+            warn!("Failed to save served history for {}: {}", session_id, e);
+        }
+    }
+
+    /// Fails while the LLM circuit breaker is open, so generation falls back to
+    /// cached content rather than calling a degraded provider
+    ///
+    /// Call once per generation: while half-open, only the first call is let
+    /// through, as the probe.
+    fn check_llm_circuit(&self) -> Result<(), ServiceError> {
+        if self.llm_circuit.allow_request() {
+            Ok(())
+        } else {
+            Err(ServiceError::OpenAIError("LLM circuit breaker is open".into()))
+        }
+    }
+
+    /// Tries to acquire the lease for generating the next object in the current window
+    ///
+    /// Slots are identified by the window's folder and how many objects it already
+    /// holds, so concurrent requests that all see the same under-filled folder produce
+    /// one new object between them instead of one each.
+    async fn try_acquire_generation_slot(
+        &self,
+        descriptor: &ContentTypeDescriptor,
+        params: &ContentParams,
+    ) -> Result<bool, ServiceError> {
+        let folder_path = self.format_timed_prefix(&Utc::now(), descriptor, params);
+        let object_count = self.object_store.list_objects(&folder_path).await?.len();
+        let slot = format!("generate#{}{}", folder_path, object_count);
+
+        lease::try_acquire(&self.kv_store, &slot, GENERATION_LEASE).await
+    }
+
+    /// Waits for another instance to generate content, serving older content if any
+    /// exists in the meantime
+    async fn wait_for_content<T>(
+        &self,
+        descriptor: &ContentTypeDescriptor,
+        params: &ContentParams,
+    ) -> Result<T, ServiceError>
+    where
+        T: for<'de> Deserialize<'de>,
+    {
+        let deadline = tokio::time::Instant::now() + GENERATION_WAIT;
+
+        loop {
+            if let Some(contents) = self.get_stale_object(descriptor, params).await? {
+                return Ok(contents);
+            }
+
+            if tokio::time::Instant::now() >= deadline {
+                return Err(ServiceError::OpenAIError(
+                    "Timed out waiting for content generation".into(),
+                ));
+            }
+
+            tokio::time::sleep(GENERATION_POLL_INTERVAL).await;
+        }
+    }
+
+    /// Generates new content, validates and moderates it, and stores it in the cache
+    ///
+    /// Content that fails validation or moderation, or is a near-duplicate of content
+    /// already cached in the window, is regenerated, up to MAX_GENERATION_ATTEMPTS
+    /// times. Returns the content and the key it was stored
+    /// under.
+    async fn generate_and_store<T>(
+        &self,
+        descriptor: &ContentTypeDescriptor,
+        params: &ContentParams,
+    ) -> Result<(T, String), ServiceError>
+    where
+        T: for<'de> Deserialize<'de> + Serialize + Sync,
+    {
+        let prompt_config = render_prompt(descriptor, params)?;
+
+        // Generate new content, regenerating anything that fails validation or
+        // moderation or repeats cached content
+        let mut rejection = String::new();
+        for attempt in 1..=MAX_GENERATION_ATTEMPTS {
+            let value = self.generate_json::<T>(&prompt_config, &descriptor.schema).await?;
+            match self.store_if_accepted(descriptor, params, value, attempt).await? {
+                Ok((value, key)) => return Ok((serde_json::from_value(value)?, key)),
+                Err(reason) => rejection = reason,
+            }
+        }
+
+        Err(ServiceError::ContentRejected(rejection))
+    }
+
+    /// Generates content for one request without caching it, for content that
+    /// mustn't be shared, such as stories about a particular child
+    ///
+    /// Content is validated, moderated, and annotated like cached content, and
+    /// regenerated up to MAX_GENERATION_ATTEMPTS times if rejected, but is never
+    /// stored, so it has no ID, narration, or illustration.
+    pub async fn generate_uncached<T>(
+        &self,
+        descriptor: &ContentTypeDescriptor,
+        params: &ContentParams,
+    ) -> Result<T, ServiceError>
+    where
+        T: for<'de> Deserialize<'de> + Serialize + Sync,
+    {
+        self.check_llm_circuit()?;
+        let prompt_config = render_prompt(descriptor, params)?;
+
+        let mut rejection = String::new();
+        for attempt in 1..=MAX_GENERATION_ATTEMPTS {
+            let mut value = self.generate_json::<T>(&prompt_config, &descriptor.schema).await?;
+            match self.rejection(descriptor, params, &value, attempt).await? {
+                Some(reason) => rejection = reason,
+                None => {
+                    for annotator in &descriptor.annotators {
+                        annotator.annotate(&mut value, params);
+                    }
+                    return Ok(serde_json::from_value(value)?);
+                }
+            }
+        }
+
+        Err(ServiceError::ContentRejected(rejection))
+    }
+
+    /// Generates new content once, sending its raw JSON text to `deltas` as the
+    /// model writes it, then validates, moderates, and stores it like
+    /// `generate_and_store`
+    async fn generate_and_store_streaming<T>(
+        &self,
+        descriptor: &ContentTypeDescriptor,
+        params: &ContentParams,
+        deltas: &mpsc::UnboundedSender<String>,
+    ) -> Result<(T, String), ServiceError>
+    where
+        T: for<'de> Deserialize<'de> + Serialize + Sync,
+    {
+        let prompt_config = render_prompt(descriptor, params)?;
+        let messages = prompt_messages(&prompt_config);
+        let model = self.config.model_for(&prompt_config.name, &prompt_config.model);
+
+        let requested_at = Utc::now();
+        let started = Instant::now();
+        let result = self.respond_streaming(model, &descriptor.schema, &messages, deltas).await;
+        if self.config.audit.should_audit(&prompt_config.name) {
+            let mut record = audit_record(&prompt_config, model, &descriptor.schema, &messages, requested_at);
+            record.duration_ms = started.elapsed().as_millis() as u64;
+            match &result {
+                Ok((text, usage)) => {
+                    record.response = Some(text.clone());
+                    record.input_tokens = usage.as_ref().map(|tokens| tokens.input_tokens);
+                    record.output_tokens = usage.as_ref().map(|tokens| tokens.output_tokens);
+                }
+                Err(e) => record.error = Some(e.to_string()),
+            }
+            self.write_audit(&record).await;
+        }
+
+        let (text, usage) = result?;
+        if let Some(tokens) = &usage {
+            self.record_usage(&prompt_config.name, tokens).await;
+        }
+        // Stored as written, with any fields `T` keeps out of responses
+        serde_json::from_str::<T>(&text)?;
+        match self.store_if_accepted(descriptor, params, serde_json::from_str(&text)?, 1).await? {
+            Ok((value, key)) => Ok((serde_json::from_value(value)?, key)),
+            Err(reason) => Err(ServiceError::ContentRejected(reason)),
+        }
+    }
+
+    /// Validates, moderates, and deduplicates generated content, then stores it with
+    /// its computed fields
+    ///
+    /// # Returns
+    /// * `Ok(Ok((value, key)))` - The stored content and the key it was stored under
+    /// * `Ok(Err(reason))` - Why the content was rejected
+    /// * `Err(ServiceError)` - If moderation or storage fails
+    async fn store_if_accepted(
+        &self,
+        descriptor: &ContentTypeDescriptor,
+        params: &ContentParams,
+        mut value: serde_json::Value,
+        attempt: usize,
+    ) -> Result<Result<(serde_json::Value, String), String>, ServiceError> {
+        if let Some(reason) = self.rejection(descriptor, params, &value, attempt).await? {
+            return Ok(Err(reason));
+        }
+
+        let embedding = self.embed_for_dedup(descriptor, &value).await;
+        if let Some(vector) = &embedding
+            && let Some((similar, similarity)) = self.find_duplicate(descriptor, params, vector).await?
+        {
+            metrics::increment("generation.duplicate_rejected");
+            warn!(
+                "Generated {} too similar to {} (attempt {}): {:.3}",
+                descriptor.prefix, similar, attempt, similarity
+            );
+            return Ok(Err(format!("too similar to {} ({:.3})", similar, similarity)));
+        }
+
+        // Add computed fields, then store it for future use
+        for annotator in &descriptor.annotators {
+            annotator.annotate(&mut value, params);
+        }
+        let key = self.new_timed_key(descriptor, params);
+        if let Some(field) = &descriptor.id_field {
+            value[field] = content::object_id(&key).into();
+        }
+        let illustration = self.illustration_source(descriptor, &value);
+        if let (Some(_), Some((_, field))) = (&illustration, &descriptor.illustration_fields) {
+            value[field] = illustration::illustration_key(&key).into();
+        }
+        self.object_store.put_object(&key, serde_json::to_vec(&value)?).await?;
+        self.spawn_narration(descriptor, &key, &value);
+        if let Some(text) = illustration {
+            self.spawn_illustration(&key, text);
+        }
+        if let Some(vector) = &embedding {
+            if let Err(e) = dedup::store(&self.object_store, &key, vector).await {
+                warn!("Failed to store embedding for {}: {}", key, e);
+            }
+            if let Err(e) = search::index(&self.kv_store, &descriptor.prefix, &key, vector).await {
+                warn!("Failed to index {} for search: {}", key, e);
+            }
         }
+        if !descriptor.tag_fields.is_empty() {
+            let tags = tags::extract(&descriptor.tag_fields, &value);
+            if let Err(e) = tags::store(&self.kv_store, &key, &tags).await {
+                warn!("Failed to store tags for {}: {}", key, e);
+            }
+        }
+        Ok(Ok((value, key)))
+    }
+
+    /// Validates and moderates generated content, returning why it was rejected
+    async fn rejection(
+        &self,
+        descriptor: &ContentTypeDescriptor,
+        params: &ContentParams,
+        value: &serde_json::Value,
+        attempt: usize,
+    ) -> Result<Option<String>, ServiceError> {
+        if let Err(reason) = validation::validate_all(&descriptor.validators, value, params) {
+            metrics::increment("generation.validation_failed");
+            warn!(
+                "Generated {} failed validation (attempt {}): {}",
+                descriptor.prefix, attempt, reason
+            );
+            return Ok(Some(reason));
+        }
+
+        if let ModerationVerdict::Flagged(categories) = self.moderator.moderate(value).await? {
+            metrics::increment("moderation.rejected");
+            warn!(
+                "Generated {} flagged by moderation (attempt {}): {:?}",
+                descriptor.prefix, attempt, categories
+            );
+            return Ok(Some(format!("flagged by moderation: {}", categories.join(", "))));
+        }
+
+        Ok(None)
+    }
+
+    /// Embeds the deduplicated field of generated content
+    ///
+    /// Deduplication only improves variety, so content is kept without it if the
+    /// field is missing or embedding fails.
+    async fn embed_for_dedup(
+        &self,
+        descriptor: &ContentTypeDescriptor,
+        value: &serde_json::Value,
+    ) -> Option<Vec<f32>> {
+        let field = descriptor.dedup_field.as_deref()?;
+        let text = value.get(field)?.as_str()?;
+        match self.embedder.embed(text).await {
+            Ok(vector) => vector,
+            Err(e) => {
+                warn!("Failed to embed generated {}, not deduplicating: {}", descriptor.prefix, e);
+                None
+            }
+        }
+    }
+
+    /// Finds cached content in the current window at least as similar to an
+    /// embedding as the dedup threshold
+    async fn find_duplicate(
+        &self,
+        descriptor: &ContentTypeDescriptor,
+        params: &ContentParams,
+        vector: &[f32],
+    ) -> Result<Option<(String, f64)>, ServiceError> {
+        let folder_path = self.format_timed_prefix(&Utc::now(), descriptor, params);
+        let objects = self.object_store.list_objects(&folder_path).await?;
+        let similar = dedup::most_similar(&self.object_store, &folder_path, &objects, vector).await?;
+        Ok(similar.filter(|(_, similarity)| *similarity >= self.config.dedup.threshold))
+    }
+
+    /// Selects the prompt version for a request when the content type's prompt is
+    /// under an A/B experiment
+    ///
+    /// # Arguments
+    /// * `descriptor` - The content type being requested
+    /// * `params` - The request's content parameters
+    /// * `session_id` - The requesting session, used for sticky assignment
+    ///
+    /// # Returns
+    /// * `Ok(ContentParams)` - The parameters, with a prompt version if one was assigned
+    /// * `Err(ServiceError)` - If the assignment can't be read or persisted
+    pub async fn with_prompt_variant(
+        &self,
+        descriptor: &ContentTypeDescriptor,
+        params: ContentParams,
+        session_id: Option<&str>,
+    ) -> Result<ContentParams, ServiceError> {
+        let Some(experiment) = prompts::get_prompt(&descriptor.prompt_name)
+            .and_then(|prompt| prompt.experiment)
+        else {
+            return Ok(params);
+        };
+
+        let variant = experiments::assign_variant(
+            &self.kv_store,
+            &descriptor.prompt_name,
+            &experiment,
+            session_id,
+        )
+        .await?;
+
+        Ok(match variant {
+            Some(variant) => params.with_prompt_version(variant),
+            None => params,
+        })
     }
 
-    /// Gets a random timed object from storage for the current hour
+    /// Gets a random timed object from storage for the current window
     ///
     /// This method implements a time-based caching strategy where objects are organized
-    /// by content type and hourly time slots. Returns `None` if the current hour's folder
-    /// has fewer than MAX_OBJECTS_PER_HOUR objects, indicating that more content should
-    /// be generated. Otherwise, returns a random existing object from the current hour.
+    /// by content type and time windows (hourly by default). Returns `None` when the
+    /// content type's cache policy decides more content should be generated for the
+    /// current window's folder. Otherwise, returns a random existing object from it.
+    ///
+    /// With a session, objects already served to it are skipped; once it has seen
+    /// them all, the one it saw longest ago is served. The served object is added to
+    /// the session's history.
     ///
     /// # Type Parameters
     /// * `T` - The type to deserialize from storage. Must implement Deserialize.
     ///
     /// # Arguments
-    /// * `content_type` - The type of content being requested (e.g., Reading)
+    /// * `content_type` - The type of content being requested
+    /// * `params` - The parameters the content was generated with
+    /// * `session_id` - The requesting session, if known
     ///
     /// # Returns
-    /// * `Ok(Some(T))` - A random object from the current hour's cache
+    /// * `Ok(Some(T))` - A random object from the current window's cache
     /// * `Ok(None)` - No cached object available (generate new content)
     /// * `Err(ServiceError)` - If storage operations fail
     ///
     /// # Example
     /// ```no_run
-    /// use thinkaroo::state::{AppState, ContentType};
-    /// use thinkaroo::storage::S3ObjectStore;
+    /// use thinkaroo::content::ContentParams;
+    /// use thinkaroo::state::AppState;
+    /// use thinkaroo::storage::ObjectStore;
+    /// use thinkaroo::keyvalue::KeyValueStore;
     /// use serde::{Deserialize, Serialize};
     ///
     /// #[derive(Serialize, Deserialize)]
@@ -113,101 +865,304 @@ impl<S: ObjectStore, K: KeyValueStore> AppState<S, K> {
     ///     data: String,
     /// }
     ///
-    /// # async fn example<S: thinkaroo::storage::ObjectStore>(state: AppState<S>) -> Result<(), thinkaroo::ServiceError> {
+    /// # async fn example<S: ObjectStore, K: KeyValueStore>(state: AppState<S, K>) -> Result<(), thinkaroo::ServiceError> {
+    /// let descriptor = state.content_types.get("reading").unwrap();
     /// let content: Option<MyContent> = state
-    ///     .get_timed_object(ContentType::Reading)
+    ///     .get_timed_object(descriptor, &ContentParams::new(), None)
     ///     .await?;
     /// # Ok(())
     /// # }
     /// ```
     pub async fn get_timed_object<T>(
         &self,
-        content_type: ContentType,
+        content_type: &ContentTypeDescriptor,
+        params: &ContentParams,
+        session_id: Option<&str>,
     ) -> Result<Option<T>, ServiceError>
     where
         T: for<'de> Deserialize<'de>,
     {
         let now = Utc::now();
-        let folder_path = Self::format_timed_prefix(&now, content_type);
+        let folder_path = self.format_timed_prefix(&now, content_type, params);
+        let policy = self.cache_policies.for_content_type(&content_type.prefix);
 
-        // List all objects in the current hour's folder for this content type
+        // List all servable objects in the current window's folder for this content type
         let objects = self.object_store.list_objects(&folder_path).await?;
+        let objects = self.without_quarantined(objects).await?;
         let object_count = objects.len();
 
-        if object_count >= MAX_OBJECTS_PER_HOUR {
-            // Pick a random object from existing ones
-            let random_index = rand::random::<usize>() % object_count;
-            let key = &objects[random_index].key;
-
-            // Fetch and parse the object
-            let body_bytes = self.object_store.get_object(key).await?;
-            let contents: T = serde_json::from_slice(&body_bytes)?;
-
-            Ok(Some(contents))
+        if !policy.should_generate(object_count) {
+            self.pick_object(&folder_path, &objects, session_id).await
         } else {
             // Need to generate new content
             Ok(None)
         }
     }
 
+    /// Gets a random cached object carrying all of `tags`, each a value in a tag
+    /// field, from the most recent window that has any
+    ///
+    /// Never generates content, since new content can't be made to carry tags.
+    /// Reaches back through earlier windows from the last day, like
+    /// `get_stale_object`, and records the served object in the session's history.
+    ///
+    /// # Returns
+    /// * `Ok(Some(T))` - A random matching object
+    /// * `Ok(None)` - No window in the lookback period has matching content
+    /// * `Err(ServiceError)` - If storage operations fail
+    pub async fn get_tagged_object<T>(
+        &self,
+        content_type: &ContentTypeDescriptor,
+        params: &ContentParams,
+        tags: &[(&str, &str)],
+        session_id: Option<&str>,
+    ) -> Result<Option<T>, ServiceError>
+    where
+        T: for<'de> Deserialize<'de>,
+    {
+        let window = self.cache_policies.for_content_type(&content_type.prefix).window;
+
+        for dt in window.lookback(Utc::now()) {
+            let folder_path = self.format_timed_prefix(&dt, content_type, params);
+            let objects = self.object_store.list_objects(&folder_path).await?;
+            let mut objects = self.without_quarantined(objects).await?;
+            for (field, value) in tags {
+                objects = tags::matching(&self.kv_store, objects, field, value).await?;
+            }
+
+            if let Some(contents) = self.pick_object(&folder_path, &objects, session_id).await? {
+                return Ok(Some(contents));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Picks one of a folder's objects at random, weighted by rating and preferring
+    /// ones the session hasn't seen, and loads it
+    ///
+    /// Returns `None` only if `objects` is empty.
+    async fn pick_object<T>(
+        &self,
+        folder_path: &str,
+        objects: &[StoredObject],
+        session_id: Option<&str>,
+    ) -> Result<Option<T>, ServiceError>
+    where
+        T: for<'de> Deserialize<'de>,
+    {
+        // Poorly rated objects are picked less often; ratings only shift the odds,
+        // so serve anyway if they can't be loaded
+        let ratings = match feedback::ratings(&self.kv_store, objects).await {
+            Ok(ratings) => ratings,
+            Err(e) => {
+                warn!("Failed to load ratings for {}: {}", folder_path, e);
+                Ratings::default()
+            }
+        };
+
+        // Pick a random object from existing ones, preferring ones the session
+        // hasn't seen
+        let key = match session_id {
+            Some(session_id) => {
+                let mut history = ServedHistory::load(&self.kv_store, session_id).await?;
+                let key = match history.pick(objects, &ratings) {
+                    Some(object) => object.key.clone(),
+                    None => return Ok(None),
+                };
+                history.record(&key);
+                if let Err(e) = history.save(&self.kv_store, session_id).await {
+                    warn!("Failed to save served history for {}: {}", session_id, e);
+                }
+                key
+            }
+            None => match ratings.weighted_pick(&objects.iter().collect::<Vec<_>>()) {
+                Some(object) => object.key.clone(),
+                None => return Ok(None),
+            },
+        };
+
+        // Fetch and parse the object
+        let body_bytes = self.object_store.get_object(&key).await?;
+        let contents: T = serde_json::from_slice(&body_bytes)?;
+
+        Ok(Some(contents))
+    }
+
+    /// Gets a random cached object from the most recent window that has any
+    ///
+    /// Used when new content can't be generated, so any cached content is better than
+    /// none. Starts with the current window, however few objects it holds, then reaches
+    /// back through earlier windows from the last day.
+    ///
+    /// # Returns
+    /// * `Ok(Some(T))` - A random object from the most recent non-empty window
+    /// * `Ok(None)` - No window in the lookback period has cached content
+    /// * `Err(ServiceError)` - If storage operations fail
+    pub async fn get_stale_object<T>(
+        &self,
+        content_type: &ContentTypeDescriptor,
+        params: &ContentParams,
+    ) -> Result<Option<T>, ServiceError>
+    where
+        T: for<'de> Deserialize<'de>,
+    {
+        let window = self.cache_policies.for_content_type(&content_type.prefix).window;
+
+        for dt in window.lookback(Utc::now()) {
+            let folder_path = self.format_timed_prefix(&dt, content_type, params);
+            let objects = self.object_store.list_objects(&folder_path).await?;
+            let objects = self.without_quarantined(objects).await?;
+
+            if objects.is_empty() {
+                continue;
+            }
+
+            let key = &objects[rand::random::<usize>() % objects.len()].key;
+            let body_bytes = self.object_store.get_object(key).await?;
+
+            return Ok(Some(serde_json::from_slice(&body_bytes)?));
+        }
+
+        Ok(None)
+    }
+
+    /// Drops flagged objects, which aren't served until an admin reviews them
+    ///
+    /// Fails rather than serving objects whose quarantine status is unknown.
+    async fn without_quarantined(
+        &self,
+        mut objects: Vec<StoredObject>,
+    ) -> Result<Vec<StoredObject>, ServiceError> {
+        let quarantined = flags::quarantined(&self.kv_store, &objects).await?;
+        objects.retain(|object| !quarantined.contains(&object.key));
+        Ok(objects)
+    }
+
     /// Stores an object in storage with a time-based key
     ///
     /// Objects are stored with keys in the format:
-    /// `{content_type_prefix}/{YYYY-MM-DD-HH}/{guid}.json`
+    /// `{content_type_prefix}/{params_partition}{window}/{guid}.json`, where the window
+    /// is `YYYY-MM-DD-HH` or `YYYY-MM-DD` depending on the cache policy
     ///
     /// # Arguments
     /// * `object` - The object to store (must be serializable)
     /// * `content_type` - The type of content being stored
+    /// * `params` - The parameters the content was generated with
     ///
     /// # Returns
-    /// * `Ok(())` - If the object was successfully stored
+    /// * `Ok(String)` - The key the object was stored under
     /// * `Err(ServiceError)` - If serialization or storage operations fail
     pub async fn store_timed_object<T>(
         &self,
         object: &T,
-        content_type: ContentType,
-    ) -> Result<(), ServiceError>
+        content_type: &ContentTypeDescriptor,
+        params: &ContentParams,
+    ) -> Result<String, ServiceError>
     where
         T: Serialize + Sync,
     {
-        let now = Utc::now();
-        let folder_path = Self::format_timed_prefix(&now, content_type);
-        let guid = Uuid::new_v4();
-        let key = format!("{}{}.json", folder_path, guid);
-
+        let key = self.new_timed_key(content_type, params);
         let json_data = serde_json::to_string(object)?;
 
         self.object_store.put_object(&key, json_data.into_bytes()).await?;
 
-        Ok(())
+        Ok(key)
+    }
+
+    /// Returns a new key in the current window's folder
+    fn new_timed_key(&self, content_type: &ContentTypeDescriptor, params: &ContentParams) -> String {
+        let folder_path = self.format_timed_prefix(&Utc::now(), content_type, params);
+        format!("{}{}.json", folder_path, Uuid::new_v4())
+    }
+
+    /// Narrates a newly stored object in the background, if its content type is
+    /// narrated, so the response isn't held up by speech synthesis
+    fn spawn_narration(&self, descriptor: &ContentTypeDescriptor, key: &str, value: &serde_json::Value) {
+        let Some(text) = descriptor
+            .narration_field
+            .as_ref()
+            .and_then(|field| value.get(field))
+            .and_then(|text| text.as_str())
+        else {
+            return;
+        };
+
+        let narrator = self.narrator.clone();
+        let object_store = self.object_store.clone();
+        let (key, text) = (key.to_string(), text.to_string());
+        tokio::spawn(async move {
+            match narration::narrate_and_store(narrator.as_ref(), &object_store, &key, &text).await {
+                Ok(()) => metrics::increment("narration.stored"),
+                Err(e) => warn!("Failed to narrate {}: {}", key, e),
+            }
+        });
+    }
+
+    /// Text of generated content to illustrate, if illustrations are enabled and
+    /// its content type is illustrated
+    fn illustration_source(&self, descriptor: &ContentTypeDescriptor, value: &serde_json::Value) -> Option<String> {
+        if !self.config.illustrations {
+            return None;
+        }
+        let (field, _) = descriptor.illustration_fields.as_ref()?;
+        value.get(field)?.as_str().map(str::to_string)
+    }
+
+    /// Draws an illustration for a newly stored object in the background, so the
+    /// response isn't held up by image generation
+    fn spawn_illustration(&self, object_key: &str, text: String) {
+        let illustrator = self.illustrator.clone();
+        let object_store = self.object_store.clone();
+        let key = illustration::illustration_key(object_key);
+        tokio::spawn(async move {
+            match illustration::illustrate_and_store(illustrator.as_ref(), &object_store, &key, &text).await {
+                Ok(()) => metrics::increment("illustration.stored"),
+                Err(e) => warn!("Failed to illustrate {}: {}", key, e),
+            }
+        });
     }
 
     /// Formats the storage prefix with content type and timestamp
     ///
-    /// Format: `{content_type_prefix}/{YYYY-MM-DD-HH}/`
+    /// Format: `{content_type_prefix}/{params_partition}{window}/`, with the window
+    /// formatted according to the content type's cache policy
     ///
     /// # Arguments
     /// * `dt` - The datetime to format
     /// * `content_type` - The content type for the prefix
+    /// * `params` - The parameters partitioning the content type's cache
     ///
     /// # Returns
-    /// A formatted string like "reading/2025-10-11-14/"
-    fn format_timed_prefix(dt: &DateTime<Utc>, content_type: ContentType) -> String {
-        format!("{}/{}/", content_type.prefix(), dt.format("%Y-%m-%d-%H"))
+    /// A formatted string like "reading/grade-3/2025-10-11-14/"
+    fn format_timed_prefix(
+        &self,
+        dt: &DateTime<Utc>,
+        content_type: &ContentTypeDescriptor,
+        params: &ContentParams,
+    ) -> String {
+        let window = self.cache_policies.for_content_type(&content_type.prefix).window;
+        format!(
+            "{}/{}{}/",
+            content_type.prefix,
+            params.partition(),
+            window.format(dt)
+        )
     }
 
     /// Generates content using OpenAI with structured JSON output
     ///
     /// This method uses OpenAI's structured output feature to generate content
-    /// that strictly adheres to the provided type's JSON schema.
+    /// that strictly adheres to the provided JSON schema. If the output fails to parse,
+    /// the model is shown its output and the error and asked to repair it, up to
+    /// MAX_REPAIR_ATTEMPTS times.
     ///
     /// # Type Parameters
-    /// * `T` - The type of content to generate. Must implement Serialize, Deserialize, and JsonSchema.
+    /// * `T` - The type to parse the generated content into. Must match the schema.
     ///
     /// # Arguments
     /// * `prompt_config` - The prompt configuration containing model, system context, and user prompt
-    /// * `schema_name` - A name for the JSON schema (e.g., "ReadingContents")
-    /// * `schema_description` - A description of what the schema represents
+    /// * `schema` - The JSON schema, with its name and description, the output must follow
     ///
     /// # Returns
     /// * `Ok(T)` - The generated content parsed into type T
@@ -215,82 +1170,420 @@ impl<S: ObjectStore, K: KeyValueStore> AppState<S, K> {
     pub async fn generate_content<T>(
         &self,
         prompt_config: &PromptConfig,
-        schema_name: &str,
-        schema_description: &str,
+        schema: &ContentSchema,
     ) -> Result<T, ServiceError>
     where
-        T: for<'de> Deserialize<'de> + Serialize + schemars::JsonSchema,
+        T: for<'de> Deserialize<'de>,
+    {
+        let value = self.generate_json::<T>(prompt_config, schema).await?;
+        Ok(serde_json::from_value(value)?)
+    }
+
+    /// Like `generate_content`, but returns the JSON the model wrote rather than
+    /// parsing it into `T`
+    ///
+    /// Output is still repaired until it parses into `T`. Fields `T` keeps out of
+    /// responses, such as answers, are kept, so they're validated and stored along
+    /// with the rest.
+    async fn generate_json<T>(
+        &self,
+        prompt_config: &PromptConfig,
+        schema: &ContentSchema,
+    ) -> Result<serde_json::Value, ServiceError>
+    where
+        T: for<'de> Deserialize<'de>,
     {
-        // Generate JSON schema for the type T
-        let schema = schema_for!(T);
-        let schema_value = serde_json::to_value(schema).map_err(|e| {
-            ServiceError::ConfigError(format!("Failed to serialize schema: {}", e))
-        })?;
-
-        // Create JSON schema response format
-        let json_schema = ResponseFormatJsonSchema {
-            description: Some(schema_description.to_string()),
-            name: schema_name.to_string(),
-            schema: Some(schema_value),
-            strict: Some(true),
+        let mut messages = prompt_messages(prompt_config);
+
+        let model = self.config.model_for(&prompt_config.name, &prompt_config.model);
+        // Every attempt of a sampled generation is audited, so repairs can be reviewed
+        // alongside the output that needed them
+        let audited = self.config.audit.should_audit(&prompt_config.name);
+        let mut attempt = 0;
+        loop {
+            let requested_at = Utc::now();
+            let started = Instant::now();
+            let result = self.respond(model, schema, &messages).await;
+
+            if audited {
+                let mut record = audit_record(prompt_config, model, schema, &messages, requested_at);
+                record.attempt = attempt;
+                record.duration_ms = started.elapsed().as_millis() as u64;
+                match &result {
+                    Ok(response) => {
+                        record.response = response.output_text.clone();
+                        record.input_tokens = response.usage.as_ref().map(|tokens| tokens.input_tokens);
+                        record.output_tokens = response.usage.as_ref().map(|tokens| tokens.output_tokens);
+                    }
+                    Err(e) => record.error = Some(e.to_string()),
+                }
+                self.write_audit(&record).await;
+            }
+
+            let response = result?;
+            if let Some(tokens) = &response.usage {
+                self.record_usage(&prompt_config.name, tokens).await;
+            }
+
+            // Extract the aggregated text content from the response
+            let content = response
+                .output_text
+                .as_deref()
+                .ok_or_else(|| ServiceError::OpenAIError("No text content in OpenAI response".to_string()))?;
+
+            // Check the JSON response parses into the target type
+            match serde_json::from_str::<T>(content).and_then(|_| serde_json::from_str(content)) {
+                Ok(result) => {
+                    if attempt > 0 {
+                        metrics::increment("generation.repair_succeeded");
+                    }
+                    return Ok(result);
+                }
+                Err(e) if attempt < MAX_REPAIR_ATTEMPTS => {
+                    attempt += 1;
+                    metrics::increment("generation.repair_attempts");
+                    warn!(
+                        "Generated {} failed to parse (attempt {}): {}",
+                        schema.name, attempt, e
+                    );
+
+                    // Show the model its invalid output and the error, and ask it to fix it
+                    messages.push(AuditMessage::new("assistant", content));
+                    messages.push(AuditMessage::new(
+                        "user",
+                        &format!(
+                            "Your previous response was not valid: {}. Respond again with \
+                             corrected JSON that follows the schema exactly.",
+                            e
+                        ),
+                    ));
+                }
+                Err(e) => {
+                    metrics::increment("generation.repair_failed");
+                    return Err(e.into());
+                }
+            }
+        }
+    }
+
+    /// Sends an audited request to the model again, returning the text it responds
+    /// with now
+    ///
+    /// The request uses the recorded model, schema, and messages, so the response
+    /// can be compared with the recorded one. Usage is not recorded and nothing is
+    /// audited.
+    pub async fn replay_audit(&self, record: &AuditRecord) -> Result<String, ServiceError> {
+        let schema = ContentSchema {
+            name: record.schema_name.clone(),
+            description: record.schema_description.clone(),
+            schema: record.schema.clone(),
+        };
+        let response = self.respond(&record.model, &schema, &record.messages).await?;
+        response
+            .output_text
+            .ok_or_else(|| ServiceError::OpenAIError("No text content in OpenAI response".to_string()))
+    }
+
+    /// Writes an audit record, logging rather than failing
+    async fn write_audit(&self, record: &AuditRecord) {
+        if let Err(e) = audit::write(&self.object_store, record).await {
+            warn!("Failed to write audit record for {}: {}", record.prompt, e);
+        }
+    }
+
+    /// Adds a response's tokens to the day's usage, logging rather than failing
+    async fn record_usage(&self, prompt_name: &str, tokens: &Usage) {
+        let recorded = usage::record(
+            &self.kv_store,
+            Utc::now().date_naive(),
+            prompt_name,
+            tokens.input_tokens,
+            tokens.output_tokens,
+        )
+        .await;
+        if let Err(e) = recorded {
+            warn!("Failed to record token usage for {}: {}", prompt_name, e);
+        }
+    }
+
+    /// Sends messages to the OpenAI Responses API, asking for output following a
+    /// JSON schema, and records the outcome for the circuit breaker
+    async fn respond(
+        &self,
+        model: &str,
+        schema: &ContentSchema,
+        messages: &[AuditMessage],
+    ) -> Result<Response, ServiceError> {
+        let request = response_request(model, schema, messages, false)?;
+        match self.openai_client.responses().create(request).await {
+            Ok(response) => {
+                self.llm_circuit.record_success();
+                Ok(response)
+            }
+            Err(e) => {
+                self.llm_circuit.record_failure();
+                Err(ServiceError::from_openai("OpenAI API call failed", e))
+            }
+        }
+    }
+
+    /// Like `respond`, but streams the response, sending each piece of output text
+    /// to `deltas` as it arrives
+    ///
+    /// # Returns
+    /// * `Ok((text, usage))` - The whole output text and the tokens used
+    /// * `Err(ServiceError)` - If the request fails or the response doesn't complete
+    async fn respond_streaming(
+        &self,
+        model: &str,
+        schema: &ContentSchema,
+        messages: &[AuditMessage],
+        deltas: &mpsc::UnboundedSender<String>,
+    ) -> Result<(String, Option<Usage>), ServiceError> {
+        let request = response_request(model, schema, messages, true)?;
+        let streamed = async {
+            let mut events = self
+                .openai_client
+                .responses()
+                .create_stream(request)
+                .await
+                .map_err(|e| ServiceError::from_openai("OpenAI API call failed", e))?;
+
+            let mut text = String::new();
+            while let Some(event) = events.next().await {
+                match event.map_err(|e| ServiceError::from_openai("OpenAI stream failed", e))? {
+                    ResponseEvent::ResponseOutputTextDelta(delta) => {
+                        text.push_str(&delta.delta);
+                        // The client may have gone away; the content is still cached
+                        let _ = deltas.send(delta.delta);
+                    }
+                    ResponseEvent::ResponseCompleted(completed) => {
+                        return Ok((text, completed.response.usage));
+                    }
+                    ResponseEvent::ResponseFailed(failed) => {
+                        let reason = failed.response.error.map(|e| e.message).unwrap_or_default();
+                        return Err(ServiceError::OpenAIError(format!("OpenAI response failed: {}", reason)));
+                    }
+                    ResponseEvent::ResponseIncomplete(_) => {
+                        return Err(ServiceError::OpenAIError("OpenAI response was incomplete".into()));
+                    }
+                    ResponseEvent::ResponseError(error) => {
+                        return Err(ServiceError::OpenAIError(format!("OpenAI stream error: {}", error.message)));
+                    }
+                    _ => {}
+                }
+            }
+            Err(ServiceError::OpenAIError("OpenAI stream ended before the response completed".into()))
         };
 
-        // Create text config with JSON schema format
-        let text_config = TextConfig {
-            format: TextResponseFormat::JsonSchema(json_schema),
-            verbosity: None,
+        let result = streamed.await;
+        match &result {
+            Ok(_) => self.llm_circuit.record_success(),
+            Err(_) => self.llm_circuit.record_failure(),
+        }
+        result
+    }
+}
+
+/// AppState whose backends are chosen at runtime from configuration
+pub type DynAppState = AppState<AnyObjectStore, AnyKeyValueStore>;
+
+impl DynAppState {
+    /// Loads configuration from `CONFIG_FILE` and the environment, then creates
+    /// the backends selected by `STORAGE_BACKEND` and `KV_BACKEND`
+    pub async fn from_env(content_types: ContentTypeRegistry) -> Result<Self, ServiceError> {
+        Self::from_config(Config::load()?, content_types).await
+    }
+
+    /// Creates the backends selected by a configuration
+    pub async fn from_config(
+        config: Config,
+        content_types: ContentTypeRegistry,
+    ) -> Result<Self, ServiceError> {
+        let object_store = AnyObjectStore::from_config(&config).await?;
+        let kv_store = AnyKeyValueStore::from_config(&config).await?;
+        let mailer = email::from_config(&config).await?;
+        let generation_queue = queue::from_config(&config).await?;
+
+        let mut state = Self::new(object_store, kv_store, config, content_types).await;
+        state.mailer = mailer;
+        state.generation_queue = generation_queue;
+        Ok(state)
+    }
+}
+
+/// Loads the prompt configuration (or the selected version of it) for a content
+/// type and fills in the parameters
+fn render_prompt(
+    descriptor: &ContentTypeDescriptor,
+    params: &ContentParams,
+) -> Result<PromptConfig, ServiceError> {
+    match params.prompt_version() {
+        Some(version) => prompts::get_prompt_version(&descriptor.prompt_name, version),
+        None => prompts::get_prompt(&descriptor.prompt_name),
+    }
+    .ok_or_else(|| ServiceError::ConfigError(descriptor.prompt_name.clone()))?
+    .render(&params.prompt_variables())
+}
+
+/// Builds the messages sent for a prompt: the system message, then few-shot
+/// examples, each a user request and ideal response, then the prompt
+fn prompt_messages(prompt_config: &PromptConfig) -> Vec<AuditMessage> {
+    let mut messages = vec![AuditMessage::new("system", &prompt_config.system_context)];
+    for example in &prompt_config.examples {
+        messages.push(AuditMessage::new("user", &example.user));
+        messages.push(AuditMessage::new("assistant", &example.assistant));
+    }
+    messages.push(AuditMessage::new("user", &prompt_config.prompt.text));
+    messages
+}
+
+/// Starts an audit record of a request, to be completed with its outcome
+fn audit_record(
+    prompt_config: &PromptConfig,
+    model: &str,
+    schema: &ContentSchema,
+    messages: &[AuditMessage],
+    requested_at: DateTime<Utc>,
+) -> AuditRecord {
+    let mut record = AuditRecord::new(&prompt_config.name, &prompt_config.version, model, requested_at);
+    record.schema_name = schema.name.clone();
+    record.schema_description = schema.description.clone();
+    record.schema = schema.schema.clone();
+    record.messages = messages.to_vec();
+    record
+}
+
+/// Builds a Responses API request asking for output following a JSON schema
+fn response_request(
+    model: &str,
+    schema: &ContentSchema,
+    messages: &[AuditMessage],
+    stream: bool,
+) -> Result<CreateResponse, ServiceError> {
+    // Create JSON schema response format
+    let json_schema = ResponseFormatJsonSchema {
+        description: Some(schema.description.clone()),
+        name: schema.name.clone(),
+        schema: Some(schema.schema.clone()),
+        strict: Some(true),
+    };
+
+    // Create text config with JSON schema format
+    let text_config = TextConfig {
+        format: TextResponseFormat::JsonSchema(json_schema),
+        verbosity: None,
+    };
+
+    let input = messages
+        .iter()
+        .map(|message| input_message(parse_role(&message.role)?, &message.content))
+        .collect::<Result<Vec<_>, _>>()?;
+    CreateResponseArgs::default()
+        .model(model)
+        .stream(stream)
+        .text(text_config)
+        .input(Input::Items(input))
+        .build()
+        .map_err(|e| ServiceError::OpenAIError(format!("Failed to build request: {}", e)))
+}
+
+/// Builds an input message with the given role and text
+fn input_message(role: Role, content: &str) -> Result<InputItem, ServiceError> {
+    let message = InputMessageArgs::default()
+        .role(role)
+        .content(content.to_string())
+        .build()
+        .map_err(|e| ServiceError::OpenAIError(format!("Failed to build {:?} message: {}", role, e)))?;
+
+    Ok(InputItem::Message(message))
+}
+
+/// Parses the role of an audited message
+fn parse_role(role: &str) -> Result<Role, ServiceError> {
+    match role {
+        "system" => Ok(Role::System),
+        "user" => Ok(Role::User),
+        "assistant" => Ok(Role::Assistant),
+        _ => Err(ServiceError::InvalidInput(format!("Unknown message role {:?}", role))),
+    }
+}
+
+#[cfg(test)]
+impl<S: ObjectStore, K: KeyValueStore> AppState<S, K> {
+    /// Answers model requests with `outputs` in turn, repeating the last, from a
+    /// local server, and turns off moderation, deduplication, narration, and
+    /// illustration
+    pub(crate) async fn with_stub_model(mut self, outputs: Vec<serde_json::Value>) -> Self {
+        use crate::{
+            dedup::NoopEmbedder, illustration::NoopIllustrator, moderation::NoopModerator,
+            narration::NoopNarrator,
         };
+        use axum::{Json, Router, routing::post};
+        use std::sync::Mutex;
 
-        // Create system message input item
-        let system_message = InputMessageArgs::default()
-            .role(Role::System)
-            .content(prompt_config.system_context.clone())
-            .build()
-            .map_err(|e| {
-                ServiceError::OpenAIError(format!("Failed to build system message: {}", e))
-            })?;
-
-        // Create user message input item
-        let user_message = InputMessageArgs::default()
-            .role(Role::User)
-            .content(prompt_config.prompt.text.clone())
-            .build()
-            .map_err(|e| {
-                ServiceError::OpenAIError(format!("Failed to build user message: {}", e))
-            })?;
-
-        // Create input with both messages
-        let input = Input::Items(vec![
-            InputItem::Message(system_message),
-            InputItem::Message(user_message),
-        ]);
-
-        // Create response request
-        let request = CreateResponseArgs::default()
-            .model(&prompt_config.model)
-            .stream(false)
-            .text(text_config)
-            .input(input)
-            .build()
-            .map_err(|e| ServiceError::OpenAIError(format!("Failed to build request: {}", e)))?;
-
-        // Call OpenAI Responses API
-        let response = self
-            .openai_client
-            .responses()
-            .create(request)
-            .await
-            .map_err(|e| ServiceError::OpenAIError(format!("OpenAI API call failed: {}", e)))?;
-
-        // Extract the aggregated text content from the response
-        let content = response
-            .output_text
-            .as_deref()
-            .ok_or_else(|| ServiceError::OpenAIError("No text content in OpenAI response".to_string()))?;
+        let outputs = Arc::new(Mutex::new(outputs));
+        let respond = move || async move {
+            let mut outputs = outputs.lock().unwrap();
+            let output = if outputs.len() > 1 { outputs.remove(0) } else { outputs[0].clone() };
+            Json(serde_json::json!({
+                "created_at": 0,
+                "id": "resp_stub",
+                "model": "stub",
+                "object": "response",
+                "output": [],
+                "output_text": output.to_string(),
+                "status": "completed",
+            }))
+        };
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let api_base = format!("http://{}", listener.local_addr().unwrap());
+        let router = Router::new().route("/responses", post(respond));
+        tokio::spawn(async move { axum::serve(listener, router).await });
+
+        self.openai_client = OpenAIClient::with_config(OpenAIConfig::new().with_api_base(api_base));
+        self.with_moderator(NoopModerator)
+            .with_embedder(NoopEmbedder)
+            .with_narrator(NoopNarrator)
+            .with_illustrator(NoopIllustrator)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{cloze, keyvalue::MemoryKeyValueStore, storage::MemoryObjectStore};
 
-        // Parse the JSON response into the target type
-        let result: T = serde_json::from_str(content)?;
+    #[tokio::test]
+    async fn open_circuit_stops_generation_before_taking_leases() {
+        let state = AppState::new(
+            MemoryObjectStore::new(),
+            MemoryKeyValueStore::new(),
+            Config::default(),
+            ContentTypeRegistry::new().register(cloze::descriptor()),
+        )
+        .await
+        .with_stub_model(vec![serde_json::json!({})])
+        .await;
+        for _ in 0..LLM_FAILURE_THRESHOLD {
+            state.llm_circuit.record_failure();
+        }
+        let descriptor = state.content_types.get(cloze::CLOZE_PREFIX).unwrap();
+        let params = &descriptor.default_params;
+
+        let circuit_open = |result: Result<_, ServiceError>| {
+            matches!(result, Err(ServiceError::OpenAIError(reason)) if reason.contains("circuit breaker"))
+        };
+        let served = state.get_or_generate::<serde_json::Value>(descriptor, params, None).await;
+        assert!(circuit_open(served.map(|_| ())));
+        assert!(state.try_acquire_generation_slot(descriptor, params).await.unwrap());
 
-        Ok(result)
+        assert!(circuit_open(state.generate_batch(descriptor, params, 1).await.map(|_| ())));
+        let job = GenerationJob::new(
+            &descriptor.prefix,
+            &state.format_timed_prefix(&Utc::now(), descriptor, params),
+            params,
+        );
+        assert!(circuit_open(state.run_generation_job(&job).await.map(|_| ())));
     }
 }
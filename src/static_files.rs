@@ -0,0 +1,95 @@
+use axum::{
+    body::Body,
+    extract::Path,
+    http::{HeaderMap, StatusCode, header},
+    response::Response,
+};
+use include_dir::{Dir, include_dir};
+use tracing::error;
+
+use crate::{ServiceError, etag, problem::ErrorResponse, storage};
+
+/// Pages and other static files, embedded so the binary can run from any directory
+static STATIC_DIR: Dir = include_dir!("$CARGO_MANIFEST_DIR/static");
+
+/// How long browsers may reuse static files other than pages without checking back
+const MAX_AGE_SECS: u64 = 3600;
+
+/// MIME type of a static file, from its extension
+fn mime_type(path: &str) -> String {
+    match storage::content_type_for_key(path) {
+        Some(mime) if mime.starts_with("text/") => format!("{}; charset=utf-8", mime),
+        Some(mime) => mime.to_string(),
+        None => "application/octet-stream".to_string(),
+    }
+}
+
+/// Cache-Control header of a static file
+///
+/// Pages are revalidated on every load, so a new deploy shows up straight away;
+/// other files may be reused for a while.
+fn cache_control(path: &str) -> String {
+    if path.ends_with(".html") {
+        "no-cache".to_string()
+    } else {
+        format!("public, max-age={}", MAX_AGE_SECS)
+    }
+}
+
+/// Serves an embedded static file, answering 304 if the client's copy is current
+pub fn serve(path: &str, headers: &HeaderMap) -> Result<Response, ErrorResponse> {
+    let file = STATIC_DIR
+        .get_file(path)
+        .ok_or_else(|| ServiceError::NotFound("File not found".to_string()))?;
+    let etag = etag::entity_tag(file.contents());
+
+    let builder = Response::builder()
+        .header(header::ETAG, &etag)
+        .header(header::CACHE_CONTROL, cache_control(path));
+    let unchanged = headers
+        .get(header::IF_NONE_MATCH)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|header| etag::none_match(header, &etag));
+    let response = if unchanged {
+        builder.status(StatusCode::NOT_MODIFIED).body(Body::empty())
+    } else {
+        builder
+            .header(header::CONTENT_TYPE, mime_type(path))
+            .body(Body::from(file.contents()))
+    };
+
+    response.map_err(|e| {
+        error!("Failed to build response for {}: {}", path, e);
+        ErrorResponse::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "internal_error",
+            "Internal server error",
+        )
+    })
+}
+
+/// Serves a file from the `static` directory
+pub async fn static_file(Path(path): Path<String>, headers: HeaderMap) -> Result<Response, ErrorResponse> {
+    serve(&path, &headers)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serves_embedded_pages_with_revalidation() {
+        let response = serve("home.html", &HeaderMap::new()).unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "text/html; charset=utf-8");
+        assert_eq!(response.headers()[header::CACHE_CONTROL], "no-cache");
+
+        let mut headers = HeaderMap::new();
+        headers.insert(header::IF_NONE_MATCH, response.headers()[header::ETAG].clone());
+        assert_eq!(serve("home.html", &headers).unwrap().status(), StatusCode::NOT_MODIFIED);
+
+        assert!(serve("../Cargo.toml", &HeaderMap::new()).is_err());
+        assert_eq!(mime_type("app.js"), "text/javascript; charset=utf-8");
+        assert_eq!(cache_control("logo.png"), "public, max-age=3600");
+    }
+}
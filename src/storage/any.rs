@@ -0,0 +1,87 @@
+use async_trait::async_trait;
+
+use super::{
+    DiskObjectStore, MemoryObjectStore, ObjectPage, ObjectStore, ObjectStream, S3ObjectStore,
+    StoredObject,
+};
+use crate::ServiceError;
+use crate::config::{Config, StorageBackend};
+
+/// Object store backend chosen at runtime
+///
+/// Lets `main` pick a backend from configuration while `AppState` keeps a single
+/// concrete store type.
+#[derive(Clone)]
+pub enum AnyObjectStore {
+    S3(S3ObjectStore),
+    Disk(DiskObjectStore),
+    Memory(MemoryObjectStore),
+}
+
+/// Calls the same method on whichever backend the store holds
+macro_rules! dispatch {
+    ($self:ident, $store:ident => $call:expr) => {
+        match $self {
+            AnyObjectStore::S3($store) => $call,
+            AnyObjectStore::Disk($store) => $call,
+            AnyObjectStore::Memory($store) => $call,
+        }
+    };
+}
+
+impl AnyObjectStore {
+    /// Creates the backend selected by `config.storage_backend()`
+    pub async fn from_config(config: &Config) -> Result<Self, ServiceError> {
+        Ok(match config.storage_backend() {
+            StorageBackend::S3 => {
+                let aws_config =
+                    aws_config::load_defaults(aws_config::BehaviorVersion::latest()).await;
+                Self::S3(S3ObjectStore::builder().bucket(&config.bucket).build(&aws_config))
+            }
+            StorageBackend::Disk => Self::Disk(match &config.disk_path {
+                Some(path) => DiskObjectStore::with_base_path(path.clone()),
+                None => DiskObjectStore::new(),
+            }),
+            StorageBackend::Memory => Self::Memory(MemoryObjectStore::new()),
+        })
+    }
+}
+
+#[async_trait]
+impl ObjectStore for AnyObjectStore {
+    async fn put_object(&self, key: &str, data: Vec<u8>) -> Result<(), ServiceError> {
+        dispatch!(self, store => store.put_object(key, data).await)
+    }
+
+    async fn get_object(&self, key: &str) -> Result<Vec<u8>, ServiceError> {
+        dispatch!(self, store => store.get_object(key).await)
+    }
+
+    async fn put_object_stream(&self, key: &str, stream: ObjectStream) -> Result<(), ServiceError> {
+        dispatch!(self, store => store.put_object_stream(key, stream).await)
+    }
+
+    async fn get_object_stream(&self, key: &str) -> Result<ObjectStream, ServiceError> {
+        dispatch!(self, store => store.get_object_stream(key).await)
+    }
+
+    async fn list_objects_page(
+        &self,
+        prefix: &str,
+        continuation_token: Option<String>,
+    ) -> Result<ObjectPage, ServiceError> {
+        dispatch!(self, store => store.list_objects_page(prefix, continuation_token).await)
+    }
+
+    async fn delete_object(&self, key: &str) -> Result<(), ServiceError> {
+        dispatch!(self, store => store.delete_object(key).await)
+    }
+
+    async fn head_object(&self, key: &str) -> Result<Option<StoredObject>, ServiceError> {
+        dispatch!(self, store => store.head_object(key).await)
+    }
+
+    async fn delete_objects_with_prefix(&self, prefix: &str) -> Result<usize, ServiceError> {
+        dispatch!(self, store => store.delete_objects_with_prefix(prefix).await)
+    }
+}
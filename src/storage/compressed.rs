@@ -0,0 +1,137 @@
+use async_trait::async_trait;
+use flate2::{Compression, read::GzDecoder, write::GzEncoder};
+use std::io::{Read, Write};
+
+use super::{ObjectPage, ObjectStore, StoredObject, content_type_for_key};
+use crate::ServiceError;
+
+/// Leading bytes of every gzip stream
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// Object store wrapper that gzips objects on put and decompresses them on get
+///
+/// Compressed objects are recognized by the gzip magic bytes rather than by key,
+/// so objects written before compression was enabled are still read as-is and
+/// keys (and therefore cache folders) are unchanged. Media that is already
+/// compressed (audio, images) is stored without recompressing. Sizes reported
+/// by listings are the stored, compressed sizes.
+#[derive(Clone)]
+pub struct CompressedObjectStore<S: ObjectStore> {
+    inner: S,
+    level: Compression,
+}
+
+impl<S: ObjectStore> CompressedObjectStore<S> {
+    /// Wraps a store, compressing with the default gzip level
+    pub fn new(inner: S) -> Self {
+        Self {
+            inner,
+            level: Compression::default(),
+        }
+    }
+
+    /// Sets the gzip compression level (0-9)
+    pub fn with_level(mut self, level: u32) -> Self {
+        self.level = Compression::new(level.min(9));
+        self
+    }
+}
+
+/// Whether objects with this key are worth compressing
+fn is_compressible(key: &str) -> bool {
+    !matches!(
+        content_type_for_key(key),
+        Some(content_type) if content_type.starts_with("audio/") || content_type.starts_with("image/")
+    )
+}
+
+/// Gzips data at the given level
+fn compress(data: &[u8], level: Compression) -> Result<Vec<u8>, ServiceError> {
+    let mut encoder = GzEncoder::new(Vec::new(), level);
+    encoder.write_all(data)?;
+    Ok(encoder.finish()?)
+}
+
+/// Decompresses gzipped data, passing through data that isn't gzipped
+fn decompress(data: Vec<u8>) -> Result<Vec<u8>, ServiceError> {
+    if !data.starts_with(&GZIP_MAGIC) {
+        return Ok(data);
+    }
+
+    let mut decompressed = Vec::new();
+    GzDecoder::new(data.as_slice()).read_to_end(&mut decompressed)?;
+    Ok(decompressed)
+}
+
+#[async_trait]
+impl<S: ObjectStore> ObjectStore for CompressedObjectStore<S> {
+    async fn put_object(&self, key: &str, data: Vec<u8>) -> Result<(), ServiceError> {
+        let data = if is_compressible(key) {
+            compress(&data, self.level)?
+        } else {
+            data
+        };
+
+        self.inner.put_object(key, data).await
+    }
+
+    async fn get_object(&self, key: &str) -> Result<Vec<u8>, ServiceError> {
+        decompress(self.inner.get_object(key).await?)
+    }
+
+    async fn list_objects_page(
+        &self,
+        prefix: &str,
+        continuation_token: Option<String>,
+    ) -> Result<ObjectPage, ServiceError> {
+        self.inner.list_objects_page(prefix, continuation_token).await
+    }
+
+    async fn delete_object(&self, key: &str) -> Result<(), ServiceError> {
+        self.inner.delete_object(key).await
+    }
+
+    async fn head_object(&self, key: &str) -> Result<Option<StoredObject>, ServiceError> {
+        self.inner.head_object(key).await
+    }
+
+    async fn delete_objects_with_prefix(&self, prefix: &str) -> Result<usize, ServiceError> {
+        self.inner.delete_objects_with_prefix(prefix).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::MemoryObjectStore;
+
+    #[tokio::test]
+    async fn compresses_on_put_and_decompresses_on_get() {
+        let inner = MemoryObjectStore::new();
+        let store = CompressedObjectStore::new(inner.clone());
+        let story = format!(r#"{{"story": "{}"}}"#, "Once upon a time. ".repeat(50)).into_bytes();
+
+        store.put_object("reading/a.json", story.clone()).await.unwrap();
+
+        let stored = inner.get_object("reading/a.json").await.unwrap();
+        assert!(stored.starts_with(&GZIP_MAGIC));
+        assert!(stored.len() < story.len());
+        assert_eq!(store.get_object("reading/a.json").await.unwrap(), story);
+    }
+
+    #[tokio::test]
+    async fn reads_uncompressed_objects_as_is() {
+        let inner = MemoryObjectStore::new();
+        inner.put_object("reading/old.json", b"{}".to_vec()).await.unwrap();
+
+        let store = CompressedObjectStore::new(inner);
+        assert_eq!(store.get_object("reading/old.json").await.unwrap(), b"{}");
+    }
+
+    #[test]
+    fn skips_already_compressed_media() {
+        assert!(is_compressible("reading/a.json"));
+        assert!(!is_compressible("assets/audio/a.mp3"));
+        assert!(!is_compressible("assets/images/a.png"));
+    }
+}
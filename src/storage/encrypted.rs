@@ -0,0 +1,203 @@
+use aes_gcm::{
+    Aes256Gcm, Key, Nonce,
+    aead::{Aead, AeadCore, KeyInit, OsRng, Payload},
+};
+use async_trait::async_trait;
+use aws_sdk_kms::{Client as KmsClient, primitives::Blob};
+use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
+
+use super::{ObjectPage, ObjectStore, StoredObject};
+use crate::ServiceError;
+
+/// Environment variable holding a base64-encoded 256-bit key
+pub const ENCRYPTION_KEY_ENV: &str = "OBJECT_ENCRYPTION_KEY";
+
+/// Leading bytes of every encrypted object, followed by the nonce and ciphertext
+const ENCRYPTED_MAGIC: &[u8; 4] = b"TKE1";
+
+/// Length of an AES-GCM nonce in bytes
+const NONCE_LEN: usize = 12;
+
+/// Object store wrapper that encrypts objects at rest with AES-256-GCM
+///
+/// Each object is encrypted with a fresh random nonce, and its key is used as
+/// associated data so an encrypted object can't be copied to another key and
+/// still decrypt. Objects without the encrypted header (written before
+/// encryption was enabled) are read as-is. When combining with compression,
+/// wrap this store in `CompressedObjectStore` so data is compressed before it
+/// is encrypted.
+#[derive(Clone)]
+pub struct EncryptedObjectStore<S: ObjectStore> {
+    inner: S,
+    cipher: Aes256Gcm,
+}
+
+impl<S: ObjectStore> EncryptedObjectStore<S> {
+    /// Wraps a store, encrypting with the given 256-bit key
+    pub fn new(inner: S, key: [u8; 32]) -> Self {
+        Self {
+            inner,
+            cipher: Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key)),
+        }
+    }
+
+    /// Wraps a store using the base64-encoded key in `OBJECT_ENCRYPTION_KEY`
+    pub fn from_env(inner: S) -> Result<Self, ServiceError> {
+        let encoded = std::env::var(ENCRYPTION_KEY_ENV).map_err(|_| {
+            ServiceError::ConfigError(format!("{} must be set", ENCRYPTION_KEY_ENV))
+        })?;
+
+        Ok(Self::new(inner, decode_key(&decode_base64(&encoded)?)?))
+    }
+
+    /// Wraps a store using a data key encrypted with AWS KMS
+    ///
+    /// `encrypted_key` is the ciphertext of a 256-bit data key, as returned by
+    /// KMS `GenerateDataKey`; it is decrypted once at startup.
+    pub async fn from_kms(
+        inner: S,
+        client: &KmsClient,
+        encrypted_key: Vec<u8>,
+    ) -> Result<Self, ServiceError> {
+        let output = client
+            .decrypt()
+            .ciphertext_blob(Blob::new(encrypted_key))
+            .send()
+            .await
+            .map_err(|e| ServiceError::ConfigError(format!("Failed to decrypt data key: {}", e)))?;
+
+        let plaintext = output.plaintext().ok_or_else(|| {
+            ServiceError::ConfigError("KMS returned no plaintext data key".to_string())
+        })?;
+
+        Ok(Self::new(inner, decode_key(plaintext.as_ref())?))
+    }
+
+    /// Encrypts data stored under the given key
+    fn encrypt(&self, key: &str, data: &[u8]) -> Result<Vec<u8>, ServiceError> {
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher
+            .encrypt(&nonce, Payload { msg: data, aad: key.as_bytes() })
+            .map_err(|_| ServiceError::ConfigError(format!("Failed to encrypt {}", key)))?;
+
+        let mut encrypted = Vec::with_capacity(ENCRYPTED_MAGIC.len() + NONCE_LEN + ciphertext.len());
+        encrypted.extend_from_slice(ENCRYPTED_MAGIC);
+        encrypted.extend_from_slice(&nonce);
+        encrypted.extend_from_slice(&ciphertext);
+        Ok(encrypted)
+    }
+
+    /// Decrypts data stored under the given key, passing through unencrypted data
+    fn decrypt(&self, key: &str, data: Vec<u8>) -> Result<Vec<u8>, ServiceError> {
+        let Some(body) = data.strip_prefix(ENCRYPTED_MAGIC) else {
+            return Ok(data);
+        };
+
+        if body.len() < NONCE_LEN {
+            return Err(ServiceError::ConfigError(format!("Encrypted object {} is truncated", key)));
+        }
+        let (nonce, ciphertext) = body.split_at(NONCE_LEN);
+
+        self.cipher
+            .decrypt(
+                Nonce::from_slice(nonce),
+                Payload { msg: ciphertext, aad: key.as_bytes() },
+            )
+            .map_err(|_| ServiceError::ConfigError(format!("Failed to decrypt {}", key)))
+    }
+}
+
+/// Decodes a base64-encoded key, reporting failures as configuration errors
+fn decode_base64(encoded: &str) -> Result<Vec<u8>, ServiceError> {
+    BASE64
+        .decode(encoded.trim())
+        .map_err(|e| ServiceError::ConfigError(format!("{} is not valid base64: {}", ENCRYPTION_KEY_ENV, e)))
+}
+
+/// Checks that key material is exactly 256 bits
+fn decode_key(bytes: &[u8]) -> Result<[u8; 32], ServiceError> {
+    bytes.try_into().map_err(|_| {
+        ServiceError::ConfigError(format!(
+            "Encryption key must be 32 bytes, got {}",
+            bytes.len()
+        ))
+    })
+}
+
+#[async_trait]
+impl<S: ObjectStore> ObjectStore for EncryptedObjectStore<S> {
+    async fn put_object(&self, key: &str, data: Vec<u8>) -> Result<(), ServiceError> {
+        let encrypted = self.encrypt(key, &data)?;
+        self.inner.put_object(key, encrypted).await
+    }
+
+    async fn get_object(&self, key: &str) -> Result<Vec<u8>, ServiceError> {
+        self.decrypt(key, self.inner.get_object(key).await?)
+    }
+
+    async fn list_objects_page(
+        &self,
+        prefix: &str,
+        continuation_token: Option<String>,
+    ) -> Result<ObjectPage, ServiceError> {
+        self.inner.list_objects_page(prefix, continuation_token).await
+    }
+
+    async fn delete_object(&self, key: &str) -> Result<(), ServiceError> {
+        self.inner.delete_object(key).await
+    }
+
+    async fn head_object(&self, key: &str) -> Result<Option<StoredObject>, ServiceError> {
+        self.inner.head_object(key).await
+    }
+
+    async fn delete_objects_with_prefix(&self, prefix: &str) -> Result<usize, ServiceError> {
+        self.inner.delete_objects_with_prefix(prefix).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::MemoryObjectStore;
+
+    #[tokio::test]
+    async fn encrypts_on_put_and_decrypts_on_get() {
+        let inner = MemoryObjectStore::new();
+        let store = EncryptedObjectStore::new(inner.clone(), [7; 32]);
+
+        store.put_object("progress/child.json", b"{\"score\": 3}".to_vec()).await.unwrap();
+
+        let stored = inner.get_object("progress/child.json").await.unwrap();
+        assert!(stored.starts_with(ENCRYPTED_MAGIC));
+        assert!(!stored.windows(5).any(|w| w == b"score"));
+        assert_eq!(
+            store.get_object("progress/child.json").await.unwrap(),
+            b"{\"score\": 3}"
+        );
+    }
+
+    #[tokio::test]
+    async fn rejects_moved_objects_and_wrong_keys() {
+        let inner = MemoryObjectStore::new();
+        let store = EncryptedObjectStore::new(inner.clone(), [7; 32]);
+        store.put_object("progress/a.json", b"{}".to_vec()).await.unwrap();
+
+        let stored = inner.get_object("progress/a.json").await.unwrap();
+        inner.put_object("progress/b.json", stored).await.unwrap();
+        assert!(store.get_object("progress/b.json").await.is_err());
+
+        let other = EncryptedObjectStore::new(inner, [8; 32]);
+        assert!(other.get_object("progress/a.json").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn reads_unencrypted_objects_as_is() {
+        let inner = MemoryObjectStore::new();
+        inner.put_object("reading/old.json", b"{}".to_vec()).await.unwrap();
+
+        let store = EncryptedObjectStore::new(inner, [7; 32]);
+        assert_eq!(store.get_object("reading/old.json").await.unwrap(), b"{}");
+    }
+}
@@ -0,0 +1,103 @@
+use async_trait::async_trait;
+use std::future::Future;
+
+use super::{ObjectPage, ObjectStore, ObjectStream, StoredObject};
+use crate::{ServiceError, metrics};
+
+/// Object store wrapper that records call counts, errors, and latency per operation
+///
+/// See `metrics::record_operation` for the counters maintained.
+#[derive(Clone)]
+pub struct InstrumentedObjectStore<S: ObjectStore> {
+    inner: S,
+    name: String,
+}
+
+impl<S: ObjectStore> InstrumentedObjectStore<S> {
+    /// Wraps a store, naming its metrics `{name}.{operation}.*`
+    pub fn new(inner: S, name: &str) -> Self {
+        Self {
+            inner,
+            name: name.to_string(),
+        }
+    }
+
+    /// Runs an operation and records its outcome
+    async fn record<T>(
+        &self,
+        operation: &str,
+        future: impl Future<Output = Result<T, ServiceError>>,
+    ) -> Result<T, ServiceError> {
+        metrics::record_operation(&self.name, operation, future).await
+    }
+}
+
+#[async_trait]
+impl<S: ObjectStore> ObjectStore for InstrumentedObjectStore<S> {
+    async fn put_object(&self, key: &str, data: Vec<u8>) -> Result<(), ServiceError> {
+        self.record("put_object", self.inner.put_object(key, data)).await
+    }
+
+    async fn get_object(&self, key: &str) -> Result<Vec<u8>, ServiceError> {
+        self.record("get_object", self.inner.get_object(key)).await
+    }
+
+    async fn put_object_stream(&self, key: &str, stream: ObjectStream) -> Result<(), ServiceError> {
+        self.record("put_object_stream", self.inner.put_object_stream(key, stream))
+            .await
+    }
+
+    async fn get_object_stream(&self, key: &str) -> Result<ObjectStream, ServiceError> {
+        self.record("get_object_stream", self.inner.get_object_stream(key))
+            .await
+    }
+
+    async fn list_objects_page(
+        &self,
+        prefix: &str,
+        continuation_token: Option<String>,
+    ) -> Result<ObjectPage, ServiceError> {
+        self.record(
+            "list_objects_page",
+            self.inner.list_objects_page(prefix, continuation_token),
+        )
+        .await
+    }
+
+    async fn delete_object(&self, key: &str) -> Result<(), ServiceError> {
+        self.record("delete_object", self.inner.delete_object(key)).await
+    }
+
+    async fn head_object(&self, key: &str) -> Result<Option<StoredObject>, ServiceError> {
+        self.record("head_object", self.inner.head_object(key)).await
+    }
+
+    async fn delete_objects_with_prefix(&self, prefix: &str) -> Result<usize, ServiceError> {
+        self.record(
+            "delete_objects_with_prefix",
+            self.inner.delete_objects_with_prefix(prefix),
+        )
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::MemoryObjectStore;
+
+    #[tokio::test]
+    async fn records_calls_and_errors() {
+        let store = InstrumentedObjectStore::new(MemoryObjectStore::new(), "test_storage");
+
+        store.put_object("a.json", Vec::new()).await.unwrap();
+        store.get_object("a.json").await.unwrap();
+        assert!(store.get_object("missing.json").await.is_err());
+
+        let snapshot = metrics::snapshot();
+        assert_eq!(snapshot["test_storage.put_object.calls"], 1);
+        assert_eq!(snapshot["test_storage.get_object.calls"], 2);
+        assert_eq!(snapshot["test_storage.get_object.errors"], 1);
+        assert!(!snapshot.contains_key("test_storage.put_object.errors"));
+    }
+}
@@ -0,0 +1,1024 @@
+use async_trait::async_trait;
+use aws_sdk_s3::Client as S3Client;
+use aws_sdk_s3::types::{CompletedMultipartUpload, CompletedPart, Delete, ObjectIdentifier};
+use bytes::Bytes;
+use chrono::{DateTime, Utc};
+use futures::{Stream, StreamExt, TryStreamExt};
+use std::collections::BTreeMap;
+use std::ops::Bound;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::Arc;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::RwLock;
+use tokio_util::io::ReaderStream;
+use tracing::warn;
+use crate::ServiceError;
+
+mod any;
+mod compressed;
+mod encrypted;
+mod instrumented;
+mod retrying;
+mod tiered;
+
+pub use any::AnyObjectStore;
+pub use compressed::CompressedObjectStore;
+pub use encrypted::{ENCRYPTION_KEY_ENV, EncryptedObjectStore};
+pub use instrumented::InstrumentedObjectStore;
+pub use retrying::RetryingObjectStore;
+pub use tiered::TieredObjectStore;
+
+/// Default S3 bucket name for storing objects
+pub const DEFAULT_S3_BUCKET_NAME: &str = "thinkaroo-reading-stories";
+
+/// Base directory for disk storage
+const DISK_STORAGE_BASE: &str = "/tmp/thinkaroo/storage";
+
+/// Represents a stored object with its key and metadata
+#[derive(Debug, Clone)]
+pub struct StoredObject {
+    pub key: String,
+
+    /// Size of the object's data in bytes
+    pub size: u64,
+
+    /// When the object was last written, if the backend reports it
+    pub last_modified: Option<DateTime<Utc>>,
+
+    /// MIME type of the object, if known
+    pub content_type: Option<String>,
+}
+
+impl StoredObject {
+    /// Creates an object description from a key and size, guessing the content type
+    /// from the key's extension
+    pub fn new(key: &str, size: u64, last_modified: Option<DateTime<Utc>>) -> Self {
+        Self {
+            key: key.to_string(),
+            size,
+            last_modified,
+            content_type: content_type_for_key(key).map(str::to_string),
+        }
+    }
+}
+
+/// Guesses an object's MIME type from the extension of its key
+pub fn content_type_for_key(key: &str) -> Option<&'static str> {
+    let extension = Path::new(key).extension()?.to_str()?;
+
+    match extension.to_ascii_lowercase().as_str() {
+        "json" => Some("application/json"),
+        "toml" => Some("application/toml"),
+        "txt" => Some("text/plain"),
+        "html" => Some("text/html"),
+        "css" => Some("text/css"),
+        "js" => Some("text/javascript"),
+        "svg" => Some("image/svg+xml"),
+        "ico" => Some("image/x-icon"),
+        "mp3" => Some("audio/mpeg"),
+        "png" => Some("image/png"),
+        "jpg" | "jpeg" => Some("image/jpeg"),
+        "webp" => Some("image/webp"),
+        _ => None,
+    }
+}
+
+/// Converts an S3 timestamp into a UTC date-time
+fn from_s3_timestamp(timestamp: &aws_smithy_types::DateTime) -> Option<DateTime<Utc>> {
+    DateTime::from_timestamp(timestamp.secs(), timestamp.subsec_nanos())
+}
+
+/// A stream of chunks of an object's data
+pub type ObjectStream = Pin<Box<dyn Stream<Item = Result<Bytes, ServiceError>> + Send>>;
+
+/// Maximum number of objects returned in a single page of a listing
+const LIST_PAGE_SIZE: usize = 1000;
+
+/// A page of objects returned by a listing
+#[derive(Debug, Clone, Default)]
+pub struct ObjectPage {
+    /// The objects in this page
+    pub objects: Vec<StoredObject>,
+
+    /// Token to pass back to fetch the next page, or `None` if this is the last page
+    pub next_token: Option<String>,
+}
+
+/// Storage trait for abstracting basic object storage operations
+///
+/// This trait provides a common interface for put, get, list, and delete operations,
+/// allowing implementations using different backends (S3, local disk, etc.)
+#[async_trait]
+pub trait ObjectStore: Clone + Send + Sync + 'static {
+    /// Stores an object with the given key and data
+    ///
+    /// # Arguments
+    /// * `key` - The key/path for the object
+    /// * `data` - The raw bytes to store
+    ///
+    /// # Returns
+    /// * `Ok(())` - If the object was successfully stored
+    /// * `Err(ServiceError)` - If storage operations fail
+    async fn put_object(&self, key: &str, data: Vec<u8>) -> Result<(), ServiceError>;
+
+    /// Retrieves an object by its key
+    ///
+    /// # Arguments
+    /// * `key` - The key/path of the object to retrieve
+    ///
+    /// # Returns
+    /// * `Ok(Vec<u8>)` - The raw bytes of the object
+    /// * `Err(ServiceError)` - If the object doesn't exist or retrieval fails
+    async fn get_object(&self, key: &str) -> Result<Vec<u8>, ServiceError>;
+
+    /// Stores an object from a stream of chunks without buffering it in memory
+    ///
+    /// The default implementation collects the stream and calls `put_object`;
+    /// backends that can write incrementally should override it.
+    ///
+    /// # Arguments
+    /// * `key` - The key/path for the object
+    /// * `stream` - The object's data as a stream of chunks
+    ///
+    /// # Returns
+    /// * `Ok(())` - If the object was successfully stored
+    /// * `Err(ServiceError)` - If the stream or storage operations fail
+    async fn put_object_stream(&self, key: &str, stream: ObjectStream) -> Result<(), ServiceError> {
+        let chunks: Vec<Bytes> = stream.try_collect().await?;
+        self.put_object(key, chunks.concat()).await
+    }
+
+    /// Retrieves an object as a stream of chunks without buffering it in memory
+    ///
+    /// The default implementation calls `get_object` and yields its data as a single
+    /// chunk; backends that can read incrementally should override it.
+    ///
+    /// # Arguments
+    /// * `key` - The key/path of the object to retrieve
+    ///
+    /// # Returns
+    /// * `Ok(ObjectStream)` - The object's data as a stream of chunks
+    /// * `Err(ServiceError)` - If the object doesn't exist or retrieval fails
+    async fn get_object_stream(&self, key: &str) -> Result<ObjectStream, ServiceError> {
+        let data = self.get_object(key).await?;
+        Ok(futures::stream::once(async move { Ok(Bytes::from(data)) }).boxed())
+    }
+
+    /// Lists one page of objects with the given prefix
+    ///
+    /// # Arguments
+    /// * `prefix` - The prefix to filter objects by
+    /// * `continuation_token` - The `next_token` of the previous page, or `None` for
+    ///   the first page
+    ///
+    /// # Returns
+    /// * `Ok(ObjectPage)` - Up to LIST_PAGE_SIZE objects and the token for the next page
+    /// * `Err(ServiceError)` - If listing fails
+    async fn list_objects_page(
+        &self,
+        prefix: &str,
+        continuation_token: Option<String>,
+    ) -> Result<ObjectPage, ServiceError>;
+
+    /// Lists all objects with the given prefix, following every page
+    ///
+    /// # Arguments
+    /// * `prefix` - The prefix to filter objects by
+    ///
+    /// # Returns
+    /// * `Ok(Vec<StoredObject>)` - A list of objects matching the prefix
+    /// * `Err(ServiceError)` - If listing fails
+    async fn list_objects(&self, prefix: &str) -> Result<Vec<StoredObject>, ServiceError> {
+        let mut objects = Vec::new();
+        let mut continuation_token = None;
+
+        loop {
+            let page = self.list_objects_page(prefix, continuation_token).await?;
+            objects.extend(page.objects);
+
+            match page.next_token {
+                Some(token) => continuation_token = Some(token),
+                None => return Ok(objects),
+            }
+        }
+    }
+
+    /// Deletes an object by its key
+    ///
+    /// Deleting an object that doesn't exist is not an error.
+    ///
+    /// # Arguments
+    /// * `key` - The key/path of the object to delete
+    ///
+    /// # Returns
+    /// * `Ok(())` - If the object was deleted or didn't exist
+    /// * `Err(ServiceError)` - If deletion fails
+    async fn delete_object(&self, key: &str) -> Result<(), ServiceError>;
+
+    /// Retrieves an object's description without fetching its data
+    ///
+    /// # Arguments
+    /// * `key` - The key/path of the object
+    ///
+    /// # Returns
+    /// * `Ok(Some(StoredObject))` - If the object exists
+    /// * `Ok(None)` - If the object doesn't exist
+    /// * `Err(ServiceError)` - If the lookup fails
+    async fn head_object(&self, key: &str) -> Result<Option<StoredObject>, ServiceError>;
+
+    /// Checks whether an object exists
+    ///
+    /// # Arguments
+    /// * `key` - The key/path of the object
+    ///
+    /// # Returns
+    /// * `Ok(bool)` - Whether the object exists
+    /// * `Err(ServiceError)` - If the lookup fails
+    async fn object_exists(&self, key: &str) -> Result<bool, ServiceError> {
+        Ok(self.head_object(key).await?.is_some())
+    }
+
+    /// Deletes all objects with the given prefix
+    ///
+    /// # Arguments
+    /// * `prefix` - The prefix of the objects to delete
+    ///
+    /// # Returns
+    /// * `Ok(usize)` - The number of objects deleted
+    /// * `Err(ServiceError)` - If listing or deletion fails
+    async fn delete_objects_with_prefix(&self, prefix: &str) -> Result<usize, ServiceError>;
+}
+
+/// Size of each part of a multipart upload; S3 requires at least 5 MiB
+const MULTIPART_PART_SIZE: usize = 8 * 1024 * 1024;
+
+/// S3-based storage implementation
+#[derive(Clone)]
+pub struct S3ObjectStore {
+    client: S3Client,
+    bucket: String,
+}
+
+impl S3ObjectStore {
+    /// Creates a new S3Storage instance using the default bucket
+    pub fn new(client: S3Client) -> Self {
+        Self {
+            client,
+            bucket: DEFAULT_S3_BUCKET_NAME.to_string(),
+        }
+    }
+
+    /// Creates a builder for configuring the bucket and endpoint, e.g. to use an
+    /// S3-compatible service such as MinIO or LocalStack
+    pub fn builder() -> S3ObjectStoreBuilder {
+        S3ObjectStoreBuilder::default()
+    }
+
+    /// Returns the name of the bucket objects are stored in
+    pub fn bucket(&self) -> &str {
+        &self.bucket
+    }
+
+    /// Uploads the parts of a multipart upload, starting with data already buffered
+    async fn upload_parts(
+        &self,
+        key: &str,
+        upload_id: &str,
+        mut buffer: Vec<u8>,
+        mut stream: ObjectStream,
+    ) -> Result<Vec<CompletedPart>, ServiceError> {
+        let mut parts = Vec::new();
+
+        loop {
+            let chunk = stream.next().await.transpose()?;
+            if let Some(chunk) = &chunk {
+                buffer.extend_from_slice(chunk);
+            }
+
+            let finished = chunk.is_none();
+            if buffer.len() >= MULTIPART_PART_SIZE || (finished && !buffer.is_empty()) {
+                let part_number = parts.len() as i32 + 1;
+                let output = self
+                    .client
+                    .upload_part()
+                    .bucket(&self.bucket)
+                    .key(key)
+                    .upload_id(upload_id)
+                    .part_number(part_number)
+                    .body(std::mem::take(&mut buffer).into())
+                    .send()
+                    .await?;
+
+                parts.push(
+                    CompletedPart::builder()
+                        .part_number(part_number)
+                        .set_e_tag(output.e_tag().map(str::to_string))
+                        .build(),
+                );
+            }
+
+            if finished {
+                return Ok(parts);
+            }
+        }
+    }
+}
+
+/// Builder for an `S3ObjectStore` with a custom bucket, endpoint, or region
+///
+/// # Examples
+///
+/// ```no_run
+/// # async fn example() {
+/// use thinkaroo::storage::S3ObjectStore;
+///
+/// let config = aws_config::load_defaults(aws_config::BehaviorVersion::latest()).await;
+/// let store = S3ObjectStore::builder()
+///     .bucket("thinkaroo-dev")
+///     .endpoint_url("http://localhost:9000")
+///     .force_path_style(true)
+///     .region("us-east-1")
+///     .build(&config);
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct S3ObjectStoreBuilder {
+    bucket: Option<String>,
+    endpoint_url: Option<String>,
+    force_path_style: bool,
+    region: Option<String>,
+}
+
+impl S3ObjectStoreBuilder {
+    /// Sets the bucket objects are stored in (default: `thinkaroo-reading-stories`)
+    pub fn bucket(mut self, bucket: impl Into<String>) -> Self {
+        self.bucket = Some(bucket.into());
+        self
+    }
+
+    /// Sends requests to a custom endpoint instead of AWS
+    pub fn endpoint_url(mut self, endpoint_url: impl Into<String>) -> Self {
+        self.endpoint_url = Some(endpoint_url.into());
+        self
+    }
+
+    /// Addresses buckets by path (`endpoint/bucket/key`) rather than by virtual
+    /// host, as most S3-compatible services require
+    pub fn force_path_style(mut self, force_path_style: bool) -> Self {
+        self.force_path_style = force_path_style;
+        self
+    }
+
+    /// Overrides the region from the shared AWS configuration
+    pub fn region(mut self, region: impl Into<String>) -> Self {
+        self.region = Some(region.into());
+        self
+    }
+
+    /// Builds the store, starting from the shared AWS configuration
+    pub fn build(self, sdk_config: &aws_config::SdkConfig) -> S3ObjectStore {
+        let mut config = aws_sdk_s3::config::Builder::from(sdk_config)
+            .force_path_style(self.force_path_style);
+
+        if let Some(endpoint_url) = self.endpoint_url {
+            config = config.endpoint_url(endpoint_url);
+        }
+        if let Some(region) = self.region {
+            config = config.region(aws_sdk_s3::config::Region::new(region));
+        }
+
+        S3ObjectStore {
+            client: S3Client::from_conf(config.build()),
+            bucket: self
+                .bucket
+                .unwrap_or_else(|| DEFAULT_S3_BUCKET_NAME.to_string()),
+        }
+    }
+}
+
+#[async_trait]
+impl ObjectStore for S3ObjectStore {
+    async fn put_object(&self, key: &str, data: Vec<u8>) -> Result<(), ServiceError> {
+        self.client
+            .put_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .body(data.into())
+            .content_type(content_type_for_key(key).unwrap_or("application/octet-stream"))
+            .send()
+            .await?;
+
+        Ok(())
+    }
+
+    async fn get_object(&self, key: &str) -> Result<Vec<u8>, ServiceError> {
+        let get_output = self
+            .client
+            .get_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .send()
+            .await?;
+
+        let body_bytes = get_output.body.collect().await?.into_bytes();
+        Ok(body_bytes.to_vec())
+    }
+
+    async fn put_object_stream(&self, key: &str, mut stream: ObjectStream) -> Result<(), ServiceError> {
+        // Objects smaller than one part are uploaded in a single request
+        let mut buffer = Vec::new();
+        while buffer.len() < MULTIPART_PART_SIZE {
+            match stream.next().await {
+                Some(chunk) => buffer.extend_from_slice(&chunk?),
+                None => return self.put_object(key, buffer).await,
+            }
+        }
+
+        let upload = self
+            .client
+            .create_multipart_upload()
+            .bucket(&self.bucket)
+            .key(key)
+            .content_type(content_type_for_key(key).unwrap_or("application/octet-stream"))
+            .send()
+            .await?;
+        let upload_id = upload
+            .upload_id()
+            .ok_or_else(|| ServiceError::S3Error("Multipart upload has no upload id".to_string()))?;
+
+        match self.upload_parts(key, upload_id, buffer, stream).await {
+            Ok(parts) => {
+                self.client
+                    .complete_multipart_upload()
+                    .bucket(&self.bucket)
+                    .key(key)
+                    .upload_id(upload_id)
+                    .multipart_upload(
+                        CompletedMultipartUpload::builder()
+                            .set_parts(Some(parts))
+                            .build(),
+                    )
+                    .send()
+                    .await?;
+
+                Ok(())
+            }
+            Err(e) => {
+                // Abort so the uploaded parts aren't billed as orphaned storage
+                if let Err(abort_error) = self
+                    .client
+                    .abort_multipart_upload()
+                    .bucket(&self.bucket)
+                    .key(key)
+                    .upload_id(upload_id)
+                    .send()
+                    .await
+                {
+                    warn!("Failed to abort multipart upload of {}: {}", key, abort_error);
+                }
+
+                Err(e)
+            }
+        }
+    }
+
+    async fn get_object_stream(&self, key: &str) -> Result<ObjectStream, ServiceError> {
+        let get_output = self
+            .client
+            .get_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .send()
+            .await?;
+
+        let reader = get_output.body.into_async_read();
+        Ok(ReaderStream::new(reader).map_err(ServiceError::from).boxed())
+    }
+
+    async fn list_objects_page(
+        &self,
+        prefix: &str,
+        continuation_token: Option<String>,
+    ) -> Result<ObjectPage, ServiceError> {
+        let list_output = self
+            .client
+            .list_objects_v2()
+            .bucket(&self.bucket)
+            .prefix(prefix)
+            .max_keys(LIST_PAGE_SIZE as i32)
+            .set_continuation_token(continuation_token)
+            .send()
+            .await?;
+
+        let objects = list_output
+            .contents()
+            .iter()
+            .filter_map(|obj| {
+                obj.key().map(|k| {
+                    StoredObject::new(
+                        k,
+                        obj.size().unwrap_or_default().max(0) as u64,
+                        obj.last_modified().and_then(from_s3_timestamp),
+                    )
+                })
+            })
+            .collect();
+
+        let next_token = if list_output.is_truncated() == Some(true) {
+            list_output.next_continuation_token().map(str::to_string)
+        } else {
+            None
+        };
+
+        Ok(ObjectPage {
+            objects,
+            next_token,
+        })
+    }
+
+    async fn delete_object(&self, key: &str) -> Result<(), ServiceError> {
+        self.client
+            .delete_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .send()
+            .await?;
+
+        Ok(())
+    }
+
+    async fn head_object(&self, key: &str) -> Result<Option<StoredObject>, ServiceError> {
+        let result = self
+            .client
+            .head_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .send()
+            .await;
+
+        match result {
+            Ok(output) => {
+                let mut object = StoredObject::new(
+                    key,
+                    output.content_length().unwrap_or_default().max(0) as u64,
+                    output.last_modified().and_then(from_s3_timestamp),
+                );
+
+                // Prefer the content type recorded when the object was stored
+                if let Some(content_type) = output.content_type() {
+                    object.content_type = Some(content_type.to_string());
+                }
+
+                Ok(Some(object))
+            }
+            Err(e) if e.as_service_error().is_some_and(|e| e.is_not_found()) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn delete_objects_with_prefix(&self, prefix: &str) -> Result<usize, ServiceError> {
+        let objects = self.list_objects(prefix).await?;
+
+        // DeleteObjects accepts at most 1000 keys per request
+        for chunk in objects.chunks(1000) {
+            let identifiers = chunk
+                .iter()
+                .map(|obj| ObjectIdentifier::builder().key(&obj.key).build())
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| ServiceError::S3Error(e.to_string()))?;
+
+            let delete = Delete::builder()
+                .set_objects(Some(identifiers))
+                .quiet(true)
+                .build()
+                .map_err(|e| ServiceError::S3Error(e.to_string()))?;
+
+            self.client
+                .delete_objects()
+                .bucket(&self.bucket)
+                .delete(delete)
+                .send()
+                .await?;
+        }
+
+        Ok(objects.len())
+    }
+}
+
+/// Disk-based storage implementation
+#[derive(Clone)]
+pub struct DiskObjectStore {
+    base_path: PathBuf,
+}
+
+impl DiskObjectStore {
+    /// Creates a new DiskStorage instance with the default base path
+    ///
+    /// Creates the base directory if it doesn't exist.
+    pub fn new() -> Self {
+        let base_path = PathBuf::from(DISK_STORAGE_BASE);
+
+        // Create base directory if it doesn't exist
+        if let Err(e) = std::fs::create_dir_all(&base_path) {
+            warn!("Warning: Failed to create base directory {:?}: {}", base_path, e);
+        }
+
+        Self { base_path }
+    }
+
+    /// Creates a new DiskStorage instance with a custom base path
+    ///
+    /// Creates the base directory if it doesn't exist.
+    pub fn with_base_path(base_path: PathBuf) -> Self {
+        // Create base directory if it doesn't exist
+        if let Err(e) = std::fs::create_dir_all(&base_path) {
+            eprintln!("Warning: Failed to create base directory {:?}: {}", base_path, e);
+        }
+
+        Self { base_path }
+    }
+
+    /// Converts a storage key to a file path
+    fn key_to_path(&self, key: &str) -> PathBuf {
+        self.base_path.join(key)
+    }
+
+    /// Lists up to `limit` files under a prefix whose keys come after `after`, in
+    /// key order
+    ///
+    /// Directories are read in key order and skipped when all their keys come at or
+    /// before `after`, so a page reads little more than the files it returns rather
+    /// than the whole tree.
+    async fn walk_objects(
+        &self,
+        prefix: &str,
+        after: Option<&str>,
+        limit: usize,
+    ) -> Result<Vec<StoredObject>, ServiceError> {
+        let search_path = self.key_to_path(prefix);
+
+        // If the search path doesn't exist, return empty list
+        if !search_path.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut objects = Vec::new();
+
+        // Depth-first walk, with each directory's entries pushed in reverse key order
+        // so they're popped in key order
+        let mut walk_stack = vec![search_path];
+
+        while let Some(current_path) = walk_stack.pop() {
+            if !current_path.is_dir() {
+                if let Some(key) = self.path_to_key(&current_path)
+                    && after.is_none_or(|after| key.as_str() > after)
+                {
+                    let metadata = tokio::fs::metadata(&current_path).await?;
+                    objects.push(stored_object_from_metadata(&key, &metadata));
+                    if objects.len() == limit {
+                        break;
+                    }
+                }
+                continue;
+            }
+
+            let mut children = Vec::new();
+            let mut entries = tokio::fs::read_dir(&current_path).await?;
+            while let Some(entry) = entries.next_entry().await? {
+                let path = entry.path();
+                let Some(key) = self.path_to_key(&path) else {
+                    continue;
+                };
+                if path.is_dir() {
+                    // Every key in the directory starts with `key/`
+                    let dir_key = format!("{}/", key);
+                    if after.is_some_and(|after| after > dir_key.as_str() && !after.starts_with(&dir_key)) {
+                        continue;
+                    }
+                    children.push((dir_key, path));
+                } else {
+                    children.push((key, path));
+                }
+            }
+            children.sort_by(|a, b| b.0.cmp(&a.0));
+            walk_stack.extend(children.into_iter().map(|(_, path)| path));
+        }
+
+        Ok(objects)
+    }
+
+    /// Converts a file path back to a storage key
+    fn path_to_key(&self, path: &Path) -> Option<String> {
+        path.strip_prefix(&self.base_path)
+            .ok()
+            .and_then(|p| p.to_str())
+            .map(|s| s.to_string())
+    }
+}
+
+/// Describes a file on disk as a stored object
+fn stored_object_from_metadata(key: &str, metadata: &std::fs::Metadata) -> StoredObject {
+    let last_modified = metadata.modified().ok().map(DateTime::<Utc>::from);
+    StoredObject::new(key, metadata.len(), last_modified)
+}
+
+impl Default for DiskObjectStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl ObjectStore for DiskObjectStore {
+    async fn put_object(&self, key: &str, data: Vec<u8>) -> Result<(), ServiceError> {
+        let file_path = self.key_to_path(key);
+
+        // Create parent directory if it doesn't exist
+        if let Some(parent) = file_path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+
+        tokio::fs::write(&file_path, data).await?;
+
+        Ok(())
+    }
+
+    async fn get_object(&self, key: &str) -> Result<Vec<u8>, ServiceError> {
+        let file_path = self.key_to_path(key);
+
+        Ok(tokio::fs::read(&file_path).await?)
+    }
+
+    async fn put_object_stream(&self, key: &str, mut stream: ObjectStream) -> Result<(), ServiceError> {
+        let file_path = self.key_to_path(key);
+
+        // Create parent directory if it doesn't exist
+        if let Some(parent) = file_path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+
+        let mut file = tokio::fs::File::create(&file_path).await?;
+        while let Some(chunk) = stream.next().await {
+            file.write_all(&chunk?).await?;
+        }
+        file.flush().await?;
+
+        Ok(())
+    }
+
+    async fn get_object_stream(&self, key: &str) -> Result<ObjectStream, ServiceError> {
+        let file = tokio::fs::File::open(self.key_to_path(key)).await?;
+        Ok(ReaderStream::new(file).map_err(ServiceError::from).boxed())
+    }
+
+    async fn list_objects_page(
+        &self,
+        prefix: &str,
+        continuation_token: Option<String>,
+    ) -> Result<ObjectPage, ServiceError> {
+        // The continuation token is the last key of the previous page; one object past
+        // the page shows whether there's another
+        let mut objects = self
+            .walk_objects(prefix, continuation_token.as_deref(), LIST_PAGE_SIZE + 1)
+            .await?;
+
+        let next_token = (objects.len() > LIST_PAGE_SIZE).then(|| {
+            objects.truncate(LIST_PAGE_SIZE);
+            objects[LIST_PAGE_SIZE - 1].key.clone()
+        });
+
+        Ok(ObjectPage {
+            objects,
+            next_token,
+        })
+    }
+
+    async fn delete_object(&self, key: &str) -> Result<(), ServiceError> {
+        match tokio::fs::remove_file(self.key_to_path(key)).await {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+
+    async fn head_object(&self, key: &str) -> Result<Option<StoredObject>, ServiceError> {
+        match tokio::fs::metadata(self.key_to_path(key)).await {
+            Ok(metadata) if metadata.is_file() => {
+                Ok(Some(stored_object_from_metadata(key, &metadata)))
+            }
+            Ok(_) => Ok(None),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn delete_objects_with_prefix(&self, prefix: &str) -> Result<usize, ServiceError> {
+        let objects = self.list_objects(prefix).await?;
+        let path = self.key_to_path(prefix);
+
+        if path.is_dir() {
+            tokio::fs::remove_dir_all(&path).await?;
+        } else if path.is_file() {
+            tokio::fs::remove_file(&path).await?;
+        }
+
+        Ok(objects.len())
+    }
+}
+
+
+/// An object held by `MemoryObjectStore`
+#[derive(Debug, Clone)]
+struct MemoryObject {
+    data: Bytes,
+    last_modified: DateTime<Utc>,
+}
+
+/// In-memory object store implementation for testing and development
+///
+/// Objects are kept in key order so listings page the same way as S3.
+#[derive(Clone)]
+pub struct MemoryObjectStore {
+    objects: Arc<RwLock<BTreeMap<String, MemoryObject>>>,
+}
+
+impl MemoryObjectStore {
+    /// Creates a new, empty MemoryObjectStore instance
+    pub fn new() -> Self {
+        Self {
+            objects: Arc::new(RwLock::new(BTreeMap::new())),
+        }
+    }
+}
+
+impl Default for MemoryObjectStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl ObjectStore for MemoryObjectStore {
+    async fn put_object(&self, key: &str, data: Vec<u8>) -> Result<(), ServiceError> {
+        let object = MemoryObject {
+            data: Bytes::from(data),
+            last_modified: Utc::now(),
+        };
+        self.objects.write().await.insert(key.to_string(), object);
+
+        Ok(())
+    }
+
+    async fn get_object(&self, key: &str) -> Result<Vec<u8>, ServiceError> {
+        self.objects
+            .read()
+            .await
+            .get(key)
+            .map(|object| object.data.to_vec())
+            .ok_or_else(|| ServiceError::NotFound(format!("Object not found: {}", key)))
+    }
+
+    async fn list_objects_page(
+        &self,
+        prefix: &str,
+        continuation_token: Option<String>,
+    ) -> Result<ObjectPage, ServiceError> {
+        let objects = self.objects.read().await;
+
+        // The continuation token is the last key of the previous page; keys sharing
+        // the prefix sort contiguously starting at the prefix itself
+        let start = match continuation_token {
+            Some(last_key) => Bound::Excluded(last_key),
+            None => Bound::Included(prefix.to_string()),
+        };
+
+        let mut matching = objects
+            .range((start, Bound::Unbounded))
+            .take_while(|(key, _)| key.starts_with(prefix))
+            .map(|(key, object)| {
+                StoredObject::new(key, object.data.len() as u64, Some(object.last_modified))
+            })
+            .peekable();
+
+        let mut page = Vec::new();
+        while page.len() < LIST_PAGE_SIZE {
+            match matching.next() {
+                Some(object) => page.push(object),
+                None => break,
+            }
+        }
+
+        let next_token = matching
+            .peek()
+            .and_then(|_| page.last())
+            .map(|object| object.key.clone());
+
+        Ok(ObjectPage {
+            objects: page,
+            next_token,
+        })
+    }
+
+    async fn delete_object(&self, key: &str) -> Result<(), ServiceError> {
+        self.objects.write().await.remove(key);
+        Ok(())
+    }
+
+    async fn head_object(&self, key: &str) -> Result<Option<StoredObject>, ServiceError> {
+        Ok(self.objects.read().await.get(key).map(|object| {
+            StoredObject::new(key, object.data.len() as u64, Some(object.last_modified))
+        }))
+    }
+
+    async fn delete_objects_with_prefix(&self, prefix: &str) -> Result<usize, ServiceError> {
+        let mut objects = self.objects.write().await;
+        let before = objects.len();
+        objects.retain(|key, _| !key.starts_with(prefix));
+
+        Ok(before - objects.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn guesses_content_type_from_extension() {
+        assert_eq!(
+            content_type_for_key("reading/2025-10-11-14/abc.json"),
+            Some("application/json")
+        );
+        assert_eq!(content_type_for_key("prompts/reading.TOML"), Some("application/toml"));
+        assert_eq!(content_type_for_key("reading/abc"), None);
+        assert_eq!(content_type_for_key("reading/abc.bin"), None);
+    }
+
+    #[tokio::test]
+    async fn memory_store_round_trips_objects() {
+        let store = MemoryObjectStore::new();
+        store.put_object("reading/a.json", b"{}".to_vec()).await.unwrap();
+
+        assert_eq!(store.get_object("reading/a.json").await.unwrap(), b"{}");
+        assert!(store.get_object("reading/b.json").await.is_err());
+
+        let object = store.head_object("reading/a.json").await.unwrap().unwrap();
+        assert_eq!(object.size, 2);
+        assert_eq!(object.content_type.as_deref(), Some("application/json"));
+
+        store.delete_object("reading/a.json").await.unwrap();
+        assert!(!store.object_exists("reading/a.json").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn memory_store_pages_listings_within_prefix() {
+        let store = MemoryObjectStore::new();
+        for i in 0..LIST_PAGE_SIZE + 5 {
+            store
+                .put_object(&format!("reading/{:05}.json", i), Vec::new())
+                .await
+                .unwrap();
+        }
+        store.put_object("readings/other.json", Vec::new()).await.unwrap();
+
+        let first = store.list_objects_page("reading/", None).await.unwrap();
+        assert_eq!(first.objects.len(), LIST_PAGE_SIZE);
+        assert!(first.next_token.is_some());
+
+        let second = store
+            .list_objects_page("reading/", first.next_token)
+            .await
+            .unwrap();
+        assert_eq!(second.objects.len(), 5);
+        assert!(second.next_token.is_none());
+
+        assert_eq!(store.delete_objects_with_prefix("reading/").await.unwrap(), LIST_PAGE_SIZE + 5);
+        assert_eq!(store.list_objects("").await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn disk_store_pages_listings_in_key_order() {
+        let base_path = std::env::temp_dir().join(format!("disk-store-{}", uuid::Uuid::new_v4()));
+        let store = DiskObjectStore::with_base_path(base_path.clone());
+        let mut keys: Vec<String> = (0..LIST_PAGE_SIZE + 5)
+            .map(|i| format!("reading/grade-{}/{:05}.json", i % 3, i))
+            .collect();
+        keys.push("reading/grade-1.json".into());
+        for key in &keys {
+            store.put_object(key, Vec::new()).await.unwrap();
+        }
+        keys.sort();
+
+        let first = store.list_objects_page("reading/", None).await.unwrap();
+        assert_eq!(first.objects.len(), LIST_PAGE_SIZE);
+        let second = store
+            .list_objects_page("reading/", first.next_token)
+            .await
+            .unwrap();
+        assert!(second.next_token.is_none());
+
+        let listed: Vec<String> = first.objects.into_iter().chain(second.objects).map(|o| o.key).collect();
+        assert_eq!(listed, keys);
+        std::fs::remove_dir_all(base_path).unwrap();
+    }
+}
@@ -0,0 +1,91 @@
+use async_trait::async_trait;
+
+use super::{ObjectPage, ObjectStore, ObjectStream, StoredObject};
+use crate::ServiceError;
+use crate::retry::RetryPolicy;
+
+/// Object store wrapper that retries transient failures with backoff
+///
+/// Streamed puts aren't retried, since the stream is consumed by the first attempt;
+/// streamed gets retry opening the stream but not failures partway through it.
+#[derive(Clone)]
+pub struct RetryingObjectStore<S: ObjectStore> {
+    inner: S,
+    policy: RetryPolicy,
+}
+
+impl<S: ObjectStore> RetryingObjectStore<S> {
+    /// Wraps a store with the default retry policy
+    pub fn new(inner: S) -> Self {
+        Self::with_policy(inner, RetryPolicy::default())
+    }
+
+    /// Wraps a store with a custom retry policy
+    pub fn with_policy(inner: S, policy: RetryPolicy) -> Self {
+        Self { inner, policy }
+    }
+}
+
+#[async_trait]
+impl<S: ObjectStore> ObjectStore for RetryingObjectStore<S> {
+    async fn put_object(&self, key: &str, data: Vec<u8>) -> Result<(), ServiceError> {
+        let inner = &self.inner;
+        self.policy
+            .run("put_object", move || inner.put_object(key, data.clone()))
+            .await
+    }
+
+    async fn get_object(&self, key: &str) -> Result<Vec<u8>, ServiceError> {
+        let inner = &self.inner;
+        self.policy
+            .run("get_object", move || inner.get_object(key))
+            .await
+    }
+
+    async fn put_object_stream(&self, key: &str, stream: ObjectStream) -> Result<(), ServiceError> {
+        self.inner.put_object_stream(key, stream).await
+    }
+
+    async fn get_object_stream(&self, key: &str) -> Result<ObjectStream, ServiceError> {
+        let inner = &self.inner;
+        self.policy
+            .run("get_object_stream", move || inner.get_object_stream(key))
+            .await
+    }
+
+    async fn list_objects_page(
+        &self,
+        prefix: &str,
+        continuation_token: Option<String>,
+    ) -> Result<ObjectPage, ServiceError> {
+        let inner = &self.inner;
+        self.policy
+            .run("list_objects_page", move || {
+                inner.list_objects_page(prefix, continuation_token.clone())
+            })
+            .await
+    }
+
+    async fn delete_object(&self, key: &str) -> Result<(), ServiceError> {
+        let inner = &self.inner;
+        self.policy
+            .run("delete_object", move || inner.delete_object(key))
+            .await
+    }
+
+    async fn head_object(&self, key: &str) -> Result<Option<StoredObject>, ServiceError> {
+        let inner = &self.inner;
+        self.policy
+            .run("head_object", move || inner.head_object(key))
+            .await
+    }
+
+    async fn delete_objects_with_prefix(&self, prefix: &str) -> Result<usize, ServiceError> {
+        let inner = &self.inner;
+        self.policy
+            .run("delete_objects_with_prefix", move || {
+                inner.delete_objects_with_prefix(prefix)
+            })
+            .await
+    }
+}
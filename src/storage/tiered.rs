@@ -0,0 +1,126 @@
+use async_trait::async_trait;
+use tracing::{debug, warn};
+
+use super::{ObjectPage, ObjectStore, ObjectStream, StoredObject};
+use crate::ServiceError;
+
+/// Object store that serves reads from a fast local layer before falling through
+/// to a remote layer
+///
+/// The remote layer is authoritative: writes go to it first and listings come
+/// from it. Objects fetched from the remote layer are back-filled into the local
+/// layer so repeated reads of hot objects don't hit the remote store.
+#[derive(Clone)]
+pub struct TieredObjectStore<L: ObjectStore, R: ObjectStore> {
+    local: L,
+    remote: R,
+}
+
+impl<L: ObjectStore, R: ObjectStore> TieredObjectStore<L, R> {
+    /// Creates a tiered store over a local cache layer and a remote layer
+    pub fn new(local: L, remote: R) -> Self {
+        Self { local, remote }
+    }
+}
+
+#[async_trait]
+impl<L: ObjectStore, R: ObjectStore> ObjectStore for TieredObjectStore<L, R> {
+    async fn put_object(&self, key: &str, data: Vec<u8>) -> Result<(), ServiceError> {
+        self.remote.put_object(key, data.clone()).await?;
+
+        if let Err(e) = self.local.put_object(key, data).await {
+            warn!("Failed to write {} to local layer: {}", key, e);
+        }
+
+        Ok(())
+    }
+
+    async fn get_object(&self, key: &str) -> Result<Vec<u8>, ServiceError> {
+        match self.local.get_object(key).await {
+            Ok(data) => return Ok(data),
+            Err(e) => debug!("Local layer miss for {}: {}", key, e),
+        }
+
+        let data = self.remote.get_object(key).await?;
+
+        if let Err(e) = self.local.put_object(key, data.clone()).await {
+            warn!("Failed to back-fill {} into local layer: {}", key, e);
+        }
+
+        Ok(data)
+    }
+
+    async fn put_object_stream(&self, key: &str, stream: ObjectStream) -> Result<(), ServiceError> {
+        // Streamed objects are large, so they aren't copied into the local layer;
+        // drop any stale local copy instead
+        self.remote.put_object_stream(key, stream).await?;
+        self.local.delete_object(key).await
+    }
+
+    async fn get_object_stream(&self, key: &str) -> Result<ObjectStream, ServiceError> {
+        match self.local.get_object_stream(key).await {
+            Ok(stream) => Ok(stream),
+            Err(_) => self.remote.get_object_stream(key).await,
+        }
+    }
+
+    async fn list_objects_page(
+        &self,
+        prefix: &str,
+        continuation_token: Option<String>,
+    ) -> Result<ObjectPage, ServiceError> {
+        self.remote.list_objects_page(prefix, continuation_token).await
+    }
+
+    async fn delete_object(&self, key: &str) -> Result<(), ServiceError> {
+        self.remote.delete_object(key).await?;
+        self.local.delete_object(key).await
+    }
+
+    async fn head_object(&self, key: &str) -> Result<Option<StoredObject>, ServiceError> {
+        self.remote.head_object(key).await
+    }
+
+    async fn delete_objects_with_prefix(&self, prefix: &str) -> Result<usize, ServiceError> {
+        let deleted = self.remote.delete_objects_with_prefix(prefix).await?;
+        self.local.delete_objects_with_prefix(prefix).await?;
+
+        Ok(deleted)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::MemoryObjectStore;
+
+    #[tokio::test]
+    async fn back_fills_local_layer_on_miss() {
+        let local = MemoryObjectStore::new();
+        let remote = MemoryObjectStore::new();
+        remote.put_object("reading/a.json", b"{}".to_vec()).await.unwrap();
+
+        let store = TieredObjectStore::new(local.clone(), remote.clone());
+        assert_eq!(store.get_object("reading/a.json").await.unwrap(), b"{}");
+        assert!(local.object_exists("reading/a.json").await.unwrap());
+
+        // Served from the local layer once back-filled
+        remote.delete_object("reading/a.json").await.unwrap();
+        assert_eq!(store.get_object("reading/a.json").await.unwrap(), b"{}");
+    }
+
+    #[tokio::test]
+    async fn writes_and_deletes_go_to_both_layers() {
+        let local = MemoryObjectStore::new();
+        let remote = MemoryObjectStore::new();
+        let store = TieredObjectStore::new(local.clone(), remote.clone());
+
+        store.put_object("reading/a.json", b"{}".to_vec()).await.unwrap();
+        assert!(local.object_exists("reading/a.json").await.unwrap());
+        assert!(remote.object_exists("reading/a.json").await.unwrap());
+
+        store.delete_object("reading/a.json").await.unwrap();
+        assert!(!local.object_exists("reading/a.json").await.unwrap());
+        assert!(!remote.object_exists("reading/a.json").await.unwrap());
+    }
+}
@@ -0,0 +1,111 @@
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+
+use crate::{
+    ServiceError,
+    keyvalue::{Column, KeyValueStore, column_json, encode_json},
+    storage::StoredObject,
+};
+
+/// How long an object's tags are kept; longer than content is usually retained
+const TAGS_TTL: Duration = Duration::from_secs(90 * 24 * 60 * 60);
+
+/// Tag values of a content object, by field (e.g. "skills" to ["inference"])
+pub type Tags = HashMap<String, Vec<String>>;
+
+/// Key of the item holding an object's tags
+fn tags_key(object_key: &str) -> String {
+    format!("tags#{}", object_key)
+}
+
+/// Collects the string values of each tag field of generated content
+///
+/// Fields that are missing or aren't arrays are left out.
+pub fn extract(fields: &[String], content: &Value) -> Tags {
+    fields
+        .iter()
+        .filter_map(|field| {
+            let values = content.get(field)?.as_array()?;
+            let values = values
+                .iter()
+                .filter_map(Value::as_str)
+                .map(|value| value.trim().to_lowercase())
+                .filter(|value| !value.is_empty())
+                .collect();
+            Some((field.clone(), values))
+        })
+        .collect()
+}
+
+/// Stores the tags of a content object, so cached content can be filtered
+/// without reading every object
+pub async fn store<K: KeyValueStore>(
+    kv_store: &K,
+    object_key: &str,
+    tags: &Tags,
+) -> Result<(), ServiceError> {
+    let columns = tags
+        .iter()
+        .map(|(field, values)| Ok(Column::new(field.clone(), encode_json(values)?)))
+        .collect::<Result<Vec<_>, ServiceError>>()?;
+    kv_store.put(tags_key(object_key), columns, Some(TAGS_TTL)).await
+}
+
+/// Keeps the objects tagged with `value` in `field`
+///
+/// Objects stored without tags never match.
+pub async fn matching<K: KeyValueStore>(
+    kv_store: &K,
+    mut objects: Vec<StoredObject>,
+    field: &str,
+    value: &str,
+) -> Result<Vec<StoredObject>, ServiceError> {
+    let keys = objects.iter().map(|object| tags_key(&object.key)).collect();
+    let items = kv_store.batch_get(keys, vec![field.to_string()]).await?;
+
+    let mut tagged = HashSet::new();
+    for object in &objects {
+        let Some(columns) = items.get(&tags_key(&object.key)) else {
+            continue;
+        };
+        let values: Vec<String> = column_json(columns, field)?.unwrap_or_default();
+        if values.iter().any(|tag| tag == value) {
+            tagged.insert(object.key.clone());
+        }
+    }
+    objects.retain(|object| tagged.contains(&object.key));
+    Ok(objects)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keyvalue::MemoryKeyValueStore;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn filters_objects_by_tag() {
+        let kv_store = MemoryKeyValueStore::new();
+        let fields = ["skills".to_string(), "topics".to_string()];
+        let a = extract(&fields, &json!({ "skills": ["inference", " Sequencing "], "topics": ["volcanoes"] }));
+        assert_eq!(a["skills"], ["inference", "sequencing"]);
+        let b = extract(&fields, &json!({ "skills": ["main_idea"], "topics": "not a list" }));
+        assert!(!b.contains_key("topics"));
+
+        store(&kv_store, "reading/a.json", &a).await.unwrap();
+        store(&kv_store, "reading/b.json", &b).await.unwrap();
+        let objects = vec![
+            StoredObject::new("reading/a.json", 1, None),
+            StoredObject::new("reading/b.json", 1, None),
+            StoredObject::new("reading/untagged.json", 1, None),
+        ];
+
+        let found = matching(&kv_store, objects.clone(), "skills", "inference").await.unwrap();
+        let keys: Vec<&str> = found.iter().map(|object| object.key.as_str()).collect();
+        assert_eq!(keys, ["reading/a.json"]);
+        let found = matching(&kv_store, objects.clone(), "topics", "volcanoes").await.unwrap();
+        assert_eq!(found.len(), 1);
+        assert!(matching(&kv_store, objects, "skills", "vocabulary").await.unwrap().is_empty());
+    }
+}
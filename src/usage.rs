@@ -0,0 +1,150 @@
+use chrono::{Days, NaiveDate};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use crate::{
+    ServiceError,
+    keyvalue::{Column, KeyValueStore},
+    metrics,
+};
+
+/// Counters kept for each prompt per day
+const COUNTERS: [&str; 3] = ["requests", "input_tokens", "output_tokens"];
+
+/// LLM token usage of one prompt
+#[derive(Debug, Default, Clone, PartialEq, Serialize)]
+pub struct PromptUsage {
+    /// Number of generation calls
+    pub requests: i64,
+
+    /// Tokens sent to the model, including instructions and examples
+    pub input_tokens: i64,
+
+    /// Tokens generated by the model
+    pub output_tokens: i64,
+}
+
+/// LLM token usage across all prompts on one day
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DailyUsage {
+    /// The UTC date, as YYYY-MM-DD
+    pub date: String,
+
+    /// Usage keyed by prompt name; prompts without calls that day are omitted
+    pub prompts: BTreeMap<String, PromptUsage>,
+}
+
+/// Key of the item holding a day's counters
+fn usage_key(date: NaiveDate) -> String {
+    format!("usage#{}", date.format("%Y-%m-%d"))
+}
+
+/// Adds one generation call's token counts to the day's totals
+///
+/// Totals are kept in the key-value store, so they cover every instance, and
+/// mirrored into the process's `llm.*` metrics.
+pub async fn record<K: KeyValueStore>(
+    kv_store: &K,
+    date: NaiveDate,
+    prompt: &str,
+    input_tokens: u32,
+    output_tokens: u32,
+) -> Result<(), ServiceError> {
+    metrics::increment("llm.requests");
+    metrics::add("llm.input_tokens", input_tokens.into());
+    metrics::add("llm.output_tokens", output_tokens.into());
+
+    let key = usage_key(date);
+    for (counter, delta) in COUNTERS.iter().zip([1, input_tokens.into(), output_tokens.into()]) {
+        kv_store
+            .increment(key.clone(), format!("{}.{}", prompt, counter), delta)
+            .await?;
+    }
+    Ok(())
+}
+
+/// Returns usage of the given prompts for the `days` days ending on `today`,
+/// most recent first
+pub async fn daily_usage<K: KeyValueStore>(
+    kv_store: &K,
+    prompts: &[String],
+    today: NaiveDate,
+    days: u64,
+) -> Result<Vec<DailyUsage>, ServiceError> {
+    let dates: Vec<NaiveDate> = (0..days)
+        .filter_map(|offset| today.checked_sub_days(Days::new(offset)))
+        .collect();
+    let column_names: Vec<String> = prompts
+        .iter()
+        .flat_map(|prompt| COUNTERS.iter().map(move |counter| format!("{}.{}", prompt, counter)))
+        .collect();
+
+    let items = kv_store
+        .batch_get(dates.iter().copied().map(usage_key).collect(), column_names)
+        .await?;
+
+    Ok(dates
+        .into_iter()
+        .map(|date| DailyUsage {
+            date: date.format("%Y-%m-%d").to_string(),
+            prompts: items
+                .get(&usage_key(date))
+                .map(|columns| prompt_usage(columns))
+                .unwrap_or_default(),
+        })
+        .collect())
+}
+
+/// Groups a day's counter columns by prompt
+fn prompt_usage(columns: &[Column]) -> BTreeMap<String, PromptUsage> {
+    let mut usage: BTreeMap<String, PromptUsage> = BTreeMap::new();
+
+    for column in columns {
+        let (Some((prompt, counter)), Some(value)) = (column.name.rsplit_once('.'), column.as_counter())
+        else {
+            continue;
+        };
+        let entry = usage.entry(prompt.to_string()).or_default();
+        match counter {
+            "requests" => entry.requests = value,
+            "input_tokens" => entry.input_tokens = value,
+            "output_tokens" => entry.output_tokens = value,
+            _ => {}
+        }
+    }
+
+    usage
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keyvalue::MemoryKeyValueStore;
+
+    #[tokio::test]
+    async fn totals_usage_per_prompt_and_day() {
+        let store = MemoryKeyValueStore::new();
+        let today = NaiveDate::from_ymd_opt(2025, 10, 11).unwrap();
+        let yesterday = NaiveDate::from_ymd_opt(2025, 10, 10).unwrap();
+
+        record(&store, today, "reading", 100, 40).await.unwrap();
+        record(&store, today, "reading", 50, 10).await.unwrap();
+        record(&store, yesterday, "math", 7, 3).await.unwrap();
+
+        let prompts = vec!["reading".to_string(), "math".to_string()];
+        let usage = daily_usage(&store, &prompts, today, 3).await.unwrap();
+
+        assert_eq!(usage.len(), 3);
+        assert_eq!(usage[0].date, "2025-10-11");
+        assert_eq!(
+            usage[0].prompts["reading"],
+            PromptUsage {
+                requests: 2,
+                input_tokens: 150,
+                output_tokens: 50
+            }
+        );
+        assert_eq!(usage[1].prompts["math"].output_tokens, 3);
+        assert!(usage[2].prompts.is_empty());
+    }
+}
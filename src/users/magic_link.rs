@@ -0,0 +1,164 @@
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use sha2::{Digest, Sha256};
+use std::time::Duration;
+
+use super::{Account, USER_ID_COLUMN, email_key, load_account, normalize_email};
+use crate::{
+    ServiceError,
+    email::EmailMessage,
+    keyvalue::{Column, KeyValueStore},
+};
+
+/// How long a magic link can be used for
+const LINK_TTL: Duration = Duration::from_secs(15 * 60);
+
+/// Shortest time between magic links sent to one address, so the endpoint can't
+/// be used to flood an inbox
+const RESEND_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Random bytes in a magic link token
+const TOKEN_BYTES: usize = 32;
+
+/// Key of the item mapping a token to the account it logs in to
+///
+/// Only a hash of the token is stored, so a leaked store can't be used to log in.
+fn link_key(token: &str) -> String {
+    format!("magic-link#{}", URL_SAFE_NO_PAD.encode(Sha256::digest(token.as_bytes())))
+}
+
+/// Key of the item marking a token as used
+fn used_key(token: &str) -> String {
+    format!("magic-link-used#{}", URL_SAFE_NO_PAD.encode(Sha256::digest(token.as_bytes())))
+}
+
+/// Key of the item throttling magic links to an address
+fn throttle_key(email: &str) -> String {
+    format!("magic-link-sent#{}", email)
+}
+
+/// Creates a single-use login token for the account with an email address
+///
+/// # Returns
+/// * `Ok(Some((account, token)))` - The account and its new token
+/// * `Ok(None)` - If no account has the address, or a link was sent to it within
+///   the last minute; callers shouldn't reveal which
+/// * `Err(ServiceError)` - If storage fails
+pub async fn create<K: KeyValueStore>(
+    kv_store: &K,
+    email: &str,
+) -> Result<Option<(Account, String)>, ServiceError> {
+    let Ok(email) = normalize_email(email) else {
+        return Ok(None);
+    };
+    let columns = kv_store
+        .get(email_key(&email), vec![USER_ID_COLUMN.to_string()])
+        .await?;
+    let Some(user_id) = columns
+        .into_iter()
+        .find(|column| column.name == USER_ID_COLUMN)
+        .and_then(|column| String::from_utf8(column.value).ok())
+    else {
+        return Ok(None);
+    };
+    let Some(account) = load_account(kv_store, &user_id).await? else {
+        return Ok(None);
+    };
+
+    let first_recently = kv_store
+        .put_if_not_exists(
+            throttle_key(&email),
+            vec![Column::new(USER_ID_COLUMN.to_string(), user_id.clone().into_bytes())],
+            Some(RESEND_INTERVAL),
+        )
+        .await?;
+    if !first_recently {
+        return Ok(None);
+    }
+
+    let token = URL_SAFE_NO_PAD.encode(rand::random::<[u8; TOKEN_BYTES]>());
+    kv_store
+        .put(
+            link_key(&token),
+            vec![Column::new(USER_ID_COLUMN.to_string(), user_id.into_bytes())],
+            Some(LINK_TTL),
+        )
+        .await?;
+    Ok(Some((account, token)))
+}
+
+/// Uses up a magic link token, returning the account it logs in to
+///
+/// # Returns
+/// * `Ok(Account)` - The account to log in to
+/// * `Err(ServiceError::Unauthorized)` - If the token is unknown, expired, or
+///   already used
+/// * `Err(ServiceError)` - If storage fails
+pub async fn redeem<K: KeyValueStore>(kv_store: &K, token: &str) -> Result<Account, ServiceError> {
+    let rejected = || ServiceError::Unauthorized("This login link is invalid or has expired".into());
+
+    let columns = kv_store
+        .get(link_key(token), vec![USER_ID_COLUMN.to_string()])
+        .await?;
+    let user_id = columns
+        .into_iter()
+        .find(|column| column.name == USER_ID_COLUMN)
+        .and_then(|column| String::from_utf8(column.value).ok())
+        .ok_or_else(rejected)?;
+
+    // Claiming the token is atomic, so it logs in at most once even if it's
+    // followed twice at the same time
+    let claimed = kv_store
+        .put_if_not_exists(
+            used_key(token),
+            vec![Column::new(USER_ID_COLUMN.to_string(), user_id.clone().into_bytes())],
+            Some(LINK_TTL),
+        )
+        .await?;
+    if !claimed {
+        return Err(rejected());
+    }
+    kv_store.delete(link_key(token)).await?;
+
+    load_account(kv_store, &user_id).await?.ok_or_else(rejected)
+}
+
+/// Builds the email carrying a magic link
+pub fn message(account: &Account, link: &str) -> EmailMessage {
+    let minutes = LINK_TTL.as_secs() / 60;
+    EmailMessage::new(
+        &account.email,
+        "Your Thinkaroo login link",
+        format!(
+            "Follow this link to log in to Thinkaroo:\n\n{}\n\nThe link works once, for the next {} minutes. If you didn't ask for it, you can ignore this email.\n",
+            link, minutes
+        ),
+    )
+    .with_html(format!(
+        "<p>Follow this link to log in to Thinkaroo:</p>\n<p><a href=\"{0}\">{0}</a></p>\n<p>The link works once, for the next {1} minutes. If you didn't ask for it, you can ignore this email.</p>\n",
+        link, minutes
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{keyvalue::MemoryKeyValueStore, users::{Role, register}};
+
+    #[tokio::test]
+    async fn links_log_in_once() {
+        let store = MemoryKeyValueStore::new();
+        let account = register(&store, "parent@example.com", "long enough password".into(), Role::Parent)
+            .await
+            .unwrap();
+
+        assert!(create(&store, "nobody@example.com").await.unwrap().is_none());
+        let (linked, token) = create(&store, " Parent@Example.com").await.unwrap().unwrap();
+        assert_eq!(linked.id, account.id);
+        // A second request within the resend interval sends nothing
+        assert!(create(&store, "parent@example.com").await.unwrap().is_none());
+
+        assert_eq!(redeem(&store, &token).await.unwrap().id, account.id);
+        assert!(matches!(redeem(&store, &token).await, Err(ServiceError::Unauthorized(_))));
+        assert!(matches!(redeem(&store, "made-up").await, Err(ServiceError::Unauthorized(_))));
+    }
+}
@@ -0,0 +1,694 @@
+mod magic_link;
+mod password;
+mod routes;
+
+pub use routes::router;
+
+use axum::{
+    extract::{FromRequestParts, OptionalFromRequestParts, Request, State},
+    http::request::Parts,
+    middleware::Next,
+    response::Response,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::{
+    ServiceError,
+    keyvalue::{Column, KeyValueStore, TypedKv, column_json},
+    problem::ErrorResponse,
+    reading::{MAX_GRADE, MIN_GRADE},
+    session::Session,
+    state::AppState,
+    storage::ObjectStore,
+};
+
+/// Session value holding the logged-in account's ID
+pub const USER_ID_SESSION_KEY: &str = "user_id";
+
+/// Session value holding the selected child profile's ID
+pub const PROFILE_ID_SESSION_KEY: &str = "profile_id";
+
+/// Most child profiles per account
+const MAX_PROFILES: usize = 8;
+
+/// Shortest password accepted
+const MIN_PASSWORD_LEN: usize = 10;
+
+/// Longest password accepted, bounding the cost of hashing it
+const MAX_PASSWORD_LEN: usize = 128;
+
+/// Longest child name accepted, in characters
+const MAX_NAME_LEN: usize = 40;
+
+/// Most interests per child profile
+const MAX_INTERESTS: usize = 10;
+
+/// Longest interest accepted, in characters
+const MAX_INTEREST_LEN: usize = 40;
+
+/// Longest pet description accepted, in characters
+const MAX_PET_LEN: usize = 40;
+
+/// Times an account update is retried when another write wins the race
+const MAX_UPDATE_ATTEMPTS: usize = 3;
+
+/// Column of the account item holding its JSON
+const ACCOUNT_COLUMN: &str = "account";
+
+/// Column of the email index item holding the account ID
+const USER_ID_COLUMN: &str = "user_id";
+
+/// Column of the item counting failed logins to an address
+const FAILED_LOGINS_COLUMN: &str = "failed_logins";
+
+/// A child's profile, used to personalize content
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChildProfile {
+    pub id: String,
+    pub name: String,
+
+    /// School grade, which sets the reading level
+    pub grade: u8,
+
+    /// Topics the child likes, lowercase
+    #[serde(default)]
+    pub interests: Vec<String>,
+
+    /// The child's pet, e.g. "a cat named Biscuit", for personalized stories
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pet: Option<String>,
+}
+
+/// A child profile's editable fields
+#[derive(Debug, Clone, Deserialize)]
+pub struct ProfileFields {
+    pub name: String,
+    pub grade: u8,
+    #[serde(default)]
+    pub interests: Vec<String>,
+    #[serde(default)]
+    pub pet: Option<String>,
+}
+
+/// What an account is for, which decides the endpoints it may use
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    /// Manages child profiles and their progress
+    #[default]
+    Parent,
+
+    /// Manages classes and their assignments
+    Teacher,
+}
+
+/// A parent or teacher account
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Account {
+    pub id: String,
+
+    /// Accounts created before roles existed are parents
+    #[serde(default)]
+    pub role: Role,
+
+    /// Normalized (trimmed, lowercase) email address used to log in
+    pub email: String,
+
+    /// Argon2 hash of the password in PHC string format
+    pub(crate) password_hash: String,
+
+    /// When the account was registered, as Unix seconds
+    pub created_at: i64,
+
+    #[serde(default)]
+    pub profiles: Vec<ChildProfile>,
+}
+
+impl Account {
+    /// Returns the child profile with the given ID
+    pub fn profile(&self, id: &str) -> Option<&ChildProfile> {
+        self.profiles.iter().find(|profile| profile.id == id)
+    }
+}
+
+/// The logged-in account and its selected child profile
+///
+/// Handlers take `CurrentUser` to require a login, answering 401 otherwise, or
+/// `Option<CurrentUser>` to personalize content when someone is logged in.
+#[derive(Debug, Clone)]
+pub struct CurrentUser {
+    pub account: Account,
+    pub profile: Option<ChildProfile>,
+}
+
+impl CurrentUser {
+    /// Checks that the account has a role, answering 403 otherwise
+    pub fn require_role(&self, role: Role) -> Result<(), ServiceError> {
+        if self.account.role == role {
+            Ok(())
+        } else {
+            Err(ServiceError::Forbidden(format!(
+                "Only {} accounts can do this",
+                match role {
+                    Role::Parent => "parent",
+                    Role::Teacher => "teacher",
+                }
+            )))
+        }
+    }
+}
+
+impl<T: Send + Sync> FromRequestParts<T> for CurrentUser {
+    type Rejection = ErrorResponse;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &T) -> Result<Self, Self::Rejection> {
+        parts
+            .extensions
+            .get::<CurrentUser>()
+            .cloned()
+            .ok_or_else(|| ServiceError::Unauthorized("Log in to continue".into()).into())
+    }
+}
+
+impl<T: Send + Sync> OptionalFromRequestParts<T> for CurrentUser {
+    type Rejection = ErrorResponse;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        _state: &T,
+    ) -> Result<Option<Self>, Self::Rejection> {
+        Ok(parts.extensions.get::<CurrentUser>().cloned())
+    }
+}
+
+/// Middleware that loads the session's logged-in account, if any
+///
+/// Must run inside the `session` middleware. An account that can't be loaded is
+/// treated as logged out rather than failing the request.
+pub async fn current_user<S: ObjectStore, K: KeyValueStore>(
+    State(state): State<AppState<S, K>>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let session = request.extensions().get::<Session>().cloned();
+    let user_id = session
+        .as_ref()
+        .and_then(|session| session.get::<String>(USER_ID_SESSION_KEY));
+
+    if let (Some(session), Some(user_id)) = (session, user_id) {
+        match load_account(&state.kv_store, &user_id).await {
+            Ok(Some(account)) => {
+                let profile = session
+                    .get::<String>(PROFILE_ID_SESSION_KEY)
+                    .and_then(|id| account.profile(&id).cloned());
+                request
+                    .extensions_mut()
+                    .insert(CurrentUser { account, profile });
+            }
+            // The account was deleted, so end the login
+            Ok(None) => {
+                session.remove(USER_ID_SESSION_KEY);
+                session.remove(PROFILE_ID_SESSION_KEY);
+            }
+            Err(e) => warn!("Failed to load account {}: {}", user_id, e),
+        }
+    }
+
+    next.run(request).await
+}
+
+/// Key of the item holding an account
+fn account_key(id: &str) -> String {
+    format!("user#{}", id)
+}
+
+/// Key of the item mapping an email address to its account
+fn email_key(email: &str) -> String {
+    format!("user-email#{}", email)
+}
+
+/// Trims and lowercases an email address, checking it looks like one
+fn normalize_email(email: &str) -> Result<String, ServiceError> {
+    let email = email.trim().to_lowercase();
+    let valid = email.len() <= 254
+        && email
+            .split_once('@')
+            .is_some_and(|(local, domain)| !local.is_empty() && domain.contains('.'))
+        && !email.chars().any(char::is_whitespace);
+
+    if valid {
+        Ok(email)
+    } else {
+        Err(ServiceError::InvalidInput("email is not a valid email address".into()))
+    }
+}
+
+/// Validates a child profile's fields, normalizing interests to lowercase
+fn validate_profile(mut fields: ProfileFields) -> Result<ProfileFields, ServiceError> {
+    fields.name = fields.name.trim().to_string();
+    if fields.name.is_empty() || fields.name.chars().count() > MAX_NAME_LEN {
+        return Err(ServiceError::InvalidInput(format!(
+            "name must be 1 to {} characters",
+            MAX_NAME_LEN
+        )));
+    }
+    if !(MIN_GRADE..=MAX_GRADE).contains(&fields.grade) {
+        return Err(ServiceError::InvalidInput(format!(
+            "grade must be between {} and {}",
+            MIN_GRADE, MAX_GRADE
+        )));
+    }
+
+    fields.interests = fields
+        .interests
+        .iter()
+        .map(|interest| interest.trim().to_lowercase())
+        .filter(|interest| !interest.is_empty())
+        .collect();
+    let valid_interests = fields.interests.len() <= MAX_INTERESTS
+        && fields.interests.iter().all(|interest| {
+            interest.len() <= MAX_INTEREST_LEN
+                && interest
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == ' ' || c == '-')
+        });
+    if !valid_interests {
+        return Err(ServiceError::InvalidInput(format!(
+            "at most {} interests of up to {} letters, digits, spaces, or hyphens",
+            MAX_INTERESTS, MAX_INTEREST_LEN
+        )));
+    }
+
+    fields.pet = fields.pet.map(|pet| pet.trim().to_string()).filter(|pet| !pet.is_empty());
+    if let Some(pet) = &fields.pet {
+        let valid_chars = pet
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == ' ' || c == '-' || c == '\'');
+        if pet.chars().count() > MAX_PET_LEN || !valid_chars {
+            return Err(ServiceError::InvalidInput(format!(
+                "pet must be at most {} letters, digits, spaces, hyphens, or apostrophes",
+                MAX_PET_LEN
+            )));
+        }
+    }
+
+    Ok(fields)
+}
+
+/// Creates an account
+///
+/// # Returns
+/// * `Ok(Account)` - The new account
+/// * `Err(ServiceError::Conflict)` - If the email address is already registered
+/// * `Err(ServiceError)` - If the input is invalid or storage fails
+pub async fn register<K: KeyValueStore>(
+    kv_store: &K,
+    email: &str,
+    password: String,
+    role: Role,
+) -> Result<Account, ServiceError> {
+    let email = normalize_email(email)?;
+    if !(MIN_PASSWORD_LEN..=MAX_PASSWORD_LEN).contains(&password.chars().count()) {
+        return Err(ServiceError::InvalidInput(format!(
+            "password must be {} to {} characters",
+            MIN_PASSWORD_LEN, MAX_PASSWORD_LEN
+        )));
+    }
+
+    let account = Account {
+        id: Uuid::new_v4().simple().to_string(),
+        role,
+        email,
+        password_hash: password::hash(password).await?,
+        created_at: Utc::now().timestamp(),
+        profiles: Vec::new(),
+    };
+
+    // Claim the email address first, so concurrent registrations can't share it
+    let claimed = kv_store
+        .put_if_not_exists(
+            email_key(&account.email),
+            vec![Column::new(USER_ID_COLUMN.to_string(), account.id.clone().into_bytes())],
+            None,
+        )
+        .await?;
+    if !claimed {
+        return Err(ServiceError::Conflict(
+            "An account with that email address already exists".into(),
+        ));
+    }
+
+    kv_store
+        .put_json(&account_key(&account.id), ACCOUNT_COLUMN, &account, None)
+        .await?;
+    Ok(account)
+}
+
+/// Checks an email address and password, returning the account they log in to
+pub async fn authenticate<K: KeyValueStore>(
+    kv_store: &K,
+    email: &str,
+    password: String,
+) -> Result<Account, ServiceError> {
+    let rejected = || ServiceError::Unauthorized("Incorrect email address or password".into());
+
+    let email = normalize_email(email).map_err(|_| rejected())?;
+    let columns = kv_store
+        .get(email_key(&email), vec![USER_ID_COLUMN.to_string()])
+        .await?;
+    let user_id = columns
+        .into_iter()
+        .find(|column| column.name == USER_ID_COLUMN)
+        .and_then(|column| String::from_utf8(column.value).ok());
+    let account = match user_id {
+        Some(user_id) => load_account(kv_store, &user_id).await?,
+        None => None,
+    };
+
+    // Check the password either way, so response times don't reveal who is registered
+    let Some(account) = account else {
+        password::verify_dummy(password).await?;
+        return Err(rejected());
+    };
+    if !password::verify(password, account.password_hash.clone()).await? {
+        return Err(rejected());
+    }
+    Ok(account)
+}
+
+/// Key of the item counting failed logins to an address in the hour of `now`
+fn failed_logins_key(email: &str, now: DateTime<Utc>) -> String {
+    format!("user-logins#{}#{}", email.trim().to_lowercase(), now.format("%Y-%m-%d-%H"))
+}
+
+/// Counts a login to an address against its hourly limit of failed logins,
+/// before its password is checked
+///
+/// # Returns
+/// * `Ok(true)` - The login is within the limit
+/// * `Ok(false)` - The address has had too many failed logins this hour
+/// * `Err(ServiceError)` - If the store can't be reached
+pub async fn take_login_attempt<K: KeyValueStore>(
+    kv_store: &K,
+    email: &str,
+    now: DateTime<Utc>,
+    limit: u32,
+) -> Result<bool, ServiceError> {
+    let count = kv_store
+        .increment(failed_logins_key(email, now), FAILED_LOGINS_COLUMN.to_string(), 1)
+        .await?;
+    Ok(count <= i64::from(limit))
+}
+
+/// Gives back the attempt of a login that succeeded, so only failures count,
+/// logging rather than failing
+pub async fn return_login_attempt<K: KeyValueStore>(kv_store: &K, email: &str, now: DateTime<Utc>) {
+    let returned = kv_store
+        .increment(failed_logins_key(email, now), FAILED_LOGINS_COLUMN.to_string(), -1)
+        .await;
+    if let Err(e) = returned {
+        warn!("Failed to return login attempt: {}", e);
+    }
+}
+
+/// Loads an account by ID
+pub async fn load_account<K: KeyValueStore>(
+    kv_store: &K,
+    id: &str,
+) -> Result<Option<Account>, ServiceError> {
+    kv_store.get_json(&account_key(id), ACCOUNT_COLUMN).await
+}
+
+/// Loads every account, skipping any that can't be read
+///
+/// Scans the whole key range of accounts, so it's for background jobs, not requests.
+pub async fn all_accounts<K: KeyValueStore>(kv_store: &K) -> Result<Vec<Account>, ServiceError> {
+    let items = kv_store
+        .scan_prefix(account_key(""), vec![ACCOUNT_COLUMN.to_string()])
+        .await?;
+
+    Ok(items
+        .into_iter()
+        .filter_map(|(key, columns)| match column_json(&columns, ACCOUNT_COLUMN) {
+            Ok(account) => account,
+            Err(e) => {
+                warn!("Skipping unreadable account {}: {}", key, e);
+                None
+            }
+        })
+        .collect())
+}
+
+/// Applies `update` to an account and saves it, retrying if the account changes
+/// concurrently
+///
+/// `update` may run more than once, each time on a freshly loaded account.
+pub async fn update_account<K, R>(
+    kv_store: &K,
+    id: &str,
+    mut update: impl FnMut(&mut Account) -> Result<R, ServiceError> + Send,
+) -> Result<R, ServiceError>
+where
+    K: KeyValueStore,
+    R: Send,
+{
+    let key = account_key(id);
+
+    for _ in 0..MAX_UPDATE_ATTEMPTS {
+        let (version, account) = kv_store.get_json_versioned::<Account>(&key, ACCOUNT_COLUMN).await?;
+        let mut account =
+            account.ok_or_else(|| ServiceError::NotFound(format!("Unknown account: {}", id)))?;
+
+        let result = update(&mut account)?;
+        if kv_store
+            .put_json_if_version(&key, ACCOUNT_COLUMN, &account, version)
+            .await?
+        {
+            return Ok(result);
+        }
+    }
+
+    Err(ServiceError::Conflict(
+        "The account was changed by another request; try again".into(),
+    ))
+}
+
+/// Adds a child profile to an account
+pub async fn add_profile<K: KeyValueStore>(
+    kv_store: &K,
+    account_id: &str,
+    fields: ProfileFields,
+) -> Result<ChildProfile, ServiceError> {
+    let fields = validate_profile(fields)?;
+    let profile = ChildProfile {
+        id: Uuid::new_v4().simple().to_string(),
+        name: fields.name,
+        grade: fields.grade,
+        interests: fields.interests,
+        pet: fields.pet,
+    };
+
+    update_account(kv_store, account_id, |account| {
+        if account.profiles.len() >= MAX_PROFILES {
+            return Err(ServiceError::InvalidInput(format!(
+                "an account can have at most {} profiles",
+                MAX_PROFILES
+            )));
+        }
+        account.profiles.push(profile.clone());
+        Ok(())
+    })
+    .await?;
+
+    Ok(profile)
+}
+
+/// Replaces a child profile's fields
+pub async fn update_profile<K: KeyValueStore>(
+    kv_store: &K,
+    account_id: &str,
+    profile_id: &str,
+    fields: ProfileFields,
+) -> Result<ChildProfile, ServiceError> {
+    let fields = validate_profile(fields)?;
+
+    update_account(kv_store, account_id, |account| {
+        let profile = account
+            .profiles
+            .iter_mut()
+            .find(|profile| profile.id == profile_id)
+            .ok_or_else(|| ServiceError::NotFound(format!("Unknown profile: {}", profile_id)))?;
+        profile.name = fields.name.clone();
+        profile.grade = fields.grade;
+        profile.interests = fields.interests.clone();
+        profile.pet = fields.pet.clone();
+        Ok(profile.clone())
+    })
+    .await
+}
+
+/// Removes a child profile from an account
+pub async fn remove_profile<K: KeyValueStore>(
+    kv_store: &K,
+    account_id: &str,
+    profile_id: &str,
+) -> Result<(), ServiceError> {
+    update_account(kv_store, account_id, |account| {
+        let before = account.profiles.len();
+        account.profiles.retain(|profile| profile.id != profile_id);
+        if account.profiles.len() == before {
+            return Err(ServiceError::NotFound(format!("Unknown profile: {}", profile_id)));
+        }
+        Ok(())
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keyvalue::MemoryKeyValueStore;
+    use axum::{Json, extract::Path, http::StatusCode};
+
+    fn fields(name: &str, grade: u8) -> ProfileFields {
+        ProfileFields {
+            name: name.to_string(),
+            grade,
+            interests: vec![" Dinosaurs ".to_string()],
+            pet: Some(" a dog named Rex ".to_string()),
+        }
+    }
+
+    #[tokio::test]
+    async fn registers_and_authenticates() {
+        let store = MemoryKeyValueStore::new();
+        let account = register(&store, " Parent@Example.com", "long enough password".into(), Role::Parent)
+            .await
+            .unwrap();
+        assert_eq!(account.email, "parent@example.com");
+
+        let again = register(&store, "parent@example.com", "another password".into(), Role::Teacher).await;
+        assert!(matches!(again, Err(ServiceError::Conflict(_))));
+
+        let logged_in = authenticate(&store, "PARENT@example.com", "long enough password".into())
+            .await
+            .unwrap();
+        assert_eq!(logged_in.id, account.id);
+
+        for (email, password) in [
+            ("parent@example.com", "wrong password"),
+            ("nobody@example.com", "long enough password"),
+        ] {
+            let result = authenticate(&store, email, password.into()).await;
+            assert!(matches!(result, Err(ServiceError::Unauthorized(_))));
+        }
+    }
+
+    #[tokio::test]
+    async fn limits_failed_logins_per_address() {
+        let store = MemoryKeyValueStore::new();
+        let now = Utc::now();
+        assert!(take_login_attempt(&store, "parent@example.com", now, 1).await.unwrap());
+        return_login_attempt(&store, "parent@example.com", now).await;
+        assert!(take_login_attempt(&store, " Parent@Example.com", now, 1).await.unwrap());
+        assert!(!take_login_attempt(&store, "parent@example.com", now, 1).await.unwrap());
+        assert!(take_login_attempt(&store, "other@example.com", now, 1).await.unwrap());
+
+        let next_hour = now + chrono::Duration::hours(1);
+        assert!(take_login_attempt(&store, "parent@example.com", next_hour, 1).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn manages_child_profiles() {
+        let store = MemoryKeyValueStore::new();
+        let account = register(&store, "parent@example.com", "long enough password".into(), Role::Parent)
+            .await
+            .unwrap();
+
+        let profile = add_profile(&store, &account.id, fields("Ada", 2)).await.unwrap();
+        assert_eq!(profile.interests, vec!["dinosaurs"]);
+        assert_eq!(profile.pet.as_deref(), Some("a dog named Rex"));
+        assert!(add_profile(&store, &account.id, fields("", 2)).await.is_err());
+        assert!(add_profile(&store, &account.id, fields("Bo", 12)).await.is_err());
+        let mut bad_pet = fields("Bo", 2);
+        bad_pet.pet = Some("<script>".to_string());
+        assert!(add_profile(&store, &account.id, bad_pet).await.is_err());
+
+        let updated = update_profile(&store, &account.id, &profile.id, fields("Ada", 3))
+            .await
+            .unwrap();
+        assert_eq!(updated.grade, 3);
+
+        let loaded = load_account(&store, &account.id).await.unwrap().unwrap();
+        assert_eq!(loaded.profiles, vec![updated]);
+
+        remove_profile(&store, &account.id, &profile.id).await.unwrap();
+        let loaded = load_account(&store, &account.id).await.unwrap().unwrap();
+        assert!(loaded.profiles.is_empty());
+    }
+
+    #[tokio::test]
+    async fn only_parents_edit_profiles() {
+        let state = crate::state::AppState::new(
+            crate::storage::MemoryObjectStore::new(),
+            MemoryKeyValueStore::new(),
+            crate::config::Config::default(),
+            crate::content::ContentTypeRegistry::new(),
+        )
+        .await;
+        let password = "long enough password".to_string();
+        let mut account = register(&state.kv_store, "parent@example.com", password, Role::Parent)
+            .await
+            .unwrap();
+        let profile = add_profile(&state.kv_store, &account.id, fields("Ada", 2)).await.unwrap();
+        account.role = Role::Teacher;
+        let user = CurrentUser { account, profile: None };
+
+        let updated = routes::update_profile(
+            State(state),
+            user,
+            Path(profile.id),
+            Json(fields("Ada", 3)),
+        )
+        .await;
+        assert_eq!(updated.err().map(|e| e.status), Some(StatusCode::FORBIDDEN));
+    }
+
+    #[tokio::test]
+    async fn magic_links_log_in_on_confirmation_only() {
+        use axum::{Router, body::Body, http::Request};
+        use tower::ServiceExt;
+
+        let state = crate::state::AppState::new(
+            crate::storage::MemoryObjectStore::new(),
+            MemoryKeyValueStore::new(),
+            crate::config::Config::default(),
+            crate::content::ContentTypeRegistry::new(),
+        )
+        .await;
+        register(&state.kv_store, "parent@example.com", "long enough password".into(), Role::Parent)
+            .await
+            .unwrap();
+        let (_, token) = magic_link::create(&state.kv_store, "parent@example.com")
+            .await
+            .unwrap()
+            .unwrap();
+        let session = axum::middleware::from_fn_with_state(state.clone(), crate::session::session);
+        let app = Router::new().nest("/account", router()).route_layer(session).with_state(state);
+
+        let uri = format!("/account/magic-link/{}", token);
+        let send = |method: &str| {
+            let request = Request::builder().method(method).uri(&uri).body(Body::empty()).unwrap();
+            app.clone().oneshot(request)
+        };
+        // Fetching the link, as a mail scanner would, leaves it usable
+        for _ in 0..2 {
+            assert_eq!(send("GET").await.unwrap().status(), StatusCode::OK);
+        }
+        assert_eq!(send("POST").await.unwrap().status(), StatusCode::SEE_OTHER);
+        assert_eq!(send("POST").await.unwrap().status(), StatusCode::UNAUTHORIZED);
+    }
+}
@@ -0,0 +1,75 @@
+use argon2::{
+    Argon2, PasswordHash, PasswordHasher, PasswordVerifier,
+    password_hash::{SaltString, rand_core::OsRng},
+};
+use std::sync::OnceLock;
+use uuid::Uuid;
+
+use crate::ServiceError;
+
+/// Hash of a random password, made on first use, checked against when there's
+/// no account so that takes as long as a wrong password
+static DUMMY_HASH: OnceLock<String> = OnceLock::new();
+
+/// Hashes a password with Argon2id and a random salt, in PHC string format
+///
+/// Hashing is deliberately slow, so it runs on the blocking thread pool.
+pub async fn hash(password: String) -> Result<String, ServiceError> {
+    tokio::task::spawn_blocking(move || hash_blocking(&password))
+        .await
+        .map_err(|e| ServiceError::ConfigError(format!("Password hashing task failed: {}", e)))?
+}
+
+/// Checks a password against a hash produced by `hash`
+pub async fn verify(password: String, hash: String) -> Result<bool, ServiceError> {
+    tokio::task::spawn_blocking(move || verify_blocking(&password, &hash))
+        .await
+        .map_err(|e| ServiceError::ConfigError(format!("Password hashing task failed: {}", e)))?
+}
+
+/// Checks a password against a hash no password matches, taking as long as
+/// `verify`, so a missing account can't be told from a wrong password by how long
+/// rejecting it takes
+pub async fn verify_dummy(password: String) -> Result<(), ServiceError> {
+    tokio::task::spawn_blocking(move || {
+        let dummy = match DUMMY_HASH.get() {
+            Some(dummy) => dummy,
+            None => {
+                let dummy = hash_blocking(&Uuid::new_v4().to_string())?;
+                DUMMY_HASH.get_or_init(|| dummy)
+            }
+        };
+        verify_blocking(&password, dummy).map(|_| ())
+    })
+    .await
+    .map_err(|e| ServiceError::ConfigError(format!("Password hashing task failed: {}", e)))?
+}
+
+fn hash_blocking(password: &str) -> Result<String, ServiceError> {
+    let salt = SaltString::generate(&mut OsRng);
+    Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|e| ServiceError::ConfigError(format!("Failed to hash password: {}", e)))
+}
+
+fn verify_blocking(password: &str, hash: &str) -> Result<bool, ServiceError> {
+    let hash = PasswordHash::new(hash)
+        .map_err(|e| ServiceError::ConfigError(format!("Invalid password hash: {}", e)))?;
+    Ok(Argon2::default()
+        .verify_password(password.as_bytes(), &hash)
+        .is_ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn verifies_only_the_hashed_password() {
+        let hashed = hash("correct horse".to_string()).await.unwrap();
+
+        assert!(verify("correct horse".to_string(), hashed.clone()).await.unwrap());
+        assert!(!verify("battery staple".to_string(), hashed).await.unwrap());
+    }
+}
@@ -0,0 +1,250 @@
+use axum::{
+    Json, Router,
+    extract::{Path, State},
+    http::StatusCode,
+    response::{Html, Redirect},
+    routing::{get, post, put},
+};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tracing::warn;
+
+use super::{
+    Account, ChildProfile, CurrentUser, PROFILE_ID_SESSION_KEY, ProfileFields, Role,
+    USER_ID_SESSION_KEY,
+};
+use crate::{
+    ServiceError, keyvalue::KeyValueStore, metrics, pages, problem::ErrorResponse,
+    session::Session, state::AppState, storage::ObjectStore,
+};
+
+/// Email address and password sent to log in
+#[derive(Deserialize)]
+pub struct Credentials {
+    pub email: String,
+    pub password: String,
+}
+
+/// Email address sent to ask for a magic login link
+#[derive(Deserialize)]
+pub struct MagicLinkRequest {
+    pub email: String,
+}
+
+/// Details sent to register an account
+#[derive(Deserialize)]
+pub struct Registration {
+    pub email: String,
+    pub password: String,
+
+    /// Parent if omitted
+    #[serde(default)]
+    pub role: Role,
+}
+
+/// Account details returned to its owner
+#[derive(Serialize)]
+pub struct AccountView {
+    pub id: String,
+    pub email: String,
+    pub role: Role,
+    pub profiles: Vec<ChildProfile>,
+
+    /// ID of the selected child profile, if any
+    pub active_profile: Option<String>,
+}
+
+impl AccountView {
+    fn new(account: Account, active_profile: Option<String>) -> Self {
+        Self {
+            id: account.id,
+            email: account.email,
+            role: account.role,
+            profiles: account.profiles,
+            active_profile,
+        }
+    }
+}
+
+/// Builds the router for account endpoints, to be nested under `/account`
+///
+/// Routes must run inside the `session` and `current_user` middleware.
+pub fn router<S, K>() -> Router<AppState<S, K>>
+where
+    S: ObjectStore + 'static,
+    K: KeyValueStore + 'static,
+{
+    Router::new()
+        .route("/", get(get_account))
+        .route("/register", post(register))
+        .route("/login", post(login))
+        .route("/logout", post(logout))
+        .route("/magic-link", post(request_magic_link))
+        .route("/magic-link/{token}", get(confirm_magic_link).post(follow_magic_link))
+        .route("/profiles", post(add_profile))
+        .route("/profiles/{id}", put(update_profile).delete(remove_profile))
+        .route("/profiles/{id}/select", post(select_profile))
+}
+
+/// Records a login in the session under a new session ID
+fn log_in(session: &Session, account: &Account) -> Result<(), ServiceError> {
+    session.renew();
+    session.remove(PROFILE_ID_SESSION_KEY);
+    session.insert(USER_ID_SESSION_KEY, &account.id)
+}
+
+/// Creates an account and logs in to it
+pub async fn register<S: ObjectStore, K: KeyValueStore>(
+    State(state): State<AppState<S, K>>,
+    session: Session,
+    Json(registration): Json<Registration>,
+) -> Result<(StatusCode, Json<AccountView>), ErrorResponse> {
+    let account = super::register(
+        &state.kv_store,
+        &registration.email,
+        registration.password,
+        registration.role,
+    )
+    .await?;
+    log_in(&session, &account)?;
+
+    Ok((StatusCode::CREATED, Json(AccountView::new(account, None))))
+}
+
+/// Logs in to an account
+///
+/// Each address may fail `limits.failed_logins_per_hour` logins an hour, after
+/// which logins to it are refused until the next hour.
+pub async fn login<S: ObjectStore, K: KeyValueStore>(
+    State(state): State<AppState<S, K>>,
+    session: Session,
+    Json(credentials): Json<Credentials>,
+) -> Result<Json<AccountView>, ErrorResponse> {
+    let now = Utc::now();
+    let limit = state.config.limits.failed_logins_per_hour;
+    if !super::take_login_attempt(&state.kv_store, &credentials.email, now, limit).await? {
+        metrics::increment("logins.limited");
+        let mut response = ErrorResponse::new(
+            StatusCode::TOO_MANY_REQUESTS,
+            "login_limit_reached",
+            "Too many failed logins to this address; try again later",
+        );
+        response.retry_after = Some(Duration::from_secs(3600 - now.timestamp().rem_euclid(3600) as u64));
+        return Err(response);
+    }
+
+    let account =
+        super::authenticate(&state.kv_store, &credentials.email, credentials.password).await?;
+    super::return_login_attempt(&state.kv_store, &credentials.email, now).await;
+    log_in(&session, &account)?;
+
+    Ok(Json(AccountView::new(account, None)))
+}
+
+/// Emails a single-use login link to an account's address
+///
+/// Always accepts the request, whether or not the address has an account, so it
+/// can't be used to find out who is registered.
+pub async fn request_magic_link<S: ObjectStore, K: KeyValueStore>(
+    State(state): State<AppState<S, K>>,
+    Json(request): Json<MagicLinkRequest>,
+) -> Result<StatusCode, ErrorResponse> {
+    if let Some((account, token)) = super::magic_link::create(&state.kv_store, &request.email).await? {
+        let link = format!("{}/v1/account/magic-link/{}", state.config.public_url(), token);
+        let message = super::magic_link::message(&account, &link);
+
+        // Send in the background, so response times don't reveal registered addresses
+        let mailer = state.mailer.clone();
+        tokio::spawn(async move {
+            if let Err(e) = mailer.send(&message).await {
+                warn!("Failed to send magic link to account {}: {}", account.id, e);
+            }
+        });
+    }
+    Ok(StatusCode::ACCEPTED)
+}
+
+/// Shows the page a magic link opens, whose button logs in with it
+///
+/// Following the link doesn't use it up, since mail scanners and link previews
+/// fetch links before people click them; only the button's POST does.
+pub async fn confirm_magic_link() -> Result<Html<String>, ErrorResponse> {
+    Ok(pages::render("magic_link", &serde_json::json!({}))?)
+}
+
+/// Logs in with a magic link, then goes to the home page
+pub async fn follow_magic_link<S: ObjectStore, K: KeyValueStore>(
+    State(state): State<AppState<S, K>>,
+    session: Session,
+    Path(token): Path<String>,
+) -> Result<Redirect, ErrorResponse> {
+    let account = super::magic_link::redeem(&state.kv_store, &token).await?;
+    log_in(&session, &account)?;
+    Ok(Redirect::to("/"))
+}
+
+/// Logs out, keeping the rest of the session
+pub async fn logout(session: Session) -> StatusCode {
+    session.remove(USER_ID_SESSION_KEY);
+    session.remove(PROFILE_ID_SESSION_KEY);
+    session.renew();
+    StatusCode::NO_CONTENT
+}
+
+/// Returns the logged-in account
+pub async fn get_account(user: CurrentUser) -> Json<AccountView> {
+    let active_profile = user.profile.map(|profile| profile.id);
+    Json(AccountView::new(user.account, active_profile))
+}
+
+/// Adds a child profile to the logged-in account
+pub async fn add_profile<S: ObjectStore, K: KeyValueStore>(
+    State(state): State<AppState<S, K>>,
+    user: CurrentUser,
+    Json(fields): Json<ProfileFields>,
+) -> Result<(StatusCode, Json<ChildProfile>), ErrorResponse> {
+    user.require_role(Role::Parent)?;
+    let profile = super::add_profile(&state.kv_store, &user.account.id, fields).await?;
+    Ok((StatusCode::CREATED, Json(profile)))
+}
+
+/// Replaces a child profile's name, grade, and interests
+pub async fn update_profile<S: ObjectStore, K: KeyValueStore>(
+    State(state): State<AppState<S, K>>,
+    user: CurrentUser,
+    Path(id): Path<String>,
+    Json(fields): Json<ProfileFields>,
+) -> Result<Json<ChildProfile>, ErrorResponse> {
+    user.require_role(Role::Parent)?;
+    let profile = super::update_profile(&state.kv_store, &user.account.id, &id, fields).await?;
+    Ok(Json(profile))
+}
+
+/// Removes a child profile from the logged-in account
+pub async fn remove_profile<S: ObjectStore, K: KeyValueStore>(
+    State(state): State<AppState<S, K>>,
+    user: CurrentUser,
+    session: Session,
+    Path(id): Path<String>,
+) -> Result<StatusCode, ErrorResponse> {
+    user.require_role(Role::Parent)?;
+    super::remove_profile(&state.kv_store, &user.account.id, &id).await?;
+    if session.get::<String>(PROFILE_ID_SESSION_KEY).as_deref() == Some(id.as_str()) {
+        session.remove(PROFILE_ID_SESSION_KEY);
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Selects the child profile content is personalized for in this session
+pub async fn select_profile(
+    user: CurrentUser,
+    session: Session,
+    Path(id): Path<String>,
+) -> Result<StatusCode, ErrorResponse> {
+    if user.account.profile(&id).is_none() {
+        return Err(ServiceError::NotFound(format!("Unknown profile: {}", id)).into());
+    }
+    session.insert(PROFILE_ID_SESSION_KEY, &id)?;
+    Ok(StatusCode::NO_CONTENT)
+}
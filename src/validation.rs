@@ -0,0 +1,249 @@
+use serde_json::Value;
+use std::fmt::Debug;
+
+use crate::content::ContentParams;
+
+/// Words that should never appear in content served to children
+pub const DEFAULT_BANNED_WORDS: &[&str] = &[
+    "blood", "bloody", "corpse", "drunk", "gun", "guns", "murder", "sexy", "suicide",
+];
+
+/// A check run on generated content before it is cached
+///
+/// Validators operate on the JSON form of the content so they can be attached to
+/// any registered content type. Content that fails validation is regenerated.
+pub trait ContentValidator: Debug + Send + Sync {
+    /// Validates content generated with the given parameters, returning a description
+    /// of the problem if it is invalid
+    fn validate(&self, content: &Value, params: &ContentParams) -> Result<(), String>;
+}
+
+/// Requires a text field's word count to fall within a range
+#[derive(Debug, Clone)]
+pub struct WordCount {
+    pub field: String,
+    pub min: usize,
+    pub max: usize,
+}
+
+impl WordCount {
+    pub fn new(field: &str, min: usize, max: usize) -> Self {
+        Self {
+            field: field.to_string(),
+            min,
+            max,
+        }
+    }
+}
+
+impl ContentValidator for WordCount {
+    fn validate(&self, content: &Value, _params: &ContentParams) -> Result<(), String> {
+        let text = content
+            .get(&self.field)
+            .and_then(Value::as_str)
+            .ok_or_else(|| format!("missing text field {:?}", self.field))?;
+
+        let words = text.split_whitespace().count();
+        if words < self.min || words > self.max {
+            return Err(format!(
+                "{:?} has {} words, expected {}-{}",
+                self.field, words, self.min, self.max
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// Requires an array field to contain at least a minimum number of items
+#[derive(Debug, Clone)]
+pub struct MinItems {
+    pub field: String,
+    pub min: usize,
+}
+
+impl MinItems {
+    pub fn new(field: &str, min: usize) -> Self {
+        Self {
+            field: field.to_string(),
+            min,
+        }
+    }
+}
+
+impl ContentValidator for MinItems {
+    fn validate(&self, content: &Value, _params: &ContentParams) -> Result<(), String> {
+        let items = content
+            .get(&self.field)
+            .and_then(Value::as_array)
+            .ok_or_else(|| format!("missing array field {:?}", self.field))?;
+
+        if items.len() < self.min {
+            return Err(format!(
+                "{:?} has {} items, expected at least {}",
+                self.field,
+                items.len(),
+                self.min
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// Rejects content containing any empty (or whitespace-only) string
+#[derive(Debug, Clone, Default)]
+pub struct NoEmptyFields;
+
+impl ContentValidator for NoEmptyFields {
+    fn validate(&self, content: &Value, _params: &ContentParams) -> Result<(), String> {
+        let mut strings = Vec::new();
+        collect_strings(content, &mut strings);
+
+        if strings.iter().any(|s| s.trim().is_empty()) {
+            return Err("content contains an empty field".to_string());
+        }
+
+        Ok(())
+    }
+}
+
+/// Rejects content containing any of a list of words, case-insensitively
+#[derive(Debug, Clone)]
+pub struct BannedWords {
+    pub words: Vec<String>,
+}
+
+impl BannedWords {
+    pub fn new(words: &[&str]) -> Self {
+        Self {
+            words: words.iter().map(|w| w.to_lowercase()).collect(),
+        }
+    }
+}
+
+impl Default for BannedWords {
+    fn default() -> Self {
+        Self::new(DEFAULT_BANNED_WORDS)
+    }
+}
+
+impl ContentValidator for BannedWords {
+    fn validate(&self, content: &Value, _params: &ContentParams) -> Result<(), String> {
+        let mut strings = Vec::new();
+        collect_strings(content, &mut strings);
+
+        for text in strings {
+            let lowercase = text.to_lowercase();
+            let banned = lowercase
+                .split(|c: char| !c.is_alphanumeric())
+                .find(|word| self.words.iter().any(|banned| banned == word));
+
+            if let Some(word) = banned {
+                return Err(format!("content contains banned word {:?}", word));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Runs every validator, returning the first problem found
+pub fn validate_all(
+    validators: &[std::sync::Arc<dyn ContentValidator>],
+    content: &Value,
+    params: &ContentParams,
+) -> Result<(), String> {
+    validators
+        .iter()
+        .try_for_each(|validator| validator.validate(content, params))
+}
+
+/// Collects every string value in a JSON document
+pub(crate) fn collect_strings<'a>(value: &'a Value, out: &mut Vec<&'a str>) {
+    match value {
+        Value::String(s) => out.push(s),
+        Value::Array(items) => items.iter().for_each(|item| collect_strings(item, out)),
+        Value::Object(map) => map.values().for_each(|item| collect_strings(item, out)),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn params() -> ContentParams {
+        ContentParams::new()
+    }
+
+    #[test]
+    fn test_word_count() {
+        let validator = WordCount::new("story", 2, 4);
+        assert!(
+            validator
+                .validate(&json!({"story": "one two three"}), &params())
+                .is_ok()
+        );
+        assert!(
+            validator
+                .validate(&json!({"story": "one"}), &params())
+                .is_err()
+        );
+        assert!(
+            validator
+                .validate(&json!({"story": "one two three four five"}), &params())
+                .is_err()
+        );
+        assert!(
+            validator
+                .validate(&json!({"title": "one two"}), &params())
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_min_items() {
+        let validator = MinItems::new("questions", 2);
+        assert!(
+            validator
+                .validate(&json!({"questions": ["a", "b"]}), &params())
+                .is_ok()
+        );
+        assert!(
+            validator
+                .validate(&json!({"questions": ["a"]}), &params())
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_no_empty_fields() {
+        assert!(
+            NoEmptyFields
+                .validate(&json!({"title": "x", "questions": ["a"]}), &params())
+                .is_ok()
+        );
+        assert!(
+            NoEmptyFields
+                .validate(&json!({"title": "x", "questions": [" "]}), &params())
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_banned_words() {
+        let validator = BannedWords::new(&["gun"]);
+        assert!(
+            validator
+                .validate(&json!({"story": "A gunnysack of apples."}), &params())
+                .is_ok()
+        );
+        assert!(
+            validator
+                .validate(&json!({"story": "He found a Gun!"}), &params())
+                .is_err()
+        );
+    }
+}
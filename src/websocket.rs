@@ -0,0 +1,325 @@
+use axum::{
+    body::Body,
+    extract::FromRequestParts,
+    http::{Method, StatusCode, header, request::Parts},
+    response::Response,
+};
+use base64::{Engine, engine::general_purpose::STANDARD};
+use hyper::upgrade::{OnUpgrade, Upgraded};
+use hyper_util::rt::TokioIo;
+use sha1::{Digest, Sha1};
+use std::future::Future;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tracing::warn;
+
+use crate::{ServiceError, problem::ErrorResponse};
+
+/// Appended to the client's key to compute `Sec-WebSocket-Accept`, per RFC 6455
+const ACCEPT_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+/// Longest message accepted from a client; live features only exchange small
+/// JSON messages
+const MAX_MESSAGE_LEN: usize = 64 * 1024;
+
+const OPCODE_CONTINUATION: u8 = 0x0;
+const OPCODE_TEXT: u8 = 0x1;
+const OPCODE_BINARY: u8 = 0x2;
+const OPCODE_CLOSE: u8 = 0x8;
+const OPCODE_PING: u8 = 0x9;
+const OPCODE_PONG: u8 = 0xA;
+
+/// Returns the `Sec-WebSocket-Accept` value answering a client's
+/// `Sec-WebSocket-Key`
+pub fn accept_key(key: &str) -> String {
+    STANDARD.encode(Sha1::digest(format!("{}{}", key, ACCEPT_GUID)))
+}
+
+/// Extractor for a request to upgrade to a WebSocket
+///
+/// Rejects requests that aren't WebSocket handshakes with 400.
+pub struct WebSocketUpgrade {
+    accept: String,
+    on_upgrade: OnUpgrade,
+}
+
+impl<T: Send + Sync> FromRequestParts<T> for WebSocketUpgrade {
+    type Rejection = ErrorResponse;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &T) -> Result<Self, Self::Rejection> {
+        let has_token = |name: header::HeaderName, token: &str| {
+            parts
+                .headers
+                .get_all(name)
+                .iter()
+                .filter_map(|value| value.to_str().ok())
+                .flat_map(|value| value.split(','))
+                .any(|value| value.trim().eq_ignore_ascii_case(token))
+        };
+        let is_handshake = parts.method == Method::GET
+            && has_token(header::CONNECTION, "upgrade")
+            && has_token(header::UPGRADE, "websocket")
+            && parts.headers.get(header::SEC_WEBSOCKET_VERSION).map(|v| v.as_bytes()) == Some(b"13");
+        let key = parts
+            .headers
+            .get(header::SEC_WEBSOCKET_KEY)
+            .and_then(|value| value.to_str().ok())
+            .filter(|_| is_handshake)
+            .ok_or_else(|| ServiceError::InvalidInput("Expected a WebSocket handshake".into()))?;
+
+        let on_upgrade = parts.extensions.remove::<OnUpgrade>().ok_or_else(|| {
+            ErrorResponse::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "configuration_error",
+                "The server doesn't support connection upgrades",
+            )
+        })?;
+
+        Ok(Self { accept: accept_key(key), on_upgrade })
+    }
+}
+
+impl WebSocketUpgrade {
+    /// Completes the handshake, running `handler` with the connection in the
+    /// background once the client switches protocols
+    pub fn on_upgrade<F, Fut>(self, handler: F) -> Response
+    where
+        F: FnOnce(WebSocket) -> Fut + Send + 'static,
+        Fut: Future<Output = ()> + Send,
+    {
+        let on_upgrade = self.on_upgrade;
+        tokio::spawn(async move {
+            match on_upgrade.await {
+                Ok(upgraded) => handler(WebSocket::new(TokioIo::new(upgraded))).await,
+                Err(e) => warn!("WebSocket upgrade failed: {}", e),
+            }
+        });
+
+        Response::builder()
+            .status(StatusCode::SWITCHING_PROTOCOLS)
+            .header(header::CONNECTION, "upgrade")
+            .header(header::UPGRADE, "websocket")
+            .header(header::SEC_WEBSOCKET_ACCEPT, self.accept)
+            .body(Body::empty())
+            .expect("handshake response is valid")
+    }
+}
+
+/// A message received from a WebSocket client
+#[derive(Debug, Clone, PartialEq)]
+pub enum Message {
+    Text(String),
+    Binary(Vec<u8>),
+}
+
+/// Server side of a WebSocket connection
+///
+/// Answers pings and close frames itself; only data messages are returned.
+pub struct WebSocket<IO = TokioIo<Upgraded>> {
+    io: IO,
+
+    /// Bytes received but not yet parsed into frames
+    received: Vec<u8>,
+
+    /// Opcode and payload so far of a fragmented message
+    fragments: Option<(u8, Vec<u8>)>,
+}
+
+impl<IO: AsyncRead + AsyncWrite + Unpin> WebSocket<IO> {
+    /// Wraps a connection that has completed the handshake
+    pub fn new(io: IO) -> Self {
+        Self { io, received: Vec::new(), fragments: None }
+    }
+
+    /// Waits for the next message from the client
+    ///
+    /// Can be cancelled, e.g. by a timeout, without losing data: partly received
+    /// frames and messages are kept for the next call.
+    ///
+    /// # Returns
+    /// * `Ok(Some(Message))` - The next text or binary message
+    /// * `Ok(None)` - If the client closed the connection
+    /// * `Err(ServiceError)` - If the client broke the protocol or the connection failed
+    pub async fn recv(&mut self) -> Result<Option<Message>, ServiceError> {
+        loop {
+            let Some(frame) = self.read_frame().await? else {
+                return Ok(None);
+            };
+
+            // Control frames may arrive between the fragments of a message
+            match frame.opcode {
+                OPCODE_PING => {
+                    self.write_frame(OPCODE_PONG, &frame.payload).await?;
+                    continue;
+                }
+                OPCODE_PONG => continue,
+                OPCODE_CLOSE => {
+                    // Echo the status code, then let the client close the connection
+                    let code = frame.payload.get(..2).unwrap_or_default().to_vec();
+                    self.write_frame(OPCODE_CLOSE, &code).await?;
+                    return Ok(None);
+                }
+                OPCODE_TEXT | OPCODE_BINARY if self.fragments.is_none() => {
+                    self.fragments = Some((frame.opcode, frame.payload));
+                }
+                OPCODE_CONTINUATION if self.fragments.is_some() => {
+                    let (_, data) = self.fragments.as_mut().expect("message was started");
+                    data.extend(frame.payload);
+                }
+                opcode => {
+                    return Err(ServiceError::InvalidInput(format!("Unexpected WebSocket opcode {}", opcode)));
+                }
+            }
+
+            if let Some((_, data)) = &self.fragments
+                && data.len() > MAX_MESSAGE_LEN
+            {
+                return Err(ServiceError::InvalidInput("WebSocket message too long".into()));
+            }
+            if frame.fin && let Some((opcode, data)) = self.fragments.take() {
+                return match opcode {
+                    OPCODE_TEXT => String::from_utf8(data)
+                        .map(|text| Some(Message::Text(text)))
+                        .map_err(|_| ServiceError::InvalidInput("WebSocket text isn't UTF-8".into())),
+                    _ => Ok(Some(Message::Binary(data))),
+                };
+            }
+        }
+    }
+
+    /// Sends a text message
+    pub async fn send_text(&mut self, text: &str) -> Result<(), ServiceError> {
+        self.write_frame(OPCODE_TEXT, text.as_bytes()).await
+    }
+
+    /// Closes the connection normally
+    pub async fn close(mut self) -> Result<(), ServiceError> {
+        self.write_frame(OPCODE_CLOSE, &1000u16.to_be_bytes()).await?;
+        Ok(self.io.shutdown().await?)
+    }
+
+    /// Reads the next frame, or `None` once the connection is closed
+    async fn read_frame(&mut self) -> Result<Option<Frame>, ServiceError> {
+        loop {
+            if let Some((len, frame)) = parse_frame(&self.received)? {
+                self.received.drain(..len);
+                return Ok(Some(frame));
+            }
+            if self.io.read_buf(&mut self.received).await? == 0 {
+                return Ok(None);
+            }
+        }
+    }
+
+    /// Writes one unfragmented frame; server frames aren't masked
+    async fn write_frame(&mut self, opcode: u8, payload: &[u8]) -> Result<(), ServiceError> {
+        let mut frame = vec![0x80 | opcode];
+        match payload.len() {
+            len @ 0..=125 => frame.push(len as u8),
+            len @ 126..=0xFFFF => {
+                frame.push(126);
+                frame.extend((len as u16).to_be_bytes());
+            }
+            len => {
+                frame.push(127);
+                frame.extend((len as u64).to_be_bytes());
+            }
+        }
+        frame.extend_from_slice(payload);
+        self.io.write_all(&frame).await?;
+        Ok(self.io.flush().await?)
+    }
+}
+
+/// A frame received from a client
+#[derive(Debug)]
+struct Frame {
+    /// Whether this is the last frame of its message
+    fin: bool,
+    opcode: u8,
+
+    /// The unmasked payload
+    payload: Vec<u8>,
+}
+
+/// Parses a client frame from the start of `data`, unmasking its payload
+///
+/// # Returns
+/// * `Ok(Some((len, frame)))` - The frame and how many bytes it took
+/// * `Ok(None)` - If `data` doesn't hold a whole frame yet
+/// * `Err(ServiceError)` - If the frame isn't masked or is too long
+fn parse_frame(data: &[u8]) -> Result<Option<(usize, Frame)>, ServiceError> {
+    let [first, second, rest @ ..] = data else {
+        return Ok(None);
+    };
+    let fin = first & 0x80 != 0;
+    let opcode = first & 0x0F;
+    if second & 0x80 == 0 {
+        return Err(ServiceError::InvalidInput("WebSocket client frames must be masked".into()));
+    }
+
+    let (len, rest) = match second & 0x7F {
+        126 => match rest {
+            [a, b, rest @ ..] => (u16::from_be_bytes([*a, *b]) as u64, rest),
+            _ => return Ok(None),
+        },
+        127 => match rest.split_first_chunk::<8>() {
+            Some((len, rest)) => (u64::from_be_bytes(*len), rest),
+            None => return Ok(None),
+        },
+        len => (len as u64, rest),
+    };
+    if len > MAX_MESSAGE_LEN as u64 {
+        return Err(ServiceError::InvalidInput("WebSocket frame too long".into()));
+    }
+
+    let len = len as usize;
+    let Some((mask, rest)) = rest.split_first_chunk::<4>() else {
+        return Ok(None);
+    };
+    let Some(payload) = rest.get(..len) else {
+        return Ok(None);
+    };
+    let payload = payload.iter().enumerate().map(|(i, byte)| byte ^ mask[i % 4]).collect();
+    Ok(Some((data.len() - rest.len() + len, Frame { fin, opcode, payload })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds a masked frame, as a client sends them
+    fn client_frame(fin: bool, opcode: u8, payload: &[u8]) -> Vec<u8> {
+        let mask = [1, 2, 3, 4];
+        let mut frame = vec![if fin { 0x80 } else { 0 } | opcode, 0x80 | payload.len() as u8];
+        frame.extend(mask);
+        frame.extend(payload.iter().enumerate().map(|(i, byte)| byte ^ mask[i % 4]));
+        frame
+    }
+
+    #[tokio::test]
+    async fn exchanges_messages_with_a_client() {
+        // Example from RFC 6455, section 1.3
+        assert_eq!(accept_key("dGhlIHNhbXBsZSBub25jZQ=="), "s3pPLMBiTxaQ9kYGzzhZRbK+xOo=");
+
+        let (mut client, server) = tokio::io::duplex(1024);
+        let mut socket = WebSocket::new(server);
+        let mut sent = client_frame(false, OPCODE_TEXT, b"hel");
+        sent.extend(client_frame(true, OPCODE_PING, b"hi"));
+        sent.extend(client_frame(true, OPCODE_CONTINUATION, b"lo"));
+        sent.extend(client_frame(true, OPCODE_CLOSE, &1000u16.to_be_bytes()));
+        client.write_all(&sent).await.unwrap();
+
+        assert_eq!(socket.recv().await.unwrap(), Some(Message::Text("hello".into())));
+        socket.send_text("ok").await.unwrap();
+        assert_eq!(socket.recv().await.unwrap(), None);
+
+        let mut received = [0u8; 10];
+        client.read_exact(&mut received).await.unwrap();
+        assert_eq!(received, [0x8A, 2, b'h', b'i', 0x81, 2, b'o', b'k', 0x88, 2]);
+
+        let (mut client, server) = tokio::io::duplex(1024);
+        let mut socket = WebSocket::new(server);
+        client.write_all(&[0x81, 2, b'n', b'o']).await.unwrap();
+        assert!(socket.recv().await.is_err());
+    }
+}
@@ -0,0 +1,100 @@
+use futures::future::join_all;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+
+use crate::{
+    keyvalue::KeyValueStore,
+    metrics,
+    queue::{JobQueue, QueuedJob},
+    state::AppState,
+    storage::ObjectStore,
+};
+
+/// How long to wait before polling again after the queue can't be reached
+const RECEIVE_RETRY_DELAY: Duration = Duration::from_secs(5);
+
+/// Generates content for jobs from the generation queue until `shutdown` is
+/// cancelled
+///
+/// Takes up to `concurrency` jobs at a time (SQS hands out at most 10) and runs
+/// them together. Handled jobs are deleted; failed ones are left on the queue to
+/// be delivered again. Once `shutdown` is cancelled, jobs already taken are
+/// finished.
+pub async fn run<S: ObjectStore, K: KeyValueStore>(
+    state: AppState<S, K>,
+    queue: Arc<dyn JobQueue>,
+    concurrency: usize,
+    shutdown: CancellationToken,
+) {
+    info!("Worker waiting for generation jobs, {} at a time", concurrency);
+    loop {
+        let received = tokio::select! {
+            received = queue.receive(concurrency) => received,
+            _ = shutdown.cancelled() => break,
+        };
+        match received {
+            Ok(jobs) => {
+                join_all(jobs.iter().map(|job| handle(&state, queue.as_ref(), job))).await;
+            }
+            Err(e) => {
+                warn!("Failed to receive generation jobs: {}", e);
+                tokio::select! {
+                    _ = tokio::time::sleep(RECEIVE_RETRY_DELAY) => {}
+                    _ = shutdown.cancelled() => break,
+                }
+            }
+        }
+    }
+    info!("Worker stopped");
+}
+
+/// Runs one job, deleting it unless it failed
+async fn handle<S: ObjectStore, K: KeyValueStore>(state: &AppState<S, K>, queue: &dyn JobQueue, queued: &QueuedJob) {
+    let job = &queued.job;
+    match state.run_generation_job(job).await {
+        Ok(outcome) => {
+            metrics::increment(&format!("generation.job.{}", outcome.as_str()));
+            info!("Generation job for {}: {}", job.slot, outcome.as_str());
+            if let Err(e) = queue.delete(&queued.receipt).await {
+                warn!("Failed to delete generation job for {}: {}", job.slot, e);
+            }
+        }
+        Err(e) => {
+            metrics::increment("generation.job.failed");
+            warn!("Generation job for {} failed, leaving it to be retried: {}", job.slot, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::Config,
+        content::{ContentParams, ContentTypeRegistry},
+        keyvalue::MemoryKeyValueStore,
+        queue::{GenerationJob, JobOutcome},
+        reading,
+        storage::MemoryObjectStore,
+    };
+
+    #[tokio::test]
+    async fn skips_jobs_that_cannot_be_run() {
+        let state = AppState::new(
+            MemoryObjectStore::new(),
+            MemoryKeyValueStore::new(),
+            Config::default(),
+            ContentTypeRegistry::new().register(reading::descriptor()),
+        )
+        .await;
+        let params = ContentParams::new().with("grade", 3);
+
+        let ended = GenerationJob::new("reading", "reading/grade-3/2000-01-01-00/", &params);
+        assert_eq!(state.run_generation_job(&ended).await.unwrap(), JobOutcome::Expired);
+
+        let unknown = GenerationJob::new("haiku", "haiku/2000-01-01-00/", &params);
+        assert_eq!(state.run_generation_job(&unknown).await.unwrap(), JobOutcome::UnknownContentType);
+    }
+}